@@ -41,7 +41,7 @@ fn app(st: &mut St) -> std::result::Result<(), String> {
     // Display result
     st.metric("Result", format!("{:.2}", result), None);
 
-    st.info(&format!(
+    st.info(format!(
         "{} {} {} = {:.2}",
         num1, operation, num2, result
     ));
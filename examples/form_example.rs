@@ -21,16 +21,16 @@ fn app(st: &mut St) -> std::result::Result<(), String> {
     // Preferences Section
     st.subheader("Preferences");
     
-    let age = st.number_input("Age", 18.0, None);
+    let _age = st.number_input("Age", 18.0, None);
     
-    let country = st.selectbox(
+    let _country = st.selectbox(
         "Country",
         vec!["United States".to_string(), "Canada".to_string(), "United Kingdom".to_string(), "Australia".to_string(), "Other".to_string()],
         0,
         None,
     );
 
-    let interests = st.multiselect(
+    let _interests = st.multiselect(
         "Interests (select multiple)",
         vec!["Data Science".to_string(), "Web Development".to_string(), "DevOps".to_string(), "Machine Learning".to_string(), "Cloud Computing".to_string()],
         vec![],
@@ -43,7 +43,7 @@ fn app(st: &mut St) -> std::result::Result<(), String> {
     st.subheader("Terms");
     
     let agree_terms = st.checkbox("I agree to the terms and conditions", false, None);
-    let subscribe_newsletter = st.checkbox("Subscribe to newsletter", true, None);
+    let _subscribe_newsletter = st.checkbox("Subscribe to newsletter", true, None);
 
     st.divider();
 
@@ -56,7 +56,7 @@ fn app(st: &mut St) -> std::result::Result<(), String> {
             st.error("You must agree to the terms and conditions");
         } else {
             st.success("✅ Registration successful!");
-            st.markdown(&format!(
+            st.markdown(format!(
                 "Welcome, {} {}! A confirmation email has been sent to {}",
                 first_name, last_name, email
             ));
@@ -1,10 +1,12 @@
 //! Chatapp CLI - Command-line interface for Chatapp applications.
 
 use clap::{Parser, Subcommand};
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::Command;
-use tracing_subscriber;
-use platypus_server::AppServer;
+use platypus_server::{AppServer, SessionLogLayer};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::prelude::*;
 
 #[derive(Parser)]
 #[command(name = "platypus")]
@@ -38,6 +40,14 @@ enum Commands {
         /// Enable hot reload
         #[arg(long)]
         hot_reload: bool,
+
+        /// Warn about elements that differ between identical reruns
+        #[arg(long)]
+        check_determinism: bool,
+
+        /// Fail runs in which an element fails to render, e.g. in CI
+        #[arg(long)]
+        fatal_element_errors: bool,
     },
 
     /// Build a Platypus application for production
@@ -62,10 +72,71 @@ enum Commands {
         template: String,
     },
 
+    /// Run an application's tests
+    Test {
+        /// Path to the app script; every test when omitted
+        #[arg(value_name = "PATH")]
+        path: Option<PathBuf>,
+
+        /// Also compare rendered app states against stored baselines
+        #[arg(long)]
+        visual: bool,
+
+        /// Record renders as the new baselines instead of comparing
+        #[arg(long, requires = "visual")]
+        update: bool,
+
+        /// Largest share of differing pixels that still passes
+        #[arg(long, default_value_t = platypus_runtime::visual::DEFAULT_THRESHOLD)]
+        threshold: f64,
+
+        /// Directory of the baselines
+        #[arg(long, default_value = platypus_runtime::visual::DEFAULT_BASELINE_DIR)]
+        baselines: PathBuf,
+    },
+
+    /// Benchmark the server's hot paths
+    Bench {
+        /// Only run benchmarks whose name contains this
+        #[arg(value_name = "FILTER")]
+        filter: Option<String>,
+
+        /// Save the results as a named baseline
+        #[arg(long, value_name = "NAME")]
+        save_baseline: Option<String>,
+
+        /// Compare the results against a saved baseline
+        #[arg(long, value_name = "NAME", conflicts_with = "save_baseline")]
+        baseline: Option<String>,
+    },
+
+    /// Manage an app's database
+    Db {
+        #[command(subcommand)]
+        command: DbCommand,
+
+        /// SQLite database to run against
+        #[arg(long, global = true, default_value = platypus_server::config::DEFAULT_DATABASE)]
+        database: PathBuf,
+
+        /// Directory of the SQL migrations
+        #[arg(long, global = true, default_value = platypus_server::config::DEFAULT_MIGRATIONS_DIR)]
+        migrations: PathBuf,
+    },
+
     /// Show version information
     Version,
 }
 
+#[derive(Subcommand)]
+enum DbCommand {
+    /// Apply the migrations not applied yet
+    Migrate,
+
+    /// Load the seed data
+    Seed,
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
@@ -76,8 +147,11 @@ async fn main() -> anyhow::Result<()> {
     } else {
         platypus_server::config::NORMAL_LOG_LEVEL
     };
-    tracing_subscriber::fmt()
-        .with_max_level(log_level.parse().unwrap_or(tracing::Level::INFO))
+    // App events inside a run also go to the session's log viewer
+    let log_level: tracing::Level = log_level.parse().unwrap_or(tracing::Level::INFO);
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::fmt::layer().with_filter(LevelFilter::from_level(log_level)))
+        .with(SessionLogLayer::new())
         .init();
 
     match cli.command {
@@ -86,8 +160,10 @@ async fn main() -> anyhow::Result<()> {
             port,
             host,
             hot_reload,
+            check_determinism,
+            fatal_element_errors,
         } => {
-            run_app(path, host, port, hot_reload).await?;
+            run_app(path, host, port, hot_reload, check_determinism, fatal_element_errors).await?;
         }
         Commands::Build { path, output } => {
             build_app(path, output)?;
@@ -95,6 +171,29 @@ async fn main() -> anyhow::Result<()> {
         Commands::New { name, template } => {
             create_project(name, template)?;
         }
+        Commands::Test {
+            path,
+            visual,
+            update,
+            threshold,
+            baselines,
+        } => {
+            test_app(path, visual, update, threshold, baselines)?;
+        }
+        Commands::Bench {
+            filter,
+            save_baseline,
+            baseline,
+        } => {
+            bench(filter, save_baseline, baseline)?;
+        }
+        Commands::Db {
+            command,
+            database,
+            migrations,
+        } => {
+            db(command, database, migrations)?;
+        }
         Commands::Version => {
             println!("Platypus {}", env!("CARGO_PKG_VERSION"));
         }
@@ -109,6 +208,8 @@ async fn run_app(
     host: String,
     port: u16,
     _hot_reload: bool,
+    check_determinism: bool,
+    fatal_element_errors: bool,
 ) -> anyhow::Result<()> {
     // Check if path is a .rs file in examples directory
     if let Some(file_name) = path.file_stem() {
@@ -117,7 +218,7 @@ async fn run_app(
         // Try to run as a cargo binary
         println!("🚀 Running example: {}", bin_name);
         let mut cmd = Command::new("cargo");
-        cmd.args(&["run", "-p", "platypus-examples", "--bin", &bin_name])
+        cmd.args(["run", "-p", "platypus-examples", "--bin", &bin_name])
             .env("PLATYPUS_HOST", &host)
             .env("PLATYPUS_PORT", port.to_string());
         
@@ -134,13 +235,13 @@ async fn run_app(
     println!("🚀 Starting Platypus server on http://{}:{}", host, port);
     println!("📝 Open your browser and navigate to the URL above");
 
-    let config = platypus_server::ServerConfig {
-        app_name: platypus_server::config::DEFAULT_APP_NAME.to_string(),
-        host,
-        port,
-        max_body_size: platypus_server::config::DEFAULT_MAX_BODY_SIZE,
-        session_timeout: platypus_server::config::DEFAULT_SESSION_TIMEOUT,
-    };
+    let config = platypus_server::ServerConfig::builder()
+        .from_env()?
+        .host(host)
+        .port(port)
+        .check_determinism(check_determinism)
+        .fatal_element_errors(fatal_element_errors)
+        .build()?;
 
     let server = AppServer::with_config(config);
     server.run().await?;
@@ -148,6 +249,91 @@ async fn run_app(
     Ok(())
 }
 
+/// Run an application's tests, with visual checks if asked for.
+///
+/// Visual checks are the `AppTest::assert_visual` calls in the tests; they
+/// read their settings from the environment set here.
+fn test_app(
+    path: Option<PathBuf>,
+    visual: bool,
+    update: bool,
+    threshold: f64,
+    baselines: PathBuf,
+) -> anyhow::Result<()> {
+    use platypus_runtime::visual;
+
+    let mut cmd = Command::new("cargo");
+    match path.as_ref().and_then(|path| path.file_stem()) {
+        Some(bin_name) => {
+            let bin_name = bin_name.to_string_lossy();
+            println!("🧪 Testing example: {}", bin_name);
+            cmd.args(["test", "-p", "platypus-examples", "--bin", &bin_name]);
+        }
+        None => {
+            cmd.args(["test", "--workspace"]);
+        }
+    }
+
+    if visual {
+        // Tests run from their package's directory
+        let baselines = std::env::current_dir()?.join(baselines);
+        println!("🖼  Visual baselines: {}", baselines.display());
+        cmd.env(visual::VISUAL_ENV, "1")
+            .env(visual::BASELINE_DIR_ENV, &baselines)
+            .env(visual::THRESHOLD_ENV, threshold.to_string());
+        if update {
+            cmd.env(visual::UPDATE_ENV, "1");
+        }
+    }
+
+    if !cmd.status()?.success() {
+        anyhow::bail!("Tests failed");
+    }
+    println!("✓ Tests passed!");
+    Ok(())
+}
+
+/// Run the server's criterion benchmarks.
+///
+/// Reports land in `target/criterion`; a saved baseline lets a later run
+/// show the change from it.
+fn bench(filter: Option<String>, save_baseline: Option<String>, baseline: Option<String>) -> anyhow::Result<()> {
+    let mut cmd = Command::new("cargo");
+    cmd.args(["bench", "-p", "platypus-server", "--bench", "hot_paths", "--"]);
+    if let Some(filter) = &filter {
+        cmd.arg(filter);
+    }
+    if let Some(name) = &save_baseline {
+        cmd.args(["--save-baseline", name]);
+    }
+    if let Some(name) = &baseline {
+        cmd.args(["--baseline", name]);
+    }
+
+    println!("⏱  Benchmarking hot paths...");
+    if !cmd.status()?.success() {
+        anyhow::bail!("Benchmarks failed");
+    }
+    println!("✓ Reports written to target/criterion");
+    Ok(())
+}
+
+/// Run an app's migrations or seed files against its database.
+fn db(command: DbCommand, database: PathBuf, migrations: PathBuf) -> anyhow::Result<()> {
+    use platypus_runtime::{migrations as db, SqliteConnection};
+
+    let conn = SqliteConnection::open(&database)?;
+    let (verb, names) = match command {
+        DbCommand::Migrate => ("Applied", db::migrate(&conn, &migrations)?),
+        DbCommand::Seed => ("Seeded", db::seed(&conn, &migrations)?),
+    };
+    for name in &names {
+        println!("  {}", name);
+    }
+    println!("✓ {} {} file(s) in {}", verb, names.len(), database.display());
+    Ok(())
+}
+
 /// Build a platypus application for production.
 fn build_app(_path: PathBuf, _output: PathBuf) -> anyhow::Result<()> {
     println!("Building platypus application...");
@@ -159,6 +345,27 @@ fn build_app(_path: PathBuf, _output: PathBuf) -> anyhow::Result<()> {
 fn create_project(name: String, template: String) -> anyhow::Result<()> {
     println!("Creating new platypus project: {}", name);
     println!("Template: {}", template);
+    if template == "data" {
+        write_data_template(Path::new(&name))?;
+        println!("🗄  Run `platypus db migrate` and `platypus db seed` to load the sample data");
+    }
     println!("✓ Project created!");
     Ok(())
 }
+
+/// Add the `data` template's schema and sample rows.
+fn write_data_template(root: &Path) -> anyhow::Result<()> {
+    use platypus_runtime::migrations::SEED_DIR;
+
+    let migrations = root.join(platypus_server::config::DEFAULT_MIGRATIONS_DIR);
+    fs::create_dir_all(migrations.join(SEED_DIR))?;
+    fs::write(
+        migrations.join("0001_create_sales.sql"),
+        include_str!("../templates/data/migrations/0001_create_sales.sql"),
+    )?;
+    fs::write(
+        migrations.join(SEED_DIR).join("0001_sales.sql"),
+        include_str!("../templates/data/migrations/seed/0001_sales.sql"),
+    )?;
+    Ok(())
+}
@@ -0,0 +1,8 @@
+-- Sales by region and month, shown by the data template's app
+CREATE TABLE sales (
+    region TEXT NOT NULL,
+    month TEXT NOT NULL,
+    units INTEGER NOT NULL,
+    revenue REAL NOT NULL,
+    PRIMARY KEY (region, month)
+);
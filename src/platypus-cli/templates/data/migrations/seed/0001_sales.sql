@@ -0,0 +1,8 @@
+-- Sample rows; safe to rerun
+INSERT OR REPLACE INTO sales (region, month, units, revenue) VALUES
+    ('North', '2024-01', 120, 2400.0),
+    ('North', '2024-02', 135, 2700.0),
+    ('South', '2024-01', 80, 1600.0),
+    ('South', '2024-02', 95, 1900.0),
+    ('West', '2024-01', 60, 1200.0),
+    ('West', '2024-02', 72, 1440.0);
@@ -0,0 +1,114 @@
+//! Random elements for property tests and fuzzing, enabled by the
+//! `arbitrary` feature.
+//!
+//! Element types implement [`Arbitrary`], covering every variant and field
+//! value, NaN included. The proptest strategies build elements from random
+//! bytes, which proptest shrinks; [`element_type`] keeps to the elements a
+//! client can be sent, whose numbers are finite, and [`page`] builds the
+//! deltas of a run from them.
+
+use crate::element::{ElementId, ElementType};
+use crate::state::{Delta, DeltaGenerator};
+use crate::traits::Layout;
+use arbitrary::{Arbitrary, Result, Unstructured};
+use proptest::collection::{vec, SizeRange};
+use proptest::prelude::*;
+use proptest::sample::Index;
+use std::collections::BTreeMap;
+
+/// Deepest nesting of generated JSON arrays and objects.
+const MAX_JSON_DEPTH: usize = 3;
+
+/// Any JSON value; non-finite numbers become null, as when serialized.
+pub fn json_value(u: &mut Unstructured) -> Result<serde_json::Value> {
+    json_value_at(u, 0)
+}
+
+fn json_value_at(u: &mut Unstructured, depth: usize) -> Result<serde_json::Value> {
+    let kinds = if depth < MAX_JSON_DEPTH { 7 } else { 5 };
+    Ok(match u.int_in_range(0..=kinds - 1)? {
+        0 => serde_json::Value::Null,
+        1 => bool::arbitrary(u)?.into(),
+        2 => i64::arbitrary(u)?.into(),
+        3 => f64::arbitrary(u)?.into(),
+        4 => String::arbitrary(u)?.into(),
+        5 => (0..u.int_in_range(0..=3)?)
+            .map(|_| json_value_at(u, depth + 1))
+            .collect::<Result<Vec<_>>>()?
+            .into(),
+        _ => serde_json::Value::Object(
+            (0..u.int_in_range(0..=3)?)
+                .map(|_| Ok((String::arbitrary(u)?, json_value_at(u, depth + 1)?)))
+                .collect::<Result<_>>()?,
+        ),
+    })
+}
+
+/// Any JSON value, or none.
+pub fn optional_json_value(u: &mut Unstructured) -> Result<Option<serde_json::Value>> {
+    Ok(if bool::arbitrary(u)? { Some(json_value(u)?) } else { None })
+}
+
+/// Responsive layouts in breakpoint order, at most one per breakpoint, as
+/// clients send them keyed by breakpoint.
+pub fn layouts(u: &mut Unstructured) -> Result<Vec<(String, Layout)>> {
+    Ok(BTreeMap::<String, Layout>::arbitrary(u)?.into_iter().collect())
+}
+
+/// Any element whose numbers are finite.
+pub fn element_type() -> impl Strategy<Value = ElementType> {
+    vec(any::<u8>(), 0..256).prop_filter_map("non-finite number", |bytes| {
+        ElementType::arbitrary(&mut Unstructured::new(&bytes))
+            .ok()
+            .filter(is_finite)
+    })
+}
+
+fn is_finite(element: &ElementType) -> bool {
+    match element {
+        ElementType::NumberInput { value, .. } => value.is_finite(),
+        ElementType::Slider { value, min, max, .. } => [value, min, max].iter().all(|n| n.is_finite()),
+        ElementType::Progress { value, eta_secs, .. } => value.is_finite() && eta_secs.is_none_or(f64::is_finite),
+        ElementType::Metric { sparkline, .. } => sparkline.iter().all(|n| n.is_finite()),
+        ElementType::AudioWaveform { regions, .. } => regions.iter().all(|(start, end)| start.is_finite() && end.is_finite()),
+        ElementType::ImageAnnotator { annotations, .. } => annotations.iter().all(|annotation| {
+            let (x, y, width, height) = annotation.shape.bounds();
+            [x, y, width, height].iter().all(|v| v.is_finite())
+        }),
+        ElementType::Column { width, .. } => width.is_none_or(f32::is_finite),
+        _ => true,
+    }
+}
+
+fn is_container(element: &ElementType) -> bool {
+    matches!(
+        element,
+        ElementType::Container { .. }
+            | ElementType::Column { .. }
+            | ElementType::Row { .. }
+            | ElementType::Tab { .. }
+            | ElementType::Expander { .. }
+            | ElementType::Fieldset { .. }
+            | ElementType::Form { .. }
+            | ElementType::ResponsiveContainer { .. }
+            | ElementType::Sidebar { .. }
+    )
+}
+
+/// The deltas of a run adding `size` elements, each at the top level or in
+/// a container added before it.
+pub fn page(size: impl Into<SizeRange>) -> impl Strategy<Value = Vec<Delta>> {
+    vec((element_type(), any::<Index>()), size).prop_map(|entries| {
+        let mut delta_gen = DeltaGenerator::new();
+        let mut containers: Vec<ElementId> = Vec::new();
+        for (element, parent) in entries {
+            let parent = parent.index(containers.len() + 1).checked_sub(1).map(|i| containers[i]);
+            let container = is_container(&element);
+            let id = delta_gen.add_element(element, parent);
+            if container {
+                containers.push(id);
+            }
+        }
+        delta_gen.drain_deltas()
+    })
+}
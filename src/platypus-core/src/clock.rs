@@ -0,0 +1,88 @@
+//! Time as seen by sessions and element generation.
+//!
+//! Code reading the time takes it from a [`Clock`] instead of the system,
+//! so tests and replays can fix it with a [`ManualClock`] and produce the
+//! same output on every run.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A source of the current time.
+pub trait Clock: Send + Sync {
+    /// Get the current time.
+    fn now(&self) -> SystemTime;
+
+    /// Get the current time in Unix seconds.
+    fn unix_secs(&self) -> u64 {
+        self.now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+    }
+}
+
+/// The system's wall clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// A clock that only moves when told to. Clones share the time.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    // Milliseconds since the Unix epoch
+    millis: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    /// Create a clock reading `start`.
+    pub fn new(start: SystemTime) -> Self {
+        ManualClock {
+            millis: Arc::new(AtomicU64::new(millis_since_epoch(start))),
+        }
+    }
+
+    /// Set the time.
+    pub fn set(&self, time: SystemTime) {
+        self.millis.store(millis_since_epoch(time), Ordering::SeqCst);
+    }
+
+    /// Move the time forward by `by`.
+    pub fn advance(&self, by: Duration) {
+        let by = u64::try_from(by.as_millis()).unwrap_or(u64::MAX);
+        self.millis.fetch_add(by, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(self.millis.load(Ordering::SeqCst))
+    }
+}
+
+fn millis_since_epoch(time: SystemTime) -> u64 {
+    let millis = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+    u64::try_from(millis).unwrap_or(u64::MAX)
+}
+
+/// Get the system clock, shared.
+pub fn system_clock() -> Arc<dyn Clock> {
+    Arc::new(SystemClock)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manual_clock() {
+        let clock = ManualClock::new(UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+        let shared: Arc<dyn Clock> = Arc::new(clock.clone());
+        assert_eq!(shared.unix_secs(), 1_700_000_000);
+        clock.advance(Duration::from_millis(1500));
+        assert_eq!(shared.now(), UNIX_EPOCH + Duration::from_millis(1_700_000_001_500));
+        assert_eq!(shared.unix_secs(), 1_700_000_001);
+    }
+}
@@ -1,32 +1,97 @@
 //! Element types and traits for UI components.
 
-use serde::{Deserialize, Serialize};
+use crate::traits::{Accessible, Layout};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt;
+use std::str::FromStr;
 
 /// Unique identifier for elements.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub struct ElementId(u64);
+///
+/// IDs count up within a namespace, usually the run that generated them,
+/// so an element of a stale run is never mistaken for one of the current
+/// run with the same number. IDs are formatted as `namespace-id`, or just
+/// `id` in namespace 0; [`FromStr`] parses both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct ElementId {
+    namespace: u32,
+    id: u64,
+}
 
 impl ElementId {
-    /// Create a new element ID.
+    /// Create a new element ID in namespace 0.
     pub fn new(id: u64) -> Self {
-        ElementId(id)
+        ElementId { namespace: 0, id }
+    }
+
+    /// Create a new element ID in `namespace`.
+    pub fn namespaced(namespace: u32, id: u64) -> Self {
+        ElementId { namespace, id }
     }
 
-    /// Get the inner value.
+    /// Get the number within the namespace.
     pub fn inner(self) -> u64 {
-        self.0
+        self.id
+    }
+
+    /// Get the namespace.
+    pub fn namespace(self) -> u32 {
+        self.namespace
     }
 }
 
 impl fmt::Display for ElementId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.0)
+        match self.namespace {
+            0 => write!(f, "{}", self.id),
+            namespace => write!(f, "{}-{}", namespace, self.id),
+        }
+    }
+}
+
+impl FromStr for ElementId {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid element ID: {:?}", s);
+        match s.split_once('-') {
+            Some((namespace, id)) => Ok(ElementId {
+                namespace: namespace.parse().map_err(|_| invalid())?,
+                id: id.parse().map_err(|_| invalid())?,
+            }),
+            None => s.parse().map(ElementId::new).map_err(|_| invalid()),
+        }
+    }
+}
+
+// IDs in namespace 0 serialize as numbers, as before namespaces existed
+impl Serialize for ElementId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.namespace {
+            0 => serializer.serialize_u64(self.id),
+            _ => serializer.collect_str(self),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ElementId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Number(u64),
+            Text(String),
+        }
+        match Repr::deserialize(deserializer)? {
+            Repr::Number(id) => Ok(ElementId::new(id)),
+            Repr::Text(s) => s.parse().map_err(serde::de::Error::custom),
+        }
     }
 }
 
 /// Enumeration of all supported element types.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[serde(tag = "type")]
 pub enum ElementType {
     // Text elements
@@ -34,65 +99,575 @@ pub enum ElementType {
     Markdown { value: String },
     Code { value: String, language: Option<String> },
     Heading { value: String, level: u32 },
+    /// Text showing a widget's value through `template` (`{}` is replaced).
+    /// The client fills in its predicted value while the rerun is in flight.
+    Echo { key: String, template: String, value: String },
+    /// A chat message bubble; `content` is Markdown.
+    ChatMessage { role: String, content: String },
 
-    // Input widgets
-    Button { label: String, key: Option<String> },
-    TextInput { label: String, value: String, key: Option<String> },
-    TextArea { label: String, value: String, key: Option<String> },
-    NumberInput { label: String, value: f64, key: Option<String> },
-    Slider { label: String, value: f64, min: f64, max: f64, key: Option<String> },
-    Checkbox { label: String, value: bool, key: Option<String> },
-    Radio { label: String, options: Vec<String>, value: Option<String>, key: Option<String> },
-    Selectbox { label: String, options: Vec<String>, value: Option<String>, key: Option<String> },
-    Multiselect { label: String, options: Vec<String>, values: Vec<String>, key: Option<String> },
-    DateInput { label: String, value: String, key: Option<String> },
-    TimeInput { label: String, value: String, key: Option<String> },
-    ColorPicker { label: String, value: String, key: Option<String> },
-    FileUploader { label: String, key: Option<String> },
+    // Input widgets; disabled ones are shown but take no input
+    Button { label: String, key: Option<String>, #[serde(default)] disabled: bool },
+    /// Button sending the changes held by the form `form_key` for a rerun.
+    FormSubmitButton { label: String, form_key: String, key: Option<String>, #[serde(default)] disabled: bool },
+    TextInput {
+        label: String,
+        value: String,
+        key: Option<String>,
+        kind: TextInputKind,
+        error: Option<String>,
+        rate_limit: RateLimit,
+        #[serde(default)]
+        disabled: bool,
+    },
+    TextArea { label: String, value: String, key: Option<String>, #[serde(default)] disabled: bool },
+    NumberInput { label: String, value: f64, key: Option<String>, display: Option<String>, #[serde(default)] disabled: bool },
+    Slider {
+        label: String,
+        value: f64,
+        min: f64,
+        max: f64,
+        key: Option<String>,
+        rate_limit: RateLimit,
+        #[serde(default)]
+        disabled: bool,
+    },
+    Checkbox { label: String, value: bool, key: Option<String>, #[serde(default)] disabled: bool },
+    Radio { label: String, options: Vec<String>, value: Option<String>, key: Option<String>, #[serde(default)] disabled: bool },
+    Selectbox { label: String, options: Vec<String>, value: Option<String>, key: Option<String>, #[serde(default)] disabled: bool },
+    Multiselect { label: String, options: Vec<String>, values: Vec<String>, key: Option<String>, #[serde(default)] disabled: bool },
+    DateInput { label: String, value: String, key: Option<String>, #[serde(default)] disabled: bool },
+    TimeInput { label: String, value: String, key: Option<String>, #[serde(default)] disabled: bool },
+    DateRangeInput {
+        label: String,
+        start: String,
+        end: String,
+        min: Option<String>,
+        max: Option<String>,
+        key: Option<String>,
+        #[serde(default)]
+        disabled: bool,
+    },
+    DateTimeInput {
+        label: String,
+        value: String,
+        timezone: String,
+        min: Option<String>,
+        max: Option<String>,
+        key: Option<String>,
+        #[serde(default)]
+        disabled: bool,
+    },
+    ColorPicker { label: String, value: String, key: Option<String>, #[serde(default)] disabled: bool },
+    FileUploader { label: String, key: Option<String>, #[serde(default)] disabled: bool },
+    Dropzone {
+        label: String,
+        accept: Vec<String>,
+        allow_paste: bool,
+        show_preview: bool,
+        key: Option<String>,
+        #[serde(default)]
+        disabled: bool,
+    },
+    ThemeToggle { label: String, mode: String, key: Option<String>, #[serde(default)] disabled: bool },
+    Feedback { kind: FeedbackKind, value: Option<u32>, key: Option<String>, #[serde(default)] disabled: bool },
+    Rating { max: u32, value: Option<u32>, key: Option<String>, #[serde(default)] disabled: bool },
+    TagsInput {
+        label: String,
+        suggestions: Vec<String>,
+        values: Vec<String>,
+        max_tags: Option<usize>,
+        allow_custom: bool,
+        error: Option<String>,
+        key: Option<String>,
+        #[serde(default)]
+        disabled: bool,
+    },
+    CodeEditor {
+        label: String,
+        value: String,
+        language: String,
+        /// 1-based inclusive line ranges that cannot be edited.
+        read_only: Vec<(u32, u32)>,
+        annotations: Vec<CodeAnnotation>,
+        /// Delay before the client sends edits.
+        debounce_ms: u32,
+        error: Option<String>,
+        key: Option<String>,
+        #[serde(default)]
+        disabled: bool,
+    },
+    JsonEditor {
+        /// Editor text: the pretty-printed value, or a rejected submission.
+        text: String,
+        #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary::optional_json_value))]
+        schema: Option<serde_json::Value>,
+        errors: Vec<JsonEditorError>,
+        key: Option<String>,
+        #[serde(default)]
+        disabled: bool,
+    },
+    RichTextEditor {
+        label: String,
+        /// Sanitized HTML.
+        value: String,
+        max_bytes: usize,
+        error: Option<String>,
+        key: Option<String>,
+        #[serde(default)]
+        disabled: bool,
+    },
 
     // Data display
-    Json { value: serde_json::Value },
+    Json {
+        #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary::json_value))]
+        value: serde_json::Value,
+    },
     Dataframe { data: String }, // JSON-encoded dataframe
+    /// Expandable pivot grid; clicked cells are sent as widget values.
+    PivotTable { key: String, data: String }, // JSON-encoded pivot
     Table { headers: Vec<String>, rows: Vec<Vec<String>> },
-    CameraInput { label: String, key: Option<String> },
+    /// Editable grid whose cells may be formulas; `values` holds what each
+    /// cell evaluates to. Edited cells are sent as widget values, a
+    /// JSON-encoded array of rows.
+    DataEditor {
+        headers: Vec<String>,
+        cells: Vec<Vec<String>>,
+        values: Vec<Vec<String>>,
+        key: String,
+        #[serde(default)]
+        disabled: bool,
+    },
+    CameraInput { label: String, key: Option<String>, #[serde(default)] disabled: bool },
+    /// Microphone recorder; recordings are uploaded as media under `key`.
+    AudioInput { label: String, key: String, #[serde(default)] disabled: bool },
 
-    // Layout
-    Container { children: Vec<ElementId> },
-    Column { children: Vec<ElementId>, width: Option<f32> },
-    Row { children: Vec<ElementId> },
-    Tab { label: String, children: Vec<ElementId> },
-    Expander { label: String, expanded: bool, children: Vec<ElementId> },
+    // Layout; hidden containers keep their elements on the client, unshown
+    Container { children: Vec<ElementId>, #[serde(default)] hidden: bool },
+    Column { children: Vec<ElementId>, width: Option<f32>, #[serde(default)] hidden: bool },
+    Row { children: Vec<ElementId>, #[serde(default)] hidden: bool },
+    Tab { label: String, children: Vec<ElementId>, #[serde(default)] hidden: bool },
+    Expander { label: String, expanded: bool, children: Vec<ElementId>, #[serde(default)] hidden: bool },
+    /// Group of related form fields under a legend.
+    Fieldset { legend: String, children: Vec<ElementId>, #[serde(default)] hidden: bool },
+    /// Widgets whose changes the client holds until the form is submitted.
+    Form { key: String, children: Vec<ElementId>, #[serde(default)] hidden: bool },
+    ResponsiveContainer {
+        children: Vec<ElementId>,
+        #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary::layouts))]
+        layouts: Vec<(String, Layout)>,
+        #[serde(default)]
+        hidden: bool,
+    },
 
     // Media
     Image { src: String, caption: Option<String>, width: Option<u32> },
-    Audio { src: String },
+    /// Image users draw boxes and polygons on; the shapes are sent as
+    /// widget values, in pixels of the image's natural size.
+    ImageAnnotator {
+        src: String,
+        /// Labels offered for new shapes; shapes are unlabeled when empty.
+        labels: Vec<String>,
+        annotations: Vec<ImageAnnotation>,
+        key: String,
+        #[serde(default)]
+        disabled: bool,
+    },
+    Audio {
+        src: String,
+        /// Play once when first shown, e.g. spoken replies.
+        #[serde(default)]
+        autoplay: bool,
+    },
+    /// Waveform of an audio clip, on which users select time regions; the
+    /// regions are sent as widget values, as seconds from the start.
+    AudioWaveform {
+        src: String,
+        regions: Vec<(f64, f64)>,
+        key: String,
+        #[serde(default)]
+        disabled: bool,
+    },
     Video { src: String },
+    DownloadButton {
+        label: String,
+        file_name: String,
+        mime_type: String,
+        url: String,
+        key: Option<String>,
+        #[serde(default)]
+        disabled: bool,
+    },
 
     // Feedback
     Success { message: String },
     Error { message: String },
     Warning { message: String },
     Info { message: String },
-    Progress { value: f32 },
+    /// Validation errors of a fieldset's fields, shown together.
+    ValidationSummary { errors: Vec<String> },
+    /// Placeholder shaped like content that is still loading, e.g. `count`
+    /// lines of text or rows of a table.
+    Skeleton { kind: SkeletonKind, count: u32 },
+    /// Placeholder for content built once the client scrolls it into view.
+    Lazy { key: String },
+    Progress {
+        value: f32,
+        text: Option<String>,
+        indeterminate: bool,
+        eta_secs: Option<f64>,
+    },
+    NotificationCenter { notifications: Vec<NotificationItem>, key: Option<String> },
+    /// Buffered log lines; later lines are streamed to the client by key.
+    LogViewer { lines: Vec<LogLineItem>, capacity: usize, key: String },
 
     // Advanced Layout
     Tabs { tabs: Vec<(String, Vec<ElementId>)> },
-    Sidebar { children: Vec<ElementId> },
-    Metric { label: String, value: String, delta: Option<String> },
-
-    // Charts
-    LineChart { data: String, title: Option<String> },
-    BarChart { data: String, title: Option<String> },
-    AreaChart { data: String, title: Option<String> },
-    ScatterChart { data: String, title: Option<String> },
-    PieChart { data: String, title: Option<String> },
+    Sidebar { children: Vec<ElementId>, #[serde(default)] hidden: bool },
+    Metric {
+        label: String,
+        value: String,
+        delta: Option<String>,
+        /// Recent values drawn as an inline sparkline, at most
+        /// `SPARKLINE_POINTS` long.
+        #[serde(default)]
+        sparkline: Vec<f64>,
+    },
+    Gauge { data: String },  // JSON-encoded value, range and thresholds
+    Donut { data: String },  // JSON-encoded segments
+
+    // Charts; with a `key`, clicks, selections and zooms are reported back
+    LineChart { data: String, title: Option<String>, #[serde(default)] key: Option<String> },
+    BarChart { data: String, title: Option<String>, #[serde(default)] key: Option<String> },
+    AreaChart { data: String, title: Option<String>, #[serde(default)] key: Option<String> },
+    ScatterChart { data: String, title: Option<String>, #[serde(default)] key: Option<String> },
+    PieChart { data: String, title: Option<String>, #[serde(default)] key: Option<String> },
+    /// OHLC candles with computed overlays and an optional volume pane.
+    CandlestickChart {
+        data: String, // JSON-encoded candles
+        title: Option<String>,
+        #[serde(default)]
+        key: Option<String>,
+    },
+    /// Line chart whose points arrive as `AppendChartData` deltas; the
+    /// client keeps the newest `capacity` of them across reruns.
+    LineChartStream { key: String, capacity: usize },
+    /// Colored matrix; clicked cells are sent as widget values.
+    Heatmap { key: String, data: String, title: Option<String> }, // JSON-encoded matrix
     PlotlyChart { spec: String },
     VegaLiteChart { spec: String },
     BokehChart { spec: String },
 
+    // Extension
+    /// Element defined outside platypus, rendered by a client-side renderer
+    /// registered under `name`.
+    Custom {
+        name: String,
+        #[cfg_attr(feature = "arbitrary", arbitrary(with = crate::arbitrary::json_value))]
+        payload: serde_json::Value,
+    },
+
     // Other
     Empty,
     Divider,
+    /// An element of a type this side does not know, e.g. from a newer
+    /// server or client; shown as an "unsupported element" placeholder.
+    Unknown { type_name: String },
+}
+
+impl ElementType {
+    /// Hide or show a layout container, keeping its elements. Returns
+    /// `false` for other elements, which cannot be hidden.
+    pub fn set_hidden(&mut self, hidden: bool) -> bool {
+        match self {
+            ElementType::Container { hidden: flag, .. }
+            | ElementType::Column { hidden: flag, .. }
+            | ElementType::Row { hidden: flag, .. }
+            | ElementType::Tab { hidden: flag, .. }
+            | ElementType::Expander { hidden: flag, .. }
+            | ElementType::Fieldset { hidden: flag, .. }
+            | ElementType::Form { hidden: flag, .. }
+            | ElementType::ResponsiveContainer { hidden: flag, .. }
+            | ElementType::Sidebar { hidden: flag, .. } => {
+                *flag = hidden;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Kind of text input, controlling masking, input-mode hints and validation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[serde(rename_all = "lowercase")]
+pub enum TextInputKind {
+    #[default]
+    Text,
+    Password,
+    Email,
+    Url,
+    Tel,
+}
+
+impl TextInputKind {
+    /// Get the kind name.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TextInputKind::Text => "text",
+            TextInputKind::Password => "password",
+            TextInputKind::Email => "email",
+            TextInputKind::Url => "url",
+            TextInputKind::Tel => "tel",
+        }
+    }
+
+    /// Check a value's format, returning an error message if it is invalid.
+    ///
+    /// Empty values are always accepted.
+    pub fn validate(self, value: &str) -> Option<String> {
+        let value = value.trim();
+        if value.is_empty() {
+            return None;
+        }
+
+        let valid = match self {
+            TextInputKind::Text | TextInputKind::Password => true,
+            TextInputKind::Email => match value.split_once('@') {
+                Some((local, domain)) => {
+                    !local.is_empty()
+                        && !domain.contains('@')
+                        && domain.split('.').count() > 1
+                        && domain.split('.').all(|part| !part.is_empty())
+                        && !value.contains(char::is_whitespace)
+                }
+                None => false,
+            },
+            TextInputKind::Url => ["http://", "https://"].iter().any(|scheme| {
+                value
+                    .strip_prefix(scheme)
+                    .is_some_and(|rest| !rest.is_empty() && !rest.starts_with('/'))
+            }) && !value.contains(char::is_whitespace),
+            TextInputKind::Tel => {
+                value.chars().all(|c| c.is_ascii_digit() || " +-().".contains(c))
+                    && value.chars().filter(|c| c.is_ascii_digit()).count() >= 7
+            }
+        };
+
+        if valid {
+            None
+        } else {
+            Some(format!("Please enter a valid {}", match self {
+                TextInputKind::Url => "URL",
+                TextInputKind::Tel => "phone number",
+                _ => "email address",
+            }))
+        }
+    }
+}
+
+/// How often a high-frequency widget may send changes. Zero disables a limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct RateLimit {
+    /// Send only once the widget has been idle this long.
+    pub debounce_ms: u32,
+    /// Send at most once per this interval while the widget changes.
+    pub throttle_ms: u32,
+}
+
+impl RateLimit {
+    /// No limit: every change is sent.
+    pub const NONE: RateLimit = RateLimit { debounce_ms: 0, throttle_ms: 0 };
+
+    /// Wait until the widget has been idle for `ms`.
+    pub const fn debounce(ms: u32) -> Self {
+        RateLimit { debounce_ms: ms, throttle_ms: 0 }
+    }
+
+    /// Send at most once every `ms`.
+    pub const fn throttle(ms: u32) -> Self {
+        RateLimit { debounce_ms: 0, throttle_ms: ms }
+    }
+
+    /// Check if no limit is set.
+    pub fn is_none(&self) -> bool {
+        self.debounce_ms == 0 && self.throttle_ms == 0
+    }
+
+    /// Get the shortest interval between two reruns this limit allows.
+    pub fn window(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.debounce_ms.max(self.throttle_ms).into())
+    }
+}
+
+/// Shape of a loading placeholder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[serde(rename_all = "lowercase")]
+pub enum SkeletonKind {
+    /// Lines of text.
+    Text,
+    /// Rows of a table.
+    Table,
+    /// A chart.
+    Chart,
+    /// Metric cards.
+    Metric,
+    /// An image.
+    Image,
+}
+
+impl SkeletonKind {
+    /// Get the kind name.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SkeletonKind::Text => "text",
+            SkeletonKind::Table => "table",
+            SkeletonKind::Chart => "chart",
+            SkeletonKind::Metric => "metric",
+            SkeletonKind::Image => "image",
+        }
+    }
+}
+
+/// Style of a feedback widget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[serde(rename_all = "lowercase")]
+pub enum FeedbackKind {
+    /// Thumbs down (0) or up (1).
+    Thumbs,
+    /// One to five stars.
+    Stars,
+}
+
+impl FeedbackKind {
+    /// Get the kind name.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FeedbackKind::Thumbs => "thumbs",
+            FeedbackKind::Stars => "stars",
+        }
+    }
+
+    /// Get the valid score range.
+    pub fn score_range(self) -> std::ops::RangeInclusive<u32> {
+        match self {
+            FeedbackKind::Thumbs => 0..=1,
+            FeedbackKind::Stars => 1..=5,
+        }
+    }
+}
+
+/// A notification shown in the notification center panel.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct NotificationItem {
+    pub id: String,
+    pub level: String,
+    pub message: String,
+    pub read: bool,
+}
+
+/// A line shown in the log viewer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct LogLineItem {
+    pub seq: u64,
+    pub level: String,
+    pub message: String,
+    /// RFC 3339 timestamp.
+    pub timestamp: String,
+}
+
+/// A message attached to a line in the code editor.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct CodeAnnotation {
+    /// 1-based line number.
+    pub line: u32,
+    pub severity: String,
+    pub message: String,
+}
+
+/// A point on an image, in pixels of its natural size.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct AnnotationPoint {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// A shape drawn on an image.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[serde(tag = "shape", rename_all = "lowercase")]
+pub enum AnnotationShape {
+    /// Axis-aligned box from its top-left corner.
+    Box { x: f64, y: f64, width: f64, height: f64 },
+    /// Closed polygon through at least three points.
+    Polygon { points: Vec<AnnotationPoint> },
+}
+
+impl AnnotationShape {
+    /// Get the bounding box as `(x, y, width, height)`.
+    pub fn bounds(&self) -> (f64, f64, f64, f64) {
+        match self {
+            AnnotationShape::Box { x, y, width, height } => (*x, *y, *width, *height),
+            AnnotationShape::Polygon { points } => {
+                let (min_x, max_x) = extent(points.iter().map(|p| p.x));
+                let (min_y, max_y) = extent(points.iter().map(|p| p.y));
+                (min_x, min_y, max_x - min_x, max_y - min_y)
+            }
+        }
+    }
+}
+
+fn extent(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    values.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| (min.min(v), max.max(v)))
+}
+
+/// A labeled shape drawn in the image annotator.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct ImageAnnotation {
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(flatten)]
+    pub shape: AnnotationShape,
+}
+
+/// A parse or schema validation error shown in the JSON editor.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct JsonEditorError {
+    /// JSON pointer to the invalid value (empty for the root).
+    pub path: String,
+    pub message: String,
+}
+
+/// ARIA attributes applied to a rendered element.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AccessibilityInfo {
+    pub label: Option<String>,
+    pub role: Option<String>,
+    pub description: Option<String>,
+}
+
+impl AccessibilityInfo {
+    /// Collect the attributes of an accessible element.
+    pub fn of(element: &dyn Accessible) -> Self {
+        AccessibilityInfo {
+            label: element.aria_label().map(str::to_string),
+            role: element.aria_role().map(str::to_string),
+            description: element.aria_description().map(str::to_string),
+        }
+    }
+
+    /// Check if no attribute is set.
+    pub fn is_empty(&self) -> bool {
+        self.label.is_none() && self.role.is_none() && self.description.is_none()
+    }
 }
 
 /// Trait for UI elements.
@@ -142,6 +717,15 @@ mod tests {
         let id = ElementId::new(42);
         assert_eq!(id.inner(), 42);
         assert_eq!(id.to_string(), "42");
+
+        let namespaced = ElementId::namespaced(3, 42);
+        assert_ne!(namespaced, id);
+        assert_eq!(namespaced.to_string(), "3-42");
+        assert_eq!("3-42".parse::<ElementId>(), Ok(namespaced));
+        assert_eq!("42".parse::<ElementId>(), Ok(id));
+        assert!("3-".parse::<ElementId>().is_err());
+        assert_eq!(serde_json::to_value(namespaced).unwrap(), "3-42");
+        assert_eq!(serde_json::from_value::<ElementId>(42.into()).unwrap(), id);
     }
 
     #[test]
@@ -150,4 +734,15 @@ mod tests {
         let elem = SimpleElement::new(id, ElementType::Text { value: "Hello".to_string() });
         assert_eq!(elem.id(), id);
     }
+
+    #[test]
+    fn test_text_input_kind_validate() {
+        assert!(TextInputKind::Email.validate("a@example.com").is_none());
+        assert!(TextInputKind::Email.validate("a@example").is_some());
+        assert!(TextInputKind::Url.validate("https://example.com/x").is_none());
+        assert!(TextInputKind::Url.validate("example.com").is_some());
+        assert!(TextInputKind::Tel.validate("+1 (555) 123-4567").is_none());
+        assert!(TextInputKind::Tel.validate("call me").is_some());
+        assert!(TextInputKind::Email.validate("").is_none());
+    }
 }
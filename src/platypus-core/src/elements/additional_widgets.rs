@@ -1,6 +1,6 @@
 //! Additional widget elements - Radio, Multiselect, DatePicker, TimePicker, etc.
 
-use crate::element::ElementId;
+use crate::element::{ElementId, ElementType};
 use crate::traits::{Renderable, Validatable, Interactive, InteractionEvent};
 use crate::error::Result;
 use serde_json::Value;
@@ -21,7 +21,7 @@ impl RadioElement {
     /// Create a new radio element.
     pub fn new(id: ElementId, label: impl Into<String>, options: Vec<String>) -> Result<Self> {
         if options.is_empty() {
-            return Err(crate::error::Error::StateError("Radio must have at least one option".to_string()));
+            return Err(crate::error::Error::validation("options", "must not be empty"));
         }
         Ok(Self {
             base: BaseElement::new(id, "radio"),
@@ -55,7 +55,7 @@ impl RadioElement {
     /// Set the selected index.
     pub fn set_selected_index(&mut self, index: usize) -> Result<()> {
         if index >= self.options.len() {
-            return Err(crate::error::Error::StateError("Index out of bounds".to_string()));
+            return Err(crate::error::Error::validation("index", "must be less than the number of options"));
         }
         self.selected_index = index;
         Ok(())
@@ -84,15 +84,29 @@ impl Renderable for RadioElement {
     fn as_any(&self) -> &dyn Any {
         self
     }
+
+    fn to_element_type(&self) -> Option<ElementType> {
+        Some(ElementType::Radio {
+            label: self.label.clone(),
+            options: self.options.clone(),
+            value: self.options.get(self.selected_index).cloned(),
+            key: None,
+            disabled: self.disabled,
+        })
+    }
+
+    fn as_validatable(&self) -> Option<&dyn Validatable> {
+        Some(self)
+    }
 }
 
 impl Validatable for RadioElement {
     fn validate(&self) -> Result<()> {
         if self.label.is_empty() {
-            return Err(crate::error::Error::StateError("Radio label cannot be empty".to_string()));
+            return Err(crate::error::Error::validation("label", "must not be empty"));
         }
         if self.options.is_empty() {
-            return Err(crate::error::Error::StateError("Radio must have at least one option".to_string()));
+            return Err(crate::error::Error::validation("options", "must not be empty"));
         }
         Ok(())
     }
@@ -100,12 +114,11 @@ impl Validatable for RadioElement {
 
 impl Interactive for RadioElement {
     fn handle_event(&mut self, event: &InteractionEvent) -> Result<()> {
-        if event.event_type == "change" {
-            if let Some(Value::Number(n)) = &event.data {
-                if let Some(index) = n.as_u64() {
-                    self.set_selected_index(index as usize)?;
-                }
-            }
+        if event.event_type == "change"
+            && let Some(Value::Number(n)) = &event.data
+            && let Some(index) = n.as_u64()
+        {
+            self.set_selected_index(index as usize)?;
         }
         Ok(())
     }
@@ -120,10 +133,10 @@ impl Interactive for RadioElement {
                 self.selected_index = index;
                 Ok(())
             } else {
-                Err(crate::error::Error::StateError("Value not found in options".to_string()))
+                Err(crate::error::Error::validation("value", "must be one of the options"))
             }
         } else {
-            Err(crate::error::Error::InvalidWidgetValue("Invalid radio value".to_string()))
+            Err(crate::error::Error::validation("value", "must be a string"))
         }
     }
 
@@ -150,7 +163,7 @@ impl MultiselectElement {
     /// Create a new multiselect element.
     pub fn new(id: ElementId, label: impl Into<String>, options: Vec<String>) -> Result<Self> {
         if options.is_empty() {
-            return Err(crate::error::Error::StateError("Multiselect must have at least one option".to_string()));
+            return Err(crate::error::Error::validation("options", "must not be empty"));
         }
         Ok(Self {
             base: BaseElement::new(id, "multiselect"),
@@ -186,7 +199,7 @@ impl MultiselectElement {
     /// Add a selection.
     pub fn add_selection(&mut self, index: usize) -> Result<()> {
         if index >= self.options.len() {
-            return Err(crate::error::Error::StateError("Index out of bounds".to_string()));
+            return Err(crate::error::Error::validation("index", "must be less than the number of options"));
         }
         if !self.selected_indices.contains(&index) {
             self.selected_indices.push(index);
@@ -228,15 +241,29 @@ impl Renderable for MultiselectElement {
     fn as_any(&self) -> &dyn Any {
         self
     }
+
+    fn to_element_type(&self) -> Option<ElementType> {
+        Some(ElementType::Multiselect {
+            label: self.label.clone(),
+            options: self.options.clone(),
+            values: self.selected_values().into_iter().map(str::to_string).collect(),
+            key: None,
+            disabled: self.disabled,
+        })
+    }
+
+    fn as_validatable(&self) -> Option<&dyn Validatable> {
+        Some(self)
+    }
 }
 
 impl Validatable for MultiselectElement {
     fn validate(&self) -> Result<()> {
         if self.label.is_empty() {
-            return Err(crate::error::Error::StateError("Multiselect label cannot be empty".to_string()));
+            return Err(crate::error::Error::validation("label", "must not be empty"));
         }
         if self.options.is_empty() {
-            return Err(crate::error::Error::StateError("Multiselect must have at least one option".to_string()));
+            return Err(crate::error::Error::validation("options", "must not be empty"));
         }
         Ok(())
     }
@@ -244,13 +271,13 @@ impl Validatable for MultiselectElement {
 
 impl Interactive for MultiselectElement {
     fn handle_event(&mut self, event: &InteractionEvent) -> Result<()> {
-        if event.event_type == "change" {
-            if let Some(Value::Array(indices)) = &event.data {
-                self.selected_indices.clear();
-                for val in indices {
-                    if let Some(n) = val.as_u64() {
-                        self.add_selection(n as usize)?;
-                    }
+        if event.event_type == "change"
+            && let Some(Value::Array(indices)) = &event.data
+        {
+            self.selected_indices.clear();
+            for val in indices {
+                if let Some(n) = val.as_u64() {
+                    self.add_selection(n as usize)?;
                 }
             }
         }
@@ -270,15 +297,15 @@ impl Interactive for MultiselectElement {
         if let Some(arr) = value.as_array() {
             self.selected_indices.clear();
             for val in arr {
-                if let Some(s) = val.as_str() {
-                    if let Some(index) = self.options.iter().position(|opt| opt == s) {
-                        self.selected_indices.push(index);
-                    }
+                if let Some(s) = val.as_str()
+                    && let Some(index) = self.options.iter().position(|opt| opt == s)
+                {
+                    self.selected_indices.push(index);
                 }
             }
             Ok(())
         } else {
-            Err(crate::error::Error::InvalidWidgetValue("Invalid multiselect value".to_string()))
+            Err(crate::error::Error::validation("value", "must be an array of strings"))
         }
     }
 
@@ -359,12 +386,25 @@ impl Renderable for DatePickerElement {
     fn as_any(&self) -> &dyn Any {
         self
     }
+
+    fn to_element_type(&self) -> Option<ElementType> {
+        Some(ElementType::DateInput {
+            label: self.label.clone(),
+            value: self.value.clone(),
+            key: None,
+            disabled: self.disabled,
+        })
+    }
+
+    fn as_validatable(&self) -> Option<&dyn Validatable> {
+        Some(self)
+    }
 }
 
 impl Validatable for DatePickerElement {
     fn validate(&self) -> Result<()> {
         if self.label.is_empty() {
-            return Err(crate::error::Error::StateError("DatePicker label cannot be empty".to_string()));
+            return Err(crate::error::Error::validation("label", "must not be empty"));
         }
         Ok(())
     }
@@ -372,10 +412,10 @@ impl Validatable for DatePickerElement {
 
 impl Interactive for DatePickerElement {
     fn handle_event(&mut self, event: &InteractionEvent) -> Result<()> {
-        if event.event_type == "change" {
-            if let Some(Value::String(date)) = &event.data {
-                self.value = date.clone();
-            }
+        if event.event_type == "change"
+            && let Some(Value::String(date)) = &event.data
+        {
+            self.value = date.clone();
         }
         Ok(())
     }
@@ -389,7 +429,7 @@ impl Interactive for DatePickerElement {
             self.value = s.to_string();
             Ok(())
         } else {
-            Err(crate::error::Error::InvalidWidgetValue("Invalid date value".to_string()))
+            Err(crate::error::Error::validation("value", "must be a date string"))
         }
     }
 
@@ -1,12 +1,31 @@
 //! Advanced elements - complex elements for specialized use cases.
 
-use crate::element::ElementId;
+use crate::element::{ElementId, ElementType};
 use crate::traits::Renderable;
 use crate::error::Result;
 use serde_json::Value;
 use std::any::Any;
 use super::BaseElement;
 
+/// Maximum number of points in a metric's sparkline.
+pub const SPARKLINE_POINTS: usize = 64;
+
+/// Downsample `values` to at most `max_points` by averaging equal-width
+/// buckets, skipping non-finite values.
+pub fn downsample(values: &[f64], max_points: usize) -> Vec<f64> {
+    let values: Vec<f64> = values.iter().copied().filter(|value| value.is_finite()).collect();
+    if values.len() <= max_points {
+        return values;
+    }
+    (0..max_points)
+        .map(|bucket| {
+            let start = bucket * values.len() / max_points;
+            let end = (bucket + 1) * values.len() / max_points;
+            values[start..end].iter().sum::<f64>() / (end - start) as f64
+        })
+        .collect()
+}
+
 /// Metric element for displaying key metrics.
 #[derive(Debug, Clone)]
 pub struct MetricElement {
@@ -14,6 +33,7 @@ pub struct MetricElement {
     label: String,
     value: String,
     delta: Option<String>,
+    sparkline: Vec<f64>,
 }
 
 impl MetricElement {
@@ -24,6 +44,7 @@ impl MetricElement {
             label: label.into(),
             value: value.into(),
             delta: None,
+            sparkline: Vec::new(),
         }
     }
 
@@ -32,6 +53,12 @@ impl MetricElement {
         self.delta = Some(delta.into());
     }
 
+    /// Set the series drawn as a sparkline, downsampled to
+    /// `SPARKLINE_POINTS`.
+    pub fn set_sparkline(&mut self, series: &[f64]) {
+        self.sparkline = downsample(series, SPARKLINE_POINTS);
+    }
+
     /// Get the label.
     pub fn label(&self) -> &str {
         &self.label
@@ -46,6 +73,11 @@ impl MetricElement {
     pub fn delta(&self) -> Option<&str> {
         self.delta.as_deref()
     }
+
+    /// Get the sparkline series.
+    pub fn sparkline(&self) -> &[f64] {
+        &self.sparkline
+    }
 }
 
 impl Renderable for MetricElement {
@@ -64,12 +96,22 @@ impl Renderable for MetricElement {
             "label": self.label,
             "value": self.value,
             "delta": self.delta,
+            "sparkline": self.sparkline,
         }))
     }
 
     fn as_any(&self) -> &dyn Any {
         self
     }
+
+    fn to_element_type(&self) -> Option<ElementType> {
+        Some(ElementType::Metric {
+            label: self.label.clone(),
+            value: self.value.clone(),
+            delta: self.delta.clone(),
+            sparkline: self.sparkline.clone(),
+        })
+    }
 }
 
 #[cfg(test)]
@@ -94,4 +136,16 @@ mod tests {
         assert_eq!(json["type"], "metric");
         assert_eq!(json["label"], "Users");
     }
+
+    #[test]
+    fn test_sparkline_is_downsampled() {
+        let series: Vec<f64> = (0..1000).map(f64::from).collect();
+        let mut metric = MetricElement::new(ElementId::new(1), "Requests", "1,000");
+        metric.set_sparkline(&series);
+        assert_eq!(metric.sparkline().len(), SPARKLINE_POINTS);
+        assert!(metric.sparkline().windows(2).all(|pair| pair[0] < pair[1]));
+
+        assert_eq!(downsample(&[1.0, f64::NAN, 3.0], 8), vec![1.0, 3.0]);
+        assert_eq!(downsample(&[1.0, 3.0, 5.0, 7.0], 2), vec![2.0, 6.0]);
+    }
 }
@@ -3,8 +3,8 @@
 //! This module contains implementations for text, images, and other
 //! content display elements.
 
-use crate::element::ElementId;
-use crate::traits::{Renderable, Validatable, Styleable, Cacheable};
+use crate::element::{ElementId, ElementType};
+use crate::traits::{Renderable, Validatable, Styleable, Cacheable, Accessible};
 use crate::error::Result;
 use serde_json::Value;
 use std::any::Any;
@@ -82,17 +82,43 @@ impl Renderable for TextElement {
     fn as_any(&self) -> &dyn Any {
         self
     }
+
+    fn to_element_type(&self) -> Option<ElementType> {
+        Some(ElementType::Text { value: self.content.clone() })
+    }
+
+    fn as_validatable(&self) -> Option<&dyn Validatable> {
+        Some(self)
+    }
+
+    fn as_accessible(&self) -> Option<&dyn Accessible> {
+        Some(self)
+    }
 }
 
 impl Validatable for TextElement {
     fn validate(&self) -> Result<()> {
         if self.content.is_empty() {
-            return Err(crate::error::Error::StateError("Text content cannot be empty".to_string()));
+            return Err(crate::error::Error::validation("content", "must not be empty"));
         }
         Ok(())
     }
 }
 
+impl Accessible for TextElement {
+    fn aria_label(&self) -> Option<&str> {
+        self.base.metadata().aria_label.as_deref()
+    }
+
+    fn aria_role(&self) -> Option<&str> {
+        self.base.metadata().aria_role.as_deref()
+    }
+
+    fn aria_description(&self) -> Option<&str> {
+        None
+    }
+}
+
 impl Styleable for TextElement {
     fn css_classes(&self) -> Vec<&str> {
         self.base.metadata().css_classes.iter().map(|s| s.as_str()).collect()
@@ -128,8 +154,8 @@ pub struct HeadingElement {
 impl HeadingElement {
     /// Create a new heading element.
     pub fn new(id: ElementId, content: impl Into<String>, level: u32) -> Result<Self> {
-        if level < 1 || level > 6 {
-            return Err(crate::error::Error::StateError("Heading level must be between 1 and 6".to_string()));
+        if !(1..=6).contains(&level) {
+            return Err(crate::error::Error::validation("level", "must be between 1 and 6"));
         }
         Ok(Self {
             base: BaseElement::new(id, format!("heading_{}", level)),
@@ -170,15 +196,23 @@ impl Renderable for HeadingElement {
     fn as_any(&self) -> &dyn Any {
         self
     }
+
+    fn to_element_type(&self) -> Option<ElementType> {
+        Some(ElementType::Heading { value: self.content.clone(), level: self.level })
+    }
+
+    fn as_validatable(&self) -> Option<&dyn Validatable> {
+        Some(self)
+    }
 }
 
 impl Validatable for HeadingElement {
     fn validate(&self) -> Result<()> {
         if self.content.is_empty() {
-            return Err(crate::error::Error::StateError("Heading content cannot be empty".to_string()));
+            return Err(crate::error::Error::validation("content", "must not be empty"));
         }
         if self.level < 1 || self.level > 6 {
-            return Err(crate::error::Error::StateError("Heading level must be between 1 and 6".to_string()));
+            return Err(crate::error::Error::validation("level", "must be between 1 and 6"));
         }
         Ok(())
     }
@@ -266,15 +300,27 @@ impl Renderable for ImageElement {
     fn as_any(&self) -> &dyn Any {
         self
     }
+
+    fn to_element_type(&self) -> Option<ElementType> {
+        Some(ElementType::Image {
+            src: self.src.clone(),
+            caption: (!self.alt.is_empty()).then(|| self.alt.clone()),
+            width: self.width,
+        })
+    }
+
+    fn as_validatable(&self) -> Option<&dyn Validatable> {
+        Some(self)
+    }
 }
 
 impl Validatable for ImageElement {
     fn validate(&self) -> Result<()> {
         if self.src.is_empty() {
-            return Err(crate::error::Error::StateError("Image source cannot be empty".to_string()));
+            return Err(crate::error::Error::validation("source", "must not be empty"));
         }
         if self.alt.is_empty() {
-            return Err(crate::error::Error::StateError("Image alt text cannot be empty".to_string()));
+            return Err(crate::error::Error::validation("alt text", "must not be empty"));
         }
         Ok(())
     }
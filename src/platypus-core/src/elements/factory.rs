@@ -1,15 +1,19 @@
 //! Element factory for convenient element creation.
 //!
 //! Provides builder-style API for creating elements with fluent interface.
+//! Element types without a dedicated struct are built as [`TypedElement`]s,
+//! validated on construction.
 
-use crate::element::ElementId;
+use crate::element::{ElementId, ElementType};
 use crate::error::Result;
+use crate::traits::{Container, Renderable};
 use super::{
     display::{TextElement, HeadingElement, ImageElement},
     input::{ButtonElement, TextInputElement},
     layout::ContainerElement,
     feedback::{FeedbackElement, FeedbackType},
     advanced::MetricElement,
+    typed::{ChartKind, TypedElement},
 };
 
 /// Builder for creating elements with fluent API.
@@ -77,6 +81,128 @@ impl ElementBuilder {
     pub fn metric(self, label: impl Into<String>, value: impl Into<String>) -> MetricElement {
         MetricElement::new(self.id, label, value)
     }
+
+    /// Create any element type, validating its properties.
+    pub fn element(self, element: ElementType) -> Result<TypedElement> {
+        TypedElement::checked(self.id, element)
+    }
+
+    /// Create a markdown element.
+    pub fn markdown(self, value: impl Into<String>) -> TypedElement {
+        TypedElement::new(self.id, ElementType::Markdown { value: value.into() })
+    }
+
+    /// Create a code block element.
+    pub fn code(self, value: impl Into<String>, language: Option<String>) -> TypedElement {
+        TypedElement::new(self.id, ElementType::Code { value: value.into(), language })
+    }
+
+    /// Create a JSON display element.
+    pub fn json(self, value: serde_json::Value) -> TypedElement {
+        TypedElement::new(self.id, ElementType::Json { value })
+    }
+
+    /// Create a chart from JSON-encoded data.
+    pub fn chart(self, kind: ChartKind, data: impl Into<String>, title: Option<String>) -> Result<TypedElement> {
+        self.element(kind.element(data.into(), title))
+    }
+
+    /// Create a Plotly chart from a JSON spec.
+    pub fn plotly_chart(self, spec: impl Into<String>) -> Result<TypedElement> {
+        self.element(ElementType::PlotlyChart { spec: spec.into() })
+    }
+
+    /// Create a Vega-Lite chart from a JSON spec.
+    pub fn vega_lite_chart(self, spec: impl Into<String>) -> Result<TypedElement> {
+        self.element(ElementType::VegaLiteChart { spec: spec.into() })
+    }
+
+    /// Create a Bokeh chart from a JSON spec.
+    pub fn bokeh_chart(self, spec: impl Into<String>) -> Result<TypedElement> {
+        self.element(ElementType::BokehChart { spec: spec.into() })
+    }
+
+    /// Create a table; every row must have one cell per header.
+    pub fn table(self, headers: Vec<String>, rows: Vec<Vec<String>>) -> Result<TypedElement> {
+        self.element(ElementType::Table { headers, rows })
+    }
+
+    /// Create a dataframe from JSON-encoded data.
+    pub fn dataframe(self, data: impl Into<String>) -> Result<TypedElement> {
+        self.element(ElementType::Dataframe { data: data.into() })
+    }
+
+    /// Create an audio player.
+    pub fn audio(self, src: impl Into<String>) -> Result<TypedElement> {
+        self.element(ElementType::Audio {
+            src: src.into(),
+            autoplay: false,
+        })
+    }
+
+    /// Create a video player.
+    pub fn video(self, src: impl Into<String>) -> Result<TypedElement> {
+        self.element(ElementType::Video { src: src.into() })
+    }
+
+    /// Create a download button.
+    pub fn download_button(
+        self,
+        label: impl Into<String>,
+        file_name: impl Into<String>,
+        mime_type: impl Into<String>,
+        url: impl Into<String>,
+    ) -> Result<TypedElement> {
+        self.element(ElementType::DownloadButton {
+            label: label.into(),
+            file_name: file_name.into(),
+            mime_type: mime_type.into(),
+            url: url.into(),
+            key: None,
+            disabled: false,
+        })
+    }
+
+    /// Create a column with an optional relative width.
+    pub fn column(self, width: Option<f32>) -> Result<TypedElement> {
+        self.element(ElementType::Column { children: vec![], width, hidden: false })
+    }
+
+    /// Create a row.
+    pub fn row(self) -> TypedElement {
+        TypedElement::new(self.id, ElementType::Row { children: vec![], hidden: false })
+    }
+
+    /// Create a single tab.
+    pub fn tab(self, label: impl Into<String>) -> TypedElement {
+        TypedElement::new(self.id, ElementType::Tab { label: label.into(), children: vec![], hidden: false })
+    }
+
+    /// Create a tab group with empty tabs.
+    pub fn tabs(self, labels: Vec<String>) -> Result<TypedElement> {
+        let tabs = labels.into_iter().map(|label| (label, vec![])).collect();
+        self.element(ElementType::Tabs { tabs })
+    }
+
+    /// Create an expander.
+    pub fn expander(self, label: impl Into<String>, expanded: bool) -> TypedElement {
+        TypedElement::new(self.id, ElementType::Expander { label: label.into(), expanded, children: vec![], hidden: false })
+    }
+
+    /// Create a sidebar.
+    pub fn sidebar(self) -> TypedElement {
+        TypedElement::new(self.id, ElementType::Sidebar { children: vec![], hidden: false })
+    }
+
+    /// Create a progress bar; `value` must be within 0.0..=1.0.
+    pub fn progress(self, value: f32) -> Result<TypedElement> {
+        self.element(ElementType::Progress { value, text: None, indeterminate: false, eta_secs: None })
+    }
+
+    /// Create a divider.
+    pub fn divider(self) -> TypedElement {
+        TypedElement::new(self.id, ElementType::Divider)
+    }
 }
 
 /// Factory for creating elements.
@@ -142,6 +268,56 @@ impl ElementFactory {
     pub fn metric(id: ElementId, label: impl Into<String>, value: impl Into<String>) -> MetricElement {
         MetricElement::new(id, label, value)
     }
+
+    /// Create any element type directly, validating its properties.
+    pub fn element(id: ElementId, element: ElementType) -> Result<TypedElement> {
+        TypedElement::checked(id, element)
+    }
+
+    /// Create a chart directly from JSON-encoded data.
+    pub fn chart(id: ElementId, kind: ChartKind, data: impl Into<String>, title: Option<String>) -> Result<TypedElement> {
+        ElementBuilder::new(id).chart(kind, data, title)
+    }
+
+    /// Create a table directly.
+    pub fn table(id: ElementId, headers: Vec<String>, rows: Vec<Vec<String>>) -> Result<TypedElement> {
+        ElementBuilder::new(id).table(headers, rows)
+    }
+
+    /// Bridge an element type into the trait-object world.
+    ///
+    /// Variants with a dedicated struct that holds all of their fields
+    /// (text, headings, metrics, feedback, containers) become that struct;
+    /// everything else becomes a [`TypedElement`]. No validation is done,
+    /// so the result always represents `element`.
+    pub fn from_element_type(id: ElementId, element: ElementType) -> Box<dyn Renderable> {
+        match element {
+            ElementType::Text { value } => Box::new(TextElement::new(id, value)),
+            ElementType::Heading { value, level } if (1..=6).contains(&level) => {
+                Box::new(HeadingElement::new(id, value, level).expect("level checked"))
+            }
+            ElementType::Metric { label, value, delta, sparkline } => {
+                let mut metric = MetricElement::new(id, label, value);
+                if let Some(delta) = delta {
+                    metric.set_delta(delta);
+                }
+                metric.set_sparkline(&sparkline);
+                Box::new(metric)
+            }
+            ElementType::Success { message } => Box::new(FeedbackElement::new(id, FeedbackType::Success, message)),
+            ElementType::Error { message } => Box::new(FeedbackElement::new(id, FeedbackType::Error, message)),
+            ElementType::Warning { message } => Box::new(FeedbackElement::new(id, FeedbackType::Warning, message)),
+            ElementType::Info { message } => Box::new(FeedbackElement::new(id, FeedbackType::Info, message)),
+            ElementType::Container { children, hidden: false } => {
+                let mut container = ContainerElement::new(id);
+                for child in children {
+                    container.add_child(child).expect("containers accept any child");
+                }
+                Box::new(container)
+            }
+            element => Box::new(TypedElement::new(id, element)),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -182,4 +358,25 @@ mod tests {
         let success = ElementFactory::success(id, "Success!");
         assert_eq!(success.feedback_type(), FeedbackType::Success);
     }
+
+    #[test]
+    fn test_element_builder_validates() {
+        let id = ElementId::new(1);
+        assert!(ElementBuilder::new(id).chart(ChartKind::Line, "[1, 2, 3]", None).is_ok());
+        assert!(ElementBuilder::new(id).chart(ChartKind::Line, "not json", None).is_err());
+        assert!(ElementBuilder::new(id).audio("").is_err());
+        assert!(ElementBuilder::new(id).column(Some(0.0)).is_err());
+    }
+
+    #[test]
+    fn test_from_element_type() {
+        let id = ElementId::new(1);
+        let text = ElementFactory::from_element_type(id, ElementType::Text { value: "Hi".to_string() });
+        assert_eq!(text.as_any().downcast_ref::<TextElement>().unwrap().content(), "Hi");
+
+        let chart = ElementFactory::from_element_type(id, ElementType::PieChart { data: "[]".to_string(), title: None, key: None });
+        assert_eq!(chart.name(), "pie_chart");
+        assert!(chart.as_any().downcast_ref::<TypedElement>().is_some());
+        assert!(matches!(text.to_element_type(), Some(ElementType::Text { value }) if value == "Hi"));
+    }
 }
@@ -1,6 +1,6 @@
 //! Feedback elements - elements for displaying feedback messages.
 
-use crate::element::ElementId;
+use crate::element::{ElementId, ElementType};
 use crate::traits::Renderable;
 use crate::error::Result;
 use serde_json::Value;
@@ -80,6 +80,15 @@ impl Renderable for FeedbackElement {
     fn as_any(&self) -> &dyn Any {
         self
     }
+
+    fn to_element_type(&self) -> Option<ElementType> {
+        Some(match self.feedback_type {
+            FeedbackType::Success => ElementType::Success { message: self.message.clone() },
+            FeedbackType::Error => ElementType::Error { message: self.message.clone() },
+            FeedbackType::Warning => ElementType::Warning { message: self.message.clone() },
+            FeedbackType::Info => ElementType::Info { message: self.message.clone() },
+        })
+    }
 }
 
 #[cfg(test)]
@@ -3,7 +3,7 @@
 //! This module contains implementations for buttons, text inputs,
 //! and other interactive form elements.
 
-use crate::element::ElementId;
+use crate::element::{ElementId, ElementType, RateLimit, TextInputKind};
 use crate::traits::{Renderable, Validatable, Styleable, Interactive, InteractionEvent};
 use crate::error::Result;
 use serde_json::Value;
@@ -73,12 +73,20 @@ impl Renderable for ButtonElement {
     fn as_any(&self) -> &dyn Any {
         self
     }
+
+    fn to_element_type(&self) -> Option<ElementType> {
+        Some(ElementType::Button { label: self.label.clone(), key: None, disabled: self.disabled })
+    }
+
+    fn as_validatable(&self) -> Option<&dyn Validatable> {
+        Some(self)
+    }
 }
 
 impl Validatable for ButtonElement {
     fn validate(&self) -> Result<()> {
         if self.label.is_empty() {
-            return Err(crate::error::Error::StateError("Button label cannot be empty".to_string()));
+            return Err(crate::error::Error::validation("label", "must not be empty"));
         }
         Ok(())
     }
@@ -119,7 +127,7 @@ impl Interactive for ButtonElement {
             self.click_count = count as u32;
             Ok(())
         } else {
-            Err(crate::error::Error::InvalidWidgetValue("Invalid value for button click count".to_string()))
+            Err(crate::error::Error::validation("click count", "must be a number"))
         }
     }
 
@@ -208,20 +216,36 @@ impl Renderable for TextInputElement {
     fn as_any(&self) -> &dyn Any {
         self
     }
+
+    fn to_element_type(&self) -> Option<ElementType> {
+        Some(ElementType::TextInput {
+            label: self.label.clone(),
+            value: self.value.clone(),
+            key: None,
+            kind: TextInputKind::Text,
+            error: None,
+            rate_limit: RateLimit::NONE,
+            disabled: self.disabled,
+        })
+    }
+
+    fn as_validatable(&self) -> Option<&dyn Validatable> {
+        Some(self)
+    }
 }
 
 impl Validatable for TextInputElement {
     fn validate(&self) -> Result<()> {
         if self.label.is_empty() {
-            return Err(crate::error::Error::StateError("Input label cannot be empty".to_string()));
+            return Err(crate::error::Error::validation("label", "must not be empty"));
         }
         if self.required && self.value.is_empty() {
-            return Err(crate::error::Error::StateError("This field is required".to_string()));
+            return Err(crate::error::Error::validation("value", "is required"));
         }
-        if let Some(max_len) = self.max_length {
-            if self.value.len() > max_len {
-                return Err(crate::error::Error::StateError(format!("Value exceeds maximum length of {}", max_len)));
-            }
+        if let Some(max_len) = self.max_length
+            && self.value.len() > max_len
+        {
+            return Err(crate::error::Error::validation("value", format!("must be at most {} long", max_len)));
         }
         Ok(())
     }
@@ -241,15 +265,15 @@ impl Styleable for TextInputElement {
 
 impl Interactive for TextInputElement {
     fn handle_event(&mut self, event: &InteractionEvent) -> Result<()> {
-        if event.event_type == "change" {
-            if let Some(Value::String(new_value)) = &event.data {
-                if let Some(max_len) = self.max_length {
-                    if new_value.len() > max_len {
-                        return Err(crate::error::Error::StateError(format!("Value exceeds maximum length of {}", max_len)));
-                    }
-                }
-                self.value = new_value.clone();
+        if event.event_type == "change"
+            && let Some(Value::String(new_value)) = &event.data
+        {
+            if let Some(max_len) = self.max_length
+                && new_value.len() > max_len
+            {
+                return Err(crate::error::Error::validation("value", format!("must be at most {} long", max_len)));
             }
+            self.value = new_value.clone();
         }
         Ok(())
     }
@@ -260,15 +284,15 @@ impl Interactive for TextInputElement {
 
     fn set_value(&mut self, value: Value) -> Result<()> {
         if let Some(s) = value.as_str() {
-            if let Some(max_len) = self.max_length {
-                if s.len() > max_len {
-                    return Err(crate::error::Error::StateError(format!("Value exceeds maximum length of {}", max_len)));
-                }
+            if let Some(max_len) = self.max_length
+                && s.len() > max_len
+            {
+                return Err(crate::error::Error::validation("value", format!("must be at most {} long", max_len)));
             }
             self.value = s.to_string();
             Ok(())
         } else {
-            Err(crate::error::Error::InvalidWidgetValue("Invalid value for text input".to_string()))
+            Err(crate::error::Error::validation("value", "must be a string"))
         }
     }
 
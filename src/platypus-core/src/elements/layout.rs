@@ -1,6 +1,6 @@
 //! Layout elements - container elements for organizing content.
 
-use crate::element::ElementId;
+use crate::element::{ElementId, ElementType};
 use crate::traits::{Renderable, Container};
 use crate::error::Result;
 use serde_json::Value;
@@ -44,6 +44,10 @@ impl Renderable for ContainerElement {
     fn as_any(&self) -> &dyn Any {
         self
     }
+
+    fn to_element_type(&self) -> Option<ElementType> {
+        Some(ElementType::Container { children: self.children.clone(), hidden: false })
+    }
 }
 
 impl Container for ContainerElement {
@@ -13,6 +13,7 @@ pub mod responsive;
 pub mod themeable;
 pub mod additional_widgets;
 pub mod factory;
+pub mod typed;
 
 pub use display::*;
 pub use input::*;
@@ -24,6 +25,7 @@ pub use responsive::*;
 pub use themeable::*;
 pub use additional_widgets::*;
 pub use factory::{ElementFactory, ElementBuilder};
+pub use typed::{ChartKind, TypedElement};
 
 use crate::element::ElementId;
 use crate::traits::Renderable;
@@ -19,23 +19,7 @@ pub struct ResponsiveContainerElement {
 impl ResponsiveContainerElement {
     /// Create a new responsive container.
     pub fn new(id: ElementId) -> Self {
-        let breakpoints = vec![
-            Breakpoint {
-                name: "mobile".to_string(),
-                min_width: 0,
-                max_width: Some(640),
-            },
-            Breakpoint {
-                name: "tablet".to_string(),
-                min_width: 641,
-                max_width: Some(1024),
-            },
-            Breakpoint {
-                name: "desktop".to_string(),
-                min_width: 1025,
-                max_width: None,
-            },
-        ];
+        let breakpoints = Breakpoint::defaults();
 
         Self {
             base: BaseElement::new(id, "responsive_container"),
@@ -113,8 +113,9 @@ impl Themeable for ThemedButtonElement {
 
     fn set_theme(&mut self, theme: &str) -> Result<()> {
         if !self.available_themes.contains(&theme.to_string()) {
-            return Err(crate::error::Error::StateError(
-                format!("Theme '{}' not available", theme)
+            return Err(crate::error::Error::validation(
+                "theme",
+                format!("'{}' is not available", theme),
             ));
         }
 
@@ -203,8 +204,9 @@ impl Themeable for ThemedTextElement {
 
     fn set_theme(&mut self, theme: &str) -> Result<()> {
         if !self.available_themes.contains(&theme.to_string()) {
-            return Err(crate::error::Error::StateError(
-                format!("Theme '{}' not available", theme)
+            return Err(crate::error::Error::validation(
+                "theme",
+                format!("'{}' is not available", theme),
             ));
         }
         self.theme = theme.to_string();
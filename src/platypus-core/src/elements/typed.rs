@@ -0,0 +1,341 @@
+//! Typed element - trait-object wrapper around any `ElementType`.
+//!
+//! Variants without a dedicated element struct (charts, tables, media,
+//! most layout) are exposed to the trait world through [`TypedElement`].
+
+use crate::element::{AccessibilityInfo, ElementId, ElementType, ImageAnnotation};
+use crate::error::{Error, Result};
+use crate::traits::{Accessible, Renderable, Validatable};
+use serde_json::Value;
+use std::any::Any;
+
+/// Kind of data-driven chart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChartKind {
+    Line,
+    Bar,
+    Area,
+    Scatter,
+    Pie,
+}
+
+impl ChartKind {
+    /// Build the chart element for JSON-encoded `data`.
+    pub fn element(self, data: String, title: Option<String>) -> ElementType {
+        self.keyed_element(data, title, None)
+    }
+
+    /// Build the chart element, reporting interactions under `key` if set.
+    pub fn keyed_element(self, data: String, title: Option<String>, key: Option<String>) -> ElementType {
+        match self {
+            ChartKind::Line => ElementType::LineChart { data, title, key },
+            ChartKind::Bar => ElementType::BarChart { data, title, key },
+            ChartKind::Area => ElementType::AreaChart { data, title, key },
+            ChartKind::Scatter => ElementType::ScatterChart { data, title, key },
+            ChartKind::Pie => ElementType::PieChart { data, title, key },
+        }
+    }
+}
+
+/// Any `ElementType` as a renderable element.
+#[derive(Debug, Clone)]
+pub struct TypedElement {
+    id: ElementId,
+    name: String,
+    element: ElementType,
+    accessibility: AccessibilityInfo,
+}
+
+impl TypedElement {
+    /// Wrap an element type without validating it.
+    pub fn new(id: ElementId, element: ElementType) -> Self {
+        Self {
+            id,
+            name: type_name(&element),
+            element,
+            accessibility: AccessibilityInfo::default(),
+        }
+    }
+
+    /// Wrap an element type, rejecting invalid properties.
+    pub fn checked(id: ElementId, element: ElementType) -> Result<Self> {
+        validate_element_type(&element)?;
+        Ok(Self::new(id, element))
+    }
+
+    /// Get the wrapped element type.
+    pub fn element(&self) -> &ElementType {
+        &self.element
+    }
+
+    /// Set the ARIA label.
+    pub fn set_aria_label(&mut self, label: impl Into<String>) {
+        self.accessibility.label = Some(label.into());
+    }
+
+    /// Set the ARIA role.
+    pub fn set_aria_role(&mut self, role: impl Into<String>) {
+        self.accessibility.role = Some(role.into());
+    }
+
+    /// Set the ARIA description.
+    pub fn set_aria_description(&mut self, description: impl Into<String>) {
+        self.accessibility.description = Some(description.into());
+    }
+
+    /// Unwrap the element type.
+    pub fn into_element(self) -> ElementType {
+        self.element
+    }
+}
+
+impl Renderable for TypedElement {
+    fn id(&self) -> ElementId {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn to_json(&self) -> Result<Value> {
+        let mut json = serde_json::to_value(&self.element)?;
+        if let Value::Object(map) = &mut json {
+            map.insert("id".to_string(), self.id.inner().into());
+            map.insert("type".to_string(), self.name.clone().into());
+        }
+        Ok(json)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn to_element_type(&self) -> Option<ElementType> {
+        Some(self.element.clone())
+    }
+
+    fn as_validatable(&self) -> Option<&dyn Validatable> {
+        Some(self)
+    }
+
+    fn as_accessible(&self) -> Option<&dyn Accessible> {
+        Some(self)
+    }
+}
+
+impl Validatable for TypedElement {
+    fn validate(&self) -> Result<()> {
+        validate_element_type(&self.element)
+    }
+}
+
+impl Accessible for TypedElement {
+    fn aria_label(&self) -> Option<&str> {
+        self.accessibility.label.as_deref()
+    }
+
+    fn aria_role(&self) -> Option<&str> {
+        self.accessibility.role.as_deref()
+    }
+
+    fn aria_description(&self) -> Option<&str> {
+        self.accessibility.description.as_deref()
+    }
+}
+
+/// Get the snake_case name of an element type, e.g. `line_chart`.
+pub fn type_name(element: &ElementType) -> String {
+    let tag = match serde_json::to_value(element) {
+        Ok(Value::Object(map)) => map.get("type").and_then(Value::as_str).map(str::to_string),
+        Ok(Value::String(tag)) => Some(tag),
+        _ => None,
+    }
+    .unwrap_or_default();
+
+    let mut name = String::with_capacity(tag.len() + 4);
+    for (index, c) in tag.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if index > 0 {
+                name.push('_');
+            }
+            name.push(c.to_ascii_lowercase());
+        } else {
+            name.push(c);
+        }
+    }
+    name
+}
+
+fn invalid(field: &str, rule: impl Into<String>) -> Result<()> {
+    Err(Error::validation(field, rule))
+}
+
+fn check_json(what: &str, text: &str) -> Result<()> {
+    match serde_json::from_str::<Value>(text) {
+        Ok(_) => Ok(()),
+        Err(e) => invalid(what, format!("must be valid JSON: {}", e)),
+    }
+}
+
+fn check_option(value: Option<&String>, options: &[String]) -> Result<()> {
+    match value {
+        Some(value) if !options.contains(value) => invalid("value", format!("'{}' is not one of the options", value)),
+        _ => Ok(()),
+    }
+}
+
+/// Check an element type's properties.
+///
+/// Variants with no constraints are always valid.
+pub fn validate_element_type(element: &ElementType) -> Result<()> {
+    match element {
+        ElementType::Heading { level, .. } if !(1..=6).contains(level) => {
+            invalid("level", "must be between 1 and 6")
+        }
+        ElementType::Slider { value, min, max, .. } => {
+            if min >= max {
+                invalid("min", "must be less than max")
+            } else if value < min || value > max {
+                invalid("value", format!("{} is outside {}..={}", value, min, max))
+            } else {
+                Ok(())
+            }
+        }
+        ElementType::Radio { options, value, .. } | ElementType::Selectbox { options, value, .. } => {
+            check_option(value.as_ref(), options)
+        }
+        ElementType::Multiselect { options, values, .. } => {
+            values.iter().try_for_each(|value| check_option(Some(value), options))
+        }
+        ElementType::Rating { max, value, .. } => match value {
+            _ if *max == 0 => invalid("max", "must be at least 1"),
+            Some(value) if value > max => invalid("value", format!("{} exceeds max {}", value, max)),
+            _ => Ok(()),
+        },
+        ElementType::Feedback { kind, value: Some(value), .. } if !kind.score_range().contains(value) => {
+            invalid("value", format!("{} is out of range for {} feedback", value, kind.as_str()))
+        }
+        ElementType::Table { headers, rows } => {
+            match rows.iter().position(|row| row.len() != headers.len()) {
+                Some(index) => invalid("rows", format!(
+                    "row {} has {} cells, expected {}",
+                    index,
+                    rows[index].len(),
+                    headers.len()
+                )),
+                None => Ok(()),
+            }
+        }
+        ElementType::DataEditor { headers, cells, values, .. } => {
+            match cells.iter().position(|row| row.len() != headers.len()) {
+                Some(index) => invalid("cells", format!(
+                    "row {} has {} cells, expected {}",
+                    index,
+                    cells[index].len(),
+                    headers.len()
+                )),
+                None if values.len() != cells.len() || values.iter().any(|row| row.len() != headers.len()) => {
+                    invalid("values", "must have one value per cell")
+                }
+                None => Ok(()),
+            }
+        }
+        ElementType::Dataframe { data } => check_json("data", data),
+        ElementType::PivotTable { data, .. } => check_json("data", data),
+        ElementType::ImageAnnotator { src, .. } if src.trim().is_empty() => invalid("src", "must not be empty"),
+        ElementType::ImageAnnotator { annotations, .. } => {
+            let empty = |annotation: &ImageAnnotation| {
+                let (x, y, width, height) = annotation.shape.bounds();
+                !([x, y, width, height].iter().all(|v| v.is_finite()) && width > 0.0 && height > 0.0)
+            };
+            match annotations.iter().position(empty) {
+                Some(index) => invalid("annotations", format!("shape {} must have a finite, positive size", index)),
+                None => Ok(()),
+            }
+        }
+        ElementType::Gauge { data } | ElementType::Donut { data } => check_json("data", data),
+        ElementType::LineChart { data, .. }
+        | ElementType::BarChart { data, .. }
+        | ElementType::AreaChart { data, .. }
+        | ElementType::ScatterChart { data, .. }
+        | ElementType::PieChart { data, .. }
+        | ElementType::CandlestickChart { data, .. }
+        | ElementType::Heatmap { data, .. } => check_json("data", data),
+        ElementType::PlotlyChart { spec }
+        | ElementType::VegaLiteChart { spec }
+        | ElementType::BokehChart { spec } => check_json("spec", spec),
+        ElementType::Image { src, .. }
+        | ElementType::Audio { src, .. }
+        | ElementType::AudioWaveform { src, .. }
+        | ElementType::Video { src }
+            if src.trim().is_empty() =>
+        {
+            invalid("src", "must not be empty")
+        }
+        ElementType::AudioWaveform { regions, .. } => {
+            match regions.iter().position(|(start, end)| !(*start >= 0.0 && start < end)) {
+                Some(index) => invalid("regions", format!("region {} must start at 0 or later and before it ends", index)),
+                None => Ok(()),
+            }
+        }
+        ElementType::DownloadButton { file_name, url, .. } => {
+            if file_name.trim().is_empty() {
+                invalid("file_name", "must not be empty")
+            } else if url.trim().is_empty() {
+                invalid("url", "must not be empty")
+            } else {
+                Ok(())
+            }
+        }
+        ElementType::Column { width: Some(width), .. } if width.is_nan() || *width <= 0.0 => {
+            invalid("width", "must be positive")
+        }
+        ElementType::Custom { name, .. } if name.trim().is_empty() => {
+            invalid("name", "must not be empty")
+        }
+        ElementType::Tabs { tabs } if tabs.is_empty() => invalid("tabs", "must not be empty"),
+        ElementType::Progress { value, .. } if !(0.0..=1.0).contains(value) => {
+            invalid("value", "must be between 0.0 and 1.0")
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_type_name() {
+        let chart = ChartKind::Line.element("[]".to_string(), None);
+        assert_eq!(type_name(&chart), "line_chart");
+        assert_eq!(type_name(&ElementType::Divider), "divider");
+    }
+
+    #[test]
+    fn test_validate_element_type() {
+        let table = ElementType::Table {
+            headers: vec!["a".to_string(), "b".to_string()],
+            rows: vec![vec!["1".to_string()]],
+        };
+        assert!(validate_element_type(&table).is_err());
+        assert!(validate_element_type(&ChartKind::Bar.element("{bad".to_string(), None)).is_err());
+        assert!(validate_element_type(&ElementType::Progress {
+            value: 0.5,
+            text: None,
+            indeterminate: false,
+            eta_secs: None,
+        })
+        .is_ok());
+    }
+
+    #[test]
+    fn test_typed_element_json() {
+        let element = TypedElement::checked(ElementId::new(3), ElementType::Audio { src: "a.mp3".to_string(), autoplay: false }).unwrap();
+        let json = element.to_json().unwrap();
+        assert_eq!(json["id"], 3);
+        assert_eq!(json["type"], "audio");
+        assert_eq!(json["src"], "a.mp3");
+    }
+}
@@ -1,6 +1,6 @@
 //! Additional widget elements - Slider, Checkbox, Selectbox, Multiselect, etc.
 
-use crate::element::ElementId;
+use crate::element::{ElementId, ElementType, RateLimit};
 use crate::traits::{Renderable, Validatable, Interactive, InteractionEvent};
 use crate::error::Result;
 use serde_json::Value;
@@ -23,7 +23,7 @@ impl SliderElement {
     /// Create a new slider element.
     pub fn new(id: ElementId, label: impl Into<String>, min: f64, max: f64) -> Result<Self> {
         if min >= max {
-            return Err(crate::error::Error::StateError("min must be less than max".to_string()));
+            return Err(crate::error::Error::validation("min", "must be less than max"));
         }
         Ok(Self {
             base: BaseElement::new(id, "slider"),
@@ -81,13 +81,30 @@ impl Renderable for SliderElement {
     fn as_any(&self) -> &dyn Any {
         self
     }
+
+    fn to_element_type(&self) -> Option<ElementType> {
+        Some(ElementType::Slider {
+            label: self.label.clone(),
+            value: self.value,
+            min: self.min,
+            max: self.max,
+            key: None,
+            rate_limit: RateLimit::NONE,
+            disabled: self.disabled,
+        })
+    }
+
+    fn as_validatable(&self) -> Option<&dyn Validatable> {
+        Some(self)
+    }
 }
 
 impl Validatable for SliderElement {
     fn validate(&self) -> Result<()> {
         if self.value < self.min || self.value > self.max {
-            return Err(crate::error::Error::StateError(
-                format!("Value {} is outside range [{}, {}]", self.value, self.min, self.max)
+            return Err(crate::error::Error::validation(
+                "value",
+                format!("{} is outside [{}, {}]", self.value, self.min, self.max),
             ));
         }
         Ok(())
@@ -96,14 +113,12 @@ impl Validatable for SliderElement {
 
 impl Interactive for SliderElement {
     fn handle_event(&mut self, event: &InteractionEvent) -> Result<()> {
-        if event.event_type == "change" {
-            if let Some(Value::Number(n)) = &event.data {
-                if let Some(f) = n.as_f64() {
-                    if f >= self.min && f <= self.max {
-                        self.value = f;
-                    }
-                }
-            }
+        if event.event_type == "change"
+            && let Some(Value::Number(n)) = &event.data
+            && let Some(f) = n.as_f64()
+            && f >= self.min && f <= self.max
+        {
+            self.value = f;
         }
         Ok(())
     }
@@ -115,14 +130,15 @@ impl Interactive for SliderElement {
     fn set_value(&mut self, value: Value) -> Result<()> {
         if let Some(n) = value.as_f64() {
             if n < self.min || n > self.max {
-                return Err(crate::error::Error::StateError(
-                    format!("Value {} is outside range [{}, {}]", n, self.min, self.max)
+                return Err(crate::error::Error::validation(
+                    "value",
+                    format!("{} is outside [{}, {}]", n, self.min, self.max),
                 ));
             }
             self.value = n;
             Ok(())
         } else {
-            Err(crate::error::Error::InvalidWidgetValue("Invalid slider value".to_string()))
+            Err(crate::error::Error::validation("value", "must be a number"))
         }
     }
 
@@ -192,12 +208,25 @@ impl Renderable for CheckboxElement {
     fn as_any(&self) -> &dyn Any {
         self
     }
+
+    fn to_element_type(&self) -> Option<ElementType> {
+        Some(ElementType::Checkbox {
+            label: self.label.clone(),
+            value: self.checked,
+            key: None,
+            disabled: self.disabled,
+        })
+    }
+
+    fn as_validatable(&self) -> Option<&dyn Validatable> {
+        Some(self)
+    }
 }
 
 impl Validatable for CheckboxElement {
     fn validate(&self) -> Result<()> {
         if self.label.is_empty() {
-            return Err(crate::error::Error::StateError("Checkbox label cannot be empty".to_string()));
+            return Err(crate::error::Error::validation("label", "must not be empty"));
         }
         Ok(())
     }
@@ -205,10 +234,10 @@ impl Validatable for CheckboxElement {
 
 impl Interactive for CheckboxElement {
     fn handle_event(&mut self, event: &InteractionEvent) -> Result<()> {
-        if event.event_type == "change" {
-            if let Some(Value::Bool(checked)) = &event.data {
-                self.checked = *checked;
-            }
+        if event.event_type == "change"
+            && let Some(Value::Bool(checked)) = &event.data
+        {
+            self.checked = *checked;
         }
         Ok(())
     }
@@ -222,7 +251,7 @@ impl Interactive for CheckboxElement {
             self.checked = b;
             Ok(())
         } else {
-            Err(crate::error::Error::InvalidWidgetValue("Invalid checkbox value".to_string()))
+            Err(crate::error::Error::validation("value", "must be a boolean"))
         }
     }
 
@@ -249,7 +278,7 @@ impl SelectboxElement {
     /// Create a new selectbox element.
     pub fn new(id: ElementId, label: impl Into<String>, options: Vec<String>) -> Result<Self> {
         if options.is_empty() {
-            return Err(crate::error::Error::StateError("Selectbox must have at least one option".to_string()));
+            return Err(crate::error::Error::validation("options", "must not be empty"));
         }
         Ok(Self {
             base: BaseElement::new(id, "selectbox"),
@@ -283,7 +312,7 @@ impl SelectboxElement {
     /// Set the selected index.
     pub fn set_selected_index(&mut self, index: usize) -> Result<()> {
         if index >= self.options.len() {
-            return Err(crate::error::Error::StateError("Index out of bounds".to_string()));
+            return Err(crate::error::Error::validation("index", "must be less than the number of options"));
         }
         self.selected_index = index;
         Ok(())
@@ -312,15 +341,29 @@ impl Renderable for SelectboxElement {
     fn as_any(&self) -> &dyn Any {
         self
     }
+
+    fn to_element_type(&self) -> Option<ElementType> {
+        Some(ElementType::Selectbox {
+            label: self.label.clone(),
+            options: self.options.clone(),
+            value: self.options.get(self.selected_index).cloned(),
+            key: None,
+            disabled: self.disabled,
+        })
+    }
+
+    fn as_validatable(&self) -> Option<&dyn Validatable> {
+        Some(self)
+    }
 }
 
 impl Validatable for SelectboxElement {
     fn validate(&self) -> Result<()> {
         if self.label.is_empty() {
-            return Err(crate::error::Error::StateError("Selectbox label cannot be empty".to_string()));
+            return Err(crate::error::Error::validation("label", "must not be empty"));
         }
         if self.options.is_empty() {
-            return Err(crate::error::Error::StateError("Selectbox must have at least one option".to_string()));
+            return Err(crate::error::Error::validation("options", "must not be empty"));
         }
         Ok(())
     }
@@ -328,12 +371,11 @@ impl Validatable for SelectboxElement {
 
 impl Interactive for SelectboxElement {
     fn handle_event(&mut self, event: &InteractionEvent) -> Result<()> {
-        if event.event_type == "change" {
-            if let Some(Value::Number(n)) = &event.data {
-                if let Some(index) = n.as_u64() {
-                    self.set_selected_index(index as usize)?;
-                }
-            }
+        if event.event_type == "change"
+            && let Some(Value::Number(n)) = &event.data
+            && let Some(index) = n.as_u64()
+        {
+            self.set_selected_index(index as usize)?;
         }
         Ok(())
     }
@@ -348,10 +390,10 @@ impl Interactive for SelectboxElement {
                 self.selected_index = index;
                 Ok(())
             } else {
-                Err(crate::error::Error::StateError("Value not found in options".to_string()))
+                Err(crate::error::Error::validation("value", "must be one of the options"))
             }
         } else {
-            Err(crate::error::Error::InvalidWidgetValue("Invalid selectbox value".to_string()))
+            Err(crate::error::Error::validation("value", "must be a string"))
         }
     }
 
@@ -14,17 +14,27 @@ pub enum Error {
     #[error("Widget not found: {0}")]
     WidgetNotFound(String),
 
-    #[error("Invalid widget value: {0}")]
-    InvalidWidgetValue(String),
+    /// A value broke a rule, e.g. an empty label or an out of range value.
+    #[error("Invalid {field}: {rule}")]
+    Validation { field: String, rule: String },
+
+    /// Two widgets of a run were given the same key.
+    #[error("Duplicate widget key `{key}`")]
+    WidgetKeyCollision { key: String },
+
+    /// A client sent a message the server cannot use.
+    #[error("Protocol error: {0}")]
+    ProtocolError(String),
 
     #[error("Script execution error: {0}")]
     ScriptExecutionError(String),
 
-    #[error("Serialization error: {0}")]
-    SerializationError(#[from] serde_json::Error),
+    /// A client sent more than the server accepts.
+    #[error("{what} of {size} bytes exceeds the limit of {limit} bytes")]
+    PayloadTooLarge { what: String, size: usize, limit: usize },
 
-    #[error("State error: {0}")]
-    StateError(String),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
 
     #[error("Internal error: {0}")]
     Internal(String),
@@ -37,13 +47,102 @@ pub enum Error {
 }
 
 impl Error {
-    /// Create a new state error.
-    pub fn state(msg: impl Into<String>) -> Self {
-        Error::StateError(msg.into())
+    /// Create a new validation error: `field` breaks `rule`.
+    pub fn validation(field: impl Into<String>, rule: impl Into<String>) -> Self {
+        Error::Validation {
+            field: field.into(),
+            rule: rule.into(),
+        }
+    }
+
+    /// Create a new protocol error.
+    pub fn protocol(msg: impl Into<String>) -> Self {
+        Error::ProtocolError(msg.into())
     }
 
     /// Create a new internal error.
     pub fn internal(msg: impl Into<String>) -> Self {
         Error::Internal(msg.into())
     }
+
+    /// Get the error's stable code.
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Error::SessionNotFound(_) => ErrorCode::SessionNotFound,
+            Error::WidgetNotFound(_) => ErrorCode::WidgetNotFound,
+            Error::Validation { .. } => ErrorCode::Validation,
+            Error::WidgetKeyCollision { .. } => ErrorCode::WidgetKeyCollision,
+            Error::ProtocolError(_) => ErrorCode::Protocol,
+            Error::ScriptExecutionError(_) => ErrorCode::ScriptExecution,
+            Error::PayloadTooLarge { .. } => ErrorCode::PayloadTooLarge,
+            Error::Serialization(_) => ErrorCode::Serialization,
+            Error::Io(_) => ErrorCode::Io,
+            Error::Internal(_) | Error::Other(_) => ErrorCode::Internal,
+        }
+    }
+}
+
+/// Category of an error. Codes are stable across releases, unlike error
+/// messages, so apps and clients can match on them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    SessionNotFound,
+    WidgetNotFound,
+    Validation,
+    WidgetKeyCollision,
+    Protocol,
+    ScriptExecution,
+    PayloadTooLarge,
+    Serialization,
+    Io,
+    /// A session could not be stored, restored or imported.
+    Session,
+    NotFound,
+    BadRequest,
+    Unauthorized,
+    Forbidden,
+    Internal,
+}
+
+impl ErrorCode {
+    /// Get the code as sent to clients.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::SessionNotFound => "session_not_found",
+            ErrorCode::WidgetNotFound => "widget_not_found",
+            ErrorCode::Validation => "validation",
+            ErrorCode::WidgetKeyCollision => "widget_key_collision",
+            ErrorCode::Protocol => "protocol",
+            ErrorCode::ScriptExecution => "script_execution",
+            ErrorCode::PayloadTooLarge => "payload_too_large",
+            ErrorCode::Serialization => "serialization",
+            ErrorCode::Io => "io",
+            ErrorCode::Session => "session",
+            ErrorCode::NotFound => "not_found",
+            ErrorCode::BadRequest => "bad_request",
+            ErrorCode::Unauthorized => "unauthorized",
+            ErrorCode::Forbidden => "forbidden",
+            ErrorCode::Internal => "internal",
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validation_error() {
+        let error = Error::validation("label", "must not be empty");
+        assert_eq!(error.to_string(), "Invalid label: must not be empty");
+        assert_eq!(error.code(), ErrorCode::Validation);
+        assert_eq!(error.code().as_str(), "validation");
+        assert!(matches!(error, Error::Validation { field, .. } if field == "label"));
+    }
 }
@@ -23,27 +23,49 @@
 //! - `elements::layout`: Containers, rows, columns, etc.
 //! - `elements::feedback`: Success, error, warning messages
 //! - `elements::advanced`: Metrics, charts, etc.
+//! - `elements::typed`: Any `ElementType` as a trait object
+//!
+//! ## Elements and element types
+//!
+//! `ElementType` is what the runtime sends to clients; element structs are
+//! the trait-based view. They meet in two places:
+//! - `ElementFactory::from_element_type` turns an `ElementType` into a
+//!   `Box<dyn Renderable>`
+//! - `Renderable::to_element_type` goes the other way, and
+//!   `DeltaGenerator::add_renderable` uses it to send trait-based elements,
+//!   validating `Validatable` ones and forwarding ARIA attributes from
+//!   `Accessible` ones
+//!
+//! Widgets in the runtime still build `ElementType`s directly. New
+//! capabilities should be added to the element structs and surfaced through
+//! `add_renderable`, so that widgets can move over one at a time.
 
+pub mod clock;
 pub mod element;
 pub mod error;
+pub mod ids;
 pub mod session;
 pub mod state;
 pub mod widget;
 pub mod traits;
 pub mod traits_impl;
 pub mod elements;
+#[cfg(feature = "arbitrary")]
+pub mod arbitrary;
 
-pub use element::{Element, ElementType, ElementId};
-pub use error::{Error, Result};
+pub use element::{AccessibilityInfo, Element, ElementType, ElementId, FeedbackKind, RateLimit, SkeletonKind, TextInputKind};
+pub use clock::{Clock, ManualClock, SystemClock};
+pub use error::{Error, ErrorCode, Result};
+pub use ids::{IdGenerator, RandomIds, SequentialIds};
 pub use session::{Session, SessionId};
-pub use state::{AppState, DeltaGenerator};
+pub use state::{AppState, DeltaFlush, DeltaGenerator};
 pub use widget::{Widget, WidgetValue};
 pub use traits::{Renderable, Validatable, Interactive, Container, Observable, DataBindable};
 
 /// Prelude module for convenient imports.
 pub mod prelude {
     pub use crate::{
-        element::{Element, ElementType, ElementId},
+        element::{AccessibilityInfo, Element, ElementType, ElementId, FeedbackKind, RateLimit, TextInputKind},
         error::{Error, Result},
         session::{Session, SessionId},
         state::{AppState, DeltaGenerator},
@@ -59,6 +81,7 @@ pub mod prelude {
             layout::ContainerElement,
             feedback::{FeedbackElement, FeedbackType},
             advanced::MetricElement,
+            typed::{ChartKind, TypedElement},
         },
     };
 }
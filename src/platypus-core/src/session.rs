@@ -52,6 +52,20 @@ pub struct Session {
     /// Session metadata.
     pub metadata: HashMap<String, String>,
 
+    /// Widget values, as last stored by the script executor.
+    #[serde(default)]
+    pub widget_state: HashMap<String, String>,
+
+    /// Schema version of the widget and session state; 0 for state saved
+    /// before the app declared one.
+    #[serde(default)]
+    pub state_version: u32,
+
+    /// Secret a client presents with the session ID to resume the session
+    /// on a new connection; empty for sessions that cannot be resumed.
+    #[serde(default)]
+    pub resume_token: String,
+
     /// Creation timestamp (Unix seconds).
     pub created_at: u64,
 
@@ -66,12 +80,20 @@ impl Session {
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap_or_default()
             .as_secs();
+        Self::new_at(script_hash, SessionId::new(), now)
+    }
 
+    /// Create a new session with the given ID, created at `now` (Unix
+    /// seconds).
+    pub fn new_at(script_hash: String, id: SessionId, now: u64) -> Self {
         Session {
-            id: SessionId::new(),
+            id,
             script_hash,
             reruns: 0,
             metadata: HashMap::new(),
+            widget_state: HashMap::new(),
+            state_version: 0,
+            resume_token: Uuid::new_v4().simple().to_string(),
             created_at: now,
             last_activity: now,
         }
@@ -1,6 +1,10 @@
 //! Application state and delta generation.
 
-use crate::element::{Element, ElementId, ElementType, SimpleElement};
+use crate::clock::{self, Clock};
+use crate::element::{AccessibilityInfo, Element, ElementId, ElementType, SimpleElement};
+use crate::error::{Error, Result};
+use crate::ids::{self, IdGenerator};
+use crate::traits::Renderable;
 use crate::widget::{SimpleWidget, Widget, WidgetValue};
 use dashmap::DashMap;
 use parking_lot::RwLock;
@@ -16,12 +20,30 @@ pub enum Delta {
         element: ElementType,
         parent_id: Option<ElementId>,
     },
+    /// Carry an unchanged element over from the client's previous run,
+    /// moving it to this delta's position instead of adding it again.
+    MoveElement {
+        id: ElementId,
+        /// The element's ID in the previous run.
+        previous_id: ElementId,
+        parent_id: Option<ElementId>,
+    },
     /// Update an existing element.
     UpdateElement { id: ElementId, element: ElementType },
     /// Remove an element.
     RemoveElement { id: ElementId },
     /// Clear all children of a container.
     ClearContainer { id: ElementId },
+    /// Set ARIA attributes on an element.
+    SetAccessibility { id: ElementId, accessibility: AccessibilityInfo },
+    /// Append points to a streamed chart.
+    AppendChartData {
+        id: ElementId,
+        /// Stream key, identifying the series across reruns.
+        key: String,
+        /// JSON-encoded points.
+        data: String,
+    },
 }
 
 /// Generates UI deltas incrementally.
@@ -30,6 +52,9 @@ pub struct DeltaGenerator {
     widgets: Arc<DashMap<String, Box<dyn Widget>>>,
     deltas: Arc<RwLock<Vec<Delta>>>,
     next_element_id: Arc<RwLock<u64>>,
+    namespace: u32,
+    clock: Arc<dyn Clock>,
+    ids: Arc<dyn IdGenerator>,
 }
 
 impl DeltaGenerator {
@@ -40,15 +65,47 @@ impl DeltaGenerator {
             widgets: Arc::new(DashMap::new()),
             deltas: Arc::new(RwLock::new(Vec::new())),
             next_element_id: Arc::new(RwLock::new(1)),
+            namespace: 0,
+            clock: clock::system_clock(),
+            ids: ids::random_ids(),
         }
     }
 
+    /// Give element IDs the namespace `namespace`, e.g. the number of the
+    /// run generating them.
+    pub fn with_namespace(mut self, namespace: u32) -> Self {
+        self.namespace = namespace;
+        self
+    }
+
+    /// Read the time from `clock` instead of the system.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Generate IDs with `ids` instead of randomly.
+    pub fn with_ids(mut self, ids: Arc<dyn IdGenerator>) -> Self {
+        self.ids = ids;
+        self
+    }
+
+    /// Get the clock the app reads the time from.
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+
+    /// Get the generator of the app's IDs.
+    pub fn ids(&self) -> &Arc<dyn IdGenerator> {
+        &self.ids
+    }
+
     /// Get next element ID.
     pub fn next_element_id(&self) -> ElementId {
         let mut id = self.next_element_id.write();
         let current = *id;
         *id += 1;
-        ElementId::new(current)
+        ElementId::namespaced(self.namespace, current)
     }
 
     /// Add an element.
@@ -70,8 +127,56 @@ impl DeltaGenerator {
         id
     }
 
+    /// Add a trait-based element.
+    ///
+    /// The element is validated if it is `Validatable` and converted with
+    /// `to_element_type`; `Accessible` elements also send their ARIA
+    /// attributes. The generator assigns the ID, ignoring the element's own.
+    pub fn add_renderable(
+        &self,
+        element: &dyn Renderable,
+        parent_id: Option<ElementId>,
+    ) -> Result<ElementId> {
+        if let Some(validatable) = element.as_validatable() {
+            validatable.validate()?;
+        }
+        let element_type = element.to_element_type().ok_or_else(|| {
+            Error::internal(format!("{} cannot be sent to clients", element.name()))
+        })?;
+
+        let id = self.add_element(element_type, parent_id);
+        if let Some(accessible) = element.as_accessible() {
+            let accessibility = AccessibilityInfo::of(accessible);
+            if !accessibility.is_empty() {
+                self.deltas.write().push(Delta::SetAccessibility { id, accessibility });
+            }
+        }
+        Ok(id)
+    }
+
+    /// Get the number of deltas generated and not yet taken.
+    pub fn delta_count(&self) -> usize {
+        self.deltas.read().len()
+    }
+
+    /// Drop the deltas generated after the first `len`, and the elements
+    /// they added.
+    pub fn truncate_deltas(&self, len: usize) {
+        let mut deltas = self.deltas.write();
+        let len = len.min(deltas.len());
+        for delta in deltas.drain(len..) {
+            if let Delta::AddElement { id, .. } = delta {
+                self.elements.remove(&id);
+            }
+        }
+    }
+
     /// Update an element.
     pub fn update_element(&self, id: ElementId, element_type: ElementType) {
+        if self.elements.contains_key(&id) {
+            let element = Box::new(SimpleElement::new(id, element_type.clone()));
+            self.elements.insert(id, element);
+        }
         self.deltas.write().push(Delta::UpdateElement {
             id,
             element: element_type,
@@ -89,9 +194,44 @@ impl DeltaGenerator {
         self.deltas.write().push(Delta::ClearContainer { id });
     }
 
-    /// Get all deltas and clear the list.
+    /// Append JSON-encoded points to the streamed chart `id`.
+    pub fn append_chart_data(&self, id: ElementId, key: String, data: String) {
+        self.deltas.write().push(Delta::AppendChartData { id, key, data });
+    }
+
+    /// Get all deltas and clear the list, as `drain_deltas` does, through
+    /// a shared reference.
     pub fn take_deltas(&self) -> Vec<Delta> {
-        std::mem::take(&mut *self.deltas.write())
+        in_tree_order(std::mem::take(&mut *self.deltas.write()))
+    }
+
+    /// Remove and return the deltas generated so far.
+    ///
+    /// Deltas come out in tree order: each container's deltas follow its
+    /// own `AddElement`, and containers follow their siblings in the order
+    /// they were declared, whatever order they were written in. Writing
+    /// into the second column before the first still lays out the first
+    /// column's elements first.
+    ///
+    /// Clones of the generator share its deltas, so this drains theirs
+    /// too.
+    pub fn drain_deltas(&mut self) -> Vec<Delta> {
+        in_tree_order(std::mem::take(&mut *self.deltas.write()))
+    }
+
+    /// Get the deltas `drain_deltas` would return, without removing them.
+    pub fn peek_deltas(&self) -> Vec<Delta> {
+        in_tree_order(self.deltas.read().clone())
+    }
+
+    /// Pass the deltas generated so far to `flush` when the returned guard
+    /// goes out of scope. The guard derefs to the generator, so elements
+    /// are added through it.
+    pub fn flush_on_drop<F: FnOnce(Vec<Delta>)>(&mut self, flush: F) -> DeltaFlush<'_, F> {
+        DeltaFlush {
+            delta_gen: self,
+            flush: Some(flush),
+        }
     }
 
     /// Add or update a widget.
@@ -143,6 +283,79 @@ impl DeltaGenerator {
     }
 }
 
+/// Buffer `deltas` per container and merge the buffers depth first.
+///
+/// A delta goes in the buffer of the container it writes into: `AddElement`
+/// its parent's, `ClearContainer` the container's own, and other deltas
+/// their element's parent's. Containers added by an earlier batch have no
+/// `AddElement` to follow, so their deltas stay in the top-level buffer.
+fn in_tree_order(deltas: Vec<Delta>) -> Vec<Delta> {
+    let mut parents: HashMap<ElementId, Option<ElementId>> = HashMap::new();
+    let mut buffers: HashMap<Option<ElementId>, Vec<Delta>> = HashMap::new();
+    for delta in deltas {
+        let buffer = match &delta {
+            Delta::AddElement { id, parent_id, .. } | Delta::MoveElement { id, parent_id, .. } => {
+                let buffer = parent_id.filter(|parent| parents.contains_key(parent));
+                parents.insert(*id, buffer);
+                buffer
+            }
+            Delta::ClearContainer { id } => Some(*id).filter(|id| parents.contains_key(id)),
+            Delta::UpdateElement { id, .. }
+            | Delta::RemoveElement { id }
+            | Delta::SetAccessibility { id, .. }
+            | Delta::AppendChartData { id, .. } => parents.get(id).copied().flatten(),
+        };
+        buffers.entry(buffer).or_default().push(delta);
+    }
+
+    let mut ordered = Vec::new();
+    let mut stack = vec![buffers.remove(&None).unwrap_or_default().into_iter()];
+    while let Some(buffer) = stack.last_mut() {
+        let Some(delta) = buffer.next() else {
+            stack.pop();
+            continue;
+        };
+        let children = match &delta {
+            Delta::AddElement { id, .. } => buffers.remove(&Some(*id)),
+            _ => None,
+        };
+        ordered.push(delta);
+        if let Some(children) = children {
+            stack.push(children.into_iter());
+        }
+    }
+    ordered
+}
+
+/// Guard passing a generator's deltas to a flush function when dropped;
+/// see [`DeltaGenerator::flush_on_drop`].
+pub struct DeltaFlush<'a, F: FnOnce(Vec<Delta>)> {
+    delta_gen: &'a mut DeltaGenerator,
+    flush: Option<F>,
+}
+
+impl<F: FnOnce(Vec<Delta>)> std::ops::Deref for DeltaFlush<'_, F> {
+    type Target = DeltaGenerator;
+
+    fn deref(&self) -> &DeltaGenerator {
+        self.delta_gen
+    }
+}
+
+impl<F: FnOnce(Vec<Delta>)> std::ops::DerefMut for DeltaFlush<'_, F> {
+    fn deref_mut(&mut self) -> &mut DeltaGenerator {
+        self.delta_gen
+    }
+}
+
+impl<F: FnOnce(Vec<Delta>)> Drop for DeltaFlush<'_, F> {
+    fn drop(&mut self) {
+        if let Some(flush) = self.flush.take() {
+            flush(self.delta_gen.drain_deltas());
+        }
+    }
+}
+
 impl Default for DeltaGenerator {
     fn default() -> Self {
         Self::new()
@@ -156,6 +369,9 @@ impl Clone for DeltaGenerator {
             widgets: Arc::clone(&self.widgets),
             deltas: Arc::clone(&self.deltas),
             next_element_id: Arc::clone(&self.next_element_id),
+            namespace: self.namespace,
+            clock: Arc::clone(&self.clock),
+            ids: Arc::clone(&self.ids),
         }
     }
 }
@@ -204,6 +420,34 @@ mod tests {
         let id1 = r#gen.next_element_id();
         let id2 = r#gen.next_element_id();
         assert_ne!(id1, id2);
+
+        // The next run's first element is not the last run's
+        let next_run = DeltaGenerator::new().with_namespace(2);
+        assert_ne!(next_run.next_element_id(), id1);
+    }
+
+    #[test]
+    fn test_add_renderable() {
+        use crate::elements::{ChartKind, ElementBuilder, HeadingElement, TextElement};
+
+        let r#gen = DeltaGenerator::new();
+        let mut text = TextElement::new(ElementId::new(99), "Hello");
+        text.set_aria_label("Greeting");
+        let id = r#gen.add_renderable(&text, None).unwrap();
+
+        let deltas = r#gen.take_deltas();
+        assert!(matches!(&deltas[0], Delta::AddElement { element: ElementType::Text { value }, .. } if value == "Hello"));
+        assert!(matches!(
+            &deltas[1],
+            Delta::SetAccessibility { id: a11y_id, accessibility } if *a11y_id == id && accessibility.label.as_deref() == Some("Greeting")
+        ));
+
+        // Validation failures are not sent
+        let empty = HeadingElement::new(ElementId::new(1), "", 2).unwrap();
+        assert!(r#gen.add_renderable(&empty, None).is_err());
+        let chart = ElementBuilder::new(ElementId::new(1)).chart(ChartKind::Bar, "[]", None).unwrap();
+        assert!(r#gen.add_renderable(&chart, None).is_ok());
+        assert_eq!(r#gen.take_deltas().len(), 1);
     }
 
     #[test]
@@ -227,4 +471,52 @@ mod tests {
         r#gen.set_widget("test".to_string(), WidgetValue::String("value".to_string()));
         assert_eq!(r#gen.get_widget("test"), Some(WidgetValue::String("value".to_string())));
     }
+
+    #[test]
+    fn test_take_deltas_in_declaration_order() {
+        let r#gen = DeltaGenerator::new();
+        let text = |value: &str| ElementType::Text { value: value.to_string() };
+        let column = || ElementType::Column {
+            width: None,
+            children: Vec::new(),
+            hidden: false,
+        };
+        let col1 = r#gen.add_element(column(), None);
+        let col2 = r#gen.add_element(column(), None);
+        r#gen.add_element(text("b1"), Some(col2));
+        r#gen.add_element(text("a1"), Some(col1));
+        r#gen.clear_container(col2);
+        r#gen.add_element(text("b2"), Some(col2));
+        r#gen.add_element(text("after"), None);
+
+        let order: Vec<String> = r#gen
+            .take_deltas()
+            .iter()
+            .map(|delta| match delta {
+                Delta::AddElement { element: ElementType::Text { value }, .. } => value.clone(),
+                Delta::AddElement { id, .. } => format!("col{}", id.inner()),
+                Delta::ClearContainer { .. } => "clear".to_string(),
+                _ => "other".to_string(),
+            })
+            .collect();
+        assert_eq!(order, vec!["col1", "a1", "col2", "b1", "clear", "b2", "after"]);
+
+        // Containers from an earlier batch keep their deltas in write order
+        r#gen.add_element(text("c1"), Some(col2));
+        r#gen.add_element(text("c2"), Some(col1));
+        assert_eq!(r#gen.take_deltas().len(), 2);
+    }
+
+    #[test]
+    fn test_flush_on_drop() {
+        let mut r#gen = DeltaGenerator::new();
+        let mut flushed = Vec::new();
+        {
+            let batch = r#gen.flush_on_drop(|deltas| flushed = deltas);
+            batch.add_element(ElementType::Text { value: "Hello".to_string() }, None);
+            assert_eq!(batch.peek_deltas().len(), 1);
+        }
+        assert_eq!(flushed.len(), 1);
+        assert!(r#gen.drain_deltas().is_empty());
+    }
 }
@@ -6,8 +6,9 @@
 //! - Modular composition (traits can be combined)
 //! - Extensibility (new capabilities can be added without modifying existing code)
 
-use crate::element::ElementId;
+use crate::element::{ElementId, ElementType};
 use crate::error::Result;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::any::Any;
 
@@ -27,6 +28,23 @@ pub trait Renderable: Send + Sync {
 
     /// Get a reference to the element as Any for downcasting.
     fn as_any(&self) -> &dyn Any;
+
+    /// Convert to the element type sent to clients.
+    ///
+    /// Elements returning `None` cannot be added to a `DeltaGenerator`.
+    fn to_element_type(&self) -> Option<ElementType> {
+        None
+    }
+
+    /// Get the element as `Validatable`, if it implements it.
+    fn as_validatable(&self) -> Option<&dyn Validatable> {
+        None
+    }
+
+    /// Get the element as `Accessible`, if it implements it.
+    fn as_accessible(&self) -> Option<&dyn Accessible> {
+        None
+    }
 }
 
 /// Trait for elements that can be validated.
@@ -216,15 +234,49 @@ pub trait Responsive: Renderable {
 }
 
 /// Represents a responsive breakpoint.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Breakpoint {
     pub name: String,
     pub min_width: u32,
     pub max_width: Option<u32>,
 }
 
+impl Breakpoint {
+    /// Default mobile/tablet/desktop breakpoints.
+    pub fn defaults() -> Vec<Breakpoint> {
+        vec![
+            Breakpoint {
+                name: "mobile".to_string(),
+                min_width: 0,
+                max_width: Some(640),
+            },
+            Breakpoint {
+                name: "tablet".to_string(),
+                min_width: 641,
+                max_width: Some(1024),
+            },
+            Breakpoint {
+                name: "desktop".to_string(),
+                min_width: 1025,
+                max_width: None,
+            },
+        ]
+    }
+
+    /// Check if a viewport width falls within this breakpoint.
+    pub fn contains(&self, width: u32) -> bool {
+        width >= self.min_width && self.max_width.is_none_or(|max| width <= max)
+    }
+
+    /// Find the breakpoint matching a viewport width.
+    pub fn for_width(breakpoints: &[Breakpoint], width: u32) -> Option<&Breakpoint> {
+        breakpoints.iter().find(|b| b.contains(width))
+    }
+}
+
 /// Represents a layout configuration.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Layout {
     pub display: String,
     pub flex_direction: Option<String>,
@@ -349,6 +401,14 @@ mod tests {
         assert!(element.to_json().is_ok());
     }
 
+    #[test]
+    fn test_breakpoint_for_width() {
+        let breakpoints = Breakpoint::defaults();
+        assert_eq!(Breakpoint::for_width(&breakpoints, 320).unwrap().name, "mobile");
+        assert_eq!(Breakpoint::for_width(&breakpoints, 1024).unwrap().name, "tablet");
+        assert_eq!(Breakpoint::for_width(&breakpoints, 1920).unwrap().name, "desktop");
+    }
+
     #[test]
     fn test_interaction_event() {
         let event = InteractionEvent {
@@ -4,7 +4,7 @@
 //! like Observable and DataBindable.
 
 use crate::element::ElementId;
-use crate::traits::{Observable, Observer, ElementChange, DataBindable};
+use crate::traits::{Observer, ElementChange};
 use crate::error::Result;
 use serde_json::Value;
 use std::sync::Arc;
@@ -152,6 +152,11 @@ impl DataBindableTextInput {
         }
     }
 
+    /// Get the element ID.
+    pub fn id(&self) -> ElementId {
+        self.id
+    }
+
     /// Get the label.
     pub fn label(&self) -> &str {
         &self.label
@@ -179,7 +184,7 @@ impl DataBindableTextInput {
             self.binding.update_data(data.clone());
             Ok(())
         } else {
-            Err(crate::error::Error::StateError("Invalid data for text input".to_string()))
+            Err(crate::error::Error::validation("value", "must be a string"))
         }
     }
 
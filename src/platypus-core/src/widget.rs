@@ -1,8 +1,11 @@
 //! Widget types and state management.
 
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::element::ImageAnnotation;
+
 /// Type-safe widget value.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(untagged)]
@@ -17,8 +20,16 @@ pub enum WidgetValue {
     StringArray(Vec<String>),
     /// Array of numbers
     NumberArray(Vec<f64>),
+    /// Labeled shapes (image annotator)
+    Annotations(Vec<ImageAnnotation>),
     /// Generic JSON value
     Json(Value),
+    /// Inclusive date range (date range input)
+    DateRange(NaiveDate, NaiveDate),
+    /// Point in time (datetime input)
+    DateTime(DateTime<Utc>),
+    /// Editor HTML (rich text editor), sanitized before use
+    RichText { html: String },
 }
 
 impl WidgetValue {
@@ -61,6 +72,38 @@ impl WidgetValue {
             _ => None,
         }
     }
+
+    /// Try to get as image annotations.
+    pub fn as_annotations(&self) -> Option<&[ImageAnnotation]> {
+        match self {
+            WidgetValue::Annotations(annotations) => Some(annotations),
+            _ => None,
+        }
+    }
+
+    /// Try to get as date range.
+    pub fn as_date_range(&self) -> Option<(NaiveDate, NaiveDate)> {
+        match self {
+            WidgetValue::DateRange(start, end) => Some((*start, *end)),
+            _ => None,
+        }
+    }
+
+    /// Try to get as datetime.
+    pub fn as_datetime(&self) -> Option<DateTime<Utc>> {
+        match self {
+            WidgetValue::DateTime(dt) => Some(*dt),
+            _ => None,
+        }
+    }
+
+    /// Try to get as rich text HTML.
+    pub fn as_rich_text(&self) -> Option<&str> {
+        match self {
+            WidgetValue::RichText { html } => Some(html),
+            _ => None,
+        }
+    }
 }
 
 impl From<String> for WidgetValue {
@@ -99,6 +142,18 @@ impl From<Vec<f64>> for WidgetValue {
     }
 }
 
+impl From<(NaiveDate, NaiveDate)> for WidgetValue {
+    fn from((start, end): (NaiveDate, NaiveDate)) -> Self {
+        WidgetValue::DateRange(start, end)
+    }
+}
+
+impl From<DateTime<Utc>> for WidgetValue {
+    fn from(dt: DateTime<Utc>) -> Self {
+        WidgetValue::DateTime(dt)
+    }
+}
+
 /// Trait for widgets with state.
 pub trait Widget: Send + Sync {
     /// Get the widget key (unique identifier).
@@ -179,6 +234,12 @@ mod tests {
 
         let b: WidgetValue = true.into();
         assert_eq!(b.as_bool(), Some(true));
+
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let end = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+        let range: WidgetValue = (start, end).into();
+        assert_eq!(range.as_date_range(), Some((start, end)));
+        assert_eq!(range.as_string(), None);
     }
 
     #[test]
@@ -0,0 +1,242 @@
+//! platypus Macros - Derive macros for struct-backed widgets.
+//!
+//! The derives are re-exported from `platypus_runtime` next to the traits
+//! they implement; depend on the runtime rather than this crate directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr, Path};
+
+/// Derive `platypus_runtime::form::Form` for a struct with named fields.
+///
+/// Each field renders the widget for its type, starting from the struct's
+/// `Default` value. Field attributes:
+/// - `#[form(label = "...")]` overrides the label derived from the field name
+/// - `#[form(validate = "path::to::fn")]` runs `fn(&T) -> Result<(), String>`
+/// - `#[form(skip)]` keeps the default without rendering a widget
+#[proc_macro_derive(Form, attributes(form))]
+pub fn derive_form(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_form(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[derive(Default)]
+struct FieldOptions {
+    label: Option<String>,
+    validate: Option<Path>,
+    skip: bool,
+}
+
+fn field_options(field: &syn::Field) -> syn::Result<FieldOptions> {
+    let mut options = FieldOptions::default();
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("form")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("label") {
+                options.label = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("validate") {
+                options.validate = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+            } else if meta.path.is_ident("skip") {
+                options.skip = true;
+            } else {
+                return Err(meta.error("expected `label`, `validate` or `skip`"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(options)
+}
+
+/// Turn `max_connections` into "Max connections".
+fn default_label(name: &str) -> String {
+    let words = name.trim_start_matches("r#").replace('_', " ");
+    let mut chars = words.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn expand_form(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(input, "Form can only be derived for structs"));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(input, "Form requires named fields"));
+    };
+
+    let mut renders = vec![];
+    let mut names = vec![];
+    for field in &fields.named {
+        let name = field.ident.as_ref().expect("named field");
+        let ty = &field.ty;
+        let options = field_options(field)?;
+        names.push(name);
+
+        if options.skip {
+            renders.push(quote! { let #name = defaults.#name; });
+            continue;
+        }
+
+        let field_name = name.to_string();
+        let label = options.label.unwrap_or_else(|| default_label(&field_name));
+        renders.push(quote! {
+            let #name = <#ty as ::platypus_runtime::form::FormField>::render_field(
+                st,
+                #label,
+                defaults.#name,
+                ::std::format!("{}.{}", key, #field_name),
+            );
+        });
+        if let Some(validate) = options.validate {
+            renders.push(quote! {
+                if let ::std::result::Result::Err(message) = #validate(&#name) {
+                    errors.push(::platypus_runtime::form::FieldError {
+                        field: #field_name.to_string(),
+                        label: #label.to_string(),
+                        message,
+                    });
+                }
+            });
+        }
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::platypus_runtime::form::Form for #ident #ty_generics #where_clause {
+            fn render_form(
+                st: &mut ::platypus_runtime::St,
+                key: &str,
+            ) -> ::std::result::Result<Self, ::platypus_runtime::form::FormErrors> {
+                let defaults: Self = ::std::default::Default::default();
+                #[allow(unused_mut)]
+                let mut errors = ::std::vec::Vec::new();
+                #(#renders)*
+                if errors.is_empty() {
+                    ::std::result::Result::Ok(Self { #(#names),* })
+                } else {
+                    ::std::result::Result::Err(::platypus_runtime::form::FormErrors(errors))
+                }
+            }
+        }
+    })
+}
+
+/// Derive `platypus_runtime::tabular::Tabular` for a struct with named fields.
+///
+/// Each field becomes a column named after the field, with cells from its
+/// `TableCell` impl. Field attributes:
+/// - `#[tabular(rename = "...")]` sets the column name
+/// - `#[tabular(format = "currency:USD")]` formats numeric cells, using the
+///   specs accepted by `NumberFormat::parse`
+/// - `#[tabular(skip)]` leaves the field out of the table
+#[proc_macro_derive(Tabular, attributes(tabular))]
+pub fn derive_tabular(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_tabular(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[derive(Default)]
+struct ColumnOptions {
+    rename: Option<String>,
+    format: Option<LitStr>,
+    skip: bool,
+}
+
+fn column_options(field: &syn::Field) -> syn::Result<ColumnOptions> {
+    let mut options = ColumnOptions::default();
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("tabular")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                options.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("format") {
+                let spec = meta.value()?.parse::<LitStr>()?;
+                check_format_spec(&spec)?;
+                options.format = Some(spec);
+            } else if meta.path.is_ident("skip") {
+                options.skip = true;
+            } else {
+                return Err(meta.error("expected `rename`, `format` or `skip`"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(options)
+}
+
+/// Reject specs `NumberFormat::parse` would not accept.
+fn check_format_spec(spec: &LitStr) -> syn::Result<()> {
+    let value = spec.value();
+    let (style, arg) = match value.trim().split_once(':') {
+        Some((style, arg)) => (style, Some(arg.trim())),
+        None => (value.trim(), None),
+    };
+    let valid = match style {
+        "decimal" | "percent" => arg.is_none_or(|decimals| decimals.parse::<usize>().is_ok()),
+        "currency" => arg.is_some_and(|code| !code.is_empty()),
+        _ => false,
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(syn::Error::new_spanned(
+            spec,
+            "expected `decimal`, `decimal:N`, `percent`, `percent:N` or `currency:CODE`",
+        ))
+    }
+}
+
+fn expand_tabular(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(input, "Tabular can only be derived for structs"));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(input, "Tabular requires named fields"));
+    };
+
+    let mut columns = vec![];
+    let mut cells = vec![];
+    for field in &fields.named {
+        let name = field.ident.as_ref().expect("named field");
+        let ty = &field.ty;
+        let options = column_options(field)?;
+        if options.skip {
+            continue;
+        }
+
+        let column = options
+            .rename
+            .unwrap_or_else(|| name.to_string().trim_start_matches("r#").to_string());
+        columns.push(match options.format {
+            Some(spec) => quote! {
+                ::platypus_runtime::tabular::Column::new(#column).format(
+                    ::platypus_runtime::format::NumberFormat::parse(#spec)
+                        .expect("format spec checked by derive"),
+                )
+            },
+            None => quote! { ::platypus_runtime::tabular::Column::new(#column) },
+        });
+        cells.push(quote! {
+            <#ty as ::platypus_runtime::tabular::TableCell>::to_cell(&self.#name)
+        });
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::platypus_runtime::tabular::Tabular for #ident #ty_generics #where_clause {
+            fn columns() -> ::std::vec::Vec<::platypus_runtime::tabular::Column> {
+                ::std::vec![#(#columns),*]
+            }
+
+            fn cells(&self) -> ::std::vec::Vec<::platypus_runtime::tabular::Cell> {
+                ::std::vec![#(#cells),*]
+            }
+        }
+    })
+}
@@ -0,0 +1,108 @@
+//! Non-element actions sent to the browser.
+//!
+//! Actions are one-off instructions (visual effects, scrolling, clipboard
+//! writes, focus moves) that run on the client without adding anything to
+//! the page.
+//! They are queued during a run and sent after the run's deltas.
+
+use parking_lot::RwLock;
+use platypus_core::element::ElementId;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Full-screen celebration effect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Effect {
+    Balloons,
+    Snow,
+}
+
+impl Effect {
+    /// Get the effect name.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Effect::Balloons => "balloons",
+            Effect::Snow => "snow",
+        }
+    }
+}
+
+/// An action for the browser to perform.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Action {
+    /// Play a celebration effect.
+    Effect(Effect),
+    /// Scroll an element into view.
+    ScrollTo { element_id: ElementId },
+    /// Write text to the clipboard.
+    CopyToClipboard { text: String },
+    /// Move keyboard focus to the widget `key`. With `autofocus`, only the
+    /// first time the client shows the widget, and not away from another
+    /// focused widget.
+    Focus { key: String, autofocus: bool },
+    /// Post a message to the page embedding the app.
+    PostToHost { data: serde_json::Value },
+}
+
+/// Actions queued during a run.
+pub struct ActionQueue {
+    actions: Arc<RwLock<Vec<Action>>>,
+}
+
+impl ActionQueue {
+    /// Create a new action queue.
+    pub fn new() -> Self {
+        ActionQueue {
+            actions: Arc::new(RwLock::new(Vec::new())),
+        }
+    }
+
+    /// Queue an action.
+    pub fn push(&self, action: Action) {
+        self.actions.write().push(action);
+    }
+
+    /// Take all queued actions.
+    pub fn take(&self) -> Vec<Action> {
+        std::mem::take(&mut *self.actions.write())
+    }
+
+    /// Get queued action count.
+    pub fn len(&self) -> usize {
+        self.actions.read().len()
+    }
+
+    /// Check if the queue is empty.
+    pub fn is_empty(&self) -> bool {
+        self.actions.read().is_empty()
+    }
+}
+
+impl Default for ActionQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for ActionQueue {
+    fn clone(&self) -> Self {
+        ActionQueue {
+            actions: Arc::clone(&self.actions),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_drains_queue() {
+        let queue = ActionQueue::new();
+        let shared = queue.clone();
+        shared.push(Action::Effect(Effect::Snow));
+        assert_eq!(queue.take(), vec![Action::Effect(Effect::Snow)]);
+        assert!(queue.is_empty());
+    }
+}
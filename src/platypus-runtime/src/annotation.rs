@@ -0,0 +1,86 @@
+//! Boxes and polygons drawn on images.
+//!
+//! `st.image_annotator(image, key)` shows an image users draw on, e.g. to
+//! label training data or mark the objects a vision model should find. The
+//! shapes drawn so far come back as [`Annotation`]s, in pixels of the
+//! image's natural size:
+//!
+//! ```
+//! use platypus_runtime::annotation::Shape;
+//! use platypus_runtime::St;
+//!
+//! let mut st = St::new();
+//! let labels = ["cat", "dog"];
+//! for annotation in st.image_annotator_with_labels("/media/pets.jpg", &labels, "pets") {
+//!     if let Shape::Box { width, height, .. } = annotation.shape {
+//!         st.write(format!("{:?}: {}x{}", annotation.label, width, height));
+//!     }
+//! }
+//! ```
+
+pub use platypus_core::element::{
+    AnnotationPoint as Point, AnnotationShape as Shape, ImageAnnotation as Annotation,
+};
+
+/// Get the shape with a box's size made positive, or `None` if it has no
+/// area or a coordinate is not finite.
+fn normalized(shape: Shape) -> Option<Shape> {
+    let shape = match shape {
+        Shape::Box { x, y, width, height } => Shape::Box {
+            x: x.min(x + width),
+            y: y.min(y + height),
+            width: width.abs(),
+            height: height.abs(),
+        },
+        Shape::Polygon { points } if points.len() < 3 => return None,
+        polygon => polygon,
+    };
+    let (x, y, width, height) = shape.bounds();
+    let finite = [x, y, width, height].iter().all(|v| v.is_finite());
+    (finite && width > 0.0 && height > 0.0).then_some(shape)
+}
+
+/// Parse the annotations an annotator reported as JSON; see
+/// [`clean_annotations`].
+pub fn parse_annotations(json: &str, labels: &[String]) -> Vec<Annotation> {
+    clean_annotations(serde_json::from_str(json).unwrap_or_default(), labels)
+}
+
+/// Drop shapes without an area and labels not in `labels` (when any are
+/// offered).
+pub fn clean_annotations(annotations: Vec<Annotation>, labels: &[String]) -> Vec<Annotation> {
+    annotations
+        .into_iter()
+        .filter_map(|annotation| {
+            let label = annotation
+                .label
+                .filter(|label| labels.is_empty() || labels.contains(label));
+            Some(Annotation {
+                label,
+                shape: normalized(annotation.shape)?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_annotations() {
+        let json = r#"[
+            {"shape": "box", "x": 50, "y": 40, "width": -30, "height": 20, "label": "cat"},
+            {"shape": "polygon", "points": [{"x": 0, "y": 0}, {"x": 10, "y": 0}, {"x": 5, "y": 8}], "label": "fox"},
+            {"shape": "box", "x": 1, "y": 1, "width": 0, "height": 5},
+            {"shape": "polygon", "points": [{"x": 0, "y": 0}, {"x": 1, "y": 1}]}
+        ]"#;
+        let annotations = parse_annotations(json, &["cat".to_string(), "dog".to_string()]);
+        assert_eq!(annotations.len(), 2);
+        assert_eq!(annotations[0].label.as_deref(), Some("cat"));
+        assert_eq!(annotations[0].shape, Shape::Box { x: 20.0, y: 40.0, width: 30.0, height: 20.0 });
+        assert_eq!(annotations[1].label, None);
+        assert_eq!(annotations[1].shape.bounds(), (0.0, 0.0, 10.0, 8.0));
+        assert!(parse_annotations("not json", &[]).is_empty());
+    }
+}
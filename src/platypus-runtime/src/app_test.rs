@@ -0,0 +1,144 @@
+//! Headless runs of an app for tests.
+//!
+//! An `AppTest` runs an app function against chosen widget values, without
+//! a server or browser, and returns the page the run left:
+//!
+//! ```
+//! use platypus_core::widget::WidgetValue;
+//! use platypus_runtime::{AppTest, St};
+//!
+//! fn app(st: &mut St) -> Result<(), String> {
+//!     let name = st.text_input("Name", "", Some("name".to_string()));
+//!     st.write(format!("Hello, {}", name));
+//!     Ok(())
+//! }
+//!
+//! let test = AppTest::new(app).widget("name", WidgetValue::String("Ada".to_string()));
+//! assert_eq!(test.run().unwrap().len(), 2);
+//! // Compared to its baseline under `platypus test --visual`
+//! test.assert_visual("greeting");
+//! ```
+
+use crate::context::St;
+use crate::render;
+use crate::visual::{VisualCheck, VisualOutcome};
+use platypus_core::clock::ManualClock;
+use platypus_core::element::ElementType;
+use platypus_core::ids::SequentialIds;
+use platypus_core::state::DeltaGenerator;
+use platypus_core::widget::WidgetValue;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Time test runs see unless set with [`AppTest::at`]: 2024-01-01T00:00:00Z.
+pub const TEST_TIME: Duration = Duration::from_secs(1_704_067_200);
+
+/// An app function, as served by the server.
+pub type AppFn = fn(&mut St) -> Result<(), String>;
+
+/// An app and the widget values to run it with.
+#[derive(Clone)]
+pub struct AppTest {
+    app: AppFn,
+    widgets: Vec<(String, WidgetValue)>,
+    time: SystemTime,
+}
+
+impl AppTest {
+    /// Test `app`, starting from default widget values.
+    ///
+    /// Runs see the same time and IDs every time, so they produce the same
+    /// page.
+    pub fn new(app: AppFn) -> Self {
+        AppTest {
+            app,
+            widgets: Vec::new(),
+            time: UNIX_EPOCH + TEST_TIME,
+        }
+    }
+
+    /// Run the app at `time` instead of [`TEST_TIME`].
+    pub fn at(mut self, time: SystemTime) -> Self {
+        self.time = time;
+        self
+    }
+
+    /// Give the widget `key` a value, as if the user had set it.
+    pub fn widget(mut self, key: impl Into<String>, value: WidgetValue) -> Self {
+        self.widgets.push((key.into(), value));
+        self
+    }
+
+    /// Run the app and return the elements left on the page.
+    ///
+    /// Runs in which an element failed to render fail, with the elements'
+    /// errors.
+    pub fn run(&self) -> Result<Vec<ElementType>, String> {
+        let delta_gen = DeltaGenerator::new()
+            .with_clock(Arc::new(ManualClock::new(self.time)))
+            .with_ids(Arc::new(SequentialIds::new(0)));
+        for (key, value) in &self.widgets {
+            delta_gen.set_widget(key.clone(), value.clone());
+        }
+        let mut st = St::with_delta_gen(delta_gen);
+        (self.app)(&mut st)?;
+        let failures = st.element_failures();
+        if !failures.is_empty() {
+            return Err(failures.join("; "));
+        }
+        Ok(render::page_elements(&st.drain_deltas()))
+    }
+
+    /// Compare the page to the baseline of the app state `name`, if visual
+    /// checks are enabled. Panics on a regression.
+    pub fn assert_visual(&self, name: &str) {
+        let Some(check) = VisualCheck::from_env() else {
+            return;
+        };
+        let page = self.run().unwrap_or_else(|e| panic!("App state {} failed to run: {}", name, e));
+        match check.compare(name, &page) {
+            Ok(VisualOutcome::Regressed { difference, actual }) => panic!(
+                "App state {} differs from its baseline in {:.2}% of pixels (threshold {:.2}%); see {}, or run with --update to accept it",
+                name,
+                difference * 100.0,
+                check.threshold * 100.0,
+                actual.display()
+            ),
+            Ok(_) => {}
+            Err(e) => panic!("Visual check of app state {} failed: {}", name, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_with_widget_values() {
+        fn app(st: &mut St) -> Result<(), String> {
+            if st.checkbox("Show details", false, Some("details".to_string())) {
+                st.write("Details");
+            }
+            Ok(())
+        }
+
+        assert_eq!(AppTest::new(app).run().unwrap().len(), 1);
+        let page = AppTest::new(app).widget("details", WidgetValue::Bool(true)).run().unwrap();
+        assert!(matches!(&page[1], ElementType::Text { value } if value == "Details"));
+    }
+
+    #[test]
+    fn test_runs_are_repeatable() {
+        fn app(st: &mut St) -> Result<(), String> {
+            let id = st.delta_gen().ids().next_id();
+            st.write(format!("{} at {}", id, st.now().to_rfc3339()));
+            Ok(())
+        }
+
+        let test = AppTest::new(app);
+        assert_eq!(format!("{:?}", test.run().unwrap()), format!("{:?}", test.run().unwrap()));
+        let page = test.at(UNIX_EPOCH).run().unwrap();
+        assert!(matches!(&page[0], ElementType::Text { value } if value.ends_with("at 1970-01-01T00:00:00+00:00")));
+    }
+}
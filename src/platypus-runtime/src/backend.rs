@@ -0,0 +1,36 @@
+//! Storage shared by the nodes of a cluster.
+//!
+//! A [`SessionStore`](crate::SessionStore) or [`DataCache`](crate::DataCache)
+//! given a [`SharedBackend`] writes through to it, so a session survives
+//! its node going down and cached data computed on one node is reused on
+//! the others. The server provides a Redis backend behind its `redis`
+//! feature.
+
+use std::time::Duration;
+
+/// Prefix of the keys holding sessions.
+pub const SESSION_KEY_PREFIX: &str = "session:";
+
+/// Prefix of the keys holding cached data.
+pub const CACHE_KEY_PREFIX: &str = "cache:";
+
+/// Longest a session no node updates is kept in the backend.
+pub const SHARED_SESSION_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Key-value storage reachable from every node.
+///
+/// Calls block on the network, so async code makes them, and the session
+/// store and cache calls that write through to them, on a blocking thread.
+pub trait SharedBackend: Send + Sync {
+    /// Name of the backend, as reported by the cluster status.
+    fn name(&self) -> &str;
+
+    /// Get the value of `key`.
+    fn get(&self, key: &str) -> Result<Option<String>, String>;
+
+    /// Set `key` to `value`, expiring after `ttl` if given.
+    fn set(&self, key: &str, value: &str, ttl: Option<Duration>) -> Result<(), String>;
+
+    /// Delete `key`.
+    fn delete(&self, key: &str) -> Result<(), String>;
+}
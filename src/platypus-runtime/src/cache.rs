@@ -1,10 +1,19 @@
 //! Caching framework for Platypus
 //! Provides @st.cache_data and @st.cache_resource decorators
 
-use std::collections::HashMap;
+use crate::backend::{SharedBackend, CACHE_KEY_PREFIX};
+use std::collections::{HashMap, VecDeque};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
+/// Prefix of the topics announcing refreshed datasets
+const REFRESH_TOPIC_PREFIX: &str = "__refresh:";
+
+/// Get the topic announcing that the dataset `key` was refreshed
+pub fn refresh_topic(key: &str) -> String {
+    format!("{}{}", REFRESH_TOPIC_PREFIX, key)
+}
+
 /// Cache entry with TTL support
 #[derive(Clone, Debug)]
 pub struct CacheEntry {
@@ -28,6 +37,8 @@ impl CacheEntry {
 #[derive(Clone)]
 pub struct DataCache {
     cache: Arc<Mutex<HashMap<String, CacheEntry>>>,
+    /// Cache of the whole cluster, behind this node's
+    shared: Option<Arc<dyn SharedBackend>>,
 }
 
 impl DataCache {
@@ -35,25 +46,45 @@ impl DataCache {
     pub fn new() -> Self {
         DataCache {
             cache: Arc::new(Mutex::new(HashMap::new())),
+            shared: None,
         }
     }
 
+    /// Write values through to `backend` and read the values other nodes
+    /// cached from it
+    pub fn with_shared(mut self, backend: Arc<dyn SharedBackend>) -> Self {
+        self.shared = Some(backend);
+        self
+    }
+
     /// Get cached value
     pub fn get(&self, key: &str) -> Option<String> {
-        let mut cache = self.cache.lock().unwrap();
-        
-        if let Some(entry) = cache.get(key) {
-            if entry.is_valid() {
-                return Some(entry.data.clone());
-            } else {
-                cache.remove(key);
+        {
+            let mut cache = self.cache.lock().unwrap();
+            if let Some(entry) = cache.get(key) {
+                if entry.is_valid() {
+                    return Some(entry.data.clone());
+                } else {
+                    cache.remove(key);
+                }
             }
         }
-        None
+        let shared = self.shared.as_ref()?;
+        shared
+            .get(&format!("{}{}", CACHE_KEY_PREFIX, key))
+            .unwrap_or_else(|e| {
+                tracing::warn!("Failed to read {} from the shared cache: {}", key, e);
+                None
+            })
     }
 
     /// Set cached value with optional TTL
     pub fn set(&self, key: String, value: String, ttl: Option<Duration>) {
+        if let Some(shared) = &self.shared
+            && let Err(e) = shared.set(&format!("{}{}", CACHE_KEY_PREFIX, key), &value, ttl)
+        {
+            tracing::warn!("Failed to write {} to the shared cache: {}", key, e);
+        }
         let mut cache = self.cache.lock().unwrap();
         cache.insert(
             key,
@@ -65,7 +96,7 @@ impl DataCache {
         );
     }
 
-    /// Clear all cache entries
+    /// Clear all cache entries of this node
     pub fn clear(&self) {
         let mut cache = self.cache.lock().unwrap();
         cache.clear();
@@ -95,6 +126,9 @@ impl Default for DataCache {
 #[derive(Clone)]
 pub struct ResourceCache {
     cache: Arc<Mutex<HashMap<String, CacheEntry>>>,
+    /// Keys oldest first, kept when the cache is bounded
+    order: Arc<Mutex<VecDeque<String>>>,
+    capacity: Option<usize>,
 }
 
 impl ResourceCache {
@@ -102,6 +136,17 @@ impl ResourceCache {
     pub fn new() -> Self {
         ResourceCache {
             cache: Arc::new(Mutex::new(HashMap::new())),
+            order: Arc::new(Mutex::new(VecDeque::new())),
+            capacity: None,
+        }
+    }
+
+    /// Create a resource cache holding at most `capacity` resources,
+    /// evicting the oldest first
+    pub fn with_capacity(capacity: usize) -> Self {
+        ResourceCache {
+            capacity: Some(capacity.max(1)),
+            ..Self::new()
         }
     }
 
@@ -109,10 +154,10 @@ impl ResourceCache {
     pub fn get(&self, key: &str) -> Option<String> {
         let cache = self.cache.lock().unwrap();
         
-        if let Some(entry) = cache.get(key) {
-            if entry.is_valid() {
-                return Some(entry.data.clone());
-            }
+        if let Some(entry) = cache.get(key)
+            && entry.is_valid()
+        {
+            return Some(entry.data.clone());
         }
         None
     }
@@ -120,20 +165,30 @@ impl ResourceCache {
     /// Set cached resource (no TTL, persists for session)
     pub fn set(&self, key: String, value: String) {
         let mut cache = self.cache.lock().unwrap();
-        cache.insert(
-            key,
-            CacheEntry {
-                data: value,
-                created_at: Instant::now(),
-                ttl: None,
-            },
-        );
+        let entry = CacheEntry {
+            data: value,
+            created_at: Instant::now(),
+            ttl: None,
+        };
+        if cache.insert(key.clone(), entry).is_none()
+            && let Some(capacity) = self.capacity
+        {
+            let mut order = self.order.lock().unwrap();
+            order.push_back(key);
+            while cache.len() > capacity {
+                let Some(oldest) = order.pop_front() else {
+                    break;
+                };
+                cache.remove(&oldest);
+            }
+        }
     }
 
     /// Clear all resources
     pub fn clear(&self) {
         let mut cache = self.cache.lock().unwrap();
         cache.clear();
+        self.order.lock().unwrap().clear();
     }
 
     /// Get resource count
@@ -0,0 +1,197 @@
+//! Live-updating line charts.
+//!
+//! `st.line_chart_stream(key)` shows a line chart whose points are kept per
+//! session across reruns. Appending sends only the new points, as an
+//! `AppendChartData` delta, so a dashboard ticking every second does not
+//! resend the whole series:
+//!
+//! ```
+//! use platypus_runtime::St;
+//!
+//! let mut st = St::new();
+//! let cpu = st.line_chart_stream("cpu");
+//! cpu.append([("12:00:01", 0.42), ("12:00:02", 0.47)]);
+//! ```
+
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use platypus_core::element::ElementId;
+use platypus_core::state::DeltaGenerator;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+/// Default number of points kept per stream.
+pub const DEFAULT_STREAM_POINTS: usize = 1000;
+
+/// A point of a streamed series.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StreamPoint {
+    /// Sequence number, increasing per stream.
+    pub seq: u64,
+    pub x: serde_json::Value,
+    pub y: f64,
+}
+
+struct Series {
+    points: VecDeque<StreamPoint>,
+    capacity: usize,
+    next_seq: u64,
+    /// Newest point sent to the client
+    sent_seq: u64,
+}
+
+impl Series {
+    fn new(capacity: usize) -> Self {
+        Series {
+            points: VecDeque::new(),
+            capacity: capacity.max(1),
+            next_seq: 1,
+            sent_seq: 0,
+        }
+    }
+
+    fn push(&mut self, x: serde_json::Value, y: f64) {
+        if self.points.len() == self.capacity {
+            self.points.pop_front();
+        }
+        self.points.push_back(StreamPoint { seq: self.next_seq, x, y });
+        self.next_seq += 1;
+    }
+
+    fn take_unsent(&mut self) -> Vec<StreamPoint> {
+        let unsent: Vec<StreamPoint> = self.points.iter().filter(|point| point.seq > self.sent_seq).cloned().collect();
+        if let Some(point) = unsent.last() {
+            self.sent_seq = point.seq;
+        }
+        unsent
+    }
+}
+
+/// Per-session streamed series by key, shared across reruns.
+pub struct ChartStreamStore {
+    series: Arc<DashMap<String, Arc<Mutex<Series>>>>,
+}
+
+impl ChartStreamStore {
+    /// Create a new chart stream store.
+    pub fn new() -> Self {
+        ChartStreamStore {
+            series: Arc::new(DashMap::new()),
+        }
+    }
+
+    fn series(&self, key: &str, capacity: usize) -> Arc<Mutex<Series>> {
+        self.series
+            .entry(key.to_string())
+            .or_insert_with(|| Arc::new(Mutex::new(Series::new(capacity))))
+            .clone()
+    }
+
+    /// Attach the stream `key` to the chart element `id`, sending the
+    /// points the client has not received yet.
+    pub(crate) fn open(&self, key: &str, capacity: usize, id: ElementId, delta_gen: DeltaGenerator) -> ChartStream {
+        let stream = ChartStream {
+            id,
+            key: key.to_string(),
+            series: self.series(key, capacity),
+            delta_gen,
+        };
+        stream.flush();
+        stream
+    }
+}
+
+impl Default for ChartStreamStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for ChartStreamStore {
+    fn clone(&self) -> Self {
+        ChartStreamStore {
+            series: Arc::clone(&self.series),
+        }
+    }
+}
+
+/// Handle to a streamed line chart, returned by `st.line_chart_stream`.
+pub struct ChartStream {
+    id: ElementId,
+    key: String,
+    series: Arc<Mutex<Series>>,
+    delta_gen: DeltaGenerator,
+}
+
+impl ChartStream {
+    /// Get the chart element ID.
+    pub fn id(&self) -> ElementId {
+        self.id
+    }
+
+    /// Append `(x, y)` points, dropping the oldest beyond the stream's
+    /// capacity, and send them to the client.
+    pub fn append<X: Into<serde_json::Value>>(&self, points: impl IntoIterator<Item = (X, f64)>) {
+        {
+            let mut series = self.series.lock();
+            for (x, y) in points {
+                series.push(x.into(), y);
+            }
+        }
+        self.flush();
+    }
+
+    /// Get the kept points, oldest first.
+    pub fn points(&self) -> Vec<StreamPoint> {
+        self.series.lock().points.iter().cloned().collect()
+    }
+
+    /// Get the number of kept points.
+    pub fn len(&self) -> usize {
+        self.series.lock().points.len()
+    }
+
+    /// Check if no points are kept.
+    pub fn is_empty(&self) -> bool {
+        self.series.lock().points.is_empty()
+    }
+
+    fn flush(&self) {
+        let unsent = self.series.lock().take_unsent();
+        if !unsent.is_empty() {
+            let data = serde_json::to_string(&unsent).unwrap_or_default();
+            self.delta_gen.append_chart_data(self.id, self.key.clone(), data);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use platypus_core::state::Delta;
+
+    #[test]
+    fn test_only_new_points_are_sent() {
+        let store = ChartStreamStore::new();
+        let delta_gen = DeltaGenerator::new();
+        let stream = store.open("cpu", 2, ElementId::new(1), delta_gen.clone());
+        stream.append([(1, 0.5), (2, 0.6)]);
+        stream.append([(3, 0.7)]);
+        assert_eq!(stream.points().iter().map(|point| point.seq).collect::<Vec<_>>(), vec![2, 3]);
+
+        let appended: Vec<usize> = delta_gen
+            .take_deltas()
+            .into_iter()
+            .filter_map(|delta| match delta {
+                Delta::AppendChartData { data, .. } => Some(serde_json::from_str::<Vec<serde_json::Value>>(&data).unwrap().len()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(appended, vec![2, 1]);
+
+        // A rerun reopening the stream has nothing new to send
+        store.open("cpu", 2, ElementId::new(5), delta_gen.clone());
+        assert!(delta_gen.take_deltas().is_empty());
+    }
+}
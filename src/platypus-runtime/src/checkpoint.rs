@@ -0,0 +1,180 @@
+//! Cooperative yield points for long runs.
+//!
+//! Apps call [`St::checkpoint`](crate::St::checkpoint) in long loops. A
+//! checkpoint stops the run once a newer run supersedes it or its deadline
+//! passes, and flushes the page built so far to the client at most once per
+//! flush interval, so the user sees progress:
+//!
+//! ```
+//! use platypus_runtime::St;
+//!
+//! fn app(st: &mut St) -> Result<(), String> {
+//!     for row in 0..10_000 {
+//!         st.checkpoint()?;
+//!         if row % 1_000 == 0 {
+//!             st.write(format!("Processed {} rows", row));
+//!         }
+//!     }
+//!     Ok(())
+//! }
+//! # app(&mut St::new()).unwrap();
+//! ```
+
+use parking_lot::Mutex;
+use platypus_core::state::{Delta, DeltaGenerator};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Default shortest time between flushes of a run's page.
+pub const DEFAULT_FLUSH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Receives the page a run has built so far, in tree order.
+pub type FlushFn = Arc<dyn Fn(Vec<Delta>) + Send + Sync>;
+
+/// Why a checkpoint stopped a run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interrupted {
+    /// A newer run superseded the run.
+    Cancelled,
+    /// The run passed its deadline.
+    DeadlineExceeded,
+}
+
+impl std::fmt::Display for Interrupted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Interrupted::Cancelled => f.write_str("Run cancelled by a newer run"),
+            Interrupted::DeadlineExceeded => f.write_str("Run exceeded its deadline"),
+        }
+    }
+}
+
+impl std::error::Error for Interrupted {}
+
+/// Lets `st.checkpoint()?` stop apps returning `Result<(), String>`.
+impl From<Interrupted> for String {
+    fn from(interrupted: Interrupted) -> Self {
+        interrupted.to_string()
+    }
+}
+
+/// Cancels a run at its next checkpoint. Clones cancel the same run.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Create a token that is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel the run.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Check if the run was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Checkpoint state of a run, shared by its context and containers.
+#[derive(Clone)]
+pub(crate) struct Checkpoints {
+    pub(crate) cancellation: CancellationToken,
+    pub(crate) deadline: Option<Instant>,
+    pub(crate) flush: Option<(FlushFn, Duration)>,
+    last_flush: Arc<Mutex<Instant>>,
+    /// Set by the first checkpoint that stopped the run
+    interrupted: Arc<Mutex<Option<Interrupted>>>,
+}
+
+impl Checkpoints {
+    pub(crate) fn new() -> Self {
+        Checkpoints {
+            cancellation: CancellationToken::new(),
+            deadline: None,
+            flush: None,
+            last_flush: Arc::new(Mutex::new(Instant::now())),
+            interrupted: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Stop the run if it was cancelled or passed its deadline, or else
+    /// flush its page if the flush interval has passed.
+    pub(crate) fn check(&self, delta_gen: &DeltaGenerator) -> Result<(), Interrupted> {
+        let mut interrupted = self.interrupted.lock();
+        if interrupted.is_none() {
+            if self.cancellation.is_cancelled() {
+                *interrupted = Some(Interrupted::Cancelled);
+            } else if self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                *interrupted = Some(Interrupted::DeadlineExceeded);
+            }
+        }
+        if let Some(interrupted) = *interrupted {
+            return Err(interrupted);
+        }
+        drop(interrupted);
+
+        if let Some((_, interval)) = &self.flush {
+            let due = self.last_flush.lock().elapsed() >= *interval;
+            if due {
+                self.flush(delta_gen);
+            }
+        }
+        Ok(())
+    }
+
+    /// Flush the run's page now, if the run flushes, e.g. to show a
+    /// placeholder before slow work starts.
+    pub(crate) fn flush(&self, delta_gen: &DeltaGenerator) {
+        if let Some((flush, _)) = &self.flush {
+            *self.last_flush.lock() = Instant::now();
+            flush(delta_gen.peek_deltas());
+        }
+    }
+
+    pub(crate) fn interrupted(&self) -> Option<Interrupted> {
+        *self.interrupted.lock()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use platypus_core::element::ElementType;
+
+    #[test]
+    fn test_check_flushes_and_stops() {
+        let delta_gen = DeltaGenerator::new();
+        let flushed = Arc::new(Mutex::new(Vec::new()));
+        let mut checkpoints = Checkpoints::new();
+        let sink = Arc::clone(&flushed);
+        checkpoints.flush = Some((Arc::new(move |deltas: Vec<Delta>| sink.lock().push(deltas.len())), Duration::ZERO));
+
+        delta_gen.add_element(ElementType::Text { value: "Row 1".to_string() }, None);
+        assert_eq!(checkpoints.check(&delta_gen), Ok(()));
+        delta_gen.add_element(ElementType::Text { value: "Row 2".to_string() }, None);
+        assert_eq!(checkpoints.check(&delta_gen), Ok(()));
+        assert_eq!(*flushed.lock(), vec![1, 2]);
+        // Flushing leaves the deltas to the end of the run
+        assert_eq!(delta_gen.take_deltas().len(), 2);
+
+        checkpoints.cancellation.clone().cancel();
+        assert_eq!(checkpoints.check(&delta_gen), Err(Interrupted::Cancelled));
+        assert_eq!(checkpoints.interrupted(), Some(Interrupted::Cancelled));
+        assert_eq!(flushed.lock().len(), 2);
+    }
+
+    #[test]
+    fn test_deadline() {
+        let mut checkpoints = Checkpoints::new();
+        checkpoints.deadline = Some(Instant::now());
+        let result: Result<(), String> = (|| Ok(checkpoints.check(&DeltaGenerator::new())?))();
+        assert_eq!(result, Err("Run exceeded its deadline".to_string()));
+    }
+}
@@ -0,0 +1,132 @@
+//! Client context reported by the browser.
+//!
+//! The frontend sends a client context message on connect and whenever the
+//! viewport, color scheme or geolocation changes, so apps can make
+//! responsive and localized decisions server-side.
+
+use platypus_core::traits::Breakpoint;
+use serde::{Deserialize, Serialize};
+
+/// Preferred color scheme reported by the browser.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorScheme {
+    #[default]
+    Light,
+    Dark,
+}
+
+impl ColorScheme {
+    /// Parse a `prefers-color-scheme` value (anything but "dark" is light).
+    pub fn parse(value: &str) -> Self {
+        if value.eq_ignore_ascii_case("dark") {
+            ColorScheme::Dark
+        } else {
+            ColorScheme::Light
+        }
+    }
+
+    /// Get the scheme name.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ColorScheme::Light => "light",
+            ColorScheme::Dark => "dark",
+        }
+    }
+}
+
+/// Geolocation shared by the user (only present with consent).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Geolocation {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Accuracy radius in meters.
+    pub accuracy: f64,
+}
+
+/// Information about the connected browser.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClientInfo {
+    /// Viewport width in CSS pixels.
+    pub viewport_width: u32,
+    /// Viewport height in CSS pixels.
+    pub viewport_height: u32,
+    /// IANA timezone name (e.g. "Europe/Paris").
+    pub timezone: String,
+    /// BCP 47 locale (e.g. "en-US").
+    pub locale: String,
+    /// Preferred color scheme.
+    pub color_scheme: ColorScheme,
+    /// Geolocation, if the user granted access.
+    pub geolocation: Option<Geolocation>,
+    /// Active breakpoint name as computed by the client.
+    pub breakpoint: String,
+    /// Key of the widget holding keyboard focus, if any.
+    pub focused_widget: Option<String>,
+}
+
+impl ClientInfo {
+    /// Check whether the client has reported anything yet.
+    pub fn is_known(&self) -> bool {
+        self.viewport_width > 0 || !self.timezone.is_empty()
+    }
+
+    /// Get the active breakpoint name.
+    ///
+    /// Falls back to computing it from the viewport width, and to "desktop"
+    /// before the client has reported anything.
+    pub fn breakpoint(&self) -> String {
+        if !self.breakpoint.is_empty() {
+            return self.breakpoint.clone();
+        }
+        let breakpoints = Breakpoint::defaults();
+        match self.viewport_width {
+            0 => "desktop".to_string(),
+            width => Breakpoint::for_width(&breakpoints, width)
+                .map(|b| b.name.clone())
+                .unwrap_or_else(|| "desktop".to_string()),
+        }
+    }
+
+    /// Check whether the client prefers a dark color scheme.
+    pub fn prefers_dark(&self) -> bool {
+        self.color_scheme == ColorScheme::Dark
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_info_from_json() {
+        let info: ClientInfo = serde_json::from_value(serde_json::json!({
+            "viewport_width": 1280,
+            "viewport_height": 720,
+            "timezone": "Asia/Hong_Kong",
+            "color_scheme": "dark",
+        }))
+        .unwrap();
+
+        assert!(info.is_known());
+        assert!(info.prefers_dark());
+        assert!(info.geolocation.is_none());
+    }
+
+    #[test]
+    fn test_breakpoint_fallback() {
+        let mut info = ClientInfo::default();
+        assert_eq!(info.breakpoint(), "desktop");
+        info.viewport_width = 500;
+        assert_eq!(info.breakpoint(), "mobile");
+        info.breakpoint = "tablet".to_string();
+        assert_eq!(info.breakpoint(), "tablet");
+    }
+
+    #[test]
+    fn test_color_scheme_parse() {
+        assert_eq!(ColorScheme::parse("DARK"), ColorScheme::Dark);
+        assert_eq!(ColorScheme::parse("no-preference"), ColorScheme::Light);
+    }
+}
@@ -0,0 +1,285 @@
+//! Code editor widget with read-only regions and line annotations.
+//!
+//! `st.code_editor_builder()` configures the editor; `show()` adds it and
+//! returns a [`CodeEditorHandle`] for pushing annotations (e.g. lint
+//! results for the returned text) with UpdateElement deltas.
+
+use crate::St;
+use platypus_core::element::{CodeAnnotation, ElementId, ElementType};
+use platypus_core::state::DeltaGenerator;
+use platypus_core::widget::WidgetValue;
+use serde::{Deserialize, Serialize};
+use std::panic::Location;
+
+/// Default delay before the client sends edits, in milliseconds.
+pub const DEFAULT_DEBOUNCE_MS: u32 = 500;
+
+/// Severity of a line annotation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnnotationSeverity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl AnnotationSeverity {
+    /// Get the severity name.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AnnotationSeverity::Info => "info",
+            AnnotationSeverity::Warning => "warning",
+            AnnotationSeverity::Error => "error",
+        }
+    }
+}
+
+/// Builder for a code editor.
+pub struct CodeEditorBuilder {
+    /// Context the editor is added to
+    context: St,
+    /// Call site that created the builder, for the editor's key
+    location: &'static Location<'static>,
+    label: String,
+    language: String,
+    value: String,
+    read_only: Vec<(u32, u32)>,
+    debounce_ms: u32,
+    key: Option<String>,
+    disabled: Option<bool>,
+}
+
+impl CodeEditorBuilder {
+    /// Create a builder adding into the context's current container.
+    pub(crate) fn new(context: St, location: &'static Location<'static>, label: String, language: String) -> Self {
+        CodeEditorBuilder {
+            context,
+            location,
+            label,
+            language,
+            value: String::new(),
+            read_only: vec![],
+            debounce_ms: DEFAULT_DEBOUNCE_MS,
+            key: None,
+            disabled: None,
+        }
+    }
+
+    /// Set the initial text.
+    pub fn value(mut self, value: impl Into<String>) -> Self {
+        self.value = value.into();
+        self
+    }
+
+    /// Lock a 1-based inclusive line range of the initial text.
+    pub fn read_only(mut self, first_line: u32, last_line: u32) -> Self {
+        let first_line = first_line.max(1);
+        self.read_only.push((first_line, last_line.max(first_line)));
+        self.read_only.sort_unstable();
+        self
+    }
+
+    /// Set the delay before the client sends edits.
+    pub fn debounce_ms(mut self, debounce_ms: u32) -> Self {
+        self.debounce_ms = debounce_ms;
+        self
+    }
+
+    /// Set the widget key.
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    /// Show the editor but take no input.
+    pub fn disabled(mut self, disabled: bool) -> Self {
+        self.disabled = Some(disabled);
+        self
+    }
+
+    /// Add the editor and return a handle with the edited text.
+    ///
+    /// Edits that change read-only lines are rejected and the initial text
+    /// is returned instead.
+    pub fn show(mut self) -> CodeEditorHandle {
+        let key = self.context.widget_key_at("code_editor", &self.key, self.location);
+        let disabled = self.disabled.unwrap_or_else(|| self.context.widget_disabled(&key));
+
+        let submitted = match self.context.delta_gen().get_widget(&key) {
+            Some(WidgetValue::String(text)) => Some(text),
+            _ => None,
+        };
+        let (text, read_only, error) = match submitted {
+            Some(text) => match locate_read_only(&self.value, &text, &self.read_only) {
+                Some(read_only) => (text, read_only, None),
+                None => (
+                    self.value.clone(),
+                    self.read_only.clone(),
+                    Some("Read-only lines cannot be edited".to_string()),
+                ),
+            },
+            None => (self.value.clone(), self.read_only.clone(), None),
+        };
+
+        let element = ElementType::CodeEditor {
+            label: self.label.clone(),
+            value: text.clone(),
+            language: self.language.clone(),
+            read_only: read_only.clone(),
+            annotations: vec![],
+            debounce_ms: self.debounce_ms,
+            error: error.clone(),
+            key: Some(key.clone()),
+            disabled,
+        };
+        let id = self.context.add_element(element);
+
+        CodeEditorHandle {
+            id,
+            delta_gen: self.context.delta_gen().clone(),
+            label: self.label,
+            language: self.language,
+            text,
+            read_only,
+            annotations: vec![],
+            debounce_ms: self.debounce_ms,
+            error,
+            key: Some(key),
+            disabled,
+        }
+    }
+}
+
+/// Handle for a code editor shown in the current run.
+pub struct CodeEditorHandle {
+    id: ElementId,
+    delta_gen: DeltaGenerator,
+    label: String,
+    language: String,
+    text: String,
+    read_only: Vec<(u32, u32)>,
+    annotations: Vec<CodeAnnotation>,
+    debounce_ms: u32,
+    error: Option<String>,
+    key: Option<String>,
+    disabled: bool,
+}
+
+impl CodeEditorHandle {
+    /// Get the element ID.
+    pub fn id(&self) -> ElementId {
+        self.id
+    }
+
+    /// Get the edited text.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Get the error from rejecting an edit, if any.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Annotate a 1-based line of the edited text.
+    pub fn annotate(&mut self, line: u32, severity: AnnotationSeverity, message: impl Into<String>) {
+        self.annotations.push(CodeAnnotation {
+            line,
+            severity: severity.as_str().to_string(),
+            message: message.into(),
+        });
+        self.delta_gen.update_element(self.id, self.element());
+    }
+
+    /// Remove all annotations.
+    pub fn clear_annotations(&mut self) {
+        self.annotations.clear();
+        self.delta_gen.update_element(self.id, self.element());
+    }
+
+    fn element(&self) -> ElementType {
+        ElementType::CodeEditor {
+            label: self.label.clone(),
+            value: self.text.clone(),
+            language: self.language.clone(),
+            read_only: self.read_only.clone(),
+            annotations: self.annotations.clone(),
+            debounce_ms: self.debounce_ms,
+            error: self.error.clone(),
+            key: self.key.clone(),
+            disabled: self.disabled,
+        }
+    }
+}
+
+/// Find the read-only line ranges of `original` in `edited`.
+///
+/// Each locked block must appear unchanged and in order; lines may be
+/// added or removed between blocks, which shifts their positions. Returns
+/// the shifted ranges, or `None` if a block was modified.
+pub fn locate_read_only(original: &str, edited: &str, ranges: &[(u32, u32)]) -> Option<Vec<(u32, u32)>> {
+    let original: Vec<&str> = original.lines().collect();
+    let edited: Vec<&str> = edited.lines().collect();
+    let mut located = vec![];
+    let mut from = 0;
+
+    for &(first, last) in ranges {
+        let start = (first as usize - 1).min(original.len());
+        let end = (last as usize).min(original.len());
+        let block = &original[start..end];
+        if block.is_empty() {
+            continue;
+        }
+
+        let offset = edited[from.min(edited.len())..]
+            .windows(block.len())
+            .position(|window| window == block)?;
+        let position = from + offset;
+        located.push((position as u32 + 1, (position + block.len()) as u32));
+        from = position + block.len();
+    }
+
+    Some(located)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use platypus_core::state::Delta;
+
+    #[test]
+    fn test_locate_read_only_shifts_ranges() {
+        let original = "# header\nfn main() {\n}\n";
+        let edited = "# header\nuse std::io;\nfn main() {\n    println!();\n}\n";
+        assert_eq!(locate_read_only(original, edited, &[(1, 1), (2, 2)]), Some(vec![(1, 1), (3, 3)]));
+        assert_eq!(locate_read_only(original, "# changed\nfn main() {\n}", &[(1, 1)]), None);
+    }
+
+    #[test]
+    fn test_rejects_edit_to_read_only_lines() {
+        let mut st = St::new();
+        st.delta_gen().set_widget("code".to_string(), WidgetValue::String("changed\nbody".to_string()));
+        let handle = st
+            .code_editor_builder("Code", "rust")
+            .value("locked\nbody")
+            .read_only(1, 1)
+            .key("code")
+            .show();
+        assert_eq!(handle.text(), "locked\nbody");
+        assert!(handle.error().is_some());
+    }
+
+    #[test]
+    fn test_annotate_emits_update() {
+        let mut st = St::new();
+        let mut handle = st.code_editor_builder("Code", "python").value("x = 1").show();
+        handle.annotate(1, AnnotationSeverity::Warning, "unused variable");
+
+        match st.take_deltas().last() {
+            Some(Delta::UpdateElement { element: ElementType::CodeEditor { annotations, .. }, .. }) => {
+                assert_eq!(annotations[0].severity, "warning");
+            }
+            other => panic!("unexpected delta: {:?}", other),
+        }
+    }
+}
@@ -138,7 +138,7 @@ impl CustomComponent {
             "version": self.metadata.version,
             "author": self.metadata.author,
             "description": self.metadata.description,
-            "properties": self.properties.iter().map(|(_, p)| {
+            "properties": self.properties.values().map(|p| {
                 json!({
                     "name": p.name,
                     "type": p.prop_type,
@@ -1,13 +1,119 @@
 //! St context - Main API for building platypus applications.
 
-use platypus_core::element::{ElementId, ElementType};
+use crate::actions::{Action, ActionQueue, Effect};
+use crate::annotation::{self, Annotation};
+use crate::cache::DataCache;
+use crate::chart_stream::{self, ChartStream, ChartStreamStore};
+use crate::checkpoint::{CancellationToken, Checkpoints, FlushFn, Interrupted};
+use crate::client::{ClientInfo, ColorScheme};
+use crate::code_editor::CodeEditorBuilder;
+use crate::conversation::{Conversation, ConversationStore};
+use crate::datetime;
+use crate::downsample;
+#[cfg(feature = "embeddings")]
+use crate::embeddings::{SearchHit, SemanticIndex};
+use crate::event::{Event, EventDispatcher, PAGE_WIDGET_KEY};
+use crate::explorer::{self, Bin, ChartKind, ColumnSummary, Frame};
+use crate::export::{self, ExportFormat};
+use crate::finance::{Candle, CandlestickData, CandlestickOptions};
+use crate::form::{self, Form, FormErrors};
+use crate::format::{self, NumberFormat};
+use crate::formula::{self, CellValue};
+use crate::heatmap::{self, CellClick, HeatmapCell, HeatmapData, HeatmapOptions};
+use crate::host::HostMessage;
+use crate::json_editor;
+use crate::lazy;
+use crate::kpi::{DonutData, DonutSegment, GaugeData, Threshold};
+use crate::log_viewer::{self, LogBuffer, LogStore};
+use crate::media::{self, MediaFile, MediaStore};
+use crate::notifications::{self, NotificationLevel, NotificationState, NotificationStore};
+use crate::pdf;
+use crate::presence::{Presence, PresenceRegistry};
+use crate::pivot::{Aggregation, DrillTarget, Pivot, PivotDrill};
+use crate::progress::{ProgressBuilder, ProgressHandle};
+use crate::render;
+use crate::rich_text::{self, RichText};
+use crate::selection::{self, ChartSelection};
+use crate::share::{ShareLink, ShareOptions, ShareStore};
+use crate::skeleton::Skeleton;
+use crate::speech::Speaker;
+use crate::sql::{self, QueryResult, SqlConnection};
+use crate::tempdir::{TempDir, TempDirs};
+use crate::topic::{TopicCursors, TopicMessage, TopicRegistry};
+use crate::room::{RoomHandle, RoomRegistry};
+use crate::tabular::Tabular;
+use crate::theme::ThemeMode;
+use crate::transcription::{AudioInput, Transcriber, TranscriptionStatus};
+use crate::transfer::{self, TransferProgress};
+use chrono::{DateTime, NaiveDate, Utc};
+use platypus_core::element::{ElementId, ElementType, FeedbackKind, RateLimit, SkeletonKind, TextInputKind};
+use platypus_core::elements::advanced::{downsample, SPARKLINE_POINTS};
+use platypus_core::elements::typed;
+use platypus_core::session::SessionId;
 use platypus_core::state::DeltaGenerator;
+use platypus_core::traits::{Layout, Renderable};
+use platypus_core::widget::WidgetValue;
+use std::collections::{HashMap, HashSet};
+use parking_lot::Mutex;
+use std::panic::{self, AssertUnwindSafe, Location};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Text inputs send once typing pauses.
+const TEXT_INPUT_RATE_LIMIT: RateLimit = RateLimit::debounce(250);
+/// Sliders send at most ten times a second while dragged.
+const SLIDER_RATE_LIMIT: RateLimit = RateLimit::throttle(100);
 
 /// The main context for building platypus applications.
 /// Provides an API similar to Streamlit's `st` module.
+#[derive(Clone)]
 pub struct St {
     delta_gen: DeltaGenerator,
     current_container: Option<ElementId>,
+    media: MediaStore,
+    client_info: ClientInfo,
+    theme_mode: ThemeMode,
+    notifications: NotificationStore,
+    logs: LogStore,
+    chart_streams: ChartStreamStore,
+    chart_point_budget: Option<usize>,
+    events: EventDispatcher,
+    actions: ActionQueue,
+    /// Messages the embedding page posted since the last run
+    host_messages: Vec<HostMessage>,
+    rate_limits: HashMap<String, RateLimit>,
+    /// Disables widgets created from here on, unless overridden by key
+    disable_all: bool,
+    disabled_widgets: HashMap<String, bool>,
+    /// Widget keys used by this run, shared with its containers
+    widget_keys: Arc<Mutex<WidgetKeys>>,
+    /// Errors of elements that failed to render, shared with its containers
+    element_failures: Arc<Mutex<Vec<String>>>,
+    checkpoints: Checkpoints,
+    /// Containers the context writes into, e.g. `columns@src/app.rs:4:5[1]/`
+    container_path: String,
+    /// Key of the form the context writes into, if any
+    form: Option<String>,
+    rooms: RoomRegistry,
+    topics: TopicRegistry,
+    topic_cursors: TopicCursors,
+    subscribed_topics: HashSet<String>,
+    /// Containers of this run that can be embedded, by name
+    fragments: HashMap<String, ElementId>,
+    session_id: Option<SessionId>,
+    joined_rooms: HashSet<String>,
+    presence: PresenceRegistry,
+    reads_presence: bool,
+    conversations: ConversationStore,
+    transcriber: Transcriber,
+    speaker: Speaker,
+    temp_dirs: TempDirs,
+    data_cache: DataCache,
+    shares: ShareStore,
+    /// Tokens of links shared this run, frozen once it ends
+    pending_shares: Vec<String>,
+    #[cfg(feature = "embeddings")]
+    semantic_index: Option<SemanticIndex>,
 }
 
 impl St {
@@ -16,6 +122,42 @@ impl St {
         St {
             delta_gen: DeltaGenerator::new(),
             current_container: None,
+            media: MediaStore::new(),
+            client_info: ClientInfo::default(),
+            theme_mode: ThemeMode::default(),
+            notifications: NotificationStore::new(),
+            logs: LogStore::new(),
+            chart_streams: ChartStreamStore::new(),
+            chart_point_budget: Some(downsample::DEFAULT_CHART_POINT_BUDGET),
+            events: EventDispatcher::new(),
+            actions: ActionQueue::new(),
+            host_messages: Vec::new(),
+            rate_limits: HashMap::new(),
+            disable_all: false,
+            disabled_widgets: HashMap::new(),
+            widget_keys: Arc::new(Mutex::new(WidgetKeys::default())),
+            element_failures: Arc::new(Mutex::new(Vec::new())),
+            checkpoints: Checkpoints::new(),
+            container_path: String::new(),
+            form: None,
+            rooms: RoomRegistry::new(),
+            topics: TopicRegistry::new(),
+            topic_cursors: TopicCursors::new(),
+            subscribed_topics: HashSet::new(),
+            fragments: HashMap::new(),
+            session_id: None,
+            joined_rooms: HashSet::new(),
+            presence: PresenceRegistry::new(),
+            reads_presence: false,
+            conversations: ConversationStore::new(),
+            transcriber: Transcriber::new(),
+            speaker: Speaker::new(),
+            temp_dirs: TempDirs::new(),
+            data_cache: DataCache::new(),
+            shares: ShareStore::new(),
+            pending_shares: Vec::new(),
+            #[cfg(feature = "embeddings")]
+            semantic_index: None,
         }
     }
 
@@ -24,14 +166,509 @@ impl St {
         St {
             delta_gen,
             current_container: None,
+            media: MediaStore::new(),
+            client_info: ClientInfo::default(),
+            theme_mode: ThemeMode::default(),
+            notifications: NotificationStore::new(),
+            logs: LogStore::new(),
+            chart_streams: ChartStreamStore::new(),
+            chart_point_budget: Some(downsample::DEFAULT_CHART_POINT_BUDGET),
+            events: EventDispatcher::new(),
+            actions: ActionQueue::new(),
+            host_messages: Vec::new(),
+            rate_limits: HashMap::new(),
+            disable_all: false,
+            disabled_widgets: HashMap::new(),
+            widget_keys: Arc::new(Mutex::new(WidgetKeys::default())),
+            element_failures: Arc::new(Mutex::new(Vec::new())),
+            checkpoints: Checkpoints::new(),
+            container_path: String::new(),
+            form: None,
+            rooms: RoomRegistry::new(),
+            topics: TopicRegistry::new(),
+            topic_cursors: TopicCursors::new(),
+            subscribed_topics: HashSet::new(),
+            fragments: HashMap::new(),
+            session_id: None,
+            joined_rooms: HashSet::new(),
+            presence: PresenceRegistry::new(),
+            reads_presence: false,
+            conversations: ConversationStore::new(),
+            transcriber: Transcriber::new(),
+            speaker: Speaker::new(),
+            temp_dirs: TempDirs::new(),
+            data_cache: DataCache::new(),
+            shares: ShareStore::new(),
+            pending_shares: Vec::new(),
+            #[cfg(feature = "embeddings")]
+            semantic_index: None,
         }
     }
 
+    /// Use a shared media store for downloads.
+    pub fn with_media(mut self, media: MediaStore) -> Self {
+        self.media = media;
+        self
+    }
+
+    /// Use the client context reported by the browser.
+    pub fn with_client_info(mut self, client_info: ClientInfo) -> Self {
+        self.client_info = client_info;
+        self
+    }
+
     /// Get the delta generator.
     pub fn delta_gen(&self) -> &DeltaGenerator {
         &self.delta_gen
     }
 
+    /// Get the current time, from the session's clock; tests and replays
+    /// fix it so runs are repeatable.
+    pub fn now(&self) -> DateTime<Utc> {
+        self.delta_gen.clock().now().into()
+    }
+
+    /// Get the media store.
+    pub fn media(&self) -> &MediaStore {
+        &self.media
+    }
+
+    /// Get the client context (viewport, timezone, color scheme, geolocation).
+    pub fn client_info(&self) -> &ClientInfo {
+        &self.client_info
+    }
+
+    /// Set the session's theme mode.
+    pub fn with_theme_mode(mut self, theme_mode: ThemeMode) -> Self {
+        self.theme_mode = theme_mode;
+        self
+    }
+
+    /// Get the session's theme mode.
+    pub fn theme_mode(&self) -> ThemeMode {
+        self.theme_mode
+    }
+
+    /// Get the color scheme in effect, resolving auto mode from the client.
+    pub fn theme(&self) -> ColorScheme {
+        self.theme_mode.resolve(&self.client_info)
+    }
+
+    /// Share a notification store so notifications outlive a single run.
+    pub fn with_notifications(mut self, notifications: NotificationStore) -> Self {
+        self.notifications = notifications;
+        self
+    }
+
+    /// Get the notification store.
+    pub fn notifications(&self) -> &NotificationStore {
+        &self.notifications
+    }
+
+    /// Share a log store so log buffers outlive a single run.
+    pub fn with_logs(mut self, logs: LogStore) -> Self {
+        self.logs = logs;
+        self
+    }
+
+    /// Get the log store.
+    pub fn logs(&self) -> &LogStore {
+        &self.logs
+    }
+
+    /// Share a chart stream store so streamed series outlive a single run.
+    pub fn with_chart_streams(mut self, chart_streams: ChartStreamStore) -> Self {
+        self.chart_streams = chart_streams;
+        self
+    }
+
+    /// Set the most points sent per line, bar, area and scatter chart;
+    /// larger series are downsampled. `None` sends every point.
+    pub fn set_chart_point_budget(&mut self, budget: Option<usize>) {
+        self.chart_point_budget = budget;
+    }
+
+    /// Get the chart point budget.
+    pub fn chart_point_budget(&self) -> Option<usize> {
+        self.chart_point_budget
+    }
+
+    /// Downsample chart records beyond the point budget.
+    fn budgeted_chart_data(&self, data: String) -> String {
+        self.chart_point_budget
+            .and_then(|budget| downsample::downsample_records(&data, budget))
+            .unwrap_or(data)
+    }
+
+    /// Share the session's event handlers so subscriptions outlive a single run.
+    pub fn with_events(mut self, events: EventDispatcher) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// Get the event dispatcher.
+    pub fn events(&self) -> &EventDispatcher {
+        &self.events
+    }
+
+    /// Handle the session's events before they are applied.
+    ///
+    /// Subscribing again with the same key replaces the handler, so this
+    /// can be called on every run.
+    pub fn on_event<F>(&self, key: impl Into<String>, handler: F)
+    where
+        F: Fn(&Event) + Send + Sync + 'static,
+    {
+        self.events.subscribe(key, handler);
+    }
+
+    /// Share the server's rooms so state is shared across sessions.
+    pub fn with_rooms(mut self, rooms: RoomRegistry) -> Self {
+        self.rooms = rooms;
+        self
+    }
+
+    /// Set the ID of the session running the app.
+    pub fn with_session_id(mut self, session_id: SessionId) -> Self {
+        self.session_id = Some(session_id);
+        self
+    }
+
+    /// Get the ID of the session running the app, if known.
+    pub fn session_id(&self) -> Option<SessionId> {
+        self.session_id
+    }
+
+    /// Join a shared state room. Values set through the handle reach every
+    /// session in the room, and changes made by others rerun this app.
+    pub fn room(&mut self, id: impl Into<String>) -> RoomHandle {
+        let id = id.into();
+        let handle = self.rooms.open(&id, self.session_id);
+        self.joined_rooms.insert(id);
+        handle
+    }
+
+    /// Get the rooms joined during this run.
+    pub fn joined_rooms(&self) -> &HashSet<String> {
+        &self.joined_rooms
+    }
+
+    /// Share the server's topics, published to e.g. from an MQTT broker.
+    pub fn with_topics(mut self, topics: TopicRegistry) -> Self {
+        self.topics = topics;
+        self
+    }
+
+    /// Keep the session's read positions in topics across reruns.
+    pub fn with_topic_cursors(mut self, cursors: TopicCursors) -> Self {
+        self.topic_cursors = cursors;
+        self
+    }
+
+    /// Take the messages published to `topic` since the session last read
+    /// it, oldest first. The app reruns when the topic gets messages.
+    pub fn subscribe(&mut self, topic: impl Into<String>) -> Vec<TopicMessage> {
+        let topic = topic.into();
+        let messages = self.topic_cursors.read(&self.topics, &topic);
+        self.subscribed_topics.insert(topic);
+        messages
+    }
+
+    /// Get the dataset `key` of the server's data cache, e.g. one loaded by
+    /// a scheduled refresh. The app reruns when the dataset is refreshed.
+    pub fn cached_data(&mut self, key: &str) -> Option<String> {
+        self.subscribe(crate::cache::refresh_topic(key));
+        self.data_cache.get(key)
+    }
+
+    /// Get the topics subscribed to during this run.
+    pub fn subscribed_topics(&self) -> &HashSet<String> {
+        &self.subscribed_topics
+    }
+
+    /// Share the server's presence registry.
+    pub fn with_presence(mut self, presence: PresenceRegistry) -> Self {
+        self.presence = presence;
+        self
+    }
+
+    /// List the other sessions connected to the app, oldest first. The app
+    /// reruns when one joins or leaves.
+    pub fn presence(&mut self) -> Vec<Presence> {
+        self.reads_presence = true;
+        self.presence.others(self.session_id)
+    }
+
+    /// List the other sessions in a room, oldest first. The app reruns when
+    /// one joins or leaves the room.
+    pub fn presence_in(&mut self, room: &str) -> Vec<Presence> {
+        self.reads_presence = true;
+        self.presence.in_room(room, self.session_id)
+    }
+
+    /// Check if this run read presence.
+    pub fn reads_presence(&self) -> bool {
+        self.reads_presence
+    }
+
+    /// Use the session's conversation store.
+    pub fn with_conversations(mut self, conversations: ConversationStore) -> Self {
+        self.conversations = conversations;
+        self
+    }
+
+    /// Get the conversation saved under `key`, or a new one.
+    pub fn conversation(&self, key: impl Into<String>) -> Conversation {
+        self.conversations.get(&key.into())
+    }
+
+    /// Save a conversation so later runs see it. Saved conversations are
+    /// persisted with the session.
+    pub fn save_conversation(&mut self, conversation: &Conversation) {
+        self.conversations.save(conversation);
+    }
+
+    /// Share the server's transcriber with the app.
+    pub fn with_transcriber(mut self, transcriber: Transcriber) -> Self {
+        self.transcriber = transcriber;
+        self
+    }
+
+    /// Keep the session's scratch directory in the server's `temp_dirs`.
+    pub fn with_temp_dirs(mut self, temp_dirs: TempDirs) -> Self {
+        self.temp_dirs = temp_dirs;
+        self
+    }
+
+    /// Get the session's scratch directory, e.g. for intermediate files of
+    /// an upload being processed. It is created on first use and deleted
+    /// when the session ends.
+    pub fn tempdir(&self) -> crate::error::Result<TempDir> {
+        self.temp_dirs.dir(self.session_id)
+    }
+
+    /// Share the server's data cache with the app, e.g. for `st.sql`
+    /// results.
+    pub fn with_data_cache(mut self, data_cache: DataCache) -> Self {
+        self.data_cache = data_cache;
+        self
+    }
+
+    /// Keep shared snapshots in the server's `shares`.
+    pub fn with_shares(mut self, shares: ShareStore) -> Self {
+        self.shares = shares;
+        self
+    }
+
+    /// Share a read-only copy of the page this run builds, frozen once the
+    /// run ends, and get its link.
+    pub fn share_snapshot(&mut self, options: ShareOptions) -> ShareLink {
+        let link = self.shares.reserve(options);
+        self.pending_shares.push(link.token.clone());
+        link
+    }
+
+    /// Revoke a shared snapshot's link. Returns whether it existed.
+    pub fn revoke_snapshot(&self, token: &str) -> bool {
+        self.shares.revoke(token)
+    }
+
+    /// Take the tokens of the links shared this run.
+    pub fn take_pending_shares(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.pending_shares)
+    }
+
+    /// Share the server's speech synthesis with the app.
+    pub fn with_speaker(mut self, speaker: Speaker) -> Self {
+        self.speaker = speaker;
+        self
+    }
+
+    /// Share a semantic index with the app.
+    #[cfg(feature = "embeddings")]
+    pub fn with_semantic_index(mut self, index: SemanticIndex) -> Self {
+        self.semantic_index = Some(index);
+        self
+    }
+
+    /// Get the semantic index, e.g. to add documents.
+    #[cfg(feature = "embeddings")]
+    pub fn semantic_index(&self) -> Option<&SemanticIndex> {
+        self.semantic_index.as_ref()
+    }
+
+    /// Find the `k` documents in the semantic index closest in meaning to
+    /// `query`, best first.
+    #[cfg(feature = "embeddings")]
+    pub fn semantic_search(&self, query: &str, k: usize) -> crate::error::Result<Vec<SearchHit>> {
+        match &self.semantic_index {
+            Some(index) => index.search(query, k),
+            None => Err(crate::error::Error::execution("no semantic index configured")),
+        }
+    }
+
+    /// Display every message of a conversation as chat messages.
+    pub fn render_conversation(&mut self, conversation: &Conversation) {
+        for message in conversation.messages() {
+            self.chat_message(message.role.as_str(), message.content.clone());
+        }
+    }
+
+    /// Set how often the widget with `key` may send changes, replacing its
+    /// default (text inputs debounce, sliders throttle). Call before the
+    /// widget is created.
+    pub fn rate_limit(&mut self, key: impl Into<String>, limit: RateLimit) {
+        self.rate_limits.insert(key.into(), limit);
+    }
+
+    fn rate_limit_for(&self, key: &str, default: RateLimit) -> RateLimit {
+        self.rate_limits.get(key).copied().unwrap_or(default)
+    }
+
+    /// Disable every widget created from here on, e.g. to freeze the UI
+    /// while a job runs or to show a read-only view. Disabled widgets are
+    /// shown but take no input. Containers created afterwards inherit it.
+    pub fn disable_all(&mut self, disabled: bool) {
+        self.disable_all = disabled;
+    }
+
+    /// Disable or enable the widget with `key`, overriding
+    /// [`disable_all`](St::disable_all). Call before the widget is created.
+    pub fn disabled(&mut self, key: impl Into<String>, disabled: bool) {
+        self.disabled_widgets.insert(key.into(), disabled);
+    }
+
+    pub(crate) fn widget_disabled(&self, key: &str) -> bool {
+        self.disabled_widgets.get(key).copied().unwrap_or(self.disable_all)
+    }
+
+    /// Get the key of a widget of `kind` created at the caller's call site.
+    ///
+    /// Unkeyed widgets get a key from the call site, the path of containers
+    /// they are in and how often the call site ran in this run, so they keep
+    /// their values across reruns. An explicit key used by two widgets shows
+    /// an error, since widgets sharing a key share one value.
+    #[track_caller]
+    fn widget_key(&mut self, kind: &str, key: &Option<String>) -> String {
+        self.widget_key_at(kind, key, Location::caller())
+    }
+
+    /// Get the key of a widget of `kind` created at `location`, for builders
+    /// that add their widget after the call site returned.
+    pub(crate) fn widget_key_at(
+        &mut self,
+        kind: &str,
+        key: &Option<String>,
+        location: &'static Location<'static>,
+    ) -> String {
+        let mut keys = self.widget_keys.lock();
+        let Some(key) = key else {
+            let site = format!("{}@{}{}", kind, self.container_path, location);
+            let occurrence = keys.occurrences.entry(site.clone()).or_insert(0);
+            *occurrence += 1;
+            return match *occurrence {
+                1 => site,
+                n => format!("{}#{}", site, n),
+            };
+        };
+        let Some(&first) = keys.sites.get(key) else {
+            keys.sites.insert(key.clone(), location);
+            return key.clone();
+        };
+        drop(keys);
+
+        let sites = if first == location {
+            format!("used more than once at {}, e.g. in a loop", location)
+        } else {
+            format!("used at {} and again at {}", first, location)
+        };
+        let collision = platypus_core::Error::WidgetKeyCollision { key: key.clone() };
+        self.error(format!("{}: {}. Pass a distinct `key` to each widget.", collision, sites));
+        key.clone()
+    }
+
+    /// Get the page selected by the client, if any.
+    pub fn page(&self) -> Option<String> {
+        self.delta_gen
+            .get_widget(PAGE_WIDGET_KEY)
+            .and_then(|value| value.as_string().map(|s| s.to_string()))
+    }
+
+    /// Get the latest progress of an upload or download for the widget
+    /// `key`, e.g. a file uploader or download button.
+    pub fn transfer_progress(&self, key: &str) -> Option<TransferProgress> {
+        let value = self.delta_gen.get_widget(&transfer::widget_key(key))?;
+        serde_json::from_str(value.as_string()?).ok()
+    }
+
+    /// Get the latest click, selection or zoom on the chart `key`.
+    pub fn chart_selection(&self, key: &str) -> Option<ChartSelection> {
+        let value = self.delta_gen.get_widget(&selection::widget_key(key))?;
+        serde_json::from_str(value.as_string()?).ok()
+    }
+
+    /// Share an action queue with the code sending actions to the browser.
+    pub fn with_actions(mut self, actions: ActionQueue) -> Self {
+        self.actions = actions;
+        self
+    }
+
+    /// Get the action queue.
+    pub fn actions(&self) -> &ActionQueue {
+        &self.actions
+    }
+
+    /// Pass this run the messages the embedding page posted since the
+    /// last run.
+    pub fn with_host_messages(mut self, messages: Vec<HostMessage>) -> Self {
+        self.host_messages = messages;
+        self
+    }
+
+    /// Get the messages the page embedding the app posted since the last
+    /// run, oldest first. Empty unless the app is embedded.
+    pub fn host_messages(&self) -> &[HostMessage] {
+        &self.host_messages
+    }
+
+    /// Post `data` to the page embedding the app, if its origin is allowed.
+    pub fn post_to_host(&mut self, data: serde_json::Value) {
+        self.send_action(Action::PostToHost { data });
+    }
+
+    /// Stop the run at its next checkpoint once `token` is cancelled.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.checkpoints.cancellation = token;
+        self
+    }
+
+    /// Stop the run at the first checkpoint after `deadline`.
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.checkpoints.deadline = Some(deadline);
+        self
+    }
+
+    /// Pass the page built so far to `flush` at checkpoints, at most once
+    /// per `interval`.
+    pub fn with_flush(mut self, flush: FlushFn, interval: Duration) -> Self {
+        self.checkpoints.flush = Some((flush, interval));
+        self
+    }
+
+    /// Get the active viewport breakpoint ("mobile", "tablet" or "desktop").
+    pub fn breakpoint(&self) -> String {
+        self.client_info.breakpoint()
+    }
+
+    /// Build a container handle sharing this context's state.
+    /// Wrap the container `id`, named `name` in the paths of the keys
+    /// generated for its widgets.
+    fn container_handle(&self, id: ElementId, name: String) -> Container {
+        let mut context = self.clone();
+        context.container_path = format!("{}{}/", self.container_path, name);
+        Container { id, context }
+    }
+
     /// Display text.
     pub fn write(&mut self, text: impl Into<String>) -> ElementId {
         let text = text.into();
@@ -90,54 +727,86 @@ impl St {
     }
 
     /// Create a button.
+    #[track_caller]
     pub fn button(&mut self, label: impl Into<String>, key: Option<String>) -> bool {
         let label = label.into();
+        let key = self.widget_key("button", &key);
         self.delta_gen.add_element(
             ElementType::Button {
                 label,
-                key: key.clone(),
+                disabled: self.widget_disabled(&key),
+                key: Some(key.clone()),
             },
             self.current_container,
         );
 
         // Check if button was clicked
-        if let Some(key) = key {
-            self.delta_gen
-                .get_widget(&key)
-                .map(|v| v.as_bool().unwrap_or(false))
-                .unwrap_or(false)
-        } else {
-            false
-        }
+        self.delta_gen
+            .get_widget(&key)
+            .map(|v| v.as_bool().unwrap_or(false))
+            .unwrap_or(false)
     }
 
     /// Create a text input.
+    #[track_caller]
     pub fn text_input(
         &mut self,
         label: impl Into<String>,
         value: impl Into<String>,
         key: Option<String>,
+    ) -> String {
+        self.text_input_with_kind(label, value, TextInputKind::Text, key)
+    }
+
+    /// Create a masked password input.
+    #[track_caller]
+    pub fn password_input(&mut self, label: impl Into<String>, key: Option<String>) -> String {
+        self.text_input_with_kind(label, "", TextInputKind::Password, key)
+    }
+
+    /// Create a text input of the given kind (email, URL, phone, ...).
+    ///
+    /// Submitted values are validated server-side; an invalid value is shown
+    /// with an error on the element and the default value is returned.
+    #[track_caller]
+    pub fn text_input_with_kind(
+        &mut self,
+        label: impl Into<String>,
+        value: impl Into<String>,
+        kind: TextInputKind,
+        key: Option<String>,
     ) -> String {
         let label = label.into();
         let value = value.into();
-        let key_str = key.clone().unwrap_or_else(|| format!("text_input_{}", label));
+        let key_str = self.widget_key("text_input", &key);
+
+        let submitted = self
+            .delta_gen
+            .get_widget(&key_str)
+            .and_then(|v| v.as_string().map(|s| s.to_string()));
+        let error = submitted.as_deref().and_then(|s| kind.validate(s));
 
         self.delta_gen.add_element(
             ElementType::TextInput {
                 label,
                 value: value.clone(),
-                key: key.clone(),
+                disabled: self.widget_disabled(&key_str),
+                key: Some(key_str.clone()),
+                kind,
+                error: error.clone(),
+                rate_limit: self.rate_limit_for(&key_str, TEXT_INPUT_RATE_LIMIT),
             },
             self.current_container,
         );
 
-        self.delta_gen
-            .get_widget(&key_str)
-            .and_then(|v| v.as_string().map(|s| s.to_string()))
-            .unwrap_or(value)
+        match (submitted, error) {
+            (Some(submitted), None) => submitted,
+            _ => value,
+        }
     }
 
     /// Create a text area.
+    #[track_caller]
     pub fn text_area(
         &mut self,
         label: impl Into<String>,
@@ -146,13 +815,14 @@ impl St {
     ) -> String {
         let label = label.into();
         let value = value.into();
-        let key_str = key.clone().unwrap_or_else(|| format!("text_area_{}", label));
+        let key_str = self.widget_key("text_area", &key);
 
         self.delta_gen.add_element(
             ElementType::TextArea {
                 label,
                 value: value.clone(),
-                key: key.clone(),
+                disabled: self.widget_disabled(&key_str),
+                key: Some(key_str.clone()),
             },
             self.current_container,
         );
@@ -164,6 +834,7 @@ impl St {
     }
 
     /// Create a number input.
+    #[track_caller]
     pub fn number_input(
         &mut self,
         label: impl Into<String>,
@@ -171,24 +842,51 @@ impl St {
         key: Option<String>,
     ) -> f64 {
         let label = label.into();
-        let key_str = key.clone().unwrap_or_else(|| format!("number_input_{}", label));
+        let key_str = self.widget_key("number_input", &key);
 
         self.delta_gen.add_element(
             ElementType::NumberInput {
                 label,
                 value,
-                key: key.clone(),
+                disabled: self.widget_disabled(&key_str),
+                key: Some(key_str.clone()),
+                display: None,
             },
             self.current_container,
         );
 
-        self.delta_gen
-            .get_widget(&key_str)
-            .and_then(|v| v.as_number())
-            .unwrap_or(value)
+        self.number_widget(&key_str).unwrap_or(value)
+    }
+
+    /// Create a number input that also shows its value formatted.
+    #[track_caller]
+    pub fn number_input_with_format(
+        &mut self,
+        label: impl Into<String>,
+        value: f64,
+        format: &NumberFormat,
+        key: Option<String>,
+    ) -> f64 {
+        let label = label.into();
+        let key_str = self.widget_key("number_input", &key);
+        let current = self.number_widget(&key_str).unwrap_or(value);
+
+        self.delta_gen.add_element(
+            ElementType::NumberInput {
+                label,
+                value,
+                disabled: self.widget_disabled(&key_str),
+                key: Some(key_str.clone()),
+                display: Some(self.format_number(current, format)),
+            },
+            self.current_container,
+        );
+
+        current
     }
 
     /// Create a slider.
+    #[track_caller]
     pub fn slider(
         &mut self,
         label: impl Into<String>,
@@ -198,7 +896,7 @@ impl St {
         key: Option<String>,
     ) -> f64 {
         let label = label.into();
-        let key_str = key.clone().unwrap_or_else(|| format!("slider_{}", label));
+        let key_str = self.widget_key("slider", &key);
 
         self.delta_gen.add_element(
             ElementType::Slider {
@@ -206,7 +904,9 @@ impl St {
                 value,
                 min,
                 max,
-                key: key.clone(),
+                disabled: self.widget_disabled(&key_str),
+                key: Some(key_str.clone()),
+                rate_limit: self.rate_limit_for(&key_str, SLIDER_RATE_LIMIT),
             },
             self.current_container,
         );
@@ -218,6 +918,7 @@ impl St {
     }
 
     /// Create a checkbox.
+    #[track_caller]
     pub fn checkbox(
         &mut self,
         label: impl Into<String>,
@@ -225,13 +926,14 @@ impl St {
         key: Option<String>,
     ) -> bool {
         let label = label.into();
-        let key_str = key.clone().unwrap_or_else(|| format!("checkbox_{}", label));
+        let key_str = self.widget_key("checkbox", &key);
 
         self.delta_gen.add_element(
             ElementType::Checkbox {
                 label,
                 value,
-                key: key.clone(),
+                disabled: self.widget_disabled(&key_str),
+                key: Some(key_str.clone()),
             },
             self.current_container,
         );
@@ -243,6 +945,7 @@ impl St {
     }
 
     /// Create a selectbox.
+    #[track_caller]
     pub fn selectbox(
         &mut self,
         label: impl Into<String>,
@@ -252,14 +955,15 @@ impl St {
     ) -> String {
         let label = label.into();
         let default = options.get(index).cloned().unwrap_or_default();
-        let key_str = key.clone().unwrap_or_else(|| format!("selectbox_{}", label));
+        let key_str = self.widget_key("selectbox", &key);
 
         self.delta_gen.add_element(
             ElementType::Selectbox {
                 label,
                 options,
                 value: Some(default.clone()),
-                key: key.clone(),
+                disabled: self.widget_disabled(&key_str),
+                key: Some(key_str.clone()),
             },
             self.current_container,
         );
@@ -271,6 +975,7 @@ impl St {
     }
 
     /// Create a multiselect.
+    #[track_caller]
     pub fn multiselect(
         &mut self,
         label: impl Into<String>,
@@ -279,14 +984,15 @@ impl St {
         key: Option<String>,
     ) -> Vec<String> {
         let label = label.into();
-        let key_str = key.clone().unwrap_or_else(|| format!("multiselect_{}", label));
+        let key_str = self.widget_key("multiselect", &key);
 
         self.delta_gen.add_element(
             ElementType::Multiselect {
                 label,
                 options,
                 values: default.clone(),
-                key: key.clone(),
+                disabled: self.widget_disabled(&key_str),
+                key: Some(key_str.clone()),
             },
             self.current_container,
         );
@@ -297,70 +1003,598 @@ impl St {
             .unwrap_or(default)
     }
 
-    /// Display JSON.
-    pub fn json(&mut self, value: serde_json::Value) -> ElementId {
-        self.delta_gen.add_element(
-            ElementType::Json { value },
-            self.current_container,
-        )
-    }
+    /// Collect thumbs or star feedback, e.g. on a chat response.
+    ///
+    /// Returns 0/1 for thumbs and 1-5 for stars, or `None` until the user
+    /// picks one.
+    #[track_caller]
+    pub fn feedback(&mut self, kind: FeedbackKind, key: Option<String>) -> Option<u32> {
+        let key_str = self.widget_key("feedback", &key);
+        let value = self.score_widget(&key_str, kind.score_range());
 
-    /// Display an image.
-    pub fn image(
-        &mut self,
-        src: impl Into<String>,
-        caption: Option<String>,
-        width: Option<u32>,
-    ) -> ElementId {
-        let src = src.into();
         self.delta_gen.add_element(
-            ElementType::Image { src, caption, width },
+            ElementType::Feedback {
+                kind,
+                value,
+                disabled: self.widget_disabled(&key_str),
+                key: Some(key_str),
+            },
             self.current_container,
-        )
-    }
+        );
 
-    /// Display success message.
-    pub fn success(&mut self, message: impl Into<String>) -> ElementId {
-        let message = message.into();
-        self.delta_gen.add_element(
-            ElementType::Success { message },
-            self.current_container,
-        )
+        value
     }
 
-    /// Display error message.
-    pub fn error(&mut self, message: impl Into<String>) -> ElementId {
-        let message = message.into();
+    /// Create a star rating from 1 to `max`, or `None` until one is picked.
+    #[track_caller]
+    pub fn rating(&mut self, max: u32, key: Option<String>) -> Option<u32> {
+        let max = max.max(1);
+        let key_str = self.widget_key("rating", &key);
+        let value = self.score_widget(&key_str, 1..=max);
+
         self.delta_gen.add_element(
-            ElementType::Error { message },
+            ElementType::Rating {
+                max,
+                value,
+                disabled: self.widget_disabled(&key_str),
+                key: Some(key_str),
+            },
             self.current_container,
-        )
+        );
+
+        value
     }
 
-    /// Display warning message.
-    pub fn warning(&mut self, message: impl Into<String>) -> ElementId {
-        let message = message.into();
-        self.delta_gen.add_element(
-            ElementType::Warning { message },
-            self.current_container,
-        )
+    /// Read a numeric widget; text typed by hand is parsed in the session
+    /// locale.
+    fn number_widget(&self, key: &str) -> Option<f64> {
+        match self.delta_gen.get_widget(key)? {
+            WidgetValue::Number(n) => Some(n),
+            WidgetValue::String(s) => format::parse_number(&s, self.locale()),
+            _ => None,
+        }
     }
 
-    /// Display info message.
-    pub fn info(&mut self, message: impl Into<String>) -> ElementId {
-        let message = message.into();
-        self.delta_gen.add_element(
-            ElementType::Info { message },
-            self.current_container,
-        )
+    /// Read a numeric score widget, ignoring values outside `range`.
+    fn score_widget(&self, key: &str, range: std::ops::RangeInclusive<u32>) -> Option<u32> {
+        let score = match self.delta_gen.get_widget(key)? {
+            WidgetValue::Number(n) => n,
+            WidgetValue::String(s) => s.trim().parse().ok()?,
+            _ => return None,
+        };
+        (score.fract() == 0.0 && score >= 0.0)
+            .then_some(score as u32)
+            .filter(|score| range.contains(score))
     }
 
-    /// Display progress bar.
-    pub fn progress(&mut self, value: f32) -> ElementId {
-        self.delta_gen.add_element(
-            ElementType::Progress { value },
-            self.current_container,
-        )
+    /// Create a tag/chip input with autocomplete suggestions.
+    #[track_caller]
+    pub fn tags_input(
+        &mut self,
+        label: impl Into<String>,
+        suggestions: Vec<impl Into<String>>,
+        key: Option<String>,
+    ) -> Vec<String> {
+        self.tags_input_with_options(label, suggestions, None, true, key)
+    }
+
+    /// Create a tag input with a tag limit and optionally only suggested tags.
+    ///
+    /// Submitted tags are trimmed and de-duplicated; tags over the limit or
+    /// outside the suggestions (when custom tags are disallowed) are dropped
+    /// and reported on the element.
+    #[track_caller]
+    pub fn tags_input_with_options(
+        &mut self,
+        label: impl Into<String>,
+        suggestions: Vec<impl Into<String>>,
+        max_tags: Option<usize>,
+        allow_custom: bool,
+        key: Option<String>,
+    ) -> Vec<String> {
+        let label = label.into();
+        let suggestions: Vec<String> = suggestions.into_iter().map(|s| s.into()).collect();
+        let key_str = self.widget_key("tags_input", &key);
+
+        let submitted = match self.delta_gen.get_widget(&key_str) {
+            Some(WidgetValue::StringArray(tags)) => tags,
+            Some(WidgetValue::String(value)) => serde_json::from_str::<Vec<String>>(&value)
+                .unwrap_or_else(|_| value.split(',').map(|t| t.to_string()).collect()),
+            _ => vec![],
+        };
+        let (values, error) = normalize_tags(submitted, &suggestions, max_tags, allow_custom);
+
+        self.delta_gen.add_element(
+            ElementType::TagsInput {
+                label,
+                suggestions,
+                values: values.clone(),
+                max_tags,
+                allow_custom,
+                error,
+                disabled: self.widget_disabled(&key_str),
+                key: Some(key_str.clone()),
+            },
+            self.current_container,
+        );
+
+        values
+    }
+
+    /// Display a code editor and get the edited text.
+    #[track_caller]
+    pub fn code_editor(
+        &mut self,
+        label: impl Into<String>,
+        language: impl Into<String>,
+        key: Option<String>,
+    ) -> String {
+        let mut builder = self.code_editor_builder(label, language);
+        if let Some(key) = key {
+            builder = builder.key(key);
+        }
+        builder.show().text().to_string()
+    }
+
+    /// Create a code editor with read-only regions and annotations.
+    #[track_caller]
+    pub fn code_editor_builder(
+        &mut self,
+        label: impl Into<String>,
+        language: impl Into<String>,
+    ) -> CodeEditorBuilder {
+        CodeEditorBuilder::new(self.clone(), Location::caller(), label.into(), language.into())
+    }
+
+    /// Add an element to the current container.
+    pub(crate) fn add_element(&self, element: ElementType) -> ElementId {
+        self.delta_gen.add_element(element, self.current_container)
+    }
+
+    /// Display a JSON editor and get the edited value.
+    ///
+    /// Edits are validated against `schema` when given. Invalid JSON or
+    /// schema violations are shown inline and `value` is returned instead.
+    #[track_caller]
+    pub fn json_editor(
+        &mut self,
+        value: serde_json::Value,
+        schema: Option<serde_json::Value>,
+        key: Option<String>,
+    ) -> serde_json::Value {
+        let key_str = self.widget_key("json_editor", &key);
+
+        let parsed = match self.delta_gen.get_widget(&key_str) {
+            Some(submitted) => json_editor::parse_value(&submitted),
+            None => Ok(value.clone()),
+        };
+        let (text, errors, result) = match parsed {
+            Ok(edited) => {
+                let errors = schema
+                    .as_ref()
+                    .map(|schema| json_editor::validate(schema, &edited))
+                    .unwrap_or_default();
+                let text = serde_json::to_string_pretty(&edited).unwrap_or_default();
+                let result = if errors.is_empty() { edited } else { value };
+                (text, errors, result)
+            }
+            Err((text, error)) => (text, vec![error], value),
+        };
+
+        self.delta_gen.add_element(
+            ElementType::JsonEditor {
+                text,
+                schema,
+                errors,
+                disabled: self.widget_disabled(&key_str),
+                key: Some(key_str),
+            },
+            self.current_container,
+        );
+
+        result
+    }
+
+    /// Display a rich text editor and get its sanitized content.
+    #[track_caller]
+    pub fn rich_text(&mut self, label: impl Into<String>, key: Option<String>) -> RichText {
+        self.rich_text_with_limit(label, rich_text::MAX_RICH_TEXT_BYTES, key)
+    }
+
+    /// Display a rich text editor with a content size limit in bytes.
+    ///
+    /// Content over the limit is rejected and an empty value is returned.
+    #[track_caller]
+    pub fn rich_text_with_limit(
+        &mut self,
+        label: impl Into<String>,
+        max_bytes: usize,
+        key: Option<String>,
+    ) -> RichText {
+        let label = label.into();
+        let key_str = self.widget_key("rich_text", &key);
+
+        let submitted = match self.delta_gen.get_widget(&key_str) {
+            Some(WidgetValue::RichText { html }) | Some(WidgetValue::String(html)) => html,
+            _ => String::new(),
+        };
+        let (text, error) = if submitted.len() > max_bytes {
+            (
+                RichText::default(),
+                Some(format!("Content exceeds the {} byte limit", max_bytes)),
+            )
+        } else {
+            (RichText::from_html(&submitted), None)
+        };
+
+        self.delta_gen.add_element(
+            ElementType::RichTextEditor {
+                label,
+                value: text.as_html().to_string(),
+                max_bytes,
+                error,
+                disabled: self.widget_disabled(&key_str),
+                key: Some(key_str.clone()),
+            },
+            self.current_container,
+        );
+
+        text
+    }
+
+    /// Render a struct as a group of widgets and return it filled in.
+    ///
+    /// Validation failures are shown as error messages and returned.
+    pub fn form_for<T: Form>(&mut self, key: &str) -> std::result::Result<T, FormErrors> {
+        let result = T::render_form(self, key);
+        if let Err(errors) = &result {
+            for error in &errors.0 {
+                self.error(format!("{}: {}", error.label, error.message));
+            }
+        }
+        result
+    }
+
+    /// Display a trait-based element, such as one built with `ElementFactory`.
+    ///
+    /// `Validatable` elements that fail validation, and elements whose
+    /// conversion panics, are shown as an inline error instead.
+    pub fn element(&mut self, element: &dyn Renderable) -> platypus_core::Result<ElementId> {
+        let parent = self.current_container;
+        let result = panic::catch_unwind(AssertUnwindSafe(|| self.delta_gen.add_renderable(element, parent)))
+            .unwrap_or_else(|payload| Err(platypus_core::Error::internal(panic_message(payload.as_ref()))));
+        if let Err(e) = &result {
+            self.element_failed(element.name(), e.to_string());
+        }
+        result
+    }
+
+    /// Build part of the page behind an error boundary.
+    ///
+    /// If `build` panics, the elements it added are dropped and an inline
+    /// error naming `name` takes their place, so the rest of the run still
+    /// renders. Returns `None` if `build` panicked.
+    ///
+    /// ```
+    /// # use platypus_runtime::St;
+    /// let mut st = St::new();
+    /// let total = st.boundary("Totals", |st| {
+    ///     st.write("Totals");
+    ///     let values: Vec<i64> = Vec::new();
+    ///     values[0]
+    /// });
+    /// assert_eq!(total, None);
+    /// assert_eq!(st.element_failures().len(), 1);
+    /// ```
+    pub fn boundary<R>(&mut self, name: &str, build: impl FnOnce(&mut St) -> R) -> Option<R> {
+        let start = self.delta_gen.delta_count();
+        match panic::catch_unwind(AssertUnwindSafe(|| build(self))) {
+            Ok(value) => Some(value),
+            Err(payload) => {
+                self.delta_gen.truncate_deltas(start);
+                self.element_failed(name, panic_message(payload.as_ref()));
+                None
+            }
+        }
+    }
+
+    /// Get the errors of elements that failed to render in this run, in the
+    /// order they failed.
+    ///
+    /// The server fails runs that have any when
+    /// `ServerConfig::fatal_element_errors` is set, e.g. in CI.
+    pub fn element_failures(&self) -> Vec<String> {
+        self.element_failures.lock().clone()
+    }
+
+    /// Yield to the server in a long loop: stop the run if a newer run
+    /// superseded it or its deadline passed, and flush the page built so
+    /// far if it is time to.
+    ///
+    /// Once a checkpoint stops the run, later checkpoints stop it too.
+    pub fn checkpoint(&self) -> std::result::Result<(), Interrupted> {
+        self.checkpoints.check(&self.delta_gen)
+    }
+
+    /// Get why a checkpoint stopped the run, if one did.
+    pub fn interrupted(&self) -> Option<Interrupted> {
+        self.checkpoints.interrupted()
+    }
+
+    fn element_failed(&mut self, name: &str, message: String) {
+        tracing::error!("{} failed to render: {}", name, message);
+        let message = format!("{} failed to render: {}", name, message);
+        self.element_failures.lock().push(message.clone());
+        self.error(message);
+    }
+
+    /// Display JSON.
+    pub fn json(&mut self, value: serde_json::Value) -> ElementId {
+        self.delta_gen.add_element(
+            ElementType::Json { value },
+            self.current_container,
+        )
+    }
+
+    /// Display an image.
+    pub fn image(
+        &mut self,
+        src: impl Into<String>,
+        caption: Option<String>,
+        width: Option<u32>,
+    ) -> ElementId {
+        let src = src.into();
+        self.delta_gen.add_element(
+            ElementType::Image { src, caption, width },
+            self.current_container,
+        )
+    }
+
+    /// Display an image users draw boxes and polygons on, returning the
+    /// shapes drawn so far.
+    #[track_caller]
+    pub fn image_annotator(&mut self, image: impl Into<String>, key: &str) -> Vec<Annotation> {
+        self.image_annotator_with_labels(image, &[], key)
+    }
+
+    /// Display an image annotator whose shapes are labeled with one of
+    /// `labels`.
+    #[track_caller]
+    pub fn image_annotator_with_labels(&mut self, image: impl Into<String>, labels: &[&str], key: &str) -> Vec<Annotation> {
+        let key = self.widget_key("image_annotator", &Some(key.into()));
+        let labels: Vec<String> = labels.iter().map(|label| label.to_string()).collect();
+        let annotations = self
+            .delta_gen
+            .get_widget(&key)
+            .and_then(|value| match value {
+                WidgetValue::Annotations(annotations) => Some(annotation::clean_annotations(annotations, &labels)),
+                WidgetValue::String(json) => Some(annotation::parse_annotations(&json, &labels)),
+                _ => None,
+            })
+            .unwrap_or_default();
+
+        self.delta_gen.add_element(
+            ElementType::ImageAnnotator {
+                src: image.into(),
+                labels,
+                annotations: annotations.clone(),
+                disabled: self.widget_disabled(&key),
+                key,
+            },
+            self.current_container,
+        );
+
+        annotations
+    }
+
+    /// Speak `text` in `voice` with the server's speech provider, showing
+    /// an audio player that plays the clip once.
+    pub fn speak(&mut self, text: impl AsRef<str>, voice: impl AsRef<str>) -> crate::error::Result<ElementId> {
+        let src = self.speaker.speak(text.as_ref(), voice.as_ref(), &self.media)?;
+        Ok(self.delta_gen.add_element(
+            ElementType::Audio { src, autoplay: true },
+            self.current_container,
+        ))
+    }
+
+    /// Display success message.
+    pub fn success(&mut self, message: impl Into<String>) -> ElementId {
+        let message = message.into();
+        self.delta_gen.add_element(
+            ElementType::Success { message },
+            self.current_container,
+        )
+    }
+
+    /// Display error message.
+    pub fn error(&mut self, message: impl Into<String>) -> ElementId {
+        let message = message.into();
+        self.delta_gen.add_element(
+            ElementType::Error { message },
+            self.current_container,
+        )
+    }
+
+    /// Display warning message.
+    pub fn warning(&mut self, message: impl Into<String>) -> ElementId {
+        let message = message.into();
+        self.delta_gen.add_element(
+            ElementType::Warning { message },
+            self.current_container,
+        )
+    }
+
+    /// Display info message.
+    pub fn info(&mut self, message: impl Into<String>) -> ElementId {
+        let message = message.into();
+        self.delta_gen.add_element(
+            ElementType::Info { message },
+            self.current_container,
+        )
+    }
+
+    /// Queue an action for the browser, sent after this run's deltas.
+    pub fn send_action(&mut self, action: Action) {
+        self.actions.push(action);
+    }
+
+    /// Float balloons up the screen.
+    pub fn balloons(&mut self) {
+        self.send_action(Action::Effect(Effect::Balloons));
+    }
+
+    /// Let snow fall across the screen.
+    pub fn snow(&mut self) {
+        self.send_action(Action::Effect(Effect::Snow));
+    }
+
+    /// Move keyboard focus to the widget with the explicit key `key`, e.g.
+    /// the field that failed validation.
+    pub fn focus(&mut self, key: impl Into<String>) {
+        self.send_action(Action::Focus {
+            key: key.into(),
+            autofocus: false,
+        });
+    }
+
+    /// Focus the widget with the explicit key `key` when the page first
+    /// shows it. Later reruns leave focus alone, as does a user already
+    /// typing into another widget.
+    pub fn autofocus(&mut self, key: impl Into<String>) {
+        self.send_action(Action::Focus {
+            key: key.into(),
+            autofocus: true,
+        });
+    }
+
+    /// Get the key of the widget holding keyboard focus on the client.
+    pub fn focused_widget(&self) -> Option<&str> {
+        self.client_info.focused_widget.as_deref()
+    }
+
+    /// Post a notification to the notification center.
+    ///
+    /// Notifications last for the current run; the same notification sent
+    /// on the next run keeps its read state. Returns the notification ID.
+    pub fn notify(&mut self, level: NotificationLevel, message: impl Into<String>) -> String {
+        let id = self.notifications.push(level, message, false);
+        self.notification_center();
+        id
+    }
+
+    /// Post a notification that persists across reruns until dismissed.
+    pub fn notify_persistent(&mut self, level: NotificationLevel, message: impl Into<String>) -> String {
+        let id = self.notifications.push(level, message, true);
+        self.notification_center();
+        id
+    }
+
+    /// Render the notification center, or refresh it if already rendered.
+    pub fn notification_center(&mut self) -> ElementId {
+        let state = match self.delta_gen.get_widget(notifications::NOTIFICATIONS_KEY) {
+            Some(WidgetValue::String(value)) => serde_json::from_str(&value).unwrap_or_default(),
+            Some(WidgetValue::Json(value)) => serde_json::from_value(value).unwrap_or_default(),
+            _ => NotificationState::default(),
+        };
+        self.notifications.apply_state(&state);
+
+        let element = ElementType::NotificationCenter {
+            notifications: self.notifications.items(),
+            key: Some(notifications::NOTIFICATIONS_KEY.to_string()),
+        };
+        match self.notifications.element_id() {
+            Some(id) => {
+                self.delta_gen.update_element(id, element);
+                id
+            }
+            None => {
+                let id = self.delta_gen.add_element(element, None);
+                self.notifications.set_element_id(id);
+                id
+            }
+        }
+    }
+
+    /// Display a log viewer and get its buffer.
+    ///
+    /// The buffer persists across reruns and can be moved into background
+    /// tasks; lines appended later are streamed to the viewer.
+    #[track_caller]
+    pub fn log_viewer(&mut self, key: impl Into<String>) -> LogBuffer {
+        let key = self.widget_key("log_viewer", &Some(key.into()));
+        let buffer = self.logs.buffer(&key);
+        self.delta_gen.add_element(
+            ElementType::LogViewer {
+                lines: buffer.lines().iter().map(|line| line.to_item()).collect(),
+                capacity: buffer.capacity(),
+                key,
+            },
+            self.current_container,
+        );
+        buffer
+    }
+
+    /// Display the log viewer for the app's `tracing` events.
+    ///
+    /// Events reach it when the server's tracing subscriber includes the
+    /// session log layer.
+    #[track_caller]
+    pub fn app_logs(&mut self) -> LogBuffer {
+        self.log_viewer(log_viewer::APP_LOG_KEY)
+    }
+
+    /// Display progress bar.
+    pub fn progress(&mut self, value: f32) -> ElementId {
+        self.progress_bar().value(value).show().id()
+    }
+
+    /// Build a progress bar with a label or indeterminate mode.
+    pub fn progress_bar(&mut self) -> ProgressBuilder {
+        ProgressBuilder::new(self.delta_gen.clone(), self.current_container)
+    }
+
+    /// Display an empty progress bar and return a handle for updating it.
+    pub fn progress_handle(&mut self) -> ProgressHandle {
+        self.progress_bar().show()
+    }
+
+    /// Display a widget's value through `template`, where `{}` is replaced
+    /// by `value`.
+    ///
+    /// While a change to the widget under `key` is being processed, the
+    /// client shows its own predicted value; the rerun's output replaces it.
+    pub fn echo(&mut self, key: impl Into<String>, template: impl Into<String>, value: impl ToString) -> ElementId {
+        self.delta_gen.add_element(
+            ElementType::Echo {
+                key: key.into(),
+                template: template.into(),
+                value: value.to_string(),
+            },
+            self.current_container,
+        )
+    }
+
+    /// Display a chat message. `role` is usually `"user"`, `"assistant"` or
+    /// `"system"`; `content` is Markdown.
+    pub fn chat_message(&mut self, role: impl Into<String>, content: impl Into<String>) -> ElementId {
+        self.delta_gen.add_element(
+            ElementType::ChatMessage {
+                role: role.into(),
+                content: content.into(),
+            },
+            self.current_container,
+        )
+    }
+
+    /// Display an element defined outside platypus.
+    ///
+    /// The client renders it with the renderer registered under `name`; the
+    /// server runs any validation hook registered for `name` first.
+    pub fn custom_element(&mut self, name: impl Into<String>, payload: serde_json::Value) -> ElementId {
+        self.delta_gen.add_element(
+            ElementType::Custom { name: name.into(), payload },
+            self.current_container,
+        )
     }
 
     /// Display divider.
@@ -371,6 +1605,40 @@ impl St {
         )
     }
 
+    /// Remove an element, and everything inside it, from the page.
+    pub fn remove(&mut self, id: ElementId) {
+        self.delta_gen.remove_element(id);
+    }
+
+    /// Display a placeholder shaped like loading content, e.g. `count` rows
+    /// of a table being queried, and return a handle that replaces it.
+    ///
+    /// When the run flushes partial pages, the placeholder is sent at once.
+    #[track_caller]
+    pub fn skeleton(&mut self, kind: SkeletonKind, count: u32) -> Skeleton {
+        let container = self.container();
+        let placeholder = self
+            .delta_gen
+            .add_element(ElementType::Skeleton { kind, count }, Some(container.id()));
+        self.checkpoints.flush(&self.delta_gen);
+        Skeleton::new(container, placeholder)
+    }
+
+    /// Build part of the page once the client scrolls it into view.
+    ///
+    /// Until then a placeholder takes its place and `build` is not run;
+    /// once seen, the part is built on every run. Returns `None` while
+    /// the placeholder is shown.
+    #[track_caller]
+    pub fn lazy<R>(&mut self, key: impl Into<String>, build: impl FnOnce(&mut St) -> R) -> Option<R> {
+        let key = self.widget_key("lazy", &Some(key.into()));
+        if self.delta_gen.get_widget(&lazy::widget_key(&key)).is_some() {
+            return Some(build(self));
+        }
+        self.delta_gen.add_element(ElementType::Lazy { key }, self.current_container);
+        None
+    }
+
     /// Display empty space.
     pub fn empty(&mut self) -> ElementId {
         self.delta_gen.add_element(
@@ -380,33 +1648,58 @@ impl St {
     }
 
     /// Create a container.
+    #[track_caller]
     pub fn container(&mut self) -> Container {
+        let location = Location::caller();
+        let id = self.delta_gen.add_element(
+            ElementType::Container { children: vec![], hidden: false },
+            self.current_container,
+        );
+        self.container_handle(id, format!("container@{}", location))
+    }
+
+    /// Create a container that can be embedded into other websites on its
+    /// own, at `/embed/{app}/{name}`.
+    #[track_caller]
+    pub fn fragment(&mut self, name: impl Into<String>) -> Container {
+        let location = Location::caller();
         let id = self.delta_gen.add_element(
-            ElementType::Container { children: vec![] },
+            ElementType::Container { children: vec![], hidden: false },
             self.current_container,
         );
-        Container::new(id, self.delta_gen.clone())
+        self.fragments.insert(name.into(), id);
+        self.container_handle(id, format!("fragment@{}", location))
+    }
+
+    /// Get the embeddable containers created during this run.
+    pub fn fragments(&self) -> &HashMap<String, ElementId> {
+        &self.fragments
     }
 
     /// Create columns.
+    #[track_caller]
     pub fn columns(&mut self, count: usize) -> Vec<Container> {
+        let location = Location::caller();
         let width = 1.0 / count as f32;
         (0..count)
-            .map(|_| {
+            .map(|index| {
                 let id = self.delta_gen.add_element(
                     ElementType::Column {
                         children: vec![],
                         width: Some(width),
+                        hidden: false,
                     },
                     self.current_container,
                 );
-                Container::new(id, self.delta_gen.clone())
+                self.container_handle(id, format!("columns@{}[{}]", location, index))
             })
             .collect()
     }
 
     /// Create tabs.
+    #[track_caller]
     pub fn tabs(&mut self, labels: Vec<&str>) -> Vec<Container> {
+        let location = Location::caller();
         let tabs_data: Vec<(String, Vec<ElementId>)> = labels
             .iter()
             .map(|label| (label.to_string(), vec![]))
@@ -419,28 +1712,225 @@ impl St {
 
         labels
             .iter()
-            .map(|_label| {
+            .enumerate()
+            .map(|(index, _label)| {
                 let tab_id = self.delta_gen.add_element(
-                    ElementType::Container { children: vec![] },
+                    ElementType::Container { children: vec![], hidden: false },
                     Some(id),
                 );
-                Container::new(tab_id, self.delta_gen.clone())
+                self.container_handle(tab_id, format!("tabs@{}[{}]", location, index))
             })
             .collect()
     }
 
     /// Create an expander.
+    #[track_caller]
     pub fn expander(&mut self, label: impl Into<String>) -> Container {
+        let location = Location::caller();
         let label = label.into();
         let id = self.delta_gen.add_element(
             ElementType::Expander {
                 label,
                 expanded: false,
                 children: vec![],
+                hidden: false,
+            },
+            self.current_container,
+        );
+        self.container_handle(id, format!("expander@{}", location))
+    }
+
+    /// Create a fieldset grouping related form fields under a legend.
+    ///
+    /// The fieldset starts with a summary of its fields' validation errors,
+    /// filled in by [`Fieldset::summarize`].
+    #[track_caller]
+    pub fn fieldset(&mut self, legend: impl Into<String>) -> Fieldset {
+        let location = Location::caller();
+        let id = self.delta_gen.add_element(
+            ElementType::Fieldset {
+                legend: legend.into(),
+                children: vec![],
+                hidden: false,
+            },
+            self.current_container,
+        );
+        let summary = self.delta_gen.add_element(ElementType::ValidationSummary { errors: vec![] }, Some(id));
+        Fieldset {
+            container: self.container_handle(id, format!("fieldset@{}", location)),
+            summary,
+        }
+    }
+
+    /// Create a form. The client holds changes to the form's widgets until
+    /// a [`form_submit_button`](St::form_submit_button) in it is pressed,
+    /// then sends them together for a single rerun.
+    #[track_caller]
+    pub fn form(&mut self, key: impl Into<String>) -> Container {
+        let key = self.widget_key("form", &Some(key.into()));
+        let id = self.delta_gen.add_element(
+            ElementType::Form {
+                key: key.clone(),
+                children: vec![],
+                hidden: false,
             },
             self.current_container,
         );
-        Container::new(id, self.delta_gen.clone())
+        let mut form = self.container_handle(id, format!("form@{}", key));
+        form.context.form = Some(key);
+        form
+    }
+
+    /// Run `build` with a context writing into the form `key`.
+    ///
+    /// ```
+    /// use platypus_runtime::St;
+    ///
+    /// let mut st = St::new();
+    /// let submitted = st.with_form("search", |st| {
+    ///     let query = st.text_input("Query", "", None);
+    ///     st.form_submit_button("Search").then_some(query)
+    /// });
+    /// # assert!(submitted.is_none());
+    /// ```
+    #[track_caller]
+    pub fn with_form<R>(&mut self, key: impl Into<String>, build: impl FnOnce(&mut St) -> R) -> R {
+        build(&mut self.form(key).st())
+    }
+
+    /// Create the submit button of the form the context writes into,
+    /// returning whether it submitted the form for this run.
+    #[track_caller]
+    pub fn form_submit_button(&mut self, label: impl Into<String>) -> bool {
+        let label = label.into();
+        let Some(form_key) = self.form.clone() else {
+            self.error(format!("Submit button \"{}\" is not in a form; create it in st.form()", label));
+            return false;
+        };
+        let key = self.widget_key("form_submit_button", &None);
+        self.delta_gen.add_element(
+            ElementType::FormSubmitButton {
+                label,
+                form_key,
+                disabled: self.widget_disabled(&key),
+                key: Some(key.clone()),
+            },
+            self.current_container,
+        );
+
+        self.delta_gen
+            .get_widget(&key)
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// Create a container with per-breakpoint layouts.
+    ///
+    /// The client switches between layouts as the viewport changes, without
+    /// rerunning the script.
+    #[track_caller]
+    pub fn responsive_container(&mut self, layouts: Vec<(impl Into<String>, Layout)>) -> Container {
+        let location = Location::caller();
+        let layouts = layouts
+            .into_iter()
+            .map(|(breakpoint, layout)| (breakpoint.into(), layout))
+            .collect();
+        let id = self.delta_gen.add_element(
+            ElementType::ResponsiveContainer {
+                children: vec![],
+                layouts,
+                hidden: false,
+            },
+            self.current_container,
+        );
+        self.container_handle(id, format!("responsive_container@{}", location))
+    }
+
+    /// Run `build` with a context writing into a new container.
+    ///
+    /// The container's scope is the closure, so elements cannot end up in it
+    /// after the fact:
+    ///
+    /// ```
+    /// use platypus_runtime::St;
+    ///
+    /// let mut st = St::new();
+    /// let clicked = st.with_container(|st| {
+    ///     st.write("Inside");
+    ///     st.button("Go", None)
+    /// });
+    /// st.write("Outside");
+    /// # assert!(!clicked);
+    /// ```
+    #[track_caller]
+    pub fn with_container<R>(&mut self, build: impl FnOnce(&mut St) -> R) -> R {
+        build(&mut self.container().st())
+    }
+
+    /// Run `build` with a context writing into a new container, hidden
+    /// unless `visible`.
+    ///
+    /// Use it to toggle a section: the section's elements are sent either
+    /// way, so the client keeps their state while the section is hidden.
+    #[track_caller]
+    pub fn with_visible_container<R>(&mut self, visible: bool, build: impl FnOnce(&mut St) -> R) -> R {
+        let location = Location::caller();
+        let id = self.delta_gen.add_element(
+            ElementType::Container {
+                children: vec![],
+                hidden: !visible,
+            },
+            self.current_container,
+        );
+        build(&mut self.container_handle(id, format!("container@{}", location)).st())
+    }
+
+    /// Run `build` with one context per column, in column order.
+    #[track_caller]
+    pub fn with_columns<R>(&mut self, count: usize, build: impl FnOnce(&mut [St]) -> R) -> R {
+        let mut columns: Vec<St> = self.columns(count).iter().map(Container::st).collect();
+        build(&mut columns)
+    }
+
+    /// Run `build` with one context per tab, in label order.
+    #[track_caller]
+    pub fn with_tabs<R>(&mut self, labels: Vec<&str>, build: impl FnOnce(&mut [St]) -> R) -> R {
+        let mut tabs: Vec<St> = self.tabs(labels).iter().map(Container::st).collect();
+        build(&mut tabs)
+    }
+
+    /// Run `build` with a context writing into a new expander.
+    #[track_caller]
+    pub fn with_expander<R>(&mut self, label: impl Into<String>, build: impl FnOnce(&mut St) -> R) -> R {
+        build(&mut self.expander(label).st())
+    }
+
+    /// Run `build` with a context writing into a new fieldset, then
+    /// summarize its fields' validation errors.
+    ///
+    /// ```
+    /// use platypus_runtime::St;
+    ///
+    /// let mut st = St::new();
+    /// let (name, phone) = st.with_fieldset("Contact", |st| {
+    ///     let name = st.text_input("Name", "", Some("name".to_string()));
+    ///     let phone = st.text_input("Phone", "", Some("phone".to_string()));
+    ///     (name, phone)
+    /// });
+    /// # assert_eq!((name.as_str(), phone.as_str()), ("", ""));
+    /// ```
+    #[track_caller]
+    pub fn with_fieldset<R>(&mut self, legend: impl Into<String>, build: impl FnOnce(&mut St) -> R) -> R {
+        let fieldset = self.fieldset(legend);
+        let result = build(&mut fieldset.st());
+        fieldset.summarize();
+        result
+    }
+
+    /// Run `build` with a context writing into the sidebar.
+    #[track_caller]
+    pub fn with_sidebar<R>(&mut self, build: impl FnOnce(&mut St) -> R) -> R {
+        build(&mut self.sidebar().st())
     }
 
     /// Display a metric.
@@ -453,21 +1943,87 @@ impl St {
         let label = label.into();
         let value = value.into();
         self.delta_gen.add_element(
-            ElementType::Metric { label, value, delta },
+            ElementType::Metric { label, value, delta, sparkline: Vec::new() },
+            self.current_container,
+        )
+    }
+
+    /// Display a metric with recent values of `series` drawn as an inline
+    /// sparkline. Long series are downsampled to `SPARKLINE_POINTS` before
+    /// being sent.
+    pub fn metric_with_sparkline(
+        &mut self,
+        label: impl Into<String>,
+        value: impl Into<String>,
+        delta: Option<String>,
+        series: &[f64],
+    ) -> ElementId {
+        let sparkline = downsample(series, SPARKLINE_POINTS);
+        self.delta_gen.add_element(
+            ElementType::Metric { label: label.into(), value: value.into(), delta, sparkline },
             self.current_container,
         )
     }
 
+    /// Display a gauge of `value` between `min` and `max`, colored by the
+    /// highest of `thresholds` it reaches.
+    pub fn gauge(&mut self, value: f64, min: f64, max: f64, thresholds: &[Threshold]) -> ElementId {
+        let data = serde_json::to_string(&GaugeData::new(value, min, max, thresholds)).unwrap_or_default();
+        self.delta_gen.add_element(ElementType::Gauge { data }, self.current_container)
+    }
+
+    /// Display a donut of `segments`, sized by their share of the total.
+    pub fn donut(&mut self, segments: &[DonutSegment]) -> ElementId {
+        let data = serde_json::to_string(&DonutData::new(segments)).unwrap_or_default();
+        self.delta_gen.add_element(ElementType::Donut { data }, self.current_container)
+    }
+
+    /// Display a numeric metric formatted for the session locale.
+    ///
+    /// The delta, if any, uses the same format with an explicit sign.
+    pub fn metric_number(
+        &mut self,
+        label: impl Into<String>,
+        value: f64,
+        delta: Option<f64>,
+        format: &NumberFormat,
+    ) -> ElementId {
+        let value = self.format_number(value, format);
+        let delta = delta.map(|d| {
+            let formatted = self.format_number(d, format);
+            if d > 0.0 { format!("+{}", formatted) } else { formatted }
+        });
+        self.metric(label, value, delta)
+    }
+
+    /// Get the session locale, defaulting to en-US.
+    pub fn locale(&self) -> &str {
+        if self.client_info.locale.is_empty() {
+            "en-US"
+        } else {
+            &self.client_info.locale
+        }
+    }
+
+    /// Format a number using the session locale.
+    pub fn format_number(&self, value: f64, format: &NumberFormat) -> String {
+        format.format(value, self.locale())
+    }
+
     /// Get sidebar context.
+    #[track_caller]
     pub fn sidebar(&mut self) -> Container {
+        let location = Location::caller();
         let id = self.delta_gen.add_element(
-            ElementType::Sidebar { children: vec![] },
+            ElementType::Sidebar { children: vec![], hidden: false },
             None,
         );
-        Container::new(id, self.delta_gen.clone())
+        self.container_handle(id, format!("sidebar@{}", location))
     }
 
-    /// Create a date input.
+    /// Create a date input, returning the date as `YYYY-MM-DD` when it
+    /// parses in the session locale.
+    #[track_caller]
     pub fn date_input(
         &mut self,
         label: impl Into<String>,
@@ -476,24 +2032,111 @@ impl St {
     ) -> String {
         let label = label.into();
         let value = value.into();
-        let key_str = key.clone().unwrap_or_else(|| format!("date_input_{}", label));
+        let key_str = self.widget_key("date_input", &key);
 
         self.delta_gen.add_element(
             ElementType::DateInput {
                 label,
                 value: value.clone(),
-                key: key.clone(),
+                disabled: self.widget_disabled(&key_str),
+                key: Some(key_str.clone()),
             },
             self.current_container,
         );
 
+        // Dates typed in the locale's order are passed on as ISO dates
         self.delta_gen
             .get_widget(&key_str)
             .and_then(|v| v.as_string().map(|s| s.to_string()))
+            .map(|date| match datetime::parse_date(&date, self.locale()) {
+                Some(date) => date.format(datetime::DATE_FORMAT).to_string(),
+                None => date,
+            })
             .unwrap_or(value)
     }
 
+    /// Create a date range input returning inclusive `(start, end)` dates.
+    ///
+    /// Endpoints are ordered and clamped to the optional bounds.
+    #[track_caller]
+    pub fn date_range_input(
+        &mut self,
+        label: impl Into<String>,
+        default: (NaiveDate, NaiveDate),
+        min: Option<NaiveDate>,
+        max: Option<NaiveDate>,
+        key: Option<String>,
+    ) -> (NaiveDate, NaiveDate) {
+        let label = label.into();
+        let key_str = self.widget_key("date_range_input", &key);
+
+        let (start, end) = self
+            .delta_gen
+            .get_widget(&key_str)
+            .and_then(|v| datetime::parse_date_range(&v, self.locale()))
+            .unwrap_or(default);
+        let start = datetime::clamp(start, min, max);
+        let end = datetime::clamp(end, min, max);
+
+        let format = |date: NaiveDate| date.format(datetime::DATE_FORMAT).to_string();
+        self.delta_gen.add_element(
+            ElementType::DateRangeInput {
+                label,
+                start: format(start),
+                end: format(end),
+                min: min.map(format),
+                max: max.map(format),
+                disabled: self.widget_disabled(&key_str),
+                key: Some(key_str.clone()),
+            },
+            self.current_container,
+        );
+
+        (start, end)
+    }
+
+    /// Create a date and time input.
+    ///
+    /// The browser shows wall-clock time in the client's timezone; the
+    /// returned value is in UTC and clamped to the optional bounds.
+    #[track_caller]
+    pub fn datetime_input(
+        &mut self,
+        label: impl Into<String>,
+        default: DateTime<Utc>,
+        min: Option<DateTime<Utc>>,
+        max: Option<DateTime<Utc>>,
+        key: Option<String>,
+    ) -> DateTime<Utc> {
+        let label = label.into();
+        let key_str = self.widget_key("datetime_input", &key);
+        let tz = datetime::client_timezone(&self.client_info);
+
+        let value = self
+            .delta_gen
+            .get_widget(&key_str)
+            .and_then(|v| datetime::parse_datetime(&v, tz))
+            .unwrap_or(default);
+        let value = datetime::clamp(value, min, max);
+
+        self.delta_gen.add_element(
+            ElementType::DateTimeInput {
+                label,
+                value: datetime::to_local(value, tz),
+                timezone: tz.name().to_string(),
+                min: min.map(|dt| datetime::to_local(dt, tz)),
+                max: max.map(|dt| datetime::to_local(dt, tz)),
+                disabled: self.widget_disabled(&key_str),
+                key: Some(key_str.clone()),
+            },
+            self.current_container,
+        );
+
+        value
+    }
+
     /// Create a time input.
+    #[track_caller]
     pub fn time_input(
         &mut self,
         label: impl Into<String>,
@@ -502,13 +2145,14 @@ impl St {
     ) -> String {
         let label = label.into();
         let value = value.into();
-        let key_str = key.clone().unwrap_or_else(|| format!("time_input_{}", label));
+        let key_str = self.widget_key("time_input", &key);
 
         self.delta_gen.add_element(
             ElementType::TimeInput {
                 label,
                 value: value.clone(),
-                key: key.clone(),
+                disabled: self.widget_disabled(&key_str),
+                key: Some(key_str.clone()),
             },
             self.current_container,
         );
@@ -520,6 +2164,7 @@ impl St {
     }
 
     /// Create a color picker.
+    #[track_caller]
     pub fn color_picker(
         &mut self,
         label: impl Into<String>,
@@ -528,13 +2173,14 @@ impl St {
     ) -> String {
         let label = label.into();
         let value = value.into();
-        let key_str = key.clone().unwrap_or_else(|| format!("color_picker_{}", label));
+        let key_str = self.widget_key("color_picker", &key);
 
         self.delta_gen.add_element(
             ElementType::ColorPicker {
                 label,
                 value: value.clone(),
-                key: key.clone(),
+                disabled: self.widget_disabled(&key_str),
+                key: Some(key_str.clone()),
             },
             self.current_container,
         );
@@ -546,18 +2192,20 @@ impl St {
     }
 
     /// Create a file uploader.
+    #[track_caller]
     pub fn file_uploader(
         &mut self,
         label: impl Into<String>,
         key: Option<String>,
     ) -> Option<String> {
         let label = label.into();
-        let key_str = key.clone().unwrap_or_else(|| format!("file_uploader_{}", label));
+        let key_str = self.widget_key("file_uploader", &key);
 
         self.delta_gen.add_element(
             ElementType::FileUploader {
                 label,
-                key: key.clone(),
+                disabled: self.widget_disabled(&key_str),
+                key: Some(key_str.clone()),
             },
             self.current_container,
         );
@@ -567,7 +2215,147 @@ impl St {
             .and_then(|v| v.as_string().map(|s| s.to_string()))
     }
 
+    /// Create a light/dark/auto theme switcher.
+    ///
+    /// The choice is sent to the server as a theme preference rather than a
+    /// widget value, persisted on the session and applied with a SetTheme
+    /// message. Returns the session's current theme mode.
+    #[track_caller]
+    pub fn theme_toggle(&mut self, label: impl Into<String>, key: Option<String>) -> ThemeMode {
+        let label = label.into();
+        let key_str = self.widget_key("theme_toggle", &key);
+
+        self.delta_gen.add_element(
+            ElementType::ThemeToggle {
+                label,
+                mode: self.theme_mode.as_str().to_string(),
+                disabled: self.widget_disabled(&key_str),
+                key: Some(key_str),
+            },
+            self.current_container,
+        );
+
+        self.theme_mode
+    }
+
+    /// Create a drop zone accepting dragged or pasted files of any type.
+    #[track_caller]
+    pub fn dropzone(&mut self, label: impl Into<String>, key: Option<String>) -> Vec<String> {
+        self.dropzone_with_filter(label, Vec::<String>::new(), true, key)
+    }
+
+    /// Create a drop zone restricted to the given MIME types or extensions.
+    ///
+    /// Uploads arrive through the same widget pipeline as `file_uploader`;
+    /// values that do not match the filter are dropped. Large files are
+    /// uploaded in chunks (see [`crate::transfer`]) and arrive as media URLs
+    /// such as `/media/{id}#report.csv`.
+    #[track_caller]
+    pub fn dropzone_with_filter(
+        &mut self,
+        label: impl Into<String>,
+        accept: Vec<impl Into<String>>,
+        show_preview: bool,
+        key: Option<String>,
+    ) -> Vec<String> {
+        let label = label.into();
+        let accept: Vec<String> = accept.into_iter().map(|a| a.into()).collect();
+        let key_str = self.widget_key("dropzone", &key);
+
+        self.delta_gen.add_element(
+            ElementType::Dropzone {
+                label,
+                accept: accept.clone(),
+                allow_paste: true,
+                show_preview,
+                disabled: self.widget_disabled(&key_str),
+                key: Some(key_str.clone()),
+            },
+            self.current_container,
+        );
+
+        self.uploaded_files(&key_str)
+            .into_iter()
+            .filter(|file| {
+                if file.starts_with(media::MEDIA_URL_PREFIX) {
+                    self.media.accepts_url(file, &accept)
+                } else {
+                    media::accepts(file, &accept)
+                }
+            })
+            .collect()
+    }
+
+    /// Create a microphone recorder. The recording is uploaded to the media
+    /// store; call [`AudioInput::transcribe`] on the result for its text.
+    #[track_caller]
+    pub fn audio_input(&mut self, label: impl Into<String>, key: Option<String>) -> AudioInput<'_> {
+        let label = label.into();
+        let key = self.widget_key("audio_input", &key);
+
+        self.delta_gen.add_element(
+            ElementType::AudioInput {
+                label,
+                disabled: self.widget_disabled(&key),
+                key: key.clone(),
+            },
+            self.current_container,
+        );
+
+        let recording = self.uploaded_files(&key).pop();
+        AudioInput::new(self, recording)
+    }
+
+    /// Display the waveform of `audio`, e.g. a media URL, on which users
+    /// select time regions. Returns the selected `(start, end)` regions in
+    /// seconds, in order.
+    #[track_caller]
+    pub fn audio_waveform(&mut self, audio: impl Into<String>, key: &str) -> Vec<(f64, f64)> {
+        let key = self.widget_key("audio_waveform", &Some(key.into()));
+        let regions = self
+            .delta_gen
+            .get_widget(&key)
+            .and_then(|value| value.as_string().map(normalize_regions))
+            .unwrap_or_default();
+
+        self.delta_gen.add_element(
+            ElementType::AudioWaveform {
+                src: audio.into(),
+                regions: regions.clone(),
+                disabled: self.widget_disabled(&key),
+                key,
+            },
+            self.current_container,
+        );
+
+        regions
+    }
+
+    /// Start, or check on, the transcription of the recording at media URL
+    /// `url`. Returns `None` when the recording is no longer stored.
+    pub(crate) fn transcription(&self, url: &str) -> crate::error::Result<Option<TranscriptionStatus>> {
+        let Some(id) = media::referenced_ids(url).next() else {
+            return Ok(None);
+        };
+        let Some(audio) = self.media.get(&id) else {
+            return Ok(None);
+        };
+        self.transcriber.start(&id, audio, self.session_id).map(Some)
+    }
+
+    /// Get the values uploaded through a file widget.
+    fn uploaded_files(&self, key: &str) -> Vec<String> {
+        match self.delta_gen.get_widget(key) {
+            Some(WidgetValue::StringArray(files)) => files,
+            Some(WidgetValue::String(value)) => {
+                serde_json::from_str::<Vec<String>>(&value).unwrap_or_else(|_| vec![value])
+            }
+            _ => vec![],
+        }
+    }
+
     /// Create a radio button group.
+    #[track_caller]
     pub fn radio(
         &mut self,
         label: impl Into<String>,
@@ -577,7 +2365,7 @@ impl St {
     ) -> String {
         let label = label.into();
         let options: Vec<String> = options.into_iter().map(|o| o.into()).collect();
-        let key_str = key.clone().unwrap_or_else(|| format!("radio_{}", label));
+        let key_str = self.widget_key("radio", &key);
         let default_value = options.get(index).cloned().unwrap_or_default();
 
         self.delta_gen.add_element(
@@ -585,7 +2373,8 @@ impl St {
                 label,
                 options: options.clone(),
                 value: Some(default_value.clone()),
-                key: key.clone(),
+                disabled: self.widget_disabled(&key_str),
+                key: Some(key_str.clone()),
             },
             self.current_container,
         );
@@ -610,6 +2399,108 @@ impl St {
         );
     }
 
+    /// Display an editable grid with `headers`, starting from `rows`.
+    ///
+    /// Cells starting with `=` are [formulas](crate::formula), evaluated on
+    /// every edit. Returns the evaluated cells of the edited grid.
+    #[track_caller]
+    pub fn data_editor(
+        &mut self,
+        headers: Vec<impl Into<String>>,
+        rows: Vec<Vec<impl Into<String>>>,
+        key: &str,
+    ) -> Vec<Vec<CellValue>> {
+        let key = self.widget_key("data_editor", &Some(key.to_string()));
+        let headers: Vec<String> = headers.into_iter().map(|h| h.into()).collect();
+        let rows: Vec<Vec<String>> = rows
+            .into_iter()
+            .map(|row| row.into_iter().map(|cell| cell.into()).collect())
+            .collect();
+        // Edits are only kept while they fit the grid the script shows
+        let cells = self
+            .delta_gen
+            .get_widget(&key)
+            .and_then(|value| serde_json::from_str::<Vec<Vec<String>>>(value.as_string()?).ok())
+            .filter(|edited| {
+                edited.len() == rows.len() && edited.iter().zip(&rows).all(|(edited, row)| edited.len() == row.len())
+            })
+            .unwrap_or(rows);
+        let values = formula::evaluate(&cells);
+
+        self.delta_gen.add_element(
+            ElementType::DataEditor {
+                headers,
+                values: values
+                    .iter()
+                    .map(|row| row.iter().map(|value| value.to_string()).collect())
+                    .collect(),
+                cells,
+                disabled: self.widget_disabled(&key),
+                key,
+            },
+            self.current_container,
+        );
+
+        values
+    }
+
+    /// Display a table, formatting numeric cells in the given columns.
+    ///
+    /// Cells that don't parse as numbers are left as-is.
+    pub fn table_with_format(
+        &mut self,
+        headers: Vec<impl Into<String>>,
+        rows: Vec<Vec<impl Into<String>>>,
+        formats: Vec<(impl Into<String>, NumberFormat)>,
+    ) {
+        let headers: Vec<String> = headers.into_iter().map(|h| h.into()).collect();
+        let formats: Vec<(usize, NumberFormat)> = formats
+            .into_iter()
+            .filter_map(|(column, format)| {
+                let column = column.into();
+                headers.iter().position(|h| *h == column).map(|index| (index, format))
+            })
+            .collect();
+
+        let rows: Vec<Vec<String>> = rows
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .enumerate()
+                    .map(|(index, cell)| {
+                        let cell = cell.into();
+                        match (formats.iter().find(|(i, _)| *i == index), cell.trim().parse::<f64>()) {
+                            (Some((_, format)), Ok(number)) => self.format_number(number, format),
+                            _ => cell,
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        self.table(headers, rows);
+    }
+
+    /// Display a table with one row per struct.
+    ///
+    /// Columns and per-column number formats come from the [`Tabular`] impl.
+    pub fn table_of<T: Tabular>(&mut self, rows: &[T]) {
+        let columns = T::columns();
+        let locale = self.locale().to_string();
+        let rows: Vec<Vec<String>> = rows
+            .iter()
+            .map(|row| {
+                row.cells()
+                    .iter()
+                    .zip(&columns)
+                    .map(|(cell, column)| cell.render(column.format.as_ref(), &locale))
+                    .collect()
+            })
+            .collect();
+        let headers: Vec<String> = columns.into_iter().map(|column| column.name).collect();
+        self.table(headers, rows);
+    }
+
     /// Display a dataframe from JSON string.
     pub fn dataframe(&mut self, data: impl Into<String>) {
         let data = data.into();
@@ -619,19 +2510,314 @@ impl St {
         );
     }
 
+    /// Display a table with a download button exporting it as CSV or XLSX.
+    ///
+    /// The format is chosen from the file name extension.
+    pub fn table_with_export(
+        &mut self,
+        headers: Vec<impl Into<String>>,
+        rows: Vec<Vec<impl Into<String>>>,
+        file_name: impl Into<String>,
+    ) -> bool {
+        let headers: Vec<String> = headers.into_iter().map(|h| h.into()).collect();
+        let rows: Vec<Vec<String>> = rows
+            .into_iter()
+            .map(|row| row.into_iter().map(|cell| cell.into()).collect())
+            .collect();
+
+        // Cells given as text export as text
+        let values: Vec<Vec<serde_json::Value>> = rows
+            .iter()
+            .map(|row| row.iter().cloned().map(serde_json::Value::String).collect())
+            .collect();
+        self.table(headers.clone(), rows);
+        self.export_download(&headers, &values, file_name.into())
+    }
+
+    /// Display a dataframe with a download button exporting it as CSV or XLSX.
+    ///
+    /// Returns false without a button if the data is not a JSON records array.
+    pub fn dataframe_with_export(
+        &mut self,
+        data: impl Into<String>,
+        file_name: impl Into<String>,
+    ) -> bool {
+        let data = data.into();
+        let table = export::records_to_values(&data);
+        self.dataframe(data);
+
+        match table {
+            Some((headers, rows)) => self.export_download(&headers, &rows, file_name.into()),
+            None => false,
+        }
+    }
+
+    /// Run `query` on `conn` and display its rows as a paginated table,
+    /// with the row count, execution time and query plan.
+    ///
+    /// Given a `ttl`, read-only queries' results are cached by connection
+    /// and query text for that long, so reruns reuse them; the cache is not
+    /// invalidated by writes to the tables read. See [`crate::sql`].
+    #[track_caller]
+    pub fn sql(
+        &mut self,
+        conn: &dyn SqlConnection,
+        query: &str,
+        ttl: Option<Duration>,
+    ) -> crate::error::Result<QueryResult> {
+        self.sql_with_params(conn, query, &[], ttl)
+    }
+
+    /// Run `query` with positional `params` on `conn`, like [`St::sql`];
+    /// results are cached by the query text and parameters.
+    #[track_caller]
+    pub fn sql_with_params(
+        &mut self,
+        conn: &dyn SqlConnection,
+        query: &str,
+        params: &[serde_json::Value],
+        ttl: Option<Duration>,
+    ) -> crate::error::Result<QueryResult> {
+        let run = sql::run(conn, query, params, &self.data_cache, ttl)?;
+        let result = run.result;
+        let pages = result.pages();
+
+        let mut summary = format!("{} rows in {:.1} ms", result.rows.len(), run.elapsed_ms);
+        if run.cached {
+            summary.push_str(" (cached)");
+        }
+        self.markdown(format!("*{}*", summary));
+        let page = if pages > 1 {
+            let page = self.number_input(format!("Page (of {})", pages), 1.0, None);
+            (page.max(1.0) as usize).min(pages)
+        } else {
+            1
+        };
+        self.table(result.columns.clone(), result.page(page - 1));
+        if let Some(plan) = run.plan {
+            self.with_expander("Query plan", |st| st.code(plan, Some("text".to_string())));
+        }
+        Ok(result)
+    }
+
+    /// Display a data explorer over a JSON records array: column
+    /// statistics, a filter, a group-by and a suggested chart, with widgets
+    /// keyed under `key`.
+    ///
+    /// Returns the rows left by the filter, or `None` without an explorer if
+    /// the data is not a JSON records array.
+    #[track_caller]
+    pub fn experimental_data_explorer(&mut self, data: impl Into<String>, key: &str) -> Option<Frame> {
+        const NONE: &str = "(none)";
+        let frame = Frame::from_records(&data.into())?;
+        let key = self.widget_key("data_explorer", &Some(key.into()));
+
+        self.markdown(format!("**{} rows × {} columns**", frame.rows.len(), frame.headers.len()));
+        self.describe_frame(&frame);
+
+        let mut columns = vec![NONE.to_string()];
+        columns.extend(frame.headers.iter().cloned());
+        let filter_column = self.selectbox("Filter column", columns, 0, Some(format!("{}_filter_column", key)));
+        let filtered = if filter_column == NONE {
+            frame
+        } else {
+            let query = self.text_input("Filter (e.g. >10 or text)", "", Some(format!("{}_filter", key)));
+            frame.filter(&filter_column, &query)
+        };
+
+        let mut groups = vec![NONE.to_string()];
+        groups.extend(filtered.groupable_columns());
+        let group_by = self.selectbox("Group by", groups, 0, Some(format!("{}_group_by", key)));
+        let result = if group_by == NONE {
+            filtered.clone()
+        } else {
+            filtered.group_by(&group_by)
+        };
+        self.dataframe(result.to_records());
+
+        if let Some(chart) = result.suggest_chart() {
+            let data = result.chart_records(&chart);
+            let title = Some(format!("{} by {}", chart.y, chart.x));
+            match chart.kind {
+                ChartKind::Bar => self.bar_chart(data, title),
+                ChartKind::Line => self.line_chart(data, title),
+                ChartKind::Scatter => self.scatter_chart(data, title),
+            };
+        }
+        Some(filtered)
+    }
+
+    /// Display summary statistics of each column of a JSON records array:
+    /// type, count, missing and distinct values, and min, max and mean of
+    /// numeric columns. Only the summary is sent to the client.
+    ///
+    /// Returns `None` without a table if the data is not a JSON records
+    /// array.
+    pub fn describe(&mut self, data: impl Into<String>) -> Option<Vec<ColumnSummary>> {
+        let frame = Frame::from_records(&data.into())?;
+        Some(self.describe_frame(&frame))
+    }
+
+    fn describe_frame(&mut self, frame: &Frame) -> Vec<ColumnSummary> {
+        let summary = frame.summarize();
+        let number = |value: Option<f64>| value.map(explorer::format_number).unwrap_or_default();
+        let rows: Vec<Vec<String>> = summary
+            .iter()
+            .map(|column| {
+                vec![
+                    column.name.clone(),
+                    format!("{:?}", column.kind).to_lowercase(),
+                    column.count.to_string(),
+                    column.missing.to_string(),
+                    column.distinct.to_string(),
+                    number(column.min),
+                    number(column.max),
+                    number(column.mean),
+                ]
+            })
+            .collect();
+        self.table(vec!["column", "type", "count", "missing", "distinct", "min", "max", "mean"], rows);
+        summary
+    }
+
+    /// Display a histogram of `series` in `bins` equal-width bins, as a bar
+    /// chart of the bin counts. Only the counts are sent to the client.
+    pub fn histogram(&mut self, series: &[f64], bins: usize) -> Vec<Bin> {
+        let bins = explorer::histogram(series, bins);
+        let records: Vec<serde_json::Value> = bins
+            .iter()
+            .map(|bin| {
+                serde_json::json!({
+                    "bin": format!("{}–{}", explorer::format_number(bin.start), explorer::format_number(bin.end)),
+                    "count": bin.count,
+                })
+            })
+            .collect();
+        self.bar_chart(serde_json::Value::Array(records).to_string(), None);
+        bins
+    }
+
+    /// Display a pivot table of a JSON records array, aggregating `values`
+    /// by `rows` down and `cols` across.
+    ///
+    /// Returns the records behind the cell the user last clicked, or `None`
+    /// if no cell was clicked or the data is not a JSON records array.
+    #[track_caller]
+    pub fn pivot_table(
+        &mut self,
+        data: impl Into<String>,
+        rows: &[&str],
+        cols: &[&str],
+        values: &str,
+        agg: Aggregation,
+        key: &str,
+    ) -> Option<PivotDrill> {
+        let frame = Frame::from_records(&data.into())?;
+        let pivot = Pivot::compute(&frame, rows, cols, values, agg);
+        let data = serde_json::to_string(&pivot).unwrap_or_default();
+        let key = self.widget_key("pivot_table", &Some(key.into()));
+        self.delta_gen.add_element(
+            ElementType::PivotTable {
+                key: key.clone(),
+                data,
+            },
+            self.current_container,
+        );
+
+        let target: DrillTarget = match self.delta_gen.get_widget(&key)? {
+            WidgetValue::String(json) => serde_json::from_str(&json).ok()?,
+            _ => return None,
+        };
+        Some(PivotDrill::select(&frame, &pivot, target))
+    }
+
+    /// Display a heatmap of `matrix`, one row per y label and one column per
+    /// x label.
+    ///
+    /// Returns the cell the user last clicked, if any.
+    #[track_caller]
+    pub fn heatmap(&mut self, matrix: &[Vec<f64>], x_labels: &[&str], y_labels: &[&str], key: &str) -> Option<HeatmapCell> {
+        self.heatmap_with(matrix, x_labels, y_labels, HeatmapOptions::default(), key)
+    }
+
+    /// Display a heatmap with the color scale and range set in `options`.
+    #[track_caller]
+    pub fn heatmap_with(
+        &mut self,
+        matrix: &[Vec<f64>],
+        x_labels: &[&str],
+        y_labels: &[&str],
+        options: HeatmapOptions,
+        key: &str,
+    ) -> Option<HeatmapCell> {
+        let labels = |labels: &[&str]| labels.iter().map(|label| label.to_string()).collect();
+        let data = HeatmapData::new(heatmap::finite_values(matrix), labels(x_labels), labels(y_labels), &options);
+        self.show_heatmap(data, options.title, key)
+    }
+
+    /// Display the correlations between the numeric columns of a JSON
+    /// records array as a heatmap on a diverging scale from -1 to 1.
+    ///
+    /// Returns the pair of columns the user last clicked, or `None` if no
+    /// cell was clicked or the data is not a JSON records array.
+    #[track_caller]
+    pub fn corr_matrix(&mut self, data: impl Into<String>, key: &str) -> Option<HeatmapCell> {
+        let frame = Frame::from_records(&data.into())?;
+        let (names, matrix) = heatmap::correlation_matrix(&frame);
+        let options = HeatmapOptions::new()
+            .scale(heatmap::ColorScale::Diverging)
+            .range(-1.0, 1.0)
+            .show_values();
+        let data = HeatmapData::new(matrix, names.clone(), names, &options);
+        self.show_heatmap(data, options.title, key)
+    }
+
+    #[track_caller]
+    fn show_heatmap(&mut self, data: HeatmapData, title: Option<String>, key: &str) -> Option<HeatmapCell> {
+        let key = self.widget_key("heatmap", &Some(key.into()));
+        self.delta_gen.add_element(
+            ElementType::Heatmap {
+                key: key.clone(),
+                data: serde_json::to_string(&data).unwrap_or_default(),
+                title,
+            },
+            self.current_container,
+        );
+
+        let click: CellClick = match self.delta_gen.get_widget(&key)? {
+            WidgetValue::String(json) => serde_json::from_str(&json).ok()?,
+            _ => return None,
+        };
+        data.cell(click.row, click.col)
+    }
+
+    fn export_download(&mut self, headers: &[String], rows: &[Vec<serde_json::Value>], file_name: String) -> bool {
+        let format = ExportFormat::from_file_name(&file_name);
+        let bytes = export::table_bytes(headers, rows, format);
+        self.download_button(
+            format!("Download {}", file_name),
+            bytes,
+            file_name.clone(),
+            format.mime_type(),
+            Some(format!("export_{}", file_name)),
+        )
+    }
+
     /// Create a camera input.
+    #[track_caller]
     pub fn camera_input(
         &mut self,
         label: impl Into<String>,
         key: Option<String>,
     ) -> Option<String> {
         let label = label.into();
-        let key_str = key.clone().unwrap_or_else(|| format!("camera_{}", label));
+        let key_str = self.widget_key("camera", &key);
 
         self.delta_gen.add_element(
             ElementType::CameraInput {
                 label,
-                key: key.clone(),
+                disabled: self.widget_disabled(&key_str),
+                key: Some(key_str.clone()),
             },
             self.current_container,
         );
@@ -647,9 +2833,9 @@ impl St {
         data: impl Into<String>,
         title: Option<String>,
     ) -> ElementId {
-        let data = data.into();
+        let data = self.budgeted_chart_data(data.into());
         self.delta_gen.add_element(
-            ElementType::LineChart { data, title },
+            ElementType::LineChart { data, title, key: None },
             self.current_container,
         )
     }
@@ -660,9 +2846,9 @@ impl St {
         data: impl Into<String>,
         title: Option<String>,
     ) -> ElementId {
-        let data = data.into();
+        let data = self.budgeted_chart_data(data.into());
         self.delta_gen.add_element(
-            ElementType::BarChart { data, title },
+            ElementType::BarChart { data, title, key: None },
             self.current_container,
         )
     }
@@ -673,9 +2859,9 @@ impl St {
         data: impl Into<String>,
         title: Option<String>,
     ) -> ElementId {
-        let data = data.into();
+        let data = self.budgeted_chart_data(data.into());
         self.delta_gen.add_element(
-            ElementType::AreaChart { data, title },
+            ElementType::AreaChart { data, title, key: None },
             self.current_container,
         )
     }
@@ -686,9 +2872,9 @@ impl St {
         data: impl Into<String>,
         title: Option<String>,
     ) -> ElementId {
-        let data = data.into();
+        let data = self.budgeted_chart_data(data.into());
         self.delta_gen.add_element(
-            ElementType::ScatterChart { data, title },
+            ElementType::ScatterChart { data, title, key: None },
             self.current_container,
         )
     }
@@ -698,10 +2884,66 @@ impl St {
         &mut self,
         data: impl Into<String>,
         title: Option<String>,
-    ) -> ElementId {
-        let data = data.into();
+    ) -> ElementId {
+        let data = data.into();
+        self.delta_gen.add_element(
+            ElementType::PieChart { data, title, key: None },
+            self.current_container,
+        )
+    }
+
+    /// Display a line chart of the stream `key`, keeping its newest
+    /// `DEFAULT_STREAM_POINTS` points across reruns. Points appended to the
+    /// returned handle are sent on their own, not with the whole series.
+    pub fn line_chart_stream(&mut self, key: impl Into<String>) -> ChartStream {
+        self.line_chart_stream_with_capacity(key, chart_stream::DEFAULT_STREAM_POINTS)
+    }
+
+    /// Display a streamed line chart keeping its newest `capacity` points.
+    pub fn line_chart_stream_with_capacity(&mut self, key: impl Into<String>, capacity: usize) -> ChartStream {
+        let key = key.into();
+        let id = self.delta_gen.add_element(
+            ElementType::LineChartStream {
+                key: key.clone(),
+                capacity,
+            },
+            self.current_container,
+        );
+        self.chart_streams.open(&key, capacity, id, self.delta_gen.clone())
+    }
+
+    /// Display a chart whose clicks, selections and zooms are reported under
+    /// `key`. Returns the latest one, as `st.chart_selection(key)` does.
+    pub fn chart(
+        &mut self,
+        kind: typed::ChartKind,
+        data: impl Into<String>,
+        title: Option<String>,
+        key: &str,
+    ) -> Option<ChartSelection> {
+        let data = match kind {
+            typed::ChartKind::Pie => data.into(),
+            _ => self.budgeted_chart_data(data.into()),
+        };
+        self.delta_gen.add_element(
+            kind.keyed_element(data, title, Some(key.to_string())),
+            self.current_container,
+        );
+        self.chart_selection(key)
+    }
+
+    /// Display a candlestick chart of OHLC `candles`.
+    pub fn candlestick_chart(&mut self, candles: &[Candle]) -> ElementId {
+        self.candlestick_chart_with(candles, CandlestickOptions::default())
+    }
+
+    /// Display a candlestick chart with moving average overlays and a
+    /// volume pane as set in `options`.
+    pub fn candlestick_chart_with(&mut self, candles: &[Candle], options: CandlestickOptions) -> ElementId {
+        let data = CandlestickData::new(candles, &options);
+        let data = serde_json::to_string(&data).unwrap_or_else(|_| "{}".to_string());
         self.delta_gen.add_element(
-            ElementType::PieChart { data, title },
+            ElementType::CandlestickChart { data, title: options.title, key: options.key },
             self.current_container,
         )
     }
@@ -733,10 +2975,66 @@ impl St {
         )
     }
 
+    /// Create a download button serving the given bytes.
+    #[track_caller]
+    pub fn download_button(
+        &mut self,
+        label: impl Into<String>,
+        data: Vec<u8>,
+        file_name: impl Into<String>,
+        mime_type: impl Into<String>,
+        key: Option<String>,
+    ) -> bool {
+        let label = label.into();
+        let file_name = file_name.into();
+        let mime_type = mime_type.into();
+        let key_str = self.widget_key("download_button", &key);
+
+        let media_id = self.media.add(MediaFile::new(file_name.clone(), mime_type.clone(), data));
+
+        self.delta_gen.add_element(
+            ElementType::DownloadButton {
+                label,
+                file_name,
+                mime_type,
+                url: MediaStore::url_for(&media_id),
+                disabled: self.widget_disabled(&key_str),
+                key: Some(key_str.clone()),
+            },
+            self.current_container,
+        );
+
+        self.delta_gen
+            .get_widget(&key_str)
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// Export the current page to PDF and offer it as a download.
+    pub fn export_pdf(&mut self, file_name: impl Into<String>) -> bool {
+        let file_name = file_name.into();
+
+        let elements = render::page_elements(&self.delta_gen.peek_deltas());
+        let bytes = pdf::render_pdf(&elements);
+
+        self.download_button(
+            format!("Download {}", file_name),
+            bytes,
+            file_name.clone(),
+            "application/pdf",
+            Some(format!("export_pdf_{}", file_name)),
+        )
+    }
+
     /// Get all deltas.
     pub fn take_deltas(&self) -> Vec<platypus_core::state::Delta> {
         self.delta_gen.take_deltas()
     }
+
+    /// Remove and return the deltas the run generated so far.
+    pub fn drain_deltas(&mut self) -> Vec<platypus_core::state::Delta> {
+        self.delta_gen.drain_deltas()
+    }
 }
 
 impl Default for St {
@@ -745,29 +3043,158 @@ impl Default for St {
     }
 }
 
+/// Widget keys used by one run.
+#[derive(Debug, Default)]
+struct WidgetKeys {
+    /// Call site of each explicit key
+    sites: HashMap<String, &'static Location<'static>>,
+    /// Times each generated key's call site ran
+    occurrences: HashMap<String, usize>,
+}
+
 /// A container for organizing elements.
 pub struct Container {
     id: ElementId,
-    delta_gen: DeltaGenerator,
+    context: St,
 }
 
 impl Container {
     /// Create a new container.
     pub fn new(id: ElementId, delta_gen: DeltaGenerator) -> Self {
-        Container { id, delta_gen }
+        Container {
+            id,
+            context: St::with_delta_gen(delta_gen),
+        }
     }
 
     /// Get a mutable St context for this container.
     pub fn st(&self) -> St {
-        let mut st = St::with_delta_gen(self.delta_gen.clone());
+        let mut st = self.context.clone();
         st.current_container = Some(self.id);
         st
     }
+
+    /// The container's element ID.
+    pub fn id(&self) -> ElementId {
+        self.id
+    }
+
+    /// Remove the elements written into this container so far.
+    ///
+    /// The container stays in place; later writes fill it again.
+    pub fn clear(&self) {
+        self.context.delta_gen.clear_container(self.id);
+    }
+
+    /// Hide or show the container.
+    ///
+    /// Unlike leaving its elements out of the run, hiding keeps them on the
+    /// client, with their scroll positions and unsent input, for when the
+    /// container is shown again.
+    pub fn set_hidden(&self, hidden: bool) {
+        let delta_gen = &self.context.delta_gen;
+        if let Some(element) = delta_gen.get_element(self.id) {
+            let mut element = element.element_type().clone();
+            if element.set_hidden(hidden) {
+                delta_gen.update_element(self.id, element);
+            }
+        }
+    }
+}
+
+/// A fieldset, and the summary of its fields' validation errors.
+pub struct Fieldset {
+    container: Container,
+    summary: ElementId,
+}
+
+impl Fieldset {
+    /// Get a mutable St context for this fieldset.
+    pub fn st(&self) -> St {
+        self.container.st()
+    }
+
+    /// The fieldset's element ID.
+    pub fn id(&self) -> ElementId {
+        self.container.id()
+    }
+
+    /// Hide or show the fieldset, keeping its fields on the client.
+    pub fn set_hidden(&self, hidden: bool) {
+        self.container.set_hidden(hidden);
+    }
+
+    /// Show the validation errors of the fields added so far in the
+    /// fieldset's summary, and return them.
+    pub fn summarize(&self) -> Vec<String> {
+        let delta_gen = &self.container.context.delta_gen;
+        let errors = form::validation_errors(&delta_gen.peek_deltas(), self.id());
+        if !errors.is_empty() {
+            delta_gen.update_element(self.summary, ElementType::ValidationSummary { errors: errors.clone() });
+        }
+        errors
+    }
+}
+
+/// Get the message a panic was raised with.
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "panicked".to_string()
+    }
+}
+
+/// Read submitted `[[start, end], ...]` regions, ordering each region's
+/// ends and the regions by start, clamping starts before 0 to 0 and
+/// dropping regions left empty.
+fn normalize_regions(json: &str) -> Vec<(f64, f64)> {
+    let mut regions: Vec<(f64, f64)> = serde_json::from_str::<Vec<(f64, f64)>>(json)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(start, end)| (start.min(end).max(0.0), start.max(end)))
+        .filter(|(start, end)| start.is_finite() && end.is_finite() && start < end)
+        .collect();
+    regions.sort_by(|a, b| a.0.total_cmp(&b.0));
+    regions
+}
+
+/// Clean up submitted tags, returning the kept tags and why any were dropped.
+fn normalize_tags(
+    submitted: Vec<String>,
+    suggestions: &[String],
+    max_tags: Option<usize>,
+    allow_custom: bool,
+) -> (Vec<String>, Option<String>) {
+    let mut tags: Vec<String> = vec![];
+    let mut error = None;
+
+    for tag in submitted {
+        let tag = tag.trim();
+        if tag.is_empty() || tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            continue;
+        }
+        if !allow_custom && !suggestions.iter().any(|s| s == tag) {
+            error = Some(format!("\"{}\" is not an allowed tag", tag));
+            continue;
+        }
+        if max_tags.is_some_and(|max| tags.len() >= max) {
+            error = Some(format!("At most {} tags allowed", tags.len()));
+            break;
+        }
+        tags.push(tag.to_string());
+    }
+
+    (tags, error)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use platypus_core::state::Delta;
+    use crate::annotation::Shape;
 
     #[test]
     fn test_st_write() {
@@ -797,6 +3224,397 @@ mod tests {
         assert_eq!(value, "John");
     }
 
+    #[test]
+    fn test_st_rate_limit() {
+        let mut st = St::new();
+        st.rate_limit("volume", RateLimit::debounce(500));
+        st.slider("Volume", 0.0, 10.0, 5.0, Some("volume".to_string()));
+        st.slider("Pan", -1.0, 1.0, 0.0, Some("pan".to_string()));
+
+        let limits: Vec<_> = st
+            .take_deltas()
+            .into_iter()
+            .filter_map(|delta| match delta {
+                Delta::AddElement { element: ElementType::Slider { rate_limit, .. }, .. } => Some(rate_limit),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(limits, vec![RateLimit::debounce(500), SLIDER_RATE_LIMIT]);
+    }
+
+    #[test]
+    fn test_st_disabled() {
+        let mut st = St::new();
+        st.disable_all(true);
+        st.disabled("name", false);
+        st.text_input("Name", "", Some("name".to_string()));
+        st.checkbox("Agree", false, Some("agree".to_string()));
+        st.code_editor_builder("Query", "sql").key("query").show();
+
+        let disabled: Vec<bool> = st
+            .take_deltas()
+            .iter()
+            .filter_map(|delta| match delta {
+                Delta::AddElement { element: ElementType::TextInput { disabled, .. }, .. }
+                | Delta::AddElement { element: ElementType::Checkbox { disabled, .. }, .. }
+                | Delta::AddElement { element: ElementType::CodeEditor { disabled, .. }, .. } => Some(*disabled),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(disabled, vec![false, true, true]);
+    }
+
+    #[test]
+    fn test_st_echo() {
+        let mut st = St::new();
+        st.echo("volume", "Volume: {}%", 40);
+        match &st.take_deltas()[0] {
+            Delta::AddElement { element: ElementType::Echo { key, template, value }, .. } => {
+                assert_eq!(key, "volume");
+                assert_eq!(template, "Volume: {}%");
+                assert_eq!(value, "40");
+            }
+            other => panic!("unexpected delta: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_st_export_pdf() {
+        let mut st = St::new();
+        st.title("Report");
+        let columns = st.columns(2);
+        columns[1].st().write("Second");
+        columns[0].st().write("First");
+        st.write("Hello");
+        st.export_pdf("report.pdf");
+
+        assert_eq!(st.media().len(), 1);
+        let (_, element) = st.delta_gen.elements().into_iter().max_by_key(|(id, _)| id.inner()).unwrap();
+        match element {
+            ElementType::DownloadButton { file_name, mime_type, url, .. } => {
+                assert_eq!(file_name, "report.pdf");
+                assert_eq!(mime_type, "application/pdf");
+                let media_id = url.trim_start_matches("/media/");
+                let pdf = String::from_utf8(st.media().get(media_id).unwrap().data.to_vec()).unwrap();
+                assert!(pdf.starts_with("%PDF"));
+                // Columns print in declaration order, not write order
+                assert!(pdf.find("(First)").unwrap() < pdf.find("(Second)").unwrap());
+            }
+            other => panic!("unexpected element: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_st_table_with_export() {
+        let mut st = St::new();
+        st.table_with_export(vec!["a"], vec![vec!["1"]], "data.xlsx");
+        st.dataframe_with_export(r#"[{"a": 1}]"#, "data.csv");
+        st.dataframe_with_export("not json", "bad.csv");
+
+        assert_eq!(st.take_deltas().len(), 5);
+        assert_eq!(st.media().len(), 2);
+    }
+
+    #[test]
+    fn test_st_dropzone() {
+        let mut st = St::new();
+        assert!(st.dropzone("Drop files", Some("drop".to_string())).is_empty());
+
+        st.delta_gen.set_widget(
+            "drop".to_string(),
+            WidgetValue::String(r#"["data:image/png;base64,AA", "notes.txt"]"#.to_string()),
+        );
+        let images = st.dropzone_with_filter("Drop files", vec!["image/*"], true, Some("drop".to_string()));
+        assert_eq!(images, vec!["data:image/png;base64,AA".to_string()]);
+    }
+
+    #[test]
+    fn test_st_client_info() {
+        let info = ClientInfo {
+            viewport_width: 390,
+            timezone: "Europe/Paris".to_string(),
+            ..Default::default()
+        };
+        let mut st = St::new().with_client_info(info);
+        assert_eq!(st.client_info().viewport_width, 390);
+
+        let inner = st.container().st();
+        assert_eq!(inner.client_info().timezone, "Europe/Paris");
+    }
+
+    #[test]
+    fn test_st_responsive_container() {
+        let mut st = St::new().with_client_info(ClientInfo {
+            viewport_width: 400,
+            ..Default::default()
+        });
+        assert_eq!(st.breakpoint(), "mobile");
+
+        let stacked = Layout {
+            display: "flex".to_string(),
+            flex_direction: Some("column".to_string()),
+            width: None,
+            height: None,
+        };
+        let container = st.responsive_container(vec![("mobile", stacked)]);
+        container.st().write("inside");
+        assert_eq!(st.take_deltas().len(), 2);
+    }
+
+    #[test]
+    fn test_st_text_input_kind() {
+        let mut st = St::new();
+        st.delta_gen()
+            .set_widget("email".to_string(), WidgetValue::String("not-an-email".to_string()));
+        let email = st.text_input_with_kind("Email", "", TextInputKind::Email, Some("email".to_string()));
+        assert_eq!(email, "");
+
+        match &st.take_deltas()[0] {
+            Delta::AddElement { element: ElementType::TextInput { kind, error, .. }, .. } => {
+                assert_eq!(*kind, TextInputKind::Email);
+                assert!(error.is_some());
+            }
+            other => panic!("unexpected delta: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_st_hidden_container() {
+        let mut st = St::new();
+        st.with_visible_container(false, |st| st.text_input("Notes", "", Some("notes".to_string())));
+        let container = st.container();
+        container.set_hidden(true);
+        container.set_hidden(false);
+
+        let hidden: Vec<bool> = st
+            .take_deltas()
+            .iter()
+            .filter_map(|delta| match delta {
+                Delta::AddElement { element: ElementType::Container { hidden, .. }, .. }
+                | Delta::UpdateElement { element: ElementType::Container { hidden, .. }, .. } => Some(*hidden),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(hidden, vec![true, false, true, false]);
+    }
+
+    #[test]
+    fn test_st_fieldset_summary() {
+        let mut st = St::new();
+        st.delta_gen()
+            .set_widget("email".to_string(), WidgetValue::String("not-an-email".to_string()));
+        st.with_fieldset("Contact", |st| {
+            st.text_input("Name", "Ada", Some("name".to_string()));
+            st.with_container(|st| {
+                st.text_input_with_kind("Email", "", TextInputKind::Email, Some("email".to_string()));
+            });
+        });
+        st.text_input_with_kind("Backup email", "", TextInputKind::Email, Some("email".to_string()));
+
+        let summaries: Vec<Vec<String>> = st
+            .take_deltas()
+            .into_iter()
+            .filter_map(|delta| match delta {
+                Delta::UpdateElement { element: ElementType::ValidationSummary { errors }, .. } => Some(errors),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].len(), 1);
+        assert!(summaries[0][0].starts_with("Email: "));
+    }
+
+    #[test]
+    fn test_st_number_formatting() {
+        let mut st = St::new().with_client_info(ClientInfo {
+            locale: "de-DE".to_string(),
+            ..Default::default()
+        });
+        st.metric_number("Revenue", 1234.5, Some(10.0), &NumberFormat::currency("EUR"));
+        st.table_with_format(vec!["name", "share"], vec![vec!["a", "0.5"]], vec![("share", NumberFormat::percent())]);
+
+        let deltas = st.take_deltas();
+        match &deltas[0] {
+            Delta::AddElement { element: ElementType::Metric { value, delta, .. }, .. } => {
+                assert_eq!(value, "1.234,50\u{a0}€");
+                assert_eq!(delta.as_deref(), Some("+10,00\u{a0}€"));
+            }
+            other => panic!("unexpected delta: {:?}", other),
+        }
+        match &deltas[1] {
+            Delta::AddElement { element: ElementType::Table { rows, .. }, .. } => {
+                assert_eq!(rows[0], vec!["a", "50%"]);
+            }
+            other => panic!("unexpected delta: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_st_parses_locale_input() {
+        let mut st = St::new().with_client_info(ClientInfo {
+            locale: "de-DE".to_string(),
+            ..Default::default()
+        });
+        st.delta_gen().set_widget("price".to_string(), WidgetValue::String("1.234,5".to_string()));
+        st.delta_gen().set_widget("due".to_string(), WidgetValue::String("31.12.2024".to_string()));
+        assert_eq!(st.number_input("Price", 0.0, Some("price".to_string())), 1234.5);
+        assert_eq!(st.date_input("Due", "2024-01-01", Some("due".to_string())), "2024-12-31");
+    }
+
+    #[test]
+    fn test_st_tags_input() {
+        let mut st = St::new();
+        st.delta_gen().set_widget(
+            "tags".to_string(),
+            WidgetValue::StringArray(vec!["rust".into(), " Rust ".into(), "go".into(), "zig".into()]),
+        );
+        let tags = st.tags_input_with_options("Tags", vec!["rust", "go"], Some(3), false, Some("tags".to_string()));
+        assert_eq!(tags, vec!["rust", "go"]);
+    }
+
+    #[test]
+    fn test_normalize_regions() {
+        assert_eq!(
+            normalize_regions("[[4.5, 2.0], [0.5, 1.0], [3.0, 3.0], [-2.0, 1.5]]"),
+            vec![(0.0, 1.5), (0.5, 1.0), (2.0, 4.5)]
+        );
+        assert!(normalize_regions("not json").is_empty());
+    }
+
+    #[test]
+    fn test_st_data_editor_evaluates_edits() {
+        let mut st = St::new();
+        let rows = vec![vec!["Rent", "1200"], vec!["Total", "=B1"]];
+        assert_eq!(st.data_editor(vec!["Item", "Amount"], rows.clone(), "budget")[1][1], CellValue::Number(1200.0));
+
+        let mut st = St::new();
+        let edited = r#"[["Rent", "900"], ["Total", "=B1*2"]]"#;
+        st.delta_gen().set_widget("budget".to_string(), WidgetValue::String(edited.to_string()));
+        assert_eq!(st.data_editor(vec!["Item", "Amount"], rows, "budget")[1][1], CellValue::Number(1800.0));
+        let Some(Delta::AddElement { element: ElementType::DataEditor { cells, values, .. }, .. }) =
+            st.drain_deltas().pop()
+        else {
+            panic!("expected a data editor");
+        };
+        assert_eq!(cells[1][1], "=B1*2");
+        assert_eq!(values[1], vec!["Total", "1800"]);
+    }
+
+    #[test]
+    fn test_st_image_annotator_reads_typed_annotations() {
+        let drawn = vec![
+            Annotation { label: Some("cat".into()), shape: Shape::Box { x: 9.0, y: 5.0, width: -4.0, height: 2.0 } },
+            Annotation { label: Some("fox".into()), shape: Shape::Box { x: 0.0, y: 0.0, width: 0.0, height: 1.0 } },
+        ];
+        let mut st = St::new();
+        st.delta_gen().set_widget("pets".to_string(), WidgetValue::Annotations(drawn));
+
+        let expected = vec![Annotation {
+            label: Some("cat".into()),
+            shape: Shape::Box { x: 5.0, y: 5.0, width: 4.0, height: 2.0 },
+        }];
+        assert_eq!(st.image_annotator_with_labels("/media/pets.jpg", &["cat"], "pets"), expected);
+        let Some(Delta::AddElement { element: ElementType::ImageAnnotator { annotations, .. }, .. }) =
+            st.drain_deltas().pop()
+        else {
+            panic!("expected an image annotator");
+        };
+        assert_eq!(annotations, expected);
+    }
+
+    #[test]
+    fn test_normalize_tags_limit() {
+        let submitted = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let (tags, error) = normalize_tags(submitted, &[], Some(2), true);
+        assert_eq!(tags, vec!["a", "b"]);
+        assert_eq!(error.as_deref(), Some("At most 2 tags allowed"));
+    }
+
+    #[test]
+    fn test_st_date_range_input() {
+        let mut st = St::new();
+        let day = |d| NaiveDate::from_ymd_opt(2024, 5, d).unwrap();
+        st.delta_gen().set_widget("range".to_string(), WidgetValue::DateRange(day(20), day(2)));
+
+        let range = st.date_range_input("Range", (day(1), day(7)), Some(day(5)), None, Some("range".to_string()));
+        assert_eq!(range, (day(5), day(20)));
+    }
+
+    #[test]
+    fn test_st_datetime_input_uses_client_timezone() {
+        let mut st = St::new().with_client_info(ClientInfo {
+            timezone: "Europe/Paris".to_string(),
+            ..Default::default()
+        });
+        st.delta_gen().set_widget("when".to_string(), WidgetValue::String("2024-01-15T10:00".to_string()));
+
+        let default = Utc::now();
+        let when = st.datetime_input("When", default, None, None, Some("when".to_string()));
+        assert_eq!(when.to_rfc3339(), "2024-01-15T09:00:00+00:00");
+    }
+
+    #[test]
+    fn test_st_feedback_and_rating() {
+        let mut st = St::new();
+        assert_eq!(st.feedback(FeedbackKind::Thumbs, Some("thumbs".to_string())), None);
+
+        st.delta_gen().set_widget("thumbs".to_string(), WidgetValue::Number(1.0));
+        st.delta_gen().set_widget("stars".to_string(), WidgetValue::Number(7.0));
+        assert_eq!(st.feedback(FeedbackKind::Thumbs, Some("thumbs".to_string())), Some(1));
+        assert_eq!(st.rating(5, Some("stars".to_string())), None);
+    }
+
+    #[test]
+    fn test_st_json_editor_rejects_schema_violations() {
+        let mut st = St::new();
+        let schema = serde_json::json!({"type": "object", "required": ["name"]});
+        let default = serde_json::json!({"name": "app"});
+        st.delta_gen().set_widget("config".to_string(), WidgetValue::String("{}".to_string()));
+
+        let value = st.json_editor(default.clone(), Some(schema), Some("config".to_string()));
+        assert_eq!(value, default);
+    }
+
+    #[test]
+    fn test_st_rich_text_sanitizes_and_limits() {
+        let mut st = St::new();
+        st.delta_gen().set_widget(
+            "notes".to_string(),
+            WidgetValue::RichText { html: "<p>Hi<script>x()</script></p>".to_string() },
+        );
+        assert_eq!(st.rich_text("Notes", Some("notes".to_string())).as_html(), "<p>Hi</p>");
+        assert!(st.rich_text_with_limit("Notes", 8, Some("notes".to_string())).is_empty());
+    }
+
+    #[test]
+    fn test_st_effects() {
+        let mut st = St::new();
+        st.balloons();
+        st.container().st().snow();
+        assert_eq!(
+            st.actions().take(),
+            vec![Action::Effect(Effect::Balloons), Action::Effect(Effect::Snow)]
+        );
+        assert_eq!(st.take_deltas().len(), 1);
+    }
+
+    #[test]
+    fn test_st_notify() {
+        let mut st = St::new();
+        st.notify(NotificationLevel::Info, "first");
+        st.notify(NotificationLevel::Warning, "second");
+        assert_eq!(st.notifications().unread_count(), 2);
+
+        let deltas = st.take_deltas();
+        assert_eq!(deltas.len(), 2);
+        match &deltas[1] {
+            Delta::UpdateElement { element: ElementType::NotificationCenter { notifications, .. }, .. } => {
+                assert_eq!(notifications.len(), 2);
+            }
+            other => panic!("unexpected delta: {:?}", other),
+        }
+    }
+
     #[test]
     fn test_st_deltas() {
         let mut st = St::new();
@@ -804,4 +3622,199 @@ mod tests {
         let deltas = st.take_deltas();
         assert_eq!(deltas.len(), 1);
     }
+
+    #[test]
+    fn test_duplicate_widget_keys_are_reported() {
+        let mut st = St::new();
+        st.checkbox("Agree", false, Some("agree".to_string()));
+        st.checkbox("Agree again", false, Some("agree".to_string()));
+        for _ in 0..2 {
+            st.button("Go", Some("go".to_string()));
+        }
+
+        let errors: Vec<String> = st
+            .take_deltas()
+            .into_iter()
+            .filter_map(|delta| match delta {
+                Delta::AddElement { element: ElementType::Error { message }, .. } => Some(message),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].starts_with("Duplicate widget key `agree`: used at src/platypus-runtime/src/context.rs:"));
+        assert!(errors[0].contains("and again at"));
+        assert!(errors[1].contains("used more than once at"));
+    }
+
+    #[test]
+    fn test_keyed_views_claim_keys() {
+        let mut st = St::new();
+        let records = r#"[{"region": "north", "sales": 3}, {"region": "south", "sales": 5}]"#;
+        st.image_annotator("/media/pets.jpg", "shared");
+        st.heatmap(&[vec![1.0]], &["x"], &["y"], "shared");
+        st.pivot_table(records, &["region"], &[], "sales", Aggregation::Sum, "pivot");
+        st.corr_matrix(records, "pivot");
+        st.experimental_data_explorer(records, "explore");
+        st.lazy("explore", |st| st.write("Appendix"));
+
+        let errors: Vec<String> = st
+            .drain_deltas()
+            .into_iter()
+            .filter_map(|delta| match delta {
+                Delta::AddElement { element: ElementType::Error { message }, .. } => Some(message),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(errors.len(), 3);
+        assert!(errors[0].starts_with("Duplicate widget key `shared`"));
+        assert!(errors[1].starts_with("Duplicate widget key `pivot`"));
+        assert!(errors[2].starts_with("Duplicate widget key `explore`"));
+    }
+
+    #[test]
+    fn test_editor_keys_are_claimed() {
+        let mut st = St::new();
+        st.code_editor("Code", "rust", Some("source".to_string()));
+        st.json_editor(serde_json::json!({}), None, Some("source".to_string()));
+        st.log_viewer("logs");
+        st.app_logs();
+        st.app_logs();
+
+        let errors = st
+            .drain_deltas()
+            .into_iter()
+            .filter(|delta| matches!(delta, Delta::AddElement { element: ElementType::Error { .. }, .. }))
+            .count();
+        assert_eq!(errors, 2);
+    }
+
+    #[test]
+    fn test_generated_keys_follow_call_site_and_container() {
+        fn page(st: &mut St) -> Vec<String> {
+            let columns = st.columns(2);
+            for column in &columns {
+                column.st().text_input("Name", "", None);
+            }
+            for _ in 0..2 {
+                st.text_input("Name", "", None);
+            }
+            st.take_deltas()
+                .into_iter()
+                .filter_map(|delta| match delta {
+                    Delta::AddElement { element: ElementType::TextInput { key, .. }, .. } => key,
+                    _ => None,
+                })
+                .collect()
+        }
+
+        let keys = page(&mut St::new());
+        assert_eq!(keys.len(), 4);
+        assert_eq!(keys.iter().collect::<HashSet<_>>().len(), 4);
+        assert!(keys[0].starts_with("text_input@columns@src/platypus-runtime/src/context.rs:"));
+        assert!(keys[0].contains("[0]/") && keys[1].contains("[1]/"));
+        assert!(keys[3].ends_with("#2"));
+        // The next run generates the same keys
+        assert_eq!(page(&mut St::new()), keys);
+    }
+
+    #[test]
+    fn test_closure_scoped_containers() {
+        let mut st = St::new();
+        let total = st.with_columns(2, |columns| {
+            columns[1].write("Right");
+            columns[0].write("Left");
+            columns.len()
+        });
+        assert_eq!(total, 2);
+        st.with_expander("More", |st| {
+            st.with_container(|st| st.text_input("Name", "", None));
+        });
+        st.write("After");
+
+        let deltas = st.take_deltas();
+        let parents: HashMap<ElementId, Option<ElementId>> = deltas
+            .iter()
+            .filter_map(|delta| match delta {
+                Delta::AddElement { id, parent_id, .. } => Some((*id, *parent_id)),
+                _ => None,
+            })
+            .collect();
+        let texts: Vec<(&str, Option<ElementId>)> = deltas
+            .iter()
+            .filter_map(|delta| match delta {
+                Delta::AddElement { element: ElementType::Text { value }, parent_id, .. } => Some((value.as_str(), *parent_id)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(texts.iter().map(|text| text.0).collect::<Vec<_>>(), vec!["Left", "Right", "After"]);
+        assert!(texts[0].1.is_some() && texts[0].1 != texts[1].1);
+        assert_eq!(texts[2].1, None);
+
+        // The input sits two containers deep, under the expander
+        let (input, key) = deltas
+            .iter()
+            .find_map(|delta| match delta {
+                Delta::AddElement { id, element: ElementType::TextInput { key, .. }, .. } => Some((*id, key.clone().unwrap())),
+                _ => None,
+            })
+            .unwrap();
+        let container = parents[&input].unwrap();
+        assert!(parents[&container].is_some_and(|expander| parents[&expander].is_none()));
+        assert!(key.starts_with("text_input@expander@") && key.contains("/container@"));
+    }
+
+    #[test]
+    fn test_remove_and_clear() {
+        let mut st = St::new();
+        let notice = st.write("Saving");
+        let section = st.container();
+        section.st().write("Old");
+        section.clear();
+        section.st().write("New");
+        st.remove(notice);
+
+        let deltas = st.take_deltas();
+        assert!(matches!(deltas.last(), Some(Delta::RemoveElement { id }) if *id == notice));
+        assert!(st.delta_gen().get_element(notice).is_none());
+        let clear = deltas
+            .iter()
+            .position(|delta| matches!(delta, Delta::ClearContainer { id } if *id == section.id()))
+            .unwrap();
+        assert!(matches!(&deltas[clear - 1], Delta::AddElement { element: ElementType::Text { value }, .. } if value == "Old"));
+        assert!(matches!(&deltas[clear + 1], Delta::AddElement { element: ElementType::Text { value }, .. } if value == "New"));
+    }
+
+    #[test]
+    fn test_error_boundaries() {
+        use platypus_core::elements::HeadingElement;
+
+        let mut st = St::new();
+        st.write("Before");
+        let result = st.boundary("Chart", |st| {
+            st.with_container(|st| st.write("Lost"));
+            panic!("no data");
+        });
+        assert!(result.is_none());
+        assert_eq!(st.boundary("Total", |_| 42), Some(42));
+        let empty = HeadingElement::new(ElementId::new(1), "", 2).unwrap();
+        assert!(st.element(&empty).is_err());
+        st.write("After");
+
+        let page: Vec<String> = st
+            .take_deltas()
+            .into_iter()
+            .filter_map(|delta| match delta {
+                Delta::AddElement { element: ElementType::Text { value }, .. } => Some(value),
+                Delta::AddElement { element: ElementType::Error { message }, .. } => Some(message),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(page, vec![
+            "Before".to_string(),
+            "Chart failed to render: no data".to_string(),
+            "heading_2 failed to render: Invalid content: must not be empty".to_string(),
+            "After".to_string(),
+        ]);
+        assert_eq!(st.element_failures(), page[1..3].to_vec());
+    }
 }
@@ -0,0 +1,292 @@
+//! Conversation history for chat apps.
+//!
+//! A [`Conversation`] holds the messages of one chat, kept in the session's
+//! [`ConversationStore`] across reruns and saved into the session's state,
+//! so it is part of session snapshots:
+//!
+//! ```
+//! use platypus_runtime::St;
+//!
+//! let mut st = St::new();
+//! let mut conv = st.conversation("support");
+//! conv.push_user("How do I reset my password?");
+//! conv.push_assistant("Open Settings and pick \"Reset password\".");
+//! // Keep the prompt within the model's context window
+//! conv.truncate_to(4000);
+//! st.save_conversation(&conv);
+//! st.render_conversation(&conv);
+//! ```
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Prefix of the session metadata keys conversations are saved under.
+pub const CONVERSATION_METADATA_PREFIX: &str = "__conversation:";
+
+/// Estimate the tokens in `text`, at about four characters per token.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// Who wrote a message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    System,
+    User,
+    Assistant,
+}
+
+impl Role {
+    /// Get the role name.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::System => "system",
+            Role::User => "user",
+            Role::Assistant => "assistant",
+        }
+    }
+}
+
+/// One message of a conversation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: Role,
+    pub content: String,
+}
+
+impl ChatMessage {
+    /// Estimate the message's tokens.
+    pub fn tokens(&self) -> usize {
+        estimate_tokens(&self.content)
+    }
+}
+
+/// The messages of one chat, oldest first.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Conversation {
+    key: String,
+    messages: Vec<ChatMessage>,
+}
+
+impl Conversation {
+    /// Create an empty conversation.
+    pub fn new(key: impl Into<String>) -> Self {
+        Conversation {
+            key: key.into(),
+            messages: Vec::new(),
+        }
+    }
+
+    /// Get the conversation key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Append a message.
+    pub fn push(&mut self, role: Role, content: impl Into<String>) {
+        self.messages.push(ChatMessage {
+            role,
+            content: content.into(),
+        });
+    }
+
+    /// Append a system message.
+    pub fn push_system(&mut self, content: impl Into<String>) {
+        self.push(Role::System, content);
+    }
+
+    /// Append a user message.
+    pub fn push_user(&mut self, content: impl Into<String>) {
+        self.push(Role::User, content);
+    }
+
+    /// Append an assistant message.
+    pub fn push_assistant(&mut self, content: impl Into<String>) {
+        self.push(Role::Assistant, content);
+    }
+
+    /// Get the messages, oldest first.
+    pub fn messages(&self) -> &[ChatMessage] {
+        &self.messages
+    }
+
+    /// Get the last message.
+    pub fn last(&self) -> Option<&ChatMessage> {
+        self.messages.last()
+    }
+
+    /// Estimate the tokens of every message.
+    pub fn tokens(&self) -> usize {
+        self.messages.iter().map(ChatMessage::tokens).sum()
+    }
+
+    /// Get the system messages plus the newest other messages that fit in
+    /// `max_tokens`, oldest first, e.g. to build a prompt.
+    pub fn window(&self, max_tokens: usize) -> Vec<ChatMessage> {
+        let keep = self.kept(max_tokens);
+        self.messages
+            .iter()
+            .zip(keep)
+            .filter(|(_, keep)| *keep)
+            .map(|(message, _)| message.clone())
+            .collect()
+    }
+
+    /// Drop the oldest non-system messages until the conversation fits in
+    /// `max_tokens`. Returns the number of messages dropped.
+    pub fn truncate_to(&mut self, max_tokens: usize) -> usize {
+        let keep = self.kept(max_tokens);
+        let before = self.messages.len();
+        let mut keep = keep.into_iter();
+        self.messages.retain(|_| keep.next().unwrap_or(true));
+        before - self.messages.len()
+    }
+
+    /// Remove every message.
+    pub fn clear(&mut self) {
+        self.messages.clear();
+    }
+
+    /// Get the number of messages.
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// Check if there are no messages.
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Mark the messages a `max_tokens` window keeps: every system message,
+    /// then other messages newest first while they fit.
+    fn kept(&self, max_tokens: usize) -> Vec<bool> {
+        let mut budget = max_tokens.saturating_sub(
+            self.messages
+                .iter()
+                .filter(|message| message.role == Role::System)
+                .map(ChatMessage::tokens)
+                .sum(),
+        );
+        let mut keep = vec![false; self.messages.len()];
+        let mut fits = true;
+        for (index, message) in self.messages.iter().enumerate().rev() {
+            if message.role == Role::System {
+                keep[index] = true;
+            } else if fits && message.tokens() <= budget {
+                budget -= message.tokens();
+                keep[index] = true;
+            } else {
+                // Never skip a message to fit an older one
+                fits = false;
+            }
+        }
+        keep
+    }
+}
+
+/// Per-session conversations, shared across reruns.
+pub struct ConversationStore {
+    conversations: Arc<RwLock<HashMap<String, Conversation>>>,
+}
+
+impl ConversationStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        ConversationStore {
+            conversations: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Get a conversation, or an empty one.
+    pub fn get(&self, key: &str) -> Conversation {
+        self.conversations
+            .read()
+            .get(key)
+            .cloned()
+            .unwrap_or_else(|| Conversation::new(key))
+    }
+
+    /// Store a conversation under its key.
+    pub fn save(&self, conversation: &Conversation) {
+        self.conversations
+            .write()
+            .insert(conversation.key.clone(), conversation.clone());
+    }
+
+    /// Get the conversations as session metadata entries.
+    pub fn to_metadata(&self) -> Vec<(String, String)> {
+        self.conversations
+            .read()
+            .values()
+            .filter_map(|conversation| {
+                let json = serde_json::to_string(conversation).ok()?;
+                Some((format!("{}{}", CONVERSATION_METADATA_PREFIX, conversation.key), json))
+            })
+            .collect()
+    }
+
+    /// Load the conversations saved in session metadata.
+    pub fn restore(&self, metadata: &HashMap<String, String>) {
+        let mut conversations = self.conversations.write();
+        for (key, json) in metadata {
+            if key.starts_with(CONVERSATION_METADATA_PREFIX)
+                && let Ok(conversation) = serde_json::from_str::<Conversation>(json)
+            {
+                conversations.insert(conversation.key.clone(), conversation);
+            }
+        }
+    }
+}
+
+impl Default for ConversationStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for ConversationStore {
+    fn clone(&self) -> Self {
+        ConversationStore {
+            conversations: Arc::clone(&self.conversations),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_keeps_system_and_newest() {
+        let mut conv = Conversation::new("chat");
+        conv.push_system("Be brief.");
+        conv.push_user("a".repeat(40));
+        conv.push_assistant("b".repeat(40));
+        conv.push_user("c".repeat(8));
+
+        // 3 system tokens + 2 + 10 fit; the oldest user message does not
+        let window = conv.window(15);
+        assert_eq!(window.len(), 3);
+        assert_eq!(window[0].role, Role::System);
+        assert_eq!(window[2].content, "cccccccc");
+
+        assert_eq!(conv.truncate_to(15), 1);
+        assert_eq!(conv.len(), 3);
+    }
+
+    #[test]
+    fn test_store_round_trips_metadata() {
+        let store = ConversationStore::new();
+        let mut conv = store.get("chat");
+        conv.push_user("hi");
+        store.save(&conv);
+
+        let metadata: HashMap<_, _> = store.to_metadata().into_iter().collect();
+        let restored = ConversationStore::new();
+        restored.restore(&metadata);
+        assert_eq!(restored.get("chat"), conv);
+    }
+}
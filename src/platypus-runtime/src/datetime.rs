@@ -0,0 +1,164 @@
+//! Parsing of date range and datetime widget values.
+//!
+//! The browser reports dates as ISO strings and datetimes as wall-clock
+//! times without an offset; wall-clock times are interpreted in the
+//! timezone from the client context. Dates typed by hand may also come in
+//! the session locale's order, such as `31/12/2024` in en-GB.
+
+use crate::client::ClientInfo;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use platypus_core::widget::WidgetValue;
+
+/// Date format used on the wire (`<input type="date">`).
+pub const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Wall-clock format used on the wire (`<input type="datetime-local">`).
+pub const DATETIME_LOCAL_FORMAT: &str = "%Y-%m-%dT%H:%M";
+
+/// Order of day, month and year in a locale's numeric dates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateOrder {
+    /// 12/31/2024
+    MonthDayYear,
+    /// 31/12/2024
+    DayMonthYear,
+    /// 2024/12/31
+    YearMonthDay,
+}
+
+impl DateOrder {
+    /// Look up the order for a BCP 47 locale (defaults to day first).
+    pub fn for_locale(locale: &str) -> Self {
+        let locale = locale.to_ascii_lowercase().replace('_', "-");
+        match locale.split('-').next().unwrap_or("") {
+            "en" if matches!(locale.as_str(), "en" | "en-us" | "en-ph") => DateOrder::MonthDayYear,
+            "zh" | "ja" | "ko" | "hu" | "lt" => DateOrder::YearMonthDay,
+            _ => DateOrder::DayMonthYear,
+        }
+    }
+}
+
+/// Parse a date, ISO or numeric in the order of `locale` with `/`, `.` or
+/// `-` separators.
+pub fn parse_date(text: &str, locale: &str) -> Option<NaiveDate> {
+    let text = text.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(text, DATE_FORMAT) {
+        return Some(date);
+    }
+    let parts: Vec<u32> = text
+        .trim_end_matches('.')
+        .split(['/', '.', '-'])
+        .map(|part| part.trim().parse().ok())
+        .collect::<Option<_>>()?;
+    let (year, month, day) = match (DateOrder::for_locale(locale), parts.as_slice()) {
+        (DateOrder::MonthDayYear, [month, day, year]) => (*year, *month, *day),
+        (DateOrder::DayMonthYear, [day, month, year]) => (*year, *month, *day),
+        (DateOrder::YearMonthDay, [year, month, day]) => (*year, *month, *day),
+        _ => return None,
+    };
+    NaiveDate::from_ymd_opt(i32::try_from(year).ok()?, month, day)
+}
+
+/// Get the client's timezone, falling back to UTC.
+pub fn client_timezone(client_info: &ClientInfo) -> Tz {
+    client_info.timezone.parse().unwrap_or(Tz::UTC)
+}
+
+/// Parse a date range widget value, ordering the endpoints.
+///
+/// Accepts a typed range, a two-element string array, or a JSON array
+/// string, with dates as [`parse_date`] reads them.
+pub fn parse_date_range(value: &WidgetValue, locale: &str) -> Option<(NaiveDate, NaiveDate)> {
+    let (start, end) = match value {
+        WidgetValue::DateRange(start, end) => (*start, *end),
+        WidgetValue::StringArray(dates) => parse_date_pair(dates, locale)?,
+        WidgetValue::String(value) => parse_date_pair(&serde_json::from_str::<Vec<String>>(value).ok()?, locale)?,
+        _ => return None,
+    };
+    Some(if start <= end { (start, end) } else { (end, start) })
+}
+
+fn parse_date_pair(dates: &[String], locale: &str) -> Option<(NaiveDate, NaiveDate)> {
+    match dates {
+        [start, end] => Some((parse_date(start, locale)?, parse_date(end, locale)?)),
+        _ => None,
+    }
+}
+
+/// Parse a datetime widget value.
+///
+/// RFC 3339 strings carry their own offset; wall-clock strings are taken
+/// to be in `tz`.
+pub fn parse_datetime(value: &WidgetValue, tz: Tz) -> Option<DateTime<Utc>> {
+    match value {
+        WidgetValue::DateTime(dt) => Some(*dt),
+        WidgetValue::String(value) => {
+            if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+                return Some(dt.with_timezone(&Utc));
+            }
+            let naive = NaiveDateTime::parse_from_str(value, DATETIME_LOCAL_FORMAT)
+                .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S"))
+                .ok()?;
+            tz.from_local_datetime(&naive)
+                .earliest()
+                .map(|dt| dt.with_timezone(&Utc))
+        }
+        _ => None,
+    }
+}
+
+/// Format a datetime as wall-clock time in `tz`.
+pub fn to_local(dt: DateTime<Utc>, tz: Tz) -> String {
+    dt.with_timezone(&tz).format(DATETIME_LOCAL_FORMAT).to_string()
+}
+
+/// Clamp a value into optional bounds.
+pub fn clamp<T: PartialOrd>(value: T, min: Option<T>, max: Option<T>) -> T {
+    match (min, max) {
+        (Some(min), _) if value < min => min,
+        (_, Some(max)) if value > max => max,
+        _ => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_date_range_orders_endpoints() {
+        let value = WidgetValue::String(r#"["2024-03-10", "2024-03-01"]"#.to_string());
+        let (start, end) = parse_date_range(&value, "en-US").unwrap();
+        assert_eq!(start.to_string(), "2024-03-01");
+        assert_eq!(end.to_string(), "2024-03-10");
+    }
+
+    #[test]
+    fn test_parse_date_in_locale_order() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 10);
+        assert_eq!(parse_date("2024-03-10", "de-DE"), date);
+        assert_eq!(parse_date("10/03/2024", "en-GB"), date);
+        assert_eq!(parse_date("10.3.2024", "de-DE"), date);
+        assert_eq!(parse_date("3/10/2024", "en-US"), date);
+        assert_eq!(parse_date("2024/3/10", "ja-JP"), date);
+        assert_eq!(parse_date("31/12/2024", "en-US"), None);
+        assert_eq!(parse_date("10/03", "en-GB"), None);
+    }
+
+    #[test]
+    fn test_parse_datetime_in_client_timezone() {
+        let tz: Tz = "Asia/Hong_Kong".parse().unwrap();
+        let value = WidgetValue::String("2024-03-01T09:30".to_string());
+        let dt = parse_datetime(&value, tz).unwrap();
+        assert_eq!(dt.to_rfc3339(), "2024-03-01T01:30:00+00:00");
+        assert_eq!(to_local(dt, tz), "2024-03-01T09:30");
+    }
+
+    #[test]
+    fn test_clamp() {
+        assert_eq!(clamp(5, Some(1), Some(3)), 3);
+        assert_eq!(clamp(0, Some(1), None), 1);
+        assert_eq!(clamp(2, None, None), 2);
+    }
+}
@@ -0,0 +1,142 @@
+//! Downsampling of large chart series.
+//!
+//! Line, bar, area and scatter charts whose records exceed the session's
+//! point budget are reduced with Largest-Triangle-Three-Buckets before they
+//! are serialized, keeping the peaks and troughs that make the series'
+//! shape. The budget is set per run, and `None` sends every point:
+//!
+//! ```
+//! use platypus_runtime::St;
+//!
+//! let data: Vec<String> = (0..100_000).map(|i| format!(r#"{{"t": {}, "v": {}}}"#, i, (i as f64).sin())).collect();
+//! let mut st = St::new();
+//! st.line_chart(format!("[{}]", data.join(",")), None);
+//!
+//! // Charts below need every point, e.g. for an exported image
+//! st.set_chart_point_budget(None);
+//! ```
+
+use serde_json::{Map, Value};
+
+/// Default most points sent per chart.
+pub const DEFAULT_CHART_POINT_BUDGET: usize = 5000;
+
+/// Pick at most `threshold` of `points` with Largest-Triangle-Three-Buckets.
+/// Returns the indices of the kept points, ascending; the first and last
+/// are always kept.
+pub fn lttb(points: &[(f64, f64)], threshold: usize) -> Vec<usize> {
+    let len = points.len();
+    if threshold >= len || len <= 2 {
+        return (0..len).collect();
+    }
+    if threshold < 3 {
+        return vec![0, len - 1];
+    }
+
+    let bucket_size = (len - 2) as f64 / (threshold - 2) as f64;
+    let bucket_start = |bucket: usize| ((bucket as f64 * bucket_size) as usize + 1).min(len - 1);
+    let mut kept = Vec::with_capacity(threshold);
+    kept.push(0);
+    let mut previous = 0;
+    for bucket in 0..threshold - 2 {
+        let (start, end) = (bucket_start(bucket), bucket_start(bucket + 1));
+        // The next bucket's average is the third corner; the last bucket
+        // uses the final point
+        let (next_start, next_end) = (end, bucket_start(bucket + 2).max(end + 1).min(len));
+        let next = &points[next_start..next_end];
+        let average = (
+            next.iter().map(|point| point.0).sum::<f64>() / next.len() as f64,
+            next.iter().map(|point| point.1).sum::<f64>() / next.len() as f64,
+        );
+
+        let (ax, ay) = points[previous];
+        let area = |(x, y): (f64, f64)| ((ax - average.0) * (y - ay) - (ax - x) * (average.1 - ay)).abs();
+        let chosen = (start..end.max(start + 1))
+            .max_by(|&a, &b| area(points[a]).total_cmp(&area(points[b])))
+            .unwrap_or(start);
+        kept.push(chosen);
+        previous = chosen;
+    }
+    kept.push(len - 1);
+    kept
+}
+
+/// Reduce a JSON records array to about `budget` records.
+///
+/// The x value is the first column when it is numeric in every record, or
+/// the record's position otherwise. Each other numeric column is reduced on
+/// its own share of the budget, and a record is kept if any column keeps
+/// it. Returns `None` when the data is within budget or not records.
+pub fn downsample_records(data: &str, budget: usize) -> Option<String> {
+    let records: Vec<Map<String, Value>> = serde_json::from_str(data).ok()?;
+    if records.len() <= budget {
+        return None;
+    }
+    let columns: Vec<&String> = records.first()?.keys().collect();
+    let numeric = |column: &String| records.iter().all(|record| record.get(column).is_some_and(Value::is_number));
+    let x_column = columns.first().copied().filter(|column| numeric(column));
+    let series: Vec<&String> = columns
+        .iter()
+        .copied()
+        .filter(|column| Some(*column) != x_column && numeric(column))
+        .collect();
+    if series.is_empty() {
+        return None;
+    }
+
+    let x: Vec<f64> = match x_column {
+        Some(column) => records.iter().map(|record| number(record.get(column))).collect(),
+        None => (0..records.len()).map(|index| index as f64).collect(),
+    };
+    let share = (budget / series.len()).max(3);
+    let mut kept = vec![false; records.len()];
+    for column in series {
+        let points: Vec<(f64, f64)> = records
+            .iter()
+            .zip(&x)
+            .map(|(record, &x)| (x, number(record.get(column))))
+            .collect();
+        for index in lttb(&points, share) {
+            kept[index] = true;
+        }
+    }
+
+    let records: Vec<Map<String, Value>> = records
+        .into_iter()
+        .zip(kept)
+        .filter_map(|(record, kept)| kept.then_some(record))
+        .collect();
+    serde_json::to_string(&records).ok()
+}
+
+fn number(value: Option<&Value>) -> f64 {
+    value.and_then(Value::as_f64).unwrap_or(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lttb_keeps_ends_and_peaks() {
+        let mut points: Vec<(f64, f64)> = (0..1000).map(|i| (i as f64, 0.0)).collect();
+        points[500].1 = 100.0;
+        let kept = lttb(&points, 10);
+        assert_eq!(kept.len(), 10);
+        assert_eq!((kept[0], kept[9]), (0, 999));
+        assert!(kept.contains(&500));
+        assert!(kept.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(lttb(&points[..5], 10), vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_downsample_records_within_budget() {
+        let data: Vec<String> = (0..100).map(|i| format!(r#"{{"t": {}, "a": {}, "b": {}}}"#, i, i % 7, i % 3)).collect();
+        let data = format!("[{}]", data.join(","));
+        let reduced: Vec<Value> = serde_json::from_str(&downsample_records(&data, 20).unwrap()).unwrap();
+        assert!(reduced.len() <= 20 && reduced.len() >= 10);
+        assert_eq!(reduced[0]["t"], 0);
+        assert!(downsample_records(&data, 100).is_none());
+        assert!(downsample_records(r#"{"not": "records"}"#, 1).is_none());
+    }
+}
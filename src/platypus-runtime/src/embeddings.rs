@@ -0,0 +1,473 @@
+//! Embeddings and vector search for retrieval-augmented apps.
+//!
+//! A [`SemanticIndex`] pairs an [`EmbeddingProvider`], which turns text into
+//! vectors, with a [`VectorIndex`] that stores them. Embeddings are cached in
+//! a [`ResourceCache`] of up to [`DEFAULT_EMBEDDING_CACHE`] vectors, so
+//! re-adding a document or repeating a query does not call the provider
+//! again. The server shares one index with every session:
+//!
+//! ```
+//! use platypus_runtime::embeddings::{HashEmbedder, SemanticIndex};
+//! use platypus_runtime::St;
+//!
+//! let index = SemanticIndex::in_memory(HashEmbedder::new(256));
+//! index.add("rust", "Rust is a systems programming language").unwrap();
+//! index.add("tea", "Green tea is brewed at a low temperature").unwrap();
+//!
+//! let mut st = St::new().with_semantic_index(index);
+//! for hit in st.semantic_search("programming in rust", 1).unwrap() {
+//!     st.write(format!("{} ({:.2})", hit.text, hit.score));
+//! }
+//! ```
+//!
+//! [`HashEmbedder`] needs no model and suits demos and tests; real apps
+//! implement [`EmbeddingProvider`] for their embedding API. With the
+//! `sqlite` feature, [`SqliteIndex`] keeps vectors on disk.
+
+use crate::cache::ResourceCache;
+use crate::error::{Error, Result};
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Default most embeddings a [`SemanticIndex`] caches; the oldest are
+/// evicted first.
+pub const DEFAULT_EMBEDDING_CACHE: usize = 10_000;
+
+/// Turns text into embedding vectors.
+pub trait EmbeddingProvider: Send + Sync {
+    /// Provider name, part of the cache key so providers don't share
+    /// embeddings.
+    fn name(&self) -> &str;
+
+    /// Length of the vectors the provider returns, also part of the cache
+    /// key, so a provider reconfigured to another size doesn't reuse them.
+    fn dimensions(&self) -> usize;
+
+    /// Embed each text, returning one vector per text in order.
+    fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>>;
+}
+
+/// Embeds text by hashing its words into a fixed number of dimensions.
+///
+/// Texts sharing words score as similar; synonyms do not. Deterministic and
+/// offline, so it suits demos and tests.
+#[derive(Debug, Clone, Copy)]
+pub struct HashEmbedder {
+    dimensions: usize,
+}
+
+impl HashEmbedder {
+    /// Create an embedder producing vectors of `dimensions` values.
+    pub fn new(dimensions: usize) -> Self {
+        HashEmbedder {
+            dimensions: dimensions.max(1),
+        }
+    }
+
+    fn embed_one(&self, text: &str) -> Vec<f32> {
+        let mut vector = vec![0.0; self.dimensions];
+        for word in text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+        {
+            let hash = hash_of(&word.to_lowercase());
+            let sign = if hash & 1 == 0 { 1.0 } else { -1.0 };
+            vector[(hash >> 1) as usize % self.dimensions] += sign;
+        }
+        normalize(&mut vector);
+        vector
+    }
+}
+
+impl EmbeddingProvider for HashEmbedder {
+    fn name(&self) -> &str {
+        "hash"
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        Ok(texts.iter().map(|text| self.embed_one(text)).collect())
+    }
+}
+
+/// A search result.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchHit {
+    pub id: String,
+    pub text: String,
+    /// Cosine similarity to the query, from -1 to 1.
+    pub score: f32,
+}
+
+/// Stores document vectors and finds the nearest ones.
+pub trait VectorIndex: Send + Sync {
+    /// Insert a document, or replace the one with the same ID.
+    fn upsert(&self, id: &str, text: &str, vector: Vec<f32>) -> Result<()>;
+
+    /// Remove a document. Returns whether it existed.
+    fn remove(&self, id: &str) -> Result<bool>;
+
+    /// Find the `k` documents most similar to `query`, best first.
+    fn search(&self, query: &[f32], k: usize) -> Result<Vec<SearchHit>>;
+
+    /// Get the number of documents.
+    fn len(&self) -> usize;
+
+    /// Check if the index has no documents.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Vector index held in memory, searched exhaustively.
+#[derive(Default)]
+pub struct MemoryIndex {
+    documents: RwLock<HashMap<String, (String, Vec<f32>)>>,
+}
+
+impl MemoryIndex {
+    /// Create an empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl VectorIndex for MemoryIndex {
+    fn upsert(&self, id: &str, text: &str, vector: Vec<f32>) -> Result<()> {
+        self.documents
+            .write()
+            .insert(id.to_string(), (text.to_string(), vector));
+        Ok(())
+    }
+
+    fn remove(&self, id: &str) -> Result<bool> {
+        Ok(self.documents.write().remove(id).is_some())
+    }
+
+    fn search(&self, query: &[f32], k: usize) -> Result<Vec<SearchHit>> {
+        let documents = self.documents.read();
+        let hits = documents.iter().map(|(id, (text, vector))| SearchHit {
+            id: id.clone(),
+            text: text.clone(),
+            score: cosine(query, vector),
+        });
+        Ok(top_k(hits, k))
+    }
+
+    fn len(&self) -> usize {
+        self.documents.read().len()
+    }
+}
+
+/// Vector index stored in a SQLite database, searched exhaustively.
+#[cfg(feature = "sqlite")]
+pub struct SqliteIndex {
+    connection: parking_lot::Mutex<rusqlite::Connection>,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteIndex {
+    /// Open or create an index in the database at `path`.
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        Self::with_connection(rusqlite::Connection::open(path).map_err(anyhow::Error::from)?)
+    }
+
+    /// Create an index in a private in-memory database.
+    pub fn in_memory() -> Result<Self> {
+        Self::with_connection(rusqlite::Connection::open_in_memory().map_err(anyhow::Error::from)?)
+    }
+
+    fn with_connection(connection: rusqlite::Connection) -> Result<Self> {
+        connection
+            .execute(
+                "CREATE TABLE IF NOT EXISTS platypus_vectors (
+                    id TEXT PRIMARY KEY,
+                    text TEXT NOT NULL,
+                    vector BLOB NOT NULL
+                )",
+                [],
+            )
+            .map_err(anyhow::Error::from)?;
+        Ok(SqliteIndex {
+            connection: parking_lot::Mutex::new(connection),
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl VectorIndex for SqliteIndex {
+    fn upsert(&self, id: &str, text: &str, vector: Vec<f32>) -> Result<()> {
+        let blob: Vec<u8> = vector.iter().flat_map(|value| value.to_le_bytes()).collect();
+        self.connection
+            .lock()
+            .execute(
+                "INSERT OR REPLACE INTO platypus_vectors (id, text, vector) VALUES (?1, ?2, ?3)",
+                rusqlite::params![id, text, blob],
+            )
+            .map_err(anyhow::Error::from)?;
+        Ok(())
+    }
+
+    fn remove(&self, id: &str) -> Result<bool> {
+        let removed = self
+            .connection
+            .lock()
+            .execute("DELETE FROM platypus_vectors WHERE id = ?1", [id])
+            .map_err(anyhow::Error::from)?;
+        Ok(removed > 0)
+    }
+
+    fn search(&self, query: &[f32], k: usize) -> Result<Vec<SearchHit>> {
+        let connection = self.connection.lock();
+        let mut statement = connection
+            .prepare("SELECT id, text, vector FROM platypus_vectors")
+            .map_err(anyhow::Error::from)?;
+        let rows = statement
+            .query_map([], |row| {
+                let blob: Vec<u8> = row.get(2)?;
+                let vector: Vec<f32> = blob
+                    .chunks_exact(4)
+                    .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                    .collect();
+                Ok(SearchHit {
+                    id: row.get(0)?,
+                    text: row.get(1)?,
+                    score: cosine(query, &vector),
+                })
+            })
+            .map_err(anyhow::Error::from)?;
+        let hits = rows.collect::<std::result::Result<Vec<_>, _>>().map_err(anyhow::Error::from)?;
+        Ok(top_k(hits.into_iter(), k))
+    }
+
+    fn len(&self) -> usize {
+        self.connection
+            .lock()
+            .query_row("SELECT COUNT(*) FROM platypus_vectors", [], |row| row.get::<_, i64>(0))
+            .map_or(0, |count| count as usize)
+    }
+}
+
+/// An embedding provider and vector index searched by meaning.
+#[derive(Clone)]
+pub struct SemanticIndex {
+    provider: Arc<dyn EmbeddingProvider>,
+    index: Arc<dyn VectorIndex>,
+    cache: ResourceCache,
+}
+
+impl SemanticIndex {
+    /// Create an index embedding with `provider` and storing into `index`.
+    pub fn new(provider: impl EmbeddingProvider + 'static, index: impl VectorIndex + 'static) -> Self {
+        SemanticIndex {
+            provider: Arc::new(provider),
+            index: Arc::new(index),
+            cache: ResourceCache::with_capacity(DEFAULT_EMBEDDING_CACHE),
+        }
+    }
+
+    /// Create an index held in memory.
+    pub fn in_memory(provider: impl EmbeddingProvider + 'static) -> Self {
+        Self::new(provider, MemoryIndex::new())
+    }
+
+    /// Cache embeddings in a shared resource cache, e.g. one made with
+    /// [`ResourceCache::with_capacity`] to bound its size.
+    pub fn with_cache(mut self, cache: ResourceCache) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Add a document, replacing the one with the same ID.
+    pub fn add(&self, id: impl Into<String>, text: impl Into<String>) -> Result<()> {
+        self.add_all([(id.into(), text.into())])
+    }
+
+    /// Add documents as `(id, text)` pairs, embedding uncached texts in one
+    /// provider call.
+    pub fn add_all(&self, documents: impl IntoIterator<Item = (String, String)>) -> Result<()> {
+        let documents: Vec<_> = documents.into_iter().collect();
+        let texts: Vec<&str> = documents.iter().map(|(_, text)| text.as_str()).collect();
+        let vectors = self.embed(&texts)?;
+        for ((id, text), vector) in documents.iter().zip(vectors) {
+            self.index.upsert(id, text, vector)?;
+        }
+        Ok(())
+    }
+
+    /// Remove a document. Returns whether it existed.
+    pub fn remove(&self, id: &str) -> Result<bool> {
+        self.index.remove(id)
+    }
+
+    /// Find the `k` documents closest in meaning to `query`, best first.
+    pub fn search(&self, query: &str, k: usize) -> Result<Vec<SearchHit>> {
+        let vector = self.embed(&[query])?.pop().unwrap_or_default();
+        self.index.search(&vector, k)
+    }
+
+    /// Get the number of documents.
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Check if there are no documents.
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Embed texts, calling the provider only for those not cached.
+    fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        let keys: Vec<String> = texts.iter().map(|text| self.cache_key(text)).collect();
+        let mut vectors: Vec<Option<Vec<f32>>> = keys
+            .iter()
+            .map(|key| self.cache.get(key).and_then(|json| serde_json::from_str(&json).ok()))
+            .collect();
+
+        let missing: Vec<usize> = (0..texts.len()).filter(|&i| vectors[i].is_none()).collect();
+        if !missing.is_empty() {
+            let batch: Vec<&str> = missing.iter().map(|&i| texts[i]).collect();
+            let embedded = self.provider.embed(&batch)?;
+            if embedded.len() != batch.len() {
+                return Err(Error::execution(format!(
+                    "embedding provider '{}' returned {} vectors for {} texts",
+                    self.provider.name(),
+                    embedded.len(),
+                    batch.len()
+                )));
+            }
+            for (i, vector) in missing.into_iter().zip(embedded) {
+                if let Ok(json) = serde_json::to_string(&vector) {
+                    self.cache.set(keys[i].clone(), json);
+                }
+                vectors[i] = Some(vector);
+            }
+        }
+        Ok(vectors.into_iter().map(Option::unwrap_or_default).collect())
+    }
+
+    fn cache_key(&self, text: &str) -> String {
+        format!(
+            "embedding:{}:{}:{}:{:016x}",
+            self.provider.name(),
+            self.provider.dimensions(),
+            text.len(),
+            hash_of(text)
+        )
+    }
+}
+
+/// 64-bit FNV-1a of `text`. Unlike `DefaultHasher`, whose output may change
+/// between Rust releases, it hashes the same text to the same value
+/// everywhere, so embeddings and cache keys stay valid across upgrades.
+fn hash_of(text: &str) -> u64 {
+    text.bytes()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3))
+}
+
+fn normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|value| value * value).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|value| *value /= norm);
+    }
+}
+
+/// Cosine similarity, or 0 when either vector is zero or they differ in
+/// length.
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norms = a.iter().map(|x| x * x).sum::<f32>().sqrt() * b.iter().map(|y| y * y).sum::<f32>().sqrt();
+    if norms > 0.0 { dot / norms } else { 0.0 }
+}
+
+fn top_k(hits: impl Iterator<Item = SearchHit>, k: usize) -> Vec<SearchHit> {
+    let mut hits: Vec<_> = hits.collect();
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.id.cmp(&b.id)));
+    hits.truncate(k);
+    hits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Counts the texts it is asked to embed.
+    struct Counting(Arc<AtomicUsize>);
+
+    impl EmbeddingProvider for Counting {
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        fn dimensions(&self) -> usize {
+            64
+        }
+
+        fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+            self.0.fetch_add(texts.len(), Ordering::Relaxed);
+            HashEmbedder::new(64).embed(texts)
+        }
+    }
+
+    #[test]
+    fn test_search_ranks_by_similarity() {
+        let index = SemanticIndex::in_memory(HashEmbedder::new(256));
+        index.add("rust", "Rust is a systems programming language").unwrap();
+        index.add("tea", "Green tea is brewed at a low temperature").unwrap();
+
+        let hits = index.search("a programming language", 2).unwrap();
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].id, "rust");
+        assert!(hits[0].score > hits[1].score);
+    }
+
+    #[test]
+    fn test_embeddings_are_cached() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let index = SemanticIndex::in_memory(Counting(Arc::clone(&calls)));
+        index.add("a", "same text").unwrap();
+        index.add("b", "same text").unwrap();
+        index.search("same text", 1).unwrap();
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+        assert_eq!(index.len(), 2);
+    }
+
+    #[test]
+    fn test_hash_embedder_is_stable() {
+        // FNV-1a test vectors
+        assert_eq!(hash_of(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(hash_of("a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn test_embedding_cache_is_bounded() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let index = SemanticIndex::in_memory(Counting(Arc::clone(&calls))).with_cache(ResourceCache::with_capacity(2));
+        for text in ["one", "two", "three", "one"] {
+            index.search(text, 1).unwrap();
+        }
+        // "one" was evicted by "three"
+        assert_eq!(calls.load(Ordering::Relaxed), 4);
+        assert_eq!(index.cache.count(), 2);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_index() {
+        let index = SemanticIndex::new(HashEmbedder::new(64), SqliteIndex::in_memory().unwrap());
+        index.add("rust", "Rust programming").unwrap();
+        index.add("tea", "Green tea").unwrap();
+        assert_eq!(index.search("rust", 1).unwrap()[0].id, "rust");
+        assert!(index.remove("tea").unwrap());
+        assert_eq!(index.len(), 1);
+    }
+}
@@ -1,5 +1,6 @@
 //! Runtime error types.
 
+use platypus_core::ErrorCode;
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -37,4 +38,16 @@ impl Error {
     pub fn execution(msg: impl Into<String>) -> Self {
         Error::Execution(msg.into())
     }
+
+    /// Get the error's stable code.
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Error::Core(e) => e.code(),
+            Error::Session(_) => ErrorCode::Session,
+            Error::Event(_) => ErrorCode::Protocol,
+            Error::Execution(_) => ErrorCode::ScriptExecution,
+            Error::Io(_) => ErrorCode::Io,
+            Error::Other(_) => ErrorCode::Internal,
+        }
+    }
 }
@@ -1,17 +1,70 @@
 //! Event types for user interactions.
+//!
+//! The server turns each client message into an [`Event`], passes it to
+//! the handlers subscribed on the session's [`EventDispatcher`], and then
+//! applies it (storing widget values, switching pages, ...) and reruns
+//! the app. Apps subscribe with `st.on_event`:
+//!
+//! ```
+//! use platypus_runtime::{Event, St};
+//!
+//! let mut st = St::new();
+//! st.on_event("audit", |event| {
+//!     if let Event::FileUploaded { key, files } = event {
+//!         println!("{} received {} files", key, files.len());
+//!     }
+//! });
+//! ```
 
+use crate::client::ClientInfo;
+use crate::selection::ChartSelection;
+use crate::theme::ThemeMode;
+use crate::transfer::TransferProgress;
+use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Widget key holding the page selected by the client.
+pub const PAGE_WIDGET_KEY: &str = "__page";
 
 /// User interaction event.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum Event {
     /// Widget state changed.
     WidgetChanged {
         key: String,
         value: serde_json::Value,
     },
+    /// Several widgets changed at once, e.g. on a form submit. Applied
+    /// together before a single rerun.
+    WidgetsChanged { changes: Vec<WidgetChange> },
+    /// Form `form` was submitted by its button `submit_key`, with the
+    /// changes it held. Applied together before a single rerun, in which
+    /// the submit button reads as pressed.
+    FormSubmitted {
+        form: String,
+        submit_key: String,
+        changes: Vec<WidgetChange>,
+    },
     /// Button clicked.
     ButtonClicked { key: String },
+    /// Files dropped or pasted into an uploader, as data URLs.
+    FileUploaded { key: String, files: Vec<String> },
+    /// The client switched pages.
+    PageChanged { page: String },
+    /// The client's browser context changed.
+    ClientContext { info: ClientInfo },
+    /// The user picked a theme.
+    ThemePreference { mode: ThemeMode },
+    /// An upload or download made progress.
+    TransferProgress { progress: TransferProgress },
+    /// The user clicked, selected in or zoomed the chart `key`.
+    ChartSelection { key: String, selection: ChartSelection },
+    /// The placeholder of the lazy element `key` entered the viewport.
+    ElementVisible { key: String },
+    /// The page embedding the app posted a message.
+    HostMessage { origin: String, data: serde_json::Value },
     /// Script rerun requested.
     RerunScript,
     /// Custom event.
@@ -36,4 +89,160 @@ impl Event {
     pub fn custom(event_type: String, data: serde_json::Value) -> Self {
         Event::Custom { event_type, data }
     }
+
+    /// Get the event name, e.g. `widget_changed`.
+    pub fn name(&self) -> &str {
+        match self {
+            Event::WidgetChanged { .. } => "widget_changed",
+            Event::WidgetsChanged { .. } => "widgets_changed",
+            Event::FormSubmitted { .. } => "form_submitted",
+            Event::ButtonClicked { .. } => "button_clicked",
+            Event::FileUploaded { .. } => "file_uploaded",
+            Event::PageChanged { .. } => "page_changed",
+            Event::ClientContext { .. } => "client_context",
+            Event::ThemePreference { .. } => "theme_preference",
+            Event::TransferProgress { .. } => "transfer_progress",
+            Event::ChartSelection { .. } => "chart_selection",
+            Event::ElementVisible { .. } => "element_visible",
+            Event::HostMessage { .. } => "host_message",
+            Event::RerunScript => "rerun_script",
+            Event::Custom { event_type, .. } => event_type,
+        }
+    }
+}
+
+/// One widget update in a batch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WidgetChange {
+    pub key: String,
+    pub value: serde_json::Value,
+}
+
+/// Callback receiving every event of a session.
+pub type EventHandler = Arc<dyn Fn(&Event) + Send + Sync>;
+
+/// Per-session event handlers, keyed so that resubscribing on every rerun
+/// replaces the handler instead of adding another.
+pub struct EventDispatcher {
+    handlers: Arc<RwLock<Vec<(String, EventHandler)>>>,
+}
+
+impl EventDispatcher {
+    /// Create a dispatcher with no handlers.
+    pub fn new() -> Self {
+        EventDispatcher {
+            handlers: Arc::new(RwLock::new(Vec::new())),
+        }
+    }
+
+    /// Subscribe a handler under `key`, replacing any handler with that key.
+    pub fn subscribe<F>(&self, key: impl Into<String>, handler: F)
+    where
+        F: Fn(&Event) + Send + Sync + 'static,
+    {
+        let key = key.into();
+        let handler: EventHandler = Arc::new(handler);
+        let mut handlers = self.handlers.write();
+        match handlers.iter_mut().find(|(existing, _)| *existing == key) {
+            Some(entry) => entry.1 = handler,
+            None => handlers.push((key, handler)),
+        }
+    }
+
+    /// Remove the handler under `key`. Returns whether one was removed.
+    pub fn unsubscribe(&self, key: &str) -> bool {
+        let mut handlers = self.handlers.write();
+        let before = handlers.len();
+        handlers.retain(|(existing, _)| existing != key);
+        handlers.len() != before
+    }
+
+    /// Call every handler with `event`, in subscription order.
+    pub fn dispatch(&self, event: &Event) {
+        // Handlers may subscribe, so call them without holding the lock
+        let handlers: Vec<EventHandler> = self
+            .handlers
+            .read()
+            .iter()
+            .map(|(_, handler)| Arc::clone(handler))
+            .collect();
+        for handler in handlers {
+            handler(event);
+        }
+    }
+
+    /// Get the number of handlers.
+    pub fn len(&self) -> usize {
+        self.handlers.read().len()
+    }
+
+    /// Check if there are no handlers.
+    pub fn is_empty(&self) -> bool {
+        self.handlers.read().is_empty()
+    }
+}
+
+impl Default for EventDispatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for EventDispatcher {
+    fn clone(&self) -> Self {
+        EventDispatcher {
+            handlers: Arc::clone(&self.handlers),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_subscribe_replaces_same_key() {
+        let dispatcher = EventDispatcher::new();
+        let calls = Arc::new(AtomicUsize::new(0));
+        for _ in 0..3 {
+            let calls = Arc::clone(&calls);
+            dispatcher.subscribe("counter", move |_| {
+                calls.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+        assert_eq!(dispatcher.len(), 1);
+
+        dispatcher.dispatch(&Event::RerunScript);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(dispatcher.unsubscribe("counter"));
+        assert!(dispatcher.is_empty());
+    }
+
+    #[test]
+    fn test_event_json() {
+        let event: Event = serde_json::from_value(serde_json::json!({
+            "type": "page_changed",
+            "page": "settings",
+        }))
+        .unwrap();
+        assert_eq!(event, Event::PageChanged { page: "settings".to_string() });
+        assert_eq!(event.name(), "page_changed");
+    }
+
+    #[test]
+    fn test_widgets_changed_json() {
+        let event: Event = serde_json::from_value(serde_json::json!({
+            "type": "widgets_changed",
+            "changes": [{"key": "a", "value": 1}, {"key": "b", "value": "x"}],
+        }))
+        .unwrap();
+        match event {
+            Event::WidgetsChanged { changes } => {
+                assert_eq!(changes.len(), 2);
+                assert_eq!(changes[1].key, "b");
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
 }
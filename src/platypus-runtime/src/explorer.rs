@@ -0,0 +1,404 @@
+//! Exploratory data analysis over `st.dataframe` records.
+//!
+//! `st.experimental_data_explorer(data, key)` shows column statistics and
+//! lets the user filter, group and chart a JSON records array without any
+//! widget code. Everything is computed server-side by [`Frame`]:
+//!
+//! ```
+//! use platypus_runtime::St;
+//!
+//! let data = r#"[{"region": "EMEA", "units": 12}, {"region": "APAC", "units": 7}]"#;
+//! let mut st = St::new();
+//! if let Some(rows) = st.experimental_data_explorer(data, "sales") {
+//!     st.write(format!("{} rows match", rows.rows.len()));
+//! }
+//! ```
+
+use crate::export;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashSet};
+
+/// Text columns with at most this many distinct values are offered for
+/// grouping and suggested as chart categories.
+pub const MAX_GROUPS: usize = 20;
+
+/// How a column's values are interpreted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColumnKind {
+    /// Every non-empty value is a number.
+    Number,
+    Text,
+}
+
+/// Statistics of one column.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ColumnSummary {
+    pub name: String,
+    pub kind: ColumnKind,
+    /// Non-empty values.
+    pub count: usize,
+    pub missing: usize,
+    pub distinct: usize,
+    /// Numeric columns only.
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub mean: Option<f64>,
+}
+
+/// Chart types the explorer suggests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChartKind {
+    Bar,
+    Line,
+    Scatter,
+}
+
+/// A chart that suits a frame: `y` plotted against `x`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChartSuggestion {
+    pub kind: ChartKind,
+    pub x: String,
+    pub y: String,
+}
+
+/// A table of string cells, as parsed from a JSON records array.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Frame {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Frame {
+    /// Parse a JSON records array, as passed to `st.dataframe`.
+    pub fn from_records(data: &str) -> Option<Self> {
+        let (headers, rows) = export::records_to_table(data)?;
+        Some(Frame { headers, rows })
+    }
+
+    /// Get a column's index.
+    pub fn column(&self, name: &str) -> Option<usize> {
+        self.headers.iter().position(|header| header == name)
+    }
+
+    /// Get a column's kind.
+    pub fn kind(&self, column: usize) -> ColumnKind {
+        let numeric = self
+            .values(column)
+            .all(|value| value.trim().parse::<f64>().is_ok());
+        if numeric && self.values(column).next().is_some() {
+            ColumnKind::Number
+        } else {
+            ColumnKind::Text
+        }
+    }
+
+    /// Summarize every column.
+    pub fn summarize(&self) -> Vec<ColumnSummary> {
+        (0..self.headers.len())
+            .map(|column| {
+                let kind = self.kind(column);
+                let count = self.values(column).count();
+                let distinct = self.values(column).collect::<HashSet<_>>().len();
+                let numbers: Vec<f64> = match kind {
+                    ColumnKind::Number => self.numbers(column).collect(),
+                    ColumnKind::Text => Vec::new(),
+                };
+                let (min, max, mean) = if numbers.is_empty() {
+                    (None, None, None)
+                } else {
+                    (
+                        numbers.iter().copied().reduce(f64::min),
+                        numbers.iter().copied().reduce(f64::max),
+                        Some(numbers.iter().sum::<f64>() / numbers.len() as f64),
+                    )
+                };
+                ColumnSummary {
+                    name: self.headers[column].clone(),
+                    kind,
+                    count,
+                    missing: self.rows.len() - count,
+                    distinct,
+                    min,
+                    max,
+                    mean,
+                }
+            })
+            .collect()
+    }
+
+    /// Keep the rows whose `column` matches `query`: a comparison such as
+    /// `>10`, `<=2.5`, `=3` or `!=EMEA`, or else a case-insensitive
+    /// substring. An empty query or unknown column keeps every row.
+    pub fn filter(&self, column: &str, query: &str) -> Frame {
+        let query = query.trim();
+        let Some(column) = self.column(column).filter(|_| !query.is_empty()) else {
+            return self.clone();
+        };
+        let condition = Condition::parse(query);
+        Frame {
+            headers: self.headers.clone(),
+            rows: self
+                .rows
+                .iter()
+                .filter(|row| condition.matches(&row[column]))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Group rows by `column`, giving each group's row count and the mean
+    /// of every numeric column. Groups are sorted by value.
+    pub fn group_by(&self, column: &str) -> Frame {
+        let Some(key) = self.column(column) else {
+            return self.clone();
+        };
+        let numeric: Vec<usize> = (0..self.headers.len())
+            .filter(|&c| c != key && self.kind(c) == ColumnKind::Number)
+            .collect();
+
+        let mut groups: BTreeMap<&str, Vec<&Vec<String>>> = BTreeMap::new();
+        for row in &self.rows {
+            groups.entry(row[key].as_str()).or_default().push(row);
+        }
+
+        let mut headers = vec![self.headers[key].clone(), "count".to_string()];
+        headers.extend(numeric.iter().map(|&c| format!("mean({})", self.headers[c])));
+        let rows = groups
+            .into_iter()
+            .map(|(group, rows)| {
+                let mut cells = vec![group.to_string(), rows.len().to_string()];
+                cells.extend(numeric.iter().map(|&c| {
+                    let numbers: Vec<f64> = rows.iter().filter_map(|row| row[c].trim().parse().ok()).collect();
+                    if numbers.is_empty() {
+                        String::new()
+                    } else {
+                        format_number(numbers.iter().sum::<f64>() / numbers.len() as f64)
+                    }
+                }));
+                cells
+            })
+            .collect();
+        Frame { headers, rows }
+    }
+
+    /// Get the text columns with few enough distinct values to group by.
+    pub fn groupable_columns(&self) -> Vec<String> {
+        self.summarize()
+            .into_iter()
+            .filter(|summary| summary.kind == ColumnKind::Text && summary.distinct <= MAX_GROUPS)
+            .map(|summary| summary.name)
+            .collect()
+    }
+
+    /// Suggest a chart: a bar chart of a numeric column over a categorical
+    /// one, else a line chart when the first numeric column is increasing,
+    /// else a scatter plot of the first two numeric columns.
+    pub fn suggest_chart(&self) -> Option<ChartSuggestion> {
+        let numeric: Vec<usize> = (0..self.headers.len())
+            .filter(|&c| self.kind(c) == ColumnKind::Number)
+            .collect();
+        if let (Some(category), Some(&value)) = (self.groupable_columns().first(), numeric.first()) {
+            return Some(ChartSuggestion {
+                kind: ChartKind::Bar,
+                x: category.clone(),
+                y: self.headers[value].clone(),
+            });
+        }
+        let (&x, &y) = (numeric.first()?, numeric.get(1)?);
+        let xs: Vec<f64> = self.numbers(x).collect();
+        let kind = if xs.len() == self.rows.len() && xs.windows(2).all(|pair| pair[0] < pair[1]) {
+            ChartKind::Line
+        } else {
+            ChartKind::Scatter
+        };
+        Some(ChartSuggestion {
+            kind,
+            x: self.headers[x].clone(),
+            y: self.headers[y].clone(),
+        })
+    }
+
+    /// Encode as a JSON records array, with numeric cells as numbers.
+    pub fn to_records(&self) -> String {
+        self.records(&(0..self.headers.len()).collect::<Vec<_>>())
+    }
+
+    /// Encode the suggested chart's two columns as a JSON records array.
+    pub fn chart_records(&self, chart: &ChartSuggestion) -> String {
+        let columns: Vec<usize> = [&chart.x, &chart.y]
+            .iter()
+            .filter_map(|name| self.column(name))
+            .collect();
+        self.records(&columns)
+    }
+
+    fn records(&self, columns: &[usize]) -> String {
+        let records: Vec<Value> = self
+            .rows
+            .iter()
+            .map(|row| {
+                let record: Map<String, Value> = columns
+                    .iter()
+                    .map(|&c| (self.headers[c].clone(), cell_value(&row[c])))
+                    .collect();
+                Value::Object(record)
+            })
+            .collect();
+        Value::Array(records).to_string()
+    }
+
+    /// Non-empty values of a column.
+    fn values(&self, column: usize) -> impl Iterator<Item = &str> {
+        self.rows
+            .iter()
+            .map(move |row| row[column].as_str())
+            .filter(|value| !value.trim().is_empty())
+    }
+
+    fn numbers(&self, column: usize) -> impl Iterator<Item = f64> + '_ {
+        self.values(column).filter_map(|value| value.trim().parse().ok())
+    }
+}
+
+/// A histogram bin: values in `start..end`, or `start..=end` for the last.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Bin {
+    pub start: f64,
+    pub end: f64,
+    pub count: usize,
+}
+
+/// Count `values` in `bins` equal-width bins spanning their range. NaNs
+/// are skipped; all-equal values fall in a single bin.
+pub fn histogram(values: &[f64], bins: usize) -> Vec<Bin> {
+    let values: Vec<f64> = values.iter().copied().filter(|value| !value.is_nan()).collect();
+    let (Some(min), Some(max)) = (
+        values.iter().copied().reduce(f64::min),
+        values.iter().copied().reduce(f64::max),
+    ) else {
+        return Vec::new();
+    };
+    if min == max || bins <= 1 {
+        return vec![Bin { start: min, end: max, count: values.len() }];
+    }
+
+    let width = (max - min) / bins as f64;
+    let mut counts = vec![0; bins];
+    for value in values {
+        let index = (((value - min) / width) as usize).min(bins - 1);
+        counts[index] += 1;
+    }
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(index, count)| Bin {
+            start: min + width * index as f64,
+            end: if index + 1 == bins { max } else { min + width * (index + 1) as f64 },
+            count,
+        })
+        .collect()
+}
+
+/// A parsed filter query.
+enum Condition {
+    Compare(&'static str, f64),
+    NotEqual(String),
+    Contains(String),
+}
+
+impl Condition {
+    fn parse(query: &str) -> Self {
+        for op in [">=", "<=", "!=", "==", ">", "<", "="] {
+            if let Some(operand) = query.strip_prefix(op) {
+                let operand = operand.trim();
+                return match (op, operand.parse::<f64>()) {
+                    ("!=", Err(_)) => Condition::NotEqual(operand.to_lowercase()),
+                    (_, Ok(number)) => Condition::Compare(op, number),
+                    (_, Err(_)) => Condition::Contains(operand.to_lowercase()),
+                };
+            }
+        }
+        Condition::Contains(query.to_lowercase())
+    }
+
+    fn matches(&self, cell: &str) -> bool {
+        match self {
+            Condition::Compare(op, operand) => cell.trim().parse::<f64>().is_ok_and(|value| match *op {
+                ">=" => value >= *operand,
+                "<=" => value <= *operand,
+                ">" => value > *operand,
+                "<" => value < *operand,
+                "!=" => value != *operand,
+                _ => value == *operand,
+            }),
+            Condition::NotEqual(text) => cell.to_lowercase() != *text,
+            Condition::Contains(text) => cell.to_lowercase().contains(text),
+        }
+    }
+}
+
+fn cell_value(cell: &str) -> Value {
+    match cell.trim().parse::<f64>() {
+        Ok(number) if number.fract() == 0.0 && number.abs() < i64::MAX as f64 => Value::from(number as i64),
+        Ok(number) => serde_json::Number::from_f64(number).map_or_else(|| Value::from(cell), Value::Number),
+        Err(_) if cell.is_empty() => Value::Null,
+        Err(_) => Value::from(cell),
+    }
+}
+
+/// Format a computed number with at most four decimals.
+pub fn format_number(number: f64) -> String {
+    let text = format!("{:.4}", number);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SALES: &str = r#"[
+        {"region": "EMEA", "units": 12, "price": 2.5},
+        {"region": "APAC", "units": 7, "price": 3},
+        {"region": "EMEA", "units": 4, "price": null}
+    ]"#;
+
+    #[test]
+    fn test_summarize_columns() {
+        let frame = Frame::from_records(SALES).unwrap();
+        let summary = frame.summarize();
+        let column = |name: &str| summary.iter().find(|column| column.name == name).unwrap();
+        assert_eq!(column("region").kind, ColumnKind::Text);
+        assert_eq!(column("region").distinct, 2);
+        assert_eq!(column("units").kind, ColumnKind::Number);
+        assert_eq!((column("units").min, column("units").max), (Some(4.0), Some(12.0)));
+        assert_eq!(column("price").missing, 1);
+    }
+
+    #[test]
+    fn test_filter_and_group() {
+        let frame = Frame::from_records(SALES).unwrap();
+        assert_eq!(frame.filter("units", ">5").rows.len(), 2);
+        assert_eq!(frame.filter("region", "emea").rows.len(), 2);
+        assert_eq!(frame.filter("region", "!=EMEA").rows.len(), 1);
+
+        let grouped = frame.group_by("region");
+        // Records keys, and so columns, are in alphabetical order
+        assert_eq!(grouped.headers, vec!["region", "count", "mean(price)", "mean(units)"]);
+        assert_eq!(grouped.rows[1], vec!["EMEA", "2", "2.5", "8"]);
+
+        let chart = frame.suggest_chart().unwrap();
+        assert_eq!((chart.kind, chart.x.as_str(), chart.y.as_str()), (ChartKind::Bar, "region", "price"));
+    }
+
+    #[test]
+    fn test_histogram_bins() {
+        let bins = histogram(&[1.0, 2.0, 2.5, 4.0, 5.0, f64::NAN], 2);
+        assert_eq!(bins.iter().map(|bin| bin.count).collect::<Vec<_>>(), vec![3, 2]);
+        assert_eq!((bins[0].start, bins[0].end, bins[1].end), (1.0, 3.0, 5.0));
+        assert_eq!(histogram(&[3.0, 3.0], 10).len(), 1);
+        assert!(histogram(&[], 5).is_empty());
+    }
+}
@@ -0,0 +1,311 @@
+//! CSV and XLSX export of tabular data.
+//!
+//! XLSX workbooks are written as a minimal single-sheet package using
+//! inline strings. Cells keep their JSON type: numbers and booleans are
+//! written as such and strings as text, so `"007"` keeps its zeros.
+//!
+//! CSV cells holding text that a spreadsheet would read as a formula
+//! (starting with `=`, `+`, `-`, `@`, a tab or a carriage return) are
+//! prefixed with `'`, so opening an export cannot run one.
+
+use serde_json::Value;
+use std::io::{Cursor, Write};
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+/// Supported export formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Xlsx,
+}
+
+impl ExportFormat {
+    /// Pick a format from a file name extension (defaults to CSV).
+    pub fn from_file_name(file_name: &str) -> Self {
+        if file_name.to_ascii_lowercase().ends_with(".xlsx") {
+            ExportFormat::Xlsx
+        } else {
+            ExportFormat::Csv
+        }
+    }
+
+    /// MIME type of the exported file.
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv",
+            ExportFormat::Xlsx => {
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"
+            }
+        }
+    }
+}
+
+/// Encode a table in the given format.
+pub fn table_bytes(headers: &[String], rows: &[Vec<Value>], format: ExportFormat) -> Vec<u8> {
+    match format {
+        ExportFormat::Csv => to_csv(headers, rows),
+        ExportFormat::Xlsx => to_xlsx(headers, rows),
+    }
+}
+
+/// Convert a JSON records array (as used by `st.dataframe`) into headers and rows.
+///
+/// Headers are the union of keys in first-seen order; missing cells are empty.
+pub fn records_to_table(data: &str) -> Option<(Vec<String>, Vec<Vec<String>>)> {
+    let (headers, rows) = records_to_values(data)?;
+    let rows = rows
+        .iter()
+        .map(|row| row.iter().map(|value| cell_text(Some(value))).collect())
+        .collect();
+    Some((headers, rows))
+}
+
+/// Convert a JSON records array into headers and rows of the records'
+/// values, as [`records_to_table`] does; missing cells are null.
+pub fn records_to_values(data: &str) -> Option<(Vec<String>, Vec<Vec<Value>>)> {
+    let records = match serde_json::from_str::<Value>(data).ok()? {
+        Value::Array(records) => records,
+        _ => return None,
+    };
+
+    let mut headers: Vec<String> = vec![];
+    for record in &records {
+        for key in record.as_object()?.keys() {
+            if !headers.contains(key) {
+                headers.push(key.clone());
+            }
+        }
+    }
+
+    let rows = records
+        .iter()
+        .map(|record| {
+            headers
+                .iter()
+                .map(|h| record.get(h).cloned().unwrap_or(Value::Null))
+                .collect()
+        })
+        .collect();
+
+    Some((headers, rows))
+}
+
+/// Get a record value as table cell text: strings unquoted, missing and
+/// null values empty.
+pub(crate) fn cell_text(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Null) | None => String::new(),
+        Some(other) => other.to_string(),
+    }
+}
+
+/// Encode a table as RFC 4180 CSV.
+pub fn to_csv(headers: &[String], rows: &[Vec<Value>]) -> Vec<u8> {
+    let mut out = String::new();
+    let header: Vec<String> = headers.iter().map(|header| csv_cell(&formula_safe(header))).collect();
+    out.push_str(&header.join(","));
+    out.push_str("\r\n");
+    for row in rows {
+        let cells: Vec<String> = row
+            .iter()
+            .map(|value| match value {
+                Value::String(text) => csv_cell(&formula_safe(text)),
+                other => csv_cell(&cell_text(Some(other))),
+            })
+            .collect();
+        out.push_str(&cells.join(","));
+        out.push_str("\r\n");
+    }
+    out.into_bytes()
+}
+
+/// Prefix text a spreadsheet would evaluate as a formula with `'`.
+/// Numbers such as `-5` are left as they are.
+fn formula_safe(text: &str) -> String {
+    if text.starts_with(['=', '+', '-', '@', '\t', '\r']) && text.parse::<f64>().is_err() {
+        format!("'{}", text)
+    } else {
+        text.to_string()
+    }
+}
+
+fn csv_cell(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+/// Encode a table as a single-sheet XLSX workbook.
+pub fn to_xlsx(headers: &[String], rows: &[Vec<Value>]) -> Vec<u8> {
+    let mut sheet = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\
+         <worksheet xmlns=\"http://schemas.openxmlformats.org/spreadsheetml/2006/main\"><sheetData>",
+    );
+    let header: Vec<Value> = headers.iter().cloned().map(Value::String).collect();
+    for (r, row) in std::iter::once(&header).chain(rows).enumerate() {
+        sheet.push_str(&format!("<row r=\"{}\">", r + 1));
+        for (c, value) in row.iter().enumerate() {
+            let reference = format!("{}{}", column_name(c), r + 1);
+            match value {
+                Value::Null => {}
+                Value::Number(number) => {
+                    sheet.push_str(&format!("<c r=\"{}\"><v>{}</v></c>", reference, number));
+                }
+                Value::Bool(flag) => {
+                    sheet.push_str(&format!("<c r=\"{}\" t=\"b\"><v>{}</v></c>", reference, u8::from(*flag)));
+                }
+                other => sheet.push_str(&format!(
+                    "<c r=\"{}\" t=\"inlineStr\"><is><t>{}</t></is></c>",
+                    reference,
+                    xml_escape(&cell_text(Some(other)))
+                )),
+            }
+        }
+        sheet.push_str("</row>");
+    }
+    sheet.push_str("</sheetData></worksheet>");
+
+    let files: [(&str, String); 5] = [
+        (
+            "[Content_Types].xml",
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\
+             <Types xmlns=\"http://schemas.openxmlformats.org/package/2006/content-types\">\
+             <Default Extension=\"rels\" ContentType=\"application/vnd.openxmlformats-package.relationships+xml\"/>\
+             <Default Extension=\"xml\" ContentType=\"application/xml\"/>\
+             <Override PartName=\"/xl/workbook.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml\"/>\
+             <Override PartName=\"/xl/worksheets/sheet1.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml\"/>\
+             </Types>"
+                .to_string(),
+        ),
+        (
+            "_rels/.rels",
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\
+             <Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\
+             <Relationship Id=\"rId1\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument\" Target=\"xl/workbook.xml\"/>\
+             </Relationships>"
+                .to_string(),
+        ),
+        (
+            "xl/workbook.xml",
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\
+             <workbook xmlns=\"http://schemas.openxmlformats.org/spreadsheetml/2006/main\" \
+             xmlns:r=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships\">\
+             <sheets><sheet name=\"Sheet1\" sheetId=\"1\" r:id=\"rId1\"/></sheets></workbook>"
+                .to_string(),
+        ),
+        (
+            "xl/_rels/workbook.xml.rels",
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\
+             <Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\
+             <Relationship Id=\"rId1\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet\" Target=\"worksheets/sheet1.xml\"/>\
+             </Relationships>"
+                .to_string(),
+        ),
+        ("xl/worksheets/sheet1.xml", sheet),
+    ];
+
+    // Writing to memory only fails on archives past 4 GiB
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, content) in &files {
+        zip.start_file(*name, SimpleFileOptions::default()).expect("XLSX entry");
+        zip.write_all(content.as_bytes()).expect("XLSX entry");
+    }
+    zip.finish().expect("XLSX archive").into_inner()
+}
+
+/// Spreadsheet column name for a zero-based index (0 -> A, 26 -> AA).
+fn column_name(mut index: usize) -> String {
+    let mut name = vec![];
+    loop {
+        name.push(b'A' + (index % 26) as u8);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap_or_default()
+}
+
+/// Escape text for XML, leaving out the control characters XML 1.0 cannot
+/// hold.
+fn xml_escape(text: &str) -> String {
+    text.replace(|c: char| c < ' ' && !matches!(c, '\t' | '\n' | '\r'), "")
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> (Vec<String>, Vec<Vec<Value>>) {
+        (
+            vec!["name".to_string(), "note".to_string()],
+            vec![vec![Value::from("Alice"), Value::from("says \"hi\", twice")]],
+        )
+    }
+
+    fn sheet(xlsx: Vec<u8>) -> String {
+        let mut archive = zip::ZipArchive::new(Cursor::new(xlsx)).unwrap();
+        std::io::read_to_string(archive.by_name("xl/worksheets/sheet1.xml").unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_to_csv_quotes_cells() {
+        let (headers, rows) = sample();
+        let csv = String::from_utf8(to_csv(&headers, &rows)).unwrap();
+        assert_eq!(csv, "name,note\r\nAlice,\"says \"\"hi\"\", twice\"\r\n");
+    }
+
+    #[test]
+    fn test_to_csv_guards_formulas() {
+        let rows = vec![vec![
+            Value::from("=HYPERLINK(\"http://x\")"),
+            Value::from("@SUM(A1)"),
+            Value::from("-5"),
+            Value::from(-5),
+        ]];
+        let csv = String::from_utf8(to_csv(&["+a".to_string()], &rows)).unwrap();
+        assert_eq!(csv, "'+a\r\n\"'=HYPERLINK(\"\"http://x\"\")\",'@SUM(A1),-5,-5\r\n");
+    }
+
+    #[test]
+    fn test_to_xlsx_is_zip() {
+        let (headers, rows) = sample();
+        let bytes = to_xlsx(&headers, &rows);
+        assert_eq!(&bytes[..4], b"PK\x03\x04");
+        assert!(sheet(bytes).contains("says &quot;hi&quot;, twice"));
+    }
+
+    #[test]
+    fn test_to_xlsx_keeps_cell_types() {
+        let data = r#"[{"zip": "007", "n": 1.5, "ok": true, "note": "a\u0001b"}]"#;
+        let (headers, rows) = records_to_values(data).unwrap();
+        let sheet = sheet(to_xlsx(&headers, &rows));
+        assert!(sheet.contains("<is><t>007</t></is>"));
+        assert!(sheet.contains("<v>1.5</v>"));
+        assert!(sheet.contains("t=\"b\"><v>1</v>"));
+        assert!(sheet.contains("<t>ab</t>"));
+    }
+
+    #[test]
+    fn test_records_to_table() {
+        let (headers, rows) = records_to_table(r#"[{"a": 1, "b": "x"}, {"a": 2}]"#).unwrap();
+        assert_eq!(headers, vec!["a", "b"]);
+        assert_eq!(rows[1], vec!["2", ""]);
+    }
+
+    #[test]
+    fn test_format_helpers() {
+        assert_eq!(ExportFormat::from_file_name("data.XLSX"), ExportFormat::Xlsx);
+        assert_eq!(ExportFormat::from_file_name("data.csv"), ExportFormat::Csv);
+        assert_eq!(column_name(27), "AB");
+    }
+}
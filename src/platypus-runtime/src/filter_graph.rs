@@ -0,0 +1,251 @@
+//! Cross-filtering between charts, widgets and tables.
+//!
+//! A [`FilterGraph`] holds one JSON records array. Chart selections and
+//! widget values register as filter sources on its columns, and each
+//! element showing the data is a target filtered by the sources it depends
+//! on. Every rerun reads the current selections, so changing one filter
+//! recomputes the dependent elements:
+//!
+//! ```
+//! use platypus_core::elements::ChartKind;
+//! use platypus_runtime::filter_graph::FilterGraph;
+//! use platypus_runtime::St;
+//!
+//! let sales = r#"[
+//!     {"month": "Jan", "category": "Electronics", "sales": 5000},
+//!     {"month": "Feb", "category": "Clothing", "sales": 3000}
+//! ]"#;
+//! let mut st = St::new();
+//! st.selectbox("Category", vec!["All".into(), "Electronics".into(), "Clothing".into()], 0, Some("category".into()));
+//!
+//! let graph = FilterGraph::from_records(sales).unwrap()
+//!     .widget_source("category", "category")
+//!     .chart_source("by_month", "month")
+//!     .target("table", &["category", "by_month"]);
+//! let filters = graph.apply(&st);
+//! // Targets depend on every other source unless registered, so the chart
+//! // is filtered by category but not by its own selection
+//! st.chart(ChartKind::Bar, filters.records("by_month"), None, "by_month");
+//! st.dataframe(filters.records("table"));
+//! ```
+
+use crate::context::St;
+use crate::explorer::Frame;
+use crate::export;
+use crate::selection::ChartSelection;
+use platypus_core::widget::WidgetValue;
+use std::collections::HashMap;
+
+/// Widget values that select every row.
+const ALL_VALUES: [&str; 2] = ["", "all"];
+
+/// Where a filter's value comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SourceKind {
+    /// A chart selection, matched against the column by the selected x
+    /// values or zoomed x range.
+    Chart,
+    /// A widget value: a comparison or substring as in `Frame::filter`, or
+    /// a multiselect's values.
+    Widget,
+}
+
+#[derive(Debug, Clone)]
+struct Source {
+    key: String,
+    column: String,
+    kind: SourceKind,
+}
+
+/// Filter sources over one dataset and the targets depending on them.
+#[derive(Debug, Clone)]
+pub struct FilterGraph {
+    frame: Frame,
+    sources: Vec<Source>,
+    /// Sources each registered target depends on
+    targets: HashMap<String, Vec<String>>,
+}
+
+impl FilterGraph {
+    /// Create a graph over `frame`.
+    pub fn new(frame: Frame) -> Self {
+        FilterGraph {
+            frame,
+            sources: Vec::new(),
+            targets: HashMap::new(),
+        }
+    }
+
+    /// Create a graph over a JSON records array, as passed to
+    /// `st.dataframe`.
+    pub fn from_records(data: &str) -> Option<Self> {
+        Frame::from_records(data).map(Self::new)
+    }
+
+    /// Filter `column` by the selection of the chart `key`.
+    pub fn chart_source(self, key: impl Into<String>, column: impl Into<String>) -> Self {
+        self.source(key.into(), column.into(), SourceKind::Chart)
+    }
+
+    /// Filter `column` by the value of the widget `key`. Empty values and
+    /// `All` keep every row.
+    pub fn widget_source(self, key: impl Into<String>, column: impl Into<String>) -> Self {
+        self.source(key.into(), column.into(), SourceKind::Widget)
+    }
+
+    fn source(mut self, key: String, column: String, kind: SourceKind) -> Self {
+        self.sources.retain(|source| source.key != key);
+        self.sources.push(Source { key, column, kind });
+        self
+    }
+
+    /// Filter the element `key` by the sources `depends_on` only. Targets
+    /// not registered depend on every source but themselves.
+    pub fn target(mut self, key: impl Into<String>, depends_on: &[&str]) -> Self {
+        self.targets
+            .insert(key.into(), depends_on.iter().map(|source| source.to_string()).collect());
+        self
+    }
+
+    /// Read the current value of every source from `st`.
+    pub fn apply(&self, st: &St) -> FilterState<'_> {
+        let values = self
+            .sources
+            .iter()
+            .filter_map(|source| {
+                let filter = match source.kind {
+                    SourceKind::Chart => st.chart_selection(&source.key).map(ActiveFilter::Chart),
+                    SourceKind::Widget => st.delta_gen().get_widget(&source.key).and_then(widget_filter),
+                }?;
+                Some((source.key.clone(), filter))
+            })
+            .collect();
+        FilterState { graph: self, values }
+    }
+}
+
+/// A source's current value.
+#[derive(Debug, Clone)]
+enum ActiveFilter {
+    Chart(ChartSelection),
+    Query(String),
+    OneOf(Vec<String>),
+}
+
+fn widget_filter(value: WidgetValue) -> Option<ActiveFilter> {
+    match value {
+        WidgetValue::String(query) if ALL_VALUES.contains(&query.trim().to_lowercase().as_str()) => None,
+        WidgetValue::String(query) => Some(ActiveFilter::Query(query)),
+        WidgetValue::Number(number) => Some(ActiveFilter::Query(format!("={}", number))),
+        WidgetValue::Bool(flag) => Some(ActiveFilter::OneOf(vec![flag.to_string()])),
+        WidgetValue::StringArray(values) if values.is_empty() => None,
+        WidgetValue::StringArray(values) => Some(ActiveFilter::OneOf(values)),
+        _ => None,
+    }
+}
+
+/// The filters in effect for one run.
+#[derive(Debug, Clone)]
+pub struct FilterState<'a> {
+    graph: &'a FilterGraph,
+    values: HashMap<String, ActiveFilter>,
+}
+
+impl FilterState<'_> {
+    /// Check if any source currently filters.
+    pub fn is_active(&self) -> bool {
+        !self.values.is_empty()
+    }
+
+    /// Get the rows for the target `key`, filtered by the sources it
+    /// depends on.
+    pub fn frame(&self, key: &str) -> Frame {
+        let depends_on = |source: &Source| match self.graph.targets.get(key) {
+            Some(sources) => sources.contains(&source.key),
+            None => source.key != key,
+        };
+        let mut frame = self.graph.frame.clone();
+        for source in self.graph.sources.iter().filter(|source| depends_on(source)) {
+            let (Some(filter), Some(column)) = (self.values.get(&source.key), frame.column(&source.column)) else {
+                continue;
+            };
+            frame = match filter {
+                ActiveFilter::Query(query) => frame.filter(&source.column, query),
+                ActiveFilter::OneOf(values) => keep_rows(frame, column, |cell| values.iter().any(|value| value == cell)),
+                ActiveFilter::Chart(selection) => filter_by_selection(frame, column, selection),
+            };
+        }
+        frame
+    }
+
+    /// Get the rows for the target `key` as a JSON records array.
+    pub fn records(&self, key: &str) -> String {
+        self.frame(key).to_records()
+    }
+}
+
+fn keep_rows(frame: Frame, column: usize, cell_matches: impl Fn(&str) -> bool) -> Frame {
+    Frame {
+        rows: frame.rows.into_iter().filter(|row| cell_matches(&row[column])).collect(),
+        headers: frame.headers,
+    }
+}
+
+fn filter_by_selection(frame: Frame, column: usize, selection: &ChartSelection) -> Frame {
+    match selection {
+        ChartSelection::Zoom { x: Some(range), .. } => keep_rows(frame, column, |cell| {
+            cell.trim().parse::<f64>().is_ok_and(|value| range.contains(value))
+        }),
+        ChartSelection::Click { .. } | ChartSelection::Select { .. } => {
+            let selected: Vec<String> = selection.points().iter().map(|point| export::cell_text(Some(&point.x))).collect();
+            keep_rows(frame, column, |cell| selected.iter().any(|value| value == cell))
+        }
+        ChartSelection::Zoom { x: None, .. } | ChartSelection::Clear => frame,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::selection::SelectedPoint;
+
+    const SALES: &str = r#"[
+        {"month": "Jan", "category": "Electronics", "sales": 5000},
+        {"month": "Jan", "category": "Clothing", "sales": 1200},
+        {"month": "Feb", "category": "Electronics", "sales": 4000},
+        {"month": "Mar", "category": "Clothing", "sales": 3000}
+    ]"#;
+
+    #[test]
+    fn test_targets_filtered_by_their_sources() {
+        let st = St::new();
+        st.delta_gen().set_widget("category".to_string(), WidgetValue::String("Electronics".to_string()));
+        let click = ChartSelection::Click {
+            point: SelectedPoint {
+                index: 0,
+                series: None,
+                x: "Jan".into(),
+                y: 6200.into(),
+            },
+        };
+        st.delta_gen().set_widget(
+            crate::selection::widget_key("by_month"),
+            WidgetValue::String(serde_json::to_string(&click).unwrap()),
+        );
+
+        let graph = FilterGraph::from_records(SALES)
+            .unwrap()
+            .widget_source("category", "category")
+            .chart_source("by_month", "month")
+            .target("totals", &[]);
+        let filters = graph.apply(&st);
+        assert!(filters.is_active());
+        // The chart is not filtered by its own selection
+        assert_eq!(filters.frame("by_month").rows.len(), 2);
+        assert_eq!(filters.frame("table").rows.len(), 1);
+        assert_eq!(filters.frame("totals").rows.len(), 4);
+
+        st.delta_gen().set_widget("category".to_string(), WidgetValue::String("All".to_string()));
+        assert_eq!(graph.apply(&st).frame("by_month").rows.len(), 4);
+    }
+}
@@ -0,0 +1,173 @@
+//! Financial charts.
+//!
+//! `st.candlestick_chart(&candles)` plots OHLC data; overlays such as
+//! moving averages are computed server-side and sent with the candles:
+//!
+//! ```
+//! use platypus_runtime::finance::{Candle, CandlestickOptions};
+//! use platypus_runtime::St;
+//!
+//! let candles = vec![
+//!     Candle::new("2024-01-02", 100.0, 104.0, 99.0, 103.0).volume(1_200.0),
+//!     Candle::new("2024-01-03", 103.0, 105.0, 101.0, 102.0).volume(900.0),
+//! ];
+//! let mut st = St::new();
+//! st.candlestick_chart_with(&candles, CandlestickOptions::new().moving_average(20).with_volume());
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+/// One period of price data.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Candle {
+    /// Period label, e.g. an ISO date.
+    pub time: String,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    #[serde(default)]
+    pub volume: Option<f64>,
+}
+
+impl Candle {
+    /// Create a candle without volume.
+    pub fn new(time: impl Into<String>, open: f64, high: f64, low: f64, close: f64) -> Self {
+        Candle {
+            time: time.into(),
+            open,
+            high,
+            low,
+            close,
+            volume: None,
+        }
+    }
+
+    /// Set the traded volume.
+    pub fn volume(mut self, volume: f64) -> Self {
+        self.volume = Some(volume);
+        self
+    }
+}
+
+/// Overlays and panes of a candlestick chart.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CandlestickOptions {
+    pub title: Option<String>,
+    /// Simple moving average periods, each drawn as a line.
+    pub moving_averages: Vec<usize>,
+    /// Draw a volume pane below the prices.
+    pub volume: bool,
+    /// Report clicked and selected candles under this key.
+    pub key: Option<String>,
+}
+
+impl CandlestickOptions {
+    /// Create options without overlays.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the chart title.
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Overlay a simple moving average of the closes over `period` candles.
+    pub fn moving_average(mut self, period: usize) -> Self {
+        if period > 0 && !self.moving_averages.contains(&period) {
+            self.moving_averages.push(period);
+        }
+        self
+    }
+
+    /// Report clicked and selected candles, read back with
+    /// `st.chart_selection(key)`.
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    /// Show traded volume in a pane below the prices.
+    pub fn with_volume(mut self) -> Self {
+        self.volume = true;
+        self
+    }
+}
+
+/// A line drawn over the candles, one value per candle.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Overlay {
+    pub name: String,
+    /// `None` until enough candles for the overlay have passed.
+    pub values: Vec<Option<f64>>,
+}
+
+/// Chart data sent to the client.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CandlestickData {
+    pub candles: Vec<Candle>,
+    pub overlays: Vec<Overlay>,
+    pub volume: bool,
+}
+
+impl CandlestickData {
+    /// Compute the chart data for `candles` with `options`' overlays.
+    pub fn new(candles: &[Candle], options: &CandlestickOptions) -> Self {
+        let closes: Vec<f64> = candles.iter().map(|candle| candle.close).collect();
+        CandlestickData {
+            candles: candles.to_vec(),
+            overlays: options
+                .moving_averages
+                .iter()
+                .map(|&period| Overlay {
+                    name: format!("MA {}", period),
+                    values: moving_average(&closes, period),
+                })
+                .collect(),
+            volume: options.volume,
+        }
+    }
+}
+
+/// Simple moving average over `period` values.
+pub fn moving_average(values: &[f64], period: usize) -> Vec<Option<f64>> {
+    if period == 0 {
+        return vec![None; values.len()];
+    }
+    let mut sum = 0.0;
+    values
+        .iter()
+        .enumerate()
+        .map(|(index, value)| {
+            sum += value;
+            if index >= period {
+                sum -= values[index - period];
+            }
+            (index + 1 >= period).then(|| sum / period as f64)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_moving_average() {
+        let averages = moving_average(&[1.0, 2.0, 3.0, 4.0], 2);
+        assert_eq!(averages, vec![None, Some(1.5), Some(2.5), Some(3.5)]);
+    }
+
+    #[test]
+    fn test_candlestick_data_json() {
+        let candles = vec![Candle::new("d1", 1.0, 2.0, 0.5, 1.5), Candle::new("d2", 1.5, 3.0, 1.0, 2.5).volume(10.0)];
+        let data = CandlestickData::new(&candles, &CandlestickOptions::new().moving_average(2).with_volume());
+        let json = serde_json::to_value(&data).unwrap();
+        assert_eq!(json["overlays"][0]["name"], "MA 2");
+        assert_eq!(json["overlays"][0]["values"][1], 2.0);
+        assert_eq!(json["candles"][1]["volume"], 10.0);
+        assert_eq!(json["volume"], true);
+    }
+}
@@ -0,0 +1,210 @@
+//! Struct-backed forms.
+//!
+//! `#[derive(Form)]` renders one widget per field, picked by the field's
+//! [`FormField`] impl, and collects the values back into the struct:
+//!
+//! ```
+//! use platypus_runtime::{Form, St};
+//!
+//! #[derive(Form, Default)]
+//! struct ServerConfig {
+//!     host: String,
+//!     #[form(label = "Port number", validate = "check_port")]
+//!     port: u16,
+//!     verbose: bool,
+//! }
+//!
+//! fn check_port(port: &u16) -> Result<(), String> {
+//!     if *port == 0 { Err("must not be 0".to_string()) } else { Ok(()) }
+//! }
+//!
+//! let mut st = St::new();
+//! let result = st.form_for::<ServerConfig>("server");
+//! assert!(result.is_err());
+//! ```
+
+use crate::context::St;
+use crate::datetime;
+use chrono::NaiveDate;
+use platypus_core::element::{ElementId, ElementType};
+use platypus_core::state::Delta;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// A struct rendered as a group of widgets. Usually derived.
+pub trait Form: Sized {
+    /// Render a widget per field and collect the values.
+    ///
+    /// Widget keys are `"{key}.{field}"`.
+    fn render_form(st: &mut St, key: &str) -> Result<Self, FormErrors>;
+}
+
+/// A type that renders as a single form widget.
+pub trait FormField: Sized {
+    /// Render the widget, starting from `default`, and return its value.
+    fn render_field(st: &mut St, label: &str, default: Self, key: String) -> Self;
+}
+
+/// A field that failed validation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldError {
+    pub field: String,
+    pub label: String,
+    pub message: String,
+}
+
+/// Validation failures for a form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormErrors(pub Vec<FieldError>);
+
+impl fmt::Display for FormErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let messages: Vec<String> = self
+            .0
+            .iter()
+            .map(|e| format!("{}: {}", e.label, e.message))
+            .collect();
+        write!(f, "{}", messages.join("; "))
+    }
+}
+
+impl std::error::Error for FormErrors {}
+
+impl From<FormErrors> for String {
+    fn from(errors: FormErrors) -> Self {
+        errors.to_string()
+    }
+}
+
+/// Collect the validation errors of the elements in `container`, at any
+/// depth, in page order. Widgets' errors are prefixed with their label;
+/// error messages, e.g. from `form_for`, are taken as they are.
+pub(crate) fn validation_errors(deltas: &[Delta], container: ElementId) -> Vec<String> {
+    let mut inside = HashSet::from([container]);
+    let mut order = Vec::new();
+    let mut errors: HashMap<ElementId, Vec<String>> = HashMap::new();
+    for delta in deltas {
+        let (id, element) = match delta {
+            Delta::AddElement { id, element, parent_id: Some(parent) } if inside.contains(parent) => {
+                inside.insert(*id);
+                order.push(*id);
+                (id, element)
+            }
+            Delta::UpdateElement { id, element } if inside.contains(id) => (id, element),
+            _ => continue,
+        };
+        errors.insert(*id, element_errors(element));
+    }
+    order.iter().flat_map(|id| errors.remove(id).unwrap_or_default()).collect()
+}
+
+fn element_errors(element: &ElementType) -> Vec<String> {
+    match element {
+        ElementType::TextInput { label, error: Some(error), .. }
+        | ElementType::TagsInput { label, error: Some(error), .. }
+        | ElementType::CodeEditor { label, error: Some(error), .. }
+        | ElementType::RichTextEditor { label, error: Some(error), .. } => vec![format!("{}: {}", label, error)],
+        ElementType::JsonEditor { errors, .. } => errors
+            .iter()
+            .map(|error| format!("{}: {}", error.path, error.message))
+            .collect(),
+        ElementType::Error { message } => vec![message.clone()],
+        _ => vec![],
+    }
+}
+
+impl FormField for String {
+    fn render_field(st: &mut St, label: &str, default: Self, key: String) -> Self {
+        st.text_input(label, default, Some(key))
+    }
+}
+
+impl FormField for Option<String> {
+    fn render_field(st: &mut St, label: &str, default: Self, key: String) -> Self {
+        let value = st.text_input(label, default.unwrap_or_default(), Some(key));
+        if value.is_empty() { None } else { Some(value) }
+    }
+}
+
+impl FormField for bool {
+    fn render_field(st: &mut St, label: &str, default: Self, key: String) -> Self {
+        st.checkbox(label, default, Some(key))
+    }
+}
+
+impl FormField for NaiveDate {
+    fn render_field(st: &mut St, label: &str, default: Self, key: String) -> Self {
+        let value = st.date_input(label, default.format(datetime::DATE_FORMAT).to_string(), Some(key));
+        NaiveDate::parse_from_str(&value, datetime::DATE_FORMAT).unwrap_or(default)
+    }
+}
+
+macro_rules! number_form_field {
+    ($($ty:ty),*) => {
+        $(
+            impl FormField for $ty {
+                fn render_field(st: &mut St, label: &str, default: Self, key: String) -> Self {
+                    st.number_input(label, default as f64, Some(key)) as $ty
+                }
+            }
+        )*
+    };
+}
+
+number_form_field!(f32, f64);
+
+macro_rules! integer_form_field {
+    ($($ty:ty),*) => {
+        $(
+            impl FormField for $ty {
+                // `as` saturates at the type's bounds
+                fn render_field(st: &mut St, label: &str, default: Self, key: String) -> Self {
+                    st.number_input(label, default as f64, Some(key)).round() as $ty
+                }
+            }
+        )*
+    };
+}
+
+integer_form_field!(i8, i16, i32, i64, u8, u16, u32, u64, usize);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Form;
+    use platypus_core::widget::WidgetValue;
+
+    #[derive(Form, Default, Debug, PartialEq)]
+    struct Profile {
+        name: String,
+        #[form(validate = "adult")]
+        age: u32,
+        #[form(skip)]
+        internal_id: u64,
+    }
+
+    fn adult(age: &u32) -> Result<(), String> {
+        if *age >= 18 { Ok(()) } else { Err("must be at least 18".to_string()) }
+    }
+
+    #[test]
+    fn test_form_collects_fields() {
+        let mut st = St::new();
+        st.delta_gen().set_widget("profile.name".to_string(), WidgetValue::String("Ada".to_string()));
+        st.delta_gen().set_widget("profile.age".to_string(), WidgetValue::Number(36.4));
+
+        let profile = st.form_for::<Profile>("profile").unwrap();
+        assert_eq!(profile, Profile { name: "Ada".to_string(), age: 36, internal_id: 0 });
+        // Two widgets; the skipped field renders nothing
+        assert_eq!(st.delta_gen().take_deltas().len(), 2);
+    }
+
+    #[test]
+    fn test_form_reports_validation_errors() {
+        let mut st = St::new();
+        let errors = st.form_for::<Profile>("profile").unwrap_err();
+        assert_eq!(errors.0.len(), 1);
+        assert_eq!(errors.0[0].field, "age");
+        assert_eq!(errors.to_string(), "Age: must be at least 18");
+    }
+}
@@ -0,0 +1,319 @@
+//! Locale-aware number formatting and parsing.
+//!
+//! Numbers are formatted server-side before serialization so metrics,
+//! number inputs and table cells render consistently regardless of the
+//! browser. Separators default from the session locale.
+//!
+//! Number inputs typed by hand arrive as text in the same conventions
+//! ("1.234,5" in de-DE); [`parse_number`] reads them back.
+
+/// Separator conventions for a locale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocaleConventions {
+    pub decimal_separator: char,
+    pub group_separator: char,
+    /// Whether currency symbols go before the amount ("$1.00" vs "1,00 €").
+    pub currency_prefix: bool,
+}
+
+impl LocaleConventions {
+    /// Look up conventions for a BCP 47 locale (defaults to en-US).
+    pub fn for_locale(locale: &str) -> Self {
+        let locale = locale.to_ascii_lowercase().replace('_', "-");
+        let language = locale.split('-').next().unwrap_or("");
+
+        let (decimal_separator, group_separator, currency_prefix) = match language {
+            _ if locale == "de-ch" => ('.', '\'', true),
+            "de" | "es" | "it" | "nl" | "pt" | "id" | "tr" | "da" => (',', '.', false),
+            "fr" | "ru" | "pl" | "cs" | "sv" | "nb" | "fi" | "uk" => (',', '\u{a0}', false),
+            _ => ('.', ',', true),
+        };
+
+        LocaleConventions {
+            decimal_separator,
+            group_separator,
+            currency_prefix,
+        }
+    }
+}
+
+/// How a number is presented.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum NumberStyle {
+    #[default]
+    Decimal,
+    /// Currency amount with an ISO 4217 code (e.g. "USD").
+    Currency(String),
+    /// Fraction shown as a percentage (0.25 -> 25%).
+    Percent,
+}
+
+/// Number format configurable per element.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NumberFormat {
+    style: NumberStyle,
+    decimals: Option<usize>,
+    significant_digits: Option<usize>,
+    grouping: bool,
+    locale: Option<String>,
+}
+
+impl NumberFormat {
+    /// Plain decimal with thousands separators.
+    pub fn decimal() -> Self {
+        NumberFormat {
+            style: NumberStyle::Decimal,
+            decimals: None,
+            significant_digits: None,
+            grouping: true,
+            locale: None,
+        }
+    }
+
+    /// Currency amount (two decimals unless overridden).
+    pub fn currency(code: impl Into<String>) -> Self {
+        NumberFormat {
+            style: NumberStyle::Currency(code.into().to_ascii_uppercase()),
+            decimals: Some(2),
+            ..Self::decimal()
+        }
+    }
+
+    /// Percentage of a fraction.
+    pub fn percent() -> Self {
+        NumberFormat {
+            style: NumberStyle::Percent,
+            decimals: Some(0),
+            ..Self::decimal()
+        }
+    }
+
+    /// Fix the number of decimal places.
+    pub fn decimals(mut self, decimals: usize) -> Self {
+        self.decimals = Some(decimals);
+        self.significant_digits = None;
+        self
+    }
+
+    /// Round to a number of significant digits.
+    pub fn significant_digits(mut self, digits: usize) -> Self {
+        self.significant_digits = Some(digits.max(1));
+        self.decimals = None;
+        self
+    }
+
+    /// Enable or disable thousands separators.
+    pub fn grouping(mut self, grouping: bool) -> Self {
+        self.grouping = grouping;
+        self
+    }
+
+    /// Use a fixed locale instead of the session's.
+    pub fn locale(mut self, locale: impl Into<String>) -> Self {
+        self.locale = Some(locale.into());
+        self
+    }
+
+    /// Parse a format spec: `decimal`, `decimal:2`, `percent`, `percent:1`
+    /// or `currency:USD`.
+    pub fn parse(spec: &str) -> Option<Self> {
+        let (style, arg) = match spec.trim().split_once(':') {
+            Some((style, arg)) => (style, Some(arg.trim())),
+            None => (spec.trim(), None),
+        };
+        let with_decimals = |format: NumberFormat| match arg {
+            Some(decimals) => decimals.parse().ok().map(|d| format.decimals(d)),
+            None => Some(format),
+        };
+
+        match style {
+            "decimal" => with_decimals(Self::decimal()),
+            "percent" => with_decimals(Self::percent()),
+            "currency" => arg.filter(|code| !code.is_empty()).map(Self::currency),
+            _ => None,
+        }
+    }
+
+    /// Format a number, using `default_locale` unless a locale was set.
+    pub fn format(&self, value: f64, default_locale: &str) -> String {
+        if !value.is_finite() {
+            return value.to_string();
+        }
+
+        let conventions = LocaleConventions::for_locale(self.locale.as_deref().unwrap_or(default_locale));
+        let value = match self.style {
+            NumberStyle::Percent => value * 100.0,
+            _ => value,
+        };
+
+        let digits = match (self.significant_digits, self.decimals) {
+            (Some(significant), _) => round_significant(value, significant),
+            (None, Some(decimals)) => format!("{:.*}", decimals, value.abs()),
+            (None, None) => trim_decimals(format!("{:.6}", value.abs())),
+        };
+        let negative = value < 0.0 && digits.chars().any(|c| c.is_ascii_digit() && c != '0');
+        let number = localize(&digits, conventions, self.grouping);
+        let sign = if negative { "-" } else { "" };
+
+        match &self.style {
+            NumberStyle::Decimal => format!("{}{}", sign, number),
+            NumberStyle::Percent => format!("{}{}%", sign, number),
+            NumberStyle::Currency(code) => {
+                let symbol = currency_symbol(code);
+                if conventions.currency_prefix {
+                    format!("{}{}{}", sign, symbol, number)
+                } else {
+                    format!("{}{}\u{a0}{}", sign, number, symbol)
+                }
+            }
+        }
+    }
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        Self::decimal()
+    }
+}
+
+fn currency_symbol(code: &str) -> String {
+    match code {
+        "USD" => "$".to_string(),
+        "EUR" => "€".to_string(),
+        "GBP" => "£".to_string(),
+        "JPY" | "CNY" => "¥".to_string(),
+        "INR" => "₹".to_string(),
+        "KRW" => "₩".to_string(),
+        other => format!("{}\u{a0}", other),
+    }
+}
+
+/// Parse a number written in the conventions of `locale`.
+///
+/// Group separators must split the integer part into groups of three
+/// ("1,234.5" but not "1,5" in en-US); a space may stand in for a
+/// non-breaking one. Plain "1234.5" is accepted in every locale unless the
+/// locale groups with dots.
+pub fn parse_number(text: &str, locale: &str) -> Option<f64> {
+    let conventions = LocaleConventions::for_locale(locale);
+    let text = text.trim().replace(' ', "\u{a0}");
+    let (sign, unsigned) = match text.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", text.strip_prefix('+').unwrap_or(&text)),
+    };
+    let (integer, fraction) = match unsigned.split_once(conventions.decimal_separator) {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None if conventions.group_separator != '.' => match unsigned.split_once('.') {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (unsigned, None),
+        },
+        None => (unsigned, None),
+    };
+
+    let groups: Vec<&str> = integer.split(conventions.group_separator).collect();
+    let grouped = groups.len() > 1;
+    let valid_groups = groups.iter().enumerate().all(|(i, group)| {
+        let digits = !group.is_empty() && group.chars().all(|c| c.is_ascii_digit());
+        digits && (!grouped || if i == 0 { group.len() <= 3 } else { group.len() == 3 })
+    });
+    let valid_fraction = fraction.is_none_or(|f| !f.is_empty() && f.chars().all(|c| c.is_ascii_digit()));
+    if !valid_groups || !valid_fraction {
+        return None;
+    }
+
+    let mut normalized = format!("{}{}", sign, groups.concat());
+    if let Some(fraction) = fraction {
+        normalized.push('.');
+        normalized.push_str(fraction);
+    }
+    normalized.parse().ok()
+}
+
+/// Format the absolute value rounded to `significant` digits.
+fn round_significant(value: f64, significant: usize) -> String {
+    let value = value.abs();
+    if value == 0.0 {
+        return "0".to_string();
+    }
+    let magnitude = value.log10().floor() as i32;
+    let decimals = (significant as i32 - 1 - magnitude).max(0) as usize;
+    let scale = 10f64.powi(significant as i32 - 1 - magnitude);
+    let rounded = (value * scale).round() / scale;
+    trim_decimals(format!("{:.*}", decimals, rounded))
+}
+
+fn trim_decimals(digits: String) -> String {
+    if digits.contains('.') {
+        digits.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        digits
+    }
+}
+
+/// Apply locale separators to an unsigned "1234.5" style string.
+fn localize(digits: &str, conventions: LocaleConventions, grouping: bool) -> String {
+    let (integer, fraction) = match digits.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (digits, None),
+    };
+
+    let mut out = String::new();
+    for (i, c) in integer.chars().enumerate() {
+        if grouping && i > 0 && (integer.len() - i) % 3 == 0 {
+            out.push(conventions.group_separator);
+        }
+        out.push(c);
+    }
+    if let Some(fraction) = fraction {
+        out.push(conventions.decimal_separator);
+        out.push_str(fraction);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decimal_grouping() {
+        assert_eq!(NumberFormat::decimal().format(1234567.5, "en-US"), "1,234,567.5");
+        assert_eq!(NumberFormat::decimal().format(1234567.5, "de-DE"), "1.234.567,5");
+        assert_eq!(NumberFormat::decimal().grouping(false).format(-1234.0, "en"), "-1234");
+    }
+
+    #[test]
+    fn test_currency_and_percent() {
+        assert_eq!(NumberFormat::currency("usd").format(1234.5, "en-US"), "$1,234.50");
+        assert_eq!(NumberFormat::currency("EUR").format(1234.5, "fr-FR"), "1\u{a0}234,50\u{a0}€");
+        assert_eq!(NumberFormat::percent().format(0.256, "en-US"), "26%");
+    }
+
+    #[test]
+    fn test_parse_spec() {
+        assert_eq!(NumberFormat::parse("currency:eur"), Some(NumberFormat::currency("EUR")));
+        assert_eq!(NumberFormat::parse("decimal:2"), Some(NumberFormat::decimal().decimals(2)));
+        assert_eq!(NumberFormat::parse("percent"), Some(NumberFormat::percent()));
+        assert_eq!(NumberFormat::parse("currency"), None);
+        assert_eq!(NumberFormat::parse("bogus"), None);
+    }
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number("1,234.5", "en-US"), Some(1234.5));
+        assert_eq!(parse_number("1.234,5", "de-DE"), Some(1234.5));
+        assert_eq!(parse_number("-1 234,5", "fr-FR"), Some(-1234.5));
+        assert_eq!(parse_number("1'234.5", "de-CH"), Some(1234.5));
+        assert_eq!(parse_number("0.25", "fr-FR"), Some(0.25));
+        assert_eq!(parse_number("1.234", "de-DE"), Some(1234.0));
+        assert_eq!(parse_number("1,5", "en-US"), None);
+        assert_eq!(parse_number("12,34,567", "en-US"), None);
+        assert_eq!(parse_number("abc", "en-US"), None);
+    }
+
+    #[test]
+    fn test_significant_digits() {
+        assert_eq!(NumberFormat::decimal().significant_digits(3).format(123456.0, "en"), "123,000");
+        assert_eq!(NumberFormat::decimal().significant_digits(2).format(0.012345, "en"), "0.012");
+    }
+}
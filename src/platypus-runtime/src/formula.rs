@@ -0,0 +1,591 @@
+//! Spreadsheet-style formulas over grids of cells.
+//!
+//! Budgeting and planning apps let users edit a grid and expect totals to
+//! follow. Cells starting with `=` are formulas over the other cells, with
+//! arithmetic, `A1` references, `A1:B3` ranges and the functions `SUM`,
+//! `AVG` (or `AVERAGE`), `MIN`, `MAX` and `COUNT`:
+//!
+//! ```
+//! use platypus_runtime::formula::{self, CellValue};
+//!
+//! let grid = vec![
+//!     vec!["Rent".to_string(), "1200".to_string()],
+//!     vec!["Food".to_string(), "450.5".to_string()],
+//!     vec!["Total".to_string(), "=SUM(B1:B2)".to_string()],
+//! ];
+//! let values = formula::evaluate(&grid);
+//! assert_eq!(values[2][1], CellValue::Number(1650.5));
+//! ```
+//!
+//! The cells of a [`St::data_editor`](crate::St::data_editor) grid are
+//! evaluated server-side on every edit, so the browser only shows results. A formula that cannot be evaluated does not fail the
+//! grid: its cell holds an error such as `#CYCLE!` for a formula that
+//! depends on itself, and formulas using that cell show the error too.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// Why a formula has no value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormulaError {
+    /// The formula depends on itself.
+    Cycle,
+    /// Text where a number was expected.
+    Value,
+    /// Division by zero, or the average of no numbers.
+    DivZero,
+    /// Unknown function.
+    Name,
+    /// The formula does not parse.
+    Syntax,
+}
+
+impl fmt::Display for FormulaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FormulaError::Cycle => "#CYCLE!",
+            FormulaError::Value => "#VALUE!",
+            FormulaError::DivZero => "#DIV/0!",
+            FormulaError::Name => "#NAME?",
+            FormulaError::Syntax => "#ERROR!",
+        })
+    }
+}
+
+/// Value of an evaluated cell.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CellValue {
+    Empty,
+    Number(f64),
+    Text(String),
+    Error(FormulaError),
+}
+
+impl fmt::Display for CellValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CellValue::Empty => Ok(()),
+            CellValue::Number(n) => write!(f, "{}", n),
+            CellValue::Text(text) => f.write_str(text),
+            CellValue::Error(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+/// A cell reference such as `B3`, zero-based.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CellRef {
+    pub row: usize,
+    pub col: usize,
+}
+
+impl FromStr for CellRef {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let split = s.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(s.len());
+        let (letters, digits) = s.split_at(split);
+        let row: usize = digits.parse().ok().filter(|row| *row > 0).ok_or_else(|| format!("Invalid cell {:?}", s))?;
+        if letters.is_empty() || letters.len() > 3 {
+            return Err(format!("Invalid cell {:?}", s));
+        }
+        let col = letters
+            .chars()
+            .fold(0, |col, c| col * 26 + (c.to_ascii_uppercase() as usize - 'A' as usize + 1));
+        Ok(CellRef { row: row - 1, col: col - 1 })
+    }
+}
+
+impl fmt::Display for CellRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut letters = Vec::new();
+        let mut col = self.col + 1;
+        while col > 0 {
+            letters.push((b'A' + ((col - 1) % 26) as u8) as char);
+            col = (col - 1) / 26;
+        }
+        letters.reverse();
+        write!(f, "{}{}", letters.into_iter().collect::<String>(), self.row + 1)
+    }
+}
+
+/// Check whether a cell holds a formula.
+pub fn is_formula(cell: &str) -> bool {
+    cell.trim_start().starts_with('=')
+}
+
+/// How deeply parentheses, signs and function calls may nest in a formula.
+const MAX_NESTING: usize = 64;
+
+/// Evaluate every cell of `grid`, given as rows of cell text.
+pub fn evaluate(grid: &[Vec<String>]) -> Vec<Vec<CellValue>> {
+    let formulas: Vec<Vec<Option<Result<Expr, FormulaError>>>> = grid
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|text| text.trim_start().strip_prefix('=').map(|formula| Parser::new(formula).parse()))
+                .collect()
+        })
+        .collect();
+    let mut sheet = Sheet {
+        grid,
+        values: grid.iter().map(|row| row.iter().map(|text| literal(text)).collect()).collect(),
+    };
+    sheet.resolve(&formulas);
+    sheet.values
+}
+
+/// Get the value of a cell that is not a formula.
+fn literal(text: &str) -> CellValue {
+    if is_formula(text) || text.trim().is_empty() {
+        return CellValue::Empty;
+    }
+    match text.trim().parse() {
+        Ok(n) => CellValue::Number(n),
+        Err(_) => CellValue::Text(text.to_string()),
+    }
+}
+
+struct Sheet<'a> {
+    grid: &'a [Vec<String>],
+    values: Vec<Vec<CellValue>>,
+}
+
+/// Progress of a formula cell through [`Sheet::resolve`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Visit {
+    Pending,
+    /// Waiting for its dependencies, at this index of the stack.
+    Active(usize),
+    Done,
+}
+
+/// A formula cell on the [`Sheet::resolve`] stack.
+struct Frame {
+    cell: CellRef,
+    dependencies: Vec<CellRef>,
+    next: usize,
+    /// Whether the cell is part of a cycle.
+    cyclic: bool,
+}
+
+impl Sheet<'_> {
+    /// Evaluate each formula after the formulas it depends on. The walk
+    /// keeps its own stack, so long chains of references such as `A1=A2`,
+    /// `A2=A3`, ... cannot overflow the call stack.
+    fn resolve(&mut self, formulas: &[Vec<Option<Result<Expr, FormulaError>>>]) {
+        let mut visits: Vec<Vec<Visit>> = formulas.iter().map(|row| vec![Visit::Pending; row.len()]).collect();
+        for (row, cells) in formulas.iter().enumerate() {
+            for col in 0..cells.len() {
+                if cells[col].is_none() || visits[row][col] != Visit::Pending {
+                    continue;
+                }
+                let mut stack = vec![self.frame(formulas, CellRef { row, col })];
+                visits[row][col] = Visit::Active(0);
+                while let Some(frame) = stack.last_mut() {
+                    if let Some(&dependency) = frame.dependencies.get(frame.next) {
+                        frame.next += 1;
+                        match visits[dependency.row][dependency.col] {
+                            Visit::Pending => {
+                                visits[dependency.row][dependency.col] = Visit::Active(stack.len());
+                                stack.push(self.frame(formulas, dependency));
+                            }
+                            Visit::Active(index) => stack[index..].iter_mut().for_each(|frame| frame.cyclic = true),
+                            Visit::Done => {}
+                        }
+                        continue;
+                    }
+                    let Frame { cell, cyclic, .. } = stack.pop().expect("stack is not empty");
+                    let value = match &formulas[cell.row][cell.col] {
+                        _ if cyclic => CellValue::Error(FormulaError::Cycle),
+                        Some(Ok(expr)) => self.eval(expr).map_or_else(CellValue::Error, CellValue::Number),
+                        Some(Err(error)) => CellValue::Error(*error),
+                        None => CellValue::Empty,
+                    };
+                    self.values[cell.row][cell.col] = value;
+                    visits[cell.row][cell.col] = Visit::Done;
+                }
+            }
+        }
+    }
+
+    /// Start resolving a formula cell, depending on the formula cells it
+    /// refers to.
+    fn frame(&self, formulas: &[Vec<Option<Result<Expr, FormulaError>>>], cell: CellRef) -> Frame {
+        let mut dependencies = Vec::new();
+        if let Some(Ok(expr)) = &formulas[cell.row][cell.col] {
+            expr.references(&mut |from, to| {
+                dependencies.extend(self.range(from, to).filter(|dep| formulas[dep.row][dep.col].is_some()));
+            });
+        }
+        Frame {
+            cell,
+            dependencies,
+            next: 0,
+            cyclic: false,
+        }
+    }
+
+    /// Get the cells of a range that are in the grid.
+    fn range(&self, from: CellRef, to: CellRef) -> impl Iterator<Item = CellRef> + '_ {
+        let rows = from.row.min(to.row)..(from.row.max(to.row) + 1).min(self.grid.len());
+        let (first_col, last_col) = (from.col.min(to.col), from.col.max(to.col));
+        rows.flat_map(move |row| {
+            (first_col..(last_col + 1).min(self.grid[row].len())).map(move |col| CellRef { row, col })
+        })
+    }
+
+    fn value(&self, cell: CellRef) -> CellValue {
+        // References past the grid are empty cells
+        self.values
+            .get(cell.row)
+            .and_then(|row| row.get(cell.col))
+            .cloned()
+            .unwrap_or(CellValue::Empty)
+    }
+
+    fn number(&self, cell: CellRef) -> Result<f64, FormulaError> {
+        match self.value(cell) {
+            CellValue::Empty => Ok(0.0),
+            CellValue::Number(n) => Ok(n),
+            CellValue::Text(_) => Err(FormulaError::Value),
+            CellValue::Error(error) => Err(error),
+        }
+    }
+
+    fn eval(&self, expr: &Expr) -> Result<f64, FormulaError> {
+        match expr {
+            Expr::Number(n) => Ok(*n),
+            Expr::Cell(cell) => self.number(*cell),
+            // A range is only meaningful as a function argument
+            Expr::Range(..) => Err(FormulaError::Value),
+            Expr::Neg(expr) => Ok(-self.eval(expr)?),
+            Expr::Chain(first, rest) => {
+                let mut left = self.eval(first)?;
+                for (op, right) in rest {
+                    let right = self.eval(right)?;
+                    left = match op {
+                        '+' => left + right,
+                        '-' => left - right,
+                        '*' => left * right,
+                        _ if right == 0.0 => return Err(FormulaError::DivZero),
+                        _ => left / right,
+                    };
+                }
+                Ok(left)
+            }
+            Expr::Call(function, args) => {
+                let mut numbers = Vec::new();
+                for arg in args {
+                    self.collect_numbers(arg, &mut numbers)?;
+                }
+                function.apply(&numbers)
+            }
+        }
+    }
+
+    /// Collect the numbers of a function argument; text and empty cells in
+    /// ranges are skipped.
+    fn collect_numbers(&self, arg: &Expr, numbers: &mut Vec<f64>) -> Result<(), FormulaError> {
+        let Expr::Range(from, to) = arg else {
+            numbers.push(self.eval(arg)?);
+            return Ok(());
+        };
+        for cell in self.range(*from, *to) {
+            match &self.values[cell.row][cell.col] {
+                CellValue::Number(n) => numbers.push(*n),
+                CellValue::Error(error) => return Err(*error),
+                CellValue::Empty | CellValue::Text(_) => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Function {
+    Sum,
+    Avg,
+    Min,
+    Max,
+    Count,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "SUM" => Some(Function::Sum),
+            "AVG" | "AVERAGE" => Some(Function::Avg),
+            "MIN" => Some(Function::Min),
+            "MAX" => Some(Function::Max),
+            "COUNT" => Some(Function::Count),
+            _ => None,
+        }
+    }
+
+    fn apply(self, numbers: &[f64]) -> Result<f64, FormulaError> {
+        // Summing from 0.0 rather than with `sum()`, whose -0.0 start shows
+        // up as the total of no numbers
+        let sum = numbers.iter().fold(0.0, |sum, n| sum + n);
+        match self {
+            Function::Sum => Ok(sum),
+            Function::Avg if numbers.is_empty() => Err(FormulaError::DivZero),
+            Function::Avg => Ok(sum / numbers.len() as f64),
+            Function::Min => Ok(numbers.iter().copied().reduce(f64::min).unwrap_or(0.0)),
+            Function::Max => Ok(numbers.iter().copied().reduce(f64::max).unwrap_or(0.0)),
+            Function::Count => Ok(numbers.len() as f64),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(f64),
+    Cell(CellRef),
+    Range(CellRef, CellRef),
+    Neg(Box<Expr>),
+    /// Operators of one precedence applied from left to right, kept flat
+    /// so long sums do not nest.
+    Chain(Box<Expr>, Vec<(char, Expr)>),
+    Call(Function, Vec<Expr>),
+}
+
+impl Expr {
+    /// Visit the cells and ranges the expression refers to, a cell as a
+    /// range of itself.
+    fn references(&self, visit: &mut impl FnMut(CellRef, CellRef)) {
+        match self {
+            Expr::Number(_) => {}
+            Expr::Cell(cell) => visit(*cell, *cell),
+            Expr::Range(from, to) => visit(*from, *to),
+            Expr::Neg(expr) => expr.references(visit),
+            Expr::Chain(first, rest) => {
+                first.references(visit);
+                rest.iter().for_each(|(_, expr)| expr.references(visit));
+            }
+            Expr::Call(_, args) => args.iter().for_each(|arg| arg.references(visit)),
+        }
+    }
+
+    fn chain(first: Expr, rest: Vec<(char, Expr)>) -> Expr {
+        if rest.is_empty() {
+            first
+        } else {
+            Expr::Chain(Box::new(first), rest)
+        }
+    }
+}
+
+/// Recursive descent parser of formulas (without the leading `=`).
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+    /// Current nesting of parentheses, signs and function calls.
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Parser { input, pos: 0, depth: 0 }
+    }
+
+    fn parse(mut self) -> Result<Expr, FormulaError> {
+        let expr = self.expr()?;
+        self.skip_whitespace();
+        if self.pos < self.input.len() {
+            return Err(FormulaError::Syntax);
+        }
+        Ok(expr)
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.input[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.input[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        let found = self.peek() == Some(expected);
+        if found {
+            self.pos += expected.len_utf8();
+        }
+        found
+    }
+
+    /// Take the longest run of characters matching `f`.
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+        self.skip_whitespace();
+        let rest = &self.input[self.pos..];
+        let len = rest.find(|c| !f(c)).unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    fn expr(&mut self) -> Result<Expr, FormulaError> {
+        let first = self.term()?;
+        let mut rest = Vec::new();
+        while let Some(op @ ('+' | '-')) = self.peek() {
+            self.pos += 1;
+            rest.push((op, self.term()?));
+        }
+        Ok(Expr::chain(first, rest))
+    }
+
+    fn term(&mut self) -> Result<Expr, FormulaError> {
+        let first = self.factor()?;
+        let mut rest = Vec::new();
+        while let Some(op @ ('*' | '/')) = self.peek() {
+            self.pos += 1;
+            rest.push((op, self.factor()?));
+        }
+        Ok(Expr::chain(first, rest))
+    }
+
+    fn factor(&mut self) -> Result<Expr, FormulaError> {
+        // Every nested factor goes through here, which bounds the recursion
+        // of the parser and of evaluating what it returns
+        if self.depth == MAX_NESTING {
+            return Err(FormulaError::Syntax);
+        }
+        self.depth += 1;
+        let factor = self.nested_factor();
+        self.depth -= 1;
+        factor
+    }
+
+    fn nested_factor(&mut self) -> Result<Expr, FormulaError> {
+        match self.peek() {
+            Some('-') => {
+                self.pos += 1;
+                Ok(Expr::Neg(Box::new(self.factor()?)))
+            }
+            Some('+') => {
+                self.pos += 1;
+                self.factor()
+            }
+            Some('(') => {
+                self.pos += 1;
+                let expr = self.expr()?;
+                if !self.eat(')') {
+                    return Err(FormulaError::Syntax);
+                }
+                Ok(expr)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => self
+                .take_while(|c| c.is_ascii_digit() || c == '.')
+                .parse()
+                .map(Expr::Number)
+                .map_err(|_| FormulaError::Syntax),
+            Some(c) if c.is_ascii_alphabetic() => {
+                let name = self.take_while(|c| c.is_ascii_alphanumeric());
+                if self.eat('(') {
+                    let function = Function::from_name(name).ok_or(FormulaError::Name)?;
+                    return Ok(Expr::Call(function, self.args()?));
+                }
+                let cell = name.parse().map_err(|_| FormulaError::Name)?;
+                if !self.eat(':') {
+                    return Ok(Expr::Cell(cell));
+                }
+                let to = self
+                    .take_while(|c| c.is_ascii_alphanumeric())
+                    .parse()
+                    .map_err(|_| FormulaError::Syntax)?;
+                Ok(Expr::Range(cell, to))
+            }
+            _ => Err(FormulaError::Syntax),
+        }
+    }
+
+    /// Parse function arguments after the opening parenthesis.
+    fn args(&mut self) -> Result<Vec<Expr>, FormulaError> {
+        let mut args = Vec::new();
+        if self.eat(')') {
+            return Ok(args);
+        }
+        loop {
+            args.push(self.expr()?);
+            if self.eat(')') {
+                return Ok(args);
+            }
+            if !self.eat(',') {
+                return Err(FormulaError::Syntax);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(rows: &[&[&str]]) -> Vec<Vec<String>> {
+        rows.iter().map(|row| row.iter().map(|cell| cell.to_string()).collect()).collect()
+    }
+
+    #[test]
+    fn test_evaluates_formulas() {
+        let values = evaluate(&grid(&[
+            &["Item", "Q1", "Q2", "Total"],
+            &["Rent", "1200", "1200", "=B2+C2"],
+            &["Food", "400", "", "=SUM(B3:C3)"],
+            &["Sum", "=SUM(B2:B3)", "=sum(C2:C3)", "=SUM(D2:D3) * (1 + 0.1)"],
+            &["Avg", "=AVG(B2:B3)", "=AVERAGE(A2:A3)", "=MAX(B2, C3, 5) - MIN(B2:C3) / COUNT(B2:C3)"],
+        ]));
+        assert_eq!(values[0][0], CellValue::Text("Item".to_string()));
+        assert_eq!(values[1][3], CellValue::Number(2400.0));
+        assert_eq!(values[2][3], CellValue::Number(400.0));
+        assert_eq!(values[3][1], CellValue::Number(1600.0));
+        assert_eq!(values[3][2], CellValue::Number(1200.0));
+        assert_eq!(values[4][1], CellValue::Number(800.0));
+        assert_eq!(values[4][2], CellValue::Error(FormulaError::DivZero));
+        assert_eq!(values[4][3], CellValue::Number(1200.0 - 400.0 / 3.0));
+        assert!((matches!(values[3][3], CellValue::Number(n) if (n - 3080.0).abs() < 1e-9)));
+    }
+
+    #[test]
+    fn test_error_cells() {
+        let values = evaluate(&grid(&[
+            &["=B1", "=A1", "=A1+1"],
+            &["=C2", "text", "=B2*2"],
+            &["=1/0", "=FOO(A1)", "=1+"],
+        ]));
+        assert_eq!(values[0][0], CellValue::Error(FormulaError::Cycle));
+        assert_eq!(values[0][1], CellValue::Error(FormulaError::Cycle));
+        assert_eq!(values[0][2], CellValue::Error(FormulaError::Cycle));
+        assert_eq!(values[1][0], CellValue::Error(FormulaError::Value));
+        assert_eq!(values[2][0], CellValue::Error(FormulaError::DivZero));
+        assert_eq!(values[2][1], CellValue::Error(FormulaError::Name));
+        assert_eq!(values[2][2], CellValue::Error(FormulaError::Syntax));
+        assert_eq!(values[2][2].to_string(), "#ERROR!");
+
+        assert_eq!("AA10".parse::<CellRef>().unwrap(), CellRef { row: 9, col: 26 });
+        assert_eq!(CellRef { row: 9, col: 26 }.to_string(), "AA10");
+        assert!("A0".parse::<CellRef>().is_err());
+    }
+
+    #[test]
+    fn test_bounded_evaluation() {
+        let deep = format!("={}1{}", "(".repeat(10_000), ")".repeat(10_000));
+        let shallow = format!("={}1{}", "(".repeat(20), ")".repeat(20));
+        let values = evaluate(&grid(&[&[&deep, &shallow, "=-----1", "=SUM()", "=SUM(B2:ZZZ200000)"], &["", "2", "x"]]));
+        assert_eq!(values[0][0], CellValue::Error(FormulaError::Syntax));
+        assert_eq!(values[0][1], CellValue::Number(1.0));
+        assert_eq!(values[0][2], CellValue::Number(-1.0));
+        assert!(matches!(values[0][3], CellValue::Number(n) if n == 0.0 && n.is_sign_positive()));
+        assert_eq!(values[0][3].to_string(), "0");
+        assert_eq!(values[0][4], CellValue::Number(2.0));
+
+        // A1=A2, A2=A3, ... resolves without deep recursion
+        let mut chain: Vec<Vec<String>> = (2..=100_000).map(|row| vec![format!("=A{}+1", row)]).collect();
+        chain.push(vec!["1".to_string()]);
+        let values = evaluate(&chain);
+        assert_eq!(values[0][0], CellValue::Number(100_000.0));
+        chain[99_999][0] = "=A1".to_string();
+        let values = evaluate(&chain);
+        assert!(values.iter().all(|row| row[0] == CellValue::Error(FormulaError::Cycle)));
+    }
+}
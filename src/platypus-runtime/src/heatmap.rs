@@ -0,0 +1,227 @@
+//! Heatmaps and correlation matrices.
+//!
+//! `st.heatmap(matrix, x_labels, y_labels, key)` colors each cell of a
+//! matrix on a color scale; clicking a cell reports it back to the app.
+//! `st.corr_matrix(data, key)` builds one from the numeric columns of a JSON
+//! records array:
+//!
+//! ```
+//! use platypus_runtime::St;
+//!
+//! let data = r#"[
+//!     {"price": 2.5, "units": 12, "returns": 1},
+//!     {"price": 3.0, "units": 7, "returns": 2},
+//!     {"price": 4.0, "units": 4, "returns": 2}
+//! ]"#;
+//! let mut st = St::new();
+//! if let Some(cell) = st.corr_matrix(data, "correlations") {
+//!     st.write(format!("{} vs {}", cell.y, cell.x));
+//! }
+//! ```
+
+use crate::explorer::{ColumnKind, Frame};
+use serde::{Deserialize, Serialize};
+
+/// Colors cells are mapped to, from the scale's minimum to its maximum.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorScale {
+    #[default]
+    Viridis,
+    Blues,
+    Reds,
+    Greens,
+    /// Blue through white to red, centered between minimum and maximum.
+    Diverging,
+}
+
+/// Color scale, range and labels of a heatmap.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HeatmapOptions {
+    pub title: Option<String>,
+    pub scale: ColorScale,
+    /// Scale minimum; the smallest value if unset.
+    pub min: Option<f64>,
+    /// Scale maximum; the largest value if unset.
+    pub max: Option<f64>,
+    /// Print each value in its cell.
+    pub show_values: bool,
+}
+
+impl HeatmapOptions {
+    /// Create options with the default scale.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the chart title.
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Set the color scale.
+    pub fn scale(mut self, scale: ColorScale) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Fix the scale range instead of fitting it to the values.
+    pub fn range(mut self, min: f64, max: f64) -> Self {
+        self.min = Some(min);
+        self.max = Some(max);
+        self
+    }
+
+    /// Print each value in its cell.
+    pub fn show_values(mut self) -> Self {
+        self.show_values = true;
+        self
+    }
+}
+
+/// Heatmap data sent to the client.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HeatmapData {
+    pub x_labels: Vec<String>,
+    pub y_labels: Vec<String>,
+    /// One row per y label; `None` for missing or non-finite values.
+    pub values: Vec<Vec<Option<f64>>>,
+    pub scale: ColorScale,
+    pub min: f64,
+    pub max: f64,
+    pub show_values: bool,
+}
+
+impl HeatmapData {
+    /// Build heatmap data, fitting the scale range to the values unless
+    /// `options` fixes it.
+    pub fn new(values: Vec<Vec<Option<f64>>>, x_labels: Vec<String>, y_labels: Vec<String>, options: &HeatmapOptions) -> Self {
+        let finite = || values.iter().flatten().flatten().copied();
+        let min = options.min.or_else(|| finite().reduce(f64::min)).unwrap_or(0.0);
+        let max = options.max.or_else(|| finite().reduce(f64::max)).unwrap_or(0.0);
+        HeatmapData {
+            x_labels,
+            y_labels,
+            values,
+            scale: options.scale,
+            min,
+            max,
+            show_values: options.show_values,
+        }
+    }
+
+    /// Get the cell at `row` and `col`, if in range.
+    pub fn cell(&self, row: usize, col: usize) -> Option<HeatmapCell> {
+        Some(HeatmapCell {
+            x: self.x_labels.get(col)?.clone(),
+            y: self.y_labels.get(row)?.clone(),
+            row,
+            col,
+            value: *self.values.get(row)?.get(col)?,
+        })
+    }
+}
+
+/// A heatmap cell the user clicked.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeatmapCell {
+    pub x: String,
+    pub y: String,
+    pub row: usize,
+    pub col: usize,
+    pub value: Option<f64>,
+}
+
+/// Click position sent by the client.
+#[derive(Debug, Deserialize)]
+pub(crate) struct CellClick {
+    pub row: usize,
+    pub col: usize,
+}
+
+/// Convert a matrix of numbers to heatmap values, dropping non-finite ones.
+pub fn finite_values(matrix: &[Vec<f64>]) -> Vec<Vec<Option<f64>>> {
+    matrix
+        .iter()
+        .map(|row| row.iter().map(|&value| value.is_finite().then_some(value)).collect())
+        .collect()
+}
+
+/// Pearson correlations between the numeric columns of `frame`, over the
+/// rows where both columns have a value. Returns the column names and the
+/// matrix; a correlation is `None` when either column is constant.
+pub fn correlation_matrix(frame: &Frame) -> (Vec<String>, Vec<Vec<Option<f64>>>) {
+    let columns: Vec<usize> = (0..frame.headers.len())
+        .filter(|&column| frame.kind(column) == ColumnKind::Number)
+        .collect();
+    let number = |record: &Vec<String>, column: usize| record[column].trim().parse::<f64>().ok();
+    let matrix = columns
+        .iter()
+        .map(|&a| {
+            columns
+                .iter()
+                .map(|&b| {
+                    let pairs: Vec<(f64, f64)> = frame
+                        .rows
+                        .iter()
+                        .filter_map(|record| Some((number(record, a)?, number(record, b)?)))
+                        .collect();
+                    pearson(&pairs)
+                })
+                .collect()
+        })
+        .collect();
+    let names = columns.iter().map(|&column| frame.headers[column].clone()).collect();
+    (names, matrix)
+}
+
+fn pearson(pairs: &[(f64, f64)]) -> Option<f64> {
+    if pairs.len() < 2 {
+        return None;
+    }
+    let n = pairs.len() as f64;
+    let mean_x = pairs.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = pairs.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let (mut covariance, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+    for (x, y) in pairs {
+        covariance += (x - mean_x) * (y - mean_y);
+        var_x += (x - mean_x).powi(2);
+        var_y += (y - mean_y).powi(2);
+    }
+    let r = covariance / (var_x * var_y).sqrt();
+    r.is_finite().then(|| r.clamp(-1.0, 1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_correlation_matrix() {
+        let frame = Frame::from_records(
+            r#"[
+                {"a": 1, "b": 2, "c": 5, "name": "x"},
+                {"a": 2, "b": 4, "c": 5, "name": "y"},
+                {"a": 3, "b": 6, "c": 5, "name": "z"}
+            ]"#,
+        )
+        .unwrap();
+        let (names, matrix) = correlation_matrix(&frame);
+        assert_eq!(names, vec!["a", "b", "c"]);
+        assert!((matrix[0][1].unwrap() - 1.0).abs() < 1e-9);
+        // A constant column has no correlation
+        assert_eq!(matrix[0][2], None);
+    }
+
+    #[test]
+    fn test_heatmap_range_and_cells() {
+        let values = finite_values(&[vec![1.0, f64::NAN], vec![-2.0, 5.0]]);
+        let labels = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        let data = HeatmapData::new(values, labels(&["x1", "x2"]), labels(&["y1", "y2"]), &HeatmapOptions::new());
+        assert_eq!((data.min, data.max), (-2.0, 5.0));
+        assert_eq!(data.cell(0, 1).unwrap().value, None);
+        assert_eq!(data.cell(1, 0).unwrap().y, "y2");
+        assert!(data.cell(2, 0).is_none());
+    }
+}
@@ -0,0 +1,55 @@
+//! Messages exchanged with the website embedding an app.
+//!
+//! When an app's fragment is framed by another site, the host page can
+//! `postMessage` into the frame and the app reads the messages with
+//! `st.host_messages()`; `st.post_to_host(value)` posts back out. Only
+//! the origins the server's embed config allows take part:
+//!
+//! ```
+//! use platypus_runtime::St;
+//! use serde_json::json;
+//!
+//! let mut st = St::new();
+//! for message in st.host_messages().to_vec() {
+//!     if message.data["type"] == "select_customer" {
+//!         st.write(format!("Customer {}", message.data["id"]));
+//!     }
+//! }
+//! if st.button("Open in portal", Some("open".to_string())) {
+//!     st.post_to_host(json!({"type": "navigate", "path": "/customers"}));
+//! }
+//! ```
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+
+/// A message the host page posted into the app.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HostMessage {
+    /// Origin of the host page, e.g. `https://portal.example.com`.
+    pub origin: String,
+    pub data: Value,
+}
+
+/// Host messages a session received and its app has not read yet.
+#[derive(Clone, Default)]
+pub struct HostMessages(Arc<Mutex<Vec<HostMessage>>>);
+
+impl HostMessages {
+    /// Create an empty queue.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a message for the next run.
+    pub fn push(&self, message: HostMessage) {
+        self.0.lock().push(message);
+    }
+
+    /// Take the queued messages, oldest first.
+    pub fn take(&self) -> Vec<HostMessage> {
+        std::mem::take(&mut *self.0.lock())
+    }
+}
@@ -0,0 +1,89 @@
+//! JSON editor values and JSON Schema validation.
+//!
+//! The editor submits raw text. It is parsed and validated on the server
+//! so the app only ever sees JSON that matches its schema; problems are
+//! rendered inline next to the editor.
+
+use platypus_core::element::JsonEditorError;
+use platypus_core::widget::WidgetValue;
+use serde_json::Value;
+
+/// Parse a JSON editor widget value.
+///
+/// Returns the parsed value, or the submitted text and a parse error.
+pub fn parse_value(value: &WidgetValue) -> Result<Value, (String, JsonEditorError)> {
+    match value {
+        WidgetValue::Json(value) => Ok(value.clone()),
+        WidgetValue::String(text) => serde_json::from_str(text).map_err(|e| {
+            (
+                text.clone(),
+                JsonEditorError {
+                    path: String::new(),
+                    message: format!("Invalid JSON: {}", e),
+                },
+            )
+        }),
+        other => serde_json::to_value(other).map_err(|e| {
+            (
+                String::new(),
+                JsonEditorError {
+                    path: String::new(),
+                    message: e.to_string(),
+                },
+            )
+        }),
+    }
+}
+
+/// Validate a value against a JSON Schema.
+///
+/// An invalid schema is reported as a single error at the root.
+pub fn validate(schema: &Value, value: &Value) -> Vec<JsonEditorError> {
+    let validator = match jsonschema::validator_for(schema) {
+        Ok(validator) => validator,
+        Err(e) => {
+            return vec![JsonEditorError {
+                path: String::new(),
+                message: format!("Invalid schema: {}", e),
+            }]
+        }
+    };
+
+    validator
+        .iter_errors(value)
+        .map(|e| JsonEditorError {
+            path: e.instance_path.to_string(),
+            message: e.to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_value() {
+        let parsed = parse_value(&WidgetValue::String(r#"{"a": 1}"#.to_string())).unwrap();
+        assert_eq!(parsed, json!({"a": 1}));
+
+        let (text, error) = parse_value(&WidgetValue::String("{oops".to_string())).unwrap_err();
+        assert_eq!(text, "{oops");
+        assert!(error.message.starts_with("Invalid JSON"));
+    }
+
+    #[test]
+    fn test_validate_reports_paths() {
+        let schema = json!({
+            "type": "object",
+            "properties": {"port": {"type": "integer", "maximum": 65535}},
+            "required": ["port"],
+        });
+        assert!(validate(&schema, &json!({"port": 8080})).is_empty());
+
+        let errors = validate(&schema, &json!({"port": 70000}));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, "/port");
+    }
+}
@@ -0,0 +1,148 @@
+//! Gauge and donut KPI elements.
+//!
+//! Both are colored from the theme palette by [`Tone`], so they follow the
+//! session's light or dark theme. A gauge takes its tone from thresholds:
+//!
+//! ```
+//! use platypus_runtime::kpi::{DonutSegment, Threshold, Tone};
+//! use platypus_runtime::St;
+//!
+//! let mut st = St::new();
+//! st.gauge(72.0, 0.0, 100.0, &[Threshold::new(60.0, Tone::Warning), Threshold::new(85.0, Tone::Danger)]);
+//! st.donut(&[DonutSegment::new("Stocks", 60.0), DonutSegment::new("Cash", 10.0).tone(Tone::Neutral)]);
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+/// A theme palette color.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Tone {
+    #[default]
+    Neutral,
+    Info,
+    Success,
+    Warning,
+    Danger,
+}
+
+/// Colors gauge values from `from` up to the next threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Threshold {
+    pub from: f64,
+    pub tone: Tone,
+}
+
+impl Threshold {
+    /// Create a threshold.
+    pub fn new(from: f64, tone: Tone) -> Self {
+        Threshold { from, tone }
+    }
+}
+
+/// Get the tone of the highest threshold `value` reaches, or neutral.
+pub fn tone_for(value: f64, thresholds: &[Threshold]) -> Tone {
+    thresholds
+        .iter()
+        .filter(|threshold| value >= threshold.from)
+        .max_by(|a, b| a.from.total_cmp(&b.from))
+        .map_or(Tone::Neutral, |threshold| threshold.tone)
+}
+
+/// Gauge data sent to the client.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GaugeData {
+    pub value: f64,
+    pub min: f64,
+    pub max: f64,
+    /// Tone of the value.
+    pub tone: Tone,
+    /// Thresholds within the range, sorted, drawn as bands.
+    pub thresholds: Vec<Threshold>,
+}
+
+impl GaugeData {
+    /// Build gauge data, coloring `value` by `thresholds`.
+    pub fn new(value: f64, min: f64, max: f64, thresholds: &[Threshold]) -> Self {
+        let (min, max) = if min <= max { (min, max) } else { (max, min) };
+        let mut bands: Vec<Threshold> = thresholds
+            .iter()
+            .filter(|threshold| threshold.from >= min && threshold.from <= max)
+            .copied()
+            .collect();
+        bands.sort_by(|a, b| a.from.total_cmp(&b.from));
+        GaugeData {
+            value,
+            min,
+            max,
+            tone: tone_for(value, thresholds),
+            thresholds: bands,
+        }
+    }
+}
+
+/// One segment of a donut.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DonutSegment {
+    pub label: String,
+    pub value: f64,
+    /// Palette color; segments without one take the next categorical color.
+    pub tone: Option<Tone>,
+}
+
+impl DonutSegment {
+    /// Create a segment.
+    pub fn new(label: impl Into<String>, value: f64) -> Self {
+        DonutSegment {
+            label: label.into(),
+            value,
+            tone: None,
+        }
+    }
+
+    /// Color the segment with `tone`.
+    pub fn tone(mut self, tone: Tone) -> Self {
+        self.tone = Some(tone);
+        self
+    }
+}
+
+/// Donut data sent to the client.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DonutData {
+    /// Segments with a positive value.
+    pub segments: Vec<DonutSegment>,
+    pub total: f64,
+}
+
+impl DonutData {
+    /// Build donut data, dropping empty and negative segments.
+    pub fn new(segments: &[DonutSegment]) -> Self {
+        let segments: Vec<DonutSegment> = segments
+            .iter()
+            .filter(|segment| segment.value.is_finite() && segment.value > 0.0)
+            .cloned()
+            .collect();
+        DonutData {
+            total: segments.iter().map(|segment| segment.value).sum(),
+            segments,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gauge_tone_from_thresholds() {
+        let thresholds = [Threshold::new(85.0, Tone::Danger), Threshold::new(60.0, Tone::Warning)];
+        assert_eq!(tone_for(30.0, &thresholds), Tone::Neutral);
+        assert_eq!(tone_for(60.0, &thresholds), Tone::Warning);
+        assert_eq!(tone_for(90.0, &thresholds), Tone::Danger);
+
+        let gauge = GaugeData::new(72.0, 100.0, 0.0, &thresholds);
+        assert_eq!((gauge.min, gauge.max, gauge.tone), (0.0, 100.0, Tone::Warning));
+        assert_eq!(gauge.thresholds[0].from, 60.0);
+    }
+}
@@ -0,0 +1,24 @@
+//! Content built once the client scrolls it into view.
+//!
+//! `st.lazy(key, build)` leaves a placeholder the first time; when the
+//! client reports it entered the viewport, as
+//! [`Event::ElementVisible`](crate::Event::ElementVisible), the app reruns
+//! and `build` fills its place. Below-the-fold content the user never
+//! scrolls to is never computed:
+//!
+//! ```
+//! use platypus_runtime::St;
+//!
+//! let mut st = St::new();
+//! st.title("Report");
+//! let built = st.lazy("appendix", |st| st.write("A long appendix"));
+//! assert_eq!(built, None);
+//! ```
+
+/// Prefix of the widget keys recording which lazy elements were seen.
+const LAZY_WIDGET_PREFIX: &str = "__lazy:";
+
+/// Get the widget key recording that the lazy element `key` was seen.
+pub fn widget_key(key: &str) -> String {
+    format!("{}{}", LAZY_WIDGET_PREFIX, key)
+}
@@ -3,32 +3,154 @@
 //! This crate provides the runtime engine for executing platypus applications,
 //! managing state, handling events, and generating UI deltas.
 
+// Lets derive output, which names `::platypus_runtime`, compile inside this crate
+extern crate self as platypus_runtime;
+
+pub mod actions;
+pub mod annotation;
+pub mod app_test;
+pub mod backend;
 pub mod cache;
+pub mod chart_stream;
+pub mod checkpoint;
+pub mod client;
+pub mod code_editor;
 pub mod components;
 pub mod context;
+pub mod conversation;
+pub mod datetime;
+pub mod downsample;
+#[cfg(feature = "embeddings")]
+pub mod embeddings;
 pub mod error;
 pub mod event;
+pub mod explorer;
+pub mod export;
+pub mod filter_graph;
+pub mod finance;
+pub mod form;
+pub mod format;
+pub mod formula;
+pub mod heatmap;
+pub mod host;
+pub mod json_editor;
+pub mod kpi;
+pub mod lazy;
+pub mod log_viewer;
+pub mod macros;
+pub mod media;
+pub mod migrations;
 pub mod navigation;
+pub mod notifications;
+pub mod pdf;
+pub mod pivot;
+pub mod presence;
+pub mod progress;
+pub mod render;
+pub mod rich_text;
+pub mod room;
 pub mod secrets;
+pub mod selection;
 pub mod session_store;
+pub mod share;
+pub mod skeleton;
+pub mod speech;
+pub mod sql;
+pub mod state_schema;
+pub mod tabular;
+pub mod tempdir;
+pub mod theme;
+pub mod topic;
+pub mod transcription;
+pub mod transfer;
+pub mod visual;
 
+pub use actions::{Action, ActionQueue, Effect};
+pub use app_test::AppTest;
+pub use backend::SharedBackend;
 pub use cache::{CacheManager, DataCache, ResourceCache};
+pub use chart_stream::{ChartStream, ChartStreamStore, StreamPoint};
+pub use checkpoint::{CancellationToken, FlushFn, Interrupted};
+pub use client::{ClientInfo, ColorScheme, Geolocation};
+pub use code_editor::{AnnotationSeverity, CodeEditorBuilder, CodeEditorHandle};
 pub use components::{ComponentInstance, ComponentMetadata, ComponentProperty, ComponentRegistry, CustomComponent};
 pub use context::St;
+pub use conversation::{ChatMessage, Conversation, ConversationStore, Role};
 pub use error::{Error, Result};
-pub use event::Event;
+#[cfg(feature = "embeddings")]
+pub use embeddings::{EmbeddingProvider, SearchHit, SemanticIndex, VectorIndex};
+pub use event::{Event, EventDispatcher, WidgetChange};
+pub use explorer::{Bin, ChartSuggestion, ColumnSummary, Frame};
+pub use export::ExportFormat;
+pub use filter_graph::{FilterGraph, FilterState};
+pub use finance::{Candle, CandlestickOptions};
+pub use form::{FieldError, Form, FormErrors, FormField};
+pub use format::{NumberFormat, NumberStyle};
+pub use heatmap::{ColorScale, HeatmapCell, HeatmapOptions};
+pub use host::{HostMessage, HostMessages};
+pub use platypus_macros::{Form, Tabular};
+pub use kpi::{DonutSegment, Threshold, Tone};
+pub use log_viewer::{LogBuffer, LogLevel, LogLine, LogStore};
+pub use media::{MediaFile, MediaStats, MediaStore};
 pub use navigation::{MultiPageApp, Navigation, Page, PageLink};
+pub use notifications::{Notification, NotificationLevel, NotificationStore};
+pub use presence::{Presence, PresenceChange, PresenceChangeKind, PresenceRegistry};
+pub use pivot::{Aggregation, Pivot, PivotDrill};
+pub use progress::{ProgressBuilder, ProgressHandle};
+pub use rich_text::RichText;
+pub use room::{RoomChange, RoomHandle, RoomRegistry, RoomState};
 pub use secrets::{Secret, SecretSource, SecretsManager, Secrets};
-pub use session_store::SessionStore;
+pub use selection::{AxisRange, ChartSelection, SelectedPoint, SelectionMode};
+pub use session_store::{SessionSnapshot, SessionStore};
+pub use share::{ShareLink, ShareOptions, ShareStore};
+pub use skeleton::Skeleton;
+pub use speech::{Speaker, SpeechProvider};
+pub use sql::{QueryResult, SqlConnection};
+pub use state_schema::{StateMigration, StateSchema};
+#[cfg(feature = "sqlite")]
+pub use sql::SqliteConnection;
+pub use tempdir::{TempDir, TempDirs};
+pub use tabular::{Cell, Column, TableCell, Tabular};
+pub use theme::ThemeMode;
+pub use topic::{TopicCursors, TopicMessage, TopicRegistry};
+pub use transcription::{AudioInput, Transcriber, TranscriptionProgress, TranscriptionProvider, TranscriptionStatus};
+pub use transfer::{TransferDirection, TransferProgress, TransferStore};
 
 pub mod prelude {
     pub use crate::{
+        app, ui, Form, Tabular,
+        actions::{Action, ActionQueue, Effect},
         cache::{CacheManager, DataCache, ResourceCache},
+        chart_stream::ChartStream,
+        client::{ClientInfo, ColorScheme, Geolocation},
+        code_editor::{AnnotationSeverity, CodeEditorBuilder, CodeEditorHandle},
         components::{ComponentInstance, ComponentMetadata, ComponentProperty, ComponentRegistry, CustomComponent},
         context::St,
+        conversation::{Conversation, Role},
         error::Result,
+        event::{Event, EventDispatcher},
+        filter_graph::FilterGraph,
+        finance::{Candle, CandlestickOptions},
+        form::FormField,
+        format::{NumberFormat, NumberStyle},
+        heatmap::{ColorScale, HeatmapOptions},
+        kpi::{DonutSegment, Threshold, Tone},
+        log_viewer::{LogBuffer, LogLevel, LogLine, LogStore},
+        media::{MediaFile, MediaStore},
         navigation::{MultiPageApp, Navigation, Page, PageLink},
+        notifications::{Notification, NotificationLevel, NotificationStore},
+        pivot::Aggregation,
+        presence::Presence,
+        progress::{ProgressBuilder, ProgressHandle},
+        rich_text::RichText,
+        room::RoomHandle,
         secrets::{Secret, SecretSource, SecretsManager, Secrets},
+        selection::ChartSelection,
         session_store::SessionStore,
+        speech::SpeechProvider,
+        tabular::TableCell,
+        theme::ThemeMode,
+        transcription::{AudioInput, TranscriptionProvider},
+        transfer::{TransferDirection, TransferProgress},
     };
 }
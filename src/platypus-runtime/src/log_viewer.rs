@@ -0,0 +1,284 @@
+//! Log viewer backed by per-session ring buffers.
+//!
+//! `st.log_viewer(key)` renders the buffer's current lines and returns a
+//! [`LogBuffer`] that the app or background tasks keep appending to. The
+//! server waits on [`LogStore::changed`] and streams new lines to the
+//! client without rerunning the script.
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use parking_lot::RwLock;
+use platypus_core::element::LogLineItem;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use tokio::sync::Notify;
+
+/// Buffer receiving the app's `tracing` events.
+pub const APP_LOG_KEY: &str = "app_logs";
+
+/// Default number of lines kept per buffer.
+pub const DEFAULT_LOG_CAPACITY: usize = 1000;
+
+/// Severity of a log line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    /// Get the level name.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Warn => "warn",
+            LogLevel::Error => "error",
+        }
+    }
+}
+
+/// A single log line.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogLine {
+    /// Sequence number, increasing per buffer.
+    pub seq: u64,
+    pub level: LogLevel,
+    pub message: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl LogLine {
+    /// Convert to element form.
+    pub fn to_item(&self) -> LogLineItem {
+        LogLineItem {
+            seq: self.seq,
+            level: self.level.as_str().to_string(),
+            message: self.message.clone(),
+            timestamp: self.timestamp.to_rfc3339(),
+        }
+    }
+}
+
+struct Ring {
+    lines: VecDeque<LogLine>,
+    capacity: usize,
+    next_seq: u64,
+}
+
+/// Fixed-capacity log buffer. Clones share the same lines.
+#[derive(Clone)]
+pub struct LogBuffer {
+    ring: Arc<RwLock<Ring>>,
+    notify: Arc<Notify>,
+}
+
+impl LogBuffer {
+    /// Create a standalone buffer keeping at most `capacity` lines.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_notify(capacity, Arc::new(Notify::new()))
+    }
+
+    fn with_notify(capacity: usize, notify: Arc<Notify>) -> Self {
+        let capacity = capacity.max(1);
+        LogBuffer {
+            ring: Arc::new(RwLock::new(Ring {
+                lines: VecDeque::with_capacity(capacity.min(DEFAULT_LOG_CAPACITY)),
+                capacity,
+                next_seq: 1,
+            })),
+            notify,
+        }
+    }
+
+    /// Append a line, dropping the oldest when full. Returns its sequence number.
+    pub fn append(&self, level: LogLevel, message: impl Into<String>) -> u64 {
+        let seq = {
+            let mut ring = self.ring.write();
+            let seq = ring.next_seq;
+            ring.next_seq += 1;
+            if ring.lines.len() == ring.capacity {
+                ring.lines.pop_front();
+            }
+            ring.lines.push_back(LogLine {
+                seq,
+                level,
+                message: message.into(),
+                timestamp: Utc::now(),
+            });
+            seq
+        };
+        self.notify.notify_one();
+        seq
+    }
+
+    /// Append an info line.
+    pub fn info(&self, message: impl Into<String>) -> u64 {
+        self.append(LogLevel::Info, message)
+    }
+
+    /// Append a warning line.
+    pub fn warn(&self, message: impl Into<String>) -> u64 {
+        self.append(LogLevel::Warn, message)
+    }
+
+    /// Append an error line.
+    pub fn error(&self, message: impl Into<String>) -> u64 {
+        self.append(LogLevel::Error, message)
+    }
+
+    /// Get all buffered lines, oldest first.
+    pub fn lines(&self) -> Vec<LogLine> {
+        self.ring.read().lines.iter().cloned().collect()
+    }
+
+    /// Get buffered lines with a sequence number after `seq`.
+    pub fn lines_since(&self, seq: u64) -> Vec<LogLine> {
+        self.ring
+            .read()
+            .lines
+            .iter()
+            .filter(|line| line.seq > seq)
+            .cloned()
+            .collect()
+    }
+
+    /// Get the sequence number of the newest line (0 if none were appended).
+    pub fn last_seq(&self) -> u64 {
+        self.ring.read().next_seq - 1
+    }
+
+    /// Get the maximum number of lines kept.
+    pub fn capacity(&self) -> usize {
+        self.ring.read().capacity
+    }
+
+    /// Get buffered line count.
+    pub fn len(&self) -> usize {
+        self.ring.read().lines.len()
+    }
+
+    /// Check if the buffer is empty.
+    pub fn is_empty(&self) -> bool {
+        self.ring.read().lines.is_empty()
+    }
+
+    /// Remove all lines. Sequence numbers keep increasing.
+    pub fn clear(&self) {
+        self.ring.write().lines.clear();
+    }
+}
+
+/// Per-session log buffers by key, shared across reruns.
+pub struct LogStore {
+    buffers: Arc<DashMap<String, LogBuffer>>,
+    notify: Arc<Notify>,
+}
+
+impl LogStore {
+    /// Create a new log store.
+    pub fn new() -> Self {
+        LogStore {
+            buffers: Arc::new(DashMap::new()),
+            notify: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Get the buffer for a key, creating it with the default capacity.
+    pub fn buffer(&self, key: &str) -> LogBuffer {
+        self.buffer_with_capacity(key, DEFAULT_LOG_CAPACITY)
+    }
+
+    /// Get the buffer for a key, creating it with `capacity` if missing.
+    pub fn buffer_with_capacity(&self, key: &str, capacity: usize) -> LogBuffer {
+        self.buffers
+            .entry(key.to_string())
+            .or_insert_with(|| LogBuffer::with_notify(capacity, Arc::clone(&self.notify)))
+            .clone()
+    }
+
+    /// Wait until a line is appended to any buffer.
+    ///
+    /// Appends made while nobody is waiting are not lost: the next call
+    /// returns immediately.
+    pub async fn changed(&self) {
+        self.notify.notified().await;
+    }
+
+    /// Collect lines not yet streamed, advancing `sent` per key.
+    pub fn take_unsent(&self, sent: &mut HashMap<String, u64>) -> Vec<(String, Vec<LogLine>)> {
+        let mut unsent = vec![];
+        for entry in self.buffers.iter() {
+            let last = sent.entry(entry.key().clone()).or_insert(0);
+            let lines = entry.value().lines_since(*last);
+            if let Some(line) = lines.last() {
+                *last = line.seq;
+                unsent.push((entry.key().clone(), lines));
+            }
+        }
+        unsent
+    }
+}
+
+impl Default for LogStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for LogStore {
+    fn clone(&self) -> Self {
+        LogStore {
+            buffers: Arc::clone(&self.buffers),
+            notify: Arc::clone(&self.notify),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring_drops_oldest() {
+        let buffer = LogBuffer::with_capacity(2);
+        buffer.info("one");
+        buffer.warn("two");
+        buffer.error("three");
+        let lines = buffer.lines();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].message, "two");
+        assert_eq!(buffer.last_seq(), 3);
+        assert_eq!(buffer.lines_since(2).len(), 1);
+    }
+
+    #[test]
+    fn test_take_unsent_advances() {
+        let store = LogStore::new();
+        let buffer = store.buffer("build");
+        buffer.info("started");
+        let mut sent = HashMap::new();
+
+        let unsent = store.take_unsent(&mut sent);
+        assert_eq!(unsent.len(), 1);
+        assert_eq!(unsent[0].1[0].message, "started");
+        assert!(store.take_unsent(&mut sent).is_empty());
+
+        store.buffer("build").info("done");
+        assert_eq!(store.take_unsent(&mut sent)[0].1[0].seq, 2);
+    }
+
+    #[tokio::test]
+    async fn test_changed_after_append() {
+        let store = LogStore::new();
+        let buffer = store.buffer("task");
+        std::thread::spawn(move || buffer.info("from a background thread"))
+            .join()
+            .unwrap();
+        store.changed().await;
+    }
+}
@@ -0,0 +1,224 @@
+//! Declarative layout macros.
+//!
+//! [`ui!`](crate::ui) expands a block of widget calls into calls on `St`,
+//! entering containers without the `let mut st = tabs[0].st()` dance:
+//!
+//! ```
+//! use platypus_runtime::{ui, St};
+//!
+//! let mut st = St::new();
+//! let show_details = true;
+//! ui!(st => {
+//!     title("Dashboard");
+//!     let name = text_input("Name", "World", None);
+//!     columns(2) => [
+//!         { metric("Users", "1,024", None); }
+//!         { write(format!("Hello, {}!", name)); }
+//!     ]
+//!     if show_details => {
+//!         expander("Details") => {
+//!             write("More information");
+//!         }
+//!     }
+//! })
+//! .unwrap();
+//! ```
+//!
+//! Statements are one of:
+//! - `method(args);` calls `st.method(args)`
+//! - `let pat = method(args);` binds the result
+//! - `method(args) => { ... }` runs the block inside a returned container
+//! - `method(args) => [ { ... } { ... } ]` runs one block per returned
+//!   container (columns, tabs)
+//! - `if cond => { ... } else => { ... }` and `for pat in iter => { ... }`
+//! - `{ ... }` runs plain Rust, with `st` in scope
+//!
+//! `ui!` evaluates to `Err` when a call returns fewer containers than it
+//! is given blocks, before any of them run.
+
+/// Build UI declaratively from `St` method calls. See the [module docs](crate::macros).
+#[macro_export]
+macro_rules! ui {
+    ($st:ident => { $($body:tt)* }) => {{
+        #[allow(unused_labels)]
+        let result: ::std::result::Result<(), ::std::string::String> = 'ui: {
+            $crate::ui!(@stmts $st 'ui; $($body)*);
+            Ok(())
+        };
+        result
+    }};
+
+    (@stmts $st:ident $ui:lifetime;) => {};
+
+    (@stmts $st:ident $ui:lifetime; let $p:pat = $method:ident ( $($args:tt)* ); $($rest:tt)*) => {
+        let $p = $st.$method($($args)*);
+        $crate::ui!(@stmts $st $ui; $($rest)*);
+    };
+
+    (@stmts $st:ident $ui:lifetime; if $cond:expr => { $($then:tt)* } else => { $($else:tt)* } $($rest:tt)*) => {
+        if $cond {
+            $crate::ui!(@stmts $st $ui; $($then)*);
+        } else {
+            $crate::ui!(@stmts $st $ui; $($else)*);
+        }
+        $crate::ui!(@stmts $st $ui; $($rest)*);
+    };
+
+    (@stmts $st:ident $ui:lifetime; if $cond:expr => { $($then:tt)* } $($rest:tt)*) => {
+        if $cond {
+            $crate::ui!(@stmts $st $ui; $($then)*);
+        }
+        $crate::ui!(@stmts $st $ui; $($rest)*);
+    };
+
+    (@stmts $st:ident $ui:lifetime; for $p:pat in $iter:expr => { $($body:tt)* } $($rest:tt)*) => {
+        for $p in $iter {
+            $crate::ui!(@stmts $st $ui; $($body)*);
+        }
+        $crate::ui!(@stmts $st $ui; $($rest)*);
+    };
+
+    (@stmts $st:ident $ui:lifetime; $method:ident ( $($args:tt)* ) => [ $({ $($body:tt)* })* ] $($rest:tt)*) => {
+        {
+            let containers = $st.$method($($args)*);
+            let blocks = [$(stringify!({ $($body)* })),*].len();
+            if blocks > containers.len() {
+                break $ui Err(format!(
+                    "{}() returned {} containers for {} blocks",
+                    stringify!($method),
+                    containers.len(),
+                    blocks,
+                ));
+            }
+            let mut containers = containers.iter();
+            $(
+                #[allow(unused_mut)]
+                let mut $st = containers.next().unwrap().st();
+                $crate::ui!(@stmts $st $ui; $($body)*);
+            )*
+        }
+        $crate::ui!(@stmts $st $ui; $($rest)*);
+    };
+
+    (@stmts $st:ident $ui:lifetime; $method:ident ( $($args:tt)* ) => { $($body:tt)* } $($rest:tt)*) => {
+        {
+            #[allow(unused_mut)]
+            let mut $st = $st.$method($($args)*).st();
+            $crate::ui!(@stmts $st $ui; $($body)*);
+        }
+        $crate::ui!(@stmts $st $ui; $($rest)*);
+    };
+
+    (@stmts $st:ident $ui:lifetime; $method:ident ( $($args:tt)* ); $($rest:tt)*) => {
+        $st.$method($($args)*);
+        $crate::ui!(@stmts $st $ui; $($rest)*);
+    };
+
+    (@stmts $st:ident $ui:lifetime; { $($raw:tt)* } $($rest:tt)*) => {
+        { $($raw)* }
+        $crate::ui!(@stmts $st $ui; $($rest)*);
+    };
+}
+
+/// Define an app function whose body is written with [`ui!`](crate::ui).
+///
+/// ```
+/// use platypus_runtime::app;
+///
+/// app!(fn hello(st) {
+///     title("Hello");
+///     write("Welcome!");
+/// });
+/// ```
+#[macro_export]
+macro_rules! app {
+    ($vis:vis fn $name:ident ( $st:ident ) { $($body:tt)* }) => {
+        $vis fn $name($st: &mut $crate::St) -> ::std::result::Result<(), String> {
+            $crate::ui!($st => { $($body)* })
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::St;
+    use platypus_core::element::ElementType;
+    use platypus_core::state::Delta;
+
+    app!(fn layout(st) {
+        title("Report");
+        columns(2) => [
+            { write("left"); }
+            { write("right"); }
+        ]
+        for i in 0..2 => {
+            expander(format!("Section {}", i)) => {
+                write("body");
+            }
+        }
+    });
+
+    #[test]
+    fn test_nested_layout_targets_containers() {
+        let mut st = St::new();
+        layout(&mut st).unwrap();
+
+        let deltas = st.delta_gen().take_deltas();
+        let parents: Vec<_> = deltas
+            .iter()
+            .filter_map(|delta| match delta {
+                Delta::AddElement { element: ElementType::Text { value }, parent_id, .. } => {
+                    Some((value.clone(), parent_id.is_some()))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            parents,
+            vec![
+                ("left".to_string(), true),
+                ("right".to_string(), true),
+                ("body".to_string(), true),
+                ("body".to_string(), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_conditionals_and_bindings() {
+        let mut st = St::new();
+        let admin = false;
+        ui!(st => {
+            let name = text_input("Name", "Ada", None);
+            if admin => {
+                write("admin");
+            } else => {
+                write(format!("hi {}", name));
+            }
+            { assert_eq!(name, "Ada"); }
+        })
+        .unwrap();
+        assert_eq!(st.delta_gen().take_deltas().len(), 2);
+    }
+
+    #[test]
+    fn test_more_blocks_than_containers_is_an_error() {
+        let mut st = St::new();
+        let result = ui!(st => {
+            columns(2) => [
+                { write("left"); }
+                { write("middle"); }
+                { write("right"); }
+            ]
+            write("after");
+        });
+        assert_eq!(result, Err("columns() returned 2 containers for 3 blocks".to_string()));
+        let texts = st
+            .delta_gen()
+            .take_deltas()
+            .into_iter()
+            .filter(|delta| matches!(delta, Delta::AddElement { element: ElementType::Text { .. }, .. }))
+            .count();
+        assert_eq!(texts, 0);
+    }
+}
@@ -0,0 +1,337 @@
+//! Media storage for files served to the browser (downloads, exports).
+//!
+//! Files are reference-counted by the sessions whose element trees (or
+//! widget values, for uploads) point at them. Every run replaces its
+//! session's references, so a download button re-rendered with new data
+//! leaves its old file unreferenced; [`MediaStore::collect_garbage`] drops
+//! such files after a grace period, and any file past its TTL.
+
+use dashmap::DashMap;
+use platypus_core::session::SessionId;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// URL prefix under which stored media is served.
+pub const MEDIA_URL_PREFIX: &str = "/media";
+
+/// A file held in memory until the browser fetches it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaFile {
+    /// File name offered to the browser.
+    pub file_name: String,
+    /// MIME type of the payload.
+    pub mime_type: String,
+    /// Raw file bytes.
+    pub data: Vec<u8>,
+}
+
+impl MediaFile {
+    /// Create a new media file.
+    pub fn new(file_name: impl Into<String>, mime_type: impl Into<String>, data: Vec<u8>) -> Self {
+        MediaFile {
+            file_name: file_name.into(),
+            mime_type: mime_type.into(),
+            data,
+        }
+    }
+
+    /// Size of the payload in bytes.
+    pub fn size(&self) -> usize {
+        self.data.len()
+    }
+}
+
+/// Check an uploaded value against an accept filter.
+///
+/// Uploaded values are either data URLs (`data:image/png;base64,...`) or
+/// file names. Filters may be exact MIME types, wildcards (`image/*`) or
+/// extensions (`.csv`). An empty filter accepts everything.
+pub fn accepts(value: &str, accept: &[String]) -> bool {
+    if accept.is_empty() {
+        return true;
+    }
+
+    let mime_type = value
+        .strip_prefix("data:")
+        .and_then(|rest| rest.split([';', ',']).next());
+
+    accept.iter().any(|filter| {
+        let filter = filter.to_ascii_lowercase();
+        match (filter.strip_suffix("/*"), mime_type) {
+            (Some(prefix), Some(mime)) => mime.to_ascii_lowercase().starts_with(&format!("{}/", prefix)),
+            (None, Some(mime)) if !filter.starts_with('.') => mime.eq_ignore_ascii_case(&filter),
+            (None, None) if filter.starts_with('.') => value.to_ascii_lowercase().ends_with(&filter),
+            _ => false,
+        }
+    })
+}
+
+/// Find the media IDs of the `/media/{id}` URLs in `text`, e.g. a
+/// serialized element or a widget value.
+pub fn referenced_ids(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.match_indices(MEDIA_URL_PREFIX)
+        .filter_map(|(at, _)| text[at + MEDIA_URL_PREFIX.len()..].strip_prefix('/'))
+        .map(|rest| {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_hexdigit() || c == '-'))
+                .unwrap_or(rest.len());
+            rest[..end].to_string()
+        })
+        .filter(|id| !id.is_empty())
+}
+
+/// Stored media, for metrics.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct MediaStats {
+    /// Files stored.
+    pub files: usize,
+    /// Bytes stored.
+    pub bytes: u64,
+    /// Files no session references.
+    pub unreferenced_files: usize,
+    /// Bytes of the files no session references.
+    pub unreferenced_bytes: u64,
+    /// Files garbage-collected since the store was created.
+    pub collected_files: u64,
+    /// Bytes garbage-collected since the store was created.
+    pub collected_bytes: u64,
+}
+
+struct MediaEntry {
+    file: MediaFile,
+    added: Instant,
+    refs: usize,
+    // When the file was last left without references
+    unreferenced_since: Instant,
+}
+
+/// Stores media files shared between the runtime and the server.
+pub struct MediaStore {
+    files: Arc<DashMap<String, MediaEntry>>,
+    // Media IDs each session references
+    owners: Arc<DashMap<SessionId, HashSet<String>>>,
+    collected_files: Arc<AtomicU64>,
+    collected_bytes: Arc<AtomicU64>,
+}
+
+impl MediaStore {
+    /// Create a new media store.
+    pub fn new() -> Self {
+        MediaStore {
+            files: Arc::new(DashMap::new()),
+            owners: Arc::new(DashMap::new()),
+            collected_files: Arc::new(AtomicU64::new(0)),
+            collected_bytes: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Store a file and return its media ID.
+    pub fn add(&self, file: MediaFile) -> String {
+        let id = Uuid::new_v4().to_string();
+        let now = Instant::now();
+        self.files.insert(
+            id.clone(),
+            MediaEntry {
+                file,
+                added: now,
+                refs: 0,
+                unreferenced_since: now,
+            },
+        );
+        id
+    }
+
+    /// Get a file by media ID.
+    pub fn get(&self, id: &str) -> Option<MediaFile> {
+        self.files.get(id).map(|entry| entry.file.clone())
+    }
+
+    /// Remove a file.
+    pub fn remove(&self, id: &str) -> Option<MediaFile> {
+        self.files.remove(id).map(|(_, entry)| entry.file)
+    }
+
+    /// Replace the media IDs a session references, e.g. after a run.
+    pub fn set_references(&self, owner: SessionId, ids: HashSet<String>) {
+        let previous = self.owners.insert(owner, ids.clone()).unwrap_or_default();
+        for id in ids.difference(&previous) {
+            if let Some(mut entry) = self.files.get_mut(id) {
+                entry.refs += 1;
+            }
+        }
+        for id in previous.difference(&ids) {
+            if let Some(mut entry) = self.files.get_mut(id) {
+                entry.refs = entry.refs.saturating_sub(1);
+                if entry.refs == 0 {
+                    entry.unreferenced_since = Instant::now();
+                }
+            }
+        }
+    }
+
+    /// Drop every reference of a session that ended.
+    pub fn release(&self, owner: SessionId) {
+        self.set_references(owner, HashSet::new());
+        self.owners.remove(&owner);
+    }
+
+    /// Get the number of sessions referencing a file.
+    pub fn ref_count(&self, id: &str) -> Option<usize> {
+        self.files.get(id).map(|entry| entry.refs)
+    }
+
+    /// Remove files unreferenced for at least `grace`, and files stored for
+    /// longer than `ttl` whether referenced or not. Returns the number of
+    /// files removed.
+    pub fn collect_garbage(&self, grace: Duration, ttl: Duration) -> usize {
+        let now = Instant::now();
+        let mut collected = 0;
+        self.files.retain(|_, entry| {
+            let idle = entry.refs == 0 && now.duration_since(entry.unreferenced_since) >= grace;
+            let expired = now.duration_since(entry.added) >= ttl;
+            if idle || expired {
+                collected += 1;
+                self.collected_bytes.fetch_add(entry.file.size() as u64, Ordering::Relaxed);
+                return false;
+            }
+            true
+        });
+        self.collected_files.fetch_add(collected as u64, Ordering::Relaxed);
+        collected
+    }
+
+    /// Get the stored files and bytes.
+    pub fn stats(&self) -> MediaStats {
+        let mut stats = MediaStats {
+            collected_files: self.collected_files.load(Ordering::Relaxed),
+            collected_bytes: self.collected_bytes.load(Ordering::Relaxed),
+            ..MediaStats::default()
+        };
+        for entry in self.files.iter() {
+            let size = entry.file.size() as u64;
+            stats.files += 1;
+            stats.bytes += size;
+            if entry.refs == 0 {
+                stats.unreferenced_files += 1;
+                stats.unreferenced_bytes += size;
+            }
+        }
+        stats
+    }
+
+    /// Get the URL the browser uses to fetch a file.
+    pub fn url_for(id: &str) -> String {
+        format!("{}/{}", MEDIA_URL_PREFIX, id)
+    }
+
+    /// Check the media URL of a chunked upload (`/media/{id}#name`) against
+    /// an accept filter, using the stored file's name and MIME type.
+    pub fn accepts_url(&self, url: &str, accept: &[String]) -> bool {
+        let Some(rest) = url.strip_prefix(MEDIA_URL_PREFIX).and_then(|rest| rest.strip_prefix('/')) else {
+            return false;
+        };
+        let id = rest.split('#').next().unwrap_or(rest);
+        self.files.get(id).is_some_and(|entry| {
+            accepts(&format!("data:{};", entry.file.mime_type), accept) || accepts(&entry.file.file_name, accept)
+        })
+    }
+
+    /// Get file count.
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Check if the store is empty.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Remove all files.
+    pub fn clear(&self) {
+        self.files.clear();
+    }
+}
+
+impl Default for MediaStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for MediaStore {
+    fn clone(&self) -> Self {
+        MediaStore {
+            files: Arc::clone(&self.files),
+            owners: Arc::clone(&self.owners),
+            collected_files: Arc::clone(&self.collected_files),
+            collected_bytes: Arc::clone(&self.collected_bytes),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_and_get() {
+        let store = MediaStore::new();
+        let id = store.add(MediaFile::new("a.txt", "text/plain", b"hello".to_vec()));
+        let file = store.get(&id).unwrap();
+        assert_eq!(file.file_name, "a.txt");
+        assert_eq!(file.size(), 5);
+    }
+
+    #[test]
+    fn test_shared_between_clones() {
+        let store = MediaStore::new();
+        let other = store.clone();
+        let id = store.add(MediaFile::new("a.txt", "text/plain", vec![]));
+        assert!(other.get(&id).is_some());
+        other.remove(&id);
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn test_accepts() {
+        let images = vec!["image/*".to_string()];
+        assert!(accepts("data:image/png;base64,AAAA", &images));
+        assert!(!accepts("data:text/csv;base64,AAAA", &images));
+        assert!(accepts("report.CSV", &[".csv".to_string()]));
+        assert!(accepts("anything", &[]));
+
+        let store = MediaStore::new();
+        let url = MediaStore::url_for(&store.add(MediaFile::new("big.png", "image/png", vec![])));
+        assert!(store.accepts_url(&format!("{}#big.png", url), &images));
+        assert!(!store.accepts_url("/media/unknown#big.png", &images));
+    }
+
+    #[test]
+    fn test_url_for() {
+        assert_eq!(MediaStore::url_for("abc"), "/media/abc");
+    }
+
+    #[test]
+    fn test_garbage_collection() {
+        let store = MediaStore::new();
+        let kept = store.add(MediaFile::new("a.txt", "text/plain", b"hello".to_vec()));
+        let dropped = store.add(MediaFile::new("b.txt", "text/plain", b"bye".to_vec()));
+        let session_id = SessionId::new();
+        let html = format!(r#"{{"url":"{}"}}"#, MediaStore::url_for(&kept));
+        store.set_references(session_id, referenced_ids(&html).collect());
+        assert_eq!(store.ref_count(&kept), Some(1));
+
+        assert_eq!(store.collect_garbage(Duration::ZERO, Duration::from_secs(60)), 1);
+        assert!(store.get(&dropped).is_none());
+        assert_eq!(store.stats().bytes, 5);
+        assert_eq!(store.stats().collected_bytes, 3);
+
+        store.release(session_id);
+        assert_eq!(store.collect_garbage(Duration::ZERO, Duration::from_secs(60)), 1);
+        assert!(store.is_empty());
+    }
+}
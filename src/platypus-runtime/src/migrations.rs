@@ -0,0 +1,125 @@
+//! SQL migrations and seed data of data apps.
+//!
+//! `platypus db migrate` runs the `*.sql` files of an app's migrations
+//! directory in name order, e.g. `0001_create_sales.sql`, each once: the
+//! names of applied files are kept in the [`MIGRATIONS_TABLE`] table. Each
+//! file runs in a transaction with the row recording it, so a failing file
+//! leaves neither its changes nor its row behind; files must therefore not
+//! begin or commit transactions themselves.
+//! `platypus db seed` runs the files of its [`SEED_DIR`] subdirectory on
+//! every call, so seed files should be safe to rerun, e.g. with
+//! `INSERT OR REPLACE`.
+
+use crate::error::{Error, Result};
+use crate::sql::SqlConnection;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Table recording the applied migrations.
+pub const MIGRATIONS_TABLE: &str = "platypus_migrations";
+
+/// Subdirectory of the migrations directory holding seed files.
+pub const SEED_DIR: &str = "seed";
+
+/// Apply the migrations in `dir` not applied yet, returning their names.
+pub fn migrate(conn: &dyn SqlConnection, dir: &Path) -> Result<Vec<String>> {
+    conn.execute_batch(&format!("CREATE TABLE IF NOT EXISTS {} (name TEXT PRIMARY KEY)", MIGRATIONS_TABLE))?;
+    let applied = conn.query(&format!("SELECT name FROM {}", MIGRATIONS_TABLE), &[])?;
+    let applied: Vec<&str> = applied.rows.iter().filter_map(|row| row.first()?.as_str()).collect();
+
+    let record = format!("INSERT INTO {} (name) VALUES (?)", MIGRATIONS_TABLE);
+    let mut names = Vec::new();
+    for (name, path) in sql_files(dir)? {
+        if applied.contains(&name.as_str()) {
+            continue;
+        }
+        conn.execute_batch("BEGIN")?;
+        let result = run_file(conn, &name, &path)
+            .and_then(|()| conn.query(&record, &[name.as_str().into()]))
+            .and_then(|_| conn.execute_batch("COMMIT"));
+        if let Err(e) = result {
+            conn.execute_batch("ROLLBACK")?;
+            return Err(e);
+        }
+        names.push(name);
+    }
+    Ok(names)
+}
+
+/// Run the seed files of the migrations directory `dir`, returning their
+/// names.
+pub fn seed(conn: &dyn SqlConnection, dir: &Path) -> Result<Vec<String>> {
+    let mut names = Vec::new();
+    for (name, path) in sql_files(&dir.join(SEED_DIR))? {
+        run_file(conn, &name, &path)?;
+        names.push(name);
+    }
+    Ok(names)
+}
+
+/// Get the names and paths of the `*.sql` files in `dir`, in name order;
+/// a missing directory has none.
+fn sql_files(dir: &Path) -> Result<Vec<(String, PathBuf)>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().is_some_and(|extension| extension == "sql") {
+            let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+            files.push((name, path));
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn run_file(conn: &dyn SqlConnection, name: &str, path: &Path) -> Result<()> {
+    conn.execute_batch(&fs::read_to_string(path)?)
+        .map_err(|e| Error::execution(format!("{} failed: {}", name, e)))
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+    use crate::sql::SqliteConnection;
+
+    #[test]
+    fn test_migrate_once_then_seed() {
+        let dir = std::env::temp_dir().join(format!("platypus-migrations-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(dir.join(SEED_DIR)).unwrap();
+        fs::write(dir.join("0002_index.sql"), "CREATE INDEX sales_region ON sales (region);").unwrap();
+        fs::write(dir.join("0001_create.sql"), "CREATE TABLE sales (region TEXT PRIMARY KEY, total INTEGER);").unwrap();
+        fs::write(dir.join(SEED_DIR).join("sales.sql"), "INSERT OR REPLACE INTO sales VALUES ('north', 10);").unwrap();
+
+        let conn = SqliteConnection::in_memory().unwrap();
+        assert_eq!(migrate(&conn, &dir).unwrap(), vec!["0001_create.sql", "0002_index.sql"]);
+        assert!(migrate(&conn, &dir).unwrap().is_empty());
+        assert_eq!(seed(&conn, &dir).unwrap(), vec!["sales.sql"]);
+        seed(&conn, &dir).unwrap();
+
+        let sales = conn.query("SELECT region, total FROM sales", &[]).unwrap();
+        assert_eq!(sales.rows, vec![vec![serde_json::json!("north"), serde_json::json!(10)]]);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_failed_migration_is_rolled_back() {
+        let dir = std::env::temp_dir().join(format!("platypus-migrations-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("0001_o'brien.sql"), "CREATE TABLE sales (total INTEGER);").unwrap();
+        fs::write(dir.join("0002_broken.sql"), "CREATE TABLE regions (name TEXT); INSERT INTO missing VALUES (1);").unwrap();
+
+        let conn = SqliteConnection::in_memory().unwrap();
+        assert!(migrate(&conn, &dir).unwrap_err().to_string().contains("0002_broken.sql"));
+        // The first file stays applied; the second left no table or row
+        let applied = conn.query(&format!("SELECT name FROM {}", MIGRATIONS_TABLE), &[]).unwrap();
+        assert_eq!(applied.rows, vec![vec![serde_json::json!("0001_o'brien.sql")]]);
+        assert!(conn.query("SELECT name FROM regions", &[]).is_err());
+
+        fs::write(dir.join("0002_broken.sql"), "CREATE TABLE regions (name TEXT);").unwrap();
+        assert_eq!(migrate(&conn, &dir).unwrap(), vec!["0002_broken.sql"]);
+        let _ = fs::remove_dir_all(dir);
+    }
+}
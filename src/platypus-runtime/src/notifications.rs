@@ -0,0 +1,236 @@
+//! Notification center with persistent alerts.
+//!
+//! Unlike transient alerts such as `st.info`, notifications collect in a
+//! per-session store rendered as a bell-icon panel. Read and dismiss state
+//! comes back from the frontend as the value of the
+//! [`NOTIFICATIONS_KEY`] widget.
+
+use parking_lot::RwLock;
+use platypus_core::element::{ElementId, NotificationItem};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// Widget key under which the frontend reports read/dismissed notifications.
+pub const NOTIFICATIONS_KEY: &str = "notification_center";
+
+/// Severity of a notification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationLevel {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl NotificationLevel {
+    /// Get the level name.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NotificationLevel::Info => "info",
+            NotificationLevel::Success => "success",
+            NotificationLevel::Warning => "warning",
+            NotificationLevel::Error => "error",
+        }
+    }
+}
+
+/// A single notification.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Notification {
+    /// Stable ID derived from level and message.
+    pub id: String,
+    pub level: NotificationLevel,
+    pub message: String,
+    /// Whether the user has opened the panel since it arrived.
+    pub read: bool,
+    /// Whether it survives reruns that don't re-send it.
+    pub persistent: bool,
+}
+
+impl Notification {
+    fn to_item(&self) -> NotificationItem {
+        NotificationItem {
+            id: self.id.clone(),
+            level: self.level.as_str().to_string(),
+            message: self.message.clone(),
+            read: self.read,
+        }
+    }
+}
+
+/// Read/dismiss state reported by the frontend.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationState {
+    pub read: Vec<String>,
+    pub dismissed: Vec<String>,
+}
+
+/// Per-session notification store, shared across reruns.
+pub struct NotificationStore {
+    notifications: Arc<RwLock<Vec<Notification>>>,
+    element_id: Arc<RwLock<Option<ElementId>>>,
+}
+
+impl NotificationStore {
+    /// Create a new notification store.
+    pub fn new() -> Self {
+        NotificationStore {
+            notifications: Arc::new(RwLock::new(Vec::new())),
+            element_id: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Add a notification and return its ID.
+    ///
+    /// Sending the same level and message again keeps the existing entry
+    /// (and its read state) instead of adding a duplicate.
+    pub fn push(&self, level: NotificationLevel, message: impl Into<String>, persistent: bool) -> String {
+        let message = message.into();
+        let id = notification_id(level, &message);
+
+        let mut notifications = self.notifications.write();
+        match notifications.iter_mut().find(|n| n.id == id) {
+            Some(existing) => existing.persistent |= persistent,
+            None => notifications.push(Notification {
+                id: id.clone(),
+                level,
+                message,
+                read: false,
+                persistent,
+            }),
+        }
+        id
+    }
+
+    /// Start a new script run, dropping non-persistent notifications.
+    pub fn begin_run(&self) {
+        self.notifications.write().retain(|n| n.persistent);
+        *self.element_id.write() = None;
+    }
+
+    /// Apply read/dismiss state reported by the frontend.
+    pub fn apply_state(&self, state: &NotificationState) {
+        let mut notifications = self.notifications.write();
+        notifications.retain(|n| !state.dismissed.contains(&n.id));
+        for notification in notifications.iter_mut() {
+            if state.read.contains(&notification.id) {
+                notification.read = true;
+            }
+        }
+    }
+
+    /// Mark a notification as read.
+    pub fn mark_read(&self, id: &str) {
+        if let Some(notification) = self.notifications.write().iter_mut().find(|n| n.id == id) {
+            notification.read = true;
+        }
+    }
+
+    /// Remove a notification.
+    pub fn dismiss(&self, id: &str) {
+        self.notifications.write().retain(|n| n.id != id);
+    }
+
+    /// Get all notifications, oldest first.
+    pub fn all(&self) -> Vec<Notification> {
+        self.notifications.read().clone()
+    }
+
+    /// Get the number of unread notifications.
+    pub fn unread_count(&self) -> usize {
+        self.notifications.read().iter().filter(|n| !n.read).count()
+    }
+
+    /// Get notification count.
+    pub fn len(&self) -> usize {
+        self.notifications.read().len()
+    }
+
+    /// Check if the store is empty.
+    pub fn is_empty(&self) -> bool {
+        self.notifications.read().is_empty()
+    }
+
+    /// Remove all notifications.
+    pub fn clear(&self) {
+        self.notifications.write().clear();
+    }
+
+    /// Notifications in element form.
+    pub(crate) fn items(&self) -> Vec<NotificationItem> {
+        self.notifications.read().iter().map(|n| n.to_item()).collect()
+    }
+
+    /// Element rendering the panel in the current run, if any.
+    pub(crate) fn element_id(&self) -> Option<ElementId> {
+        *self.element_id.read()
+    }
+
+    pub(crate) fn set_element_id(&self, id: ElementId) {
+        *self.element_id.write() = Some(id);
+    }
+}
+
+impl Default for NotificationStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for NotificationStore {
+    fn clone(&self) -> Self {
+        NotificationStore {
+            notifications: Arc::clone(&self.notifications),
+            element_id: Arc::clone(&self.element_id),
+        }
+    }
+}
+
+fn notification_id(level: NotificationLevel, message: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    level.hash(&mut hasher);
+    message.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_deduplicates() {
+        let store = NotificationStore::new();
+        let a = store.push(NotificationLevel::Info, "Saved", false);
+        let b = store.push(NotificationLevel::Info, "Saved", true);
+        assert_eq!(a, b);
+        assert_eq!(store.len(), 1);
+        assert!(store.all()[0].persistent);
+    }
+
+    #[test]
+    fn test_begin_run_keeps_persistent() {
+        let store = NotificationStore::new();
+        store.push(NotificationLevel::Info, "transient", false);
+        store.push(NotificationLevel::Error, "sticky", true);
+        store.begin_run();
+        assert_eq!(store.len(), 1);
+        assert_eq!(store.all()[0].message, "sticky");
+    }
+
+    #[test]
+    fn test_apply_state() {
+        let store = NotificationStore::new();
+        let read = store.push(NotificationLevel::Info, "one", false);
+        let dismissed = store.push(NotificationLevel::Warning, "two", false);
+        store.apply_state(&NotificationState {
+            read: vec![read],
+            dismissed: vec![dismissed],
+        });
+        assert_eq!(store.len(), 1);
+        assert_eq!(store.unread_count(), 0);
+    }
+}
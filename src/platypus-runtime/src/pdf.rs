@@ -0,0 +1,588 @@
+//! Pure-Rust PDF export of an element tree.
+//!
+//! Elements are flattened to lines of text (headings, paragraphs, tables),
+//! laid out top to bottom and paginated onto Letter pages using the
+//! built-in Helvetica font. Text is encoded in WinAnsiEncoding, which
+//! covers Latin-1; characters outside it print as `?`.
+//!
+//! Line, bar, area, scatter and pie charts whose data is a JSON records
+//! array are drawn as vector graphics. Plotly, Vega-Lite and Bokeh specs
+//! need their libraries to render and print as placeholders.
+
+use platypus_core::element::{ElementType, TextInputKind};
+use platypus_core::elements::ChartKind;
+use serde_json::{Map, Value};
+
+/// Page width in points (US Letter).
+const PAGE_WIDTH: f32 = 612.0;
+/// Page height in points (US Letter).
+const PAGE_HEIGHT: f32 = 792.0;
+/// Page margin in points.
+const MARGIN: f32 = 50.0;
+/// Body font size in points.
+const BODY_FONT_SIZE: f32 = 11.0;
+/// Line height as a multiple of font size.
+const LINE_SPACING: f32 = 1.4;
+/// Approximate Helvetica glyph width as a fraction of font size.
+const AVG_GLYPH_WIDTH: f32 = 0.5;
+/// Height of a drawn chart in points.
+const CHART_HEIGHT: f32 = 180.0;
+/// Colors of a chart's series, as RGB fractions.
+const PALETTE: [(f32, f32, f32); 6] = [
+    (0.12, 0.47, 0.71),
+    (1.0, 0.50, 0.05),
+    (0.17, 0.63, 0.17),
+    (0.84, 0.15, 0.16),
+    (0.58, 0.40, 0.74),
+    (0.55, 0.34, 0.29),
+];
+
+/// A single line of laid-out text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PdfLine {
+    pub text: String,
+    pub font_size: f32,
+    /// Level of the heading the line belongs to, if any
+    pub level: Option<u32>,
+}
+
+impl PdfLine {
+    /// Create a body-sized line.
+    pub fn body(text: impl Into<String>) -> Self {
+        PdfLine {
+            text: text.into(),
+            font_size: BODY_FONT_SIZE,
+            level: None,
+        }
+    }
+
+    /// Create a heading line for the given level (1-6).
+    pub fn heading(text: impl Into<String>, level: u32) -> Self {
+        let font_size = match level {
+            1 => 22.0,
+            2 => 18.0,
+            3 => 15.0,
+            _ => 13.0,
+        };
+        PdfLine {
+            text: text.into(),
+            font_size,
+            level: Some(level),
+        }
+    }
+
+    /// Get the heading level of a heading line.
+    pub fn heading_level(&self) -> Option<u32> {
+        self.level
+    }
+}
+
+/// A chart's records, read into series to draw.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PdfChart {
+    pub kind: ChartKind,
+    /// X value of each record: the first column when it is numeric in
+    /// every record, or the record's position otherwise
+    pub x: Vec<f64>,
+    /// Each other numeric column's values
+    pub series: Vec<Vec<f64>>,
+}
+
+impl PdfChart {
+    /// Read a chart's JSON records array. Returns `None` when the data is
+    /// not records or has no numeric column to draw.
+    pub fn from_records(kind: ChartKind, data: &str) -> Option<Self> {
+        let records: Vec<Map<String, Value>> = serde_json::from_str(data).ok()?;
+        let columns: Vec<&String> = records.first()?.keys().collect();
+        let numeric = |column: &String| records.iter().all(|record| record.get(column).is_some_and(Value::is_number));
+        let x_column = columns.first().copied().filter(|column| numeric(column));
+        let value =
+            |record: &Map<String, Value>, column: &String| record.get(column).and_then(Value::as_f64).unwrap_or(0.0);
+        let series: Vec<Vec<f64>> = columns
+            .iter()
+            .copied()
+            .filter(|column| Some(*column) != x_column && numeric(column))
+            .map(|column| records.iter().map(|record| value(record, column)).collect())
+            .collect();
+        if series.is_empty() {
+            return None;
+        }
+        let x = match x_column {
+            Some(column) => records.iter().map(|record| value(record, column)).collect(),
+            None => (0..records.len()).map(|index| index as f64).collect(),
+        };
+        Some(PdfChart { kind, x, series })
+    }
+}
+
+/// A laid-out block: a line of text or a drawn chart.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PdfBlock {
+    Line(PdfLine),
+    Chart(PdfChart),
+}
+
+impl PdfBlock {
+    fn height(&self) -> f32 {
+        match self {
+            PdfBlock::Line(line) => line.font_size * LINE_SPACING,
+            PdfBlock::Chart(_) => CHART_HEIGHT + BODY_FONT_SIZE * LINE_SPACING,
+        }
+    }
+}
+
+/// Lay out an element as blocks: charts with records are drawn under
+/// their title, everything else is its [`element_lines`].
+pub fn element_blocks(element: &ElementType) -> Vec<PdfBlock> {
+    let (kind, data, title) = match element {
+        ElementType::LineChart { data, title, .. } => (ChartKind::Line, data, title),
+        ElementType::BarChart { data, title, .. } => (ChartKind::Bar, data, title),
+        ElementType::AreaChart { data, title, .. } => (ChartKind::Area, data, title),
+        ElementType::ScatterChart { data, title, .. } => (ChartKind::Scatter, data, title),
+        ElementType::PieChart { data, title, .. } => (ChartKind::Pie, data, title),
+        _ => return element_lines(element).into_iter().map(PdfBlock::Line).collect(),
+    };
+    let Some(chart) = PdfChart::from_records(kind, data) else {
+        return element_lines(element).into_iter().map(PdfBlock::Line).collect();
+    };
+    let mut blocks: Vec<PdfBlock> = title.iter().map(|title| PdfBlock::Line(PdfLine::body(title.clone()))).collect();
+    blocks.push(PdfBlock::Chart(chart));
+    blocks
+}
+
+/// Flatten an element into printable lines.
+///
+/// Layout-only and interactive-only elements produce no lines.
+pub fn element_lines(element: &ElementType) -> Vec<PdfLine> {
+    match element {
+        ElementType::Text { value } | ElementType::Markdown { value } | ElementType::Code { value, .. } => {
+            value.lines().map(PdfLine::body).collect()
+        }
+        ElementType::Heading { value, level } => vec![PdfLine::heading(value.clone(), *level)],
+        ElementType::Json { value } => serde_json::to_string_pretty(value)
+            .unwrap_or_default()
+            .lines()
+            .map(PdfLine::body)
+            .collect(),
+        ElementType::Table { headers, rows } => {
+            let mut lines = vec![PdfLine::body(headers.join(" | "))];
+            lines.extend(rows.iter().map(|row| PdfLine::body(row.join(" | "))));
+            lines
+        }
+        ElementType::DataEditor { headers, values, .. } => {
+            let mut lines = vec![PdfLine::body(headers.join(" | "))];
+            lines.extend(values.iter().map(|row| PdfLine::body(row.join(" | "))));
+            lines
+        }
+        ElementType::Dataframe { .. } => vec![PdfLine::body("[Dataframe]")],
+        ElementType::Success { message } => vec![PdfLine::body(format!("Success: {}", message))],
+        ElementType::Error { message } => vec![PdfLine::body(format!("Error: {}", message))],
+        ElementType::Warning { message } => vec![PdfLine::body(format!("Warning: {}", message))],
+        ElementType::Info { message } => vec![PdfLine::body(format!("Info: {}", message))],
+        ElementType::Metric { label, value, delta, .. } => {
+            let text = match delta {
+                Some(delta) => format!("{}: {} ({})", label, value, delta),
+                None => format!("{}: {}", label, value),
+            };
+            vec![PdfLine::body(text)]
+        }
+        ElementType::Progress { value, text, .. } => {
+            let label = text.as_deref().unwrap_or("Progress");
+            vec![PdfLine::body(format!("{}: {:.0}%", label, value * 100.0))]
+        }
+        ElementType::LineChart { title, .. } => chart_placeholder("Line chart", title),
+        ElementType::BarChart { title, .. } => chart_placeholder("Bar chart", title),
+        ElementType::AreaChart { title, .. } => chart_placeholder("Area chart", title),
+        ElementType::ScatterChart { title, .. } => chart_placeholder("Scatter chart", title),
+        ElementType::PieChart { title, .. } => chart_placeholder("Pie chart", title),
+        ElementType::PlotlyChart { .. } => chart_placeholder("Plotly chart", &None),
+        ElementType::VegaLiteChart { .. } => chart_placeholder("Vega-Lite chart", &None),
+        ElementType::BokehChart { .. } => chart_placeholder("Bokeh chart", &None),
+        ElementType::Image { caption, .. } => {
+            vec![PdfLine::body(format!("[Image{}]", caption.as_deref().map(|c| format!(": {}", c)).unwrap_or_default()))]
+        }
+        ElementType::ImageAnnotator { annotations, .. } => {
+            vec![PdfLine::body(format!("[Annotated image: {} shapes]", annotations.len()))]
+        }
+        ElementType::Divider => vec![PdfLine::body("-".repeat(60))],
+        ElementType::Unknown { type_name } => vec![PdfLine::body(format!("[Unsupported element: {}]", type_name))],
+        ElementType::TextInput {
+            label,
+            kind: TextInputKind::Password,
+            ..
+        } => vec![PdfLine::body(format!("{}: ********", label))],
+        ElementType::TextInput { label, value, .. }
+        | ElementType::TextArea { label, value, .. }
+        | ElementType::DateInput { label, value, .. }
+        | ElementType::TimeInput { label, value, .. }
+        | ElementType::ColorPicker { label, value, .. } => {
+            vec![PdfLine::body(format!("{}: {}", label, value))]
+        }
+        ElementType::NumberInput {
+            label,
+            display: Some(display),
+            ..
+        } => vec![PdfLine::body(format!("{}: {}", label, display))],
+        ElementType::NumberInput { label, value, .. } | ElementType::Slider { label, value, .. } => {
+            vec![PdfLine::body(format!("{}: {}", label, value))]
+        }
+        ElementType::Checkbox { label, value, .. } => {
+            vec![PdfLine::body(format!("[{}] {}", if *value { "x" } else { " " }, label))]
+        }
+        ElementType::Radio { label, value, .. } | ElementType::Selectbox { label, value, .. } => {
+            vec![PdfLine::body(format!("{}: {}", label, value.clone().unwrap_or_default()))]
+        }
+        ElementType::Multiselect { label, values, .. } => {
+            vec![PdfLine::body(format!("{}: {}", label, values.join(", ")))]
+        }
+        _ => vec![],
+    }
+}
+
+fn chart_placeholder(kind: &str, title: &Option<String>) -> Vec<PdfLine> {
+    match title {
+        Some(title) => vec![PdfLine::body(format!("[{}: {}]", kind, title))],
+        None => vec![PdfLine::body(format!("[{}]", kind))],
+    }
+}
+
+/// Wrap lines that do not fit within the printable width.
+fn wrap_line(line: &PdfLine) -> Vec<PdfLine> {
+    let max_chars = ((PAGE_WIDTH - 2.0 * MARGIN) / (line.font_size * AVG_GLYPH_WIDTH)) as usize;
+    let chars: Vec<char> = line.text.chars().collect();
+    if chars.len() <= max_chars {
+        return vec![line.clone()];
+    }
+    chars
+        .chunks(max_chars.max(1))
+        .map(|chunk| PdfLine {
+            text: chunk.iter().collect(),
+            ..line.clone()
+        })
+        .collect()
+}
+
+/// Split blocks into pages that fit the printable height.
+pub fn paginate(blocks: &[PdfBlock]) -> Vec<Vec<PdfBlock>> {
+    let usable = PAGE_HEIGHT - 2.0 * MARGIN;
+    let mut pages = vec![];
+    let mut current = vec![];
+    let mut used = 0.0;
+
+    let wrapped = blocks.iter().flat_map(|block| match block {
+        PdfBlock::Line(line) => wrap_line(line).into_iter().map(PdfBlock::Line).collect(),
+        PdfBlock::Chart(_) => vec![block.clone()],
+    });
+    for block in wrapped {
+        let height = block.height();
+        if used + height > usable && !current.is_empty() {
+            pages.push(std::mem::take(&mut current));
+            used = 0.0;
+        }
+        used += height;
+        current.push(block);
+    }
+
+    if !current.is_empty() || pages.is_empty() {
+        pages.push(current);
+    }
+    pages
+}
+
+/// Encode a character in WinAnsiEncoding; characters outside it become
+/// `?`.
+fn win_ansi(c: char) -> u8 {
+    match c {
+        ' '..='~' | '\u{a0}'..='\u{ff}' => c as u8,
+        '€' => 0x80,
+        '‚' => 0x82,
+        'ƒ' => 0x83,
+        '„' => 0x84,
+        '…' => 0x85,
+        '†' => 0x86,
+        '‡' => 0x87,
+        'ˆ' => 0x88,
+        '‰' => 0x89,
+        'Š' => 0x8a,
+        '‹' => 0x8b,
+        'Œ' => 0x8c,
+        'Ž' => 0x8e,
+        '‘' => 0x91,
+        '’' => 0x92,
+        '“' => 0x93,
+        '”' => 0x94,
+        '•' => 0x95,
+        '–' => 0x96,
+        '—' => 0x97,
+        '˜' => 0x98,
+        '™' => 0x99,
+        'š' => 0x9a,
+        '›' => 0x9b,
+        'œ' => 0x9c,
+        'ž' => 0x9e,
+        'Ÿ' => 0x9f,
+        _ => b'?',
+    }
+}
+
+/// Escape a string for use inside a PDF literal string, with bytes past
+/// ASCII written as octal escapes.
+fn escape_text(text: &str) -> String {
+    text.chars()
+        .map(|c| match win_ansi(c) {
+            b'(' => "\\(".to_string(),
+            b')' => "\\)".to_string(),
+            b'\\' => "\\\\".to_string(),
+            byte if byte.is_ascii() => (byte as char).to_string(),
+            byte => format!("\\{:03o}", byte),
+        })
+        .collect()
+}
+
+fn page_content(blocks: &[PdfBlock]) -> String {
+    let mut content = String::new();
+    let mut y = PAGE_HEIGHT - MARGIN;
+    for block in blocks {
+        y -= block.height();
+        match block {
+            PdfBlock::Line(line) => content.push_str(&format!(
+                "BT /F1 {} Tf 1 0 0 1 {} {} Tm ({}) Tj ET\n",
+                line.font_size,
+                MARGIN,
+                y,
+                escape_text(&line.text)
+            )),
+            PdfBlock::Chart(chart) => content.push_str(&chart_content(chart, y + BODY_FONT_SIZE * LINE_SPACING)),
+        }
+    }
+    content
+}
+
+/// Draw `chart` in the printable width, its bottom at `bottom`.
+fn chart_content(chart: &PdfChart, bottom: f32) -> String {
+    let (left, width, height) = (MARGIN, PAGE_WIDTH - 2.0 * MARGIN, CHART_HEIGHT);
+    let mut content = String::from("q\n");
+    if chart.kind == ChartKind::Pie {
+        let values: Vec<f64> = chart.series[0].iter().map(|value| value.max(0.0)).collect();
+        let total: f64 = values.iter().sum();
+        let radius = height / 2.0;
+        let (cx, cy) = (left + radius, bottom + radius);
+        let mut start = 0.0;
+        for (index, value) in values.iter().enumerate() {
+            if total <= 0.0 || *value <= 0.0 {
+                continue;
+            }
+            let sweep = value / total * std::f64::consts::TAU;
+            let (r, g, b) = PALETTE[index % PALETTE.len()];
+            content.push_str(&format!("{:.2} {:.2} {:.2} rg {:.2} {:.2} m\n", r, g, b, cx, cy));
+            // Approximate the arc with segments of at most 5 degrees
+            let steps = (sweep.to_degrees() / 5.0).ceil().max(1.0) as usize;
+            for step in 0..=steps {
+                let angle = start + sweep * step as f64 / steps as f64;
+                let (x, y) = (cx + radius * angle.cos() as f32, cy + radius * angle.sin() as f32);
+                content.push_str(&format!("{:.2} {:.2} l\n", x, y));
+            }
+            content.push_str("h f\n");
+            start += sweep;
+        }
+        content.push_str("Q\n");
+        return content;
+    }
+
+    let bounds = |values: &mut dyn Iterator<Item = f64>| {
+        values.fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), value| (low.min(value), high.max(value)))
+    };
+    let (x_min, x_max) = bounds(&mut chart.x.iter().copied());
+    let (y_min, y_max) = bounds(&mut chart.series.iter().flatten().copied().chain([0.0]));
+    let span = |low: f64, high: f64| if high > low { high - low } else { 1.0 };
+    let to_x = |x: f64| left + ((x - x_min) / span(x_min, x_max)) as f32 * width;
+    let to_y = |y: f64| bottom + ((y - y_min) / span(y_min, y_max)) as f32 * height;
+
+    content.push_str(&format!(
+        "0.6 G 0.5 w {:.2} {:.2} m {:.2} {:.2} l {:.2} {:.2} m {:.2} {:.2} l S\n",
+        left,
+        bottom + height,
+        left,
+        bottom,
+        left,
+        to_y(0.0),
+        left + width,
+        to_y(0.0)
+    ));
+    let count = chart.x.len();
+    for (index, values) in chart.series.iter().enumerate() {
+        let (r, g, b) = PALETTE[index % PALETTE.len()];
+        let points: Vec<(f32, f32)> = chart.x.iter().zip(values).map(|(&x, &y)| (to_x(x), to_y(y))).collect();
+        match chart.kind {
+            ChartKind::Line | ChartKind::Area => {
+                let path: Vec<String> = points.iter().map(|(x, y)| format!("{:.2} {:.2}", x, y)).collect();
+                if chart.kind == ChartKind::Area {
+                    let (first, last) = (points[0].0, points[points.len() - 1].0);
+                    content.push_str(&format!(
+                        "{:.2} {:.2} {:.2} rg {:.2} {:.2} m {} l {:.2} {:.2} l h f\n",
+                        r,
+                        g,
+                        b,
+                        first,
+                        to_y(0.0),
+                        path.join(" l "),
+                        last,
+                        to_y(0.0)
+                    ));
+                } else {
+                    content.push_str(&format!("{:.2} {:.2} {:.2} RG 1.5 w {} m", r, g, b, path[0]));
+                    for point in &path[1..] {
+                        content.push_str(&format!(" {} l", point));
+                    }
+                    content.push_str(" S\n");
+                }
+            }
+            ChartKind::Scatter => {
+                content.push_str(&format!("{:.2} {:.2} {:.2} rg\n", r, g, b));
+                for (x, y) in points {
+                    content.push_str(&format!("{:.2} {:.2} 3 3 re f\n", x - 1.5, y - 1.5));
+                }
+            }
+            ChartKind::Bar => {
+                // Records share the width; a record's bars share 80% of its slot
+                let slot = width / count as f32;
+                let bar = slot * 0.8 / chart.series.len() as f32;
+                content.push_str(&format!("{:.2} {:.2} {:.2} rg\n", r, g, b));
+                for (record, (_, y)) in points.iter().enumerate() {
+                    let x = left + slot * (record as f32 + 0.1) + bar * index as f32;
+                    let base = to_y(0.0);
+                    let (y, height) = (base.min(*y), (y - base).abs());
+                    content.push_str(&format!("{:.2} {:.2} {:.2} {:.2} re f\n", x, y, bar, height));
+                }
+            }
+            ChartKind::Pie => {}
+        }
+    }
+    content.push_str("Q\n");
+    content
+}
+
+/// Render elements, in document order, to PDF bytes.
+pub fn render_pdf(elements: &[ElementType]) -> Vec<u8> {
+    let blocks: Vec<PdfBlock> = elements.iter().flat_map(element_blocks).collect();
+    let pages = paginate(&blocks);
+
+    // Object layout: 1 catalog, 2 pages, 3 font, then (page, content) pairs.
+    let mut objects: Vec<String> = vec![];
+    let kids: Vec<String> = (0..pages.len())
+        .map(|i| format!("{} 0 R", 4 + i * 2))
+        .collect();
+
+    objects.push("<< /Type /Catalog /Pages 2 0 R >>".to_string());
+    objects.push(format!(
+        "<< /Type /Pages /Kids [{}] /Count {} >>",
+        kids.join(" "),
+        pages.len()
+    ));
+    objects.push("<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_string());
+
+    for (i, page) in pages.iter().enumerate() {
+        let content_id = 5 + i * 2;
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+            PAGE_WIDTH, PAGE_HEIGHT, content_id
+        ));
+        let stream = page_content(page);
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            stream.len(),
+            stream
+        ));
+    }
+
+    let mut out = String::from("%PDF-1.4\n");
+    let mut offsets = vec![];
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.push_str(&format!("{} 0 obj\n{}\nendobj\n", i + 1, object));
+    }
+
+    let xref_offset = out.len();
+    out.push_str(&format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1));
+    for offset in offsets {
+        out.push_str(&format!("{:010} 00000 n \n", offset));
+    }
+    out.push_str(&format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref_offset
+    ));
+
+    out.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_element_lines() {
+        let lines = element_lines(&ElementType::Table {
+            headers: vec!["a".to_string(), "b".to_string()],
+            rows: vec![vec!["1".to_string(), "2".to_string()]],
+        });
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1].text, "1 | 2");
+        assert!(element_lines(&ElementType::Empty).is_empty());
+    }
+
+    #[test]
+    fn test_paginate() {
+        let lines: Vec<PdfBlock> = (0..200).map(|i| PdfBlock::Line(PdfLine::body(i.to_string()))).collect();
+        let pages = paginate(&lines);
+        assert!(pages.len() > 1);
+        assert_eq!(pages.iter().map(|p| p.len()).sum::<usize>(), 200);
+    }
+
+    #[test]
+    fn test_render_pdf() {
+        let bytes = render_pdf(&[
+            ElementType::Heading { value: "Report (Q1)".to_string(), level: 1 },
+            ElementType::Text { value: "Hello".to_string() },
+        ]);
+        let text = String::from_utf8(bytes).unwrap();
+        assert!(text.starts_with("%PDF-1.4"));
+        assert!(text.contains("(Report \\(Q1\\)) Tj"));
+        assert!(text.trim_end().ends_with("%%EOF"));
+    }
+
+    #[test]
+    fn test_escape_text_encodes_latin_1() {
+        assert_eq!(escape_text("Café – 5€"), "Caf\\351 \\226 5\\200");
+        assert_eq!(escape_text("日本"), "??");
+    }
+
+    #[test]
+    fn test_charts_are_drawn() {
+        let data = r#"[{"x": 1, "y": 10}, {"x": 2, "y": -5}, {"x": 3, "y": 15}]"#.to_string();
+        let chart = PdfChart::from_records(ChartKind::Line, &data).unwrap();
+        assert_eq!(chart.x, vec![1.0, 2.0, 3.0]);
+        assert_eq!(chart.series, vec![vec![10.0, -5.0, 15.0]]);
+
+        let blocks = element_blocks(&ElementType::BarChart {
+            data: data.clone(),
+            title: Some("Sales".to_string()),
+            key: None,
+        });
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0], PdfBlock::Line(PdfLine::body("Sales")));
+        let content = page_content(&blocks);
+        assert_eq!(content.matches(" re f").count(), 3);
+
+        let pie = element_blocks(&ElementType::PieChart { data, title: None, key: None });
+        assert_eq!(page_content(&pie).matches("h f").count(), 2);
+
+        // Data that is not records keeps its placeholder
+        let blocks = element_blocks(&ElementType::LineChart {
+            data: "{}".to_string(),
+            title: None,
+            key: None,
+        });
+        assert_eq!(blocks, vec![PdfBlock::Line(PdfLine::body("[Line chart]"))]);
+    }
+}
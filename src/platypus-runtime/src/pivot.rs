@@ -0,0 +1,261 @@
+//! Pivot tables over `st.dataframe` records.
+//!
+//! `st.pivot_table(data, rows, cols, values, agg)` aggregates a JSON records
+//! array server-side and renders an expandable grid: with several row
+//! fields, each group shows its subtotal and expands into its children.
+//! Clicking a cell drills down, returning the records behind it:
+//!
+//! ```
+//! use platypus_runtime::pivot::Aggregation;
+//! use platypus_runtime::St;
+//!
+//! let data = r#"[
+//!     {"region": "EMEA", "country": "DE", "year": 2024, "revenue": 120},
+//!     {"region": "EMEA", "country": "FR", "year": 2024, "revenue": 80}
+//! ]"#;
+//! let mut st = St::new();
+//! let drill = st.pivot_table(data, &["region", "country"], &["year"], "revenue", Aggregation::Sum, "sales");
+//! if let Some(drill) = drill {
+//!     st.dataframe(drill.records.to_records());
+//! }
+//! ```
+
+use crate::explorer::Frame;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// How the values in a pivot cell are combined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Aggregation {
+    Sum,
+    Mean,
+    /// Number of records, numeric or not.
+    Count,
+    Min,
+    Max,
+}
+
+impl Aggregation {
+    /// Get the aggregation name.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Aggregation::Sum => "sum",
+            Aggregation::Mean => "mean",
+            Aggregation::Count => "count",
+            Aggregation::Min => "min",
+            Aggregation::Max => "max",
+        }
+    }
+}
+
+/// Running totals of the values falling in one cell.
+#[derive(Debug, Clone, Copy, Default)]
+struct Accumulator {
+    records: usize,
+    numbers: usize,
+    sum: f64,
+    min: Option<f64>,
+    max: Option<f64>,
+}
+
+impl Accumulator {
+    fn add(&mut self, value: Option<f64>) {
+        self.records += 1;
+        if let Some(value) = value {
+            self.numbers += 1;
+            self.sum += value;
+            self.min = Some(self.min.map_or(value, |min| min.min(value)));
+            self.max = Some(self.max.map_or(value, |max| max.max(value)));
+        }
+    }
+
+    fn value(&self, agg: Aggregation) -> Option<f64> {
+        match agg {
+            Aggregation::Count => Some(self.records as f64),
+            _ if self.numbers == 0 => None,
+            Aggregation::Sum => Some(self.sum),
+            Aggregation::Mean => Some(self.sum / self.numbers as f64),
+            Aggregation::Min => self.min,
+            Aggregation::Max => self.max,
+        }
+    }
+}
+
+/// One row of the grid: a group of records sharing the first `path.len()`
+/// row field values.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PivotRow {
+    pub path: Vec<String>,
+    /// One value per column key; `None` when no record falls in the cell.
+    pub cells: Vec<Option<f64>>,
+    pub total: Option<f64>,
+}
+
+/// A computed pivot table, as sent to the client.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Pivot {
+    pub row_fields: Vec<String>,
+    pub col_fields: Vec<String>,
+    pub value_field: String,
+    pub agg: Aggregation,
+    /// Column keys, sorted.
+    pub columns: Vec<Vec<String>>,
+    /// Groups at every depth, each followed by its children, sorted.
+    pub rows: Vec<PivotRow>,
+    /// Column totals and the grand total.
+    pub totals: PivotRow,
+}
+
+impl Pivot {
+    /// Pivot `frame`, aggregating `value_field` by `row_fields` down and
+    /// `col_fields` across. Unknown fields are ignored.
+    pub fn compute(
+        frame: &Frame,
+        row_fields: &[&str],
+        col_fields: &[&str],
+        value_field: &str,
+        agg: Aggregation,
+    ) -> Self {
+        let row_columns: Vec<usize> = row_fields.iter().filter_map(|field| frame.column(field)).collect();
+        let col_columns: Vec<usize> = col_fields.iter().filter_map(|field| frame.column(field)).collect();
+        let value_column = frame.column(value_field);
+
+        // Keyed by (row path, column key); `None` as column key is the total
+        let mut cells: BTreeMap<(Vec<String>, Option<Vec<String>>), Accumulator> = BTreeMap::new();
+        let mut columns: Vec<Vec<String>> = Vec::new();
+        for record in &frame.rows {
+            let path: Vec<String> = row_columns.iter().map(|&c| record[c].clone()).collect();
+            let column: Vec<String> = col_columns.iter().map(|&c| record[c].clone()).collect();
+            let value = value_column.and_then(|c| record[c].trim().parse::<f64>().ok());
+            if !columns.contains(&column) {
+                columns.push(column.clone());
+            }
+            for depth in 0..=path.len() {
+                let prefix = path[..depth].to_vec();
+                cells.entry((prefix.clone(), Some(column.clone()))).or_default().add(value);
+                cells.entry((prefix, None)).or_default().add(value);
+            }
+        }
+        columns.sort();
+
+        let row_for = |path: Vec<String>| PivotRow {
+            cells: columns
+                .iter()
+                .map(|column| {
+                    cells
+                        .get(&(path.clone(), Some(column.clone())))
+                        .and_then(|cell| cell.value(agg))
+                })
+                .collect(),
+            total: cells.get(&(path.clone(), None)).and_then(|cell| cell.value(agg)),
+            path,
+        };
+        // Sorted paths put each group right before its children
+        let rows = cells
+            .keys()
+            .filter(|(path, column)| column.is_none() && !path.is_empty())
+            .map(|(path, _)| row_for(path.clone()))
+            .collect();
+        let totals = row_for(Vec::new());
+
+        Pivot {
+            row_fields: row_columns.iter().map(|&c| frame.headers[c].clone()).collect(),
+            col_fields: col_columns.iter().map(|&c| frame.headers[c].clone()).collect(),
+            value_field: value_field.to_string(),
+            agg,
+            columns,
+            rows,
+            totals,
+        }
+    }
+}
+
+/// A drill-down request sent when the user clicks a pivot cell.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DrillTarget {
+    /// Row group path; empty for the totals row.
+    #[serde(default)]
+    pub row: Vec<String>,
+    /// Column key; `None` for the totals column.
+    #[serde(default)]
+    pub col: Option<Vec<String>>,
+}
+
+/// The records behind a clicked pivot cell.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PivotDrill {
+    pub target: DrillTarget,
+    pub records: Frame,
+}
+
+impl PivotDrill {
+    /// Select the records of `frame` behind `target`.
+    pub fn select(frame: &Frame, pivot: &Pivot, target: DrillTarget) -> Self {
+        let row_columns: Vec<usize> = pivot.row_fields.iter().filter_map(|field| frame.column(field)).collect();
+        let col_columns: Vec<usize> = pivot.col_fields.iter().filter_map(|field| frame.column(field)).collect();
+        let rows = frame
+            .rows
+            .iter()
+            .filter(|record| {
+                let in_row = target
+                    .row
+                    .iter()
+                    .zip(&row_columns)
+                    .all(|(value, &c)| record[c] == *value);
+                let in_col = target.col.as_ref().is_none_or(|col| {
+                    col.iter().zip(&col_columns).all(|(value, &c)| record[c] == *value)
+                });
+                in_row && in_col
+            })
+            .cloned()
+            .collect();
+        PivotDrill {
+            target,
+            records: Frame {
+                headers: frame.headers.clone(),
+                rows,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SALES: &str = r#"[
+        {"region": "EMEA", "country": "DE", "year": "2023", "revenue": 100},
+        {"region": "EMEA", "country": "DE", "year": "2024", "revenue": 120},
+        {"region": "EMEA", "country": "FR", "year": "2024", "revenue": 80},
+        {"region": "APAC", "country": "JP", "year": "2024", "revenue": 50}
+    ]"#;
+
+    #[test]
+    fn test_pivot_with_subtotals() {
+        let frame = Frame::from_records(SALES).unwrap();
+        let pivot = Pivot::compute(&frame, &["region", "country"], &["year"], "revenue", Aggregation::Sum);
+
+        assert_eq!(pivot.columns, vec![vec!["2023".to_string()], vec!["2024".to_string()]]);
+        let paths: Vec<String> = pivot.rows.iter().map(|row| row.path.join("/")).collect();
+        assert_eq!(paths, vec!["APAC", "APAC/JP", "EMEA", "EMEA/DE", "EMEA/FR"]);
+        // The EMEA group subtotals its countries
+        assert_eq!(pivot.rows[2].cells, vec![Some(100.0), Some(200.0)]);
+        assert_eq!(pivot.rows[1].cells, vec![None, Some(50.0)]);
+        assert_eq!(pivot.totals.total, Some(350.0));
+
+        let mean = Pivot::compute(&frame, &["region"], &[], "revenue", Aggregation::Mean);
+        assert_eq!(mean.rows[1].total, Some(100.0));
+    }
+
+    #[test]
+    fn test_drill_down_selects_records() {
+        let frame = Frame::from_records(SALES).unwrap();
+        let pivot = Pivot::compute(&frame, &["region", "country"], &["year"], "revenue", Aggregation::Sum);
+        let target = DrillTarget {
+            row: vec!["EMEA".to_string()],
+            col: Some(vec!["2024".to_string()]),
+        };
+        assert_eq!(PivotDrill::select(&frame, &pivot, target).records.rows.len(), 2);
+    }
+}
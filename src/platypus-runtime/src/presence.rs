@@ -0,0 +1,206 @@
+//! Presence: who else is connected to the app or a room.
+//!
+//! Every connected session is listed in the server's [`PresenceRegistry`]
+//! with the rooms its last run joined. Apps read it with `st.presence()`
+//! or `st.presence_in(room)`; when a session joins, leaves or moves between
+//! rooms, the sessions that read presence rerun:
+//!
+//! ```
+//! use platypus_runtime::St;
+//!
+//! let mut st = St::new();
+//! let viewers = st.presence_in("doc-42");
+//! st.write(format!("{} other people viewing", viewers.len()));
+//! ```
+
+use crate::room::CHANGE_CAPACITY;
+use dashmap::DashMap;
+use platypus_core::session::SessionId;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+/// Session metadata key under which auth plugins store the signed-in
+/// user's display name. Sessions without it are listed anonymously.
+pub const USER_METADATA_KEY: &str = "user";
+
+/// A connected session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Presence {
+    pub session_id: SessionId,
+    /// Signed-in user, when an auth plugin provides one.
+    pub user: Option<String>,
+    /// Rooms the session's last run joined.
+    pub rooms: BTreeSet<String>,
+    /// Connection time (Unix seconds).
+    pub joined_at: u64,
+}
+
+impl Presence {
+    /// Get the user name, or an anonymous name derived from the session ID.
+    pub fn display_name(&self) -> String {
+        match &self.user {
+            Some(user) => user.clone(),
+            None => format!("Guest {}", &self.session_id.to_string()[..4]),
+        }
+    }
+}
+
+/// What happened to a session's presence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PresenceChangeKind {
+    Joined,
+    /// The session's user or rooms changed.
+    Updated,
+    Left,
+}
+
+/// A presence change, broadcast to every session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PresenceChange {
+    pub session_id: SessionId,
+    pub kind: PresenceChangeKind,
+}
+
+/// Connected sessions, shared by every session on the server.
+pub struct PresenceRegistry {
+    sessions: Arc<DashMap<SessionId, Presence>>,
+    changes: broadcast::Sender<PresenceChange>,
+}
+
+impl PresenceRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        PresenceRegistry {
+            sessions: Arc::new(DashMap::new()),
+            changes: broadcast::channel(CHANGE_CAPACITY).0,
+        }
+    }
+
+    /// Record a session's user and rooms, joining it if it is new.
+    /// Notifies other sessions only when something changed.
+    pub fn update(&self, session_id: SessionId, user: Option<String>, rooms: BTreeSet<String>) {
+        let kind = match self.sessions.get_mut(&session_id) {
+            Some(mut presence) => {
+                if presence.user == user && presence.rooms == rooms {
+                    return;
+                }
+                presence.user = user;
+                presence.rooms = rooms;
+                PresenceChangeKind::Updated
+            }
+            None => {
+                let joined_at = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs();
+                self.sessions.insert(
+                    session_id,
+                    Presence {
+                        session_id,
+                        user,
+                        rooms,
+                        joined_at,
+                    },
+                );
+                PresenceChangeKind::Joined
+            }
+        };
+        self.notify(session_id, kind);
+    }
+
+    /// Remove a session that disconnected.
+    pub fn leave(&self, session_id: SessionId) {
+        if self.sessions.remove(&session_id).is_some() {
+            self.notify(session_id, PresenceChangeKind::Left);
+        }
+    }
+
+    /// List the sessions other than `except`, oldest first.
+    pub fn others(&self, except: Option<SessionId>) -> Vec<Presence> {
+        let mut others: Vec<_> = self
+            .sessions
+            .iter()
+            .filter(|presence| Some(presence.session_id) != except)
+            .map(|presence| presence.clone())
+            .collect();
+        others.sort_by(|a, b| {
+            (a.joined_at, a.session_id.to_string()).cmp(&(b.joined_at, b.session_id.to_string()))
+        });
+        others
+    }
+
+    /// List the sessions other than `except` in a room, oldest first.
+    pub fn in_room(&self, room: &str, except: Option<SessionId>) -> Vec<Presence> {
+        self.others(except)
+            .into_iter()
+            .filter(|presence| presence.rooms.contains(room))
+            .collect()
+    }
+
+    /// Subscribe to presence changes.
+    pub fn subscribe(&self) -> broadcast::Receiver<PresenceChange> {
+        self.changes.subscribe()
+    }
+
+    /// Get the number of connected sessions.
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// Check if no session is connected.
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    fn notify(&self, session_id: SessionId, kind: PresenceChangeKind) {
+        let _ = self.changes.send(PresenceChange { session_id, kind });
+    }
+}
+
+impl Default for PresenceRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for PresenceRegistry {
+    fn clone(&self) -> Self {
+        PresenceRegistry {
+            sessions: Arc::clone(&self.sessions),
+            changes: self.changes.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presence_join_update_leave() {
+        let registry = PresenceRegistry::new();
+        let (alice, bob) = (SessionId::new(), SessionId::new());
+        let mut changes = registry.subscribe();
+
+        registry.update(alice, Some("Alice".to_string()), BTreeSet::from(["doc".to_string()]));
+        registry.update(bob, None, BTreeSet::new());
+        assert_eq!(changes.try_recv().unwrap().kind, PresenceChangeKind::Joined);
+
+        let others = registry.others(Some(bob));
+        assert_eq!(others.len(), 1);
+        assert_eq!(others[0].display_name(), "Alice");
+        assert_eq!(registry.in_room("doc", Some(alice)).len(), 0);
+        assert!(registry.others(Some(alice))[0].display_name().starts_with("Guest "));
+
+        // An unchanged update is not broadcast
+        changes.try_recv().unwrap();
+        registry.update(bob, None, BTreeSet::new());
+        assert!(changes.try_recv().is_err());
+
+        registry.leave(alice);
+        assert_eq!(changes.try_recv().unwrap().kind, PresenceChangeKind::Left);
+        assert_eq!(registry.len(), 1);
+    }
+}
@@ -0,0 +1,170 @@
+//! Progress bars with labels, ETA and indeterminate mode.
+//!
+//! `st.progress_bar()` returns a builder; `show()` adds the element and
+//! hands back a [`ProgressHandle`] that updates it in place with
+//! UpdateElement deltas instead of adding new elements.
+
+use platypus_core::element::{ElementId, ElementType};
+use platypus_core::state::DeltaGenerator;
+use std::time::Instant;
+
+/// Builder for a progress bar.
+pub struct ProgressBuilder {
+    delta_gen: DeltaGenerator,
+    parent_id: Option<ElementId>,
+    value: f32,
+    text: Option<String>,
+    indeterminate: bool,
+}
+
+impl ProgressBuilder {
+    /// Create a builder adding into the given container.
+    pub(crate) fn new(delta_gen: DeltaGenerator, parent_id: Option<ElementId>) -> Self {
+        ProgressBuilder {
+            delta_gen,
+            parent_id,
+            value: 0.0,
+            text: None,
+            indeterminate: false,
+        }
+    }
+
+    /// Set the initial value (clamped to 0.0..=1.0).
+    pub fn value(mut self, value: f32) -> Self {
+        self.value = value.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Set the label shown next to the bar.
+    pub fn text(mut self, text: impl Into<String>) -> Self {
+        self.text = Some(text.into());
+        self
+    }
+
+    /// Show an animated bar without a known completion fraction.
+    pub fn indeterminate(mut self) -> Self {
+        self.indeterminate = true;
+        self
+    }
+
+    /// Add the progress bar and return a handle for updating it.
+    pub fn show(self) -> ProgressHandle {
+        let id = self.delta_gen.add_element(
+            ElementType::Progress {
+                value: self.value,
+                text: self.text.clone(),
+                indeterminate: self.indeterminate,
+                eta_secs: None,
+            },
+            self.parent_id,
+        );
+
+        ProgressHandle {
+            id,
+            delta_gen: self.delta_gen,
+            started: Instant::now(),
+            value: self.value,
+            text: self.text,
+        }
+    }
+}
+
+/// Handle for updating a progress bar in place.
+pub struct ProgressHandle {
+    id: ElementId,
+    delta_gen: DeltaGenerator,
+    started: Instant,
+    value: f32,
+    text: Option<String>,
+}
+
+impl ProgressHandle {
+    /// Get the element ID.
+    pub fn id(&self) -> ElementId {
+        self.id
+    }
+
+    /// Get the current value.
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    /// Set the value and label, updating the ETA.
+    pub fn set(&mut self, value: f32, text: impl Into<String>) {
+        self.text = Some(text.into());
+        self.set_value(value);
+    }
+
+    /// Set the value, keeping the current label.
+    pub fn set_value(&mut self, value: f32) {
+        self.value = value.clamp(0.0, 1.0);
+        let eta_secs = eta_secs(self.started.elapsed().as_secs_f64(), self.value);
+        self.emit(false, eta_secs);
+    }
+
+    /// Switch to indeterminate mode with a label.
+    pub fn set_indeterminate(&mut self, text: impl Into<String>) {
+        self.text = Some(text.into());
+        self.emit(true, None);
+    }
+
+    /// Mark the bar as complete.
+    pub fn complete(&mut self) {
+        self.value = 1.0;
+        self.emit(false, None);
+    }
+
+    fn emit(&self, indeterminate: bool, eta_secs: Option<f64>) {
+        self.delta_gen.update_element(
+            self.id,
+            ElementType::Progress {
+                value: self.value,
+                text: self.text.clone(),
+                indeterminate,
+                eta_secs,
+            },
+        );
+    }
+}
+
+/// Estimate remaining seconds from elapsed time and completed fraction.
+fn eta_secs(elapsed_secs: f64, value: f32) -> Option<f64> {
+    if value <= 0.0 || value >= 1.0 {
+        return None;
+    }
+    let value = value as f64;
+    Some(elapsed_secs * (1.0 - value) / value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use platypus_core::state::Delta;
+
+    #[test]
+    fn test_handle_emits_updates() {
+        let delta_gen = DeltaGenerator::new();
+        let mut handle = ProgressBuilder::new(delta_gen.clone(), None)
+            .text("Starting")
+            .show();
+        handle.set(0.4, "Parsing...");
+
+        let deltas = delta_gen.take_deltas();
+        assert_eq!(deltas.len(), 2);
+        match &deltas[1] {
+            Delta::UpdateElement { id, element: ElementType::Progress { value, text, .. } } => {
+                assert_eq!(*id, handle.id());
+                assert_eq!(*value, 0.4);
+                assert_eq!(text.as_deref(), Some("Parsing..."));
+            }
+            other => panic!("unexpected delta: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_eta() {
+        assert_eq!(eta_secs(10.0, 0.5), Some(10.0));
+        assert_eq!(eta_secs(10.0, 0.0), None);
+        assert_eq!(eta_secs(10.0, 1.0), None);
+    }
+}
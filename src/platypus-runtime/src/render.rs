@@ -0,0 +1,342 @@
+//! Static rendering of a page to HTML and PNG.
+//!
+//! Pages are flattened to lines with the PDF exporter's
+//! [`element_lines`](crate::pdf::element_lines), so a page shows the same
+//! text in every format. The HTML is print-friendly; the PNG draws the
+//! lines in a built-in bitmap font, so the same page always renders to the
+//! same bytes, as reports and visual regression tests need. Images stop at
+//! `MAX_PNG_HEIGHT` pixels, and a longer page ends in a line counting the
+//! lines left out.
+
+use crate::pdf::{self, PdfLine};
+use platypus_core::element::{ElementId, ElementType};
+use platypus_core::state::Delta;
+use std::collections::{HashMap, HashSet};
+
+/// Image width in pixels.
+pub const PNG_WIDTH: usize = 800;
+/// Most image height in pixels, about 1000 body lines.
+pub const MAX_PNG_HEIGHT: usize = 16_384;
+/// Margin around the image's text in pixels.
+const PNG_MARGIN: usize = 24;
+/// Glyph cell size in pixels, at body size.
+const GLYPH_WIDTH: usize = 7;
+const GLYPH_HEIGHT: usize = 13;
+/// Line height in pixels, at body size.
+const LINE_HEIGHT: usize = 16;
+const BACKGROUND: u8 = 0xff;
+const INK: u8 = 0x22;
+
+/// The elements a run's deltas leave on the page, in page order, with
+/// updates applied and removed elements left out.
+pub fn page_elements(deltas: &[Delta]) -> Vec<ElementType> {
+    let mut parents: HashMap<ElementId, Option<ElementId>> = HashMap::new();
+    let mut removed: HashSet<ElementId> = HashSet::new();
+    let mut page: Vec<(ElementId, ElementType)> = Vec::new();
+    for delta in deltas {
+        match delta {
+            Delta::AddElement { id, element, parent_id } => {
+                parents.insert(*id, *parent_id);
+                page.push((*id, element.clone()));
+            }
+            Delta::UpdateElement { id, element } => {
+                if let Some(entry) = page.iter_mut().find(|(added, _)| added == id) {
+                    entry.1 = element.clone();
+                }
+            }
+            Delta::RemoveElement { id } => {
+                removed.insert(*id);
+            }
+            Delta::ClearContainer { id } => {
+                removed.extend(parents.iter().filter(|(_, parent)| **parent == Some(*id)).map(|(child, _)| *child));
+            }
+            _ => {}
+        }
+    }
+
+    let is_removed = |mut id: ElementId| loop {
+        if removed.contains(&id) {
+            return true;
+        }
+        match parents.get(&id).copied().flatten() {
+            Some(parent) => id = parent,
+            None => return false,
+        }
+    };
+    page.into_iter()
+        .filter(|(id, _)| !is_removed(*id))
+        .map(|(_, element)| element)
+        .collect()
+}
+
+/// Render elements to a standalone, print-friendly HTML document.
+pub fn render_html(title: &str, elements: &[ElementType]) -> String {
+    let mut body = String::new();
+    for line in elements.iter().flat_map(pdf::element_lines) {
+        let text = html_escape(&line.text);
+        match line.heading_level().map(|level| level.clamp(1, 6)) {
+            Some(level) => body.push_str(&format!("<h{0}>{1}</h{0}>\n", level, text)),
+            None => body.push_str(&format!("<p>{}</p>\n", text)),
+        }
+    }
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n\
+         body {{ font-family: sans-serif; max-width: 48rem; margin: 2rem auto; color: #222; }}\n\
+         p {{ margin: 0.25rem 0; white-space: pre-wrap; }}\n\
+         @media print {{ body {{ margin: 0; }} }}\n\
+         </style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        html_escape(title),
+        body
+    )
+}
+
+/// Render elements to a grayscale PNG, `PNG_WIDTH` pixels wide and as tall
+/// as the page, up to `MAX_PNG_HEIGHT`.
+pub fn render_png(elements: &[ElementType]) -> Vec<u8> {
+    let mut lines: Vec<(String, usize)> = elements
+        .iter()
+        .flat_map(pdf::element_lines)
+        .flat_map(|line| wrap(&line))
+        .collect();
+    let usable = MAX_PNG_HEIGHT - 2 * PNG_MARGIN;
+    if lines.iter().map(|(_, scale)| LINE_HEIGHT * scale).sum::<usize>() > usable {
+        // Keep the lines that fit above a body line counting the rest
+        let mut used = LINE_HEIGHT;
+        let fits = lines
+            .iter()
+            .take_while(|(_, scale)| {
+                used += LINE_HEIGHT * scale;
+                used <= usable
+            })
+            .count();
+        let omitted = lines.len() - fits;
+        lines.truncate(fits);
+        lines.push((format!("[{} more lines]", omitted), 1));
+    }
+    let height = 2 * PNG_MARGIN + lines.iter().map(|(_, scale)| LINE_HEIGHT * scale).sum::<usize>();
+
+    let mut pixels = vec![BACKGROUND; PNG_WIDTH * height];
+    let mut top = PNG_MARGIN;
+    for (text, scale) in &lines {
+        for (column, character) in text.chars().enumerate() {
+            draw_glyph(&mut pixels, PNG_MARGIN + column * GLYPH_WIDTH * scale, top, character, *scale);
+        }
+        top += LINE_HEIGHT * scale;
+    }
+    encode_png(PNG_WIDTH, height, &pixels)
+}
+
+/// Split a line into rows that fit the image, with its glyph scale:
+/// headings are drawn at twice body size.
+fn wrap(line: &PdfLine) -> Vec<(String, usize)> {
+    let scale = if line.heading_level().is_some() { 2 } else { 1 };
+    let max_chars = (PNG_WIDTH - 2 * PNG_MARGIN) / (GLYPH_WIDTH * scale);
+    let chars: Vec<char> = line.text.chars().collect();
+    if chars.is_empty() {
+        return vec![(String::new(), scale)];
+    }
+    chars
+        .chunks(max_chars)
+        .map(|chunk| (chunk.iter().collect(), scale))
+        .collect()
+}
+
+fn draw_glyph(pixels: &mut [u8], left: usize, top: usize, character: char, scale: usize) {
+    let index = match character {
+        ' '..='~' => character as usize - ' ' as usize,
+        _ => '?' as usize - ' ' as usize,
+    };
+    for (row, bits) in GLYPHS[index].iter().enumerate() {
+        for column in 0..GLYPH_WIDTH {
+            if bits & (1 << (GLYPH_WIDTH - 1 - column)) == 0 {
+                continue;
+            }
+            for y in 0..scale {
+                let start = (top + row * scale + y) * PNG_WIDTH + left + column * scale;
+                pixels[start..start + scale].fill(INK);
+            }
+        }
+    }
+}
+
+/// Encode 8-bit grayscale pixels as a PNG.
+fn encode_png(width: usize, height: usize, pixels: &[u8]) -> Vec<u8> {
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    // Writing whole, correctly sized images to a Vec cannot fail
+    let mut writer = encoder.write_header().expect("PNG header");
+    writer.write_image_data(pixels).expect("PNG image data");
+    writer.finish().expect("PNG end");
+    png
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Printable ASCII glyphs, one row of `GLYPH_WIDTH` bits per byte, high
+/// bit leftmost; rasterized from DejaVu Sans Mono.
+const GLYPHS: [[u8; GLYPH_HEIGHT]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x00, 0x00, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x00, 0x08, 0x08, 0x00, 0x00], // '!'
+    [0x00, 0x00, 0x04, 0x14, 0x14, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '"'
+    [0x00, 0x00, 0x00, 0x0a, 0x12, 0x3f, 0x14, 0x34, 0x7e, 0x2c, 0x28, 0x00, 0x00], // '#'
+    [0x00, 0x00, 0x08, 0x08, 0x3e, 0x28, 0x38, 0x1e, 0x0a, 0x0a, 0x3c, 0x08, 0x08], // '$'
+    [0x00, 0x00, 0x00, 0x30, 0x48, 0x78, 0x3e, 0x36, 0x0b, 0x09, 0x06, 0x00, 0x00], // '%'
+    [0x00, 0x00, 0x08, 0x34, 0x20, 0x10, 0x38, 0x69, 0x45, 0x66, 0x3f, 0x00, 0x00], // '&'
+    [0x00, 0x00, 0x08, 0x08, 0x08, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // "'"
+    [0x00, 0x00, 0x04, 0x08, 0x08, 0x08, 0x18, 0x18, 0x18, 0x08, 0x08, 0x0c, 0x04], // '('
+    [0x00, 0x00, 0x10, 0x08, 0x08, 0x08, 0x0c, 0x0c, 0x0c, 0x08, 0x08, 0x18, 0x10], // ')'
+    [0x00, 0x00, 0x00, 0x08, 0x1c, 0x1c, 0x2a, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '*'
+    [0x00, 0x00, 0x00, 0x00, 0x08, 0x08, 0x08, 0x3e, 0x08, 0x08, 0x00, 0x00, 0x00], // '+'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x08, 0x18, 0x00], // ','
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1c, 0x00, 0x00, 0x00, 0x00, 0x00], // '-'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x08, 0x00, 0x00], // '.'
+    [0x00, 0x00, 0x02, 0x02, 0x04, 0x04, 0x08, 0x08, 0x10, 0x10, 0x20, 0x20, 0x00], // '/'
+    [0x00, 0x00, 0x08, 0x36, 0x22, 0x22, 0x2a, 0x2a, 0x22, 0x26, 0x1c, 0x00, 0x00], // '0'
+    [0x00, 0x00, 0x08, 0x3c, 0x0c, 0x0c, 0x0c, 0x0c, 0x0c, 0x0c, 0x3e, 0x00, 0x00], // '1'
+    [0x00, 0x00, 0x18, 0x3e, 0x02, 0x02, 0x04, 0x0c, 0x18, 0x30, 0x3e, 0x00, 0x00], // '2'
+    [0x00, 0x00, 0x18, 0x3e, 0x02, 0x06, 0x1c, 0x06, 0x02, 0x06, 0x3c, 0x00, 0x00], // '3'
+    [0x00, 0x00, 0x04, 0x0c, 0x0c, 0x14, 0x34, 0x24, 0x7e, 0x04, 0x04, 0x00, 0x00], // '4'
+    [0x00, 0x00, 0x1c, 0x3c, 0x20, 0x38, 0x3e, 0x02, 0x02, 0x06, 0x3c, 0x00, 0x00], // '5'
+    [0x00, 0x00, 0x0c, 0x1c, 0x20, 0x2c, 0x36, 0x22, 0x22, 0x22, 0x1c, 0x00, 0x00], // '6'
+    [0x00, 0x00, 0x3e, 0x3e, 0x06, 0x04, 0x04, 0x08, 0x08, 0x18, 0x10, 0x00, 0x00], // '7'
+    [0x00, 0x00, 0x08, 0x36, 0x22, 0x22, 0x1c, 0x22, 0x22, 0x22, 0x3c, 0x00, 0x00], // '8'
+    [0x00, 0x00, 0x18, 0x36, 0x22, 0x22, 0x22, 0x3e, 0x02, 0x06, 0x3c, 0x00, 0x00], // '9'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x08, 0x00, 0x00, 0x08, 0x08, 0x00, 0x00], // ':'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x08, 0x00, 0x00, 0x08, 0x08, 0x18, 0x00], // ';'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x06, 0x38, 0x70, 0x1c, 0x07, 0x00, 0x00, 0x00], // '<'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x3e, 0x00, 0x7f, 0x00, 0x00, 0x00, 0x00], // '='
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x30, 0x0e, 0x07, 0x1c, 0x60, 0x00, 0x00, 0x00], // '>'
+    [0x00, 0x00, 0x08, 0x3e, 0x02, 0x06, 0x0c, 0x08, 0x08, 0x00, 0x08, 0x00, 0x00], // '?'
+    [0x00, 0x00, 0x00, 0x1c, 0x32, 0x25, 0x4b, 0x51, 0x51, 0x4f, 0x20, 0x30, 0x0e], // '@'
+    [0x00, 0x00, 0x08, 0x1c, 0x1c, 0x14, 0x34, 0x26, 0x3e, 0x22, 0x63, 0x00, 0x00], // 'A'
+    [0x00, 0x00, 0x38, 0x3e, 0x22, 0x22, 0x3c, 0x22, 0x22, 0x22, 0x3e, 0x00, 0x00], // 'B'
+    [0x00, 0x00, 0x0c, 0x1e, 0x20, 0x20, 0x20, 0x20, 0x20, 0x30, 0x1e, 0x00, 0x00], // 'C'
+    [0x00, 0x00, 0x30, 0x3c, 0x26, 0x22, 0x22, 0x22, 0x22, 0x26, 0x3c, 0x00, 0x00], // 'D'
+    [0x00, 0x00, 0x3e, 0x3e, 0x20, 0x20, 0x3e, 0x20, 0x20, 0x20, 0x3e, 0x00, 0x00], // 'E'
+    [0x00, 0x00, 0x1e, 0x3e, 0x30, 0x30, 0x3e, 0x30, 0x30, 0x30, 0x30, 0x00, 0x00], // 'F'
+    [0x00, 0x00, 0x0c, 0x1e, 0x20, 0x20, 0x60, 0x66, 0x22, 0x32, 0x1e, 0x00, 0x00], // 'G'
+    [0x00, 0x00, 0x22, 0x22, 0x22, 0x22, 0x3e, 0x22, 0x22, 0x22, 0x22, 0x00, 0x00], // 'H'
+    [0x00, 0x00, 0x1c, 0x1c, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x3e, 0x00, 0x00], // 'I'
+    [0x00, 0x00, 0x1c, 0x1c, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x3c, 0x00, 0x00], // 'J'
+    [0x00, 0x00, 0x20, 0x22, 0x24, 0x28, 0x38, 0x2c, 0x24, 0x26, 0x23, 0x00, 0x00], // 'K'
+    [0x00, 0x00, 0x00, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x3f, 0x00, 0x00], // 'L'
+    [0x00, 0x00, 0x22, 0x63, 0x77, 0x77, 0x7b, 0x6b, 0x63, 0x63, 0x63, 0x00, 0x00], // 'M'
+    [0x00, 0x00, 0x22, 0x32, 0x32, 0x32, 0x2a, 0x2a, 0x26, 0x26, 0x26, 0x00, 0x00], // 'N'
+    [0x00, 0x00, 0x08, 0x3e, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x1c, 0x00, 0x00], // 'O'
+    [0x00, 0x00, 0x38, 0x3e, 0x22, 0x22, 0x36, 0x3c, 0x20, 0x20, 0x20, 0x00, 0x00], // 'P'
+    [0x00, 0x00, 0x08, 0x3e, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x1c, 0x04, 0x00], // 'Q'
+    [0x00, 0x00, 0x30, 0x3e, 0x22, 0x22, 0x3c, 0x3c, 0x26, 0x22, 0x23, 0x00, 0x00], // 'R'
+    [0x00, 0x00, 0x0c, 0x3e, 0x20, 0x20, 0x3c, 0x06, 0x02, 0x02, 0x3c, 0x00, 0x00], // 'S'
+    [0x00, 0x00, 0x3e, 0x3e, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x00, 0x00], // 'T'
+    [0x00, 0x00, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x1c, 0x00, 0x00], // 'U'
+    [0x00, 0x00, 0x00, 0x62, 0x22, 0x22, 0x36, 0x14, 0x14, 0x1c, 0x08, 0x00, 0x00], // 'V'
+    [0x00, 0x00, 0x41, 0x41, 0x43, 0x6b, 0x7a, 0x36, 0x36, 0x36, 0x36, 0x00, 0x00], // 'W'
+    [0x00, 0x00, 0x00, 0x22, 0x16, 0x1c, 0x08, 0x1c, 0x14, 0x22, 0x63, 0x00, 0x00], // 'X'
+    [0x00, 0x00, 0x00, 0x22, 0x36, 0x14, 0x1c, 0x08, 0x08, 0x08, 0x08, 0x00, 0x00], // 'Y'
+    [0x00, 0x00, 0x3e, 0x3e, 0x06, 0x04, 0x0c, 0x18, 0x10, 0x30, 0x3f, 0x00, 0x00], // 'Z'
+    [0x00, 0x00, 0x0c, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x0c], // '['
+    [0x00, 0x00, 0x00, 0x20, 0x30, 0x10, 0x18, 0x08, 0x08, 0x04, 0x04, 0x02, 0x00], // '\\'
+    [0x00, 0x00, 0x18, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x18], // ']'
+    [0x00, 0x00, 0x08, 0x1c, 0x36, 0x22, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '^'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '_'
+    [0x00, 0x00, 0x10, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '`'
+    [0x00, 0x00, 0x00, 0x00, 0x08, 0x3e, 0x02, 0x1e, 0x22, 0x26, 0x3e, 0x00, 0x00], // 'a'
+    [0x00, 0x00, 0x20, 0x20, 0x28, 0x3e, 0x22, 0x22, 0x22, 0x32, 0x3c, 0x00, 0x00], // 'b'
+    [0x00, 0x00, 0x00, 0x00, 0x04, 0x1e, 0x30, 0x20, 0x20, 0x30, 0x1e, 0x00, 0x00], // 'c'
+    [0x00, 0x00, 0x02, 0x02, 0x0a, 0x3e, 0x22, 0x22, 0x22, 0x26, 0x1e, 0x00, 0x00], // 'd'
+    [0x00, 0x00, 0x00, 0x00, 0x08, 0x3e, 0x22, 0x3e, 0x20, 0x20, 0x1e, 0x00, 0x00], // 'e'
+    [0x00, 0x00, 0x06, 0x08, 0x18, 0x3e, 0x08, 0x08, 0x08, 0x08, 0x08, 0x00, 0x00], // 'f'
+    [0x00, 0x00, 0x00, 0x00, 0x08, 0x3e, 0x22, 0x22, 0x22, 0x26, 0x1e, 0x02, 0x14], // 'g'
+    [0x00, 0x00, 0x20, 0x20, 0x20, 0x3e, 0x22, 0x22, 0x22, 0x22, 0x22, 0x00, 0x00], // 'h'
+    [0x00, 0x00, 0x08, 0x08, 0x00, 0x18, 0x08, 0x08, 0x08, 0x08, 0x3e, 0x00, 0x00], // 'i'
+    [0x00, 0x00, 0x08, 0x00, 0x00, 0x1c, 0x0c, 0x0c, 0x0c, 0x0c, 0x0c, 0x08, 0x38], // 'j'
+    [0x00, 0x00, 0x20, 0x30, 0x30, 0x36, 0x3c, 0x38, 0x34, 0x36, 0x32, 0x00, 0x00], // 'k'
+    [0x00, 0x00, 0x30, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x08, 0x0e, 0x00, 0x00], // 'l'
+    [0x00, 0x00, 0x00, 0x00, 0x10, 0x7e, 0x6a, 0x6a, 0x6a, 0x6a, 0x6a, 0x00, 0x00], // 'm'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x3e, 0x22, 0x22, 0x22, 0x22, 0x22, 0x00, 0x00], // 'n'
+    [0x00, 0x00, 0x00, 0x00, 0x08, 0x3e, 0x22, 0x22, 0x22, 0x22, 0x1c, 0x00, 0x00], // 'o'
+    [0x00, 0x00, 0x00, 0x00, 0x08, 0x3e, 0x22, 0x22, 0x22, 0x32, 0x3c, 0x20, 0x20], // 'p'
+    [0x00, 0x00, 0x00, 0x00, 0x08, 0x3e, 0x22, 0x22, 0x22, 0x26, 0x1e, 0x02, 0x02], // 'q'
+    [0x00, 0x00, 0x00, 0x00, 0x02, 0x1f, 0x10, 0x10, 0x10, 0x10, 0x10, 0x00, 0x00], // 'r'
+    [0x00, 0x00, 0x00, 0x00, 0x08, 0x3e, 0x20, 0x1c, 0x06, 0x02, 0x3c, 0x00, 0x00], // 's'
+    [0x00, 0x00, 0x00, 0x18, 0x18, 0x3c, 0x18, 0x18, 0x18, 0x18, 0x0e, 0x00, 0x00], // 't'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x22, 0x22, 0x22, 0x22, 0x26, 0x1e, 0x00, 0x00], // 'u'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x22, 0x22, 0x34, 0x14, 0x1c, 0x08, 0x00, 0x00], // 'v'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x41, 0x6b, 0x2a, 0x3e, 0x36, 0x36, 0x00, 0x00], // 'w'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x26, 0x14, 0x08, 0x1c, 0x34, 0x22, 0x00, 0x00], // 'x'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x22, 0x22, 0x14, 0x14, 0x1c, 0x08, 0x08, 0x30], // 'y'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x1e, 0x04, 0x08, 0x18, 0x10, 0x3e, 0x00, 0x00], // 'z'
+    [0x00, 0x00, 0x06, 0x08, 0x08, 0x08, 0x08, 0x38, 0x08, 0x08, 0x08, 0x08, 0x0e], // '{'
+    [0x00, 0x00, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08], // '|'
+    [0x00, 0x00, 0x30, 0x08, 0x08, 0x08, 0x08, 0x0e, 0x08, 0x08, 0x08, 0x08, 0x30], // '}'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x7e, 0x00, 0x00, 0x00, 0x00, 0x00], // '~'
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_elements() {
+        let text = |value: &str| ElementType::Text { value: value.to_string() };
+        let add = |id: u64, element: ElementType, parent: Option<u64>| Delta::AddElement {
+            id: ElementId::new(id),
+            element,
+            parent_id: parent.map(ElementId::new),
+        };
+        let deltas = vec![
+            add(1, text("kept"), None),
+            add(2, ElementType::Container { children: vec![], hidden: false }, None),
+            add(3, text("cleared"), Some(2)),
+            Delta::ClearContainer { id: ElementId::new(2) },
+            add(4, text("refilled"), Some(2)),
+            add(5, text("removed"), None),
+            Delta::RemoveElement { id: ElementId::new(5) },
+            Delta::UpdateElement { id: ElementId::new(1), element: text("updated") },
+        ];
+        let page = page_elements(&deltas);
+        assert_eq!(page.len(), 3);
+        assert!(matches!(&page[0], ElementType::Text { value } if value == "updated"));
+        assert!(matches!(&page[2], ElementType::Text { value } if value == "refilled"));
+    }
+
+    #[test]
+    fn test_render_formats() {
+        let elements = vec![
+            ElementType::Heading { value: "Q3 <report>".to_string(), level: 1 },
+            ElementType::Text { value: "Revenue up".to_string() },
+        ];
+        let html = render_html("Report", &elements);
+        assert!(html.contains("<h1>Q3 &lt;report&gt;</h1>") && html.contains("<p>Revenue up</p>"));
+
+        let png = render_png(&elements);
+        assert_eq!(&png[1..4], b"PNG");
+        assert_eq!(u32::from_be_bytes(png[16..20].try_into().unwrap()) as usize, PNG_WIDTH);
+        assert_eq!(u32::from_be_bytes(png[20..24].try_into().unwrap()) as usize, 2 * PNG_MARGIN + 3 * LINE_HEIGHT);
+        assert!(png.ends_with(&[0xae, 0x42, 0x60, 0x82]));
+        assert_eq!(render_png(&elements), png);
+        assert_ne!(render_png(&elements[1..]), png);
+
+        // Headings keep their own level past the sizes fonts distinguish
+        let html = render_html("Report", &[ElementType::Heading { value: "Notes".to_string(), level: 6 }]);
+        assert!(html.contains("<h6>Notes</h6>"));
+    }
+
+    #[test]
+    fn test_render_png_height_is_capped() {
+        let elements: Vec<ElementType> =
+            (0..5000).map(|index| ElementType::Text { value: index.to_string() }).collect();
+        let png = render_png(&elements);
+        let height = u32::from_be_bytes(png[20..24].try_into().unwrap()) as usize;
+        assert!(height <= MAX_PNG_HEIGHT);
+        let fits = (MAX_PNG_HEIGHT - 2 * PNG_MARGIN) / LINE_HEIGHT - 1;
+        assert_eq!(height, 2 * PNG_MARGIN + (fits + 1) * LINE_HEIGHT);
+    }
+}
@@ -0,0 +1,324 @@
+//! Rich text editor output: HTML sanitization and markdown conversion.
+//!
+//! Editor output is untrusted HTML. It is reduced to an allowlist of
+//! formatting tags before it reaches the app or is echoed back to other
+//! elements.
+
+/// Default size limit for editor content in bytes.
+pub const MAX_RICH_TEXT_BYTES: usize = 64 * 1024;
+
+/// Tags kept by the sanitizer.
+const ALLOWED_TAGS: &[&str] = &[
+    "p", "br", "b", "strong", "i", "em", "u", "s", "a", "ul", "ol", "li", "h1", "h2", "h3",
+    "blockquote", "code", "pre",
+];
+
+/// Tags dropped together with their content.
+const DROPPED_TAGS: &[&str] = &["script", "style", "iframe", "object", "embed", "template", "noscript"];
+
+/// Sanitized rich text from the editor.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RichText {
+    html: String,
+}
+
+impl RichText {
+    /// Sanitize editor HTML.
+    pub fn from_html(html: &str) -> Self {
+        RichText {
+            html: sanitize_html(html),
+        }
+    }
+
+    /// Get the sanitized HTML.
+    pub fn as_html(&self) -> &str {
+        &self.html
+    }
+
+    /// Convert to markdown.
+    pub fn to_markdown(&self) -> String {
+        html_to_markdown(&self.html)
+    }
+
+    /// Check if there is no content.
+    pub fn is_empty(&self) -> bool {
+        self.html.trim().is_empty()
+    }
+}
+
+enum Token<'a> {
+    Text(&'a str),
+    Open { name: String, attrs: &'a str },
+    Close(String),
+}
+
+/// Split HTML into text and tag tokens, dropping comments and doctypes.
+fn tokenize(html: &str) -> Vec<Token<'_>> {
+    let mut tokens = vec![];
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        if start > 0 {
+            tokens.push(Token::Text(&rest[..start]));
+        }
+        let tag = &rest[start..];
+
+        if let Some(comment) = tag.strip_prefix("<!--") {
+            rest = comment.find("-->").map(|end| &comment[end + 3..]).unwrap_or("");
+            continue;
+        }
+
+        let Some(end) = tag_end(tag) else {
+            tokens.push(Token::Text(tag));
+            rest = "";
+            break;
+        };
+        let inner = tag[1..end].trim();
+        rest = &tag[end + 1..];
+
+        let (closing, inner) = match inner.strip_prefix('/') {
+            Some(inner) => (true, inner),
+            None => (false, inner),
+        };
+        let name_len = inner
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or(inner.len());
+        let name = inner[..name_len].to_ascii_lowercase();
+        if name.is_empty() {
+            continue;
+        }
+
+        if closing {
+            tokens.push(Token::Close(name));
+        } else {
+            tokens.push(Token::Open {
+                name,
+                attrs: inner[name_len..].trim_end_matches('/'),
+            });
+        }
+    }
+
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest));
+    }
+    tokens
+}
+
+/// Find the `>` closing a tag, skipping quoted attribute values.
+fn tag_end(tag: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in tag.char_indices().skip(1) {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if c == q => quote = None,
+            (None, '>') => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Get an attribute value from a tag's attribute string.
+fn attr_value(attrs: &str, name: &str) -> Option<String> {
+    let lower = attrs.to_ascii_lowercase();
+    let mut search = 0;
+    while let Some(pos) = lower[search..].find(name) {
+        let pos = search + pos;
+        let before_ok = pos == 0 || lower.as_bytes()[pos - 1].is_ascii_whitespace();
+        let after = lower[pos + name.len()..].trim_start();
+        if before_ok && after.starts_with('=') {
+            let value_start = attrs.len() - after.len() + 1;
+            let value = attrs[value_start..].trim_start();
+            return Some(match value.chars().next() {
+                Some(q @ ('"' | '\'')) => value[1..].split(q).next().unwrap_or("").to_string(),
+                _ => value.split_whitespace().next().unwrap_or("").to_string(),
+            });
+        }
+        search = pos + name.len();
+    }
+    None
+}
+
+fn safe_href(href: &str) -> bool {
+    let href = href.trim().to_ascii_lowercase();
+    ["http://", "https://", "mailto:", "#"].iter().any(|p| href.starts_with(p))
+}
+
+fn escape_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for (i, c) in text.char_indices() {
+        match c {
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '&' if !is_entity(&text[i..]) => out.push_str("&amp;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn is_entity(text: &str) -> bool {
+    text[1..]
+        .find(';')
+        .is_some_and(|end| end > 0 && end <= 8 && text[1..1 + end].chars().all(|c| c.is_ascii_alphanumeric() || c == '#'))
+}
+
+/// Reduce HTML to the allowed formatting tags.
+///
+/// Disallowed tags are removed but their text is kept, except for tags
+/// like `<script>` whose content is dropped. Only `href` on links survives,
+/// and only for http(s), mailto and fragment URLs.
+pub fn sanitize_html(html: &str) -> String {
+    let mut out = String::new();
+    let mut open: Vec<String> = vec![];
+    let mut dropping: Option<String> = None;
+
+    for token in tokenize(html) {
+        if let Some(tag) = &dropping {
+            if matches!(&token, Token::Close(name) if name == tag) {
+                dropping = None;
+            }
+            continue;
+        }
+
+        match token {
+            Token::Text(text) => out.push_str(&escape_text(text)),
+            Token::Open { name, .. } if DROPPED_TAGS.contains(&name.as_str()) => dropping = Some(name),
+            Token::Open { name, .. } if !ALLOWED_TAGS.contains(&name.as_str()) => {}
+            Token::Open { name, .. } if name == "br" => out.push_str("<br>"),
+            Token::Open { name, attrs } if name == "a" => {
+                match attr_value(attrs, "href").filter(|href| safe_href(href)) {
+                    Some(href) => out.push_str(&format!(
+                        "<a href=\"{}\" rel=\"noopener noreferrer\">",
+                        href.replace('"', "&quot;")
+                    )),
+                    None => out.push_str("<a>"),
+                }
+                open.push(name);
+            }
+            Token::Open { name, .. } => {
+                out.push_str(&format!("<{}>", name));
+                open.push(name);
+            }
+            Token::Close(name) => {
+                if let Some(pos) = open.iter().rposition(|n| *n == name) {
+                    for tag in open.drain(pos..).rev() {
+                        out.push_str(&format!("</{}>", tag));
+                    }
+                }
+            }
+        }
+    }
+
+    for tag in open.into_iter().rev() {
+        out.push_str(&format!("</{}>", tag));
+    }
+    out
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+/// Convert sanitized HTML to markdown.
+pub fn html_to_markdown(html: &str) -> String {
+    let mut out = String::new();
+    let mut lists: Vec<(bool, usize)> = vec![];
+    let mut links: Vec<String> = vec![];
+    let mut in_pre = false;
+
+    for token in tokenize(html) {
+        match token {
+            Token::Text(text) => out.push_str(&decode_entities(text)),
+            Token::Open { name, attrs } => match name.as_str() {
+                "b" | "strong" => out.push_str("**"),
+                "i" | "em" => out.push('*'),
+                "s" => out.push_str("~~"),
+                "code" if !in_pre => out.push('`'),
+                "pre" => {
+                    in_pre = true;
+                    out.push_str("\n```\n");
+                }
+                "h1" | "h2" | "h3" => {
+                    let level = name[1..].parse().unwrap_or(1);
+                    out.push_str(&format!("\n{} ", "#".repeat(level)));
+                }
+                "blockquote" => out.push_str("\n> "),
+                "br" => out.push_str("  \n"),
+                "ul" | "ol" => lists.push((name == "ol", 0)),
+                "li" => {
+                    let indent = "  ".repeat(lists.len().saturating_sub(1));
+                    match lists.last_mut() {
+                        Some((true, n)) => {
+                            *n += 1;
+                            out.push_str(&format!("\n{}{}. ", indent, n));
+                        }
+                        _ => out.push_str(&format!("\n{}- ", indent)),
+                    }
+                }
+                "a" => {
+                    links.push(attr_value(attrs, "href").unwrap_or_default());
+                    out.push('[');
+                }
+                _ => {}
+            },
+            Token::Close(name) => match name.as_str() {
+                "b" | "strong" => out.push_str("**"),
+                "i" | "em" => out.push('*'),
+                "s" => out.push_str("~~"),
+                "code" if !in_pre => out.push('`'),
+                "pre" => {
+                    in_pre = false;
+                    out.push_str("\n```\n");
+                }
+                "p" | "h1" | "h2" | "h3" | "blockquote" => out.push_str("\n\n"),
+                "ul" | "ol" => {
+                    lists.pop();
+                    out.push('\n');
+                }
+                "a" => out.push_str(&format!("]({})", links.pop().unwrap_or_default())),
+                _ => {}
+            },
+        }
+    }
+
+    out.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_strips_scripts_and_attributes() {
+        let html = r#"<p onclick="evil()">Hi <b>there</b><script>alert(1)</script><img src=x onerror=y></p>"#;
+        assert_eq!(sanitize_html(html), "<p>Hi <b>there</b></p>");
+    }
+
+    #[test]
+    fn test_sanitize_links() {
+        assert_eq!(
+            sanitize_html(r#"<a href="javascript:alert(1)">x</a><a href='https://a.com'>y</a>"#),
+            r#"<a>x</a><a href="https://a.com" rel="noopener noreferrer">y</a>"#
+        );
+    }
+
+    #[test]
+    fn test_sanitize_balances_tags() {
+        assert_eq!(sanitize_html("<b><i>x</b> 1 < 2 & 3"), "<b><i>x</i></b> 1 &lt; 2 &amp; 3");
+    }
+
+    #[test]
+    fn test_to_markdown() {
+        let text = RichText::from_html(r#"<h2>Title</h2><p>Some <strong>bold</strong> <a href="https://a.com">link</a></p><ul><li>one</li><li>two</li></ul>"#);
+        assert_eq!(
+            text.to_markdown(),
+            "## Title\n\nSome **bold** [link](https://a.com)\n\n\n- one\n- two"
+        );
+    }
+}
@@ -0,0 +1,374 @@
+//! Shared state rooms for collaborative apps.
+//!
+//! A room is a key-value state shared by every session that opens it with
+//! `st.room(id)`. Writes apply immediately and are broadcast to the other
+//! sessions in the room, which rerun to show them:
+//!
+//! ```
+//! use platypus_runtime::St;
+//!
+//! let mut st = St::new();
+//! let doc = st.room("doc-42");
+//! let title: String = doc.get_as("title").unwrap_or_default();
+//! let edited = st.text_input("Title", &title, Some("title".to_string()));
+//! if edited != title {
+//!     doc.set("title", &edited);
+//! }
+//! ```
+//!
+//! Concurrent writes to a key resolve last-write-wins, ordered by a Lamport
+//! clock with the writer's session ID as tie-breaker, which also lets
+//! [`RoomRegistry::merge`] combine replicas of a room from different
+//! servers. With the `crdt` feature rooms also hold PN-counters, whose
+//! concurrent increments all survive a merge.
+
+use dashmap::DashMap;
+use parking_lot::RwLock;
+use platypus_core::session::SessionId;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+/// Changes queued per subscriber before it lags and must resync, for the
+/// broadcast channels of rooms, presence, topics and transcriptions.
+pub(crate) const CHANGE_CAPACITY: usize = 256;
+
+/// A write to a room, broadcast to the sessions in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoomChange {
+    /// Room ID.
+    pub room: String,
+    /// Session that wrote, or `None` for a merged replica.
+    pub origin: Option<SessionId>,
+}
+
+/// One value in a room, stamped with the write that set it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoomEntry {
+    pub value: serde_json::Value,
+    /// Lamport clock of the write.
+    pub clock: u64,
+    /// Session ID of the writer.
+    pub writer: String,
+}
+
+impl RoomEntry {
+    fn wins_over(&self, other: &RoomEntry) -> bool {
+        (self.clock, &self.writer) > (other.clock, &other.writer)
+    }
+}
+
+/// Increments and decrements one writer contributed to a counter.
+#[cfg(feature = "crdt")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CounterShare {
+    pub increments: u64,
+    pub decrements: u64,
+}
+
+/// State of a room, serializable so replicas can be merged.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RoomState {
+    /// Highest Lamport clock seen.
+    pub clock: u64,
+    pub entries: HashMap<String, RoomEntry>,
+    /// PN-counters: each writer's share by counter key.
+    #[cfg(feature = "crdt")]
+    #[serde(default)]
+    pub counters: HashMap<String, HashMap<String, CounterShare>>,
+}
+
+impl RoomState {
+    fn set(&mut self, key: &str, value: serde_json::Value, writer: &str) -> bool {
+        if self.entries.get(key).is_some_and(|entry| entry.value == value) {
+            return false;
+        }
+        self.clock += 1;
+        let entry = RoomEntry {
+            value,
+            clock: self.clock,
+            writer: writer.to_string(),
+        };
+        self.entries.insert(key.to_string(), entry);
+        true
+    }
+
+    fn append(&mut self, key: &str, values: Vec<serde_json::Value>, keep: usize, writer: &str) -> bool {
+        if values.is_empty() {
+            return false;
+        }
+        self.clock += 1;
+        let entry = self.entries.entry(key.to_string()).or_insert_with(|| RoomEntry {
+            value: serde_json::Value::Null,
+            clock: 0,
+            writer: String::new(),
+        });
+        if !entry.value.is_array() {
+            entry.value = serde_json::Value::Array(Vec::new());
+        }
+        if let serde_json::Value::Array(items) = &mut entry.value {
+            items.extend(values);
+            let excess = items.len().saturating_sub(keep);
+            items.drain(..excess);
+        }
+        entry.clock = self.clock;
+        entry.writer = writer.to_string();
+        true
+    }
+
+    /// Merge another replica into this one. Each key keeps its last write,
+    /// and each counter share its largest count.
+    pub fn merge(&mut self, other: RoomState) -> bool {
+        let before = self.clone();
+        self.clock = self.clock.max(other.clock);
+        for (key, entry) in other.entries {
+            match self.entries.get(&key) {
+                Some(current) if !entry.wins_over(current) => {}
+                _ => {
+                    self.entries.insert(key, entry);
+                }
+            }
+        }
+        #[cfg(feature = "crdt")]
+        for (key, shares) in other.counters {
+            let counter = self.counters.entry(key).or_default();
+            for (writer, share) in shares {
+                let current = counter.entry(writer).or_default();
+                current.increments = current.increments.max(share.increments);
+                current.decrements = current.decrements.max(share.decrements);
+            }
+        }
+        *self != before
+    }
+}
+
+/// Every room on the server, with the channel their changes go out on.
+pub struct RoomRegistry {
+    rooms: Arc<DashMap<String, Arc<RwLock<RoomState>>>>,
+    changes: broadcast::Sender<RoomChange>,
+}
+
+impl RoomRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        RoomRegistry {
+            rooms: Arc::new(DashMap::new()),
+            changes: broadcast::channel(CHANGE_CAPACITY).0,
+        }
+    }
+
+    /// Open a room, creating it empty, with writes attributed to `writer`.
+    pub fn open(&self, id: &str, writer: Option<SessionId>) -> RoomHandle {
+        let state = self.rooms.entry(id.to_string()).or_default().clone();
+        RoomHandle {
+            id: id.to_string(),
+            state,
+            writer,
+            changes: self.changes.clone(),
+        }
+    }
+
+    /// Subscribe to the changes of every room.
+    pub fn subscribe(&self) -> broadcast::Receiver<RoomChange> {
+        self.changes.subscribe()
+    }
+
+    /// Get a copy of a room's state, e.g. to replicate it.
+    pub fn state(&self, id: &str) -> Option<RoomState> {
+        self.rooms.get(id).map(|state| state.read().clone())
+    }
+
+    /// Merge a replica of a room, notifying its sessions if anything changed.
+    pub fn merge(&self, id: &str, replica: RoomState) {
+        let state = self.rooms.entry(id.to_string()).or_default().clone();
+        if state.write().merge(replica) {
+            let _ = self.changes.send(RoomChange {
+                room: id.to_string(),
+                origin: None,
+            });
+        }
+    }
+
+    /// Remove a room.
+    pub fn remove(&self, id: &str) {
+        self.rooms.remove(id);
+    }
+
+    /// Get the number of rooms.
+    pub fn len(&self) -> usize {
+        self.rooms.len()
+    }
+
+    /// Check if there are no rooms.
+    pub fn is_empty(&self) -> bool {
+        self.rooms.is_empty()
+    }
+}
+
+impl Default for RoomRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for RoomRegistry {
+    fn clone(&self) -> Self {
+        RoomRegistry {
+            rooms: Arc::clone(&self.rooms),
+            changes: self.changes.clone(),
+        }
+    }
+}
+
+/// A session's handle on a shared room.
+#[derive(Clone)]
+pub struct RoomHandle {
+    id: String,
+    state: Arc<RwLock<RoomState>>,
+    writer: Option<SessionId>,
+    changes: broadcast::Sender<RoomChange>,
+}
+
+impl RoomHandle {
+    /// Get the room ID.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Get a value.
+    pub fn get(&self, key: &str) -> Option<serde_json::Value> {
+        self.state.read().entries.get(key).map(|entry| entry.value.clone())
+    }
+
+    /// Get a value deserialized as `T`.
+    pub fn get_as<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        serde_json::from_value(self.get(key)?).ok()
+    }
+
+    /// Get every value.
+    pub fn entries(&self) -> HashMap<String, serde_json::Value> {
+        self.state
+            .read()
+            .entries
+            .iter()
+            .map(|(key, entry)| (key.clone(), entry.value.clone()))
+            .collect()
+    }
+
+    /// Set a value, notifying the other sessions in the room. Writing the
+    /// current value again is a no-op, so apps can write on every run.
+    pub fn set(&self, key: &str, value: impl Serialize) {
+        let Ok(value) = serde_json::to_value(value) else {
+            return;
+        };
+        let changed = self.state.write().set(key, value, &self.writer_name());
+        if changed {
+            self.notify();
+        }
+    }
+
+    /// Append `values` to the array at `key`, keeping its newest `keep`
+    /// items, and notify the other sessions in the room. The array is
+    /// changed in place, so appending costs the same however long it is; a
+    /// missing or non-array value is replaced by a new array.
+    pub fn append(&self, key: &str, values: impl IntoIterator<Item = serde_json::Value>, keep: usize) {
+        let values = values.into_iter().collect();
+        let changed = self.state.write().append(key, values, keep, &self.writer_name());
+        if changed {
+            self.notify();
+        }
+    }
+
+    /// Add `by` (possibly negative) to a counter. Concurrent increments
+    /// from different sessions or replicas all count.
+    #[cfg(feature = "crdt")]
+    pub fn increment(&self, key: &str, by: i64) {
+        if by == 0 {
+            return;
+        }
+        {
+            let mut state = self.state.write();
+            let share = state
+                .counters
+                .entry(key.to_string())
+                .or_default()
+                .entry(self.writer_name())
+                .or_default();
+            if by > 0 {
+                share.increments += by.unsigned_abs();
+            } else {
+                share.decrements += by.unsigned_abs();
+            }
+        }
+        self.notify();
+    }
+
+    /// Get a counter's value.
+    #[cfg(feature = "crdt")]
+    pub fn counter(&self, key: &str) -> i64 {
+        self.state.read().counters.get(key).map_or(0, |shares| {
+            shares
+                .values()
+                .map(|share| share.increments as i64 - share.decrements as i64)
+                .sum()
+        })
+    }
+
+    fn writer_name(&self) -> String {
+        self.writer.map(|id| id.to_string()).unwrap_or_default()
+    }
+
+    fn notify(&self) {
+        // Nobody subscribed is fine: no other session is connected
+        let _ = self.changes.send(RoomChange {
+            room: self.id.clone(),
+            origin: self.writer,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_room_broadcasts_changes() {
+        let registry = RoomRegistry::new();
+        let (alice, bob) = (SessionId::new(), SessionId::new());
+        let mut changes = registry.subscribe();
+
+        registry.open("doc", Some(alice)).set("title", "Draft");
+        assert_eq!(registry.open("doc", Some(bob)).get_as::<String>("title").as_deref(), Some("Draft"));
+        assert_eq!(changes.try_recv().unwrap().origin, Some(alice));
+
+        // Rewriting the same value is not a change
+        registry.open("doc", Some(bob)).set("title", "Draft");
+        assert!(changes.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_merge_last_write_wins() {
+        let (a, b) = (RoomRegistry::new(), RoomRegistry::new());
+        a.open("doc", Some(SessionId::new())).set("title", "Old");
+        let newer = b.open("doc", Some(SessionId::new()));
+        newer.set("title", "Older");
+        newer.set("title", "New");
+
+        a.merge("doc", b.state("doc").unwrap());
+        assert_eq!(a.open("doc", None).get_as::<String>("title").as_deref(), Some("New"));
+    }
+
+    #[cfg(feature = "crdt")]
+    #[test]
+    fn test_counter_merge_keeps_concurrent_increments() {
+        let (a, b) = (RoomRegistry::new(), RoomRegistry::new());
+        a.open("poll", Some(SessionId::new())).increment("votes", 2);
+        b.open("poll", Some(SessionId::new())).increment("votes", 3);
+
+        a.merge("poll", b.state("poll").unwrap());
+        a.merge("poll", b.state("poll").unwrap());
+        assert_eq!(a.open("poll", None).counter("votes"), 5);
+    }
+}
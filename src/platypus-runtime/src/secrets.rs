@@ -186,6 +186,11 @@ impl Secrets {
     }
 }
 
+/// Compare secrets without leaking the position of the first difference.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -204,6 +209,15 @@ mod tests {
         assert_eq!(masked, "p*********3");
     }
 
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"", b""));
+        assert!(constant_time_eq(b"token", b"token"));
+        assert!(!constant_time_eq(b"token", b"tokem"));
+        assert!(!constant_time_eq(b"token", b"token2"));
+        assert!(!constant_time_eq(b"", b"token"));
+    }
+
     #[test]
     fn test_secrets_manager() {
         let mut manager = SecretsManager::new();
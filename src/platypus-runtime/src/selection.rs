@@ -0,0 +1,126 @@
+//! Chart interaction events.
+//!
+//! Charts given a key report clicks, box or lasso selections and zoom
+//! ranges back to the app as [`Event::ChartSelection`](crate::Event::ChartSelection).
+//! The latest one is read with `st.chart_selection`, e.g. to filter other
+//! elements by the points picked in a chart:
+//!
+//! ```
+//! use platypus_core::elements::ChartKind;
+//! use platypus_runtime::St;
+//!
+//! let data = r#"[{"month": "Jan", "sales": 10}, {"month": "Feb", "sales": 14}]"#;
+//! let mut st = St::new();
+//! st.chart(ChartKind::Bar, data, None, "sales");
+//! if let Some(selection) = st.chart_selection("sales") {
+//!     st.write(format!("Selected rows: {:?}", selection.indices()));
+//! }
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+/// Prefix of the widget keys holding chart selections.
+const SELECTION_WIDGET_PREFIX: &str = "__chart_selection:";
+
+/// Get the widget key holding the selection of the chart `key`.
+pub fn widget_key(key: &str) -> String {
+    format!("{}{}", SELECTION_WIDGET_PREFIX, key)
+}
+
+/// A data point picked in a chart.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SelectedPoint {
+    /// Index of the point's record in the chart data.
+    pub index: usize,
+    /// Series the point belongs to, for charts with several.
+    #[serde(default)]
+    pub series: Option<String>,
+    #[serde(default)]
+    pub x: serde_json::Value,
+    #[serde(default)]
+    pub y: serde_json::Value,
+}
+
+/// How points were selected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SelectionMode {
+    Box,
+    Lasso,
+}
+
+/// A visible axis range.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AxisRange {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl AxisRange {
+    /// Check if `value` lies within the range.
+    pub fn contains(&self, value: f64) -> bool {
+        value >= self.min && value <= self.max
+    }
+}
+
+/// The latest interaction with a chart.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum ChartSelection {
+    /// A single point was clicked.
+    Click { point: SelectedPoint },
+    /// Points were selected by dragging a box or lasso.
+    Select {
+        mode: SelectionMode,
+        points: Vec<SelectedPoint>,
+    },
+    /// The chart was zoomed or panned; unset axes show everything.
+    Zoom {
+        #[serde(default)]
+        x: Option<AxisRange>,
+        #[serde(default)]
+        y: Option<AxisRange>,
+    },
+    /// The selection was cleared, e.g. by double-clicking the chart.
+    Clear,
+}
+
+impl ChartSelection {
+    /// Get the selected points, if any.
+    pub fn points(&self) -> &[SelectedPoint] {
+        match self {
+            ChartSelection::Click { point } => std::slice::from_ref(point),
+            ChartSelection::Select { points, .. } => points,
+            ChartSelection::Zoom { .. } | ChartSelection::Clear => &[],
+        }
+    }
+
+    /// Get the record indices of the selected points, sorted and without
+    /// duplicates.
+    pub fn indices(&self) -> Vec<usize> {
+        let mut indices: Vec<usize> = self.points().iter().map(|point| point.index).collect();
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_selection_json() {
+        let selection: ChartSelection = serde_json::from_str(
+            r#"{"kind": "select", "mode": "lasso", "points": [{"index": 3, "x": "Mar", "y": 7}, {"index": 1}]}"#,
+        )
+        .unwrap();
+        assert_eq!(selection.indices(), vec![1, 3]);
+
+        let zoom: ChartSelection = serde_json::from_str(r#"{"kind": "zoom", "x": {"min": 2, "max": 5}}"#).unwrap();
+        let ChartSelection::Zoom { x: Some(x), y: None } = zoom else {
+            panic!("unexpected selection: {:?}", zoom);
+        };
+        assert!(x.contains(3.0) && !x.contains(6.0));
+    }
+}
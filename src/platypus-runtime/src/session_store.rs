@@ -1,13 +1,68 @@
 //! Session storage and management.
+//!
+//! Sessions can be exported as a [`SessionSnapshot`] and imported into
+//! another store, e.g. on a support engineer's machine or a new server.
+//! An imported session waits until a client connects with its ID and
+//! claims it.
+//!
+//! In a cluster, a store with a [`SharedBackend`] writes its sessions
+//! through to it, and a client connecting to another node with the ID of
+//! a session whose node went down claims it from there.
+//!
+//! Either way the client presents the session's resume token along with
+//! its ID, so knowing an ID is not enough to take over a session.
+//!
+//! Sessions saved by an older version of the app are migrated by the
+//! store's [`StateSchema`] as they are imported or taken over.
 
-use crate::error::Result;
+use crate::backend::{SharedBackend, SESSION_KEY_PREFIX, SHARED_SESSION_TTL};
+use crate::error::{Error, Result};
+use crate::event::PAGE_WIDGET_KEY;
+use crate::secrets::constant_time_eq;
+use crate::state_schema::StateSchema;
 use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
+use platypus_core::clock::{self, Clock};
+use platypus_core::ids::{self, IdGenerator};
 use platypus_core::session::{Session, SessionId};
 
+/// Version of the snapshot format written by [`SessionStore::export`].
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Serializable state of one session.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionSnapshot {
+    /// Snapshot format version.
+    pub version: u32,
+    /// ID of the exported session.
+    pub session_id: SessionId,
+    /// Hash of the script the session ran.
+    pub script_hash: String,
+    /// Widget values by key.
+    pub widget_state: HashMap<String, String>,
+    /// Schema version of the widget and session state.
+    #[serde(default)]
+    pub state_version: u32,
+    /// Session state (metadata such as the theme preference).
+    pub session_state: HashMap<String, String>,
+    /// Page selected in a multipage app.
+    pub page: Option<String>,
+    /// Export time (Unix seconds).
+    pub exported_at: u64,
+}
+
 /// Manages active sessions.
 pub struct SessionStore {
     sessions: Arc<DashMap<String, Session>>,
+    // Imported sessions no client has claimed yet
+    unclaimed: Arc<DashMap<String, ()>>,
+    // Sessions of the whole cluster
+    shared: Option<Arc<dyn SharedBackend>>,
+    schema: StateSchema,
+    clock: Arc<dyn Clock>,
+    ids: Arc<dyn IdGenerator>,
 }
 
 impl SessionStore {
@@ -15,13 +70,50 @@ impl SessionStore {
     pub fn new() -> Self {
         SessionStore {
             sessions: Arc::new(DashMap::new()),
+            unclaimed: Arc::new(DashMap::new()),
+            shared: None,
+            schema: StateSchema::default(),
+            clock: clock::system_clock(),
+            ids: ids::random_ids(),
         }
     }
 
+    /// Write sessions through to `backend`, so other nodes can take them
+    /// over.
+    pub fn with_shared(mut self, backend: Arc<dyn SharedBackend>) -> Self {
+        self.shared = Some(backend);
+        self
+    }
+
+    /// Version new sessions' state with `schema`, and migrate older
+    /// sessions with it as they are imported or taken over.
+    pub fn with_schema(mut self, schema: StateSchema) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    /// Stamp sessions and snapshots with the time of `clock`.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Give new sessions IDs from `ids`.
+    pub fn with_ids(mut self, ids: Arc<dyn IdGenerator>) -> Self {
+        self.ids = ids;
+        self
+    }
+
+    fn new_session(&self, script_hash: String) -> Session {
+        Session::new_at(script_hash, SessionId::from_uuid(self.ids.next_id()), self.clock.unix_secs())
+    }
+
     /// Create a new session.
     pub fn create_session(&self, script_hash: String) -> SessionId {
-        let session = Session::new(script_hash);
+        let mut session = self.new_session(script_hash);
+        session.state_version = self.schema.version();
         let session_id = session.id;
+        self.write_shared(&session);
         self.sessions.insert(session_id.to_string(), session);
         session_id
     }
@@ -31,11 +123,12 @@ impl SessionStore {
         self.sessions
             .get(&session_id.to_string())
             .map(|entry| entry.clone())
-            .ok_or_else(|| crate::error::Error::session(format!("Session not found: {}", session_id)))
+            .ok_or_else(|| platypus_core::Error::SessionNotFound(session_id.to_string()).into())
     }
 
     /// Update a session.
     pub fn update_session(&self, session: Session) -> Result<()> {
+        self.write_shared(&session);
         self.sessions.insert(session.id.to_string(), session);
         Ok(())
     }
@@ -43,9 +136,109 @@ impl SessionStore {
     /// Remove a session.
     pub fn remove_session(&self, session_id: SessionId) -> Result<()> {
         self.sessions.remove(&session_id.to_string());
+        self.unclaimed.remove(&session_id.to_string());
+        if let Some(shared) = &self.shared
+            && let Err(e) = shared.delete(&shared_key(session_id))
+        {
+            tracing::warn!("Failed to remove session {} from the shared store: {}", session_id, e);
+        }
         Ok(())
     }
 
+    fn write_shared(&self, session: &Session) {
+        let Some(shared) = &self.shared else {
+            return;
+        };
+        let written = serde_json::to_string(session)
+            .map_err(|e| e.to_string())
+            .and_then(|json| shared.set(&shared_key(session.id), &json, Some(SHARED_SESSION_TTL)));
+        if let Err(e) = written {
+            tracing::warn!("Failed to write session {} to the shared store: {}", session.id, e);
+        }
+    }
+
+    /// Take over a session another node wrote to the shared store.
+    fn take_over(&self, session_id: SessionId, token: &str) -> bool {
+        let Some(shared) = &self.shared else {
+            return false;
+        };
+        let session = match shared.get(&shared_key(session_id)) {
+            Ok(json) => json.and_then(|json| serde_json::from_str::<Session>(&json).ok()),
+            Err(e) => {
+                tracing::warn!("Failed to read session {} from the shared store: {}", session_id, e);
+                None
+            }
+        };
+        match session {
+            Some(mut session) if session.id == session_id && resumes(&session, token) => {
+                // A client of an outdated session starts over instead
+                if let Err(e) = self.schema.migrate(&mut session) {
+                    tracing::warn!("Failed to take over session {}: {}", session_id, e);
+                    return false;
+                }
+                self.sessions.insert(session_id.to_string(), session);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Export a session's widget, session and navigation state.
+    pub fn export(&self, session_id: SessionId) -> Result<SessionSnapshot> {
+        let session = self.get_session(session_id)?;
+        let mut widget_state = session.widget_state;
+        let page = widget_state.remove(PAGE_WIDGET_KEY);
+
+        Ok(SessionSnapshot {
+            version: SNAPSHOT_VERSION,
+            session_id,
+            script_hash: session.script_hash,
+            widget_state,
+            state_version: session.state_version,
+            session_state: session.metadata,
+            page,
+            exported_at: self.clock.unix_secs(),
+        })
+    }
+
+    /// Import a snapshot as a new session, waiting to be claimed by a
+    /// client. Returns the new session's ID.
+    pub fn import(&self, snapshot: SessionSnapshot) -> Result<SessionId> {
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(Error::session(format!(
+                "Unsupported snapshot version: {}",
+                snapshot.version
+            )));
+        }
+
+        let mut session = self.new_session(snapshot.script_hash);
+        session.metadata = snapshot.session_state;
+        session.widget_state = snapshot.widget_state;
+        session.state_version = snapshot.state_version;
+        if let Some(page) = snapshot.page {
+            session.widget_state.insert(PAGE_WIDGET_KEY.to_string(), page);
+        }
+        self.schema.migrate(&mut session)?;
+
+        let session_id = session.id;
+        self.write_shared(&session);
+        self.sessions.insert(session_id.to_string(), session);
+        self.unclaimed.insert(session_id.to_string(), ());
+        Ok(session_id)
+    }
+
+    /// Claim an imported session for a connecting client presenting the
+    /// session's resume token. Each imported session can be claimed once.
+    /// Sessions of other nodes are taken over from the shared store;
+    /// returns false for any other ID or a wrong token.
+    pub fn claim(&self, session_id: SessionId, token: &str) -> bool {
+        let key = session_id.to_string();
+        match self.sessions.get(&key) {
+            Some(session) => resumes(&session, token) && self.unclaimed.remove(&key).is_some(),
+            None => self.take_over(session_id, token),
+        }
+    }
+
     /// Get all sessions.
     pub fn all_sessions(&self) -> Vec<Session> {
         self.sessions.iter().map(|entry| entry.value().clone()).collect()
@@ -62,6 +255,11 @@ impl SessionStore {
     }
 }
 
+/// Whether `token` is the resume token of `session`.
+fn resumes(session: &Session, token: &str) -> bool {
+    !session.resume_token.is_empty() && constant_time_eq(session.resume_token.as_bytes(), token.as_bytes())
+}
+
 impl Default for SessionStore {
     fn default() -> Self {
         Self::new()
@@ -72,10 +270,19 @@ impl Clone for SessionStore {
     fn clone(&self) -> Self {
         SessionStore {
             sessions: Arc::clone(&self.sessions),
+            unclaimed: Arc::clone(&self.unclaimed),
+            shared: self.shared.clone(),
+            schema: self.schema.clone(),
+            clock: Arc::clone(&self.clock),
+            ids: Arc::clone(&self.ids),
         }
     }
 }
 
+fn shared_key(session_id: SessionId) -> String {
+    format!("{}{}", SESSION_KEY_PREFIX, session_id)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -102,4 +309,74 @@ mod tests {
         store.remove_session(session_id).unwrap();
         assert!(store.get_session(session_id).is_err());
     }
+
+    #[test]
+    fn test_export_import() {
+        let store = SessionStore::new();
+        let session_id = store.create_session("script_hash".to_string());
+        let mut session = store.get_session(session_id).unwrap();
+        session.widget_state.insert("name".to_string(), "Ada".to_string());
+        session.widget_state.insert(PAGE_WIDGET_KEY.to_string(), "settings".to_string());
+        session.set_metadata("theme".to_string(), "dark".to_string());
+        store.update_session(session).unwrap();
+
+        let snapshot = store.export(session_id).unwrap();
+        assert_eq!(snapshot.page.as_deref(), Some("settings"));
+        let json = serde_json::to_string(&snapshot).unwrap();
+
+        let other = SessionStore::new();
+        let imported = other.import(serde_json::from_str(&json).unwrap()).unwrap();
+        let session = other.get_session(imported).unwrap();
+        assert_eq!(session.widget_state.get("name").map(String::as_str), Some("Ada"));
+        assert_eq!(session.get_metadata("theme"), Some("dark"));
+        let token = session.resume_token.clone();
+        assert!(!other.claim(imported, "guessed"));
+        assert!(other.claim(imported, &token));
+        assert!(!other.claim(imported, &token));
+        assert!(!other.claim(session_id, &token));
+    }
+
+    #[derive(Default)]
+    struct MemoryBackend(DashMap<String, String>);
+
+    impl SharedBackend for MemoryBackend {
+        fn name(&self) -> &str {
+            "memory"
+        }
+
+        fn get(&self, key: &str) -> std::result::Result<Option<String>, String> {
+            Ok(self.0.get(key).map(|value| value.clone()))
+        }
+
+        fn set(&self, key: &str, value: &str, _ttl: Option<std::time::Duration>) -> std::result::Result<(), String> {
+            self.0.insert(key.to_string(), value.to_string());
+            Ok(())
+        }
+
+        fn delete(&self, key: &str) -> std::result::Result<(), String> {
+            self.0.remove(key);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_take_over_from_shared_store() {
+        let backend: Arc<dyn SharedBackend> = Arc::new(MemoryBackend::default());
+        let node_a = SessionStore::new().with_shared(Arc::clone(&backend));
+        let node_b = SessionStore::new().with_shared(Arc::clone(&backend));
+        let session_id = node_a.create_session("script_hash".to_string());
+        let mut session = node_a.get_session(session_id).unwrap();
+        session.widget_state.insert("name".to_string(), "Ada".to_string());
+        let token = session.resume_token.clone();
+        node_a.update_session(session).unwrap();
+
+        // Node A went down; its client reconnects to node B
+        assert!(!node_b.claim(session_id, ""));
+        assert!(!node_b.claim(session_id, "guessed"));
+        assert!(node_b.claim(session_id, &token));
+        assert_eq!(node_b.get_session(session_id).unwrap().widget_state["name"], "Ada");
+        assert!(!node_b.claim(session_id, &token));
+        node_b.remove_session(session_id).unwrap();
+        assert!(!SessionStore::new().with_shared(backend).claim(session_id, &token));
+    }
 }
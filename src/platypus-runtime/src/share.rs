@@ -0,0 +1,256 @@
+//! Read-only snapshots of a session's page, shared by URL.
+//!
+//! `st.share_snapshot(options)` returns a link to a frozen copy of the page
+//! the current run builds. The copy is rendered once the run ends and
+//! served as static HTML, without running the app. It is the page's text
+//! as [`render::render_html`] flattens it, the same lines a PDF export
+//! shows: headings, text, table cells and widget labels with their values.
+//! Viewers cannot interact with it; elements without a text form, such as
+//! images, appear as placeholders, and some are left out:
+//!
+//! ```
+//! use platypus_runtime::share::ShareOptions;
+//! use platypus_runtime::St;
+//! use std::time::Duration;
+//!
+//! let mut st = St::new();
+//! st.title("Q3 forecast");
+//! if st.button("Share", Some("share".to_string())) {
+//!     let link = st.share_snapshot(ShareOptions::new().expires_in(Duration::from_secs(3600)).max_views(10));
+//!     st.code(link.url, None);
+//! }
+//! ```
+//!
+//! Links expire, by default after [`DEFAULT_SHARE_TTL`], and can be limited
+//! to a number of views, protected by a passcode given as the `passcode`
+//! query parameter, or revoked. After [`FREE_PASSCODE_ATTEMPTS`] wrong
+//! passcodes in a row, each further one locks the link for twice as long
+//! as the last, from [`PASSCODE_BACKOFF`] up to [`MAX_PASSCODE_BACKOFF`].
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use platypus_core::element::ElementType;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::render;
+use crate::secrets::constant_time_eq;
+
+/// Default time a shared snapshot can be viewed.
+pub const DEFAULT_SHARE_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Path under which snapshots are served, followed by their token.
+pub const SHARE_PATH_PREFIX: &str = "/share/";
+
+/// Wrong passcodes in a row a link accepts before locking.
+pub const FREE_PASSCODE_ATTEMPTS: u32 = 5;
+
+/// Time a link is locked after the first wrong passcode past the free ones.
+pub const PASSCODE_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Longest time a link is locked after a wrong passcode.
+pub const MAX_PASSCODE_BACKOFF: Duration = Duration::from_secs(60 * 60);
+
+/// Title of snapshots shared without one.
+const DEFAULT_SHARE_TITLE: &str = "Shared snapshot";
+
+/// Expiry and access controls of a shared snapshot.
+#[derive(Debug, Clone, Default)]
+pub struct ShareOptions {
+    title: Option<String>,
+    ttl: Option<Duration>,
+    max_views: Option<u32>,
+    passcode: Option<String>,
+}
+
+impl ShareOptions {
+    /// Share for [`DEFAULT_SHARE_TTL`] with anyone who has the link.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the snapshot page's title.
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Expire the link `ttl` after it is created.
+    pub fn expires_in(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Expire the link once it was viewed `views` times.
+    pub fn max_views(mut self, views: u32) -> Self {
+        self.max_views = Some(views);
+        self
+    }
+
+    /// Only show the snapshot to viewers giving `passcode`.
+    pub fn passcode(mut self, passcode: impl Into<String>) -> Self {
+        self.passcode = Some(passcode.into());
+        self
+    }
+}
+
+/// A link to a shared snapshot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShareLink {
+    /// Secret that identifies the snapshot.
+    pub token: String,
+    /// Path of the snapshot page on the server.
+    pub url: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Why a snapshot was not shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShareError {
+    /// The link is unknown, expired, used up, revoked or not ready yet.
+    NotFound,
+    /// The passcode is missing or wrong.
+    Forbidden,
+    /// Too many wrong passcodes were given; the link is locked for a while.
+    Locked,
+}
+
+struct Share {
+    title: String,
+    /// Rendered page, once the run that shared it ended
+    html: Option<String>,
+    expires_at: DateTime<Utc>,
+    views_left: Option<u32>,
+    passcode: Option<String>,
+    /// Wrong passcodes given since the last right one
+    failed_attempts: u32,
+    locked_until: Option<DateTime<Utc>>,
+}
+
+/// Every shared snapshot on the server.
+#[derive(Clone, Default)]
+pub struct ShareStore {
+    shares: Arc<DashMap<String, Share>>,
+}
+
+impl ShareStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a link whose page is frozen later, with [`ShareStore::freeze`].
+    pub fn reserve(&self, options: ShareOptions) -> ShareLink {
+        let now = Utc::now();
+        self.shares.retain(|_, share| share.expires_at > now);
+
+        let token = uuid::Uuid::new_v4().simple().to_string();
+        let ttl = options.ttl.unwrap_or(DEFAULT_SHARE_TTL);
+        let expires_at = now + chrono::Duration::from_std(ttl).unwrap_or(chrono::Duration::MAX);
+        self.shares.insert(
+            token.clone(),
+            Share {
+                title: options.title.unwrap_or_else(|| DEFAULT_SHARE_TITLE.to_string()),
+                html: None,
+                expires_at,
+                views_left: options.max_views,
+                passcode: options.passcode,
+                failed_attempts: 0,
+                locked_until: None,
+            },
+        );
+        ShareLink {
+            url: format!("{}{}", SHARE_PATH_PREFIX, token),
+            token,
+            expires_at,
+        }
+    }
+
+    /// Freeze the page of a reserved link.
+    pub fn freeze(&self, token: &str, elements: &[ElementType]) {
+        if let Some(mut share) = self.shares.get_mut(token) {
+            share.html = Some(render::render_html(&share.title, elements));
+        }
+    }
+
+    /// Get the page of a link, counting the view.
+    pub fn view(&self, token: &str, passcode: Option<&str>) -> Result<String, ShareError> {
+        let mut share = self.shares.get_mut(token).ok_or(ShareError::NotFound)?;
+        let now = Utc::now();
+        if share.expires_at <= now || share.views_left == Some(0) {
+            drop(share);
+            self.shares.remove(token);
+            return Err(ShareError::NotFound);
+        }
+        if share.locked_until.is_some_and(|until| until > now) {
+            return Err(ShareError::Locked);
+        }
+        if let Some(expected) = &share.passcode {
+            if !passcode.is_some_and(|given| constant_time_eq(given.as_bytes(), expected.as_bytes())) {
+                share.failed_attempts += 1;
+                if let Some(locks) = share.failed_attempts.checked_sub(FREE_PASSCODE_ATTEMPTS + 1) {
+                    let backoff = PASSCODE_BACKOFF
+                        .saturating_mul(2u32.saturating_pow(locks))
+                        .min(MAX_PASSCODE_BACKOFF);
+                    let backoff = chrono::Duration::from_std(backoff).unwrap_or(chrono::Duration::MAX);
+                    share.locked_until = Some(now + backoff);
+                }
+                return Err(ShareError::Forbidden);
+            }
+            share.failed_attempts = 0;
+        }
+        let html = share.html.clone().ok_or(ShareError::NotFound)?;
+        if let Some(views) = &mut share.views_left {
+            *views -= 1;
+        }
+        Ok(html)
+    }
+
+    /// Revoke a link. Returns whether it existed.
+    pub fn revoke(&self, token: &str) -> bool {
+        self.shares.remove(token).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_view_controls() {
+        let store = ShareStore::new();
+        let link = store.reserve(ShareOptions::new().title("Forecast").max_views(1).passcode("1234"));
+        assert!(link.url.ends_with(&link.token));
+        // Not viewable until the run ends
+        assert_eq!(store.view(&link.token, Some("1234")), Err(ShareError::NotFound));
+
+        store.freeze(&link.token, &[ElementType::Heading { value: "Q3".to_string(), level: 1 }]);
+        assert_eq!(store.view(&link.token, None), Err(ShareError::Forbidden));
+        assert!(store.view(&link.token, Some("1234")).unwrap().contains("<h1>Q3</h1>"));
+        assert_eq!(store.view(&link.token, Some("1234")), Err(ShareError::NotFound));
+
+        let expired = store.reserve(ShareOptions::new().expires_in(Duration::ZERO));
+        store.freeze(&expired.token, &[]);
+        assert_eq!(store.view(&expired.token, None), Err(ShareError::NotFound));
+        let revoked = store.reserve(ShareOptions::new());
+        assert!(store.revoke(&revoked.token));
+        assert!(!store.revoke(&revoked.token));
+    }
+
+    #[test]
+    fn test_wrong_passcodes_lock_the_link() {
+        let store = ShareStore::new();
+        let link = store.reserve(ShareOptions::new().passcode("1234"));
+        store.freeze(&link.token, &[]);
+        for _ in 0..FREE_PASSCODE_ATTEMPTS {
+            assert_eq!(store.view(&link.token, Some("0000")), Err(ShareError::Forbidden));
+        }
+        // A right passcode starts the count over
+        assert!(store.view(&link.token, Some("1234")).is_ok());
+        for _ in 0..FREE_PASSCODE_ATTEMPTS {
+            assert_eq!(store.view(&link.token, None), Err(ShareError::Forbidden));
+        }
+        assert_eq!(store.view(&link.token, Some("0000")), Err(ShareError::Forbidden));
+        assert_eq!(store.view(&link.token, Some("1234")), Err(ShareError::Locked));
+    }
+}
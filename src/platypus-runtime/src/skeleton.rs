@@ -0,0 +1,79 @@
+//! Placeholders for slow content.
+//!
+//! `st.skeleton(kind, count)` shows a placeholder shaped like the content
+//! being loaded and returns a [`Skeleton`], whose `fill` replaces it. In a
+//! run that flushes partial pages, the placeholder is sent right away, so
+//! the user sees the page's layout while the data loads:
+//!
+//! ```
+//! use platypus_runtime::St;
+//! use platypus_core::SkeletonKind;
+//!
+//! let mut st = St::new();
+//! let skeleton = st.skeleton(SkeletonKind::Table, 5);
+//! let rows = vec![vec!["north", "10"], vec!["south", "7"]]; // a slow query
+//! skeleton.fill(|st| st.table(vec!["region", "sales"], rows));
+//! ```
+
+use crate::context::{Container, St};
+use platypus_core::element::ElementId;
+
+/// A placeholder shown until its content is ready.
+pub struct Skeleton {
+    container: Container,
+    placeholder: ElementId,
+}
+
+impl Skeleton {
+    pub(crate) fn new(container: Container, placeholder: ElementId) -> Self {
+        Skeleton { container, placeholder }
+    }
+
+    /// The element ID of the container the content goes in.
+    pub fn id(&self) -> ElementId {
+        self.container.id()
+    }
+
+    /// Replace the placeholder with the content `build` writes.
+    pub fn fill<R>(self, build: impl FnOnce(&mut St) -> R) -> R {
+        let mut st = self.container.st();
+        st.remove(self.placeholder);
+        build(&mut st)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::Mutex;
+    use platypus_core::element::ElementType;
+    use platypus_core::state::Delta;
+    use platypus_core::SkeletonKind;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn test_skeleton_flushes_then_fills() {
+        let flushed = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&flushed);
+        let mut st = St::new().with_flush(Arc::new(move |deltas: Vec<Delta>| sink.lock().push(deltas)), Duration::from_secs(60));
+
+        let skeleton = st.skeleton(SkeletonKind::Text, 3);
+        {
+            let flushed = flushed.lock();
+            assert_eq!(flushed.len(), 1);
+            assert!(flushed[0].iter().any(|delta| matches!(
+                delta,
+                Delta::AddElement { element: ElementType::Skeleton { kind: SkeletonKind::Text, count: 3 }, .. }
+            )));
+        }
+
+        skeleton.fill(|st| st.write("Loaded"));
+        let deltas = st.take_deltas();
+        assert!(deltas.iter().any(|delta| matches!(delta, Delta::RemoveElement { .. })));
+        assert!(matches!(
+            deltas.last(),
+            Some(Delta::AddElement { element: ElementType::Text { value }, .. }) if value == "Loaded"
+        ));
+    }
+}
@@ -0,0 +1,130 @@
+//! Text-to-speech for voice-assistant style apps.
+//!
+//! `st.speak(text, voice)` synthesizes speech with the server's
+//! [`SpeechProvider`], stores it in the media store and plays it:
+//!
+//! ```
+//! use platypus_runtime::St;
+//!
+//! # fn app(st: &mut St) -> platypus_runtime::Result<()> {
+//! st.speak("Your order has shipped.", "alloy")?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Clips are synthesized once per provider, voice and text, so reruns reuse
+//! them, and the client plays each clip once rather than on every rerun.
+
+use crate::error::{Error, Result};
+use crate::media::{MediaFile, MediaStore};
+use dashmap::DashMap;
+use std::sync::Arc;
+
+/// Turns text into spoken audio.
+pub trait SpeechProvider: Send + Sync {
+    /// Provider name, part of the clip cache key.
+    fn name(&self) -> &str;
+
+    /// Synthesize `text` spoken in `voice`, returning an audio file such as
+    /// `speech.mp3` with an `audio/*` MIME type.
+    fn synthesize(&self, text: &str, voice: &str) -> Result<MediaFile>;
+}
+
+/// Synthesizes speech with the server's provider, reusing stored clips.
+pub struct Speaker {
+    provider: Option<Arc<dyn SpeechProvider>>,
+    /// Media IDs of synthesized clips by provider, voice and text
+    clips: Arc<DashMap<(String, String, String), String>>,
+}
+
+impl Speaker {
+    /// Create a speaker without a provider; `speak` fails until one is set.
+    pub fn new() -> Self {
+        Speaker {
+            provider: None,
+            clips: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// Synthesize with `provider`.
+    pub fn with_provider(mut self, provider: impl SpeechProvider + 'static) -> Self {
+        self.provider = Some(Arc::new(provider));
+        self
+    }
+
+    /// Check if a provider is set.
+    pub fn has_provider(&self) -> bool {
+        self.provider.is_some()
+    }
+
+    /// Get the media URL of `text` spoken in `voice`, synthesizing and
+    /// storing it in `media` unless a stored clip exists.
+    pub fn speak(&self, text: &str, voice: &str, media: &MediaStore) -> Result<String> {
+        let Some(provider) = &self.provider else {
+            return Err(Error::execution("no speech provider configured"));
+        };
+        let key = (provider.name().to_string(), voice.to_string(), text.to_string());
+        // Clips are collected once no session shows them
+        if let Some(id) = self.clips.get(&key).map(|id| id.clone())
+            && media.get(&id).is_some()
+        {
+            return Ok(MediaStore::url_for(&id));
+        }
+
+        let clip = provider.synthesize(text, voice)?;
+        let id = media.add(clip);
+        self.clips.insert(key, id.clone());
+        Ok(MediaStore::url_for(&id))
+    }
+}
+
+impl Default for Speaker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for Speaker {
+    fn clone(&self) -> Self {
+        Speaker {
+            provider: self.provider.clone(),
+            clips: Arc::clone(&self.clips),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct Beep(Arc<AtomicUsize>);
+
+    impl SpeechProvider for Beep {
+        fn name(&self) -> &str {
+            "beep"
+        }
+
+        fn synthesize(&self, text: &str, voice: &str) -> Result<MediaFile> {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            Ok(MediaFile::new("speech.wav", "audio/wav", format!("{voice}:{text}").into_bytes()))
+        }
+    }
+
+    #[test]
+    fn test_clips_are_reused_while_stored() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let speaker = Speaker::new().with_provider(Beep(Arc::clone(&calls)));
+        let media = MediaStore::new();
+
+        let url = speaker.speak("Hello", "alloy", &media).unwrap();
+        assert_eq!(speaker.speak("Hello", "alloy", &media).unwrap(), url);
+        assert_ne!(speaker.speak("Hello", "echo", &media).unwrap(), url);
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+
+        // A collected clip is synthesized again
+        media.clear();
+        speaker.speak("Hello", "alloy", &media).unwrap();
+        assert_eq!(calls.load(Ordering::Relaxed), 3);
+    }
+}
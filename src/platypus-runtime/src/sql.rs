@@ -0,0 +1,301 @@
+//! SQL query results.
+//!
+//! `st.sql(conn, query, ttl)` runs a query on a [`SqlConnection`] and shows
+//! its rows as a paginated table, with the row count, the execution time
+//! and, if the connection can explain queries, the query plan.
+//!
+//! Given a `ttl`, the result is cached in the server's [`DataCache`] by
+//! connection, query text and parameters, so reruns within the `ttl` don't
+//! query again. A cached result is not invalidated when the tables it
+//! reads change: it is served until the `ttl` passes, so queries whose
+//! results must be current pass `None` and always run. Statements the
+//! connection does not report as read-only are never cached.
+//!
+//! ```
+//! use platypus_runtime::sql::{QueryResult, SqlConnection};
+//! use platypus_runtime::St;
+//! use serde_json::{json, Value};
+//! use std::time::Duration;
+//!
+//! struct Orders;
+//!
+//! impl SqlConnection for Orders {
+//!     fn name(&self) -> &str {
+//!         "orders"
+//!     }
+//!
+//!     fn query(&self, _query: &str, _params: &[Value]) -> platypus_runtime::Result<QueryResult> {
+//!         Ok(QueryResult {
+//!             columns: vec!["region".to_string(), "total".to_string()],
+//!             rows: vec![vec![json!("north"), json!(10)]],
+//!         })
+//!     }
+//! }
+//!
+//! let mut st = St::new();
+//! let query = "SELECT region, SUM(total) FROM orders GROUP BY region";
+//! let result = st.sql(&Orders, query, Some(Duration::from_secs(60))).unwrap();
+//! assert_eq!(result.rows.len(), 1);
+//! ```
+
+use crate::cache::DataCache;
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::{Duration, Instant};
+
+/// Rows shown per page of a query's table.
+pub const SQL_PAGE_SIZE: usize = 50;
+
+/// A database connection queries run on.
+pub trait SqlConnection: Send + Sync {
+    /// Connection name, part of the result cache key.
+    fn name(&self) -> &str;
+
+    /// Run `query` with its positional `params`.
+    fn query(&self, query: &str, params: &[Value]) -> Result<QueryResult>;
+
+    /// Get the plan of `query`, e.g. the output of `EXPLAIN`, or `None` if
+    /// the connection cannot explain queries.
+    fn explain(&self, _query: &str, _params: &[Value]) -> Result<Option<String>> {
+        Ok(None)
+    }
+
+    /// Whether `query` only reads, so its result may be cached. By default
+    /// only statements starting with `SELECT` or `VALUES` are.
+    fn is_read_only(&self, query: &str) -> bool {
+        let keyword = query.split_whitespace().next().unwrap_or_default();
+        ["SELECT", "VALUES"].iter().any(|read| keyword.eq_ignore_ascii_case(read))
+    }
+
+    /// Run `statements`, e.g. a migration file, discarding any rows.
+    fn execute_batch(&self, _statements: &str) -> Result<()> {
+        Err(Error::execution(format!("Connection {} cannot run statements", self.name())))
+    }
+}
+
+/// Rows returned by a query.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+impl QueryResult {
+    /// Get the number of pages of the result's table; empty results have one.
+    pub fn pages(&self) -> usize {
+        self.rows.len().div_ceil(SQL_PAGE_SIZE).max(1)
+    }
+
+    /// Get the cells of page `page`, counted from 0, as table text.
+    pub fn page(&self, page: usize) -> Vec<Vec<String>> {
+        self.rows
+            .iter()
+            .skip(page * SQL_PAGE_SIZE)
+            .take(SQL_PAGE_SIZE)
+            .map(|row| row.iter().map(cell_text).collect())
+            .collect()
+    }
+}
+
+/// A query's result as cached, with how it was produced.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct QueryRun {
+    pub(crate) result: QueryResult,
+    pub(crate) plan: Option<String>,
+    pub(crate) elapsed_ms: f64,
+    /// Whether the result came from the cache
+    #[serde(skip)]
+    pub(crate) cached: bool,
+}
+
+/// Run `query` on `conn`, or reuse its result cached within `ttl`. Results
+/// are only cached given a `ttl` and a read-only query.
+pub(crate) fn run(
+    conn: &dyn SqlConnection,
+    query: &str,
+    params: &[Value],
+    cache: &DataCache,
+    ttl: Option<Duration>,
+) -> Result<QueryRun> {
+    let ttl = ttl.filter(|_| conn.is_read_only(query));
+    let key = format!("sql:{}:{}:{}", conn.name(), query, Value::from(params));
+    if ttl.is_some()
+        && let Some(run) = cache.get(&key).and_then(|json| serde_json::from_str::<QueryRun>(&json).ok())
+    {
+        return Ok(QueryRun { cached: true, ..run });
+    }
+
+    let start = Instant::now();
+    let result = conn.query(query, params)?;
+    let elapsed_ms = start.elapsed().as_secs_f64() * 1000.0;
+    let plan = conn.explain(query, params)?;
+    let run = QueryRun { result, plan, elapsed_ms, cached: false };
+    if let Some(ttl) = ttl {
+        let json = serde_json::to_string(&run).map_err(|e| Error::execution(e.to_string()))?;
+        cache.set(key, json, Some(ttl));
+    }
+    Ok(run)
+}
+
+/// Connection to a SQLite database.
+#[cfg(feature = "sqlite")]
+pub struct SqliteConnection {
+    name: String,
+    connection: parking_lot::Mutex<rusqlite::Connection>,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteConnection {
+    /// Open or create the database at `path`.
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let path = path.as_ref();
+        Ok(SqliteConnection {
+            name: path.display().to_string(),
+            connection: parking_lot::Mutex::new(rusqlite::Connection::open(path).map_err(anyhow::Error::from)?),
+        })
+    }
+
+    /// Create a private in-memory database.
+    pub fn in_memory() -> Result<Self> {
+        Ok(SqliteConnection {
+            name: ":memory:".to_string(),
+            connection: parking_lot::Mutex::new(rusqlite::Connection::open_in_memory().map_err(anyhow::Error::from)?),
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl SqlConnection for SqliteConnection {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn query(&self, query: &str, params: &[Value]) -> Result<QueryResult> {
+        let connection = self.connection.lock();
+        let mut statement = connection.prepare(query).map_err(anyhow::Error::from)?;
+        let columns: Vec<String> = statement.column_names().into_iter().map(String::from).collect();
+        let count = columns.len();
+        let rows = statement
+            .query_map(rusqlite::params_from_iter(params.iter().map(sqlite_param)), |row| {
+                (0..count).map(|i| row.get_ref(i).map(sqlite_json)).collect()
+            })
+            .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+            .map_err(anyhow::Error::from)?;
+        Ok(QueryResult { columns, rows })
+    }
+
+    fn is_read_only(&self, query: &str) -> bool {
+        self.connection.lock().prepare(query).is_ok_and(|statement| statement.readonly())
+    }
+
+    fn explain(&self, query: &str, params: &[Value]) -> Result<Option<String>> {
+        // Rows of id, parent, unused and detail
+        let plan = self.query(&format!("EXPLAIN QUERY PLAN {}", query), params)?;
+        let details: Vec<String> = plan.rows.iter().filter_map(|row| row.get(3)).map(cell_text).collect();
+        Ok(Some(details.join("\n")))
+    }
+
+    fn execute_batch(&self, statements: &str) -> Result<()> {
+        self.connection.lock().execute_batch(statements).map_err(anyhow::Error::from)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+fn sqlite_param(value: &Value) -> rusqlite::types::Value {
+    use rusqlite::types::Value as Sql;
+    match value {
+        Value::Null => Sql::Null,
+        Value::Bool(b) => Sql::Integer(*b as i64),
+        Value::Number(n) => n.as_i64().map_or_else(|| Sql::Real(n.as_f64().unwrap_or(f64::NAN)), Sql::Integer),
+        Value::String(s) => Sql::Text(s.clone()),
+        other => Sql::Text(other.to_string()),
+    }
+}
+
+#[cfg(feature = "sqlite")]
+fn sqlite_json(value: rusqlite::types::ValueRef) -> Value {
+    use rusqlite::types::ValueRef;
+    match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(i) => i.into(),
+        ValueRef::Real(f) => f.into(),
+        ValueRef::Text(text) => String::from_utf8_lossy(text).into_owned().into(),
+        ValueRef::Blob(blob) => format!("<{} bytes>", blob.len()).into(),
+    }
+}
+
+/// Show strings unquoted and nulls as empty cells.
+fn cell_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct Counting(AtomicUsize);
+
+    impl SqlConnection for Counting {
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        fn query(&self, _query: &str, params: &[Value]) -> Result<QueryResult> {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            Ok(QueryResult {
+                columns: vec!["n".to_string(), "label".to_string()],
+                rows: (0..120).map(|n| vec![json!(n), params[0].clone()]).collect(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_run_caches_by_query_and_params() {
+        let conn = Counting(AtomicUsize::new(0));
+        let cache = DataCache::new();
+        let ttl = Some(Duration::from_secs(60));
+        let first = run(&conn, "SELECT n", &[json!("a")], &cache, ttl).unwrap();
+        assert!(!first.cached);
+        assert!(run(&conn, "SELECT n", &[json!("a")], &cache, ttl).unwrap().cached);
+        assert!(!run(&conn, "SELECT n", &[json!(null)], &cache, ttl).unwrap().cached);
+        assert_eq!(conn.0.load(Ordering::Relaxed), 2);
+
+        assert_eq!(first.result.pages(), 3);
+        assert_eq!(first.result.page(2).len(), 20);
+        assert_eq!(first.result.page(2)[0], vec!["100", "a"]);
+    }
+
+    #[test]
+    fn test_run_caches_only_read_only_queries_with_a_ttl() {
+        let conn = Counting(AtomicUsize::new(0));
+        let cache = DataCache::new();
+        let ttl = Some(Duration::from_secs(60));
+        run(&conn, "SELECT n", &[json!("a")], &cache, None).unwrap();
+        assert!(!run(&conn, "SELECT n", &[json!("a")], &cache, None).unwrap().cached);
+        run(&conn, "DELETE FROM t RETURNING n", &[json!("a")], &cache, ttl).unwrap();
+        assert!(!run(&conn, "DELETE FROM t RETURNING n", &[json!("a")], &cache, ttl).unwrap().cached);
+        assert_eq!(conn.0.load(Ordering::Relaxed), 4);
+        assert_eq!(cache.size(), 0);
+
+        run(&conn, "select n", &[json!("a")], &cache, Some(Duration::ZERO)).unwrap();
+        assert!(!run(&conn, "select n", &[json!("a")], &cache, ttl).unwrap().cached);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_reports_read_only_statements() {
+        let conn = SqliteConnection::in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE t (n INTEGER)").unwrap();
+        assert!(conn.is_read_only("WITH x AS (SELECT n FROM t) SELECT n FROM x"));
+        assert!(!conn.is_read_only("INSERT INTO t VALUES (1)"));
+        assert!(!conn.is_read_only("not sql"));
+    }
+}
@@ -0,0 +1,204 @@
+//! Versioning and migration of persisted widget and session state.
+//!
+//! Sessions outlive app updates: they are exported as snapshots and, in a
+//! cluster, kept in the shared store while nodes are redeployed. An update
+//! renaming a widget or changing the type of its value would leave the
+//! state of such sessions unreadable, so apps declare the version of their
+//! state and how to migrate each older version to the next:
+//!
+//! ```
+//! use platypus_runtime::StateSchema;
+//!
+//! let schema = StateSchema::new(2)
+//!     // Version 1 renamed the region filter
+//!     .migration(0, |state| {
+//!         state.rename_widget("region", "regions");
+//!         Ok(())
+//!     })
+//!     // Version 2 made it a list
+//!     .migration(1, |state| state.convert_widget("regions", |region: String| vec![region]));
+//! ```
+//!
+//! A [`SessionStore`](crate::SessionStore) with a schema migrates the
+//! sessions it imports or takes over from another node before they run.
+//! State saved before the app declared a schema has version 0.
+
+use crate::error::{Error, Result};
+use platypus_core::session::Session;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::Arc;
+
+/// Migrates state from one version to the next.
+pub type MigrationFn = Arc<dyn Fn(&mut StateMigration<'_>) -> Result<()> + Send + Sync>;
+
+/// Version of an app's state, with the migrations from older versions.
+#[derive(Clone, Default)]
+pub struct StateSchema {
+    version: u32,
+    migrations: BTreeMap<u32, MigrationFn>,
+}
+
+impl fmt::Debug for StateSchema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StateSchema")
+            .field("version", &self.version)
+            .field("migrations", &self.migrations.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl StateSchema {
+    /// Declare the current version of the app's state.
+    pub fn new(version: u32) -> Self {
+        StateSchema {
+            version,
+            migrations: BTreeMap::new(),
+        }
+    }
+
+    /// Get the current version.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Migrate state of version `from` to version `from + 1`.
+    pub fn migration<F>(mut self, from: u32, migrate: F) -> Self
+    where
+        F: Fn(&mut StateMigration<'_>) -> Result<()> + Send + Sync + 'static,
+    {
+        self.migrations.insert(from, Arc::new(migrate));
+        self
+    }
+
+    /// Migrate a session's state to the current version, returning whether
+    /// it needed migrating. On failure the session is left unchanged.
+    pub fn migrate(&self, session: &mut Session) -> Result<bool> {
+        if session.state_version == self.version {
+            return Ok(false);
+        }
+        if session.state_version > self.version {
+            return Err(Error::session(format!(
+                "Session state version {} is newer than the app's version {}",
+                session.state_version, self.version
+            )));
+        }
+
+        let mut widgets = session.widget_state.clone();
+        let mut metadata = session.metadata.clone();
+        for from in session.state_version..self.version {
+            let migrate = self
+                .migrations
+                .get(&from)
+                .ok_or_else(|| Error::session(format!("No migration from state version {}", from)))?;
+            let mut state = StateMigration {
+                widgets: &mut widgets,
+                metadata: &mut metadata,
+            };
+            migrate(&mut state)
+                .map_err(|e| Error::session(format!("Migrating state from version {} failed: {}", from, e)))?;
+        }
+        session.widget_state = widgets;
+        session.metadata = metadata;
+        session.state_version = self.version;
+        Ok(true)
+    }
+}
+
+/// State of a session being migrated.
+///
+/// Widget values are stored as strings: plain strings as they are, other
+/// values as JSON. The typed accessors handle both.
+pub struct StateMigration<'a> {
+    widgets: &'a mut HashMap<String, String>,
+    metadata: &'a mut HashMap<String, String>,
+}
+
+impl StateMigration<'_> {
+    /// Get the value of widget `key`, if stored and of type `T`.
+    pub fn widget<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let value = self.widgets.get(key)?;
+        serde_json::from_str(value)
+            .or_else(|_| serde_json::from_value(serde_json::Value::String(value.clone())))
+            .ok()
+    }
+
+    /// Set the value of widget `key`.
+    pub fn set_widget<T: Serialize>(&mut self, key: impl Into<String>, value: &T) -> Result<()> {
+        let value = match serde_json::to_value(value).map_err(|e| Error::session(e.to_string()))? {
+            serde_json::Value::String(s) => s,
+            value => value.to_string(),
+        };
+        self.widgets.insert(key.into(), value);
+        Ok(())
+    }
+
+    /// Convert the value of widget `key` with `convert`. Missing widgets are
+    /// left alone; a value not of type `T` fails the migration.
+    pub fn convert_widget<T, U, F>(&mut self, key: &str, convert: F) -> Result<()>
+    where
+        T: DeserializeOwned,
+        U: Serialize,
+        F: FnOnce(T) -> U,
+    {
+        if !self.widgets.contains_key(key) {
+            return Ok(());
+        }
+        let value = self
+            .widget(key)
+            .ok_or_else(|| Error::session(format!("Widget {} has a value of another type", key)))?;
+        self.set_widget(key, &convert(value))
+    }
+
+    /// Move the value of widget `from` to `to`.
+    pub fn rename_widget(&mut self, from: &str, to: impl Into<String>) {
+        if let Some(value) = self.widgets.remove(from) {
+            self.widgets.insert(to.into(), value);
+        }
+    }
+
+    /// Drop the value of widget `key`.
+    pub fn remove_widget(&mut self, key: &str) {
+        self.widgets.remove(key);
+    }
+
+    /// Get the session state (metadata such as the theme preference).
+    pub fn session_state(&mut self) -> &mut HashMap<String, String> {
+        self.metadata
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrates_through_versions() {
+        let schema = StateSchema::new(2)
+            .migration(0, |state| {
+                state.rename_widget("region", "regions");
+                Ok(())
+            })
+            .migration(1, |state| state.convert_widget("regions", |region: String| vec![region]));
+
+        let mut session = Session::new("app".to_string());
+        session.widget_state.insert("region".to_string(), "EMEA".to_string());
+        assert!(schema.migrate(&mut session).unwrap());
+        assert_eq!(session.state_version, 2);
+        assert_eq!(session.widget_state["regions"], r#"["EMEA"]"#);
+        assert!(!schema.migrate(&mut session).unwrap());
+
+        session.state_version = 3;
+        assert!(schema.migrate(&mut session).is_err());
+
+        // A failed migration leaves the session as it was
+        let counter = StateSchema::new(1).migration(0, |state| state.convert_widget("count", |count: u32| count + 1));
+        let mut session = Session::new("app".to_string());
+        session.widget_state.insert("count".to_string(), "many".to_string());
+        assert!(counter.migrate(&mut session).is_err());
+        assert_eq!(session.state_version, 0);
+        assert_eq!(session.widget_state["count"], "many");
+    }
+}
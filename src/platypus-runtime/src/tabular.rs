@@ -0,0 +1,219 @@
+//! Struct-backed tables.
+//!
+//! `#[derive(Tabular)]` turns each field into a column, so a `Vec` of
+//! structs renders with `st.table_of(&rows)`:
+//!
+//! ```
+//! use platypus_runtime::{St, Tabular};
+//!
+//! #[derive(Tabular)]
+//! struct Sale {
+//!     region: String,
+//!     #[tabular(rename = "Units sold")]
+//!     units: u32,
+//!     #[tabular(format = "currency:USD")]
+//!     revenue: f64,
+//!     #[tabular(skip)]
+//!     internal_id: u64,
+//! }
+//!
+//! let rows = vec![Sale { region: "EMEA".into(), units: 12, revenue: 1520.5, internal_id: 7 }];
+//! let mut st = St::new();
+//! st.table_of(&rows);
+//! ```
+//!
+//! Format specs are those accepted by [`NumberFormat::parse`] and apply to
+//! numeric cells only.
+
+use crate::format::NumberFormat;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone};
+
+/// A typed table cell.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    Empty,
+    Text(String),
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+}
+
+impl Cell {
+    /// Get the numeric value, if any.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Cell::Int(value) => Some(*value as f64),
+            Cell::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Render the cell, applying `format` to numbers.
+    pub fn render(&self, format: Option<&NumberFormat>, locale: &str) -> String {
+        match (self, format, self.as_f64()) {
+            (_, Some(format), Some(number)) => format.format(number, locale),
+            (Cell::Empty, _, _) => String::new(),
+            (Cell::Text(text), _, _) => text.clone(),
+            (Cell::Bool(value), _, _) => value.to_string(),
+            (Cell::Int(value), _, _) => value.to_string(),
+            (Cell::Float(value), _, _) => value.to_string(),
+        }
+    }
+}
+
+/// A value that can be shown in a table cell.
+pub trait TableCell {
+    fn to_cell(&self) -> Cell;
+}
+
+/// A table column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    pub name: String,
+    pub format: Option<NumberFormat>,
+}
+
+impl Column {
+    /// Create an unformatted column.
+    pub fn new(name: impl Into<String>) -> Self {
+        Column { name: name.into(), format: None }
+    }
+
+    /// Format numeric cells in this column.
+    pub fn format(mut self, format: NumberFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+}
+
+/// A struct rendered as a table row. Usually derived.
+pub trait Tabular {
+    /// Get the columns, in display order.
+    fn columns() -> Vec<Column>;
+
+    /// Get this row's cells, one per column.
+    fn cells(&self) -> Vec<Cell>;
+}
+
+impl TableCell for String {
+    fn to_cell(&self) -> Cell {
+        Cell::Text(self.clone())
+    }
+}
+
+impl TableCell for &str {
+    fn to_cell(&self) -> Cell {
+        Cell::Text(self.to_string())
+    }
+}
+
+impl TableCell for bool {
+    fn to_cell(&self) -> Cell {
+        Cell::Bool(*self)
+    }
+}
+
+impl TableCell for NaiveDate {
+    fn to_cell(&self) -> Cell {
+        Cell::Text(self.format(crate::datetime::DATE_FORMAT).to_string())
+    }
+}
+
+impl TableCell for NaiveDateTime {
+    fn to_cell(&self) -> Cell {
+        Cell::Text(self.to_string())
+    }
+}
+
+impl<Tz: TimeZone> TableCell for DateTime<Tz>
+where
+    Tz::Offset: std::fmt::Display,
+{
+    fn to_cell(&self) -> Cell {
+        Cell::Text(self.to_rfc3339())
+    }
+}
+
+impl<T: TableCell> TableCell for Option<T> {
+    fn to_cell(&self) -> Cell {
+        self.as_ref().map_or(Cell::Empty, TableCell::to_cell)
+    }
+}
+
+macro_rules! int_table_cell {
+    ($($ty:ty),*) => {
+        $(
+            impl TableCell for $ty {
+                fn to_cell(&self) -> Cell {
+                    // Values past i64::MAX fall back to floating point
+                    i64::try_from(*self).map_or(Cell::Float(*self as f64), Cell::Int)
+                }
+            }
+        )*
+    };
+}
+
+int_table_cell!(i8, i16, i32, i64, u8, u16, u32, u64, usize, isize);
+
+impl TableCell for f32 {
+    fn to_cell(&self) -> Cell {
+        Cell::Float(*self as f64)
+    }
+}
+
+impl TableCell for f64 {
+    fn to_cell(&self) -> Cell {
+        Cell::Float(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{St, Tabular};
+    use platypus_core::element::ElementType;
+    use platypus_core::state::Delta;
+
+    #[derive(Tabular)]
+    struct Sale {
+        region: String,
+        #[tabular(rename = "Units sold")]
+        units: u32,
+        #[tabular(format = "currency:USD")]
+        revenue: f64,
+        note: Option<String>,
+        #[tabular(skip)]
+        #[allow(dead_code)]
+        internal_id: u64,
+    }
+
+    #[test]
+    fn test_table_of_renders_columns_and_formats() {
+        let rows = vec![Sale {
+            region: "EMEA".to_string(),
+            units: 12,
+            revenue: 1520.5,
+            note: None,
+            internal_id: 7,
+        }];
+        let mut st = St::new();
+        st.table_of(&rows);
+
+        let deltas = st.delta_gen().take_deltas();
+        let Some(Delta::AddElement { element: ElementType::Table { headers, rows }, .. }) = deltas.first() else {
+            panic!("expected a table");
+        };
+        assert_eq!(headers, &vec!["region", "Units sold", "revenue", "note"]);
+        assert_eq!(rows[0][0], "EMEA");
+        assert_eq!(rows[0][1], "12");
+        assert_eq!(rows[0][2], st.format_number(1520.5, &NumberFormat::currency("USD")));
+        assert_eq!(rows[0][3], "");
+    }
+
+    #[test]
+    fn test_cell_render() {
+        assert_eq!(u64::MAX.to_cell(), Cell::Float(u64::MAX as f64));
+        assert_eq!(Cell::Bool(true).render(None, "en-US"), "true");
+        assert_eq!(Cell::Text("x".into()).render(Some(&NumberFormat::percent()), "en-US"), "x");
+    }
+}
@@ -0,0 +1,174 @@
+//! Per-session scratch directories.
+//!
+//! `st.tempdir()` gives the session a directory of its own under the
+//! server's [`TempDirs`] root, created on first use and removed when the
+//! session ends. Files written through [`TempDir::write`] count against a
+//! quota, so one session cannot fill the disk:
+//!
+//! ```
+//! use platypus_runtime::St;
+//!
+//! # fn app(st: &mut St) -> platypus_runtime::Result<()> {
+//! let dir = st.tempdir()?;
+//! let path = dir.write("upload.csv", "region,sales\nnorth,10\n")?;
+//! st.write(format!("Saved {}", path.display()));
+//! # Ok(())
+//! # }
+//! # app(&mut St::new()).unwrap();
+//! ```
+
+use crate::error::{Error, Result};
+use platypus_core::session::SessionId;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Default bytes a session may keep in its directory.
+pub const DEFAULT_TEMP_QUOTA: u64 = 100 * 1024 * 1024;
+
+/// Name of the directory of contexts without a session, e.g. in tests.
+const LOCAL_DIR: &str = "local";
+
+/// The server's session directories.
+#[derive(Debug, Clone)]
+pub struct TempDirs {
+    root: PathBuf,
+    quota: u64,
+}
+
+impl TempDirs {
+    /// Keep session directories under the system's temporary directory,
+    /// apart from other server processes.
+    pub fn new() -> Self {
+        TempDirs {
+            root: std::env::temp_dir().join(format!("platypus-{}", std::process::id())),
+            quota: DEFAULT_TEMP_QUOTA,
+        }
+    }
+
+    /// Keep session directories under `root`.
+    pub fn with_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = root.into();
+        self
+    }
+
+    /// Set the bytes a session may keep in its directory.
+    pub fn with_quota(mut self, quota: u64) -> Self {
+        self.quota = quota;
+        self
+    }
+
+    /// Get the directory of `session_id`, creating it on first use.
+    pub fn dir(&self, session_id: Option<SessionId>) -> Result<TempDir> {
+        let name = session_id.map_or_else(|| LOCAL_DIR.to_string(), |id| id.to_string());
+        let path = self.root.join(name);
+        fs::create_dir_all(&path)?;
+        Ok(TempDir { path, quota: self.quota })
+    }
+
+    /// Delete the directory of an ended session and everything in it.
+    pub fn remove(&self, session_id: SessionId) {
+        let path = self.root.join(session_id.to_string());
+        if let Err(e) = fs::remove_dir_all(&path)
+            && e.kind() != std::io::ErrorKind::NotFound
+        {
+            tracing::warn!("Failed to remove {}: {}", path.display(), e);
+        }
+    }
+}
+
+impl Default for TempDirs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A session's scratch directory.
+#[derive(Debug, Clone)]
+pub struct TempDir {
+    path: PathBuf,
+    quota: u64,
+}
+
+impl TempDir {
+    /// Get the directory's path. Files written here directly count against
+    /// the quota of later writes.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get the bytes the session may keep in the directory.
+    pub fn quota(&self) -> u64 {
+        self.quota
+    }
+
+    /// Get the bytes of the files in the directory.
+    pub fn usage(&self) -> Result<u64> {
+        dir_size(&self.path)
+    }
+
+    /// Get the path of `name` in the directory. Fails for names outside it,
+    /// e.g. absolute paths or ones with `..`.
+    pub fn join(&self, name: &str) -> Result<PathBuf> {
+        let relative = Path::new(name);
+        let inside = relative.components().all(|c| matches!(c, Component::Normal(_)));
+        if name.is_empty() || !inside {
+            return Err(Error::execution(format!("Invalid temporary file name: {}", name)));
+        }
+        Ok(self.path.join(relative))
+    }
+
+    /// Write `contents` to the file `name`, replacing it, unless the
+    /// directory would exceed its quota. Returns the file's path.
+    pub fn write(&self, name: &str, contents: impl AsRef<[u8]>) -> Result<PathBuf> {
+        let path = self.join(name)?;
+        let contents = contents.as_ref();
+        let replaced = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        let usage = self.usage()?.saturating_sub(replaced) + contents.len() as u64;
+        if usage > self.quota {
+            return Err(Error::execution(format!(
+                "Temporary directory quota of {} bytes exceeded",
+                self.quota
+            )));
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, contents)?;
+        Ok(path)
+    }
+}
+
+fn dir_size(path: &Path) -> Result<u64> {
+    let mut size = 0;
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        size += if metadata.is_dir() { dir_size(&entry.path())? } else { metadata.len() };
+    }
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quota_and_removal() {
+        let root = std::env::temp_dir().join(format!("platypus-test-{}", uuid::Uuid::new_v4()));
+        let dirs = TempDirs::new().with_root(&root).with_quota(10);
+        let session_id = SessionId::new();
+        let dir = dirs.dir(Some(session_id)).unwrap();
+
+        dir.write("a.txt", "12345").unwrap();
+        dir.write("nested/b.txt", "123").unwrap();
+        // Replacing a file only counts the difference
+        dir.write("a.txt", "1234567").unwrap();
+        assert_eq!(dir.usage().unwrap(), 10);
+        assert!(dir.write("c.txt", "1").is_err());
+        assert!(dir.write("../escape.txt", "").is_err());
+
+        dirs.remove(session_id);
+        assert!(!dir.path().exists());
+        let _ = fs::remove_dir_all(root);
+    }
+}
@@ -0,0 +1,74 @@
+//! Light/dark theme selection.
+//!
+//! A session's theme mode is either chosen explicitly through
+//! `st.theme_toggle()` or left on auto, in which case it follows the
+//! color scheme reported by the client.
+
+use crate::client::{ClientInfo, ColorScheme};
+use serde::{Deserialize, Serialize};
+
+/// Session metadata key under which the theme mode is persisted.
+pub const THEME_METADATA_KEY: &str = "theme";
+
+/// Theme mode selected for a session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeMode {
+    /// Follow the client's `prefers-color-scheme`.
+    #[default]
+    Auto,
+    Light,
+    Dark,
+}
+
+impl ThemeMode {
+    /// Parse a theme mode name.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "auto" => Some(ThemeMode::Auto),
+            "light" => Some(ThemeMode::Light),
+            "dark" => Some(ThemeMode::Dark),
+            _ => None,
+        }
+    }
+
+    /// Get the mode name.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ThemeMode::Auto => "auto",
+            ThemeMode::Light => "light",
+            ThemeMode::Dark => "dark",
+        }
+    }
+
+    /// Resolve the color scheme to apply for a client.
+    pub fn resolve(self, client_info: &ClientInfo) -> ColorScheme {
+        match self {
+            ThemeMode::Auto => client_info.color_scheme,
+            ThemeMode::Light => ColorScheme::Light,
+            ThemeMode::Dark => ColorScheme::Dark,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(ThemeMode::parse("Dark"), Some(ThemeMode::Dark));
+        assert_eq!(ThemeMode::parse("auto"), Some(ThemeMode::Auto));
+        assert_eq!(ThemeMode::parse("sepia"), None);
+    }
+
+    #[test]
+    fn test_resolve() {
+        let client_info = ClientInfo {
+            color_scheme: ColorScheme::Dark,
+            ..Default::default()
+        };
+        assert_eq!(ThemeMode::Auto.resolve(&client_info), ColorScheme::Dark);
+        assert_eq!(ThemeMode::Light.resolve(&client_info), ColorScheme::Light);
+    }
+}
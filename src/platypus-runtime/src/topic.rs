@@ -0,0 +1,204 @@
+//! Publish/subscribe topics for pushed data, e.g. sensor readings.
+//!
+//! The server publishes to a [`TopicRegistry`], e.g. from an MQTT broker,
+//! and `st.subscribe(topic)` returns the messages the session has not seen
+//! yet. Sessions that subscribed rerun when their topics get messages, so
+//! a dashboard can append readings to a live chart:
+//!
+//! ```
+//! use platypus_runtime::{St, TopicRegistry};
+//! use serde_json::json;
+//!
+//! let topics = TopicRegistry::new();
+//! topics.publish("sensors/kitchen", json!({"t": "12:00:01", "celsius": 21.5}));
+//!
+//! let mut st = St::new().with_topics(topics);
+//! let chart = st.line_chart_stream("kitchen");
+//! chart.append(st.subscribe("sensors/kitchen").into_iter().filter_map(|message| {
+//!     Some((message.payload["t"].clone(), message.payload["celsius"].as_f64()?))
+//! }));
+//! assert_eq!(chart.len(), 1);
+//! ```
+
+use crate::room::CHANGE_CAPACITY;
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+/// Default messages kept per topic for sessions that have not read them.
+pub const DEFAULT_TOPIC_BACKLOG: usize = 1000;
+
+/// A message published to a topic.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TopicMessage {
+    pub topic: String,
+    /// Sequence number, increasing per topic from 1.
+    pub seq: u64,
+    pub payload: Value,
+}
+
+/// Forwards the messages published on this node to the other nodes of a
+/// cluster.
+pub type Relay = Arc<dyn Fn(&TopicMessage) + Send + Sync>;
+
+#[derive(Default)]
+struct Backlog {
+    messages: VecDeque<TopicMessage>,
+    last_seq: u64,
+}
+
+/// Every topic on the server, with the channel their names go out on when
+/// they get messages.
+pub struct TopicRegistry {
+    topics: Arc<DashMap<String, Arc<Mutex<Backlog>>>>,
+    changes: broadcast::Sender<String>,
+    backlog: usize,
+    relay: Option<Relay>,
+}
+
+impl TopicRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        TopicRegistry {
+            topics: Arc::new(DashMap::new()),
+            changes: broadcast::channel(CHANGE_CAPACITY).0,
+            backlog: DEFAULT_TOPIC_BACKLOG,
+            relay: None,
+        }
+    }
+
+    /// Pass every message published on this node to `relay`, which fans
+    /// it out to the other nodes of a cluster.
+    pub fn with_relay(mut self, relay: Relay) -> Self {
+        self.relay = Some(relay);
+        self
+    }
+
+    /// Keep the newest `backlog` messages per topic.
+    pub fn with_backlog(mut self, backlog: usize) -> Self {
+        self.backlog = backlog.max(1);
+        self
+    }
+
+    /// Publish `payload` to `topic`, dropping its oldest message beyond
+    /// the backlog.
+    pub fn publish(&self, topic: &str, payload: Value) {
+        let message = self.append(topic, payload);
+        if let Some(relay) = &self.relay {
+            relay(&message);
+        }
+    }
+
+    /// Publish a message another node of the cluster relayed, without
+    /// relaying it back.
+    pub fn receive(&self, topic: &str, payload: Value) {
+        self.append(topic, payload);
+    }
+
+    fn append(&self, topic: &str, payload: Value) -> TopicMessage {
+        let backlog = self.topics.entry(topic.to_string()).or_default().clone();
+        let message = {
+            let mut backlog = backlog.lock();
+            backlog.last_seq += 1;
+            let seq = backlog.last_seq;
+            if backlog.messages.len() == self.backlog {
+                backlog.messages.pop_front();
+            }
+            let message = TopicMessage {
+                topic: topic.to_string(),
+                seq,
+                payload,
+            };
+            backlog.messages.push_back(message.clone());
+            message
+        };
+        // Nobody subscribed is fine: no session is connected
+        let _ = self.changes.send(topic.to_string());
+        message
+    }
+
+    /// Publish a raw payload, parsed as JSON or else kept as text.
+    pub fn publish_bytes(&self, topic: &str, payload: &[u8]) {
+        let payload = serde_json::from_slice(payload).unwrap_or_else(|_| String::from_utf8_lossy(payload).into_owned().into());
+        self.publish(topic, payload);
+    }
+
+    /// Subscribe to the names of topics as they get messages.
+    pub fn subscribe(&self) -> broadcast::Receiver<String> {
+        self.changes.subscribe()
+    }
+
+    /// Get the kept messages of `topic` after `seq`, oldest first.
+    pub fn since(&self, topic: &str, seq: u64) -> Vec<TopicMessage> {
+        self.topics.get(topic).map_or_else(Vec::new, |backlog| {
+            backlog.lock().messages.iter().filter(|message| message.seq > seq).cloned().collect()
+        })
+    }
+}
+
+impl Default for TopicRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for TopicRegistry {
+    fn clone(&self) -> Self {
+        TopicRegistry {
+            topics: Arc::clone(&self.topics),
+            changes: self.changes.clone(),
+            backlog: self.backlog,
+            relay: self.relay.clone(),
+        }
+    }
+}
+
+/// The last message a session read per topic, shared across its reruns.
+#[derive(Clone, Default)]
+pub struct TopicCursors(Arc<Mutex<HashMap<String, u64>>>);
+
+impl TopicCursors {
+    /// Create cursors that have read nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take the messages of `topic` not read yet.
+    pub(crate) fn read(&self, topics: &TopicRegistry, topic: &str) -> Vec<TopicMessage> {
+        let mut cursors = self.0.lock();
+        let cursor = cursors.entry(topic.to_string()).or_default();
+        let messages = topics.since(topic, *cursor);
+        if let Some(message) = messages.last() {
+            *cursor = message.seq;
+        }
+        messages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_cursor_reads_each_message_once() {
+        let topics = TopicRegistry::new().with_backlog(2);
+        let mut changes = topics.subscribe();
+        let cursors = TopicCursors::new();
+        topics.publish("a", json!(1));
+        topics.publish_bytes("a", b"not json");
+        topics.publish("a", json!(3));
+        assert_eq!(changes.try_recv().unwrap(), "a");
+
+        // The first message fell out of the backlog
+        let payloads: Vec<Value> = cursors.read(&topics, "a").into_iter().map(|message| message.payload).collect();
+        assert_eq!(payloads, [json!("not json"), json!(3)]);
+        assert!(cursors.read(&topics, "a").is_empty());
+        topics.publish("a", json!(4));
+        assert_eq!(cursors.read(&topics, "a")[0].seq, 4);
+    }
+}
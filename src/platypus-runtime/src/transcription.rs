@@ -0,0 +1,302 @@
+//! Speech-to-text for recordings from `st.audio_input`.
+//!
+//! The server's [`Transcriber`] runs a [`TranscriptionProvider`] in the
+//! background, one job per recording. While a job runs, `transcribe()` shows
+//! its progress and returns `None`; the session reruns as the job advances
+//! and once it finishes:
+//!
+//! ```
+//! use platypus_runtime::St;
+//!
+//! # fn app(st: &mut St) -> platypus_runtime::Result<()> {
+//! if let Some(text) = st.audio_input("Ask a question", None).transcribe()? {
+//!     st.write(format!("You said: {}", text));
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::context::St;
+use crate::error::{Error, Result};
+use crate::media::{self, MediaFile};
+use crate::room::CHANGE_CAPACITY;
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use platypus_core::session::SessionId;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+/// Turns recorded speech into text.
+pub trait TranscriptionProvider: Send + Sync {
+    /// Provider name, for logs.
+    fn name(&self) -> &str;
+
+    /// Transcribe a recording. Runs off the app's thread, so it may block;
+    /// long transcriptions should report progress as they go.
+    fn transcribe(&self, audio: &MediaFile, progress: &TranscriptionProgress) -> Result<String>;
+}
+
+/// State of a transcription job.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TranscriptionStatus {
+    /// Running; `progress` is the reported fraction done, from 0 to 1.
+    Pending { progress: f32 },
+    Done(String),
+    Failed(String),
+}
+
+/// Reports a running job's progress to the session that started it.
+pub struct TranscriptionProgress {
+    status: Arc<Mutex<TranscriptionStatus>>,
+    owner: Option<SessionId>,
+    changes: broadcast::Sender<SessionId>,
+}
+
+impl TranscriptionProgress {
+    /// Set the fraction done (clamped to 0.0..=1.0). The session reruns when
+    /// it advances by at least a percent.
+    pub fn set(&self, fraction: f32) {
+        let fraction = fraction.clamp(0.0, 1.0);
+        let advanced = {
+            let mut status = self.status.lock();
+            match &mut *status {
+                TranscriptionStatus::Pending { progress } if fraction - *progress >= 0.01 => {
+                    *progress = fraction;
+                    true
+                }
+                _ => false,
+            }
+        };
+        if advanced {
+            self.notify();
+        }
+    }
+
+    fn finish(&self, result: Result<String>) {
+        *self.status.lock() = match result {
+            Ok(text) => TranscriptionStatus::Done(text),
+            Err(e) => TranscriptionStatus::Failed(e.to_string()),
+        };
+        self.notify();
+    }
+
+    fn notify(&self) {
+        if let Some(owner) = self.owner {
+            let _ = self.changes.send(owner);
+        }
+    }
+}
+
+struct Job {
+    owner: Option<SessionId>,
+    status: Arc<Mutex<TranscriptionStatus>>,
+}
+
+/// Transcription jobs of every session, run by the server's provider.
+pub struct Transcriber {
+    provider: Option<Arc<dyn TranscriptionProvider>>,
+    jobs: Arc<DashMap<String, Job>>,
+    changes: broadcast::Sender<SessionId>,
+}
+
+impl Transcriber {
+    /// Create a transcriber without a provider; `transcribe()` fails until
+    /// one is set.
+    pub fn new() -> Self {
+        Transcriber {
+            provider: None,
+            jobs: Arc::new(DashMap::new()),
+            changes: broadcast::channel(CHANGE_CAPACITY).0,
+        }
+    }
+
+    /// Transcribe with `provider`.
+    pub fn with_provider(mut self, provider: impl TranscriptionProvider + 'static) -> Self {
+        self.provider = Some(Arc::new(provider));
+        self
+    }
+
+    /// Check if a provider is set.
+    pub fn has_provider(&self) -> bool {
+        self.provider.is_some()
+    }
+
+    /// Start transcribing the recording with media ID `id` for `owner`,
+    /// unless a job for it exists. Returns the job's status.
+    pub fn start(&self, id: &str, audio: MediaFile, owner: Option<SessionId>) -> Result<TranscriptionStatus> {
+        if let Some(status) = self.status(id) {
+            return Ok(status);
+        }
+        let Some(provider) = self.provider.clone() else {
+            return Err(Error::execution("no transcription provider configured"));
+        };
+
+        let status = Arc::new(Mutex::new(TranscriptionStatus::Pending { progress: 0.0 }));
+        self.jobs.insert(
+            id.to_string(),
+            Job {
+                owner,
+                status: Arc::clone(&status),
+            },
+        );
+        let progress = TranscriptionProgress {
+            status,
+            owner,
+            changes: self.changes.clone(),
+        };
+        let run = move || {
+            tracing::debug!("Transcribing {} with {}", audio.file_name, provider.name());
+            progress.finish(provider.transcribe(&audio, &progress));
+        };
+        // Providers block on network or model inference
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn_blocking(run);
+            }
+            Err(_) => {
+                std::thread::spawn(run);
+            }
+        }
+        Ok(TranscriptionStatus::Pending { progress: 0.0 })
+    }
+
+    /// Get the status of the job for media ID `id`.
+    pub fn status(&self, id: &str) -> Option<TranscriptionStatus> {
+        self.jobs.get(id).map(|job| job.status.lock().clone())
+    }
+
+    /// Subscribe to job changes, identified by the session that started
+    /// the job.
+    pub fn subscribe(&self) -> broadcast::Receiver<SessionId> {
+        self.changes.subscribe()
+    }
+
+    /// Drop the jobs a session started. Running jobs finish unobserved.
+    pub fn forget(&self, owner: SessionId) {
+        self.jobs.retain(|_, job| job.owner != Some(owner));
+    }
+
+    /// Get the number of jobs.
+    pub fn len(&self) -> usize {
+        self.jobs.len()
+    }
+
+    /// Check if there are no jobs.
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+}
+
+impl Default for Transcriber {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for Transcriber {
+    fn clone(&self) -> Self {
+        Transcriber {
+            provider: self.provider.clone(),
+            jobs: Arc::clone(&self.jobs),
+            changes: self.changes.clone(),
+        }
+    }
+}
+
+/// An audio input's recording, returned by `st.audio_input`.
+pub struct AudioInput<'a> {
+    st: &'a mut St,
+    recording: Option<String>,
+}
+
+impl<'a> AudioInput<'a> {
+    pub(crate) fn new(st: &'a mut St, recording: Option<String>) -> Self {
+        AudioInput { st, recording }
+    }
+
+    /// Get the recording's media URL, if one was made.
+    pub fn recording(&self) -> Option<&str> {
+        self.recording.as_deref()
+    }
+
+    /// Get the recorded audio, if it is still stored.
+    pub fn file(&self) -> Option<MediaFile> {
+        let id = media::referenced_ids(self.recording.as_deref()?).next()?;
+        self.st.media().get(&id)
+    }
+
+    /// Transcribe the recording with the server's provider.
+    ///
+    /// Returns `None` without a recording, and while the transcription runs
+    /// in the background, showing its progress; the app reruns once it is
+    /// done.
+    pub fn transcribe(self) -> Result<Option<String>> {
+        let Some(url) = self.recording else {
+            return Ok(None);
+        };
+        match self.st.transcription(&url)? {
+            None => Ok(None),
+            Some(TranscriptionStatus::Pending { progress }) => {
+                let bar = self.st.progress_bar().text("Transcribing…");
+                if progress > 0.0 {
+                    bar.value(progress).show();
+                } else {
+                    bar.indeterminate().show();
+                }
+                Ok(None)
+            }
+            Some(TranscriptionStatus::Done(text)) => Ok(Some(text)),
+            Some(TranscriptionStatus::Failed(e)) => Err(Error::execution(format!("transcription failed: {}", e))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    struct Echo;
+
+    impl TranscriptionProvider for Echo {
+        fn name(&self) -> &str {
+            "echo"
+        }
+
+        fn transcribe(&self, audio: &MediaFile, progress: &TranscriptionProgress) -> Result<String> {
+            progress.set(0.5);
+            Ok(String::from_utf8_lossy(&audio.data).into_owned())
+        }
+    }
+
+    #[test]
+    fn test_job_runs_in_background() {
+        let transcriber = Transcriber::new().with_provider(Echo);
+        let owner = SessionId::new();
+        let mut changes = transcriber.subscribe();
+        let audio = MediaFile::new("clip.webm", "audio/webm", b"hello".to_vec());
+
+        transcriber.start("clip", audio.clone(), Some(owner)).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while matches!(transcriber.status("clip"), Some(TranscriptionStatus::Pending { .. })) {
+            assert!(Instant::now() < deadline, "transcription did not finish");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(transcriber.status("clip"), Some(TranscriptionStatus::Done("hello".to_string())));
+        assert_eq!(changes.try_recv().unwrap(), owner);
+
+        // A finished job is not run again
+        assert_eq!(
+            transcriber.start("clip", audio, Some(owner)).unwrap(),
+            TranscriptionStatus::Done("hello".to_string())
+        );
+        transcriber.forget(owner);
+        assert!(transcriber.is_empty());
+    }
+
+    #[test]
+    fn test_start_without_provider_fails() {
+        let audio = MediaFile::new("clip.webm", "audio/webm", Vec::new());
+        assert!(Transcriber::new().start("clip", audio, None).is_err());
+    }
+}
@@ -0,0 +1,273 @@
+//! Upload and download progress.
+//!
+//! Large uploads are sent to `PUT /upload/{id}` in chunks, each carrying a
+//! `Content-Range`, so an interrupted upload resumes from the bytes the
+//! server already holds. The server reports upload progress and the client
+//! reports download progress; both reach the app as
+//! [`Event::TransferProgress`](crate::Event::TransferProgress) and are read
+//! back with `st.transfer_progress`:
+//!
+//! ```
+//! use platypus_runtime::St;
+//!
+//! let mut st = St::new();
+//! if let Some(progress) = st.transfer_progress("report") {
+//!     st.progress(progress.fraction().unwrap_or(0.0) as f32);
+//! }
+//! ```
+
+use crate::media::{MediaFile, MediaStore};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::watch;
+
+/// URL prefix under which chunked uploads are received.
+pub const UPLOAD_URL_PREFIX: &str = "/upload";
+
+/// Prefix of the widget keys holding transfer progress.
+const TRANSFER_WIDGET_PREFIX: &str = "__transfer:";
+
+/// Get the widget key holding the progress of transfers for `key`.
+pub fn widget_key(key: &str) -> String {
+    format!("{}{}", TRANSFER_WIDGET_PREFIX, key)
+}
+
+/// Direction of a transfer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferDirection {
+    Upload,
+    Download,
+}
+
+impl TransferDirection {
+    /// Get the direction name.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransferDirection::Upload => "upload",
+            TransferDirection::Download => "download",
+        }
+    }
+
+    /// Parse a direction name.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "upload" => Some(TransferDirection::Upload),
+            "download" => Some(TransferDirection::Download),
+            _ => None,
+        }
+    }
+}
+
+/// Progress of one upload or download.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransferProgress {
+    /// Transfer ID: the upload ID, or the media ID of a download.
+    pub id: String,
+    /// Key of the widget the transfer belongs to.
+    pub key: String,
+    pub direction: TransferDirection,
+    /// Bytes transferred so far.
+    pub loaded: u64,
+    /// Total size, if known.
+    pub total: Option<u64>,
+    /// Media URL of a completed upload.
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+impl TransferProgress {
+    /// Get the fraction transferred, if the total is known.
+    pub fn fraction(&self) -> Option<f64> {
+        match self.total {
+            Some(0) => Some(1.0),
+            Some(total) => Some((self.loaded as f64 / total as f64).min(1.0)),
+            None => None,
+        }
+    }
+
+    /// Check if every byte was transferred.
+    pub fn is_complete(&self) -> bool {
+        self.total.is_some_and(|total| self.loaded >= total)
+    }
+}
+
+/// Byte range of an upload chunk, from its `Content-Range` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkRange {
+    /// First byte offset.
+    pub start: u64,
+    /// Total upload size.
+    pub total: u64,
+}
+
+/// Why an upload chunk was rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UploadError {
+    /// The chunk does not start where the received bytes end; resume from
+    /// `received`.
+    OutOfOrder { received: u64 },
+    /// The chunk runs past the declared total, or the total changed.
+    InvalidRange,
+}
+
+/// What an upload is, sent with every chunk.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct UploadMeta {
+    /// Key of the widget the upload belongs to.
+    #[serde(default)]
+    pub key: String,
+    #[serde(default)]
+    pub file_name: String,
+    #[serde(default)]
+    pub mime_type: String,
+}
+
+struct Upload {
+    meta: UploadMeta,
+    total: u64,
+    data: Vec<u8>,
+    url: Option<String>,
+}
+
+impl Upload {
+    fn progress(&self, id: &str) -> TransferProgress {
+        TransferProgress {
+            id: id.to_string(),
+            key: self.meta.key.clone(),
+            direction: TransferDirection::Upload,
+            // The data moved to the media store once complete
+            loaded: if self.url.is_some() { self.total } else { self.data.len() as u64 },
+            total: Some(self.total),
+            url: self.url.clone(),
+        }
+    }
+}
+
+/// Chunked uploads in progress, shared by the HTTP handler and the
+/// WebSocket sessions reporting their progress.
+pub struct TransferStore {
+    uploads: Arc<DashMap<String, Upload>>,
+    // Bumped on every chunk; receivers never miss a change
+    version: Arc<watch::Sender<u64>>,
+}
+
+impl TransferStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        TransferStore {
+            uploads: Arc::new(DashMap::new()),
+            version: Arc::new(watch::Sender::new(0)),
+        }
+    }
+
+    /// Append a chunk to an upload, starting it on the first chunk.
+    ///
+    /// Once every byte arrived the file moves to `media` and the progress
+    /// carries its URL.
+    pub fn append(
+        &self,
+        id: &str,
+        meta: &UploadMeta,
+        range: ChunkRange,
+        chunk: &[u8],
+        media: &MediaStore,
+    ) -> Result<TransferProgress, UploadError> {
+        let mut upload = self.uploads.entry(id.to_string()).or_insert_with(|| Upload {
+            meta: meta.clone(),
+            total: range.total,
+            data: Vec::new(),
+            url: None,
+        });
+
+        let received = upload.data.len() as u64;
+        if range.total != upload.total || range.start + chunk.len() as u64 > range.total {
+            return Err(UploadError::InvalidRange);
+        }
+        if range.start != received {
+            return Err(UploadError::OutOfOrder { received });
+        }
+
+        upload.data.extend_from_slice(chunk);
+        if upload.data.len() as u64 == upload.total && upload.url.is_none() {
+            let data = std::mem::take(&mut upload.data);
+            let file = MediaFile::new(upload.meta.file_name.clone(), upload.meta.mime_type.clone(), data);
+            upload.url = Some(MediaStore::url_for(&media.add(file)));
+        }
+
+        let progress = upload.progress(id);
+        drop(upload);
+        self.version.send_modify(|version| *version += 1);
+        Ok(progress)
+    }
+
+    /// Get an upload's progress.
+    pub fn progress(&self, id: &str) -> Option<TransferProgress> {
+        self.uploads.get(id).map(|upload| upload.progress(id))
+    }
+
+    /// Forget an upload.
+    pub fn remove(&self, id: &str) {
+        self.uploads.remove(id);
+    }
+
+    /// Subscribe to changes; the receiver is notified whenever a chunk is
+    /// appended to any upload.
+    pub fn subscribe(&self) -> watch::Receiver<u64> {
+        self.version.subscribe()
+    }
+
+    /// Get the number of uploads.
+    pub fn len(&self) -> usize {
+        self.uploads.len()
+    }
+
+    /// Check if there are no uploads.
+    pub fn is_empty(&self) -> bool {
+        self.uploads.is_empty()
+    }
+}
+
+impl Default for TransferStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for TransferStore {
+    fn clone(&self) -> Self {
+        TransferStore {
+            uploads: Arc::clone(&self.uploads),
+            version: Arc::clone(&self.version),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunked_upload_resumes() {
+        let store = TransferStore::new();
+        let media = MediaStore::new();
+        let meta = UploadMeta {
+            key: "files".to_string(),
+            file_name: "a.txt".to_string(),
+            mime_type: "text/plain".to_string(),
+        };
+        let append = |start, chunk: &[u8]| store.append("u1", &meta, ChunkRange { start, total: 6 }, chunk, &media);
+
+        let progress = append(0, b"abc").unwrap();
+        assert_eq!(progress.fraction(), Some(0.5));
+        // A retried chunk that skips ahead is told where to resume
+        assert_eq!(append(4, b"ef"), Err(UploadError::OutOfOrder { received: 3 }));
+
+        let progress = append(3, b"def").unwrap();
+        assert!(progress.is_complete());
+        let url = progress.url.unwrap();
+        let file = media.get(url.trim_start_matches("/media/")).unwrap();
+        assert_eq!(file.data, b"abcdef");
+    }
+}
@@ -0,0 +1,182 @@
+//! Visual regression checks of rendered pages.
+//!
+//! A named app state is rendered to PNG and HTML with
+//! [`render`](crate::render) and compared to the baseline image stored
+//! under its name. A page differing in more than the threshold's share of
+//! pixels is a regression, and its render is written next to the baseline
+//! for review. Checks run under `platypus test --visual`, which sets the
+//! environment variables below.
+
+use crate::render;
+use platypus_core::element::ElementType;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Set to enable visual checks.
+pub const VISUAL_ENV: &str = "PLATYPUS_VISUAL";
+/// Directory of the baselines.
+pub const BASELINE_DIR_ENV: &str = "PLATYPUS_VISUAL_BASELINES";
+/// Set to record every render as the new baseline.
+pub const UPDATE_ENV: &str = "PLATYPUS_VISUAL_UPDATE";
+/// Largest share of differing pixels that still matches.
+pub const THRESHOLD_ENV: &str = "PLATYPUS_VISUAL_THRESHOLD";
+
+/// Default directory of the baselines.
+pub const DEFAULT_BASELINE_DIR: &str = "tests/visual";
+/// Default threshold. Renders are deterministic, so any difference is a
+/// change to the page.
+pub const DEFAULT_THRESHOLD: f64 = 0.0;
+
+/// Where baselines live and how strictly renders must match them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VisualCheck {
+    pub baseline_dir: PathBuf,
+    pub threshold: f64,
+    /// Record renders as baselines instead of comparing.
+    pub update: bool,
+}
+
+/// Result of comparing a render to its baseline.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VisualOutcome {
+    /// Within the threshold of the baseline.
+    Matched { difference: f64 },
+    /// The baseline was missing or being updated, and was written.
+    Recorded,
+    /// Differs from the baseline by more than the threshold; the render
+    /// was written to `actual`.
+    Regressed { difference: f64, actual: PathBuf },
+}
+
+impl VisualCheck {
+    /// Compare against the baselines in `baseline_dir`.
+    pub fn new(baseline_dir: impl Into<PathBuf>) -> Self {
+        VisualCheck {
+            baseline_dir: baseline_dir.into(),
+            threshold: DEFAULT_THRESHOLD,
+            update: false,
+        }
+    }
+
+    /// Read the check from the environment; `None` unless visual checks
+    /// are enabled.
+    pub fn from_env() -> Option<Self> {
+        std::env::var_os(VISUAL_ENV)?;
+        let baseline_dir = std::env::var_os(BASELINE_DIR_ENV).map_or_else(|| PathBuf::from(DEFAULT_BASELINE_DIR), PathBuf::from);
+        let threshold = std::env::var(THRESHOLD_ENV)
+            .ok()
+            .and_then(|threshold| threshold.parse().ok())
+            .unwrap_or(DEFAULT_THRESHOLD);
+        Some(VisualCheck {
+            baseline_dir,
+            threshold,
+            update: std::env::var_os(UPDATE_ENV).is_some(),
+        })
+    }
+
+    /// Render `elements` and compare them to the baseline `name`.
+    pub fn compare(&self, name: &str, elements: &[ElementType]) -> io::Result<VisualOutcome> {
+        let png = render::render_png(elements);
+        let html = render::render_html(name, elements);
+        let baseline = self.path(name, "png");
+        let actual = self.path(&format!("{}.actual", name), "png");
+
+        let difference = match fs::read(&baseline) {
+            Ok(stored) if !self.update => pixel_difference(&stored, &png),
+            Ok(_) => None,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e),
+        };
+        let Some(difference) = difference else {
+            fs::create_dir_all(&self.baseline_dir)?;
+            fs::write(&baseline, &png)?;
+            fs::write(self.path(name, "html"), &html)?;
+            remove_if_present(&actual)?;
+            return Ok(VisualOutcome::Recorded);
+        };
+
+        if difference <= self.threshold {
+            remove_if_present(&actual)?;
+            Ok(VisualOutcome::Matched { difference })
+        } else {
+            fs::write(&actual, &png)?;
+            fs::write(self.path(&format!("{}.actual", name), "html"), &html)?;
+            Ok(VisualOutcome::Regressed { difference, actual })
+        }
+    }
+
+    fn path(&self, name: &str, extension: &str) -> PathBuf {
+        self.baseline_dir.join(format!("{}.{}", name, extension))
+    }
+}
+
+fn remove_if_present(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Share of pixels that differ between two rendered PNGs, counting rows
+/// only one image has as different. `None` if the baseline is not a
+/// render, e.g. one edited in an image tool.
+pub fn pixel_difference(baseline: &[u8], render: &[u8]) -> Option<f64> {
+    let (width, baseline) = decode_png(baseline)?;
+    let (render_width, render) = decode_png(render)?;
+    if width != render_width {
+        return Some(1.0);
+    }
+    let total = baseline.len().max(render.len());
+    if total == 0 {
+        return Some(0.0);
+    }
+    let same = baseline.iter().zip(&render).filter(|(a, b)| a == b).count();
+    Some((total - same) as f64 / total as f64)
+}
+
+/// Decode an 8-bit grayscale PNG, as written by [`render::render_png`].
+/// Returns the width and pixels.
+fn decode_png(png: &[u8]) -> Option<(usize, Vec<u8>)> {
+    let mut reader = png::Decoder::new(png).read_info().ok()?;
+    let info = reader.info();
+    if info.color_type != png::ColorType::Grayscale || info.bit_depth != png::BitDepth::Eight {
+        return None;
+    }
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut pixels).ok()?;
+    pixels.truncate(frame.buffer_size());
+    Some((frame.width as usize, pixels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_against_baseline() {
+        let dir = std::env::temp_dir().join(format!("platypus-visual-{}", std::process::id()));
+        let text = |value: &str| vec![ElementType::Text { value: value.to_string() }];
+        let check = VisualCheck::new(&dir);
+
+        assert_eq!(check.compare("page", &text("Total: 10")).unwrap(), VisualOutcome::Recorded);
+        assert!(dir.join("page.html").exists());
+        assert_eq!(check.compare("page", &text("Total: 10")).unwrap(), VisualOutcome::Matched { difference: 0.0 });
+        match check.compare("page", &text("Total: 99")).unwrap() {
+            VisualOutcome::Regressed { difference, actual } => {
+                assert!(difference > 0.0 && difference < 0.05);
+                assert!(actual.exists());
+            }
+            other => panic!("unexpected outcome: {:?}", other),
+        }
+        let lenient = VisualCheck { threshold: 0.01, ..check.clone() };
+        assert!(matches!(lenient.compare("page", &text("Total: 99")).unwrap(), VisualOutcome::Matched { .. }));
+
+        // Accepting the change records it and clears the review files
+        let update = VisualCheck { update: true, ..check.clone() };
+        assert_eq!(update.compare("page", &text("Total: 99")).unwrap(), VisualOutcome::Recorded);
+        assert!(!dir.join("page.actual.png").exists());
+        assert!(pixel_difference(b"not a png", &render::render_png(&text("x"))).is_none());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
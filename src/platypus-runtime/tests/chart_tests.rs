@@ -1,7 +1,17 @@
 //! Chart functionality tests
 //! Tests for all chart types: line, bar, area, scatter, pie, plotly, vega-lite
 
+use platypus_core::element::{ElementId, ElementType};
+use platypus_core::elements::ChartKind;
+use platypus_core::state::Delta;
+use platypus_core::widget::WidgetValue;
 use platypus_runtime::prelude::*;
+use serde_json::{json, Value};
+
+/// Get the element `id` was added as.
+fn element(st: &St, id: ElementId) -> ElementType {
+    st.delta_gen().get_element(id).unwrap().element_type().clone()
+}
 
 // ============================================================================
 // SIMPLE CHART TESTS
@@ -211,6 +221,126 @@ fn test_financial_dashboard() {
     assert!(deltas.len() >= 6);
 }
 
+#[test]
+fn test_candlestick_chart() {
+    let mut st = St::new();
+    let candles = vec![
+        Candle::new("2024-01-02", 100.0, 104.0, 99.0, 103.0).volume(1_200.0),
+        Candle::new("2024-01-03", 103.0, 105.0, 101.0, 102.0).volume(900.0),
+        Candle::new("2024-01-04", 102.0, 108.0, 102.0, 107.0).volume(1_500.0),
+    ];
+    let id = st.candlestick_chart_with(
+        &candles,
+        CandlestickOptions::new().title("ACME").moving_average(2).with_volume(),
+    );
+    
+    match element(&st, id) {
+        ElementType::CandlestickChart { data, title, key } => {
+            assert_eq!(title.as_deref(), Some("ACME"));
+            assert_eq!(key, None);
+            let data: Value = serde_json::from_str(&data).unwrap();
+            let first = json!({
+                "time": "2024-01-02", "open": 100.0, "high": 104.0, "low": 99.0, "close": 103.0, "volume": 1200.0
+            });
+            assert_eq!(data["candles"][0], first);
+            assert_eq!(data["candles"].as_array().unwrap().len(), 3);
+            assert_eq!(data["overlays"], json!([{"name": "MA 2", "values": [null, 102.5, 104.5]}]));
+            assert_eq!(data["volume"], json!(true));
+        }
+        other => panic!("unexpected element: {:?}", other),
+    }
+    assert_eq!(st.drain_deltas().len(), 1);
+}
+
+#[test]
+fn test_gauge_and_donut() {
+    let mut st = St::new();
+    let gauge = st.gauge(72.0, 0.0, 100.0, &[Threshold::new(85.0, Tone::Danger), Threshold::new(60.0, Tone::Warning)]);
+    let donut = st.donut(&[
+        DonutSegment::new("Stocks", 60.0),
+        DonutSegment::new("Bonds", 30.0).tone(Tone::Info),
+        DonutSegment::new("Cash", 0.0),
+    ]);
+
+    match element(&st, gauge) {
+        ElementType::Gauge { data } => assert_eq!(
+            serde_json::from_str::<Value>(&data).unwrap(),
+            json!({
+                "value": 72.0,
+                "min": 0.0,
+                "max": 100.0,
+                "tone": "warning",
+                "thresholds": [{"from": 60.0, "tone": "warning"}, {"from": 85.0, "tone": "danger"}]
+            })
+        ),
+        other => panic!("unexpected element: {:?}", other),
+    }
+    match element(&st, donut) {
+        ElementType::Donut { data } => assert_eq!(
+            serde_json::from_str::<Value>(&data).unwrap(),
+            json!({
+                "segments": [
+                    {"label": "Stocks", "value": 60.0, "tone": null},
+                    {"label": "Bonds", "value": 30.0, "tone": "info"}
+                ],
+                "total": 90.0
+            })
+        ),
+        other => panic!("unexpected element: {:?}", other),
+    }
+    assert_eq!(st.drain_deltas().len(), 2);
+}
+
+#[test]
+fn test_heatmap_and_corr_matrix() {
+    let mut st = St::new();
+    st.delta_gen().set_widget("traffic".to_string(), WidgetValue::String(r#"{"row": 1, "col": 0}"#.to_string()));
+    let matrix = vec![vec![1.0, 2.0], vec![3.0, 4.0]];
+    let cell = st.heatmap(&matrix, &["Mon", "Tue"], &["AM", "PM"], "traffic").unwrap();
+    assert_eq!((cell.x.as_str(), cell.y.as_str(), cell.value), ("Mon", "PM", Some(3.0)));
+    let returns = r#"[
+        {"acme": 1.0, "globex": 2.0},
+        {"acme": 2.0, "globex": 3.5},
+        {"acme": 3.0, "globex": 6.0}
+    ]"#;
+    assert!(st.corr_matrix(returns, "correlations").is_none());
+    assert!(st.corr_matrix("not json", "invalid").is_none());
+
+    let heatmaps: Vec<(String, Value)> = st
+        .drain_deltas()
+        .into_iter()
+        .map(|delta| match delta {
+            Delta::AddElement { element: ElementType::Heatmap { key, data, title: None }, .. } => {
+                (key, serde_json::from_str(&data).unwrap())
+            }
+            other => panic!("unexpected delta: {:?}", other),
+        })
+        .collect();
+    assert_eq!(heatmaps.len(), 2);
+    assert_eq!(heatmaps[0].0, "traffic");
+    assert_eq!(
+        heatmaps[0].1,
+        json!({
+            "x_labels": ["Mon", "Tue"],
+            "y_labels": ["AM", "PM"],
+            "values": [[1.0, 2.0], [3.0, 4.0]],
+            "scale": "viridis",
+            "min": 1.0,
+            "max": 4.0,
+            "show_values": false
+        })
+    );
+    let correlations = &heatmaps[1].1;
+    assert_eq!(heatmaps[1].0, "correlations");
+    assert_eq!(correlations["x_labels"], json!(["acme", "globex"]));
+    assert_eq!(correlations["scale"], json!("diverging"));
+    assert_eq!((correlations["min"].as_f64(), correlations["max"].as_f64()), (Some(-1.0), Some(1.0)));
+    assert_eq!(correlations["values"][0][0], json!(1.0));
+    let r = correlations["values"][0][1].as_f64().unwrap();
+    assert!((r - 0.9897).abs() < 1e-4, "correlation {}", r);
+    assert_eq!(correlations["values"][1][0], correlations["values"][0][1]);
+}
+
 // ============================================================================
 // MULTIPLE CHARTS TEST
 // ============================================================================
@@ -285,6 +415,48 @@ fn test_chart_with_filters() {
     assert!(deltas.len() >= 5);
 }
 
+#[test]
+fn test_cross_filtered_chart_and_table() {
+    let mut st = St::new();
+    let sales = r#"[
+        {"month": "Jan", "category": "Electronics", "sales": 5000},
+        {"month": "Feb", "category": "Clothing", "sales": 3000}
+    ]"#;
+    st.selectbox(
+        "Category",
+        vec!["All".to_string(), "Electronics".to_string(), "Clothing".to_string()],
+        0,
+        Some("category_key".to_string()),
+    );
+    st.delta_gen().set_widget("category_key".to_string(), WidgetValue::String("Clothing".to_string()));
+
+    let graph = FilterGraph::from_records(sales)
+        .unwrap()
+        .widget_source("category_key", "category")
+        .chart_source("sales_chart", "month");
+    let filters = graph.apply(&st);
+    assert!(st.chart(ChartKind::Bar, filters.records("sales_chart"), None, "sales_chart").is_none());
+    assert_eq!(filters.frame("sales_table").rows.len(), 1);
+    st.dataframe(filters.records("sales_table"));
+
+    let clothing = json!([{"month": "Feb", "category": "Clothing", "sales": 3000}]);
+    let deltas = st.drain_deltas();
+    assert_eq!(deltas.len(), 3);
+    match &deltas[1] {
+        Delta::AddElement { element: ElementType::BarChart { data, title: None, key }, .. } => {
+            assert_eq!(key.as_deref(), Some("sales_chart"));
+            assert_eq!(serde_json::from_str::<Value>(data).unwrap(), clothing);
+        }
+        other => panic!("unexpected delta: {:?}", other),
+    }
+    match &deltas[2] {
+        Delta::AddElement { element: ElementType::Dataframe { data }, .. } => {
+            assert_eq!(serde_json::from_str::<Value>(data).unwrap(), clothing);
+        }
+        other => panic!("unexpected delta: {:?}", other),
+    }
+}
+
 // ============================================================================
 // PERFORMANCE TEST
 // ============================================================================
@@ -297,7 +297,7 @@ fn test_streamlit_api_compatibility() {
     
     // Verify all elements were created
     let deltas = st.take_deltas();
-    assert!(deltas.len() > 0);
+    assert!(!deltas.is_empty());
 }
 
 #[test]
@@ -384,11 +384,11 @@ fn test_complex_app_workflow() {
     st.markdown("Please fill out the form below");
     
     // Form fields
-    let name = st.text_input("Full Name", "", Some("name".to_string()));
-    let email = st.text_input("Email", "", Some("email".to_string()));
-    let age = st.number_input("Age", 0.0, Some("age".to_string()));
-    let country = st.selectbox("Country", vec!["USA".to_string(), "UK".to_string(), "Canada".to_string()], 0, Some("country".to_string()));
-    let subscribe = st.checkbox("Subscribe to newsletter", false, Some("subscribe".to_string()));
+    let _name = st.text_input("Full Name", "", Some("name".to_string()));
+    let _email = st.text_input("Email", "", Some("email".to_string()));
+    let _age = st.number_input("Age", 0.0, Some("age".to_string()));
+    let _country = st.selectbox("Country", vec!["USA".to_string(), "UK".to_string(), "Canada".to_string()], 0, Some("country".to_string()));
+    let _subscribe = st.checkbox("Subscribe to newsletter", false, Some("subscribe".to_string()));
     
     // Simulate form submission
     if st.button("Submit", Some("submit".to_string())) {
@@ -419,7 +419,7 @@ fn test_tabs() {
     
     // Verify deltas were generated
     let deltas = st.take_deltas();
-    assert!(deltas.len() >= 1); // At least the tabs container
+    assert!(!deltas.is_empty()); // At least the tabs container
 }
 
 #[test]
@@ -434,7 +434,7 @@ fn test_expander() {
     
     // Verify deltas were generated
     let deltas = st.take_deltas();
-    assert!(deltas.len() >= 1); // At least the expander
+    assert!(!deltas.is_empty()); // At least the expander
 }
 
 #[test]
@@ -466,7 +466,7 @@ fn test_sidebar() {
     
     // Verify deltas were generated
     let deltas = st.take_deltas();
-    assert!(deltas.len() >= 1); // At least the sidebar
+    assert!(!deltas.is_empty()); // At least the sidebar
 }
 
 #[test]
@@ -502,7 +502,7 @@ fn test_advanced_layout_workflow() {
     
     // Verify all elements were created
     let deltas = st.take_deltas();
-    assert!(deltas.len() > 0);
+    assert!(!deltas.is_empty());
 }
 
 #[test]
@@ -562,10 +562,10 @@ fn test_form_with_date_time_color() {
     
     st.title("Event Form");
     
-    let event_name = st.text_input("Event name", "", Some("name".to_string()));
-    let event_date = st.date_input("Event date", "2025-01-01", Some("date".to_string()));
-    let event_time = st.time_input("Event time", "10:00", Some("time".to_string()));
-    let event_color = st.color_picker("Event color", "#0000FF", Some("color".to_string()));
+    let _event_name = st.text_input("Event name", "", Some("name".to_string()));
+    let _event_date = st.date_input("Event date", "2025-01-01", Some("date".to_string()));
+    let _event_time = st.time_input("Event time", "10:00", Some("time".to_string()));
+    let _event_color = st.color_picker("Event color", "#0000FF", Some("color".to_string()));
     
     if st.button("Create Event", Some("create".to_string())) {
         st.success("Event created!");
@@ -670,24 +670,24 @@ fn test_complete_form_workflow() {
     st.markdown("Please fill out all fields");
     
     // Personal info
-    let name = st.text_input("Full Name", "", Some("name".to_string()));
-    let email = st.text_input("Email", "", Some("email".to_string()));
-    let age = st.number_input("Age", 0.0, Some("age".to_string()));
+    let _name = st.text_input("Full Name", "", Some("name".to_string()));
+    let _email = st.text_input("Email", "", Some("email".to_string()));
+    let _age = st.number_input("Age", 0.0, Some("age".to_string()));
     
     // Preferences
-    let gender = st.radio("Gender", vec!["Male", "Female", "Other"], 0, Some("gender".to_string()));
-    let country = st.selectbox("Country", vec!["USA".to_string(), "UK".to_string(), "Canada".to_string()], 0, Some("country".to_string()));
+    let _gender = st.radio("Gender", vec!["Male", "Female", "Other"], 0, Some("gender".to_string()));
+    let _country = st.selectbox("Country", vec!["USA".to_string(), "UK".to_string(), "Canada".to_string()], 0, Some("country".to_string()));
     
     // Additional info
-    let subscribe = st.checkbox("Subscribe to newsletter", false, Some("subscribe".to_string()));
-    let comments = st.text_area("Comments", "", Some("comments".to_string()));
+    let _subscribe = st.checkbox("Subscribe to newsletter", false, Some("subscribe".to_string()));
+    let _comments = st.text_area("Comments", "", Some("comments".to_string()));
     
     // Date and time
-    let event_date = st.date_input("Event date", "2025-01-01", Some("date".to_string()));
-    let event_time = st.time_input("Event time", "10:00", Some("time".to_string()));
+    let _event_date = st.date_input("Event date", "2025-01-01", Some("date".to_string()));
+    let _event_time = st.time_input("Event time", "10:00", Some("time".to_string()));
     
     // File upload
-    let attachment = st.file_uploader("Upload attachment", Some("file".to_string()));
+    let _attachment = st.file_uploader("Upload attachment", Some("file".to_string()));
     
     // Submit
     if st.button("Submit Form", Some("submit".to_string())) {
@@ -105,7 +105,7 @@ fn test_message_compression_large_payload() {
     
     // In a real scenario, we'd compress these deltas
     // For now, just verify they're created
-    assert!(deltas.len() > 0);
+    assert!(!deltas.is_empty());
 }
 
 #[test]
@@ -288,8 +288,6 @@ fn test_session_state_across_reruns() {
 
 #[test]
 fn test_session_state_with_complex_data() {
-    let mut st = St::new();
-    
     let mut session: HashMap<String, serde_json::Value> = HashMap::new();
     
     let data = serde_json::json!({
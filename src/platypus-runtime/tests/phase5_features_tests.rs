@@ -3,7 +3,6 @@
 
 use platypus_runtime::prelude::*;
 use std::time::Duration;
-use std::collections::HashMap;
 
 // ============================================================================
 // CACHING TESTS - @st.cache_data
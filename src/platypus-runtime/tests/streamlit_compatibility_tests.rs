@@ -429,8 +429,9 @@ fn test_streamlit_state_persistence() {
     let count2 = st.number_input("Counter", 5.0, Some("counter_key".to_string()));
     assert_eq!(count2, 5.0);
     
+    // Reusing a key within a run is reported as an error element
     let deltas = st.take_deltas();
-    assert_eq!(deltas.len(), 2);
+    assert_eq!(deltas.len(), 3);
 }
 
 #[test]
@@ -457,7 +458,7 @@ fn test_streamlit_loop_rendering() {
     
     st.title("Item List");
     
-    let items = vec!["Item 1", "Item 2", "Item 3", "Item 4", "Item 5"];
+    let items = ["Item 1", "Item 2", "Item 3", "Item 4", "Item 5"];
     
     for (i, item) in items.iter().enumerate() {
         st.write(format!("{}. {}", i + 1, item));
@@ -473,7 +474,7 @@ fn test_streamlit_loop_rendering() {
 
 #[test]
 fn test_streamlit_empty_app() {
-    let mut st = St::new();
+    let st = St::new();
     
     let deltas = st.take_deltas();
     assert_eq!(deltas.len(), 0);
@@ -527,10 +528,10 @@ fn test_streamlit_mixed_content_types() {
 fn test_streamlit_rapid_sequential_calls() {
     let mut st = St::new();
     
-    for _ in 0..50 {
+    for i in 0..50 {
         st.write("Quick");
-        st.button("Click", Some("btn_key".to_string()));
-        st.checkbox("Check", false, Some("check_key".to_string()));
+        st.button("Click", Some(format!("btn_key_{}", i)));
+        st.checkbox("Check", false, Some(format!("check_key_{}", i)));
     }
     
     let deltas = st.take_deltas();
@@ -0,0 +1,132 @@
+//! Benchmarks of the paths every rerun takes: building deltas, converting
+//! them to proto and JSON, and serializing them for the websocket.
+//!
+//! Run with `platypus bench`, or `cargo bench -p platypus-server`.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use platypus_core::element::{ElementId, ElementType};
+use platypus_core::state::{Delta, DeltaGenerator};
+use platypus_runtime::St;
+use platypus_server::message;
+use platypus_server::CompressionConfig;
+
+/// Page sizes, in elements.
+const SIZES: [usize; 3] = [100, 1_000, 10_000];
+
+/// A page of about `size` elements: sections of columns holding text,
+/// metrics, widgets and a small table.
+fn page(size: usize) -> Vec<Delta> {
+    let mut st = St::new();
+    st.title("Dashboard");
+    for section in 0..size / 10 {
+        let section_key = format!("s{}", section);
+        st.with_columns(2, |columns| {
+            let [left, right] = columns else {
+                return;
+            };
+            left.header(format!("Section {}", section));
+            left.write("Lorem ipsum dolor sit amet, consectetur adipiscing elit.");
+            left.metric("Revenue", format!("${}", section * 1_000), Some("+4%".to_string()));
+            left.text_input("Filter", "", Some(format!("{}_filter", section_key)));
+            right.slider("Threshold", 0.0, 100.0, 50.0, Some(format!("{}_slider", section_key)));
+            right.checkbox("Enabled", true, Some(format!("{}_enabled", section_key)));
+            right.table(
+                vec!["Name".to_string(), "Value".to_string()],
+                (0..3).map(|row| vec![format!("Row {}", row), row.to_string()]).collect(),
+            );
+        });
+    }
+    st.take_deltas()
+}
+
+fn elements(deltas: &[Delta]) -> Vec<ElementType> {
+    deltas
+        .iter()
+        .filter_map(|delta| match delta {
+            Delta::AddElement { element, .. } => Some(element.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn delta_generator(c: &mut Criterion) {
+    let mut group = c.benchmark_group("delta_generator/add_take");
+    for size in SIZES {
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            b.iter(|| {
+                let delta_gen = DeltaGenerator::new();
+                let mut parent = None;
+                for index in 0..size {
+                    if index % 10 == 0 {
+                        parent = Some(delta_gen.add_element(ElementType::Container { children: vec![], hidden: false }, None));
+                    } else {
+                        delta_gen.add_element(ElementType::Text { value: format!("Item {}", index) }, parent);
+                    }
+                }
+                black_box(delta_gen.take_deltas())
+            });
+        });
+    }
+    group.finish();
+}
+
+fn element_type_to_proto(c: &mut Criterion) {
+    let mut group = c.benchmark_group("element_type_to_proto");
+    for size in SIZES {
+        let elements = elements(&page(size));
+        group.throughput(Throughput::Elements(elements.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &elements, |b, elements| {
+            b.iter(|| {
+                for (id, element) in elements.iter().enumerate() {
+                    black_box(message::element_type_to_proto(ElementId::new(id as u64), element));
+                }
+            });
+        });
+    }
+    group.finish();
+}
+
+fn deltas_to_json(c: &mut Criterion) {
+    let mut group = c.benchmark_group("deltas_to_json");
+    for size in SIZES {
+        let deltas = page(size);
+        group.throughput(Throughput::Elements(deltas.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &deltas, |b, deltas| {
+            b.iter_batched(|| deltas.clone(), message::deltas_to_json, BatchSize::LargeInput);
+        });
+    }
+    group.finish();
+}
+
+/// What the websocket sends for a run: numbered JSON, compressed when
+/// large, or encoded proto.
+fn ws_serialization(c: &mut Criterion) {
+    let compression = CompressionConfig::default();
+    let mut group = c.benchmark_group("ws_serialization");
+    for size in SIZES {
+        let deltas = page(size);
+        group.throughput(Throughput::Elements(deltas.len() as u64));
+        group.bench_with_input(BenchmarkId::new("json", size), &deltas, |b, deltas| {
+            b.iter_batched(
+                || deltas.clone(),
+                |deltas| {
+                    let json = serde_json::to_string(&message::reconciled_deltas_to_json(deltas, 1)).unwrap();
+                    compression.compress(json.as_bytes()).unwrap_or_else(|| json.into_bytes())
+                },
+                BatchSize::LargeInput,
+            );
+        });
+        group.bench_with_input(BenchmarkId::new("proto", size), &deltas, |b, deltas| {
+            b.iter_batched(
+                || deltas.clone(),
+                |deltas| message::serialize_forward_msg(&message::create_reconciled_delta_msg(deltas, 1)).unwrap(),
+                BatchSize::LargeInput,
+            );
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, delta_generator, element_type_to_proto, deltas_to_json, ws_serialization);
+criterion_main!(benches);
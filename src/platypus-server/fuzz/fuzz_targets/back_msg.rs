@@ -0,0 +1,10 @@
+//! Arbitrary bytes as a binary websocket message.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use platypus_server_fuzz::Session;
+
+fuzz_target!(|data: &[u8]| {
+    Session::new().handle_binary(data);
+});
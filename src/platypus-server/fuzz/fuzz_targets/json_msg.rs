@@ -0,0 +1,16 @@
+//! Arbitrary text as JSON websocket messages, one per line.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use platypus_server_fuzz::Session;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    let mut session = Session::new();
+    for line in text.lines() {
+        session.handle_text(line);
+    }
+});
@@ -0,0 +1,114 @@
+//! Shared harness of the fuzz targets.
+//!
+//! Messages are decoded the way the websocket handler decodes them, and
+//! their events are handled by an executor whose app reads every kind of
+//! widget, so fuzzed values reach the widgets' parsers too. A session sees
+//! all messages of an input, e.g. a client context before a date. Run with
+//! `cargo fuzz run back_msg` or `cargo fuzz run json_msg -- -dict=json_msg.dict`
+//! from this directory.
+
+use chrono::{NaiveDate, TimeZone, Utc};
+use platypus_core::element::FeedbackKind;
+use platypus_core::session::SessionId;
+use platypus_runtime::{Aggregation, NumberFormat, SessionStore, St};
+use platypus_server::executor::ScriptExecutor;
+use platypus_server::message;
+use platypus_server::sequence::Sequencer;
+use std::sync::Arc;
+
+const RECORDS: &str = r#"[{"region":"north","product":"a","sales":10,"cost":4},{"region":"south","product":"b","sales":7,"cost":5}]"#;
+
+fn app(st: &mut St) -> Result<(), String> {
+    let key = |key: &str| Some(key.to_string());
+    st.page();
+    st.transfer_progress("upload");
+    st.chart_selection("chart");
+    st.button("Button", key("button"));
+    st.text_input("Text", "", key("text"));
+    st.text_area("Area", "", key("area"));
+    st.number_input("Number", 1.0, key("number"));
+    st.number_input_with_format("Price", 1.0, &NumberFormat::currency("usd"), key("price"));
+    st.slider("Slider", 0.0, 10.0, 5.0, key("slider"));
+    st.checkbox("Checkbox", false, key("checkbox"));
+    st.selectbox("Select", vec!["a".to_string(), "b".to_string()], 0, key("select"));
+    st.multiselect("Multi", vec!["a".to_string(), "b".to_string()], vec![], key("multi"));
+    st.radio("Radio", vec!["a", "b"], 0, key("radio"));
+    st.feedback(FeedbackKind::Stars, key("feedback"));
+    st.rating(5, key("rating"));
+    st.tags_input_with_options("Tags", vec!["a", "b"], Some(2), false, key("tags"));
+    st.code_editor("Code", "rust", key("code"));
+    st.json_editor(serde_json::json!({"a": 1}), None, key("json"));
+    st.rich_text_with_limit("Rich", 64, key("rich"));
+    st.date_input("Date", "2024-01-01", key("date"));
+    let (start, end) = (NaiveDate::from_ymd_opt(2024, 1, 1), NaiveDate::from_ymd_opt(2024, 12, 31));
+    if let (Some(start), Some(end)) = (start, end) {
+        st.date_range_input("Range", (start, end), Some(start), Some(end), key("range"));
+    }
+    st.datetime_input("When", Utc.timestamp_opt(0, 0).unwrap(), None, None, key("when"));
+    st.time_input("Time", "12:00", key("time"));
+    st.color_picker("Color", "#000000", key("color"));
+    st.file_uploader("File", key("file"));
+    st.camera_input("Camera", key("camera"));
+    st.pivot_table(RECORDS, &["region"], &["product"], "sales", Aggregation::Sum, "pivot");
+    st.corr_matrix(RECORDS, "heatmap");
+    st.notification_center();
+    Ok(())
+}
+
+/// A fresh session of the app, receiving fuzzed messages.
+pub struct Session {
+    executor: ScriptExecutor,
+    session_id: SessionId,
+    sequencer: Sequencer,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        let session_store = Arc::new(SessionStore::new());
+        let session_id = session_store.create_session("fuzz".to_string());
+        Session {
+            executor: ScriptExecutor::with_app(session_store, app),
+            session_id,
+            sequencer: Sequencer::new(),
+        }
+    }
+
+    /// Handle a binary websocket message.
+    pub fn handle_binary(&mut self, data: &[u8]) {
+        let Ok(back_msg) = message::deserialize_back_msg(data) else {
+            return;
+        };
+        self.sequencer.ack(back_msg.ack);
+        if message::back_msg_is_resync(&back_msg) {
+            self.executor.snapshot();
+            return;
+        }
+        if let Some(event) = message::event_from_back_msg(back_msg) {
+            let _ = self.executor.handle_event(self.session_id, event);
+        }
+    }
+
+    /// Handle a text websocket message.
+    pub fn handle_text(&mut self, text: &str) {
+        let Some(msg) = message::parse_json_msg(text) else {
+            return;
+        };
+        message::json_seq(&msg);
+        if let Some(ack) = message::json_ack(&msg) {
+            self.sequencer.ack(ack);
+        }
+        if message::json_is_resync(&msg) {
+            self.executor.snapshot();
+            return;
+        }
+        if let Some(event) = message::event_from_json(&msg) {
+            let _ = self.executor.handle_event(self.session_id, event);
+        }
+    }
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,338 @@
+//! Building and checking server configs.
+//!
+//! [`ServerConfig::builder`] starts from the defaults, so only the settings
+//! that differ need setting, and environment variables can override them
+//! for deployments:
+//!
+//! ```no_run
+//! use platypus_server::ServerConfig;
+//!
+//! # fn main() -> platypus_server::Result<()> {
+//! let config = ServerConfig::builder()
+//!     .app_name("Sales Dashboard")
+//!     .port(8080)
+//!     .from_env()?
+//!     .build()?;
+//! for warning in config.warn_on_insecure() {
+//!     eprintln!("warning: {}", warning);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! `build` checks the config, as `AppServer::run` does for configs
+//! created otherwise.
+
+use std::net::IpAddr;
+use std::time::Duration;
+
+use crate::cluster::ClusterConfig;
+use crate::compression::CompressionConfig;
+use crate::embed::EmbedConfig;
+use crate::error::{Error, Result};
+use crate::firewall::FirewallConfig;
+use crate::limits::RequestLimits;
+use crate::listener::ListenAddr;
+use crate::media_gc::MediaRetention;
+use crate::server::ServerConfig;
+
+/// Environment variable overriding the app name.
+pub const APP_NAME_ENV: &str = "PLATYPUS_APP_NAME";
+/// Environment variable overriding the host.
+pub const HOST_ENV: &str = "PLATYPUS_HOST";
+/// Environment variable overriding the port.
+pub const PORT_ENV: &str = "PLATYPUS_PORT";
+/// Environment variable listing the addresses to listen on, comma-separated.
+pub const LISTEN_ENV: &str = "PLATYPUS_LISTEN";
+/// Environment variable overriding the maximum body size (bytes).
+pub const MAX_BODY_SIZE_ENV: &str = "PLATYPUS_MAX_BODY_SIZE";
+/// Environment variable overriding the session timeout (seconds).
+pub const SESSION_TIMEOUT_ENV: &str = "PLATYPUS_SESSION_TIMEOUT";
+/// Environment variable overriding the run timeout (seconds).
+pub const RUN_TIMEOUT_ENV: &str = "PLATYPUS_RUN_TIMEOUT";
+/// Environment variable setting the admin token.
+pub const ADMIN_TOKEN_ENV: &str = "PLATYPUS_ADMIN_TOKEN";
+/// Environment variable overriding the cluster node ID.
+pub const NODE_ID_ENV: &str = "PLATYPUS_NODE_ID";
+
+/// Admin tokens shorter than this are reported as insecure.
+const MIN_ADMIN_TOKEN_LEN: usize = 16;
+
+/// Builds a [`ServerConfig`] from the defaults.
+#[derive(Debug, Clone, Default)]
+pub struct ServerConfigBuilder {
+    config: ServerConfig,
+}
+
+impl ServerConfig {
+    /// Start building a config from the defaults.
+    pub fn builder() -> ServerConfigBuilder {
+        ServerConfigBuilder::default()
+    }
+
+    /// Check the config, reporting every problem found.
+    pub fn validate(&self) -> Result<()> {
+        let mut problems = Vec::new();
+        // host and port are unused when listening elsewhere
+        if self.listen.is_empty() && self.port == 0 {
+            problems.push("port must be 1-65535".to_string());
+        }
+        if self.listen.is_empty() && self.host.parse::<IpAddr>().is_err() {
+            problems.push(format!("host {:?} is not an IP address", self.host));
+        }
+        if self.max_body_size == 0 {
+            problems.push("max_body_size must not be 0".to_string());
+        }
+        if self.session_timeout > 0 && self.idle_warning >= self.session_timeout {
+            problems.push(format!(
+                "idle_warning ({}s) must be shorter than session_timeout ({}s)",
+                self.idle_warning, self.session_timeout
+            ));
+        }
+        if self.run_timeout == Some(0) {
+            problems.push("run_timeout must not be 0; leave it unset for no limit".to_string());
+        }
+        if self.limits.upload_chunk == 0 {
+            problems.push("limits.upload_chunk must not be 0".to_string());
+        }
+        if self.limits.back_msg > self.limits.websocket_message {
+            problems.push("limits.back_msg must not exceed limits.websocket_message".to_string());
+        }
+        if self.compression.level > 10 {
+            problems.push("compression.level must be 0-10".to_string());
+        }
+        if self.media_retention.interval == 0 {
+            problems.push("media_retention.interval must not be 0".to_string());
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::config(problems.join("; ")))
+        }
+    }
+
+    /// Report settings that expose the server more than they may mean to.
+    pub fn warn_on_insecure(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let public: Vec<_> = self
+            .listen_addrs()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|addr| matches!(addr, ListenAddr::Tcp(addr) if !addr.ip().is_loopback()))
+            .collect();
+        if let Some(addr) = public.first().filter(|_| self.firewall.allow.is_empty()) {
+            warnings.push(format!(
+                "Listening on {} without an IP allow list; any client that can reach it can connect",
+                addr
+            ));
+        }
+        if let Some(token) = self.admin_token.as_deref().filter(|token| token.len() < MIN_ADMIN_TOKEN_LEN) {
+            warnings.push(format!(
+                "Admin token is {} characters; use at least {}",
+                token.chars().count(),
+                MIN_ADMIN_TOKEN_LEN
+            ));
+        }
+        if self.embed.frame_ancestors.iter().any(|source| source == "*") {
+            warnings.push("Any site may frame the app's fragments (embed.frame_ancestors has \"*\")".to_string());
+        }
+        if self.embed.host_origins.iter().any(|origin| origin == "*") {
+            warnings.push("Any host page may message embedded apps (embed.host_origins has \"*\")".to_string());
+        }
+        if !public.is_empty() && self.check_determinism {
+            warnings.push("Determinism checks run every app twice; meant for development".to_string());
+        }
+        warnings
+    }
+}
+
+impl ServerConfigBuilder {
+    /// Set the application name.
+    pub fn app_name(mut self, app_name: impl Into<String>) -> Self {
+        self.config.app_name = app_name.into();
+        self
+    }
+
+    /// Set the host to bind to.
+    pub fn host(mut self, host: impl Into<String>) -> Self {
+        self.config.host = host.into();
+        self
+    }
+
+    /// Set the port to listen on.
+    pub fn port(mut self, port: u16) -> Self {
+        self.config.port = port;
+        self
+    }
+
+    /// Listen on `addr` too, instead of `host:port`.
+    pub fn listen(mut self, addr: ListenAddr) -> Self {
+        self.config.listen.push(addr);
+        self
+    }
+
+    /// Set the maximum body size (bytes).
+    pub fn max_body_size(mut self, max_body_size: u64) -> Self {
+        self.config.max_body_size = max_body_size;
+        self
+    }
+
+    /// Set how long idle sessions last, `Duration::ZERO` for ever.
+    pub fn session_timeout(mut self, timeout: Duration) -> Self {
+        self.config.session_timeout = timeout.as_secs();
+        self
+    }
+
+    /// Set how long before expiring idle sessions are warned.
+    pub fn idle_warning(mut self, warning: Duration) -> Self {
+        self.config.idle_warning = warning.as_secs();
+        self
+    }
+
+    /// Set the size limits of uploads and WebSocket messages.
+    pub fn limits(mut self, limits: RequestLimits) -> Self {
+        self.config.limits = limits;
+        self
+    }
+
+    /// Set the WebSocket message compression.
+    pub fn compression(mut self, compression: CompressionConfig) -> Self {
+        self.config.compression = compression;
+        self
+    }
+
+    /// Set the media garbage collection.
+    pub fn media_retention(mut self, media_retention: MediaRetention) -> Self {
+        self.config.media_retention = media_retention;
+        self
+    }
+
+    /// Set the bearer token enabling the admin endpoints.
+    pub fn admin_token(mut self, token: impl Into<String>) -> Self {
+        self.config.admin_token = Some(token.into());
+        self
+    }
+
+    /// Warn about elements that differ between identical reruns.
+    pub fn check_determinism(mut self, check: bool) -> Self {
+        self.config.check_determinism = check;
+        self
+    }
+
+    /// Fail runs in which an element failed to render.
+    pub fn fatal_element_errors(mut self, fatal: bool) -> Self {
+        self.config.fatal_element_errors = fatal;
+        self
+    }
+
+    /// Set the longest a run may take.
+    pub fn run_timeout(mut self, timeout: Duration) -> Self {
+        self.config.run_timeout = Some(timeout.as_secs());
+        self
+    }
+
+    /// Set the sites allowed to embed the app's fragments.
+    pub fn embed(mut self, embed: EmbedConfig) -> Self {
+        self.config.embed = embed;
+        self
+    }
+
+    /// Set this node's identity in a cluster.
+    pub fn cluster(mut self, cluster: ClusterConfig) -> Self {
+        self.config.cluster = cluster;
+        self
+    }
+
+    /// Set the clients and requests the server refuses.
+    pub fn firewall(mut self, firewall: FirewallConfig) -> Self {
+        self.config.firewall = firewall;
+        self
+    }
+
+    /// Override settings with the `PLATYPUS_*` environment variables set.
+    pub fn from_env(self) -> Result<Self> {
+        self.with_overrides(|name| std::env::var(name).ok())
+    }
+
+    /// Override settings with the variables `lookup` finds.
+    fn with_overrides(mut self, lookup: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let number = |name: &str| -> Result<Option<u64>> {
+            lookup(name)
+                .map(|value| value.trim().parse().map_err(|_| Error::config(format!("{} must be a number, not {:?}", name, value))))
+                .transpose()
+        };
+        if let Some(app_name) = lookup(APP_NAME_ENV) {
+            self.config.app_name = app_name;
+        }
+        if let Some(host) = lookup(HOST_ENV) {
+            self.config.host = host;
+        }
+        if let Some(port) = number(PORT_ENV)? {
+            self.config.port = u16::try_from(port).map_err(|_| Error::config(format!("{} must be 1-65535", PORT_ENV)))?;
+        }
+        if let Some(listen) = lookup(LISTEN_ENV) {
+            self.config.listen = listen
+                .split(',')
+                .map(|addr| addr.trim().parse().map_err(|e| Error::config(format!("{}: {}", LISTEN_ENV, e))))
+                .collect::<Result<_>>()?;
+        }
+        if let Some(max_body_size) = number(MAX_BODY_SIZE_ENV)? {
+            self.config.max_body_size = max_body_size;
+        }
+        if let Some(timeout) = number(SESSION_TIMEOUT_ENV)? {
+            self.config.session_timeout = timeout;
+        }
+        if let Some(timeout) = number(RUN_TIMEOUT_ENV)? {
+            self.config.run_timeout = Some(timeout);
+        }
+        if let Some(token) = lookup(ADMIN_TOKEN_ENV) {
+            self.config.admin_token = Some(token);
+        }
+        if let Some(node_id) = lookup(NODE_ID_ENV) {
+            self.config.cluster.node_id = node_id;
+        }
+        Ok(self)
+    }
+
+    /// Check the config and build it.
+    pub fn build(self) -> Result<ServerConfig> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_env_overrides_and_validation() {
+        let env = HashMap::from([(PORT_ENV, "9000"), (ADMIN_TOKEN_ENV, "short"), (HOST_ENV, "0.0.0.0")]);
+        let config = ServerConfig::builder()
+            .port(8080)
+            .with_overrides(|name| env.get(name).map(|value| value.to_string()))
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(config.port, 9000);
+        assert_eq!(config.admin_token.as_deref(), Some("short"));
+        assert_eq!(config.warn_on_insecure().len(), 2);
+        assert!(ServerConfig::default().warn_on_insecure().is_empty());
+
+        let bad_port = ServerConfig::builder().with_overrides(|_| Some("70000".to_string()));
+        assert!(matches!(bad_port, Err(Error::Config(_))));
+
+        let invalid = ServerConfig::builder()
+            .host("localhost")
+            .port(0)
+            .session_timeout(Duration::from_secs(30))
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            invalid.to_string(),
+            "Invalid config: port must be 1-65535; host \"localhost\" is not an IP address; \
+             idle_warning (60s) must be shorter than session_timeout (30s)"
+        );
+    }
+}
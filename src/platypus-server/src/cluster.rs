@@ -0,0 +1,176 @@
+//! Cluster mode: one app served by several nodes behind a load balancer.
+//!
+//! Each node answers with an affinity cookie naming it, so a load balancer
+//! configured for cookie-based stickiness (e.g. HAProxy `cookie`, or nginx
+//! `sticky cookie`) keeps routing a browser to the node holding its
+//! session. Nodes given a [`SharedBackend`](platypus_runtime::SharedBackend) write sessions and cached data
+//! through to it, so another node takes a session over when its node goes
+//! down; topic messages fan out to every node. With the `redis` feature:
+//!
+//! ```no_run
+//! # #[cfg(feature = "redis")]
+//! # fn main() -> platypus_server::Result<()> {
+//! use platypus_server::AppServer;
+//!
+//! let server = AppServer::new().with_redis("redis://cache.internal:6379")?;
+//! # Ok(())
+//! # }
+//! # #[cfg(not(feature = "redis"))]
+//! # fn main() {}
+//! ```
+//!
+//! `GET /cluster/status` reports the node, its sessions and the peers it
+//! heard from.
+
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderMap, HeaderValue};
+use axum::middleware::Next;
+use axum::response::Response;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config;
+use crate::server::ServerState;
+
+/// Interval between the heartbeats nodes send their peers.
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Peers missing this many heartbeats are no longer reported.
+const MISSED_HEARTBEATS: u64 = 3;
+
+/// Identity of this node in the cluster.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClusterConfig {
+    /// Name of this node, random unless set.
+    pub node_id: String,
+    /// Cookie naming the node a browser's session lives on, for sticky
+    /// load balancing. No cookie is set when unset.
+    pub affinity_cookie: Option<String>,
+}
+
+impl Default for ClusterConfig {
+    fn default() -> Self {
+        ClusterConfig {
+            node_id: uuid::Uuid::new_v4().simple().to_string()[..8].to_string(),
+            affinity_cookie: Some(config::DEFAULT_AFFINITY_COOKIE.to_string()),
+        }
+    }
+}
+
+impl ClusterConfig {
+    /// Get the `Set-Cookie` value pinning a browser to this node, unless
+    /// the request already carries it.
+    pub fn affinity_header(&self, headers: &HeaderMap) -> Option<String> {
+        let cookie = self.affinity_cookie.as_deref()?;
+        let pinned = headers
+            .get_all(header::COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(';'))
+            .filter_map(|pair| pair.trim().split_once('='))
+            .any(|(name, value)| name == cookie && value == self.node_id);
+        (!pinned).then(|| format!("{}={}; Path=/; HttpOnly; SameSite=Lax", cookie, self.node_id))
+    }
+}
+
+/// A node heard from through the fan-out.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerStatus {
+    pub node_id: String,
+    /// Sessions on the node at its last heartbeat.
+    pub sessions: usize,
+    /// Time of its last heartbeat (Unix seconds).
+    pub last_seen: u64,
+}
+
+/// The other nodes of the cluster, by their heartbeats.
+#[derive(Clone, Default)]
+pub struct ClusterPeers(Arc<DashMap<String, PeerStatus>>);
+
+impl ClusterPeers {
+    /// Create an empty peer list.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a heartbeat.
+    pub fn record(&self, peer: PeerStatus) {
+        self.0.insert(peer.node_id.clone(), peer);
+    }
+
+    /// Get the peers that sent a recent heartbeat, forgetting the rest.
+    pub fn alive(&self) -> Vec<PeerStatus> {
+        let cutoff = unix_now().saturating_sub(HEARTBEAT_INTERVAL.as_secs() * MISSED_HEARTBEATS);
+        self.0.retain(|_, peer| peer.last_seen >= cutoff);
+        let mut peers: Vec<_> = self.0.iter().map(|peer| peer.clone()).collect();
+        peers.sort_by(|a, b| a.node_id.cmp(&b.node_id));
+        peers
+    }
+}
+
+/// State of a node, as reported by `/cluster/status`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ClusterStatus {
+    pub node_id: String,
+    pub sessions: usize,
+    pub uptime_secs: u64,
+    /// Name of the shared backend, if the node has one.
+    pub backend: Option<String>,
+    pub peers: Vec<PeerStatus>,
+}
+
+impl ClusterStatus {
+    /// Get the status of the node serving `state`.
+    pub fn of(state: &ServerState) -> Self {
+        ClusterStatus {
+            node_id: state.config.cluster.node_id.clone(),
+            sessions: state.session_store.session_count(),
+            uptime_secs: state.start_time.elapsed().as_secs(),
+            backend: state.shared_backend.as_ref().map(|backend| backend.name().to_string()),
+            peers: state.peers.alive(),
+        }
+    }
+}
+
+/// Set the affinity cookie on responses to browsers not pinned to this
+/// node yet.
+pub async fn sticky_sessions(State(state): State<Arc<ServerState>>, request: Request, next: Next) -> Response {
+    let cookie = state.config.cluster.affinity_header(request.headers());
+    let mut response = next.run(request).await;
+    if let Some(value) = cookie.and_then(|cookie| HeaderValue::from_str(&cookie).ok()) {
+        response.headers_mut().append(header::SET_COOKIE, value);
+    }
+    response
+}
+
+pub(crate) fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_affinity_header() {
+        let config = ClusterConfig {
+            node_id: "node-a".to_string(),
+            ..Default::default()
+        };
+        let mut headers = HeaderMap::new();
+        assert_eq!(
+            config.affinity_header(&headers).as_deref(),
+            Some("platypus_node=node-a; Path=/; HttpOnly; SameSite=Lax")
+        );
+        headers.insert(header::COOKIE, "theme=dark; platypus_node=node-a".parse().unwrap());
+        assert_eq!(config.affinity_header(&headers), None);
+        headers.insert(header::COOKIE, "platypus_node=node-b".parse().unwrap());
+        assert!(config.affinity_header(&headers).is_some());
+    }
+}
@@ -0,0 +1,176 @@
+//! Message compression for WebSocket connections.
+//!
+//! axum's WebSocket (tungstenite) implements no WebSocket extensions: it
+//! does not expose the RSV1 frame bit and declines the browser's
+//! `Sec-WebSocket-Extensions` offer, so RFC 7692 permessage-deflate cannot
+//! be negotiated at the frame level. Instead the client opts in when
+//! connecting (`/ws?compression=deflate-raw`) and the server sends each
+//! JSON message over the threshold as a binary frame of raw DEFLATE data,
+//! which the frontend inflates with `DecompressionStream`. Smaller messages
+//! stay text frames. Clients that don't ask keep receiving text frames.
+
+use crate::config;
+use dashmap::DashMap;
+use platypus_core::session::SessionId;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Value of the `compression` query parameter a client sends to opt in.
+pub const DEFLATE_RAW: &str = "deflate-raw";
+
+/// Compression settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompressionConfig {
+    /// Compress messages for clients that opt in.
+    pub enabled: bool,
+    /// Smallest message (bytes) worth compressing.
+    pub threshold: usize,
+    /// DEFLATE level, from 0 (stored) to 10 (smallest, slowest).
+    pub level: u8,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        CompressionConfig {
+            enabled: true,
+            threshold: config::DEFAULT_COMPRESSION_THRESHOLD,
+            level: config::DEFAULT_COMPRESSION_LEVEL,
+        }
+    }
+}
+
+impl CompressionConfig {
+    /// Check whether a client asking for `requested` gets compression.
+    pub fn negotiate(&self, requested: Option<&str>) -> bool {
+        self.enabled && requested == Some(DEFLATE_RAW)
+    }
+
+    /// Compress `data` if it reaches the threshold.
+    pub fn compress(&self, data: &[u8]) -> Option<Vec<u8>> {
+        (data.len() >= self.threshold).then(|| miniz_oxide::deflate::compress_to_vec(data, self.level))
+    }
+}
+
+/// Bytes sent to one session.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct CompressionStats {
+    /// Messages sent.
+    pub messages: u64,
+    /// Messages sent compressed.
+    pub compressed_messages: u64,
+    /// Message bytes before compression.
+    pub raw_bytes: u64,
+    /// Message bytes as sent.
+    pub wire_bytes: u64,
+}
+
+impl CompressionStats {
+    /// Record one message of `raw` bytes sent as `wire` bytes.
+    pub fn record(&mut self, raw: usize, wire: usize, compressed: bool) {
+        self.messages += 1;
+        self.compressed_messages += u64::from(compressed);
+        self.raw_bytes += raw as u64;
+        self.wire_bytes += wire as u64;
+    }
+
+    /// Get wire bytes as a fraction of raw bytes (1.0 when nothing was sent).
+    pub fn ratio(&self) -> f64 {
+        if self.raw_bytes == 0 {
+            1.0
+        } else {
+            self.wire_bytes as f64 / self.raw_bytes as f64
+        }
+    }
+}
+
+/// Per-session compression stats of the connected sessions.
+pub struct CompressionMetrics {
+    sessions: Arc<DashMap<SessionId, CompressionStats>>,
+}
+
+impl CompressionMetrics {
+    /// Create empty metrics.
+    pub fn new() -> Self {
+        CompressionMetrics {
+            sessions: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// Record a message sent to a session.
+    pub fn record(&self, session_id: SessionId, raw: usize, wire: usize, compressed: bool) {
+        self.sessions
+            .entry(session_id)
+            .or_default()
+            .record(raw, wire, compressed);
+    }
+
+    /// Get a session's stats.
+    pub fn get(&self, session_id: SessionId) -> Option<CompressionStats> {
+        self.sessions.get(&session_id).map(|stats| *stats)
+    }
+
+    /// Forget a session that disconnected.
+    pub fn remove(&self, session_id: SessionId) {
+        self.sessions.remove(&session_id);
+    }
+
+    /// Get the stats summed over all sessions.
+    pub fn total(&self) -> CompressionStats {
+        self.sessions
+            .iter()
+            .fold(CompressionStats::default(), |mut total, stats| {
+                total.messages += stats.messages;
+                total.compressed_messages += stats.compressed_messages;
+                total.raw_bytes += stats.raw_bytes;
+                total.wire_bytes += stats.wire_bytes;
+                total
+            })
+    }
+}
+
+impl Default for CompressionMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for CompressionMetrics {
+    fn clone(&self) -> Self {
+        CompressionMetrics {
+            sessions: Arc::clone(&self.sessions),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compress_is_raw_deflate() {
+        let config = CompressionConfig { threshold: 0, ..Default::default() };
+        let json = r#"{"type":"text","value":"hello"},"#.repeat(200);
+        for data in [&b""[..], b"ab", json.as_bytes()] {
+            let compressed = config.compress(data).unwrap();
+            assert_eq!(miniz_oxide::inflate::decompress_to_vec(&compressed).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn test_compress_threshold_and_ratio() {
+        let config = CompressionConfig { threshold: 64, ..Default::default() };
+        assert!(config.compress(b"short").is_none());
+
+        let data = r#"{"type":"text","value":"hello"},"#.repeat(100);
+        let compressed = config.compress(data.as_bytes()).unwrap();
+        assert!(compressed.len() * 10 < data.len());
+
+        let metrics = CompressionMetrics::new();
+        let session_id = SessionId::new();
+        metrics.record(session_id, data.len(), compressed.len(), true);
+        assert!(metrics.get(session_id).unwrap().ratio() < 0.1);
+        assert!(config.negotiate(Some(DEFLATE_RAW)));
+        assert!(!config.negotiate(None));
+    }
+}
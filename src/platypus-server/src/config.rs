@@ -17,15 +17,53 @@ pub const DEFAULT_PORT: u16 = 8501;
 /// Default maximum body size (100 MB)
 pub const DEFAULT_MAX_BODY_SIZE: u64 = 100 * 1024 * 1024;
 
+/// Default largest chunk of a resumable upload (8 MB)
+pub const DEFAULT_MAX_UPLOAD_CHUNK: usize = 8 * 1024 * 1024;
+
+/// Default largest message a session may send (16 MB), above the files
+/// the frontend sends inline
+pub const DEFAULT_MAX_BACK_MSG: usize = 16 * 1024 * 1024;
+
+/// Default largest WebSocket message (64 MB)
+pub const DEFAULT_MAX_WEBSOCKET_MESSAGE: usize = 64 * 1024 * 1024;
+
+/// Default largest total size of a request's headers (16 KB)
+pub const DEFAULT_MAX_HEADER_BYTES: usize = 16 * 1024;
+
 /// Default session timeout (1 hour in seconds)
 pub const DEFAULT_SESSION_TIMEOUT: u64 = 3600;
 
+/// Default warning before an idle session expires (1 minute in seconds)
+pub const DEFAULT_IDLE_WARNING: u64 = 60;
+
+/// Default smallest WebSocket message that gets compressed (bytes)
+pub const DEFAULT_COMPRESSION_THRESHOLD: usize = 1024;
+
+/// Default DEFLATE level, trading speed for size like zlib's default
+pub const DEFAULT_COMPRESSION_LEVEL: u8 = 6;
+
+/// Default time an unreferenced media file is kept (5 minutes in seconds),
+/// so a download started just before a rerun replaced its button completes
+pub const DEFAULT_MEDIA_GRACE: u64 = 300;
+
+/// Default longest time any media file is kept (1 day in seconds)
+pub const DEFAULT_MEDIA_TTL: u64 = 86400;
+
+/// Default interval between media garbage collections (seconds)
+pub const DEFAULT_MEDIA_GC_INTERVAL: u64 = 60;
+
 /// Default output directory for builds
 pub const DEFAULT_OUTPUT_DIR: &str = "dist";
 
 /// Default template for new projects
 pub const DEFAULT_TEMPLATE: &str = "basic";
 
+/// Default SQLite database of `platypus db` commands
+pub const DEFAULT_DATABASE: &str = "data.db";
+
+/// Default directory of an app's SQL migrations
+pub const DEFAULT_MIGRATIONS_DIR: &str = "migrations";
+
 /// Health check endpoint path
 pub const HEALTH_CHECK_PATH: &str = "/health";
 
@@ -38,6 +76,36 @@ pub const INDEX_PATH: &str = "/";
 /// WebSocket endpoint path
 pub const WEBSOCKET_PATH: &str = "/ws";
 
+/// Media (downloads, exports) endpoint path
+pub const MEDIA_PATH: &str = "/media/:id";
+
+/// Chunked upload endpoint path
+pub const UPLOAD_PATH: &str = "/upload/:id";
+
+/// Session snapshot export endpoint path
+pub const ADMIN_SNAPSHOT_PATH: &str = "/admin/sessions/:id/snapshot";
+
+/// Session snapshot import endpoint path
+pub const ADMIN_IMPORT_PATH: &str = "/admin/sessions/import";
+
+/// App version swap endpoint path
+pub const ADMIN_APP_SWAP_PATH: &str = "/admin/app/swap";
+
+/// Static page rendering endpoint path: `/render/{session_id}.html` or `.png`
+pub const RENDER_PATH: &str = "/render/:file";
+
+/// Shared snapshot endpoint path: `/share/{token}`
+pub const SHARE_PATH: &str = "/share/:token";
+
+/// Embedded fragment endpoint path: `/embed/{app}/{fragment}`
+pub const EMBED_PATH: &str = "/embed/:app/:fragment";
+
+/// Cluster status endpoint path
+pub const CLUSTER_STATUS_PATH: &str = "/cluster/status";
+
+/// Default cookie pinning a browser to a cluster node
+pub const DEFAULT_AFFINITY_COOKIE: &str = "platypus_node";
+
 /// Log level for verbose mode
 pub const VERBOSE_LOG_LEVEL: &str = "debug";
 
@@ -49,6 +117,11 @@ pub fn session_timeout_duration() -> Duration {
     Duration::from_secs(DEFAULT_SESSION_TIMEOUT)
 }
 
+/// Default idle warning, for configs that leave it out
+pub fn default_idle_warning() -> u64 {
+    DEFAULT_IDLE_WARNING
+}
+
 /// Maximum body size as usize
 pub fn max_body_size_usize() -> usize {
     DEFAULT_MAX_BODY_SIZE as usize
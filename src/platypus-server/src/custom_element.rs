@@ -0,0 +1,121 @@
+//! Validation hooks for custom elements.
+//!
+//! Crates defining their own elements send them as `ElementType::Custom`
+//! and can register a hook here to check payloads before they reach the
+//! client. Custom elements without a hook are sent as-is.
+
+use dashmap::DashMap;
+use platypus_core::element::ElementType;
+use platypus_core::state::Delta;
+use std::sync::Arc;
+
+/// Checks a custom element's payload, returning an error message if invalid.
+pub type CustomElementValidator = Arc<dyn Fn(&serde_json::Value) -> Result<(), String> + Send + Sync>;
+
+/// Validation hooks for custom elements, by name.
+pub struct CustomElementRegistry {
+    validators: Arc<DashMap<String, CustomElementValidator>>,
+}
+
+impl CustomElementRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        CustomElementRegistry {
+            validators: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// Register the validation hook for a custom element name.
+    pub fn register<F>(&self, name: impl Into<String>, validator: F)
+    where
+        F: Fn(&serde_json::Value) -> Result<(), String> + Send + Sync + 'static,
+    {
+        self.validators.insert(name.into(), Arc::new(validator));
+    }
+
+    /// Check if a hook is registered for a name.
+    pub fn contains(&self, name: &str) -> bool {
+        self.validators.contains_key(name)
+    }
+
+    /// Validate a payload against the hook registered for `name`.
+    pub fn validate(&self, name: &str, payload: &serde_json::Value) -> Result<(), String> {
+        // Clone the hook so it runs without holding the map lock
+        let validator = self.validators.get(name).map(|entry| Arc::clone(entry.value()));
+        match validator {
+            Some(validator) => validator(payload),
+            None => Ok(()),
+        }
+    }
+
+    /// Replace custom elements that fail validation with error messages.
+    pub fn check_deltas(&self, deltas: &mut [Delta]) {
+        for delta in deltas {
+            let element = match delta {
+                Delta::AddElement { element, .. } | Delta::UpdateElement { element, .. } => element,
+                _ => continue,
+            };
+            if let ElementType::Custom { name, payload } = element
+                && let Err(message) = self.validate(name, payload)
+            {
+                tracing::warn!("Invalid custom element {}: {}", name, message);
+                *element = ElementType::Error {
+                    message: format!("Invalid {} element: {}", name, message),
+                };
+            }
+        }
+    }
+}
+
+impl Default for CustomElementRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for CustomElementRegistry {
+    fn clone(&self) -> Self {
+        CustomElementRegistry {
+            validators: Arc::clone(&self.validators),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use platypus_core::element::ElementId;
+    use serde_json::json;
+
+    fn custom(payload: serde_json::Value) -> Delta {
+        Delta::AddElement {
+            id: ElementId::new(1),
+            element: ElementType::Custom { name: "gauge".to_string(), payload },
+            parent_id: None,
+        }
+    }
+
+    #[test]
+    fn test_check_deltas_replaces_invalid() {
+        let registry = CustomElementRegistry::new();
+        registry.register("gauge", |payload| match payload["value"].as_f64() {
+            Some(value) if (0.0..=100.0).contains(&value) => Ok(()),
+            _ => Err("value must be between 0 and 100".to_string()),
+        });
+
+        let mut deltas = vec![custom(json!({"value": 42})), custom(json!({"value": 420}))];
+        registry.check_deltas(&mut deltas);
+
+        assert!(matches!(&deltas[0], Delta::AddElement { element: ElementType::Custom { .. }, .. }));
+        assert!(matches!(
+            &deltas[1],
+            Delta::AddElement { element: ElementType::Error { message }, .. } if message.contains("between 0 and 100")
+        ));
+    }
+
+    #[test]
+    fn test_unregistered_names_pass() {
+        let registry = CustomElementRegistry::new();
+        assert!(registry.validate("unknown", &json!(null)).is_ok());
+    }
+}
@@ -0,0 +1,170 @@
+//! Dev-mode check that reruns are deterministic.
+//!
+//! With `ServerConfig::check_determinism` set, the executor runs the script
+//! twice more with identical widget values and client context, and compares
+//! the element trees. Elements that differ, e.g. widgets with random keys
+//! or text showing the current time, defeat caching and make every rerun
+//! replace them on the page; each is reported as a warning element.
+
+use crate::message;
+use platypus_core::element::ElementType;
+use platypus_core::state::Delta;
+use platypus_runtime::media;
+use std::fmt;
+
+/// Why an element differed between two identical runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Cause {
+    /// Only the widget key differed.
+    Key { first: String, second: String },
+    /// The element's content or type differed.
+    Content,
+    /// Only one run added the element.
+    Missing,
+}
+
+/// An element that differed between two identical runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunDifference {
+    /// Position of the element among the run's elements.
+    pub position: usize,
+    /// Type of the element in the first run, or the second if it has none.
+    pub element_type: String,
+    pub cause: Cause,
+}
+
+impl fmt::Display for RunDifference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Element {} ({}) ", self.position + 1, self.element_type)?;
+        match &self.cause {
+            Cause::Key { first, second } => write!(
+                f,
+                "got a different key on an identical rerun ({:?} vs {:?}); derive keys from stable values, not random ones",
+                first, second
+            ),
+            Cause::Content => write!(
+                f,
+                "changed on an identical rerun; random or time-based content breaks caching and diffing, so compute it outside the script or behind a widget"
+            ),
+            Cause::Missing => write!(
+                f,
+                "was added by only one of two identical reruns; conditions on random or time-based values change the page layout"
+            ),
+        }
+    }
+}
+
+/// Elements added by a run, with their parents' positions.
+fn element_tree(deltas: &[Delta]) -> Vec<(Option<u64>, &ElementType)> {
+    deltas
+        .iter()
+        .filter_map(|delta| match delta {
+            Delta::AddElement { element, parent_id, .. } => Some((parent_id.map(|id| id.inner()), element)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Serialize an element with its media IDs masked, since media is stored
+/// anew by every run.
+fn normalized(element: &ElementType) -> serde_json::Value {
+    let mut json = message::element_to_json(element);
+    let text = json.to_string();
+    let ids: Vec<String> = media::referenced_ids(&text).collect();
+    if !ids.is_empty() {
+        let masked = ids.iter().fold(text, |text, id| text.replace(id.as_str(), "*"));
+        json = serde_json::from_str(&masked).unwrap_or(json);
+    }
+    json
+}
+
+fn type_name(json: &serde_json::Value) -> String {
+    json["type"].as_str().unwrap_or("element").to_string()
+}
+
+/// Compare the deltas of two runs with identical state.
+pub fn compare_runs(first: &[Delta], second: &[Delta]) -> Vec<RunDifference> {
+    let (first, second) = (element_tree(first), element_tree(second));
+    let mut differences = Vec::new();
+    for position in 0..first.len().max(second.len()) {
+        let (a, b) = match (first.get(position), second.get(position)) {
+            (Some(a), Some(b)) => (a, b),
+            (Some((_, element)), None) | (None, Some((_, element))) => {
+                differences.push(RunDifference {
+                    position,
+                    element_type: type_name(&normalized(element)),
+                    cause: Cause::Missing,
+                });
+                continue;
+            }
+            (None, None) => break,
+        };
+        let (mut a_json, mut b_json) = (normalized(a.1), normalized(b.1));
+        if a.0 == b.0 && a_json == b_json {
+            continue;
+        }
+
+        let element_type = type_name(&a_json);
+        let keys = (a_json.get_mut("key").map(serde_json::Value::take), b_json.get_mut("key").map(serde_json::Value::take));
+        let cause = match keys {
+            (Some(first), Some(second)) if first != second && a.0 == b.0 && a_json == b_json => Cause::Key {
+                first: first.as_str().unwrap_or_default().to_string(),
+                second: second.as_str().unwrap_or_default().to_string(),
+            },
+            _ => Cause::Content,
+        };
+        differences.push(RunDifference {
+            position,
+            element_type,
+            cause,
+        });
+    }
+    differences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use platypus_core::element::ElementId;
+
+    fn add(id: u64, element: ElementType) -> Delta {
+        Delta::AddElement {
+            id: ElementId::new(id),
+            element,
+            parent_id: None,
+        }
+    }
+
+    #[test]
+    fn test_compare_runs() {
+        let button = |key: &str| ElementType::Button {
+            label: "Go".to_string(),
+            key: Some(key.to_string()),
+            disabled: false,
+        };
+        let text = |value: &str| ElementType::Text { value: value.to_string() };
+        let image = |id: &str| ElementType::Image {
+            src: format!("/media/{}", id),
+            caption: None,
+            width: None,
+        };
+
+        let first = vec![add(1, button("a1")), add(2, text("12:00:01")), add(3, image("1f2e")), add(4, text("same"))];
+        let second = vec![add(1, button("b2")), add(2, text("12:00:02")), add(3, image("9c0d"))];
+        let differences = compare_runs(&first, &second);
+        assert_eq!(
+            differences.iter().map(|difference| &difference.cause).collect::<Vec<_>>(),
+            vec![
+                &Cause::Key {
+                    first: "a1".to_string(),
+                    second: "b2".to_string()
+                },
+                &Cause::Content,
+                &Cause::Missing,
+            ]
+        );
+        assert_eq!(differences[2].position, 3);
+        assert!(differences[0].to_string().starts_with("Element 1 (button)"));
+        assert!(compare_runs(&first, &first).is_empty());
+    }
+}
@@ -0,0 +1,150 @@
+//! Embedding single containers of an app into other websites.
+//!
+//! An app marks a container as embeddable with `st.fragment(name)`, and a
+//! website frames it at `/embed/{app}/{name}`, where `app` is the slug of
+//! the app name, e.g. `sales-dashboard` for "Sales Dashboard":
+//!
+//! ```html
+//! <iframe src="https://apps.example.com/embed/sales-dashboard/kpis"></iframe>
+//! ```
+//!
+//! The framed page shows only the fragment's elements. It posts a
+//! `platypus:ready` message to the host page once connected and
+//! `platypus:resize` messages with its height as it changes, so the host
+//! can size the frame. Embedding is disabled unless
+//! [`EmbedConfig::frame_ancestors`] lists the sites allowed to frame.
+//!
+//! Host pages listed in [`EmbedConfig::host_origins`] can also exchange
+//! messages with the app, through `st.host_messages()` and
+//! `st.post_to_host(value)`.
+
+use platypus_core::element::ElementId;
+use platypus_core::state::Delta;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Origins allowed to frame embedded fragments.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmbedConfig {
+    /// `frame-ancestors` sources, e.g. `https://example.com` or `*` for
+    /// any site. Embedding is disabled when empty.
+    pub frame_ancestors: Vec<String>,
+    /// Origins of host pages that may exchange messages with embedded
+    /// apps, or `*` for any. Messaging is disabled when empty.
+    pub host_origins: Vec<String>,
+}
+
+impl EmbedConfig {
+    /// Check whether fragments can be embedded at all.
+    pub fn enabled(&self) -> bool {
+        !self.frame_ancestors.is_empty()
+    }
+
+    /// Get the `Content-Security-Policy` of embedded pages.
+    pub fn content_security_policy(&self) -> String {
+        format!("frame-ancestors {}", self.frame_ancestors.join(" "))
+    }
+}
+
+/// Check whether `origin` is one of the `allowed` host origins.
+pub fn allows_origin(allowed: &[String], origin: &str) -> bool {
+    allowed.iter().any(|allowed| allowed == "*" || allowed == origin)
+}
+
+/// Get the slug identifying an app in embed URLs: its name, lowercased,
+/// with runs of other characters than letters and digits made a `-`.
+pub fn app_slug(name: &str) -> String {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Keep the deltas of the elements inside the container `root`, making its
+/// children top-level elements.
+pub fn fragment_deltas(deltas: Vec<Delta>, root: ElementId) -> Vec<Delta> {
+    let mut inside = HashSet::from([root]);
+    // Give an element added inside the fragment its parent there, None at
+    // the fragment's top level
+    let enter = |inside: &mut HashSet<ElementId>, id: ElementId, parent_id: Option<ElementId>| {
+        let parent = parent_id.filter(|parent| inside.contains(parent))?;
+        inside.insert(id);
+        Some((parent != root).then_some(parent))
+    };
+    let mut kept = Vec::new();
+    for delta in deltas {
+        let delta = match delta {
+            Delta::AddElement { id, element, parent_id } => {
+                enter(&mut inside, id, parent_id).map(|parent_id| Delta::AddElement { id, element, parent_id })
+            }
+            Delta::MoveElement { id, previous_id, parent_id } => {
+                enter(&mut inside, id, parent_id).map(|parent_id| Delta::MoveElement { id, previous_id, parent_id })
+            }
+            Delta::UpdateElement { id, .. }
+            | Delta::RemoveElement { id }
+            | Delta::ClearContainer { id }
+            | Delta::SetAccessibility { id, .. }
+            | Delta::AppendChartData { id, .. } => (id != root && inside.contains(&id)).then_some(delta),
+        };
+        kept.extend(delta);
+    }
+    kept
+}
+
+/// Get the page framing the fragment `fragment`: the app page, told to
+/// connect in embed mode and which hosts it may exchange messages with.
+pub fn embed_page(fragment: &str, config: &EmbedConfig) -> String {
+    let embed = serde_json::json!({ "fragment": fragment, "origins": config.host_origins });
+    // Escaped so no value can close the script
+    let embed = embed.to_string().replace('<', "\\u003c");
+    let script = format!("<script>window.PLATYPUS_EMBED = {};</script>\n</head>", embed);
+    include_str!("../frontend/index.html").replacen("</head>", &script, 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use platypus_core::element::ElementType;
+
+    fn add(id: u64, parent_id: Option<u64>) -> Delta {
+        Delta::AddElement {
+            id: ElementId::new(id),
+            element: ElementType::Empty,
+            parent_id: parent_id.map(ElementId::new),
+        }
+    }
+
+    #[test]
+    fn test_fragment_deltas_keep_subtree() {
+        let deltas = vec![
+            add(1, None),
+            add(2, None),
+            add(3, Some(2)),
+            add(4, Some(3)),
+            add(5, Some(1)),
+            Delta::UpdateElement { id: ElementId::new(2), element: ElementType::Empty },
+            Delta::ClearContainer { id: ElementId::new(3) },
+        ];
+        let fragment = fragment_deltas(deltas, ElementId::new(2));
+        assert_eq!(fragment.len(), 3);
+        assert!(matches!(fragment[0], Delta::AddElement { id, parent_id: None, .. } if id == ElementId::new(3)));
+        assert!(matches!(fragment[1], Delta::AddElement { parent_id: Some(parent), .. } if parent == ElementId::new(3)));
+        assert!(matches!(fragment[2], Delta::ClearContainer { id } if id == ElementId::new(3)));
+    }
+
+    #[test]
+    fn test_app_slug_and_page() {
+        assert_eq!(app_slug("Sales Dashboard (EU)"), "sales-dashboard-eu");
+        let config = EmbedConfig {
+            frame_ancestors: vec!["*".to_string()],
+            host_origins: vec!["https://portal.example.com".to_string()],
+        };
+        let page = embed_page("</script>", &config);
+        assert!(page.contains(r#""fragment":"\u003c/script>""#));
+        assert!(allows_origin(&config.host_origins, "https://portal.example.com"));
+        assert!(!allows_origin(&config.host_origins, "https://evil.example.com"));
+        assert!(!EmbedConfig::default().enabled());
+    }
+}
@@ -2,6 +2,7 @@
 
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
+use platypus_core::ErrorCode;
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -29,6 +30,21 @@ pub enum Error {
     #[error("Bad request: {0}")]
     BadRequest(String),
 
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    #[error("Payload too large: {0}")]
+    PayloadTooLarge(String),
+
+    #[error("Headers too large: {0}")]
+    HeadersTooLarge(String),
+
+    #[error("Invalid config: {0}")]
+    Config(String),
+
     #[error("Internal error: {0}")]
     Internal(String),
 
@@ -49,16 +65,59 @@ impl Error {
         Error::BadRequest(msg.into())
     }
 
+    pub fn unauthorized(msg: impl Into<String>) -> Self {
+        Error::Unauthorized(msg.into())
+    }
+
+    pub fn forbidden(msg: impl Into<String>) -> Self {
+        Error::Forbidden(msg.into())
+    }
+
+    pub fn payload_too_large(msg: impl Into<String>) -> Self {
+        Error::PayloadTooLarge(msg.into())
+    }
+
+    pub fn headers_too_large(msg: impl Into<String>) -> Self {
+        Error::HeadersTooLarge(msg.into())
+    }
+
+    pub fn config(msg: impl Into<String>) -> Self {
+        Error::Config(msg.into())
+    }
+
     pub fn internal(msg: impl Into<String>) -> Self {
         Error::Internal(msg.into())
     }
+
+    /// Get the error's stable code.
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Error::Runtime(e) => e.code(),
+            Error::Core(e) => e.code(),
+            Error::WebSocket(_) => ErrorCode::Protocol,
+            Error::Serialization(_) => ErrorCode::Serialization,
+            Error::Io(_) => ErrorCode::Io,
+            Error::NotFound(_) => ErrorCode::NotFound,
+            Error::BadRequest(_) => ErrorCode::BadRequest,
+            Error::Unauthorized(_) => ErrorCode::Unauthorized,
+            Error::Forbidden(_) => ErrorCode::Forbidden,
+            Error::PayloadTooLarge(_) | Error::HeadersTooLarge(_) => ErrorCode::PayloadTooLarge,
+            Error::Config(_) => ErrorCode::Validation,
+            Error::Internal(_) | Error::Other(_) => ErrorCode::Internal,
+        }
+    }
 }
 
 impl IntoResponse for Error {
     fn into_response(self) -> Response {
+        let code = self.code();
         let (status, error_message) = match self {
             Error::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
             Error::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+            Error::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
+            Error::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
+            Error::PayloadTooLarge(msg) => (StatusCode::PAYLOAD_TOO_LARGE, msg),
+            Error::HeadersTooLarge(msg) => (StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE, msg),
             Error::WebSocket(msg) => (StatusCode::BAD_REQUEST, msg),
             Error::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
             _ => (
@@ -68,7 +127,8 @@ impl IntoResponse for Error {
         };
 
         let body = serde_json::json!({
-            "error": error_message
+            "error": error_message,
+            "code": code.as_str(),
         });
 
         (status, axum::Json(body)).into_response()
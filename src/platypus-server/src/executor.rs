@@ -1,12 +1,31 @@
 //! Script execution and delta generation for handling user interactions.
 
+use crate::custom_element::CustomElementRegistry;
+use crate::determinism::{self, RunDifference};
+use crate::embed;
+use crate::log_layer;
+use crate::reload::AppSlot;
+use platypus_core::clock::{self, Clock};
+use platypus_core::element::{ElementType, RateLimit};
+use platypus_core::ids::{self, IdGenerator};
 use platypus_core::state::{Delta, DeltaGenerator};
-use platypus_core::widget::WidgetValue;
-use platypus_runtime::{St, SessionStore};
-use platypus_core::session::SessionId;
+use platypus_runtime::theme::THEME_METADATA_KEY;
+use platypus_runtime::{
+    Action, ActionQueue, ClientInfo, ColorScheme, ChartStreamStore, ConversationStore, DataCache, Event, EventDispatcher,
+    HostMessage, HostMessages, LogStore, MediaStore, NotificationStore, PresenceRegistry, RoomRegistry, ShareStore, Speaker,
+    St, SessionStore, TempDirs, ThemeMode, TopicCursors, TopicRegistry, Transcriber, WidgetChange,
+};
+use platypus_runtime::checkpoint::{CancellationToken, FlushFn, DEFAULT_FLUSH_INTERVAL};
+use platypus_runtime::event::PAGE_WIDGET_KEY;
+use platypus_runtime::presence::USER_METADATA_KEY;
+use platypus_runtime::{lazy, media, render, selection, transfer};
+use platypus_core::session::{Session, SessionId};
 use std::sync::Arc;
 use std::sync::Mutex;
-use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
 
 /// App function type for custom app logic
 pub type AppFn = fn(&mut St) -> Result<(), String>;
@@ -14,11 +33,78 @@ pub type AppFn = fn(&mut St) -> Result<(), String>;
 /// Widget state storage
 type WidgetState = Arc<Mutex<HashMap<String, String>>>;
 
+/// Per-widget debounce: changes to these keys are stored immediately, but
+/// the rerun waits until the widget has been quiet for the given delay
+pub type WidgetDebounce = Arc<HashMap<String, Duration>>;
+
+/// Stored values are strings; plain strings are kept unquoted
+fn widget_value_string(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
 /// Handles script execution and generates UI deltas
 pub struct ScriptExecutor {
     session_store: Arc<SessionStore>,
-    app_fn: Option<AppFn>,
+    app: AppSlot,
     widget_state: WidgetState,
+    media_store: MediaStore,
+    custom_elements: CustomElementRegistry,
+    client_info: Arc<Mutex<ClientInfo>>,
+    notifications: NotificationStore,
+    logs: LogStore,
+    chart_streams: ChartStreamStore,
+    events: EventDispatcher,
+    actions: ActionQueue,
+    debounce: WidgetDebounce,
+    /// Rate limits of the widgets rendered by the last run
+    rate_limits: Mutex<HashMap<String, RateLimit>>,
+    /// Keys of the disabled widgets rendered by the last run
+    disabled_widgets: Mutex<HashSet<String>>,
+    last_run: Mutex<Option<Instant>>,
+    /// Dev mode: compare two identical runs for nondeterministic output
+    check_determinism: bool,
+    fatal_element_errors: bool,
+    /// Cancels the current run at its next checkpoint
+    run_token: Mutex<CancellationToken>,
+    run_timeout: Option<Duration>,
+    /// Page flushed at the last checkpoint of the current run
+    partial_page: Arc<watch::Sender<Vec<Delta>>>,
+    /// Deltas of the last run, resent as a snapshot on resync
+    last_deltas: Mutex<Vec<Delta>>,
+    pending_rerun: Mutex<Option<Instant>>,
+    /// Submit button pressed for the next run only
+    submitted: Mutex<Option<String>>,
+    rooms: RoomRegistry,
+    /// Rooms the last run joined
+    joined_rooms: Mutex<HashSet<String>>,
+    topics: TopicRegistry,
+    topic_cursors: TopicCursors,
+    /// Topics the last run subscribed to
+    subscribed_topics: Mutex<HashSet<String>>,
+    presence: PresenceRegistry,
+    /// Whether the last run read presence
+    reads_presence: AtomicBool,
+    conversations: ConversationStore,
+    transcriber: Transcriber,
+    speaker: Speaker,
+    temp_dirs: TempDirs,
+    data_cache: DataCache,
+    shares: ShareStore,
+    /// Fragment an embedding page shows, instead of the whole page
+    fragment: Option<String>,
+    /// Origins of the embedding pages the app exchanges messages with
+    host_origins: Vec<String>,
+    host_messages: HostMessages,
+    /// Runs so far, namespacing each run's element IDs
+    runs: AtomicU32,
+    /// Time and IDs the app sees, fixed for tests and replays
+    clock: Arc<dyn Clock>,
+    ids: Arc<dyn IdGenerator>,
+    #[cfg(feature = "embeddings")]
+    semantic_index: Option<platypus_runtime::SemanticIndex>,
 }
 
 impl ScriptExecutor {
@@ -26,8 +112,49 @@ impl ScriptExecutor {
     pub fn new(session_store: Arc<SessionStore>) -> Self {
         ScriptExecutor { 
             session_store, 
-            app_fn: None,
+            app: AppSlot::default(),
             widget_state: Arc::new(Mutex::new(HashMap::new())),
+            media_store: MediaStore::new(),
+            custom_elements: CustomElementRegistry::new(),
+            client_info: Arc::new(Mutex::new(ClientInfo::default())),
+            notifications: NotificationStore::new(),
+            logs: LogStore::new(),
+            chart_streams: ChartStreamStore::new(),
+            events: EventDispatcher::new(),
+            actions: ActionQueue::new(),
+            debounce: WidgetDebounce::default(),
+            rate_limits: Mutex::new(HashMap::new()),
+            disabled_widgets: Mutex::new(HashSet::new()),
+            last_run: Mutex::new(None),
+            check_determinism: false,
+            fatal_element_errors: false,
+            run_token: Mutex::new(CancellationToken::new()),
+            run_timeout: None,
+            partial_page: Arc::new(watch::channel(Vec::new()).0),
+            last_deltas: Mutex::new(Vec::new()),
+            pending_rerun: Mutex::new(None),
+            submitted: Mutex::new(None),
+            rooms: RoomRegistry::new(),
+            joined_rooms: Mutex::new(HashSet::new()),
+            topics: TopicRegistry::new(),
+            topic_cursors: TopicCursors::new(),
+            subscribed_topics: Mutex::new(HashSet::new()),
+            presence: PresenceRegistry::new(),
+            reads_presence: AtomicBool::new(false),
+            conversations: ConversationStore::new(),
+            transcriber: Transcriber::new(),
+            speaker: Speaker::new(),
+            temp_dirs: TempDirs::new(),
+            data_cache: DataCache::new(),
+            shares: ShareStore::new(),
+            fragment: None,
+            host_origins: Vec::new(),
+            host_messages: HostMessages::new(),
+            runs: AtomicU32::new(0),
+            clock: clock::system_clock(),
+            ids: ids::random_ids(),
+            #[cfg(feature = "embeddings")]
+            semantic_index: None,
         }
     }
 
@@ -35,17 +162,379 @@ impl ScriptExecutor {
     pub fn with_app(session_store: Arc<SessionStore>, app_fn: AppFn) -> Self {
         ScriptExecutor { 
             session_store, 
-            app_fn: Some(app_fn),
+            app: AppSlot::new(Some(app_fn)),
             widget_state: Arc::new(Mutex::new(HashMap::new())),
+            media_store: MediaStore::new(),
+            custom_elements: CustomElementRegistry::new(),
+            client_info: Arc::new(Mutex::new(ClientInfo::default())),
+            notifications: NotificationStore::new(),
+            logs: LogStore::new(),
+            chart_streams: ChartStreamStore::new(),
+            events: EventDispatcher::new(),
+            actions: ActionQueue::new(),
+            debounce: WidgetDebounce::default(),
+            rate_limits: Mutex::new(HashMap::new()),
+            disabled_widgets: Mutex::new(HashSet::new()),
+            last_run: Mutex::new(None),
+            check_determinism: false,
+            fatal_element_errors: false,
+            run_token: Mutex::new(CancellationToken::new()),
+            run_timeout: None,
+            partial_page: Arc::new(watch::channel(Vec::new()).0),
+            last_deltas: Mutex::new(Vec::new()),
+            pending_rerun: Mutex::new(None),
+            submitted: Mutex::new(None),
+            rooms: RoomRegistry::new(),
+            joined_rooms: Mutex::new(HashSet::new()),
+            topics: TopicRegistry::new(),
+            topic_cursors: TopicCursors::new(),
+            subscribed_topics: Mutex::new(HashSet::new()),
+            presence: PresenceRegistry::new(),
+            reads_presence: AtomicBool::new(false),
+            conversations: ConversationStore::new(),
+            transcriber: Transcriber::new(),
+            speaker: Speaker::new(),
+            temp_dirs: TempDirs::new(),
+            data_cache: DataCache::new(),
+            shares: ShareStore::new(),
+            fragment: None,
+            host_origins: Vec::new(),
+            host_messages: HostMessages::new(),
+            runs: AtomicU32::new(0),
+            clock: clock::system_clock(),
+            ids: ids::random_ids(),
+            #[cfg(feature = "embeddings")]
+            semantic_index: None,
         }
     }
 
+    /// Run the app in the server's slot, so swapping it reaches this session
+    pub fn with_app_slot(mut self, app: AppSlot) -> Self {
+        self.app = app;
+        self
+    }
+
+    /// Give the app the time of `clock`
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Give the app IDs from `ids`
+    pub fn with_ids(mut self, ids: Arc<dyn IdGenerator>) -> Self {
+        self.ids = ids;
+        self
+    }
+
+    /// Share a media store with the server so downloads can be served
+    pub fn with_media_store(mut self, media_store: MediaStore) -> Self {
+        self.media_store = media_store;
+        self
+    }
+
+    /// Share the server's custom element validation hooks
+    pub fn with_custom_elements(mut self, custom_elements: CustomElementRegistry) -> Self {
+        self.custom_elements = custom_elements;
+        self
+    }
+
+    /// Start from the widget values and conversations of a resumed session
+    pub fn restore_session(&self, session: Session) {
+        self.conversations.restore(&session.metadata);
+        if let Ok(mut state) = self.widget_state.lock() {
+            *state = session.widget_state;
+        }
+    }
+
+    /// Share the server's rooms with the app
+    pub fn with_rooms(mut self, rooms: RoomRegistry) -> Self {
+        self.rooms = rooms;
+        self
+    }
+
+    /// Share the server's topics with the app
+    pub fn with_topics(mut self, topics: TopicRegistry) -> Self {
+        self.topics = topics;
+        self
+    }
+
+    /// Share the server's presence registry with the app
+    pub fn with_presence(mut self, presence: PresenceRegistry) -> Self {
+        self.presence = presence;
+        self
+    }
+
+    /// Share the server's semantic index with the app
+    #[cfg(feature = "embeddings")]
+    pub fn with_semantic_index(mut self, index: platypus_runtime::SemanticIndex) -> Self {
+        self.semantic_index = Some(index);
+        self
+    }
+
+    /// Share the server's transcriber with the app
+    pub fn with_transcriber(mut self, transcriber: Transcriber) -> Self {
+        self.transcriber = transcriber;
+        self
+    }
+
+    /// Share the server's speech synthesis with the app
+    pub fn with_speaker(mut self, speaker: Speaker) -> Self {
+        self.speaker = speaker;
+        self
+    }
+
+    /// Share the server's session scratch directories with the app
+    pub fn with_temp_dirs(mut self, temp_dirs: TempDirs) -> Self {
+        self.temp_dirs = temp_dirs;
+        self
+    }
+
+    /// Share the server's data cache with the app
+    pub fn with_data_cache(mut self, data_cache: DataCache) -> Self {
+        self.data_cache = data_cache;
+        self
+    }
+
+    /// Keep the app's shared snapshots in the server's store
+    pub fn with_shares(mut self, shares: ShareStore) -> Self {
+        self.shares = shares;
+        self
+    }
+
+    /// Send only the deltas of the fragment `fragment` created with
+    /// `st.fragment`, for a page embedding it
+    pub fn with_fragment(mut self, fragment: Option<String>) -> Self {
+        self.fragment = fragment;
+        self
+    }
+
+    /// Accept messages from the embedding pages at `origins`, or `*` for
+    /// any
+    pub fn with_host_origins(mut self, origins: Vec<String>) -> Self {
+        self.host_origins = origins;
+        self
+    }
+
+    /// Check if the last run read presence, so presence changes rerun it
+    pub fn reads_presence(&self) -> bool {
+        self.reads_presence.load(Ordering::Relaxed)
+    }
+
+    /// Check if the last run joined a room
+    pub fn in_room(&self, room: &str) -> bool {
+        self.joined_rooms.lock().is_ok_and(|rooms| rooms.contains(room))
+    }
+
+    /// Check if the last run subscribed to a topic
+    pub fn subscribes_to(&self, topic: &str) -> bool {
+        self.subscribed_topics.lock().is_ok_and(|topics| topics.contains(topic))
+    }
+
+    /// Get the deltas of the last run, which render the whole page
+    pub fn snapshot(&self) -> Vec<Delta> {
+        self.last_deltas.lock().map(|deltas| deltas.clone()).unwrap_or_default()
+    }
+
+    /// Warn about elements that differ between runs with identical state.
+    ///
+    /// Each run is followed by two isolated runs with the same widget values
+    /// and client context, so this is meant for development only.
+    pub fn with_determinism_check(mut self, enabled: bool) -> Self {
+        self.check_determinism = enabled;
+        self
+    }
+
+    /// Fail runs in which an element failed to render, instead of showing
+    /// an inline error in its place; meant for CI.
+    pub fn with_fatal_element_errors(mut self, fatal: bool) -> Self {
+        self.fatal_element_errors = fatal;
+        self
+    }
+
+    /// Stop runs at their first checkpoint after `timeout`.
+    pub fn with_run_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.run_timeout = timeout;
+        self
+    }
+
+    /// Cancel the current run at its next checkpoint, e.g. because a newer
+    /// widget change supersedes it.
+    pub fn cancel_run(&self) {
+        if let Ok(token) = self.run_token.lock() {
+            token.cancel();
+        }
+    }
+
+    /// Watch the pages runs flush at checkpoints.
+    pub fn partial_pages(&self) -> watch::Receiver<Vec<Delta>> {
+        self.partial_page.subscribe()
+    }
+
+    /// Debounce reruns for the given widget keys
+    pub fn with_debounce(mut self, debounce: WidgetDebounce) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
     /// Execute a script and return deltas
-    pub fn execute_script(&self, _session_id: SessionId) -> Result<Vec<Delta>, String> {
-        // Get or create session
-        let delta_gen = DeltaGenerator::new();
-        
-        // Restore widget state from previous interactions
+    pub fn execute_script(&self, session_id: SessionId) -> Result<Vec<Delta>, String> {
+        // This run sees every stored change, so a debounced rerun is moot
+        if let Ok(mut pending) = self.pending_rerun.lock() {
+            *pending = None;
+        }
+        if let Ok(mut last_run) = self.last_run.lock() {
+            *last_run = Some(Instant::now());
+        }
+
+        // Track the rerun on the session, keeping its widget values current
+        // for snapshots
+        if let Ok(mut session) = self.session_store.get_session(session_id) {
+            session.increment_reruns();
+            if let Ok(state) = self.widget_state.lock() {
+                session.widget_state = state.clone();
+            }
+            let _ = self.session_store.update_session(session);
+        }
+
+        let run = self.runs.fetch_add(1, Ordering::Relaxed) + 1;
+        let delta_gen = self.restored_delta_gen(run);
+        if let Some(submit_key) = self.submitted.lock().ok().and_then(|mut submitted| submitted.take()) {
+            delta_gen.set_widget(submit_key, platypus_core::widget::WidgetValue::Bool(true));
+        }
+        let client_info = self
+            .client_info
+            .lock()
+            .map(|info| info.clone())
+            .unwrap_or_default();
+
+        // Persistent notifications carry over; the rest must be re-sent
+        self.notifications.begin_run();
+        // Actions from an earlier run that failed are never delivered
+        self.actions.take();
+
+        let mut st = St::with_delta_gen(delta_gen.clone())
+            .with_media(self.media_store.clone())
+            .with_client_info(client_info.clone())
+            .with_theme_mode(self.theme_mode(session_id))
+            .with_notifications(self.notifications.clone())
+            .with_logs(self.logs.clone())
+            .with_chart_streams(self.chart_streams.clone())
+            .with_events(self.events.clone())
+            .with_actions(self.actions.clone())
+            .with_rooms(self.rooms.clone())
+            .with_topics(self.topics.clone())
+            .with_topic_cursors(self.topic_cursors.clone())
+            .with_presence(self.presence.clone())
+            .with_conversations(self.conversations.clone())
+            .with_transcriber(self.transcriber.clone())
+            .with_speaker(self.speaker.clone())
+            .with_temp_dirs(self.temp_dirs.clone())
+            .with_data_cache(self.data_cache.clone())
+            .with_shares(self.shares.clone())
+            .with_host_messages(self.host_messages.take())
+            .with_session_id(session_id);
+        #[cfg(feature = "embeddings")]
+        if let Some(index) = &self.semantic_index {
+            st = st.with_semantic_index(index.clone());
+        }
+        st = self.with_checkpoints(st);
+
+        // Run inside a session span so app tracing events reach its log viewer
+        log_layer::register_session(session_id, self.logs.clone());
+        let span = tracing::info_span!(log_layer::RUN_SPAN, session_id = %session_id);
+
+        // Execute the app logic (placeholder - would be user's script)
+        let result = span.in_scope(|| self.run_app(&mut st));
+        // The page of an interrupted run is incomplete, even if the app
+        // carried on after the checkpoint
+        if let Some(interrupted) = st.interrupted() {
+            return Err(interrupted.to_string());
+        }
+        result?;
+        let failures = st.element_failures();
+        if self.fatal_element_errors && !failures.is_empty() {
+            return Err(failures.join("; "));
+        }
+        if let Ok(mut rooms) = self.joined_rooms.lock() {
+            rooms.clone_from(st.joined_rooms());
+        }
+        if let Ok(mut topics) = self.subscribed_topics.lock() {
+            topics.clone_from(st.subscribed_topics());
+        }
+        self.reads_presence.store(st.reads_presence(), Ordering::Relaxed);
+        let user = self
+            .session_store
+            .get_session(session_id)
+            .ok()
+            .and_then(|session| session.get_metadata(USER_METADATA_KEY).map(str::to_string));
+        self.presence.update(session_id, user, st.joined_rooms().iter().cloned().collect());
+        self.persist_conversations(session_id);
+
+        if self.check_determinism {
+            for difference in self.compare_isolated_runs(session_id, &client_info) {
+                tracing::warn!("Nondeterministic output in session {}: {}", session_id, difference);
+                st.warning(difference.to_string());
+            }
+        }
+
+        // Keep the panel visible for notifications the script didn't re-send
+        if !self.notifications.is_empty() {
+            st.notification_center();
+        }
+
+        // Get deltas, catching invalid custom elements before they are sent
+        let mut deltas = st.drain_deltas();
+        self.custom_elements.check_deltas(&mut deltas);
+        self.record_rate_limits(&deltas);
+        self.record_disabled_widgets(&deltas);
+        self.record_media_references(session_id, &deltas);
+        self.freeze_shares(&mut st, &deltas);
+        if let Some(fragment) = &self.fragment {
+            let root = st.fragments().get(fragment).ok_or_else(|| format!("No fragment named {}", fragment))?;
+            deltas = embed::fragment_deltas(deltas, *root);
+        }
+        if let Ok(mut last_deltas) = self.last_deltas.lock() {
+            last_deltas.clone_from(&deltas);
+        }
+        Ok(deltas)
+    }
+
+    /// Give a run a fresh cancellation token, its deadline, and a flush
+    /// publishing its partial pages.
+    fn with_checkpoints(&self, st: St) -> St {
+        let token = CancellationToken::new();
+        if let Ok(mut run_token) = self.run_token.lock() {
+            *run_token = token.clone();
+        }
+        let st = st.with_cancellation(token);
+        // Partial pages are of the whole page, so embeds wait for the run
+        if self.fragment.is_some() {
+            return self.with_deadline(st);
+        }
+        let custom_elements = self.custom_elements.clone();
+        let partial_page = Arc::clone(&self.partial_page);
+        let flush: FlushFn = Arc::new(move |mut deltas| {
+            custom_elements.check_deltas(&mut deltas);
+            partial_page.send_replace(deltas);
+        });
+        self.with_deadline(st.with_flush(flush, DEFAULT_FLUSH_INTERVAL))
+    }
+
+    /// Stop a run at its first checkpoint after the run timeout
+    fn with_deadline(&self, st: St) -> St {
+        match self.run_timeout {
+            Some(timeout) => st.with_deadline(Instant::now() + timeout),
+            None => st,
+        }
+    }
+
+    /// Create a delta generator holding the widget values of previous
+    /// interactions
+    fn restored_delta_gen(&self, namespace: u32) -> DeltaGenerator {
+        let delta_gen = DeltaGenerator::new()
+            .with_namespace(namespace)
+            .with_clock(Arc::clone(&self.clock))
+            .with_ids(Arc::clone(&self.ids));
         if let Ok(state) = self.widget_state.lock() {
             for (key, value) in state.iter() {
                 // Try to parse as number first, then as string
@@ -56,15 +545,264 @@ impl ScriptExecutor {
                 }
             }
         }
-        
-        let mut st = St::with_delta_gen(delta_gen.clone());
+        delta_gen
+    }
 
-        // Execute the app logic (placeholder - would be user's script)
-        self.run_app(&mut st)?;
+    /// Run the app twice with the current widget values and fresh stores,
+    /// so neither run sees the other's notifications, logs or actions, and
+    /// compare their elements
+    fn compare_isolated_runs(&self, session_id: SessionId, client_info: &ClientInfo) -> Vec<RunDifference> {
+        let run = || {
+            let mut st = St::with_delta_gen(self.restored_delta_gen(0))
+                .with_client_info(client_info.clone())
+                .with_theme_mode(self.theme_mode(session_id))
+                .with_session_id(session_id);
+            self.run_app(&mut st).map(|()| st.drain_deltas())
+        };
+        match (run(), run()) {
+            (Ok(first), Ok(second)) => determinism::compare_runs(&first, &second),
+            _ => Vec::new(),
+        }
+    }
 
-        // Get deltas
-        let deltas = st.delta_gen().take_deltas();
-        Ok(deltas)
+    /// Save the session's conversations into its state, so they outlive
+    /// the connection and are part of snapshots
+    fn persist_conversations(&self, session_id: SessionId) {
+        let saved = self.conversations.to_metadata();
+        if saved.is_empty() {
+            return;
+        }
+        if let Ok(mut session) = self.session_store.get_session(session_id) {
+            for (key, json) in saved {
+                session.set_metadata(key, json);
+            }
+            let _ = self.session_store.update_session(session);
+        }
+    }
+
+    /// Pass an event to the app's handlers, apply it and rerun script
+    ///
+    /// Returns `None` when a debounced widget changed and the rerun was
+    /// deferred until [`Self::rerun_deadline`].
+    pub fn handle_event(&self, session_id: SessionId, event: Event) -> Result<Option<Vec<Delta>>, String> {
+        // Rerun without the change, restoring the client's value
+        if let Event::WidgetChanged { key, .. } = &event
+            && self.is_disabled(key)
+        {
+            return self.execute_script(session_id).map(Some);
+        }
+        if let Event::HostMessage { origin, .. } = &event
+            && !embed::allows_origin(&self.host_origins, origin)
+        {
+            tracing::warn!("Dropped message from host {}, which is not allowed", origin);
+            return Ok(None);
+        }
+        self.events.dispatch(&event);
+
+        if let Event::WidgetChanged { key, value } = &event
+            && let Some(deadline) = self.deferred_until(key)
+        {
+            self.store_widget_value(key, value);
+            if let Ok(mut pending) = self.pending_rerun.lock() {
+                *pending = Some(deadline);
+            }
+            return Ok(None);
+        }
+
+        // Focus moves are kept for the next run without rerunning
+        if let Event::ClientContext { info } = &event
+            && let Ok(mut current) = self.client_info.lock()
+            && (ClientInfo { focused_widget: current.focused_widget.clone(), ..info.clone() }) == *current
+        {
+            current.focused_widget.clone_from(&info.focused_widget);
+            return Ok(None);
+        }
+
+        self.apply_event(session_id, event).map(Some)
+    }
+
+    /// Get when a change to `key` may rerun, if not right away
+    fn deferred_until(&self, key: &str) -> Option<Instant> {
+        let now = Instant::now();
+        if let Some(delay) = self.debounce.get(key) {
+            return Some(now + *delay);
+        }
+
+        // Coalesce changes that arrive faster than the widget's own rate
+        // limit allows, e.g. from a client that ignores it
+        let window = self.rate_limits.lock().ok()?.get(key)?.window();
+        let due = (*self.last_run.lock().ok()?)? + window;
+        (due > now).then_some(due)
+    }
+
+    fn record_rate_limits(&self, deltas: &[Delta]) {
+        let limits = deltas
+            .iter()
+            .filter_map(|delta| match delta {
+                Delta::AddElement {
+                    element:
+                        ElementType::TextInput { key: Some(key), rate_limit, .. }
+                        | ElementType::Slider { key: Some(key), rate_limit, .. },
+                    ..
+                } if !rate_limit.is_none() => Some((key.clone(), *rate_limit)),
+                _ => None,
+            })
+            .collect();
+        if let Ok(mut rate_limits) = self.rate_limits.lock() {
+            *rate_limits = limits;
+        }
+    }
+
+    fn record_disabled_widgets(&self, deltas: &[Delta]) {
+        let keys = deltas
+            .iter()
+            .filter_map(|delta| match delta {
+                Delta::AddElement { element, .. } | Delta::UpdateElement { element, .. } => {
+                    let json = serde_json::to_value(element).ok()?;
+                    (json["disabled"] == true).then(|| json["key"].as_str().map(str::to_string))?
+                }
+                _ => None,
+            })
+            .collect();
+        if let Ok(mut disabled_widgets) = self.disabled_widgets.lock() {
+            *disabled_widgets = keys;
+        }
+    }
+
+    /// Check if the last run rendered the widget with `key` disabled, so
+    /// changes to it are ignored, e.g. from a client that ignores it
+    fn is_disabled(&self, key: &str) -> bool {
+        let disabled = self.disabled_widgets.lock().is_ok_and(|keys| keys.contains(key));
+        if disabled {
+            tracing::warn!("Ignored change to disabled widget {}", key);
+        }
+        disabled
+    }
+
+    /// Check if the last run showed a submit button of `form` with
+    /// `submit_key`, so clients cannot press buttons the app did not show
+    fn is_submit_button(&self, form: &str, submit_key: &str) -> bool {
+        let shown = self.last_deltas.lock().is_ok_and(|deltas| {
+            deltas.iter().any(|delta| matches!(
+                delta,
+                Delta::AddElement { element: ElementType::FormSubmitButton { form_key, key: Some(key), .. }, .. }
+                    if form_key == form && key == submit_key
+            ))
+        });
+        if !shown {
+            tracing::warn!("Ignored submission of form {} by unknown button {}", form, submit_key);
+        }
+        shown
+    }
+
+    /// Freeze the pages of the snapshots this run shared, now it has ended
+    fn freeze_shares(&self, st: &mut St, deltas: &[Delta]) {
+        let tokens = st.take_pending_shares();
+        if tokens.is_empty() {
+            return;
+        }
+        let elements = render::page_elements(deltas);
+        for token in tokens {
+            self.shares.freeze(&token, &elements);
+        }
+    }
+
+    /// Point the session's media references at the files this run's
+    /// elements and the session's widget values (uploads) use
+    fn record_media_references(&self, session_id: SessionId, deltas: &[Delta]) {
+        let mut ids = HashSet::new();
+        for delta in deltas {
+            if let Delta::AddElement { element, .. } | Delta::UpdateElement { element, .. } = delta
+                && let Ok(json) = serde_json::to_string(element)
+            {
+                ids.extend(media::referenced_ids(&json));
+            }
+        }
+        if let Ok(state) = self.widget_state.lock() {
+            for value in state.values() {
+                ids.extend(media::referenced_ids(value));
+            }
+        }
+        self.media_store.set_references(session_id, ids);
+    }
+
+    fn apply_event(&self, session_id: SessionId, event: Event) -> Result<Vec<Delta>, String> {
+        match event {
+            Event::WidgetChanged { key, value } => {
+                self.store_widget_value(&key, &value);
+                self.execute_script(session_id)
+            }
+            Event::WidgetsChanged { changes } => self.handle_widget_batch(session_id, &changes),
+            Event::FormSubmitted { form, submit_key, changes } => {
+                if self.is_submit_button(&form, &submit_key)
+                    && !self.is_disabled(&submit_key)
+                    && let Ok(mut submitted) = self.submitted.lock()
+                {
+                    *submitted = Some(submit_key);
+                }
+                self.handle_widget_batch(session_id, &changes)
+            }
+            Event::FileUploaded { key, files } => {
+                let files = serde_json::to_string(&files).map_err(|e| e.to_string())?;
+                self.handle_widget_change(session_id, &key, &files)
+            }
+            Event::PageChanged { page } => self.handle_widget_change(session_id, PAGE_WIDGET_KEY, &page),
+            Event::ClientContext { info } => self.handle_client_context(session_id, info),
+            Event::ThemePreference { mode } => self.handle_theme_preference(session_id, mode),
+            Event::TransferProgress { progress } => {
+                let value = serde_json::to_string(&progress).map_err(|e| e.to_string())?;
+                self.handle_widget_change(session_id, &transfer::widget_key(&progress.key), &value)
+            }
+            Event::ChartSelection { key, selection } => {
+                let value = serde_json::to_string(&selection).map_err(|e| e.to_string())?;
+                self.handle_widget_change(session_id, &selection::widget_key(&key), &value)
+            }
+            Event::ElementVisible { key } => self.handle_widget_change(session_id, &lazy::widget_key(&key), "true"),
+            Event::HostMessage { origin, data } => {
+                self.host_messages.push(HostMessage { origin, data });
+                self.execute_script(session_id)
+            }
+            Event::ButtonClicked { .. } | Event::RerunScript | Event::Custom { .. } => {
+                self.execute_script(session_id)
+            }
+        }
+    }
+
+    /// Get the app's event handlers
+    pub fn events(&self) -> &EventDispatcher {
+        &self.events
+    }
+
+    /// Get when the deferred rerun of a debounced widget is due
+    pub fn rerun_deadline(&self) -> Option<Instant> {
+        self.pending_rerun.lock().ok().and_then(|pending| *pending)
+    }
+
+    /// Run the deferred rerun, if one is pending
+    pub fn run_pending(&self, session_id: SessionId) -> Option<Result<Vec<Delta>, String>> {
+        self.rerun_deadline()?;
+        Some(self.execute_script(session_id))
+    }
+
+    /// Store several widget changes together and rerun script once
+    pub fn handle_widget_batch(&self, session_id: SessionId, changes: &[WidgetChange]) -> Result<Vec<Delta>, String> {
+        // One lock for the whole batch, so no run sees half of it
+        if let Ok(mut state) = self.widget_state.lock() {
+            for change in changes.iter().filter(|change| !self.is_disabled(&change.key)) {
+                state.insert(change.key.clone(), widget_value_string(&change.value));
+            }
+            tracing::debug!("Stored {} widget changes", changes.len());
+        }
+
+        self.execute_script(session_id)
+    }
+
+    fn store_widget_value(&self, key: &str, value: &serde_json::Value) {
+        if let Ok(mut state) = self.widget_state.lock() {
+            let value = widget_value_string(value);
+            tracing::debug!("Stored widget state: {} = {}", key, value);
+            state.insert(key.to_string(), value);
+        }
     }
 
     /// Handle widget state change and rerun script
@@ -75,7 +813,9 @@ impl ScriptExecutor {
         value: &str,
     ) -> Result<Vec<Delta>, String> {
         // Store widget state
-        if let Ok(mut state) = self.widget_state.lock() {
+        if !self.is_disabled(widget_key)
+            && let Ok(mut state) = self.widget_state.lock()
+        {
             state.insert(widget_key.to_string(), value.to_string());
             tracing::debug!("Stored widget state: {} = {}", widget_key, value);
         }
@@ -84,10 +824,74 @@ impl ScriptExecutor {
         self.execute_script(session_id)
     }
 
+    /// Handle an updated client context and rerun script
+    pub fn handle_client_context(
+        &self,
+        session_id: SessionId,
+        client_info: ClientInfo,
+    ) -> Result<Vec<Delta>, String> {
+        if let Ok(mut info) = self.client_info.lock() {
+            tracing::debug!("Updated client context: {:?}", client_info);
+            *info = client_info;
+        }
+
+        self.execute_script(session_id)
+    }
+
+    /// Take the actions queued by the last run
+    pub fn take_actions(&self) -> Vec<Action> {
+        self.actions.take()
+    }
+
+    /// Get the session's log buffers
+    pub fn logs(&self) -> &LogStore {
+        &self.logs
+    }
+
+    /// Persist a theme preference on the session and rerun script
+    pub fn handle_theme_preference(
+        &self,
+        session_id: SessionId,
+        mode: ThemeMode,
+    ) -> Result<Vec<Delta>, String> {
+        let mut session = self
+            .session_store
+            .get_session(session_id)
+            .map_err(|e| e.to_string())?;
+        session.set_metadata(THEME_METADATA_KEY.to_string(), mode.as_str().to_string());
+        self.session_store
+            .update_session(session)
+            .map_err(|e| e.to_string())?;
+
+        self.execute_script(session_id)
+    }
+
+    /// Get the session's theme mode (auto unless the user picked one)
+    pub fn theme_mode(&self, session_id: SessionId) -> ThemeMode {
+        self.session_store
+            .get_session(session_id)
+            .ok()
+            .and_then(|session| session.get_metadata(THEME_METADATA_KEY).and_then(ThemeMode::parse))
+            .unwrap_or_default()
+    }
+
+    /// Get the session's theme mode and the color scheme it resolves to
+    pub fn theme(&self, session_id: SessionId) -> (ThemeMode, ColorScheme) {
+        let mode = self.theme_mode(session_id);
+        let client_info = self
+            .client_info
+            .lock()
+            .map(|info| info.clone())
+            .unwrap_or_default();
+        (mode, mode.resolve(&client_info))
+    }
+
     /// Run the application logic
     fn run_app(&self, st: &mut St) -> Result<(), String> {
-        if let Some(app_fn) = self.app_fn {
-            app_fn(st)
+        if let Some(app_fn) = self.app.current() {
+            // A panic outside the app's own boundaries shows as an inline
+            // error, instead of failing the run
+            st.boundary("App", app_fn).unwrap_or(Ok(()))
         } else {
             // Default demo app
             st.title("Platypus Demo");
@@ -108,13 +912,14 @@ impl ScriptExecutor {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use platypus_core::session::SessionId;
+    use std::sync::atomic::AtomicUsize;
+    
 
     #[test]
     fn test_executor_creation() {
         let session_store = Arc::new(SessionStore::new());
         let executor = ScriptExecutor::new(session_store);
-        assert_eq!(std::mem::size_of_val(&executor) > 0, true);
+        assert!(std::mem::size_of_val(&executor) > 0);
     }
 
     #[test]
@@ -130,6 +935,96 @@ mod tests {
         assert!(!deltas.is_empty(), "Script should generate deltas");
     }
 
+    #[test]
+    fn test_handle_client_context() {
+        fn app(st: &mut St) -> Result<(), String> {
+            let width = st.client_info().viewport_width;
+            st.write(format!("width={}", width));
+            Ok(())
+        }
+
+        let session_store = Arc::new(SessionStore::new());
+        let executor = ScriptExecutor::with_app(session_store.clone(), app);
+        let session_id = session_store.create_session("test".to_string());
+
+        let info = ClientInfo {
+            viewport_width: 800,
+            ..Default::default()
+        };
+        let deltas = executor.handle_client_context(session_id, info.clone()).unwrap();
+        match &deltas[0] {
+            Delta::AddElement { element: platypus_core::element::ElementType::Text { value }, .. } => {
+                assert_eq!(value, "width=800");
+            }
+            other => panic!("unexpected delta: {:?}", other),
+        }
+
+        // A focus move alone is recorded without a rerun
+        let focused = ClientInfo {
+            focused_widget: Some("name".to_string()),
+            ..info
+        };
+        let event = Event::ClientContext { info: focused.clone() };
+        assert!(executor.handle_event(session_id, event).unwrap().is_none());
+        assert_eq!(*executor.client_info.lock().unwrap(), focused);
+    }
+
+    #[test]
+    fn test_theme_preference_persists() {
+        fn app(st: &mut St) -> Result<(), String> {
+            let mode = st.theme_toggle("Theme", None);
+            st.write(mode.as_str());
+            Ok(())
+        }
+
+        let session_store = Arc::new(SessionStore::new());
+        let executor = ScriptExecutor::with_app(session_store.clone(), app);
+        let session_id = session_store.create_session("test".to_string());
+
+        let dark = ClientInfo {
+            color_scheme: ColorScheme::Dark,
+            ..Default::default()
+        };
+        executor.handle_client_context(session_id, dark).unwrap();
+        assert_eq!(executor.theme(session_id), (ThemeMode::Auto, ColorScheme::Dark));
+
+        executor.handle_theme_preference(session_id, ThemeMode::Light).unwrap();
+        assert_eq!(executor.theme(session_id), (ThemeMode::Light, ColorScheme::Light));
+
+        let deltas = executor.execute_script(session_id).unwrap();
+        match &deltas[1] {
+            Delta::AddElement { element: platypus_core::element::ElementType::Text { value }, .. } => {
+                assert_eq!(value, "light");
+            }
+            other => panic!("unexpected delta: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_persistent_notifications() {
+        fn app(st: &mut St) -> Result<(), String> {
+            if st.text_input("Action", "", Some("action".to_string())) == "import" {
+                st.notify_persistent(platypus_runtime::NotificationLevel::Success, "Imported");
+            }
+            Ok(())
+        }
+
+        let session_store = Arc::new(SessionStore::new());
+        let executor = ScriptExecutor::with_app(session_store.clone(), app);
+        let session_id = session_store.create_session("test".to_string());
+
+        executor.handle_widget_change(session_id, "action", "import").unwrap();
+        executor.handle_widget_change(session_id, "action", "").unwrap();
+        assert_eq!(executor.notifications.len(), 1);
+
+        let dismissed = executor.notifications.all()[0].id.clone();
+        let state = format!(r#"{{"read":[],"dismissed":["{}"]}}"#, dismissed);
+        executor
+            .handle_widget_change(session_id, platypus_runtime::notifications::NOTIFICATIONS_KEY, &state)
+            .unwrap();
+        assert!(executor.notifications.is_empty());
+    }
+
     #[test]
     fn test_handle_widget_change() {
         let session_store = Arc::new(SessionStore::new());
@@ -143,4 +1038,262 @@ mod tests {
         );
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_handle_event_dispatches_and_applies() {
+        fn app(st: &mut St) -> Result<(), String> {
+            let page = st.page().unwrap_or_else(|| "home".to_string());
+            st.write(format!("page={}", page));
+            Ok(())
+        }
+
+        let session_store = Arc::new(SessionStore::new());
+        let executor = ScriptExecutor::with_app(session_store.clone(), app);
+        let session_id = session_store.create_session("test".to_string());
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&seen);
+        executor.events().subscribe("log", move |event| log.lock().unwrap().push(event.name().to_string()));
+
+        let deltas = executor
+            .handle_event(session_id, Event::PageChanged { page: "settings".to_string() })
+            .unwrap()
+            .unwrap();
+        match &deltas[0] {
+            Delta::AddElement { element: platypus_core::element::ElementType::Text { value }, .. } => {
+                assert_eq!(value, "page=settings");
+            }
+            other => panic!("unexpected delta: {:?}", other),
+        }
+        assert_eq!(*seen.lock().unwrap(), vec!["page_changed".to_string()]);
+    }
+
+    #[test]
+    fn test_widget_batch_and_debounce() {
+        fn app(st: &mut St) -> Result<(), String> {
+            let a = st.text_input("A", "", Some("a".to_string()));
+            let b = st.text_input("B", "", Some("b".to_string()));
+            st.write(format!("{}{}", a, b));
+            Ok(())
+        }
+
+        let session_store = Arc::new(SessionStore::new());
+        let debounce = HashMap::from([("a".to_string(), Duration::from_millis(50))]);
+        let executor = ScriptExecutor::with_app(session_store.clone(), app).with_debounce(Arc::new(debounce));
+        let session_id = session_store.create_session("test".to_string());
+        let runs = || session_store.get_session(session_id).unwrap().reruns;
+
+        let change = |key: &str, value: &str| WidgetChange { key: key.to_string(), value: value.into() };
+        let batch = Event::WidgetsChanged { changes: vec![change("a", "x"), change("b", "y")] };
+        assert!(executor.handle_event(session_id, batch).unwrap().is_some());
+        assert_eq!(runs(), 1);
+
+        let drag = Event::WidgetChanged { key: "a".to_string(), value: "z".into() };
+        assert!(executor.handle_event(session_id, drag).unwrap().is_none());
+        assert_eq!(runs(), 1);
+        assert!(executor.rerun_deadline().is_some());
+
+        let deltas = executor.run_pending(session_id).unwrap().unwrap();
+        assert!(deltas.iter().any(|delta| matches!(
+            delta,
+            Delta::AddElement { element: platypus_core::element::ElementType::Text { value }, .. } if value == "zy"
+        )));
+        assert!(executor.run_pending(session_id).is_none());
+    }
+
+    #[test]
+    fn test_form_submit_runs_once() {
+        fn app(st: &mut St) -> Result<(), String> {
+            let form = st.form("signup");
+            let mut form = form.st();
+            let name = form.text_input("Name", "", Some("name".to_string()));
+            if form.form_submit_button("Sign up") {
+                st.write(format!("Welcome {}", name));
+            }
+            Ok(())
+        }
+
+        let session_store = Arc::new(SessionStore::new());
+        let executor = ScriptExecutor::with_app(session_store.clone(), app);
+        let session_id = session_store.create_session("test".to_string());
+        let welcomed = |deltas: &[Delta]| {
+            deltas.iter().any(|delta| matches!(
+                delta,
+                Delta::AddElement { element: platypus_core::element::ElementType::Text { value }, .. } if value == "Welcome Ada"
+            ))
+        };
+
+        let submit_key = executor
+            .execute_script(session_id)
+            .unwrap()
+            .into_iter()
+            .find_map(|delta| match delta {
+                Delta::AddElement { element: platypus_core::element::ElementType::FormSubmitButton { key, .. }, .. } => key,
+                _ => None,
+            })
+            .unwrap();
+        let submit = |form: &str, submit_key: &str| Event::FormSubmitted {
+            form: form.to_string(),
+            submit_key: submit_key.to_string(),
+            changes: vec![WidgetChange { key: "name".to_string(), value: "Ada".into() }],
+        };
+        // Only the button the run showed submits its form
+        assert!(!welcomed(&executor.handle_event(session_id, submit("signup", "other")).unwrap().unwrap()));
+        assert!(!welcomed(&executor.handle_event(session_id, submit("other", &submit_key)).unwrap().unwrap()));
+        assert!(welcomed(&executor.handle_event(session_id, submit("signup", &submit_key)).unwrap().unwrap()));
+        // The button reads as pressed for the submitting run only
+        assert!(!welcomed(&executor.execute_script(session_id).unwrap()));
+    }
+
+    #[test]
+    fn test_rate_limited_changes_coalesce() {
+        fn app(st: &mut St) -> Result<(), String> {
+            st.rate_limit("volume", RateLimit::throttle(60_000));
+            let volume = st.slider("Volume", 0.0, 10.0, 0.0, Some("volume".to_string()));
+            st.write(format!("volume={}", volume));
+            Ok(())
+        }
+
+        let session_store = Arc::new(SessionStore::new());
+        let executor = ScriptExecutor::with_app(session_store.clone(), app);
+        let session_id = session_store.create_session("test".to_string());
+        executor.execute_script(session_id).unwrap();
+
+        for value in [3, 4, 5] {
+            let drag = Event::WidgetChanged { key: "volume".to_string(), value: value.into() };
+            assert!(executor.handle_event(session_id, drag).unwrap().is_none());
+        }
+        assert_eq!(session_store.get_session(session_id).unwrap().reruns, 1);
+
+        let deltas = executor.run_pending(session_id).unwrap().unwrap();
+        assert!(deltas.iter().any(|delta| matches!(
+            delta,
+            Delta::AddElement { element: ElementType::Text { value }, .. } if value == "volume=5"
+        )));
+    }
+
+    #[test]
+    fn test_determinism_check_warns() {
+        fn app(st: &mut St) -> Result<(), String> {
+            static RUNS: AtomicUsize = AtomicUsize::new(0);
+            st.write("stable");
+            st.button("Go", Some(format!("go_{}", RUNS.fetch_add(1, Ordering::Relaxed))));
+            Ok(())
+        }
+
+        let session_store = Arc::new(SessionStore::new());
+        let executor = ScriptExecutor::with_app(session_store.clone(), app).with_determinism_check(true);
+        let session_id = session_store.create_session("test".to_string());
+
+        let deltas = executor.execute_script(session_id).unwrap();
+        let warnings: Vec<&String> = deltas
+            .iter()
+            .filter_map(|delta| match delta {
+                Delta::AddElement { element: ElementType::Warning { message }, .. } => Some(message),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("Element 2 (button)"));
+    }
+
+    #[test]
+    fn test_app_panic_renders_inline() {
+        fn app(st: &mut St) -> Result<(), String> {
+            st.write("Loaded");
+            let rows: Vec<u32> = Vec::new();
+            st.write(format!("First row: {}", rows[0]));
+            Ok(())
+        }
+
+        let session_store = Arc::new(SessionStore::new());
+        let session_id = session_store.create_session("test".to_string());
+        let deltas = ScriptExecutor::with_app(session_store.clone(), app).execute_script(session_id).unwrap();
+        assert_eq!(deltas.len(), 1);
+        assert!(matches!(
+            &deltas[0],
+            Delta::AddElement { element: ElementType::Error { message }, .. } if message.starts_with("App failed to render: index out of bounds")
+        ));
+
+        let executor = ScriptExecutor::with_app(session_store, app).with_fatal_element_errors(true);
+        assert!(executor.execute_script(session_id).is_err());
+    }
+
+    #[test]
+    fn test_lazy_builds_once_visible() {
+        fn app(st: &mut St) -> Result<(), String> {
+            st.lazy("appendix", |st| st.write("Appendix"));
+            Ok(())
+        }
+
+        let session_store = Arc::new(SessionStore::new());
+        let executor = ScriptExecutor::with_app(session_store.clone(), app);
+        let session_id = session_store.create_session("test".to_string());
+        let deltas = executor.execute_script(session_id).unwrap();
+        assert!(matches!(&deltas[0], Delta::AddElement { element: ElementType::Lazy { key }, .. } if key == "appendix"));
+
+        let event = Event::ElementVisible { key: "appendix".to_string() };
+        let deltas = executor.handle_event(session_id, event).unwrap().unwrap();
+        assert!(matches!(&deltas[0], Delta::AddElement { element: ElementType::Text { value }, .. } if value == "Appendix"));
+    }
+
+    #[test]
+    fn test_host_messages_from_allowed_origins() {
+        fn app(st: &mut St) -> Result<(), String> {
+            for message in st.host_messages().to_vec() {
+                st.write(format!("{} {}", message.origin, message.data));
+                st.post_to_host(message.data);
+            }
+            Ok(())
+        }
+
+        let session_store = Arc::new(SessionStore::new());
+        let executor = ScriptExecutor::with_app(session_store.clone(), app)
+            .with_host_origins(vec!["https://portal.example.com".to_string()]);
+        let session_id = session_store.create_session("test".to_string());
+        let message = |origin: &str| Event::HostMessage { origin: origin.to_string(), data: serde_json::json!(7) };
+        assert!(executor.handle_event(session_id, message("https://evil.example.com")).unwrap().is_none());
+
+        let deltas = executor.handle_event(session_id, message("https://portal.example.com")).unwrap().unwrap();
+        assert!(matches!(&deltas[0], Delta::AddElement { element: ElementType::Text { value }, .. } if value == "https://portal.example.com 7"));
+        assert_eq!(executor.take_actions(), vec![Action::PostToHost { data: serde_json::json!(7) }]);
+    }
+
+    #[test]
+    fn test_disabled_widget_ignores_changes() {
+        fn app(st: &mut St) -> Result<(), String> {
+            st.disable_all(true);
+            let name = st.text_input("Name", "Ada", Some("name".to_string()));
+            st.write(format!("name={}", name));
+            Ok(())
+        }
+
+        let session_store = Arc::new(SessionStore::new());
+        let executor = ScriptExecutor::with_app(session_store.clone(), app);
+        let session_id = session_store.create_session("test".to_string());
+        executor.execute_script(session_id).unwrap();
+
+        let event = Event::WidgetChanged { key: "name".to_string(), value: serde_json::json!("Grace") };
+        let deltas = executor.handle_event(session_id, event).unwrap().unwrap();
+        assert!(deltas.iter().any(|delta| matches!(
+            delta,
+            Delta::AddElement { element: ElementType::Text { value }, .. } if value == "name=Ada"
+        )));
+    }
+
+    #[test]
+    fn test_run_stops_at_deadline() {
+        fn app(st: &mut St) -> Result<(), String> {
+            st.write("Loading");
+            loop {
+                st.checkpoint()?;
+            }
+        }
+
+        let session_store = Arc::new(SessionStore::new());
+        let session_id = session_store.create_session("test".to_string());
+        let executor = ScriptExecutor::with_app(session_store, app).with_run_timeout(Some(Duration::ZERO));
+        assert_eq!(executor.execute_script(session_id).err().as_deref(), Some("Run exceeded its deadline"));
+        assert!(executor.snapshot().is_empty());
+    }
 }
@@ -0,0 +1,241 @@
+//! IP allow/deny lists and request filtering.
+//!
+//! Apps are often served straight to an internal network, with no proxy in
+//! front to filter requests, so the server does the basics itself: it
+//! refuses clients outside [`FirewallConfig::allow`] or inside
+//! [`FirewallConfig::deny`], paths trying to climb out of their route
+//! (`..` segments, in any encoding, backslashes and NUL bytes), and
+//! requests whose headers exceed [`FirewallConfig::max_header_bytes`].
+//! Addresses are those of the connecting peers, e.g.:
+//!
+//! ```toml
+//! [firewall]
+//! allow = ["10.0.0.0/8", "192.168.1.20"]
+//! deny = ["10.66.0.0/16"]
+//! ```
+
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::HeaderMap;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::config;
+use crate::error::Error;
+
+/// An IP address, or a network in CIDR notation such as `10.0.0.0/8`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct IpNet {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpNet {
+    /// Check whether `ip` is in the network.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip.to_canonical()) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => prefix_matches(u32::from(net).into(), u32::from(ip).into(), 32, self.prefix),
+            (IpAddr::V6(net), IpAddr::V6(ip)) => prefix_matches(net.into(), ip.into(), 128, self.prefix),
+            _ => false,
+        }
+    }
+}
+
+fn prefix_matches(net: u128, ip: u128, bits: u8, prefix: u8) -> bool {
+    let shift = bits - prefix;
+    shift == bits || (net ^ ip) >> shift == 0
+}
+
+impl FromStr for IpNet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix) = s.split_once('/').unwrap_or((s, ""));
+        let addr = IpAddr::from_str(addr.trim())
+            .map_err(|e| format!("Invalid address {}: {}", s, e))?
+            .to_canonical();
+        let bits = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            "" => bits,
+            prefix => prefix
+                .parse()
+                .ok()
+                .filter(|prefix| *prefix <= bits)
+                .ok_or_else(|| format!("Invalid prefix length in {}", s))?,
+        };
+        Ok(IpNet { addr, prefix })
+    }
+}
+
+impl TryFrom<String> for IpNet {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<IpNet> for String {
+    fn from(net: IpNet) -> Self {
+        net.to_string()
+    }
+}
+
+impl fmt::Display for IpNet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+
+/// Which clients and requests the server accepts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FirewallConfig {
+    /// Clients allowed to connect; any client when empty.
+    pub allow: Vec<IpNet>,
+    /// Clients refused even when allowed.
+    pub deny: Vec<IpNet>,
+    /// Largest total size (bytes) of a request's headers.
+    pub max_header_bytes: usize,
+}
+
+impl Default for FirewallConfig {
+    fn default() -> Self {
+        FirewallConfig {
+            allow: Vec::new(),
+            deny: Vec::new(),
+            max_header_bytes: config::DEFAULT_MAX_HEADER_BYTES,
+        }
+    }
+}
+
+impl FirewallConfig {
+    /// Check whether the client at `ip` may connect.
+    pub fn allows(&self, ip: IpAddr) -> bool {
+        (self.allow.is_empty() || self.allow.iter().any(|net| net.contains(ip)))
+            && !self.deny.iter().any(|net| net.contains(ip))
+    }
+
+    /// Check a request from `peer`, if known, before it is routed.
+    pub fn check(&self, peer: Option<IpAddr>, path: &str, headers: &HeaderMap) -> Result<(), Error> {
+        if let Some(ip) = peer.filter(|ip| !self.allows(*ip)) {
+            return Err(Error::forbidden(format!("Address {} is not allowed", ip)));
+        }
+        if escapes_route(path) {
+            return Err(Error::bad_request("Invalid path"));
+        }
+        let header_bytes: usize = headers.iter().map(|(name, value)| name.as_str().len() + value.len()).sum();
+        if header_bytes > self.max_header_bytes {
+            return Err(Error::headers_too_large(format!(
+                "Headers of {} bytes exceed the limit of {} bytes",
+                header_bytes, self.max_header_bytes
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Check whether a request path tries to leave its route: a `.` or `..`
+/// segment, a backslash or a NUL byte, even percent-encoded (repeatedly).
+pub fn escapes_route(path: &str) -> bool {
+    let mut path = path.to_string();
+    // Three rounds catch double and triple encoding
+    for _ in 0..3 {
+        let suspicious = path.contains(['\\', '\0']) || path.split('/').any(|segment| segment == ".." || segment == ".");
+        if suspicious {
+            return true;
+        }
+        let decoded = percent_decode(&path);
+        if decoded == path {
+            return false;
+        }
+        path = decoded;
+    }
+    true
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|hex| std::str::from_utf8(hex).ok());
+        match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+            Some(byte) if bytes[i] == b'%' => {
+                decoded.push(byte);
+                i += 3;
+            }
+            _ => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Refuse requests the firewall config does not accept, before routing.
+pub async fn filter_requests(State(config): State<Arc<FirewallConfig>>, request: Request, next: Next) -> Response {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+    match config.check(peer, request.uri().path(), request.headers()) {
+        Ok(()) => next.run(request).await,
+        Err(error) => {
+            tracing::warn!("Refused {} {} from {:?}: {}", request.method(), request.uri().path(), peer, error);
+            error.into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allow_and_deny_lists() {
+        let net = |s: &str| s.parse::<IpNet>().unwrap();
+        let config = FirewallConfig {
+            allow: vec![net("10.0.0.0/8"), net("fd00::/8"), net("192.168.1.20")],
+            deny: vec![net("10.66.0.0/16")],
+            ..Default::default()
+        };
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+        assert!(config.allows(ip("10.1.2.3")));
+        assert!(config.allows(ip("::ffff:10.1.2.3")));
+        assert!(config.allows(ip("fd12::1")));
+        assert!(config.allows(ip("192.168.1.20")));
+        assert!(!config.allows(ip("192.168.1.21")));
+        assert!(!config.allows(ip("10.66.4.5")));
+        assert!(FirewallConfig::default().allows(ip("8.8.8.8")));
+        assert!("10.0.0.0/33".parse::<IpNet>().is_err());
+        assert_eq!(net("0.0.0.0/0").to_string(), "0.0.0.0/0");
+        assert!(net("0.0.0.0/0").contains(ip("8.8.8.8")));
+    }
+
+    #[test]
+    fn test_request_filtering() {
+        for path in ["/media/../secret", "/media/%2e%2e/secret", "/media/%252E%252e/x", "/a%5cb", "/a%00", "/./ws"] {
+            assert!(escapes_route(path), "{}", path);
+        }
+        for path in ["/", "/media/abc.png", "/share/a..b", "/embed/app/kpis%20q3"] {
+            assert!(!escapes_route(path), "{}", path);
+        }
+
+        let config = FirewallConfig {
+            max_header_bytes: 32,
+            ..Default::default()
+        };
+        let mut headers = HeaderMap::new();
+        headers.insert("x-small", "1".parse().unwrap());
+        assert!(config.check(None, "/", &headers).is_ok());
+        headers.insert("cookie", "a".repeat(64).parse().unwrap());
+        assert!(matches!(config.check(None, "/", &headers), Err(Error::HeadersTooLarge(_))));
+    }
+}
@@ -1,14 +1,25 @@
 //! HTTP request handlers.
 
 use axum::{
-    extract::State,
-    response::{Html, IntoResponse},
+    body::Bytes,
+    extract::{Path, Query, State},
+    response::{Html, IntoResponse, Response},
     Json,
-    http::StatusCode,
+    http::{header, HeaderMap, StatusCode},
 };
-use serde_json::json;
+use platypus_core::session::SessionId;
+use platypus_runtime::transfer::{ChunkRange, UploadError, UploadMeta};
+use platypus_runtime::secrets::constant_time_eq;
+use platypus_runtime::share::ShareError;
+use platypus_runtime::{render, SessionSnapshot};
+use serde::Deserialize;
+use serde_json::{json, Value};
 use std::sync::Arc;
 
+use crate::cluster::ClusterStatus;
+use crate::embed;
+use crate::error::{Error, Result};
+use crate::executor::ScriptExecutor;
 use crate::server::ServerState;
 
 /// Health check endpoint.
@@ -26,7 +37,8 @@ pub async fn app_info(State(state): State<Arc<ServerState>>) -> Json<serde_json:
     Json(json!({
         "name": &state.config.app_name,
         "sessions": session_count,
-        "uptime": state.start_time.elapsed().as_secs()
+        "uptime": state.start_time.elapsed().as_secs(),
+        "media": state.media_store.stats()
     }))
 }
 
@@ -35,6 +47,259 @@ pub async fn index() -> Html<&'static str> {
     Html(include_str!("../frontend/index.html"))
 }
 
+/// Serve a stored media file as an attachment.
+pub async fn media(
+    State(state): State<Arc<ServerState>>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse> {
+    let file = state
+        .media_store
+        .get(&id)
+        .ok_or_else(|| Error::not_found(format!("Media not found: {}", id)))?;
+
+    Ok((
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, file.mime_type),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", file.file_name),
+            ),
+        ],
+        file.data,
+    ))
+}
+
+/// Receive one chunk of a resumable upload.
+///
+/// The chunk's place in the file comes from its `Content-Range` header
+/// (`bytes 0-1048575/5242880`); without one the body is the whole file.
+/// Answers 201 once the upload is complete, and 409 with the bytes
+/// received so far when the chunk does not continue the upload.
+pub async fn upload_chunk(
+    State(state): State<Arc<ServerState>>,
+    Path(id): Path<String>,
+    Query(meta): Query<UploadMeta>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response> {
+    let content_range = headers.get(header::CONTENT_RANGE).and_then(|value| value.to_str().ok());
+    let range = parse_content_range(content_range, body.len() as u64)
+        .ok_or_else(|| Error::bad_request(format!("Invalid Content-Range: {:?}", content_range)))?;
+
+    match state.transfers.append(&id, &meta, range, &body, &state.media_store) {
+        Ok(progress) => {
+            let status = if progress.is_complete() { StatusCode::CREATED } else { StatusCode::OK };
+            Ok((status, Json(progress)).into_response())
+        }
+        Err(UploadError::OutOfOrder { received }) => Ok((
+            StatusCode::CONFLICT,
+            Json(json!({ "error": "Chunk out of order", "received": received })),
+        )
+            .into_response()),
+        Err(UploadError::InvalidRange) => Err(Error::bad_request("Chunk does not fit the upload")),
+    }
+}
+
+/// Get the progress of an upload, to resume it after an interruption.
+pub async fn upload_status(
+    State(state): State<Arc<ServerState>>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse> {
+    state
+        .transfers
+        .progress(&id)
+        .map(Json)
+        .ok_or_else(|| Error::not_found(format!("Upload not found: {}", id)))
+}
+
+/// Parse a `Content-Range: bytes start-end/total` header for a chunk of
+/// `len` bytes.
+fn parse_content_range(value: Option<&str>, len: u64) -> Option<ChunkRange> {
+    let Some(value) = value else {
+        return Some(ChunkRange { start: 0, total: len });
+    };
+    let (range, total) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let total = total.parse().ok()?;
+    if range == "*" {
+        // An empty file has no byte range
+        return (len == 0).then_some(ChunkRange { start: total, total });
+    }
+    let (start, end) = range.split_once('-')?;
+    let (start, end): (u64, u64) = (start.parse().ok()?, end.parse().ok()?);
+    (end >= start && end - start + 1 == len).then_some(ChunkRange { start, total })
+}
+
+/// Export a session as a snapshot. Requires the admin token.
+pub async fn export_session(
+    State(state): State<Arc<ServerState>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<SessionSnapshot>> {
+    authorize_admin(&state, &headers)?;
+    let session_id = parse_session_id(&id)?;
+    let snapshot = state
+        .session_store
+        .export(session_id)
+        .map_err(|_| Error::not_found(format!("Session not found: {}", id)))?;
+    Ok(Json(snapshot))
+}
+
+/// Import a session snapshot. Requires the admin token; the response
+/// carries the URL that resumes the session in a browser.
+pub async fn import_session(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    Json(snapshot): Json<SessionSnapshot>,
+) -> Result<impl IntoResponse> {
+    authorize_admin(&state, &headers)?;
+    let session_store = Arc::clone(&state.session_store);
+    let session = tokio::task::spawn_blocking(move || {
+        session_store.import(snapshot).and_then(|session_id| session_store.get_session(session_id))
+    })
+    .await
+    .map_err(|e| Error::internal(e.to_string()))?
+    .map_err(|e| Error::bad_request(e.to_string()))?;
+    Ok((
+        StatusCode::CREATED,
+        Json(json!({
+            "session_id": session.id,
+            "url": format!("{}?session={}&token={}", crate::config::INDEX_PATH, session.id, session.resume_token),
+        })),
+    ))
+}
+
+/// Body of an app swap request.
+#[derive(Debug, Deserialize)]
+pub struct SwapRequest {
+    /// Registered version to run from now on.
+    pub version: String,
+}
+
+/// Swap the app to a version registered with `AppServer::with_app_version`.
+/// Connected sessions rerun with it. Requires the admin token.
+pub async fn swap_app(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    Json(request): Json<SwapRequest>,
+) -> Result<Json<Value>> {
+    authorize_admin(&state, &headers)?;
+    if !state.app.swap_to(&request.version) {
+        return Err(Error::not_found(format!(
+            "No app version {}; registered: {}",
+            request.version,
+            state.app.versions().join(", ")
+        )));
+    }
+    tracing::info!("Swapped app to version {}", request.version);
+    Ok(Json(json!({
+        "version": request.version,
+        "sessions": state.session_store.session_count(),
+    })))
+}
+
+/// Render a session's current page to a static artifact, as requested by
+/// the file extension: `{session_id}.html` or `{session_id}.png`. The app
+/// reruns with the session's widget values. Requires the admin token.
+pub async fn render_session(
+    State(state): State<Arc<ServerState>>,
+    Path(file): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    authorize_admin(&state, &headers)?;
+    let (id, extension) = file
+        .rsplit_once('.')
+        .ok_or_else(|| Error::bad_request(format!("Missing format in {}; use .html or .png", file)))?;
+    let session_id = parse_session_id(id)?;
+    let session = state
+        .session_store
+        .get_session(session_id)
+        .map_err(|_| Error::not_found(format!("Session not found: {}", id)))?;
+    let app_fn = state.app.current().ok_or_else(|| Error::not_found("No app to render"))?;
+
+    let executor = ScriptExecutor::with_app(Arc::clone(&state.session_store), app_fn)
+        .with_media_store(state.media_store.clone());
+    executor.restore_session(session);
+    // The run writes the session to the shared store, whose calls block
+    let deltas = tokio::task::spawn_blocking(move || executor.execute_script(session_id))
+        .await
+        .map_err(|e| Error::internal(e.to_string()))?
+        .map_err(Error::internal)?;
+    let elements = render::page_elements(&deltas);
+
+    match extension {
+        "html" => Ok(Html(render::render_html(&state.config.app_name, &elements)).into_response()),
+        "png" => Ok(([(header::CONTENT_TYPE, "image/png")], render::render_png(&elements)).into_response()),
+        other => Err(Error::bad_request(format!("Unsupported format: {}; use .html or .png", other))),
+    }
+}
+
+/// Query parameters of a shared snapshot.
+#[derive(Debug, Deserialize)]
+pub struct ShareQuery {
+    /// Passcode, for snapshots shared with one.
+    pub passcode: Option<String>,
+}
+
+/// Serve a snapshot shared with `st.share_snapshot`, as frozen when it was
+/// shared. The app does not run.
+pub async fn shared_snapshot(
+    State(state): State<Arc<ServerState>>,
+    Path(token): Path<String>,
+    Query(query): Query<ShareQuery>,
+) -> Result<Response> {
+    match state.shares.view(&token, query.passcode.as_deref()) {
+        // Views may be limited, so caches must not serve extra ones
+        Ok(html) => Ok(([(header::CACHE_CONTROL, "no-store")], Html(html)).into_response()),
+        Err(ShareError::NotFound) => Err(Error::not_found("Shared snapshot not found or expired")),
+        Err(ShareError::Forbidden) => Err(Error::unauthorized("Invalid passcode")),
+        Err(ShareError::Locked) => Err(Error::forbidden("Too many invalid passcodes; try again later")),
+    }
+}
+
+/// Serve the page framing an app's fragment, for the sites the embed
+/// config allows.
+pub async fn embed(State(state): State<Arc<ServerState>>, Path((app, fragment)): Path<(String, String)>) -> Result<Response> {
+    let config = &state.config.embed;
+    if !config.enabled() || app != embed::app_slug(&state.config.app_name) {
+        return Err(Error::not_found(format!("No embeddable app: {}", app)));
+    }
+    Ok((
+        [(header::CONTENT_SECURITY_POLICY, config.content_security_policy())],
+        Html(embed::embed_page(&fragment, config)),
+    )
+        .into_response())
+}
+
+/// Get this node's cluster status: its sessions, shared backend and peers.
+pub async fn cluster_status(State(state): State<Arc<ServerState>>) -> Json<ClusterStatus> {
+    Json(ClusterStatus::of(&state))
+}
+
+fn parse_session_id(id: &str) -> Result<SessionId> {
+    uuid::Uuid::parse_str(id)
+        .map(SessionId::from_uuid)
+        .map_err(|_| Error::bad_request(format!("Invalid session ID: {}", id)))
+}
+
+/// Check the `Authorization: Bearer` header against the admin token. The
+/// admin endpoints do not exist unless a token is configured.
+fn authorize_admin(state: &ServerState, headers: &HeaderMap) -> Result<()> {
+    let Some(token) = state.config.admin_token.as_deref().filter(|token| !token.is_empty()) else {
+        return Err(Error::not_found("Admin API is disabled"));
+    };
+    let given = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    if constant_time_eq(given.as_bytes(), token.as_bytes()) {
+        Ok(())
+    } else {
+        Err(Error::unauthorized("Invalid admin token"))
+    }
+}
+
 /// Serve favicon.
 pub async fn favicon() -> impl IntoResponse {
     // Simple 1x1 transparent PNG favicon
@@ -63,4 +328,72 @@ mod tests {
         let json = response.0;
         assert_eq!(json["status"], "ok");
     }
+
+    #[test]
+    fn test_parse_content_range() {
+        assert_eq!(
+            parse_content_range(Some("bytes 100-199/1000"), 100),
+            Some(ChunkRange { start: 100, total: 1000 })
+        );
+        assert_eq!(parse_content_range(None, 42), Some(ChunkRange { start: 0, total: 42 }));
+        // The header must agree with the body length
+        assert_eq!(parse_content_range(Some("bytes 0-9/20"), 5), None);
+        assert_eq!(parse_content_range(Some("items 0-9/20"), 10), None);
+    }
+
+    #[test]
+    fn test_authorize_admin() {
+        let mut state = ServerState {
+            config: crate::server::ServerConfig::default(),
+            session_store: Arc::new(platypus_runtime::SessionStore::new()),
+            media_store: platypus_runtime::MediaStore::new(),
+            transfers: platypus_runtime::TransferStore::new(),
+            shares: platypus_runtime::ShareStore::new(),
+            shared_backend: None,
+            peers: crate::cluster::ClusterPeers::new(),
+            start_time: std::time::Instant::now(),
+            app: crate::reload::AppSlot::default(),
+        };
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, "Bearer secret".parse().unwrap());
+        assert!(matches!(authorize_admin(&state, &headers), Err(Error::NotFound(_))));
+
+        state.config.admin_token = Some("secret".to_string());
+        assert!(authorize_admin(&state, &headers).is_ok());
+        assert!(matches!(authorize_admin(&state, &HeaderMap::new()), Err(Error::Unauthorized(_))));
+    }
+
+    #[tokio::test]
+    async fn test_render_session() {
+        fn app(st: &mut platypus_runtime::St) -> std::result::Result<(), String> {
+            st.title("Report");
+            Ok(())
+        }
+
+        let config = crate::server::ServerConfig::builder().admin_token("secret").build().unwrap();
+        let session_store = Arc::new(platypus_runtime::SessionStore::new());
+        let session_id = session_store.create_session("test".to_string());
+        let state = Arc::new(ServerState {
+            config,
+            session_store,
+            media_store: platypus_runtime::MediaStore::new(),
+            transfers: platypus_runtime::TransferStore::new(),
+            shares: platypus_runtime::ShareStore::new(),
+            shared_backend: None,
+            peers: crate::cluster::ClusterPeers::new(),
+            start_time: std::time::Instant::now(),
+            app: crate::reload::AppSlot::new(Some(app)),
+        });
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, "Bearer secret".parse().unwrap());
+
+        let render = |file: String| render_session(State(Arc::clone(&state)), Path(file), headers.clone());
+        let html = render(format!("{}.html", session_id)).await.unwrap();
+        let body = axum::body::to_bytes(html.into_body(), usize::MAX).await.unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("<h1>Report</h1>"));
+
+        let png = render(format!("{}.png", session_id)).await.unwrap();
+        assert_eq!(png.headers()[header::CONTENT_TYPE], "image/png");
+        assert!(matches!(render(format!("{}.gif", session_id)).await, Err(Error::BadRequest(_))));
+    }
 }
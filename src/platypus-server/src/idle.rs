@@ -0,0 +1,100 @@
+//! Idle detection for connected sessions.
+//!
+//! A session nobody interacts with for `session_timeout` seconds expires:
+//! the server closes its socket and forgets it. `idle_warning` seconds
+//! before that, the client is sent a `session_expiring` message so it can
+//! ask the user whether they are still there; a `keep_alive` message, or
+//! any interaction, restarts the timer. Messages the client sends on its
+//! own, like acknowledgements, do not count as activity.
+
+use std::time::{Duration, Instant};
+
+/// What the client is told about its idle session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdleNotice {
+    /// The session expires after this long without activity.
+    Expiring(Duration),
+    /// The session expired.
+    Expired,
+}
+
+/// Idle timer of one session.
+#[derive(Debug)]
+pub struct IdleTimer {
+    timeout: Duration,
+    warning: Duration,
+    last_activity: Instant,
+    warned: bool,
+}
+
+impl IdleTimer {
+    /// Expire after `timeout` without activity, warning `warning` before.
+    /// A zero timeout never expires.
+    pub fn new(timeout: Duration, warning: Duration) -> Self {
+        IdleTimer {
+            timeout,
+            warning: warning.min(timeout),
+            last_activity: Instant::now(),
+            warned: false,
+        }
+    }
+
+    /// Record activity, restarting the timer.
+    pub fn touch(&mut self) {
+        self.last_activity = Instant::now();
+        self.warned = false;
+    }
+
+    /// Get when the next notice is due, if ever.
+    pub fn deadline(&self) -> Option<Instant> {
+        if self.timeout.is_zero() {
+            return None;
+        }
+        let expiry = self.last_activity + self.timeout;
+        Some(if self.warned { expiry } else { expiry - self.warning })
+    }
+
+    /// Get the notice due at `now`, if any.
+    pub fn poll(&mut self, now: Instant) -> Option<IdleNotice> {
+        if now < self.deadline()? {
+            return None;
+        }
+        if self.warned || self.warning.is_zero() {
+            return Some(IdleNotice::Expired);
+        }
+        self.warned = true;
+        let expiry = self.last_activity + self.timeout;
+        Some(IdleNotice::Expiring(expiry.saturating_duration_since(now)))
+    }
+}
+
+/// Wait until `deadline`, or forever if there is none.
+pub async fn idle_due(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warns_then_expires() {
+        let mut timer = IdleTimer::new(Duration::from_secs(60), Duration::from_secs(10));
+        let start = timer.last_activity;
+        assert_eq!(timer.deadline(), Some(start + Duration::from_secs(50)));
+        assert_eq!(timer.poll(start + Duration::from_secs(49)), None);
+        assert_eq!(
+            timer.poll(start + Duration::from_secs(50)),
+            Some(IdleNotice::Expiring(Duration::from_secs(10)))
+        );
+        assert_eq!(timer.deadline(), Some(start + Duration::from_secs(60)));
+        assert_eq!(timer.poll(start + Duration::from_secs(60)), Some(IdleNotice::Expired));
+
+        timer.touch();
+        assert!(timer.deadline().unwrap() > start + Duration::from_secs(50));
+        assert_eq!(IdleTimer::new(Duration::ZERO, Duration::from_secs(10)).deadline(), None);
+    }
+}
@@ -3,15 +3,50 @@
 //! This crate provides the web server implementation for platypus applications,
 //! including HTTP endpoints and WebSocket support for real-time communication.
 
+pub mod builder;
+pub mod cluster;
+pub mod compression;
 pub mod config;
+pub mod custom_element;
+pub mod determinism;
+pub mod embed;
 pub mod error;
 pub mod executor;
+pub mod firewall;
 pub mod handler;
+pub mod idle;
+pub mod limits;
+pub mod listener;
+pub mod log_layer;
+pub mod media_gc;
 pub mod message;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+pub mod plugin;
+#[cfg(feature = "redis")]
+pub mod redis;
+pub mod reconcile;
+pub mod refresh;
+pub mod reload;
+pub mod sequence;
 pub mod server;
+#[cfg(feature = "streaming")]
+pub mod streaming;
 pub mod ws;
 
+pub use builder::ServerConfigBuilder;
+pub use cluster::ClusterConfig;
+pub use compression::{CompressionConfig, CompressionMetrics};
+pub use custom_element::CustomElementRegistry;
+pub use embed::EmbedConfig;
 pub use error::{Error, Result};
+pub use firewall::FirewallConfig;
+pub use limits::RequestLimits;
+pub use listener::ListenAddr;
+pub use log_layer::SessionLogLayer;
+pub use media_gc::MediaRetention;
+pub use plugin::{ServerPlugin, SessionEvent};
+pub use reload::AppSlot;
 pub use server::{AppServer, ServerConfig};
 
 pub mod prelude {
@@ -0,0 +1,103 @@
+//! Size limits of what clients send.
+//!
+//! Each kind of payload has its own limit: `max_body_size` for HTTP
+//! requests in general, [`RequestLimits::upload_chunk`] for the chunks of
+//! resumable uploads, [`RequestLimits::back_msg`] for the messages a
+//! session sends over its WebSocket, and
+//! [`RequestLimits::websocket_message`] for the WebSocket transport itself.
+//! Requests over a limit are answered with a `payload_too_large` error,
+//! like other errors, and an oversized session message is answered the
+//! same way over the socket, which stays open.
+
+use axum::extract::{Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+use crate::error::Error;
+
+/// Size limits (bytes) of uploads and WebSocket messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RequestLimits {
+    /// Largest chunk of a resumable upload.
+    pub upload_chunk: usize,
+    /// Largest message a session may send. Small files are sent inline,
+    /// so this bounds them too.
+    pub back_msg: usize,
+    /// Largest WebSocket message or frame at all; the connection closes
+    /// on larger ones. Keep it above `back_msg`, so oversized messages
+    /// get an error instead.
+    pub websocket_message: usize,
+}
+
+impl Default for RequestLimits {
+    fn default() -> Self {
+        RequestLimits {
+            upload_chunk: config::DEFAULT_MAX_UPLOAD_CHUNK,
+            back_msg: config::DEFAULT_MAX_BACK_MSG,
+            websocket_message: config::DEFAULT_MAX_WEBSOCKET_MESSAGE,
+        }
+    }
+}
+
+impl RequestLimits {
+    /// Check the size of a message a session sent.
+    pub fn check_back_msg(&self, size: usize) -> Result<(), platypus_core::Error> {
+        if size <= self.back_msg {
+            return Ok(());
+        }
+        Err(platypus_core::Error::PayloadTooLarge {
+            what: "Message".to_string(),
+            size,
+            limit: self.back_msg,
+        })
+    }
+}
+
+/// Answer requests rejected for a body over `limit` bytes with a
+/// structured error, instead of axum's plain text rejection.
+pub async fn reject_oversized(State(limit): State<usize>, request: Request, next: Next) -> Response {
+    structure_rejection(next.run(request).await, limit)
+}
+
+fn structure_rejection(response: Response, limit: usize) -> Response {
+    let structured = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|value| value.as_bytes().starts_with(b"application/json"));
+    if response.status() != StatusCode::PAYLOAD_TOO_LARGE || structured {
+        return response;
+    }
+    Error::payload_too_large(format!("Request body exceeds the limit of {} bytes", limit)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_back_msg() {
+        let limits = RequestLimits { back_msg: 10, ..Default::default() };
+        assert!(limits.check_back_msg(10).is_ok());
+        let error = limits.check_back_msg(11).unwrap_err();
+        assert_eq!(error.code().as_str(), "payload_too_large");
+        assert_eq!(error.to_string(), "Message of 11 bytes exceeds the limit of 10 bytes");
+    }
+
+    #[tokio::test]
+    async fn test_structure_rejection() {
+        let rejection = (StatusCode::PAYLOAD_TOO_LARGE, "length limit exceeded").into_response();
+        let response = structure_rejection(rejection, 4);
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["code"], "payload_too_large");
+        assert_eq!(body["error"], "Request body exceeds the limit of 4 bytes");
+
+        let ok = structure_rejection(StatusCode::OK.into_response(), 4);
+        assert_eq!(ok.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,199 @@
+//! The addresses a server listens on.
+//!
+//! A server listens on `host:port` unless [`ServerConfig::listen`] lists
+//! other addresses: TCP addresses such as `127.0.0.1:8501` or `[::1]:8501`,
+//! and, on Unix, domain sockets as `unix:` paths, e.g. for a local reverse
+//! proxy or a sandbox without network access:
+//!
+//! ```toml
+//! listen = ["127.0.0.1:8501", "unix:/run/platypus/app.sock"]
+//! ```
+//!
+//! Clients connecting through a Unix socket have no IP address, so the
+//! firewall's allow and deny lists do not apply to them.
+//!
+//! [`ServerConfig::listen`]: crate::ServerConfig::listen
+
+use axum::Router;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+use tokio::net::TcpListener;
+
+/// Prefix of Unix domain socket addresses.
+pub const UNIX_PREFIX: &str = "unix:";
+
+/// An address to listen on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum ListenAddr {
+    Tcp(SocketAddr),
+    /// Path of a Unix domain socket.
+    Unix(PathBuf),
+}
+
+impl FromStr for ListenAddr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix(UNIX_PREFIX) {
+            Some("") => Err("Missing Unix socket path".to_string()),
+            Some(_) if cfg!(not(unix)) => Err(format!("Unix sockets are not supported here: {}", s)),
+            Some(path) => Ok(ListenAddr::Unix(PathBuf::from(path))),
+            None => s
+                .parse()
+                .map(ListenAddr::Tcp)
+                .map_err(|_| format!("Invalid listen address {:?}; use host:port or unix:/path", s)),
+        }
+    }
+}
+
+impl TryFrom<String> for ListenAddr {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<ListenAddr> for String {
+    fn from(addr: ListenAddr) -> Self {
+        addr.to_string()
+    }
+}
+
+impl fmt::Display for ListenAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ListenAddr::Tcp(addr) => write!(f, "{}", addr),
+            ListenAddr::Unix(path) => write!(f, "{}{}", UNIX_PREFIX, path.display()),
+        }
+    }
+}
+
+/// A bound listener.
+pub enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixListener, PathBuf),
+}
+
+impl Listener {
+    /// Bind `addr`. A socket file left by a server that did not shut down
+    /// cleanly is replaced.
+    pub async fn bind(addr: &ListenAddr) -> io::Result<Self> {
+        match addr {
+            ListenAddr::Tcp(addr) => TcpListener::bind(addr).await.map(Listener::Tcp),
+            #[cfg(unix)]
+            ListenAddr::Unix(path) => {
+                match std::fs::remove_file(path) {
+                    Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                    _ => {}
+                }
+                tokio::net::UnixListener::bind(path).map(|listener| Listener::Unix(listener, path.clone()))
+            }
+            #[cfg(not(unix))]
+            ListenAddr::Unix(path) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("Unix sockets are not supported here: {}", path.display()),
+            )),
+        }
+    }
+
+    /// Serve `router`. Failed accepts, e.g. when out of file descriptors,
+    /// are logged and retried rather than ending the server.
+    pub async fn serve(self, router: Router) -> io::Result<()> {
+        match self {
+            // Peer addresses, for the firewall's allow and deny lists
+            Listener::Tcp(listener) => {
+                axum::serve(listener, router.into_make_service_with_connect_info::<SocketAddr>()).await
+            }
+            #[cfg(unix)]
+            Listener::Unix(listener, path) => {
+                let served = serve_unix(&listener, router).await;
+                let _ = std::fs::remove_file(path);
+                served
+            }
+        }
+    }
+}
+
+/// Delay before accepting again after an accept failed.
+#[cfg(unix)]
+const ACCEPT_BACKOFF: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Serve `router` to the clients of a Unix socket, with upgrades for
+/// WebSockets.
+#[cfg(unix)]
+async fn serve_unix(listener: &tokio::net::UnixListener, router: Router) -> io::Result<()> {
+    use hyper_util::rt::TokioIo;
+    use hyper_util::service::TowerToHyperService;
+
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                // Back off as `axum::serve` does, so running out of file
+                // descriptors doesn't spin
+                tracing::error!("Failed to accept a Unix socket connection: {}", e);
+                tokio::time::sleep(ACCEPT_BACKOFF).await;
+                continue;
+            }
+        };
+        let service = TowerToHyperService::new(router.clone());
+        tokio::spawn(async move {
+            let connection = hyper::server::conn::http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .with_upgrades();
+            if let Err(e) = connection.await {
+                tracing::debug!("Unix socket connection ended: {}", e);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_listen_addr() {
+        let tcp: ListenAddr = "[::1]:8501".parse().unwrap();
+        assert_eq!(tcp, ListenAddr::Tcp("[::1]:8501".parse().unwrap()));
+        assert_eq!(tcp.to_string(), "[::1]:8501");
+        assert!("localhost:8501".parse::<ListenAddr>().is_err());
+        assert!("unix:".parse::<ListenAddr>().is_err());
+        #[cfg(unix)]
+        assert_eq!(
+            "unix:/run/app.sock".parse::<ListenAddr>().unwrap(),
+            ListenAddr::Unix(PathBuf::from("/run/app.sock"))
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_serve_unix_socket() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let path = std::env::temp_dir().join(format!("platypus-{}.sock", uuid::Uuid::new_v4()));
+        let listener = Listener::bind(&ListenAddr::Unix(path.clone())).await.unwrap();
+        let router = Router::new().route("/health", axum::routing::get(|| async { "ok" }));
+        let server = tokio::spawn(listener.serve(router));
+
+        let mut stream = tokio::net::UnixStream::connect(&path).await.unwrap();
+        stream
+            .write_all(b"GET /health HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.ends_with("ok"));
+
+        server.abort();
+        let _ = std::fs::remove_file(path);
+    }
+}
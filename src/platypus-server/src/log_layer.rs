@@ -0,0 +1,167 @@
+//! Tracing bridge from app code to the browser's log viewer.
+//!
+//! Each script run executes inside a [`RUN_SPAN`] span carrying the session
+//! ID. [`SessionLogLayer`] picks up events emitted within that span and
+//! appends them to the session's [`APP_LOG_KEY`] log buffer, so
+//! `tracing::info!` in an app shows up in `st.app_logs()`.
+
+use dashmap::DashMap;
+use platypus_runtime::log_viewer::APP_LOG_KEY;
+use platypus_runtime::{LogLevel, LogStore};
+use std::fmt::{self, Write};
+use std::sync::LazyLock;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Name of the span wrapping each script run.
+pub const RUN_SPAN: &str = "platypus_run";
+
+/// Log stores of live sessions, by session ID.
+static SESSION_LOGS: LazyLock<DashMap<String, LogStore>> = LazyLock::new(DashMap::new);
+
+/// Route events in the session's run span to its log store.
+pub fn register_session(session_id: impl fmt::Display, logs: LogStore) {
+    SESSION_LOGS.insert(session_id.to_string(), logs);
+}
+
+/// Stop routing events for a closed session.
+pub fn unregister_session(session_id: impl fmt::Display) {
+    SESSION_LOGS.remove(&session_id.to_string());
+}
+
+/// Session ID recorded on a run span.
+struct SessionScope(String);
+
+/// Layer forwarding app events to session log buffers.
+pub struct SessionLogLayer {
+    max_level: Level,
+}
+
+impl SessionLogLayer {
+    /// Forward events at DEBUG and above.
+    pub fn new() -> Self {
+        SessionLogLayer { max_level: Level::DEBUG }
+    }
+
+    /// Forward events at `max_level` and above (e.g. `Level::INFO`).
+    pub fn with_max_level(max_level: Level) -> Self {
+        SessionLogLayer { max_level }
+    }
+}
+
+impl Default for SessionLogLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Layer<S> for SessionLogLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if attrs.metadata().name() != RUN_SPAN {
+            return;
+        }
+        let mut visitor = FieldVisitor::default();
+        attrs.record(&mut visitor);
+        if let (Some(session_id), Some(span)) = (visitor.session_id, ctx.span(id)) {
+            span.extensions_mut().insert(SessionScope(session_id));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let level = *event.metadata().level();
+        if level > self.max_level {
+            return;
+        }
+
+        let Some(session_id) = ctx.event_scope(event).and_then(|scope| {
+            scope
+                .from_root()
+                .find_map(|span| span.extensions().get::<SessionScope>().map(|s| s.0.clone()))
+        }) else {
+            return;
+        };
+        let Some(logs) = SESSION_LOGS.get(&session_id).map(|logs| logs.clone()) else {
+            return;
+        };
+
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+        logs.buffer(APP_LOG_KEY).append(log_level(level), visitor.message);
+    }
+}
+
+fn log_level(level: Level) -> LogLevel {
+    match level {
+        Level::ERROR => LogLevel::Error,
+        Level::WARN => LogLevel::Warn,
+        Level::INFO => LogLevel::Info,
+        _ => LogLevel::Debug,
+    }
+}
+
+/// Collects an event's message and fields as "message key=value ...".
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    session_id: Option<String>,
+}
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "session_id" {
+            self.session_id = Some(value.to_string());
+        }
+        self.record_debug(field, &format_args!("{}", value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        match field.name() {
+            "message" => {
+                let fields = std::mem::take(&mut self.message);
+                let _ = write!(self.message, "{:?}{}", value, fields);
+            }
+            "session_id" => {
+                if self.session_id.is_none() {
+                    self.session_id = Some(format!("{:?}", value));
+                }
+            }
+            name => {
+                let _ = write!(self.message, " {}={:?}", name, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_events_routed_by_session() {
+        let logs = LogStore::new();
+        register_session("session-a", logs.clone());
+        let subscriber = tracing_subscriber::registry().with(SessionLogLayer::with_max_level(Level::INFO));
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("outside any run");
+            let span = tracing::info_span!(RUN_SPAN, session_id = %"session-a");
+            let _guard = span.enter();
+            tracing::info!(rows = 3, "loaded data");
+            tracing::debug!("filtered out");
+            tracing::warn!("slow query");
+        });
+        unregister_session("session-a");
+
+        let lines = logs.buffer(APP_LOG_KEY).lines();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].message, "loaded data rows=3");
+        assert_eq!(lines[1].level, LogLevel::Warn);
+    }
+}
@@ -0,0 +1,76 @@
+//! Periodic garbage collection of stored media.
+//!
+//! Download buttons store a new file on every run and uploads outlive the
+//! widgets that received them, so the media store only stays bounded if
+//! files nobody references are dropped. The collector runs on an interval
+//! for the lifetime of the server.
+
+use crate::config;
+use platypus_runtime::MediaStore;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How long stored media is kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MediaRetention {
+    /// Seconds a file no session references is kept.
+    pub grace: u64,
+    /// Seconds any file is kept, referenced or not.
+    pub ttl: u64,
+    /// Seconds between collections.
+    pub interval: u64,
+}
+
+impl Default for MediaRetention {
+    fn default() -> Self {
+        MediaRetention {
+            grace: config::DEFAULT_MEDIA_GRACE,
+            ttl: config::DEFAULT_MEDIA_TTL,
+            interval: config::DEFAULT_MEDIA_GC_INTERVAL,
+        }
+    }
+}
+
+impl MediaRetention {
+    /// Run one collection.
+    pub fn collect(&self, store: &MediaStore) -> usize {
+        let collected = store.collect_garbage(Duration::from_secs(self.grace), Duration::from_secs(self.ttl));
+        if collected > 0 {
+            let stats = store.stats();
+            tracing::debug!(
+                "Collected {} media files; {} files ({} bytes) remain",
+                collected,
+                stats.files,
+                stats.bytes
+            );
+        }
+        collected
+    }
+
+    /// Collect garbage from `store` every `interval` seconds.
+    pub fn spawn_collector(self, store: MediaStore) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(self.interval.max(1)));
+            loop {
+                interval.tick().await;
+                self.collect(&store);
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use platypus_runtime::MediaFile;
+
+    #[test]
+    fn test_collect_unreferenced() {
+        let store = MediaStore::new();
+        store.add(MediaFile::new("a.txt", "text/plain", b"hello".to_vec()));
+        let retention = MediaRetention { grace: 0, ..Default::default() };
+        assert_eq!(retention.collect(&store), 1);
+        assert_eq!(store.stats().collected_bytes, 5);
+    }
+}
@@ -1,13 +1,41 @@
 //! Message handling for proto serialization and deserialization.
 
-use platypus_core::element::ElementType;
+use platypus_core::element::{AnnotationShape, ElementId, ElementType, LogLineItem};
 use platypus_core::state::Delta as CoreDelta;
 use platypus_proto::*;
+use platypus_runtime::{
+    Action, ClientInfo, ColorScheme, Event, ThemeMode, TransferDirection, TransferProgress, WidgetChange,
+};
+use platypus_core::ids::IdGenerator;
 use prost::Message;
+use std::cell::RefCell;
+use std::sync::Arc;
+use std::time::Duration;
 use uuid::Uuid;
 
+thread_local! {
+    /// Generator of the hashes of messages created on this thread
+    static HASH_IDS: RefCell<Option<Arc<dyn IdGenerator>>> = const { RefCell::new(None) };
+}
+
+/// Hash the messages created on this thread by `f` with `ids` instead of
+/// randomly, so tests and replays produce identical messages.
+pub fn with_hash_ids<T>(ids: Arc<dyn IdGenerator>, f: impl FnOnce() -> T) -> T {
+    let previous = HASH_IDS.with(|cell| cell.replace(Some(ids)));
+    let result = f();
+    HASH_IDS.with(|cell| *cell.borrow_mut() = previous);
+    result
+}
+
+fn message_hash() -> String {
+    HASH_IDS
+        .with(|cell| cell.borrow().as_ref().map(|ids| ids.next_id()))
+        .unwrap_or_else(Uuid::new_v4)
+        .to_string()
+}
+
 /// Convert Rust ElementType to proto Element
-pub fn element_type_to_proto(id: u64, element: &ElementType) -> Element {
+pub fn element_type_to_proto(id: ElementId, element: &ElementType) -> Element {
     let id_str = id.to_string();
     
     let element_type = match element {
@@ -33,31 +61,62 @@ pub fn element_type_to_proto(id: u64, element: &ElementType) -> Element {
                 level: *level,
             })
         }
-        ElementType::Button { label, key } => {
+        ElementType::Button { label, key, disabled } => {
             element::Type::Button(ButtonElement {
                 label: label.clone(),
                 key: key.clone().unwrap_or_default(),
+                disabled: *disabled,
+            })
+        }
+        ElementType::FormSubmitButton { label, form_key, key, disabled } => {
+            element::Type::FormSubmitButton(FormSubmitButtonElement {
+                label: label.clone(),
+                form_key: form_key.clone(),
+                key: key.clone().unwrap_or_default(),
+                disabled: *disabled,
             })
         }
-        ElementType::TextInput { label, value, key } => {
+        ElementType::TextInput {
+            label,
+            value,
+            key,
+            kind,
+            error,
+            rate_limit,
+            disabled,
+        } => {
             element::Type::TextInput(TextInputElement {
                 label: label.clone(),
                 value: value.clone(),
                 key: key.clone().unwrap_or_default(),
+                kind: kind.as_str().to_string(),
+                error: error.clone().unwrap_or_default(),
+                debounce_ms: rate_limit.debounce_ms,
+                throttle_ms: rate_limit.throttle_ms,
+                disabled: *disabled,
             })
         }
-        ElementType::TextArea { label, value, key } => {
+        ElementType::TextArea { label, value, key, disabled } => {
             element::Type::TextArea(TextAreaElement {
                 label: label.clone(),
                 value: value.clone(),
                 key: key.clone().unwrap_or_default(),
+                disabled: *disabled,
             })
         }
-        ElementType::NumberInput { label, value, key } => {
+        ElementType::NumberInput {
+            label,
+            value,
+            key,
+            display,
+            disabled,
+        } => {
             element::Type::NumberInput(NumberInputElement {
                 label: label.clone(),
                 value: *value,
                 key: key.clone().unwrap_or_default(),
+                display: display.clone().unwrap_or_default(),
+                disabled: *disabled,
             })
         }
         ElementType::Slider {
@@ -66,6 +125,8 @@ pub fn element_type_to_proto(id: u64, element: &ElementType) -> Element {
             min,
             max,
             key,
+            rate_limit,
+            disabled,
         } => {
             element::Type::Slider(SliderElement {
                 label: label.clone(),
@@ -73,13 +134,17 @@ pub fn element_type_to_proto(id: u64, element: &ElementType) -> Element {
                 min: *min,
                 max: *max,
                 key: key.clone().unwrap_or_default(),
+                debounce_ms: rate_limit.debounce_ms,
+                throttle_ms: rate_limit.throttle_ms,
+                disabled: *disabled,
             })
         }
-        ElementType::Checkbox { label, value, key } => {
+        ElementType::Checkbox { label, value, key, disabled } => {
             element::Type::Checkbox(CheckboxElement {
                 label: label.clone(),
                 value: *value,
                 key: key.clone().unwrap_or_default(),
+                disabled: *disabled,
             })
         }
         ElementType::Radio {
@@ -87,12 +152,14 @@ pub fn element_type_to_proto(id: u64, element: &ElementType) -> Element {
             options,
             value,
             key,
+            disabled,
         } => {
             element::Type::Radio(RadioElement {
                 label: label.clone(),
                 options: options.clone(),
                 value: value.clone().unwrap_or_default(),
                 key: key.clone().unwrap_or_default(),
+                disabled: *disabled,
             })
         }
         ElementType::Selectbox {
@@ -100,12 +167,14 @@ pub fn element_type_to_proto(id: u64, element: &ElementType) -> Element {
             options,
             value,
             key,
+            disabled,
         } => {
             element::Type::Selectbox(SelectboxElement {
                 label: label.clone(),
                 options: options.clone(),
                 value: value.clone().unwrap_or_default(),
                 key: key.clone().unwrap_or_default(),
+                disabled: *disabled,
             })
         }
         ElementType::Multiselect {
@@ -113,45 +182,213 @@ pub fn element_type_to_proto(id: u64, element: &ElementType) -> Element {
             options,
             values,
             key,
+            disabled,
         } => {
             element::Type::Multiselect(MultiSelectElement {
                 label: label.clone(),
                 options: options.clone(),
                 values: values.clone(),
                 key: key.clone().unwrap_or_default(),
+                disabled: *disabled,
+            })
+        }
+        ElementType::DateRangeInput {
+            label,
+            start,
+            end,
+            min,
+            max,
+            key,
+            disabled,
+        } => {
+            element::Type::DateRangeInput(DateRangeInputElement {
+                label: label.clone(),
+                start: start.clone(),
+                end: end.clone(),
+                min: min.clone().unwrap_or_default(),
+                max: max.clone().unwrap_or_default(),
+                key: key.clone().unwrap_or_default(),
+                disabled: *disabled,
+            })
+        }
+        ElementType::DateTimeInput {
+            label,
+            value,
+            timezone,
+            min,
+            max,
+            key,
+            disabled,
+        } => {
+            element::Type::DatetimeInput(DateTimeInputElement {
+                label: label.clone(),
+                value: value.clone(),
+                timezone: timezone.clone(),
+                min: min.clone().unwrap_or_default(),
+                max: max.clone().unwrap_or_default(),
+                key: key.clone().unwrap_or_default(),
+                disabled: *disabled,
             })
         }
-        ElementType::DateInput { label, value, key } => {
+        ElementType::DateInput { label, value, key, disabled } => {
             element::Type::DateInput(DateInputElement {
                 label: label.clone(),
                 value: value.clone(),
                 key: key.clone().unwrap_or_default(),
+                disabled: *disabled,
             })
         }
-        ElementType::TimeInput { label, value, key } => {
+        ElementType::TimeInput { label, value, key, disabled } => {
             element::Type::TimeInput(TimeInputElement {
                 label: label.clone(),
                 value: value.clone(),
                 key: key.clone().unwrap_or_default(),
+                disabled: *disabled,
             })
         }
-        ElementType::ColorPicker { label, value, key } => {
+        ElementType::ColorPicker { label, value, key, disabled } => {
             element::Type::ColorPicker(ColorPickerElement {
                 label: label.clone(),
                 value: value.clone(),
                 key: key.clone().unwrap_or_default(),
+                disabled: *disabled,
             })
         }
-        ElementType::FileUploader { label, key } => {
+        ElementType::FileUploader { label, key, disabled } => {
             element::Type::FileUploader(FileUploaderElement {
                 label: label.clone(),
                 key: key.clone().unwrap_or_default(),
+                disabled: *disabled,
+            })
+        }
+        ElementType::Dropzone {
+            label,
+            accept,
+            allow_paste,
+            show_preview,
+            key,
+            disabled,
+        } => {
+            element::Type::Dropzone(DropzoneElement {
+                label: label.clone(),
+                accept: accept.clone(),
+                allow_paste: *allow_paste,
+                show_preview: *show_preview,
+                key: key.clone().unwrap_or_default(),
+                disabled: *disabled,
+            })
+        }
+        ElementType::TagsInput {
+            label,
+            suggestions,
+            values,
+            max_tags,
+            allow_custom,
+            error,
+            key,
+            disabled,
+        } => {
+            element::Type::TagsInput(TagsInputElement {
+                label: label.clone(),
+                suggestions: suggestions.clone(),
+                values: values.clone(),
+                max_tags: max_tags.unwrap_or_default() as u32,
+                allow_custom: *allow_custom,
+                error: error.clone().unwrap_or_default(),
+                key: key.clone().unwrap_or_default(),
+                disabled: *disabled,
+            })
+        }
+        ElementType::Feedback { kind, value, key, disabled } => {
+            element::Type::Feedback(FeedbackElement {
+                kind: kind.as_str().to_string(),
+                value: value.map(|v| v as i32).unwrap_or(-1),
+                key: key.clone().unwrap_or_default(),
+                disabled: *disabled,
+            })
+        }
+        ElementType::Rating { max, value, key, disabled } => {
+            element::Type::Rating(RatingElement {
+                max: *max,
+                value: value.map(|v| v as i32).unwrap_or(-1),
+                key: key.clone().unwrap_or_default(),
+                disabled: *disabled,
+            })
+        }
+        ElementType::CodeEditor {
+            label,
+            value,
+            language,
+            read_only,
+            annotations,
+            debounce_ms,
+            error,
+            key,
+            disabled,
+        } => {
+            element::Type::CodeEditor(CodeEditorElement {
+                label: label.clone(),
+                value: value.clone(),
+                language: language.clone(),
+                read_only: read_only
+                    .iter()
+                    .map(|&(first, last)| LineRange { first, last })
+                    .collect(),
+                annotations: annotations
+                    .iter()
+                    .map(|a| CodeAnnotation {
+                        line: a.line,
+                        severity: a.severity.clone(),
+                        message: a.message.clone(),
+                    })
+                    .collect(),
+                debounce_ms: *debounce_ms,
+                error: error.clone().unwrap_or_default(),
+                key: key.clone().unwrap_or_default(),
+                disabled: *disabled,
+            })
+        }
+        ElementType::JsonEditor { text, schema, errors, key, disabled } => {
+            element::Type::JsonEditor(JsonEditorElement {
+                text: text.clone(),
+                schema: schema
+                    .as_ref()
+                    .map(|s| serde_json::to_string(s).unwrap_or_default())
+                    .unwrap_or_default(),
+                errors: errors
+                    .iter()
+                    .map(|e| JsonEditorError {
+                        path: e.path.clone(),
+                        message: e.message.clone(),
+                    })
+                    .collect(),
+                key: key.clone().unwrap_or_default(),
+                disabled: *disabled,
+            })
+        }
+        ElementType::RichTextEditor { label, value, max_bytes, error, key, disabled } => {
+            element::Type::RichTextEditor(RichTextEditorElement {
+                label: label.clone(),
+                value: value.clone(),
+                max_bytes: *max_bytes as u64,
+                error: error.clone().unwrap_or_default(),
+                key: key.clone().unwrap_or_default(),
+                disabled: *disabled,
+            })
+        }
+        ElementType::ThemeToggle { label, mode, key, disabled } => {
+            element::Type::ThemeToggle(ThemeToggleElement {
+                label: label.clone(),
+                mode: mode.clone(),
+                key: key.clone().unwrap_or_default(),
+                disabled: *disabled,
             })
         }
-        ElementType::CameraInput { label, key } => {
+        ElementType::CameraInput { label, key, disabled } => {
             element::Type::CameraInput(CameraInputElement {
                 label: label.clone(),
                 key: key.clone().unwrap_or_default(),
+                disabled: *disabled,
             })
         }
         ElementType::Json { value } => {
@@ -166,9 +403,45 @@ pub fn element_type_to_proto(id: u64, element: &ElementType) -> Element {
                 width: width.unwrap_or(0),
             })
         }
-        ElementType::Audio { src } => {
+        ElementType::ImageAnnotator { src, labels, annotations, key, disabled } => {
+            element::Type::ImageAnnotator(ImageAnnotatorElement {
+                src: src.clone(),
+                labels: labels.clone(),
+                annotations: annotations
+                    .iter()
+                    .map(|annotation| ImageAnnotation {
+                        label: annotation.label.clone().unwrap_or_default(),
+                        shape: Some(match &annotation.shape {
+                            &AnnotationShape::Box { x, y, width, height } => {
+                                image_annotation::Shape::Box(AnnotationBox { x, y, width, height })
+                            }
+                            AnnotationShape::Polygon { points } => {
+                                image_annotation::Shape::Polygon(AnnotationPolygon {
+                                    points: points.iter().map(|p| AnnotationPoint { x: p.x, y: p.y }).collect(),
+                                })
+                            }
+                        }),
+                    })
+                    .collect(),
+                key: key.clone(),
+                disabled: *disabled,
+            })
+        }
+        ElementType::AudioWaveform { src, regions, key, disabled } => {
+            element::Type::AudioWaveform(AudioWaveformElement {
+                src: src.clone(),
+                regions: regions
+                    .iter()
+                    .map(|&(start, end)| TimeRegion { start, end })
+                    .collect(),
+                key: key.clone(),
+                disabled: *disabled,
+            })
+        }
+        ElementType::Audio { src, autoplay } => {
             element::Type::Audio(AudioElement {
                 src: src.clone(),
+                autoplay: *autoplay,
             })
         }
         ElementType::Video { src } => {
@@ -176,37 +449,111 @@ pub fn element_type_to_proto(id: u64, element: &ElementType) -> Element {
                 src: src.clone(),
             })
         }
-        ElementType::Container { children } => {
+        ElementType::DownloadButton {
+            label,
+            file_name,
+            mime_type,
+            url,
+            key,
+            disabled,
+        } => {
+            element::Type::DownloadButton(DownloadButtonElement {
+                label: label.clone(),
+                file_name: file_name.clone(),
+                mime_type: mime_type.clone(),
+                url: url.clone(),
+                key: key.clone().unwrap_or_default(),
+                disabled: *disabled,
+            })
+        }
+        ElementType::Container { children, hidden } => {
             element::Type::Container(ContainerElement {
                 children: children.iter().map(|c| c.to_string()).collect(),
+                hidden: *hidden,
             })
         }
-        ElementType::Column { children, width } => {
+        ElementType::Column { children, width, hidden } => {
             element::Type::Column(ColumnElement {
                 children: children.iter().map(|c| c.to_string()).collect(),
                 width: width.unwrap_or(1.0),
+                hidden: *hidden,
             })
         }
-        ElementType::Row { children } => {
+        ElementType::Row { children, hidden } => {
             element::Type::Row(RowElement {
                 children: children.iter().map(|c| c.to_string()).collect(),
+                hidden: *hidden,
             })
         }
-        ElementType::Tab { label, children } => {
+        ElementType::Tab { label, children, hidden } => {
             element::Type::Tab(TabElement {
                 label: label.clone(),
                 children: children.iter().map(|c| c.to_string()).collect(),
+                hidden: *hidden,
             })
         }
         ElementType::Expander {
             label,
             expanded,
             children,
+            hidden,
         } => {
             element::Type::Expander(ExpanderElement {
                 label: label.clone(),
                 expanded: *expanded,
                 children: children.iter().map(|c| c.to_string()).collect(),
+                hidden: *hidden,
+            })
+        }
+        ElementType::Fieldset { legend, children, hidden } => {
+            element::Type::Fieldset(FieldsetElement {
+                legend: legend.clone(),
+                children: children.iter().map(|c| c.to_string()).collect(),
+                hidden: *hidden,
+            })
+        }
+        ElementType::Form { key, children, hidden } => {
+            element::Type::Form(FormElement {
+                key: key.clone(),
+                children: children.iter().map(|c| c.to_string()).collect(),
+                hidden: *hidden,
+            })
+        }
+        ElementType::ResponsiveContainer { children, layouts, hidden } => {
+            element::Type::ResponsiveContainer(ResponsiveContainerElement {
+                children: children.iter().map(|c| c.to_string()).collect(),
+                layouts: layouts
+                    .iter()
+                    .map(|(breakpoint, layout)| BreakpointLayout {
+                        breakpoint: breakpoint.clone(),
+                        display: layout.display.clone(),
+                        flex_direction: layout.flex_direction.clone().unwrap_or_default(),
+                        width: layout.width.clone().unwrap_or_default(),
+                        height: layout.height.clone().unwrap_or_default(),
+                    })
+                    .collect(),
+                    hidden: *hidden,
+            })
+        }
+        ElementType::LogViewer { lines, capacity, key } => {
+            element::Type::LogViewer(LogViewerElement {
+                lines: lines.iter().map(log_line_to_proto).collect(),
+                capacity: *capacity as u64,
+                key: key.clone(),
+            })
+        }
+        ElementType::NotificationCenter { notifications, key } => {
+            element::Type::NotificationCenter(NotificationCenterElement {
+                notifications: notifications
+                    .iter()
+                    .map(|n| NotificationItem {
+                        id: n.id.clone(),
+                        level: n.level.clone(),
+                        message: n.message.clone(),
+                        read: n.read,
+                    })
+                    .collect(),
+                key: key.clone().unwrap_or_default(),
             })
         }
         ElementType::Tabs { tabs } => {
@@ -220,16 +567,28 @@ pub fn element_type_to_proto(id: u64, element: &ElementType) -> Element {
                     .collect(),
             })
         }
-        ElementType::Sidebar { children } => {
+        ElementType::Sidebar { children, hidden } => {
             element::Type::Sidebar(SidebarElement {
                 children: children.iter().map(|c| c.to_string()).collect(),
+                hidden: *hidden,
             })
         }
-        ElementType::Metric { label, value, delta } => {
+        ElementType::Metric { label, value, delta, sparkline } => {
             element::Type::Metric(MetricElement {
                 label: label.clone(),
                 value: value.clone(),
                 delta: delta.clone().unwrap_or_default(),
+                sparkline: sparkline.clone(),
+            })
+        }
+        ElementType::Gauge { data } => {
+            element::Type::Gauge(GaugeElement {
+                data: data.clone(),
+            })
+        }
+        ElementType::Donut { data } => {
+            element::Type::Donut(DonutElement {
+                data: data.clone(),
             })
         }
         ElementType::Success { message } => {
@@ -252,14 +611,42 @@ pub fn element_type_to_proto(id: u64, element: &ElementType) -> Element {
                 message: message.clone(),
             })
         }
-        ElementType::Progress { value } => {
-            element::Type::Progress(ProgressElement { value: *value })
+        ElementType::ValidationSummary { errors } => {
+            element::Type::ValidationSummary(ValidationSummaryElement {
+                errors: errors.clone(),
+            })
+        }
+        ElementType::Lazy { key } => element::Type::Lazy(LazyElement { key: key.clone() }),
+        ElementType::Skeleton { kind, count } => {
+            element::Type::Skeleton(SkeletonElement {
+                kind: kind.as_str().to_string(),
+                count: *count,
+            })
+        }
+        ElementType::Progress {
+            value,
+            text,
+            indeterminate,
+            eta_secs,
+        } => {
+            element::Type::Progress(ProgressElement {
+                value: *value,
+                text: text.clone().unwrap_or_default(),
+                indeterminate: *indeterminate,
+                eta_secs: eta_secs.unwrap_or_default(),
+            })
         }
         ElementType::Dataframe { data } => {
             element::Type::Dataframe(DataFrameElement {
                 data: data.clone(),
             })
         }
+        ElementType::PivotTable { key, data } => {
+            element::Type::PivotTable(PivotTableElement {
+                key: key.clone(),
+                data: data.clone(),
+            })
+        }
         ElementType::Table { headers, rows } => {
             element::Type::Table(TableElement {
                 headers: headers.clone(),
@@ -271,40 +658,101 @@ pub fn element_type_to_proto(id: u64, element: &ElementType) -> Element {
                     .collect(),
             })
         }
+        ElementType::DataEditor { headers, cells, values, key, disabled } => {
+            let rows = |rows: &Vec<Vec<String>>| rows.iter().map(|row| TableRow { cells: row.clone() }).collect();
+            element::Type::DataEditor(DataEditorElement {
+                headers: headers.clone(),
+                cells: rows(cells),
+                values: rows(values),
+                key: key.clone(),
+                disabled: *disabled,
+            })
+        }
         ElementType::Divider => {
             element::Type::Divider(DividerElement {})
         }
+        ElementType::Echo { key, template, value } => {
+            element::Type::Echo(EchoElement {
+                key: key.clone(),
+                template: template.clone(),
+                value: value.clone(),
+            })
+        }
+        ElementType::AudioInput { label, key, disabled } => {
+            element::Type::AudioInput(AudioInputElement {
+                label: label.clone(),
+                key: key.clone(),
+                disabled: *disabled,
+            })
+        }
+        ElementType::ChatMessage { role, content } => {
+            element::Type::ChatMessage(ChatMessageElement {
+                role: role.clone(),
+                content: content.clone(),
+            })
+        }
+        ElementType::Custom { name, payload } => {
+            element::Type::Custom(CustomElement {
+                name: name.clone(),
+                json_payload: payload.to_string(),
+            })
+        }
         ElementType::Empty => {
             element::Type::Empty(EmptyElement {})
         }
-        ElementType::LineChart { data, title } => {
+        ElementType::LineChart { data, title, key } => {
             element::Type::LineChart(LineChartElement {
                 data: data.clone(),
                 title: title.clone().unwrap_or_default(),
+                key: key.clone().unwrap_or_default(),
             })
         }
-        ElementType::BarChart { data, title } => {
+        ElementType::BarChart { data, title, key } => {
             element::Type::BarChart(BarChartElement {
                 data: data.clone(),
                 title: title.clone().unwrap_or_default(),
+                key: key.clone().unwrap_or_default(),
             })
         }
-        ElementType::AreaChart { data, title } => {
+        ElementType::AreaChart { data, title, key } => {
             element::Type::AreaChart(AreaChartElement {
                 data: data.clone(),
                 title: title.clone().unwrap_or_default(),
+                key: key.clone().unwrap_or_default(),
             })
         }
-        ElementType::ScatterChart { data, title } => {
+        ElementType::ScatterChart { data, title, key } => {
             element::Type::ScatterChart(ScatterChartElement {
                 data: data.clone(),
                 title: title.clone().unwrap_or_default(),
+                key: key.clone().unwrap_or_default(),
             })
         }
-        ElementType::PieChart { data, title } => {
+        ElementType::PieChart { data, title, key } => {
             element::Type::PieChart(PieChartElement {
                 data: data.clone(),
                 title: title.clone().unwrap_or_default(),
+                key: key.clone().unwrap_or_default(),
+            })
+        }
+        ElementType::CandlestickChart { data, title, key } => {
+            element::Type::CandlestickChart(CandlestickChartElement {
+                data: data.clone(),
+                title: title.clone().unwrap_or_default(),
+                key: key.clone().unwrap_or_default(),
+            })
+        }
+        ElementType::LineChartStream { key, capacity } => {
+            element::Type::LineChartStream(LineChartStreamElement {
+                key: key.clone(),
+                capacity: *capacity as u64,
+            })
+        }
+        ElementType::Heatmap { key, data, title } => {
+            element::Type::Heatmap(HeatmapElement {
+                key: key.clone(),
+                data: data.clone(),
+                title: title.clone().unwrap_or_default(),
             })
         }
         ElementType::PlotlyChart { spec } => {
@@ -322,6 +770,9 @@ pub fn element_type_to_proto(id: u64, element: &ElementType) -> Element {
                 spec: spec.clone(),
             })
         }
+        ElementType::Unknown { type_name } => element::Type::Unknown(UnknownElement {
+            type_name: type_name.clone(),
+        }),
     };
 
     Element {
@@ -334,34 +785,58 @@ pub fn element_type_to_proto(id: u64, element: &ElementType) -> Element {
 pub fn create_delta_msg(deltas: Vec<CoreDelta>) -> ForwardMsg {
     let delta_msgs = deltas
         .into_iter()
-        .filter_map(|delta| match delta {
+        .map(|delta| match delta {
             CoreDelta::AddElement {
                 id,
                 element,
                 parent_id,
             } => {
-                let proto_element = element_type_to_proto(id.inner(), &element);
-                Some(delta::Type::AddElement(AddElementDelta {
+                let proto_element = element_type_to_proto(id, &element);
+                delta::Type::AddElement(AddElementDelta {
                     element: Some(proto_element),
                     parent_id: parent_id.map(|p| p.to_string()).unwrap_or_default(),
-                }))
+                })
             }
             CoreDelta::UpdateElement { id, element } => {
-                let proto_element = element_type_to_proto(id.inner(), &element);
-                Some(delta::Type::UpdateElement(UpdateElementDelta {
+                let proto_element = element_type_to_proto(id, &element);
+                delta::Type::UpdateElement(UpdateElementDelta {
                     element: Some(proto_element),
-                }))
+                })
             }
             CoreDelta::RemoveElement { id } => {
-                Some(delta::Type::RemoveElement(RemoveElementDelta {
+                delta::Type::RemoveElement(RemoveElementDelta {
                     element_id: id.to_string(),
-                }))
+                })
             }
             CoreDelta::ClearContainer { id } => {
-                Some(delta::Type::ClearContainer(ClearContainerDelta {
+                delta::Type::ClearContainer(ClearContainerDelta {
                     container_id: id.to_string(),
-                }))
+                })
+            }
+            CoreDelta::SetAccessibility { id, accessibility } => {
+                delta::Type::SetAccessibility(SetAccessibilityDelta {
+                    element_id: id.to_string(),
+                    label: accessibility.label.unwrap_or_default(),
+                    role: accessibility.role.unwrap_or_default(),
+                    description: accessibility.description.unwrap_or_default(),
+                })
             }
+            CoreDelta::AppendChartData { id, key, data } => {
+                delta::Type::AppendChartData(AppendChartDataDelta {
+                    element_id: id.to_string(),
+                    key,
+                    data,
+                })
+            }
+            CoreDelta::MoveElement {
+                id,
+                previous_id,
+                parent_id,
+            } => delta::Type::MoveElement(MoveElementDelta {
+                element_id: id.to_string(),
+                previous_id: previous_id.to_string(),
+                parent_id: parent_id.map(|p| p.to_string()).unwrap_or_default(),
+            }),
         })
         .map(|delta_type| Delta {
             r#type: Some(delta_type),
@@ -369,48 +844,151 @@ pub fn create_delta_msg(deltas: Vec<CoreDelta>) -> ForwardMsg {
         .collect();
 
     ForwardMsg {
-        hash: Uuid::new_v4().to_string(),
+        hash: message_hash(),
+        seq: 0,
         r#type: Some(forward_msg::Type::Delta(DeltaMsg {
             deltas: delta_msgs,
+            reconcile_seq: 0,
         })),
     }
 }
 
+/// Create a ForwardMsg with the deltas of a run reflecting client messages
+/// up to `reconcile_seq`
+pub fn create_reconciled_delta_msg(deltas: Vec<CoreDelta>, reconcile_seq: u64) -> ForwardMsg {
+    let mut msg = create_delta_msg(deltas);
+    if let Some(forward_msg::Type::Delta(delta_msg)) = &mut msg.r#type {
+        delta_msg.reconcile_seq = reconcile_seq;
+    }
+    msg
+}
+
+/// Convert the deltas of a run reflecting client messages up to
+/// `reconcile_seq` to JSON for the frontend
+pub fn reconciled_deltas_to_json(deltas: Vec<CoreDelta>, reconcile_seq: u64) -> serde_json::Value {
+    let mut msg = deltas_to_json(deltas);
+    msg["seq"] = reconcile_seq.into();
+    msg
+}
+
+/// Parse a text message from the web frontend. Anything but a JSON object
+/// is logged and dropped.
+pub fn parse_json_msg(text: &str) -> Option<serde_json::Value> {
+    match serde_json::from_str::<serde_json::Value>(text) {
+        Ok(msg) if msg.is_object() => Some(msg),
+        Ok(_) => {
+            tracing::warn!("Ignoring text message that is not a JSON object");
+            None
+        }
+        Err(e) => {
+            tracing::warn!("Invalid JSON message: {}", e);
+            None
+        }
+    }
+}
+
+/// Get the client sequence number of a JSON message, 0 if it has none
+pub fn json_seq(msg: &serde_json::Value) -> u64 {
+    msg.get("seq").and_then(|seq| seq.as_u64()).unwrap_or(0)
+}
+
+/// Get the server seq a JSON message acknowledges, if it has one
+pub fn json_ack(msg: &serde_json::Value) -> Option<u64> {
+    msg.get("ack").and_then(|ack| ack.as_u64())
+}
+
+/// Check if a JSON message asks for a snapshot after a gap
+pub fn json_is_resync(msg: &serde_json::Value) -> bool {
+    msg.get("type").and_then(|kind| kind.as_str()) == Some("resync")
+}
+
+/// Check if a BackMsg asks for a snapshot after a gap
+pub fn back_msg_is_resync(msg: &BackMsg) -> bool {
+    matches!(msg.r#type, Some(back_msg::Type::Resync(_)))
+}
+
+/// Check if a JSON message keeps an idle session alive
+pub fn json_is_keep_alive(msg: &serde_json::Value) -> bool {
+    msg.get("type").and_then(|kind| kind.as_str()) == Some("keep_alive")
+}
+
+/// Check if a BackMsg keeps an idle session alive
+pub fn back_msg_is_keep_alive(msg: &BackMsg) -> bool {
+    matches!(msg.r#type, Some(back_msg::Type::KeepAlive(_)))
+}
+
+/// Convert the deltas of the last run to a JSON snapshot, which the client
+/// renders in place of its state whatever messages it missed
+pub fn snapshot_to_json(deltas: Vec<CoreDelta>, reconcile_seq: u64) -> serde_json::Value {
+    let mut msg = reconciled_deltas_to_json(deltas, reconcile_seq);
+    msg["snapshot"] = true.into();
+    msg
+}
+
 /// Convert deltas to JSON for frontend
 pub fn deltas_to_json(deltas: Vec<CoreDelta>) -> serde_json::Value {
     let elements: Vec<serde_json::Value> = deltas
         .into_iter()
-        .filter_map(|delta| match delta {
+        .map(|delta| match delta {
             CoreDelta::AddElement {
                 id,
                 element,
                 parent_id,
             } => {
-                Some(serde_json::json!({
+                serde_json::json!({
                     "type": "add_element",
-                    "id": id.inner().to_string(),
+                    "id": id.to_string(),
                     "parent_id": parent_id.map(|p| p.to_string()),
                     "element": element_to_json(&element),
-                }))
+                })
             }
             CoreDelta::UpdateElement { id, element } => {
-                Some(serde_json::json!({
+                serde_json::json!({
                     "type": "update_element",
-                    "id": id.inner().to_string(),
+                    "id": id.to_string(),
                     "element": element_to_json(&element),
-                }))
+                })
             }
             CoreDelta::RemoveElement { id } => {
-                Some(serde_json::json!({
+                serde_json::json!({
                     "type": "remove_element",
-                    "id": id.inner().to_string(),
-                }))
+                    "id": id.to_string(),
+                })
             }
             CoreDelta::ClearContainer { id } => {
-                Some(serde_json::json!({
+                serde_json::json!({
                     "type": "clear_container",
-                    "id": id.inner().to_string(),
-                }))
+                    "id": id.to_string(),
+                })
+            }
+            CoreDelta::SetAccessibility { id, accessibility } => {
+                serde_json::json!({
+                    "type": "set_accessibility",
+                    "id": id.to_string(),
+                    "label": accessibility.label,
+                    "role": accessibility.role,
+                    "description": accessibility.description,
+                })
+            }
+            CoreDelta::AppendChartData { id, key, data } => {
+                serde_json::json!({
+                    "type": "append_chart_data",
+                    "id": id.to_string(),
+                    "key": key,
+                    "data": data,
+                })
+            }
+            CoreDelta::MoveElement {
+                id,
+                previous_id,
+                parent_id,
+            } => {
+                serde_json::json!({
+                    "type": "move_element",
+                    "id": id.to_string(),
+                    "previous_id": previous_id.to_string(),
+                    "parent_id": parent_id.map(|p| p.to_string()),
+                })
             }
         })
         .collect();
@@ -421,319 +999,164 @@ pub fn deltas_to_json(deltas: Vec<CoreDelta>) -> serde_json::Value {
     })
 }
 
-/// Convert ElementType to JSON
-fn element_to_json(element: &ElementType) -> serde_json::Value {
-    match element {
-        ElementType::Text { value } => {
-            serde_json::json!({
-                "type": "text",
-                "value": value,
-            })
-        }
-        ElementType::Markdown { value } => {
-            serde_json::json!({
-                "type": "markdown",
-                "value": value,
-            })
-        }
-        ElementType::Code { value, language } => {
-            serde_json::json!({
-                "type": "code",
-                "value": value,
-                "language": language,
-            })
+/// JSON `type` names that are not the variant name in snake case
+const JSON_TYPE_RENAMES: [(&str, &str); 1] = [("DateTimeInput", "datetime_input")];
+
+/// Variants whose `RateLimit` is sent as top-level `debounce_ms` and
+/// `throttle_ms` fields
+const RATE_LIMITED_TYPES: [&str; 2] = ["TextInput", "Slider"];
+
+/// Get the JSON `type` of the `ElementType` variant `tag`.
+fn json_type(tag: &str) -> String {
+    if let Some((_, renamed)) = JSON_TYPE_RENAMES.iter().find(|(variant, _)| *variant == tag) {
+        return renamed.to_string();
+    }
+    let mut name = String::with_capacity(tag.len() + 4);
+    for (index, ch) in tag.char_indices() {
+        if ch.is_ascii_uppercase() && index > 0 {
+            name.push('_');
         }
-        ElementType::Heading { value, level } => {
-            serde_json::json!({
-                "type": "heading",
-                "value": value,
-                "level": level,
-            })
+        name.push(ch.to_ascii_lowercase());
+    }
+    name
+}
+
+/// Get the `ElementType` variant of the JSON `type` name.
+fn variant_tag(name: &str) -> String {
+    if let Some((variant, _)) = JSON_TYPE_RENAMES.iter().find(|(_, renamed)| *renamed == name) {
+        return variant.to_string();
+    }
+    name.split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Map the element IDs in a JSON array through `convert`.
+fn map_ids(ids: &mut serde_json::Value, convert: impl Fn(&serde_json::Value) -> Option<serde_json::Value>) {
+    if let Some(ids) = ids.as_array_mut() {
+        for id in ids.iter_mut() {
+            if let Some(converted) = convert(id) {
+                *id = converted;
+            }
         }
-        ElementType::Button { label, key } => {
-            serde_json::json!({
-                "type": "button",
-                "label": label,
-                "key": key,
-            })
+    }
+}
+
+fn id_to_string(id: &serde_json::Value) -> Option<serde_json::Value> {
+    let id: ElementId = serde_json::from_value(id.clone()).ok()?;
+    Some(id.to_string().into())
+}
+
+fn id_from_string(id: &serde_json::Value) -> Option<serde_json::Value> {
+    let id: ElementId = id.as_str()?.parse().ok()?;
+    serde_json::to_value(id).ok()
+}
+
+/// Convert ElementType to JSON for the frontend.
+///
+/// Fields come from `ElementType`'s serde representation, so every field
+/// reaches the frontend as it does over proto. Only the frontend's `type`
+/// names and a few field shapes differ: element IDs are strings, rate limits
+/// are flattened and responsive layouts are keyed by breakpoint.
+/// [`element_from_json`] reverses them.
+pub fn element_to_json(element: &ElementType) -> serde_json::Value {
+    let mut json = match serde_json::to_value(element) {
+        Ok(json) => json,
+        Err(e) => {
+            // Shown in the element's place, as for elements failing to build
+            tracing::error!("Element failed to serialize: {}", e);
+            return serde_json::json!({
+                "type": "error",
+                "message": format!("Element failed to serialize: {}", e),
+            });
         }
-        ElementType::TextInput { label, value, key } => {
-            serde_json::json!({
-                "type": "text_input",
-                "label": label,
-                "value": value,
-                "key": key,
-            })
+    };
+    let Some(fields) = json.as_object_mut() else {
+        return json;
+    };
+    if let Some(tag) = fields.get("type").and_then(|tag| tag.as_str()) {
+        let name = json_type(tag);
+        fields.insert("type".to_string(), name.into());
+    }
+    if let Some(serde_json::Value::Object(rate_limit)) = fields.remove("rate_limit") {
+        fields.extend(rate_limit);
+    }
+    if let Some(children) = fields.get_mut("children") {
+        map_ids(children, id_to_string);
+    }
+    if let Some(tabs) = fields.get_mut("tabs").and_then(|tabs| tabs.as_array_mut()) {
+        for tab in tabs {
+            if let Some(ids) = tab.get_mut(1) {
+                map_ids(ids, id_to_string);
+            }
         }
-        ElementType::TextArea { label, value, key } => {
-            serde_json::json!({
-                "type": "text_area",
-                "label": label,
-                "value": value,
-                "key": key,
-            })
-        }
-        ElementType::NumberInput { label, value, key } => {
-            serde_json::json!({
-                "type": "number_input",
-                "label": label,
-                "value": value,
-                "key": key,
-            })
-        }
-        ElementType::Slider { label, value, min, max, key } => {
-            serde_json::json!({
-                "type": "slider",
-                "label": label,
-                "value": value,
-                "min": min,
-                "max": max,
-                "key": key,
-            })
-        }
-        ElementType::Checkbox { label, value, key } => {
-            serde_json::json!({
-                "type": "checkbox",
-                "label": label,
-                "value": value,
-                "key": key,
-            })
-        }
-        ElementType::Selectbox { label, options, value, key } => {
-            serde_json::json!({
-                "type": "selectbox",
-                "label": label,
-                "options": options,
-                "value": value,
-                "key": key,
-            })
-        }
-        ElementType::Multiselect { label, options, values, key } => {
-            serde_json::json!({
-                "type": "multiselect",
-                "label": label,
-                "options": options,
-                "values": values,
-                "key": key,
-            })
-        }
-        ElementType::Json { value } => {
-            serde_json::json!({
-                "type": "json",
-                "value": value,
-            })
-        }
-        ElementType::Image { src, caption, width } => {
-            serde_json::json!({
-                "type": "image",
-                "src": src,
-                "caption": caption,
-                "width": width,
-            })
-        }
-        ElementType::Divider => {
-            serde_json::json!({
-                "type": "divider",
-            })
-        }
-        ElementType::Empty => {
-            serde_json::json!({
-                "type": "empty",
-            })
-        }
-        ElementType::Success { message } => {
-            serde_json::json!({
-                "type": "success",
-                "message": message,
-            })
-        }
-        ElementType::Error { message } => {
-            serde_json::json!({
-                "type": "error",
-                "message": message,
-            })
-        }
-        ElementType::Warning { message } => {
-            serde_json::json!({
-                "type": "warning",
-                "message": message,
-            })
-        }
-        ElementType::Info { message } => {
-            serde_json::json!({
-                "type": "info",
-                "message": message,
-            })
-        }
-        ElementType::Metric { label, value, delta } => {
-            serde_json::json!({
-                "type": "metric",
-                "label": label,
-                "value": value,
-                "delta": delta,
-            })
-        }
-        ElementType::Progress { value } => {
-            serde_json::json!({
-                "type": "progress",
-                "value": value,
-            })
-        }
-        ElementType::Column { .. } => {
-            serde_json::json!({
-                "type": "column",
-            })
-        }
-        ElementType::Row { .. } => {
-            serde_json::json!({
-                "type": "row",
-            })
-        }
-        ElementType::Tab { .. } => {
-            serde_json::json!({
-                "type": "tab",
-            })
-        }
-        ElementType::Expander { .. } => {
-            serde_json::json!({
-                "type": "expander",
-            })
-        }
-        ElementType::Sidebar { .. } => {
-            serde_json::json!({
-                "type": "sidebar",
-            })
-        }
-        ElementType::Container { .. } => {
-            serde_json::json!({
-                "type": "container",
-            })
-        }
-        ElementType::Radio { label, options, value, key } => {
-            serde_json::json!({
-                "type": "radio",
-                "label": label,
-                "options": options,
-                "value": value,
-                "key": key,
-            })
-        }
-        ElementType::DateInput { label, value, key } => {
-            serde_json::json!({
-                "type": "date_input",
-                "label": label,
-                "value": value,
-                "key": key,
-            })
-        }
-        ElementType::TimeInput { label, value, key } => {
-            serde_json::json!({
-                "type": "time_input",
-                "label": label,
-                "value": value,
-                "key": key,
-            })
-        }
-        ElementType::ColorPicker { label, value, key } => {
-            serde_json::json!({
-                "type": "color_picker",
-                "label": label,
-                "value": value,
-                "key": key,
-            })
-        }
-        ElementType::FileUploader { label, key } => {
-            serde_json::json!({
-                "type": "file_uploader",
-                "label": label,
-                "key": key,
-            })
-        }
-        ElementType::Dataframe { data } => {
-            serde_json::json!({
-                "type": "dataframe",
-                "data": data,
-            })
-        }
-        ElementType::Table { headers, rows } => {
-            serde_json::json!({
-                "type": "table",
-                "headers": headers,
-                "rows": rows,
-            })
-        }
-        ElementType::CameraInput { label, key } => {
-            serde_json::json!({
-                "type": "camera_input",
-                "label": label,
-                "key": key,
-            })
-        }
-        ElementType::Audio { src } => {
-            serde_json::json!({
-                "type": "audio",
-                "src": src,
-            })
-        }
-        ElementType::Video { src } => {
-            serde_json::json!({
-                "type": "video",
-                "src": src,
-            })
-        }
-        ElementType::Tabs { tabs } => {
-            serde_json::json!({
-                "type": "tabs",
-                "tabs": tabs,
-            })
-        }
-        ElementType::LineChart { .. } => {
-            serde_json::json!({
-                "type": "line_chart",
-            })
-        }
-        ElementType::BarChart { .. } => {
-            serde_json::json!({
-                "type": "bar_chart",
-            })
-        }
-        ElementType::AreaChart { .. } => {
-            serde_json::json!({
-                "type": "area_chart",
-            })
-        }
-        ElementType::ScatterChart { .. } => {
-            serde_json::json!({
-                "type": "scatter_chart",
-            })
-        }
-        ElementType::PieChart { .. } => {
-            serde_json::json!({
-                "type": "pie_chart",
-            })
-        }
-        ElementType::PlotlyChart { .. } => {
-            serde_json::json!({
-                "type": "plotly_chart",
-            })
-        }
-        ElementType::VegaLiteChart { .. } => {
-            serde_json::json!({
-                "type": "vega_lite_chart",
-            })
-        }
-        ElementType::BokehChart { .. } => {
-            serde_json::json!({
-                "type": "bokeh_chart",
-            })
+    }
+    if let Some(serde_json::Value::Array(layouts)) = fields.remove("layouts") {
+        let layouts: serde_json::Map<String, serde_json::Value> = layouts
+            .into_iter()
+            .filter_map(|entry| match entry {
+                serde_json::Value::Array(mut pair) if pair.len() == 2 => {
+                    let layout = pair.pop()?;
+                    Some((pair.pop()?.as_str()?.to_string(), layout))
+                }
+                _ => None,
+            })
+            .collect();
+        fields.insert("layouts".to_string(), layouts.into());
+    }
+    json
+}
+
+/// Parse an element sent as JSON by [`element_to_json`].
+pub fn element_from_json(json: &serde_json::Value) -> Option<ElementType> {
+    let mut fields = json.as_object()?.clone();
+    let tag = variant_tag(fields.get("type")?.as_str()?);
+    if RATE_LIMITED_TYPES.contains(&tag.as_str()) {
+        let rate_limit: serde_json::Map<String, serde_json::Value> = ["debounce_ms", "throttle_ms"]
+            .into_iter()
+            .filter_map(|field| Some((field.to_string(), fields.remove(field)?)))
+            .collect();
+        fields.insert("rate_limit".to_string(), rate_limit.into());
+    }
+    fields.insert("type".to_string(), tag.into());
+    if let Some(children) = fields.get_mut("children") {
+        map_ids(children, id_from_string);
+    }
+    if let Some(tabs) = fields.get_mut("tabs").and_then(|tabs| tabs.as_array_mut()) {
+        for tab in tabs {
+            if let Some(ids) = tab.get_mut(1) {
+                map_ids(ids, id_from_string);
+            }
         }
     }
+    if let Some(serde_json::Value::Object(layouts)) = fields.remove("layouts") {
+        let layouts: Vec<serde_json::Value> = layouts
+            .into_iter()
+            .map(|(breakpoint, layout)| serde_json::json!([breakpoint, layout]))
+            .collect();
+        fields.insert("layouts".to_string(), layouts.into());
+    }
+    match serde_json::from_value(serde_json::Value::Object(fields)) {
+        Ok(element) => Some(element),
+        // A type added after this server, kept as a placeholder
+        Err(e) if e.to_string().starts_with("unknown variant") => Some(ElementType::Unknown {
+            type_name: json.get("type")?.as_str()?.to_string(),
+        }),
+        Err(_) => None,
+    }
 }
 
 /// Create a NewSessionMsg
 pub fn create_session_msg(session_id: &str, script_hash: &str) -> ForwardMsg {
     ForwardMsg {
-        hash: Uuid::new_v4().to_string(),
+        hash: message_hash(),
+        seq: 0,
         r#type: Some(forward_msg::Type::NewSession(NewSessionMsg {
             session_id: session_id.to_string(),
             script_hash: script_hash.to_string(),
@@ -742,6 +1165,391 @@ pub fn create_session_msg(session_id: &str, script_hash: &str) -> ForwardMsg {
     }
 }
 
+/// Create a SetThemeMsg
+pub fn create_set_theme_msg(mode: ThemeMode, theme: ColorScheme) -> ForwardMsg {
+    ForwardMsg {
+        hash: message_hash(),
+        seq: 0,
+        r#type: Some(forward_msg::Type::SetTheme(SetThemeMsg {
+            mode: mode.as_str().to_string(),
+            theme: theme.as_str().to_string(),
+        })),
+    }
+}
+
+/// Convert a theme change to JSON for the frontend
+pub fn set_theme_to_json(mode: ThemeMode, theme: ColorScheme) -> serde_json::Value {
+    serde_json::json!({
+        "type": "set_theme",
+        "mode": mode.as_str(),
+        "theme": theme.as_str(),
+    })
+}
+
+/// Create an ActionMsg
+pub fn create_action_msg(action: &Action) -> ForwardMsg {
+    let action_type = match action {
+        Action::Effect(effect) => action_msg::Type::Effect(EffectAction {
+            effect: effect.as_str().to_string(),
+        }),
+        Action::ScrollTo { element_id } => action_msg::Type::ScrollTo(ScrollToAction {
+            element_id: element_id.to_string(),
+        }),
+        Action::CopyToClipboard { text } => {
+            action_msg::Type::CopyToClipboard(CopyToClipboardAction { text: text.clone() })
+        }
+        Action::Focus { key, autofocus } => action_msg::Type::Focus(FocusAction {
+            widget_key: key.clone(),
+            autofocus: *autofocus,
+        }),
+        Action::PostToHost { data } => action_msg::Type::PostToHost(PostToHostAction { data: data.to_string() }),
+    };
+
+    ForwardMsg {
+        hash: message_hash(),
+        seq: 0,
+        r#type: Some(forward_msg::Type::Action(ActionMsg {
+            r#type: Some(action_type),
+        })),
+    }
+}
+
+/// Convert an action to JSON for the frontend
+pub fn action_to_json(action: &Action) -> serde_json::Value {
+    match action {
+        Action::Effect(effect) => serde_json::json!({
+            "type": "action",
+            "action": "effect",
+            "effect": effect.as_str(),
+        }),
+        Action::ScrollTo { element_id } => serde_json::json!({
+            "type": "action",
+            "action": "scroll_to",
+            "element_id": element_id.to_string(),
+        }),
+        Action::CopyToClipboard { text } => serde_json::json!({
+            "type": "action",
+            "action": "copy_to_clipboard",
+            "text": text,
+        }),
+        Action::Focus { key, autofocus } => serde_json::json!({
+            "type": "action",
+            "action": "focus",
+            "key": key,
+            "autofocus": autofocus,
+        }),
+        Action::PostToHost { data } => serde_json::json!({
+            "type": "action",
+            "action": "post_to_host",
+            "data": data,
+        }),
+    }
+}
+
+/// Get the code and details of an error as sent to clients
+pub fn error_info(error: &platypus_core::Error) -> ErrorInfo {
+    let (field, rule) = match error {
+        platypus_core::Error::Validation { field, rule } => (field.clone(), rule.clone()),
+        _ => Default::default(),
+    };
+    ErrorInfo {
+        code: error.code().as_str().to_string(),
+        message: error.to_string(),
+        field,
+        rule,
+    }
+}
+
+/// Create an ErrorMsg
+pub fn create_error_msg(error: &platypus_core::Error) -> ForwardMsg {
+    ForwardMsg {
+        hash: message_hash(),
+        seq: 0,
+        r#type: Some(forward_msg::Type::Error(ErrorMsg {
+            message: error.to_string(),
+            traceback: String::new(),
+            info: Some(error_info(error)),
+        })),
+    }
+}
+
+/// Convert an error to JSON for the frontend
+pub fn error_to_json(error: &platypus_core::Error) -> serde_json::Value {
+    let info = error_info(error);
+    let mut json = serde_json::json!({
+        "code": info.code,
+        "message": info.message,
+    });
+    if !info.field.is_empty() {
+        json["field"] = info.field.into();
+        json["rule"] = info.rule.into();
+    }
+    serde_json::json!({
+        "type": "error",
+        "error": json,
+    })
+}
+
+fn log_line_to_proto(line: &LogLineItem) -> LogLine {
+    LogLine {
+        seq: line.seq,
+        level: line.level.clone(),
+        message: line.message.clone(),
+        timestamp: line.timestamp.clone(),
+    }
+}
+
+/// Create a LogLines message for lines appended to a log viewer
+pub fn create_log_lines_msg(key: &str, lines: &[LogLineItem]) -> ForwardMsg {
+    ForwardMsg {
+        hash: message_hash(),
+        seq: 0,
+        r#type: Some(forward_msg::Type::LogLines(LogLinesMsg {
+            key: key.to_string(),
+            lines: lines.iter().map(log_line_to_proto).collect(),
+        })),
+    }
+}
+
+/// Convert appended log lines to JSON for the frontend
+pub fn log_lines_to_json(key: &str, lines: &[LogLineItem]) -> serde_json::Value {
+    serde_json::json!({
+        "type": "log_lines",
+        "key": key,
+        "lines": lines,
+    })
+}
+
+/// Create a SessionExpiring message for an idle session
+pub fn create_session_expiring_msg(expires_in: Duration) -> ForwardMsg {
+    ForwardMsg {
+        hash: message_hash(),
+        seq: 0,
+        r#type: Some(forward_msg::Type::SessionExpiring(SessionExpiringMsg {
+            expires_in_secs: expires_in.as_secs(),
+        })),
+    }
+}
+
+/// Convert an idle session warning to JSON for the frontend
+pub fn session_expiring_to_json(expires_in: Duration) -> serde_json::Value {
+    serde_json::json!({
+        "type": "session_expiring",
+        "expires_in": expires_in.as_secs(),
+    })
+}
+
+/// Create a SessionExpired message
+pub fn create_session_expired_msg() -> ForwardMsg {
+    ForwardMsg {
+        hash: message_hash(),
+        seq: 0,
+        r#type: Some(forward_msg::Type::SessionExpired(SessionExpiredMsg {})),
+    }
+}
+
+/// Convert a session expiry to JSON for the frontend
+pub fn session_expired_to_json() -> serde_json::Value {
+    serde_json::json!({ "type": "session_expired" })
+}
+
+fn transfer_progress_to_proto(progress: &TransferProgress) -> TransferProgressMsg {
+    TransferProgressMsg {
+        id: progress.id.clone(),
+        key: progress.key.clone(),
+        direction: progress.direction.as_str().to_string(),
+        loaded: progress.loaded,
+        total: progress.total,
+        url: progress.url.clone().unwrap_or_default(),
+    }
+}
+
+/// Create a TransferProgress message for an upload the server received
+pub fn create_transfer_progress_msg(progress: &TransferProgress) -> ForwardMsg {
+    ForwardMsg {
+        hash: message_hash(),
+        seq: 0,
+        r#type: Some(forward_msg::Type::TransferProgress(transfer_progress_to_proto(progress))),
+    }
+}
+
+/// Convert transfer progress to JSON for the frontend
+pub fn transfer_progress_to_json(progress: &TransferProgress) -> serde_json::Value {
+    serde_json::json!({
+        "type": "transfer_progress",
+        "progress": progress,
+    })
+}
+
+/// Convert a proto TransferProgressMsg into runtime transfer progress
+pub fn transfer_progress_from_proto(msg: TransferProgressMsg) -> Option<TransferProgress> {
+    Some(TransferProgress {
+        direction: TransferDirection::parse(&msg.direction)?,
+        id: msg.id,
+        key: msg.key,
+        loaded: msg.loaded,
+        total: msg.total,
+        url: (!msg.url.is_empty()).then_some(msg.url),
+    })
+}
+
+/// Convert a proto ClientContextMsg into runtime client info
+pub fn client_context_from_proto(msg: &ClientContextMsg) -> ClientInfo {
+    ClientInfo {
+        viewport_width: msg.viewport_width,
+        viewport_height: msg.viewport_height,
+        timezone: msg.timezone.clone(),
+        locale: msg.locale.clone(),
+        color_scheme: ColorScheme::parse(&msg.color_scheme),
+        geolocation: msg.geolocation.as_ref().map(|g| platypus_runtime::Geolocation {
+            latitude: g.latitude,
+            longitude: g.longitude,
+            accuracy: g.accuracy,
+        }),
+        breakpoint: msg.breakpoint.clone(),
+        focused_widget: (!msg.focused_widget.is_empty()).then(|| msg.focused_widget.clone()),
+    }
+}
+
+/// Convert a BackMsg into an event, if it carries one
+pub fn event_from_back_msg(msg: BackMsg) -> Option<Event> {
+    match msg.r#type? {
+        back_msg::Type::WidgetStateChange(change) => Some(Event::WidgetChanged {
+            key: change.widget_key,
+            // Already JSON-encoded; stored as sent
+            value: serde_json::Value::String(change.value),
+        }),
+        back_msg::Type::WidgetStateBatch(batch) => Some(Event::WidgetsChanged {
+            changes: batch
+                .changes
+                .into_iter()
+                .map(|change| WidgetChange {
+                    key: change.widget_key,
+                    value: serde_json::Value::String(change.value),
+                })
+                .collect(),
+        }),
+        back_msg::Type::FormSubmit(submit) => Some(Event::FormSubmitted {
+            form: submit.form_key,
+            submit_key: submit.submit_key,
+            changes: submit
+                .changes
+                .into_iter()
+                .map(|change| WidgetChange {
+                    key: change.widget_key,
+                    value: serde_json::Value::String(change.value),
+                })
+                .collect(),
+        }),
+        back_msg::Type::RerunScript(_) => Some(Event::RerunScript),
+        back_msg::Type::UserInteraction(interaction) => Some(Event::Custom {
+            event_type: interaction.interaction_type,
+            data: serde_json::from_str(&interaction.data).unwrap_or(serde_json::Value::Null),
+        }),
+        back_msg::Type::ClientContext(context) => Some(Event::ClientContext {
+            info: client_context_from_proto(&context),
+        }),
+        back_msg::Type::ThemePreference(preference) => theme_preference_event(&preference.mode),
+        back_msg::Type::TransferProgress(progress) => {
+            transfer_progress_from_proto(progress).map(|progress| Event::TransferProgress { progress })
+        }
+        back_msg::Type::ElementVisible(msg) => Some(Event::ElementVisible { key: msg.key }),
+        back_msg::Type::HostMessage(msg) => Some(Event::HostMessage {
+            origin: msg.origin,
+            data: serde_json::from_str(&msg.data).unwrap_or(serde_json::Value::Null),
+        }),
+        // Handled by the connection, not the app
+        back_msg::Type::Resync(_) | back_msg::Type::KeepAlive(_) => None,
+        back_msg::Type::ChartSelection(msg) => match serde_json::from_str(&msg.selection) {
+            Ok(selection) => Some(Event::ChartSelection {
+                key: msg.chart_key,
+                selection,
+            }),
+            Err(e) => {
+                tracing::warn!("Invalid chart selection: {}", e);
+                None
+            }
+        },
+    }
+}
+
+/// Convert a JSON message from the web frontend into an event, if it carries one
+pub fn event_from_json(msg: &serde_json::Value) -> Option<Event> {
+    let str_field = |name: &str| msg.get(name).and_then(|v| v.as_str()).map(str::to_string);
+
+    match msg.get("type")?.as_str()? {
+        "widget_change" => Some(Event::WidgetChanged {
+            key: str_field("key")?,
+            value: msg.get("value")?.clone(),
+        }),
+        "widget_batch" => Some(Event::WidgetsChanged {
+            changes: serde_json::from_value(msg.get("changes")?.clone()).ok()?,
+        }),
+        "form_submit" => Some(Event::FormSubmitted {
+            form: str_field("form")?,
+            submit_key: str_field("submit_key")?,
+            changes: serde_json::from_value(msg.get("changes")?.clone()).ok()?,
+        }),
+        "button_click" => Some(Event::ButtonClicked { key: str_field("key")? }),
+        "file_upload" => Some(Event::FileUploaded {
+            key: str_field("key")?,
+            files: serde_json::from_value(msg.get("files")?.clone()).ok()?,
+        }),
+        "page_change" => Some(Event::PageChanged { page: str_field("page")? }),
+        "client_context" => match serde_json::from_value::<ClientInfo>(msg.get("context")?.clone()) {
+            Ok(info) => Some(Event::ClientContext { info }),
+            Err(e) => {
+                tracing::warn!("Invalid client context: {}", e);
+                None
+            }
+        },
+        "theme_preference" => theme_preference_event(&str_field("mode")?),
+        "transfer_progress" => match serde_json::from_value(msg.get("progress")?.clone()) {
+            Ok(progress) => Some(Event::TransferProgress { progress }),
+            Err(e) => {
+                tracing::warn!("Invalid transfer progress: {}", e);
+                None
+            }
+        },
+        "chart_selection" => match serde_json::from_value(msg.get("selection")?.clone()) {
+            Ok(selection) => Some(Event::ChartSelection {
+                key: str_field("key")?,
+                selection,
+            }),
+            Err(e) => {
+                tracing::warn!("Invalid chart selection: {}", e);
+                None
+            }
+        },
+        "element_visible" => Some(Event::ElementVisible { key: str_field("key")? }),
+        "host_message" => Some(Event::HostMessage {
+            origin: str_field("origin")?,
+            data: msg.get("data").cloned().unwrap_or_default(),
+        }),
+        "rerun" => Some(Event::RerunScript),
+        "custom" => Some(Event::Custom {
+            event_type: str_field("event_type")?,
+            data: msg.get("data").cloned().unwrap_or_default(),
+        }),
+        // Handled by the connection, not the app
+        "ack" | "resync" => None,
+        other => {
+            tracing::warn!("Unknown message type: {}", other);
+            None
+        }
+    }
+}
+
+fn theme_preference_event(mode: &str) -> Option<Event> {
+    match ThemeMode::parse(mode) {
+        Some(mode) => Some(Event::ThemePreference { mode }),
+        None => {
+            tracing::warn!("Invalid theme mode: {}", mode);
+            None
+        }
+    }
+}
+
 /// Serialize ForwardMsg to bytes
 pub fn serialize_forward_msg(msg: &ForwardMsg) -> Result<Vec<u8>, prost::EncodeError> {
     let mut buf = Vec::new();
@@ -749,6 +1557,29 @@ pub fn serialize_forward_msg(msg: &ForwardMsg) -> Result<Vec<u8>, prost::EncodeE
     Ok(buf)
 }
 
+/// Deserialize ForwardMsg from bytes. Elements of types added after this
+/// build arrive with no type, and become `UnknownElement` placeholders.
+pub fn deserialize_forward_msg(bytes: &[u8]) -> Result<ForwardMsg, prost::DecodeError> {
+    let mut msg = ForwardMsg::decode(bytes)?;
+    let elements: Vec<&mut Element> = match &mut msg.r#type {
+        Some(forward_msg::Type::NewSession(session)) => session.elements.iter_mut().collect(),
+        Some(forward_msg::Type::Delta(delta_msg)) => delta_msg
+            .deltas
+            .iter_mut()
+            .filter_map(|delta| match &mut delta.r#type {
+                Some(delta::Type::AddElement(add)) => add.element.as_mut(),
+                Some(delta::Type::UpdateElement(update)) => update.element.as_mut(),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    for element in elements.into_iter().filter(|element| element.r#type.is_none()) {
+        element.r#type = Some(element::Type::Unknown(UnknownElement::default()));
+    }
+    Ok(msg)
+}
+
 /// Deserialize BackMsg from bytes
 pub fn deserialize_back_msg(bytes: &[u8]) -> Result<BackMsg, prost::DecodeError> {
     BackMsg::decode(bytes)
@@ -764,7 +1595,7 @@ mod tests {
         let element = ElementType::Text {
             value: "Hello".to_string(),
         };
-        let proto = element_type_to_proto(1, &element);
+        let proto = element_type_to_proto(ElementId::new(1), &element);
         assert_eq!(proto.id, "1");
     }
 
@@ -777,8 +1608,102 @@ mod tests {
             },
             parent_id: None,
         };
-        let msg = create_delta_msg(vec![delta]);
+        let msg = create_delta_msg(vec![delta.clone()]);
         assert!(!msg.hash.is_empty());
+
+        let encode = || {
+            with_hash_ids(Arc::new(platypus_core::SequentialIds::new(1)), || {
+                serialize_forward_msg(&create_delta_msg(vec![delta.clone()])).unwrap()
+            })
+        };
+        assert_eq!(encode(), encode());
+    }
+
+    #[test]
+    fn test_unknown_elements_become_placeholders() {
+        let json = serde_json::json!({"type": "hologram", "key": "h1"});
+        assert!(matches!(
+            element_from_json(&json),
+            Some(ElementType::Unknown { type_name }) if type_name == "hologram"
+        ));
+        assert!(element_from_json(&serde_json::json!({"type": "text"})).is_none());
+
+        // An element type added after this build arrives with no type
+        let mut msg = create_delta_msg(vec![CoreDelta::AddElement {
+            id: ElementId::new(1),
+            element: ElementType::Divider,
+            parent_id: None,
+        }]);
+        if let Some(forward_msg::Type::Delta(delta_msg)) = &mut msg.r#type
+            && let Some(delta::Type::AddElement(add)) = &mut delta_msg.deltas[0].r#type
+        {
+            add.element.as_mut().unwrap().r#type = None;
+        }
+        let decoded = deserialize_forward_msg(&serialize_forward_msg(&msg).unwrap()).unwrap();
+        let Some(forward_msg::Type::Delta(delta_msg)) = decoded.r#type else {
+            panic!("not a delta: {:?}", decoded);
+        };
+        let Some(delta::Type::AddElement(add)) = &delta_msg.deltas[0].r#type else {
+            panic!("not an added element");
+        };
+        assert!(matches!(add.element.as_ref().unwrap().r#type, Some(element::Type::Unknown(_))));
+    }
+
+    #[test]
+    fn test_client_context_from_proto() {
+        let msg = ClientContextMsg {
+            viewport_width: 1024,
+            color_scheme: "dark".to_string(),
+            geolocation: Some(Geolocation {
+                latitude: 22.3,
+                longitude: 114.2,
+                accuracy: 10.0,
+            }),
+            focused_widget: "email".to_string(),
+            ..Default::default()
+        };
+        let info = client_context_from_proto(&msg);
+        assert_eq!(info.viewport_width, 1024);
+        assert!(info.prefers_dark());
+        assert!(info.geolocation.is_some());
+        assert_eq!(info.focused_widget.as_deref(), Some("email"));
+    }
+
+    #[test]
+    fn test_create_set_theme_msg() {
+        let msg = create_set_theme_msg(ThemeMode::Auto, ColorScheme::Dark);
+        match msg.r#type {
+            Some(forward_msg::Type::SetTheme(set_theme)) => {
+                assert_eq!(set_theme.mode, "auto");
+                assert_eq!(set_theme.theme, "dark");
+            }
+            other => panic!("unexpected message: {:?}", other),
+        }
+        assert_eq!(set_theme_to_json(ThemeMode::Light, ColorScheme::Light)["theme"], "light");
+    }
+
+    #[test]
+    fn test_action_messages() {
+        let action = Action::Effect(platypus_runtime::Effect::Balloons);
+        match create_action_msg(&action).r#type {
+            Some(forward_msg::Type::Action(ActionMsg {
+                r#type: Some(action_msg::Type::Effect(effect)),
+            })) => assert_eq!(effect.effect, "balloons"),
+            other => panic!("unexpected message: {:?}", other),
+        }
+        assert_eq!(action_to_json(&action)["effect"], "balloons");
+
+        let focus = Action::Focus {
+            key: "email".to_string(),
+            autofocus: true,
+        };
+        assert!(matches!(
+            create_action_msg(&focus).r#type,
+            Some(forward_msg::Type::Action(ActionMsg {
+                r#type: Some(action_msg::Type::Focus(FocusAction { widget_key, autofocus: true })),
+            })) if widget_key == "email"
+        ));
+        assert_eq!(action_to_json(&focus)["action"], "focus");
     }
 
     #[test]
@@ -787,4 +1712,484 @@ mod tests {
         let bytes = serialize_forward_msg(&msg).unwrap();
         assert!(!bytes.is_empty());
     }
+
+    #[test]
+    fn test_widget_batch_event() {
+        let msg = serde_json::json!({
+            "type": "widget_batch",
+            "changes": [{"key": "name", "value": "Ada"}, {"key": "age", "value": 36}],
+        });
+        match event_from_json(&msg) {
+            Some(Event::WidgetsChanged { changes }) => {
+                assert_eq!(changes.len(), 2);
+                assert_eq!(changes[1].value, 36);
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_malformed_messages() {
+        for text in ["", "not json", "[1, 2]", "null", "\"widget_change\"", "{\"type\": "] {
+            assert!(parse_json_msg(text).is_none(), "{:?}", text);
+        }
+        for text in [
+            r#"{"type": "ack", "ack": -1, "seq": "1"}"#,
+            r#"{"type": "widget_change", "value": 1}"#,
+            r#"{"type": "client_context", "context": {"viewport_width": -5}}"#,
+            r#"{"type": "chart_selection", "key": "c", "selection": {"points": "all"}}"#,
+            r#"{"type": 7}"#,
+            r#"{"type": "unknown"}"#,
+        ] {
+            let msg = parse_json_msg(text).unwrap();
+            assert_eq!(json_seq(&msg), 0);
+            assert!(json_ack(&msg).is_none());
+            assert!(event_from_json(&msg).is_none(), "{}", text);
+        }
+        assert!(deserialize_back_msg(&[0xff, 0xff, 0xff]).is_err());
+    }
+
+    #[test]
+    fn test_reconciled_delta_msg() {
+        let msg = create_reconciled_delta_msg(vec![], 7);
+        match msg.r#type {
+            Some(forward_msg::Type::Delta(delta)) => assert_eq!(delta.reconcile_seq, 7),
+            other => panic!("unexpected message: {:?}", other),
+        }
+        assert_eq!(reconciled_deltas_to_json(vec![], 7)["seq"], 7);
+        assert_eq!(json_seq(&serde_json::json!({"type": "rerun", "seq": 3})), 3);
+    }
+
+    #[test]
+    fn test_transfer_progress_event() {
+        let msg = serde_json::json!({
+            "type": "transfer_progress",
+            "progress": {"id": "m1", "key": "report", "direction": "download", "loaded": 512, "total": 1024},
+        });
+        let Some(Event::TransferProgress { progress }) = event_from_json(&msg) else {
+            panic!("expected a transfer progress event");
+        };
+        assert_eq!(progress.fraction(), Some(0.5));
+        assert_eq!(transfer_progress_to_json(&progress)["progress"]["direction"], "download");
+    }
+
+    #[test]
+    fn test_chart_selection_event() {
+        let msg = serde_json::json!({
+            "type": "chart_selection",
+            "key": "sales",
+            "selection": {"kind": "click", "point": {"index": 2, "x": "Mar", "y": 7}},
+        });
+        let Some(Event::ChartSelection { key, selection }) = event_from_json(&msg) else {
+            panic!("expected a chart selection event");
+        };
+        assert_eq!((key.as_str(), selection.indices()), ("sales", vec![2]));
+
+        let back = BackMsg {
+            r#type: Some(back_msg::Type::ChartSelection(ChartSelectionMsg {
+                chart_key: "sales".to_string(),
+                selection: r#"{"kind": "zoom"}"#.to_string(),
+            })),
+            ..Default::default()
+        };
+        assert!(matches!(event_from_back_msg(back), Some(Event::ChartSelection { .. })));
+    }
+
+    /// One element of every variant, with distinct field values.
+    fn sample_elements() -> Vec<ElementType> {
+        use platypus_core::element::{
+            CodeAnnotation, FeedbackKind, ImageAnnotation, JsonEditorError, NotificationItem, RateLimit, SkeletonKind,
+            TextInputKind,
+        };
+        use platypus_core::traits::Layout;
+
+        let key = || Some("key-1".to_string());
+        let ids = || vec![ElementId::new(7), ElementId::new(8)];
+        let rate_limit = RateLimit { debounce_ms: 150, throttle_ms: 40 };
+        vec![
+            ElementType::Text { value: "text-value".into() },
+            ElementType::Markdown { value: "**markdown**".into() },
+            ElementType::Code { value: "fn main() {}".into(), language: Some("rust".into()) },
+            ElementType::Heading { value: "heading".into(), level: 2 },
+            ElementType::Echo { key: "echo-key".into(), template: "Hi {}".into(), value: "echo-value".into() },
+            ElementType::ChatMessage { role: "assistant".into(), content: "chat-content".into() },
+            ElementType::Button { label: "button-label".into(), key: key(), disabled: true },
+            ElementType::TextInput {
+                label: "text-input".into(),
+                value: "typed".into(),
+                key: key(),
+                kind: TextInputKind::Email,
+                error: Some("input-error".into()),
+                rate_limit,
+                disabled: false,
+            },
+            ElementType::TextArea { label: "text-area".into(), value: "area-value".into(), key: key(), disabled: false },
+            ElementType::NumberInput { label: "number".into(), value: 2.5, key: key(), display: Some("%.1f".into()), disabled: false },
+            ElementType::Slider { label: "slider".into(), value: 3.0, min: 1.0, max: 9.0, key: key(), rate_limit, disabled: false },
+            ElementType::Checkbox { label: "checkbox".into(), value: true, key: key(), disabled: false },
+            ElementType::Radio { label: "radio".into(), options: vec!["radio-a".into()], value: Some("radio-a".into()), key: key(), disabled: false },
+            ElementType::Selectbox { label: "select".into(), options: vec!["select-a".into()], value: None, key: key(), disabled: false },
+            ElementType::Multiselect { label: "multi".into(), options: vec!["multi-a".into()], values: vec!["multi-a".into()], key: key(), disabled: false },
+            ElementType::DateInput { label: "date".into(), value: "2024-01-02".into(), key: key(), disabled: false },
+            ElementType::TimeInput { label: "time".into(), value: "12:30".into(), key: key(), disabled: false },
+            ElementType::DateRangeInput {
+                label: "range".into(),
+                start: "2024-01-01".into(),
+                end: "2024-01-31".into(),
+                min: Some("2023-01-01".into()),
+                max: None,
+                key: key(),
+                disabled: false,
+            },
+            ElementType::DateTimeInput {
+                label: "datetime".into(),
+                value: "2024-01-02T03:04".into(),
+                timezone: "Asia/Hong_Kong".into(),
+                min: None,
+                max: Some("2025-01-01T00:00".into()),
+                key: key(),
+                disabled: false,
+            },
+            ElementType::ColorPicker { label: "color".into(), value: "#ff0000".into(), key: key(), disabled: false },
+            ElementType::FileUploader { label: "upload".into(), key: key(), disabled: false },
+            ElementType::Dropzone {
+                label: "dropzone".into(),
+                accept: vec!["image/*".into()],
+                allow_paste: true,
+                show_preview: true,
+                key: key(),
+                disabled: false,
+            },
+            ElementType::ThemeToggle { label: "theme".into(), mode: "dark".into(), key: key(), disabled: false },
+            ElementType::Feedback { kind: FeedbackKind::Stars, value: Some(4), key: key(), disabled: false },
+            ElementType::Rating { max: 5, value: Some(3), key: key(), disabled: false },
+            ElementType::TagsInput {
+                label: "tags".into(),
+                suggestions: vec!["tag-suggestion".into()],
+                values: vec!["tag-value".into()],
+                max_tags: Some(3),
+                allow_custom: true,
+                error: None,
+                key: key(),
+                disabled: false,
+            },
+            ElementType::CodeEditor {
+                label: "editor".into(),
+                value: "select 1".into(),
+                language: "sql".into(),
+                read_only: vec![(1, 2)],
+                annotations: vec![CodeAnnotation { line: 1, severity: "warning".into(), message: "annotation".into() }],
+                debounce_ms: 300,
+                error: None,
+                key: key(),
+                disabled: false,
+            },
+            ElementType::JsonEditor {
+                text: "{}".into(),
+                schema: Some(serde_json::json!({"type": "object"})),
+                errors: vec![JsonEditorError { path: "/name".into(), message: "json-error".into() }],
+                key: key(),
+                disabled: false,
+            },
+            ElementType::RichTextEditor {
+                label: "rich".into(),
+                value: "<p>rich</p>".into(),
+                max_bytes: 1024,
+                error: None,
+                key: key(),
+                disabled: false,
+            },
+            ElementType::Json { value: serde_json::json!({"json-field": [1, 2]}) },
+            ElementType::Dataframe { data: "[{\"a\": 1}]".into() },
+            ElementType::PivotTable { key: "pivot-key".into(), data: "{\"rows\": []}".into() },
+            ElementType::Table { headers: vec!["header".into()], rows: vec![vec!["cell".into()]] },
+            ElementType::CameraInput { label: "camera".into(), key: key(), disabled: false },
+            ElementType::AudioInput { label: "microphone".into(), key: "audio-key".into(), disabled: false },
+            ElementType::Container { children: ids(), hidden: true },
+            ElementType::Column { children: ids(), width: Some(2.0), hidden: false },
+            ElementType::Row { children: ids(), hidden: false },
+            ElementType::Tab { label: "tab".into(), children: ids(), hidden: false },
+            ElementType::Expander { label: "expander".into(), expanded: true, children: ids(), hidden: false },
+            ElementType::Fieldset { legend: "fieldset".into(), children: ids(), hidden: false },
+            ElementType::Form { key: "form-key".into(), children: ids(), hidden: true },
+            ElementType::AudioWaveform {
+                src: "/media/waveform".into(),
+                regions: vec![(1.5, 4.25)],
+                key: "waveform-key".into(),
+                disabled: true,
+            },
+            ElementType::DataEditor {
+                headers: vec!["header".into()],
+                cells: vec![vec!["=1+1".into()]],
+                values: vec![vec!["2".into()]],
+                key: "editor-key".into(),
+                disabled: true,
+            },
+            ElementType::FormSubmitButton {
+                label: "submit-label".into(),
+                form_key: "form-key".into(),
+                key: key(),
+                disabled: true,
+            },
+            ElementType::ResponsiveContainer {
+                children: ids(),
+                layouts: vec![(
+                    "md".into(),
+                    Layout {
+                        display: "flex".into(),
+                        flex_direction: Some("row".into()),
+                        width: Some("50%".into()),
+                        height: None,
+                    },
+                )],
+                hidden: false,
+            },
+            ElementType::Image { src: "/media/image".into(), caption: Some("caption".into()), width: Some(320) },
+            ElementType::ImageAnnotator {
+                src: "/media/annotated".into(),
+                labels: vec!["label-cat".into()],
+                annotations: vec![ImageAnnotation {
+                    label: Some("label-cat".into()),
+                    shape: AnnotationShape::Box { x: 1.0, y: 2.0, width: 3.0, height: 4.0 },
+                }],
+                key: "annotator-key".into(),
+                disabled: true,
+            },
+            ElementType::Audio { src: "/media/audio".into(), autoplay: true },
+            ElementType::Video { src: "/media/video".into() },
+            ElementType::DownloadButton {
+                label: "download".into(),
+                file_name: "report.csv".into(),
+                mime_type: "text/csv".into(),
+                url: "/media/report".into(),
+                key: key(),
+                disabled: false,
+            },
+            ElementType::Success { message: "success".into() },
+            ElementType::Error { message: "error".into() },
+            ElementType::Warning { message: "warning".into() },
+            ElementType::Info { message: "info".into() },
+            ElementType::ValidationSummary { errors: vec!["summary-error".into()] },
+            ElementType::Skeleton { kind: SkeletonKind::Table, count: 3 },
+            ElementType::Lazy { key: "lazy-key".into() },
+            ElementType::Progress { value: 0.5, text: Some("halfway".into()), indeterminate: false, eta_secs: Some(3.0) },
+            ElementType::NotificationCenter {
+                notifications: vec![NotificationItem {
+                    id: "note-1".into(),
+                    level: "info".into(),
+                    message: "notification".into(),
+                    read: false,
+                }],
+                key: key(),
+            },
+            ElementType::LogViewer {
+                lines: vec![LogLineItem {
+                    seq: 1,
+                    level: "warn".into(),
+                    message: "log-line".into(),
+                    timestamp: "12:00:00".into(),
+                }],
+                capacity: 100,
+                key: "log-key".into(),
+            },
+            ElementType::Tabs { tabs: vec![("first-tab".into(), ids())] },
+            ElementType::Sidebar { children: ids(), hidden: false },
+            ElementType::Metric { label: "metric".into(), value: "42".into(), delta: Some("+2".into()), sparkline: vec![1.0, 2.0] },
+            ElementType::Gauge { data: "{\"value\": 72}".into() },
+            ElementType::Donut { data: "{\"segments\": []}".into() },
+            ElementType::LineChart { data: "[{\"line\": 1}]".into(), title: Some("line".into()), key: key() },
+            ElementType::BarChart { data: "[{\"bar\": 1}]".into(), title: Some("bar".into()), key: None },
+            ElementType::AreaChart { data: "[{\"area\": 1}]".into(), title: None, key: key() },
+            ElementType::ScatterChart { data: "[{\"scatter\": 1}]".into(), title: Some("scatter".into()), key: key() },
+            ElementType::PieChart { data: "[{\"pie\": 1}]".into(), title: Some("pie".into()), key: None },
+            ElementType::CandlestickChart { data: "{\"candles\": []}".into(), title: Some("candles".into()), key: key() },
+            ElementType::LineChartStream { key: "stream-key".into(), capacity: 500 },
+            ElementType::Heatmap { key: "heatmap-key".into(), data: "{\"values\": []}".into(), title: Some("heatmap".into()) },
+            ElementType::PlotlyChart { spec: "{\"plotly\": 1}".into() },
+            ElementType::VegaLiteChart { spec: "{\"vega\": 1}".into() },
+            ElementType::BokehChart { spec: "{\"bokeh\": 1}".into() },
+            ElementType::Custom { name: "custom-name".into(), payload: serde_json::json!({"custom-field": "custom-value"}) },
+            ElementType::Empty,
+            ElementType::Divider,
+            ElementType::Unknown { type_name: "hologram".into() },
+        ]
+    }
+
+    /// Fails to compile when a variant is added without a sample above.
+    fn has_sample(element: &ElementType) -> bool {
+        match element {
+            ElementType::Text { .. }
+            | ElementType::Markdown { .. }
+            | ElementType::Code { .. }
+            | ElementType::Heading { .. }
+            | ElementType::Echo { .. }
+            | ElementType::ChatMessage { .. }
+            | ElementType::Button { .. }
+            | ElementType::TextInput { .. }
+            | ElementType::TextArea { .. }
+            | ElementType::NumberInput { .. }
+            | ElementType::Slider { .. }
+            | ElementType::Checkbox { .. }
+            | ElementType::Radio { .. }
+            | ElementType::Selectbox { .. }
+            | ElementType::Multiselect { .. }
+            | ElementType::DateInput { .. }
+            | ElementType::TimeInput { .. }
+            | ElementType::DateRangeInput { .. }
+            | ElementType::DateTimeInput { .. }
+            | ElementType::ColorPicker { .. }
+            | ElementType::FileUploader { .. }
+            | ElementType::Dropzone { .. }
+            | ElementType::ThemeToggle { .. }
+            | ElementType::Feedback { .. }
+            | ElementType::Rating { .. }
+            | ElementType::TagsInput { .. }
+            | ElementType::CodeEditor { .. }
+            | ElementType::JsonEditor { .. }
+            | ElementType::RichTextEditor { .. }
+            | ElementType::Json { .. }
+            | ElementType::Dataframe { .. }
+            | ElementType::PivotTable { .. }
+            | ElementType::Table { .. }
+            | ElementType::CameraInput { .. }
+            | ElementType::AudioInput { .. }
+            | ElementType::Container { .. }
+            | ElementType::Column { .. }
+            | ElementType::Row { .. }
+            | ElementType::Tab { .. }
+            | ElementType::Expander { .. }
+            | ElementType::Fieldset { .. }
+            | ElementType::Form { .. }
+            | ElementType::AudioWaveform { .. }
+            | ElementType::DataEditor { .. }
+            | ElementType::FormSubmitButton { .. }
+            | ElementType::ResponsiveContainer { .. }
+            | ElementType::Image { .. }
+            | ElementType::ImageAnnotator { .. }
+            | ElementType::Audio { .. }
+            | ElementType::Video { .. }
+            | ElementType::DownloadButton { .. }
+            | ElementType::Success { .. }
+            | ElementType::Error { .. }
+            | ElementType::Warning { .. }
+            | ElementType::Info { .. }
+            | ElementType::ValidationSummary { .. }
+            | ElementType::Skeleton { .. }
+            | ElementType::Lazy { .. }
+            | ElementType::Progress { .. }
+            | ElementType::NotificationCenter { .. }
+            | ElementType::LogViewer { .. }
+            | ElementType::Tabs { .. }
+            | ElementType::Sidebar { .. }
+            | ElementType::Metric { .. }
+            | ElementType::Gauge { .. }
+            | ElementType::Donut { .. }
+            | ElementType::LineChart { .. }
+            | ElementType::BarChart { .. }
+            | ElementType::AreaChart { .. }
+            | ElementType::ScatterChart { .. }
+            | ElementType::PieChart { .. }
+            | ElementType::CandlestickChart { .. }
+            | ElementType::LineChartStream { .. }
+            | ElementType::Heatmap { .. }
+            | ElementType::PlotlyChart { .. }
+            | ElementType::VegaLiteChart { .. }
+            | ElementType::BokehChart { .. }
+            | ElementType::Custom { .. }
+            | ElementType::Empty
+            | ElementType::Divider
+            | ElementType::Unknown { .. } => true,
+        }
+    }
+
+    /// Collect the string values of `json`, including object keys, but not
+    /// the `shape` tags of image annotations, which are oneof cases in the
+    /// proto.
+    fn json_strings(json: &serde_json::Value, strings: &mut Vec<String>) {
+        match json {
+            serde_json::Value::String(value) => strings.push(value.clone()),
+            serde_json::Value::Array(values) => values.iter().for_each(|value| json_strings(value, strings)),
+            serde_json::Value::Object(fields) => fields
+                .iter()
+                .filter(|(name, _)| name.as_str() != "shape")
+                .for_each(|(_, value)| json_strings(value, strings)),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn test_element_json_round_trip() {
+        let samples = sample_elements();
+        let tags: std::collections::HashSet<String> = samples
+            .iter()
+            .filter(|element| has_sample(element))
+            .map(|element| serde_json::to_value(element).unwrap()["type"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(tags.len(), samples.len(), "duplicate sample variant");
+
+        for element in &samples {
+            let json = element_to_json(element);
+            let parsed = element_from_json(&json).unwrap_or_else(|| panic!("unparsed JSON: {}", json));
+            assert_eq!(serde_json::to_value(&parsed).unwrap(), serde_json::to_value(element).unwrap());
+        }
+
+        let json = element_to_json(&samples[18]);
+        assert_eq!(json["type"], "datetime_input");
+        let json = element_to_json(&samples[10]);
+        assert_eq!((json["type"].as_str(), json["debounce_ms"].as_u64()), (Some("slider"), Some(150)));
+    }
+
+    #[test]
+    fn test_element_proto_round_trip_and_parity() {
+        for (index, element) in sample_elements().iter().enumerate() {
+            let proto = element_type_to_proto(ElementId::new(index as u64), element);
+            let decoded = Element::decode(proto.encode_to_vec().as_slice()).unwrap();
+            assert_eq!(decoded, proto);
+
+            // Every string the JSON transport carries is in the proto too
+            let debug = format!("{:?}", proto);
+            let mut strings = Vec::new();
+            json_strings(&element_to_json(element), &mut strings);
+            let tag = element_to_json(element)["type"].as_str().unwrap().to_string();
+            for value in strings.into_iter().filter(|value| *value != tag) {
+                let escaped = format!("{:?}", value);
+                let escaped = &escaped[1..escaped.len() - 1];
+                assert!(debug.contains(escaped), "{} proto lacks {:?}: {}", tag, value, debug);
+            }
+        }
+    }
+
+    #[test]
+    fn test_resync_messages() {
+        let msg = serde_json::json!({"type": "resync", "ack": 41});
+        assert!(json_is_resync(&msg));
+        assert_eq!(json_ack(&msg), Some(41));
+        assert!(event_from_json(&msg).is_none());
+
+        let back = BackMsg {
+            r#type: Some(back_msg::Type::Resync(ResyncMsg { last_seq: 41 })),
+            ack: 41,
+            ..Default::default()
+        };
+        assert!(back_msg_is_resync(&back));
+
+        let snapshot = snapshot_to_json(vec![], 3);
+        assert_eq!((snapshot["snapshot"].as_bool(), json_seq(&snapshot)), (Some(true), 3));
+    }
+
+    #[test]
+    fn test_error_messages() {
+        let error = platypus_core::Error::validation("value", "must be at most 5 long");
+        let json = error_to_json(&error);
+        assert_eq!(json["type"], "error");
+        assert_eq!(json["error"]["code"], "validation");
+        assert_eq!(json["error"]["field"], "value");
+        assert_eq!(json["error"]["rule"], "must be at most 5 long");
+
+        let msg = create_error_msg(&platypus_core::Error::ScriptExecutionError("boom".to_string()));
+        let Some(forward_msg::Type::Error(ErrorMsg { info: Some(info), .. })) = msg.r#type else {
+            panic!("expected an error message");
+        };
+        assert_eq!((info.code.as_str(), info.field.as_str()), ("script_execution", ""));
+    }
 }
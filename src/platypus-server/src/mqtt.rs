@@ -0,0 +1,84 @@
+//! MQTT subscriptions for IoT dashboards, enabled by the `mqtt` feature.
+//!
+//! The server subscribes to topic filters on a broker and publishes every
+//! message it receives to the [`TopicRegistry`] topic of the same name,
+//! where apps read them with `st.subscribe(topic)`:
+//!
+//! ```no_run
+//! use platypus_server::mqtt::MqttSource;
+//! use platypus_server::AppServer;
+//!
+//! let sensors = MqttSource::new("dashboard", "localhost", 1883).with_filter("sensors/+/temperature");
+//! let server = AppServer::new().with_mqtt_source(sensors);
+//! ```
+//!
+//! Subscriptions are at-least-once and renewed whenever the client
+//! reconnects.
+
+use platypus_runtime::TopicRegistry;
+use rumqttc::{AsyncClient, Event, MqttOptions, Packet, QoS};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// Requests queued for the broker before subscribing waits.
+const REQUEST_CAPACITY: usize = 64;
+
+/// Wait before polling again after a connection error.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// A broker connection and the topic filters subscribed on it.
+#[derive(Debug, Clone)]
+pub struct MqttSource {
+    options: MqttOptions,
+    filters: Vec<String>,
+}
+
+impl MqttSource {
+    /// Connect to the broker at `host` and `port` as `client_id`.
+    pub fn new(client_id: &str, host: &str, port: u16) -> Self {
+        Self::with_options(MqttOptions::new(client_id, host, port))
+    }
+
+    /// Connect with `options`, e.g. with credentials or keep-alive set.
+    pub fn with_options(options: MqttOptions) -> Self {
+        MqttSource {
+            options,
+            filters: Vec::new(),
+        }
+    }
+
+    /// Subscribe to `filter`, which may use the `+` and `#` wildcards.
+    pub fn with_filter(mut self, filter: impl Into<String>) -> Self {
+        self.filters.push(filter.into());
+        self
+    }
+
+    /// Publish the messages of the subscribed filters to `topics` until
+    /// the task is aborted.
+    pub fn spawn(&self, topics: TopicRegistry) -> JoinHandle<()> {
+        let (client, mut events) = AsyncClient::new(self.options.clone(), REQUEST_CAPACITY);
+        let filters = self.filters.clone();
+        tokio::spawn(async move {
+            loop {
+                match events.poll().await {
+                    // A new session forgets subscriptions, so renew them
+                    Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                        for filter in &filters {
+                            if let Err(e) = client.try_subscribe(filter.as_str(), QoS::AtLeastOnce) {
+                                tracing::warn!("Failed to subscribe to MQTT filter {}: {}", filter, e);
+                            }
+                        }
+                    }
+                    Ok(Event::Incoming(Packet::Publish(publish))) => {
+                        topics.publish_bytes(&publish.topic, &publish.payload);
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        tracing::warn!("MQTT connection error: {}", e);
+                        tokio::time::sleep(RECONNECT_DELAY).await;
+                    }
+                }
+            }
+        })
+    }
+}
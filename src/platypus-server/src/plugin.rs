@@ -0,0 +1,146 @@
+//! Server plugins.
+//!
+//! A [`ServerPlugin`] hooks into the server lifecycle so features such as
+//! auth, telemetry or custom protocols can ship as separate crates:
+//!
+//! ```
+//! use platypus_server::plugin::{BackMessage, PluginFlow, ServerPlugin};
+//! use platypus_server::AppServer;
+//! use platypus_core::session::SessionId;
+//!
+//! struct ReadOnly;
+//!
+//! impl ServerPlugin for ReadOnly {
+//!     fn name(&self) -> &str {
+//!         "read_only"
+//!     }
+//!
+//!     fn on_backmsg(&self, _session_id: SessionId, msg: BackMessage<'_>) -> PluginFlow {
+//!         match msg {
+//!             BackMessage::Json(msg) if msg["type"] == "widget_change" => PluginFlow::Stop,
+//!             _ => PluginFlow::Continue,
+//!         }
+//!     }
+//! }
+//!
+//! let server = AppServer::new().with_plugin(ReadOnly);
+//! assert_eq!(server.plugins().len(), 1);
+//! ```
+
+use crate::error::Result;
+use crate::server::ServerConfig;
+use axum::Router;
+use platypus_core::session::SessionId;
+use platypus_proto::BackMsg;
+use std::sync::Arc;
+
+/// Registered plugins, in registration order.
+pub type Plugins = Arc<[Arc<dyn ServerPlugin>]>;
+
+/// A message received from a client.
+#[derive(Debug, Clone, Copy)]
+pub enum BackMessage<'a> {
+    /// A protobuf BackMsg.
+    Proto(&'a BackMsg),
+    /// A JSON message from the web frontend.
+    Json(&'a serde_json::Value),
+}
+
+/// Whether a client message should be handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PluginFlow {
+    Continue,
+    /// Drop the message; later plugins and the server never see it.
+    Stop,
+}
+
+/// Session lifecycle event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionEvent {
+    /// A client connected and its session was created.
+    Started,
+    /// The client disconnected and its session was removed.
+    Ended,
+}
+
+/// Extension hooks into the server. Every hook has a no-op default.
+pub trait ServerPlugin: Send + Sync + 'static {
+    /// Get the plugin name, used in logs.
+    fn name(&self) -> &str;
+
+    /// Called once before the server binds its address.
+    ///
+    /// An error aborts startup.
+    fn on_startup(&self, _config: &ServerConfig) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called when a session starts or ends.
+    fn on_session(&self, _session_id: SessionId, _event: SessionEvent) {}
+
+    /// Called for each message received from a client, before it is handled.
+    fn on_backmsg(&self, _session_id: SessionId, _msg: BackMessage<'_>) -> PluginFlow {
+        PluginFlow::Continue
+    }
+
+    /// Called for each JSON message before it is sent to a client.
+    fn on_forwardmsg(&self, _session_id: SessionId, _msg: &mut serde_json::Value) {}
+
+    /// Get extra HTTP routes to serve alongside the app.
+    fn extra_routes(&self) -> Option<Router> {
+        None
+    }
+}
+
+/// Run `on_backmsg` hooks in order, stopping at the first `Stop`.
+pub fn backmsg_flow(plugins: &[Arc<dyn ServerPlugin>], session_id: SessionId, msg: BackMessage<'_>) -> PluginFlow {
+    for plugin in plugins {
+        if plugin.on_backmsg(session_id, msg) == PluginFlow::Stop {
+            tracing::debug!("Plugin {} dropped a message", plugin.name());
+            return PluginFlow::Stop;
+        }
+    }
+    PluginFlow::Continue
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct Counter(AtomicUsize);
+
+    impl ServerPlugin for Counter {
+        fn name(&self) -> &str {
+            "counter"
+        }
+
+        fn on_backmsg(&self, _session_id: SessionId, _msg: BackMessage<'_>) -> PluginFlow {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            PluginFlow::Continue
+        }
+    }
+
+    struct Block;
+
+    impl ServerPlugin for Block {
+        fn name(&self) -> &str {
+            "block"
+        }
+
+        fn on_backmsg(&self, _session_id: SessionId, _msg: BackMessage<'_>) -> PluginFlow {
+            PluginFlow::Stop
+        }
+    }
+
+    #[test]
+    fn test_backmsg_flow_stops_at_first_stop() {
+        let counter = Arc::new(Counter(AtomicUsize::new(0)));
+        let plugins: Vec<Arc<dyn ServerPlugin>> = vec![counter.clone(), Arc::new(Block), counter.clone()];
+        let msg = serde_json::json!({"type": "rerun"});
+
+        let flow = backmsg_flow(&plugins, SessionId::new(), BackMessage::Json(&msg));
+        assert_eq!(flow, PluginFlow::Stop);
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,133 @@
+//! Run-to-run reconciliation of keyed elements.
+//!
+//! Every rerun rebuilds the page, and a client rendering it from scratch
+//! would replace each widget, losing its focus, selection and scroll
+//! position. Before a run is sent, its widgets are matched to the client's
+//! previous run by key and type, wherever they moved to; unchanged ones are
+//! sent as `MoveElement` instead of `AddElement`, and the client reattaches
+//! the node it already rendered.
+
+use crate::message;
+use platypus_core::element::{ElementId, ElementType};
+use platypus_core::state::Delta;
+use std::collections::HashMap;
+
+/// A keyed element's identity across runs: its key and type.
+type Identity = (String, String);
+
+/// Tracks the keyed elements a client last received.
+#[derive(Debug, Default)]
+pub struct Reconciler {
+    previous: HashMap<Identity, (ElementId, serde_json::Value)>,
+}
+
+impl Reconciler {
+    /// Create a reconciler for a client showing no page.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the client's page with the full run `deltas`, e.g. after a
+    /// snapshot was sent.
+    pub fn reset(&mut self, deltas: &[Delta]) {
+        self.previous = keyed_elements(deltas);
+    }
+
+    /// Rewrite the run `deltas` to move unchanged keyed elements from the
+    /// client's previous run, and remember the run as the client's page.
+    pub fn reconcile(&mut self, deltas: Vec<Delta>) -> Vec<Delta> {
+        let current = keyed_elements(&deltas);
+        let moved: HashMap<ElementId, ElementId> = current
+            .iter()
+            .filter_map(|(identity, (id, json))| {
+                let (previous_id, previous_json) = self.previous.get(identity)?;
+                (previous_json == json).then_some((*id, *previous_id))
+            })
+            .collect();
+        self.previous = current;
+
+        deltas
+            .into_iter()
+            .map(|delta| match delta {
+                Delta::AddElement { id, parent_id, .. } if moved.contains_key(&id) => Delta::MoveElement {
+                    id,
+                    previous_id: moved[&id],
+                    parent_id,
+                },
+                delta => delta,
+            })
+            .collect()
+    }
+}
+
+/// Keyed elements added by a run, as they look once the run's updates are
+/// applied. A key used twice identifies only its first element.
+fn keyed_elements(deltas: &[Delta]) -> HashMap<Identity, (ElementId, serde_json::Value)> {
+    let mut elements: Vec<(ElementId, &ElementType)> = Vec::new();
+    let mut updates: HashMap<ElementId, &ElementType> = HashMap::new();
+    for delta in deltas {
+        match delta {
+            Delta::AddElement { id, element, .. } => elements.push((*id, element)),
+            Delta::UpdateElement { id, element } => {
+                updates.insert(*id, element);
+            }
+            _ => {}
+        }
+    }
+
+    let mut keyed = HashMap::new();
+    for (id, element) in elements {
+        let json = message::element_to_json(updates.get(&id).copied().unwrap_or(element));
+        let (Some(key), Some(element_type)) = (json["key"].as_str(), json["type"].as_str()) else {
+            continue;
+        };
+        keyed
+            .entry((key.to_string(), element_type.to_string()))
+            .or_insert((id, json));
+    }
+    keyed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add(id: u64, element: ElementType) -> Delta {
+        Delta::AddElement {
+            id: ElementId::new(id),
+            element,
+            parent_id: None,
+        }
+    }
+
+    fn input(key: &str, value: &str) -> ElementType {
+        ElementType::TextArea {
+            label: "Notes".to_string(),
+            value: value.to_string(),
+            key: Some(key.to_string()),
+            disabled: false,
+        }
+    }
+
+    #[test]
+    fn test_reconcile_moves_unchanged_keyed_elements() {
+        let mut reconciler = Reconciler::new();
+        let first = vec![add(1, input("a", "")), add(2, input("b", "")), add(3, ElementType::Divider)];
+        assert_eq!(reconciler.reconcile(first.clone()).len(), 3);
+
+        // "b" moved ahead of "a", whose value changed
+        let second = vec![add(1, input("b", "")), add(2, ElementType::Divider), add(3, input("a", "typed"))];
+        let sent = reconciler.reconcile(second);
+        assert!(matches!(
+            sent[0],
+            Delta::MoveElement { id, previous_id, parent_id: None } if id == ElementId::new(1) && previous_id == ElementId::new(2)
+        ));
+        assert!(matches!(sent[1], Delta::AddElement { .. }));
+        assert!(matches!(sent[2], Delta::AddElement { .. }));
+
+        // After a snapshot the client's page is the full run
+        reconciler.reset(&first);
+        let sent = reconciler.reconcile(vec![add(7, input("a", ""))]);
+        assert!(matches!(sent[0], Delta::MoveElement { previous_id, .. } if previous_id == ElementId::new(1)));
+    }
+}
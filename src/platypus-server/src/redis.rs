@@ -0,0 +1,279 @@
+//! Redis backends for cluster mode, enabled by the `redis` feature.
+//!
+//! [`RedisBackend`] holds the sessions and cached data of every node, and
+//! [`FanOut`] relays topic messages and heartbeats between the nodes
+//! through the Redis stream [`STREAM_KEY`]. Both are set up by
+//! `AppServer::with_redis`; see [`crate::cluster`].
+
+use platypus_runtime::topic::{Relay, TopicMessage};
+use platypus_runtime::{SessionStore, SharedBackend, TopicRegistry};
+use redis::streams::{StreamId, StreamMaxlen, StreamReadOptions, StreamReadReply};
+use redis::{AsyncCommands, Commands, RedisResult};
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+use crate::cluster::{self, ClusterPeers, PeerStatus, HEARTBEAT_INTERVAL};
+
+/// Stream the nodes of a cluster exchange messages on.
+pub const STREAM_KEY: &str = "platypus:fanout";
+
+/// Entries kept in the stream, approximately.
+const STREAM_MAXLEN: usize = 10_000;
+
+/// Messages queued for the stream before the oldest are dropped.
+const FAN_OUT_CAPACITY: usize = 1024;
+
+/// Longest a read waits for new entries.
+const READ_BLOCK: Duration = Duration::from_secs(5);
+
+/// Most entries taken per read.
+const READ_COUNT: usize = 100;
+
+/// Longest connecting to Redis may take.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Wait before reconnecting after a connection error.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Sessions and cached data stored in Redis.
+pub struct RedisBackend {
+    client: redis::Client,
+    // Connected on first use and again after a connection error
+    connection: Mutex<Option<redis::Connection>>,
+}
+
+impl RedisBackend {
+    /// Store in the Redis server of `client`.
+    pub fn new(client: redis::Client) -> Self {
+        RedisBackend {
+            client,
+            connection: Mutex::new(None),
+        }
+    }
+
+    fn with_connection<T>(&self, command: impl FnOnce(&mut redis::Connection) -> RedisResult<T>) -> Result<T, String> {
+        let mut connection = self.connection.lock().map_err(|e| e.to_string())?;
+        let result = match &mut *connection {
+            Some(connection) => command(connection),
+            None => {
+                let connected = self.client.get_connection_with_timeout(CONNECT_TIMEOUT).map_err(|e| e.to_string())?;
+                command(connection.insert(connected))
+            }
+        };
+        if result.as_ref().is_err_and(|e| e.is_connection_dropped() || e.is_io_error()) {
+            *connection = None;
+        }
+        result.map_err(|e| e.to_string())
+    }
+}
+
+impl SharedBackend for RedisBackend {
+    fn name(&self) -> &str {
+        "redis"
+    }
+
+    fn get(&self, key: &str) -> Result<Option<String>, String> {
+        self.with_connection(|connection| connection.get(key))
+    }
+
+    fn set(&self, key: &str, value: &str, ttl: Option<Duration>) -> Result<(), String> {
+        self.with_connection(|connection| match ttl {
+            Some(ttl) => connection.set_ex(key, value, ttl.as_secs().max(1)),
+            None => connection.set(key, value),
+        })
+    }
+
+    fn delete(&self, key: &str) -> Result<(), String> {
+        self.with_connection(|connection| connection.del(key))
+    }
+}
+
+/// An entry of the fan-out stream.
+#[derive(Debug, Clone, PartialEq)]
+enum Entry {
+    Heartbeat { sessions: usize },
+    Message { topic: String, payload: Value },
+}
+
+impl Entry {
+    fn fields(&self, node_id: &str) -> Vec<(&'static str, String)> {
+        let mut fields = vec![("node", node_id.to_string())];
+        match self {
+            Entry::Heartbeat { sessions } => fields.push(("sessions", sessions.to_string())),
+            Entry::Message { topic, payload } => {
+                fields.push(("topic", topic.clone()));
+                fields.push(("payload", payload.to_string()));
+            }
+        }
+        fields
+    }
+
+    /// Parse an entry of the stream, with the node that added it.
+    fn parse(entry: &StreamId) -> Option<(String, Entry)> {
+        let node = entry.get("node")?;
+        let parsed = match entry.get::<String>("topic") {
+            Some(topic) => Entry::Message {
+                topic,
+                payload: serde_json::from_str(&entry.get::<String>("payload")?).ok()?,
+            },
+            None => Entry::Heartbeat {
+                sessions: entry.get("sessions")?,
+            },
+        };
+        Some((node, parsed))
+    }
+}
+
+/// Relays the topic messages published on this node to the other nodes,
+/// and theirs to this node.
+pub struct FanOut {
+    client: redis::Client,
+    node_id: String,
+    outbound: broadcast::Sender<TopicMessage>,
+}
+
+impl FanOut {
+    /// Relay through the Redis server of `client` as `node_id`.
+    pub fn new(client: redis::Client, node_id: String) -> Self {
+        FanOut {
+            client,
+            node_id,
+            outbound: broadcast::channel(FAN_OUT_CAPACITY).0,
+        }
+    }
+
+    /// Get the relay to give the node's topic registry.
+    pub fn relay(&self) -> Relay {
+        let outbound = self.outbound.clone();
+        // Nobody subscribed is fine: the server is not running yet
+        Arc::new(move |message| {
+            let _ = outbound.send(message.clone());
+        })
+    }
+
+    /// Relay messages and send heartbeats until the tasks are aborted.
+    pub fn spawn(&self, topics: TopicRegistry, peers: ClusterPeers, session_store: Arc<SessionStore>) -> [JoinHandle<()>; 2] {
+        [self.spawn_writer(session_store), self.spawn_reader(topics, peers)]
+    }
+
+    fn spawn_writer(&self, session_store: Arc<SessionStore>) -> JoinHandle<()> {
+        let client = self.client.clone();
+        let node_id = self.node_id.clone();
+        let mut outbound = self.outbound.subscribe();
+        tokio::spawn(async move {
+            let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+            let mut connection = None;
+            loop {
+                let entry = tokio::select! {
+                    _ = heartbeat.tick() => Entry::Heartbeat { sessions: session_store.session_count() },
+                    message = outbound.recv() => match message {
+                        Ok(message) => Entry::Message { topic: message.topic, payload: message.payload },
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            tracing::warn!("Dropped {} topic messages for other nodes", skipped);
+                            continue;
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                };
+                if connection.is_none() {
+                    connection = client
+                        .get_multiplexed_tokio_connection()
+                        .await
+                        .inspect_err(|e| tracing::warn!("Failed to connect to Redis: {}", e))
+                        .ok();
+                }
+                let Some(redis) = connection.as_mut() else {
+                    continue;
+                };
+                let added: RedisResult<String> = redis
+                    .xadd_maxlen(STREAM_KEY, StreamMaxlen::Approx(STREAM_MAXLEN), "*", &entry.fields(&node_id))
+                    .await;
+                if let Err(e) = added {
+                    tracing::warn!("Failed to relay to other nodes: {}", e);
+                    connection = None;
+                }
+            }
+        })
+    }
+
+    fn spawn_reader(&self, topics: TopicRegistry, peers: ClusterPeers) -> JoinHandle<()> {
+        let client = self.client.clone();
+        let node_id = self.node_id.clone();
+        tokio::spawn(async move {
+            let options = StreamReadOptions::default()
+                .block(READ_BLOCK.as_millis() as usize)
+                .count(READ_COUNT);
+            // Only entries added from now on
+            let mut last_id = "$".to_string();
+            let mut connection = None;
+            loop {
+                if connection.is_none() {
+                    match client.get_multiplexed_tokio_connection().await {
+                        Ok(connected) => connection = Some(connected),
+                        Err(e) => {
+                            tracing::warn!("Failed to connect to Redis: {}", e);
+                            tokio::time::sleep(RECONNECT_DELAY).await;
+                            continue;
+                        }
+                    }
+                }
+                let Some(redis) = connection.as_mut() else {
+                    continue;
+                };
+                let read: RedisResult<Option<StreamReadReply>> =
+                    redis.xread_options(&[STREAM_KEY], &[&last_id], &options).await;
+                let reply = match read {
+                    Ok(reply) => reply.unwrap_or_default(),
+                    Err(e) => {
+                        tracing::warn!("Failed to read from other nodes: {}", e);
+                        connection = None;
+                        tokio::time::sleep(RECONNECT_DELAY).await;
+                        continue;
+                    }
+                };
+                for entry in reply.keys.into_iter().flat_map(|key| key.ids) {
+                    last_id.clone_from(&entry.id);
+                    match Entry::parse(&entry) {
+                        Some((node, _)) if node == node_id => {}
+                        Some((node, Entry::Heartbeat { sessions })) => peers.record(PeerStatus {
+                            node_id: node,
+                            sessions,
+                            last_seen: cluster::unix_now(),
+                        }),
+                        Some((_, Entry::Message { topic, payload })) => topics.receive(&topic, payload),
+                        None => tracing::warn!("Invalid fan-out entry {}", entry.id),
+                    }
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_entries_round_trip() {
+        let entries = [
+            Entry::Heartbeat { sessions: 12 },
+            Entry::Message {
+                topic: "sensors/kitchen".to_string(),
+                payload: json!({"celsius": 21.5}),
+            },
+        ];
+        for entry in entries {
+            let map = entry
+                .fields("node-a")
+                .into_iter()
+                .map(|(field, value)| (field.to_string(), redis::Value::Data(value.into_bytes())))
+                .collect();
+            let stream_id = StreamId { id: "1-0".to_string(), map };
+            assert_eq!(Entry::parse(&stream_id), Some(("node-a".to_string(), entry)));
+        }
+    }
+}
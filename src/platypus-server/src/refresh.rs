@@ -0,0 +1,103 @@
+//! Scheduled refresh of cached datasets.
+//!
+//! A refresh recomputes a dataset of the server's data cache in the
+//! background, once at startup to warm the cache and then on an interval:
+//!
+//! ```no_run
+//! use platypus_server::AppServer;
+//! use std::time::Duration;
+//!
+//! let server = AppServer::new().with_refresh("sales_data", Duration::from_secs(300), || {
+//!     Ok(r#"[{"region": "north", "total": 10}]"#.to_string())
+//! });
+//! ```
+//!
+//! Apps read the dataset with `st.cached_data("sales_data")`, and sessions
+//! that read it rerun when fresh data lands. A failed load keeps the
+//! previous value.
+
+use platypus_runtime::{cache, DataCache, TopicRegistry};
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// Computes a dataset, as stored in the data cache.
+pub type Loader = Arc<dyn Fn() -> Result<String, String> + Send + Sync>;
+
+/// A dataset recomputed on an interval.
+#[derive(Clone)]
+pub struct Refresh {
+    key: String,
+    every: Duration,
+    loader: Loader,
+}
+
+impl Refresh {
+    /// Recompute the dataset `key` with `loader` every `every`.
+    pub fn new(key: impl Into<String>, every: Duration, loader: impl Fn() -> Result<String, String> + Send + Sync + 'static) -> Self {
+        Refresh {
+            key: key.into(),
+            every,
+            loader: Arc::new(loader),
+        }
+    }
+
+    /// Get the dataset's cache key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Load the dataset into `cache`, notifying the sessions that read it.
+    /// Returns whether the load succeeded.
+    pub fn run(&self, cache: &DataCache, topics: &TopicRegistry) -> bool {
+        match (self.loader)() {
+            Ok(data) => {
+                cache.set(self.key.clone(), data, None);
+                topics.publish(&cache::refresh_topic(&self.key), Value::Null);
+                true
+            }
+            Err(e) => {
+                tracing::warn!("Failed to refresh {}: {}", self.key, e);
+                false
+            }
+        }
+    }
+
+    /// Load the dataset now and then every interval, until the task is
+    /// aborted.
+    pub fn spawn(&self, cache: DataCache, topics: TopicRegistry) -> JoinHandle<()> {
+        let refresh = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(refresh.every.max(Duration::from_secs(1)));
+            loop {
+                interval.tick().await;
+                let (refresh, cache, topics) = (refresh.clone(), cache.clone(), topics.clone());
+                // Loaders may block on queries or files
+                if let Err(e) = tokio::task::spawn_blocking(move || refresh.run(&cache, &topics)).await {
+                    tracing::warn!("Refresh task failed: {}", e);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_stores_and_notifies() {
+        let cache = DataCache::new();
+        let topics = TopicRegistry::new();
+        let mut changes = topics.subscribe();
+        let refresh = Refresh::new("sales_data", Duration::from_secs(60), || Ok("[1, 2]".to_string()));
+        assert!(refresh.run(&cache, &topics));
+        assert_eq!(cache.get("sales_data").as_deref(), Some("[1, 2]"));
+        assert_eq!(changes.try_recv().unwrap(), cache::refresh_topic("sales_data"));
+
+        let failing = Refresh::new("sales_data", Duration::from_secs(60), || Err("offline".to_string()));
+        assert!(!failing.run(&cache, &topics));
+        assert_eq!(cache.get("sales_data").as_deref(), Some("[1, 2]"));
+    }
+}
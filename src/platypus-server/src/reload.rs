@@ -0,0 +1,143 @@
+//! Replacing the app of a running server without dropping connections.
+//!
+//! Every session runs the app in the server's [`AppSlot`]. Swapping it,
+//! with `AppServer::swap_app` or through the admin endpoint, reruns each
+//! connected session with the new app; widget values and session state
+//! are kept, and WebSockets stay open. For blue-green updates, register
+//! both versions and switch between them:
+//!
+//! ```no_run
+//! use platypus_runtime::St;
+//! use platypus_server::AppServer;
+//!
+//! fn blue(st: &mut St) -> Result<(), String> {
+//!     st.title("Dashboard");
+//!     Ok(())
+//! }
+//!
+//! fn green(st: &mut St) -> Result<(), String> {
+//!     st.title("Dashboard v2");
+//!     Ok(())
+//! }
+//!
+//! let server = AppServer::with_app(blue)
+//!     .with_app_version("blue", blue)
+//!     .with_app_version("green", green);
+//! // Later, from a task holding the server, or
+//! // `POST /admin/app/swap {"version": "green"}`
+//! server.swap_app(green);
+//! ```
+
+use dashmap::DashMap;
+use std::sync::Arc;
+use tokio::sync::watch;
+
+use crate::executor::AppFn;
+
+/// The app a server runs, and the versions it can swap to.
+#[derive(Clone)]
+pub struct AppSlot {
+    current: Arc<watch::Sender<Option<AppFn>>>,
+    versions: Arc<DashMap<String, AppFn>>,
+}
+
+impl AppSlot {
+    /// Create a slot running `app_fn`, or the demo app if None.
+    pub fn new(app_fn: Option<AppFn>) -> Self {
+        AppSlot {
+            current: Arc::new(watch::channel(app_fn).0),
+            versions: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// Get the app to run.
+    pub fn current(&self) -> Option<AppFn> {
+        *self.current.borrow()
+    }
+
+    /// Run `app_fn` from now on, rerunning the connected sessions.
+    pub fn swap(&self, app_fn: AppFn) {
+        self.current.send_replace(Some(app_fn));
+    }
+
+    /// Register `app_fn` as a version the admin endpoint can swap to.
+    pub fn register(&self, version: impl Into<String>, app_fn: AppFn) {
+        self.versions.insert(version.into(), app_fn);
+    }
+
+    /// Swap to a registered version. Returns false if there is none by
+    /// that name.
+    pub fn swap_to(&self, version: &str) -> bool {
+        let Some(app_fn) = self.versions.get(version).map(|app_fn| *app_fn) else {
+            return false;
+        };
+        self.swap(app_fn);
+        true
+    }
+
+    /// Get the names of the registered versions, sorted.
+    pub fn versions(&self) -> Vec<String> {
+        let mut versions: Vec<_> = self.versions.iter().map(|version| version.key().clone()).collect();
+        versions.sort();
+        versions
+    }
+
+    /// Get notified of swaps.
+    pub fn subscribe(&self) -> watch::Receiver<Option<AppFn>> {
+        self.current.subscribe()
+    }
+}
+
+impl Default for AppSlot {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::ScriptExecutor;
+    use platypus_core::element::ElementType;
+    use platypus_core::state::Delta;
+    use platypus_runtime::{Event, SessionStore, St};
+
+    fn blue(st: &mut St) -> Result<(), String> {
+        let name = st.text_input("Name", "Ada", Some("name".to_string()));
+        st.write(format!("blue {}", name));
+        Ok(())
+    }
+
+    fn green(st: &mut St) -> Result<(), String> {
+        let name = st.text_input("Name", "Ada", Some("name".to_string()));
+        st.write(format!("green {}", name));
+        Ok(())
+    }
+
+    fn text(deltas: &[Delta]) -> Option<&str> {
+        deltas.iter().find_map(|delta| match delta {
+            Delta::AddElement { element: ElementType::Text { value }, .. } => Some(value.as_str()),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn test_swap_keeps_widget_state() {
+        let slot = AppSlot::new(Some(blue));
+        slot.register("green", green);
+        let swaps = slot.subscribe();
+        let session_store = Arc::new(SessionStore::new());
+        let executor = ScriptExecutor::new(session_store.clone()).with_app_slot(slot.clone());
+        let session_id = session_store.create_session("test".to_string());
+        let event = Event::WidgetChanged { key: "name".to_string(), value: serde_json::json!("Grace") };
+        let deltas = executor.handle_event(session_id, event).unwrap().unwrap();
+        assert_eq!(text(&deltas), Some("blue Grace"));
+
+        assert!(!slot.swap_to("purple"));
+        assert!(!swaps.has_changed().unwrap());
+        assert!(slot.swap_to("green"));
+        assert!(swaps.has_changed().unwrap());
+        let deltas = executor.execute_script(session_id).unwrap();
+        assert_eq!(text(&deltas), Some("green Grace"));
+    }
+}
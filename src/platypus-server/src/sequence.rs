@@ -0,0 +1,86 @@
+//! Ordering of the messages sent to one client.
+//!
+//! Every message gets a server seq one above the previous, and the client
+//! acknowledges the highest one it received without a gap. A client that
+//! sees a gap asks for a resync and is sent a snapshot of the session, so a
+//! dropped or reordered message never leaves it showing a stale page.
+
+use platypus_proto::ForwardMsg;
+
+/// JSON field holding a message's server seq.
+pub const SEQ_FIELD: &str = "server_seq";
+
+/// Numbers outgoing messages and tracks the client's acknowledgements.
+#[derive(Debug, Default)]
+pub struct Sequencer {
+    sent: u64,
+    acked: u64,
+}
+
+impl Sequencer {
+    /// Create a sequencer for a new connection.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number the JSON message `msg` as the next one sent.
+    pub fn stamp(&mut self, msg: &mut serde_json::Value) -> u64 {
+        self.sent += 1;
+        if let Some(fields) = msg.as_object_mut() {
+            fields.insert(SEQ_FIELD.to_string(), self.sent.into());
+        }
+        self.sent
+    }
+
+    /// Number the proto message `msg` as the next one sent.
+    pub fn stamp_proto(&mut self, msg: &mut ForwardMsg) -> u64 {
+        self.sent += 1;
+        msg.seq = self.sent;
+        self.sent
+    }
+
+    /// Record that the client received every message up to `seq`. Returns
+    /// false for stale acknowledgements and ones of unsent messages.
+    pub fn ack(&mut self, seq: u64) -> bool {
+        if seq <= self.acked || seq > self.sent {
+            return false;
+        }
+        self.acked = seq;
+        true
+    }
+
+    /// Get the seq of the last message sent.
+    pub fn sent(&self) -> u64 {
+        self.sent
+    }
+
+    /// Get the highest seq the client acknowledged.
+    pub fn acked(&self) -> u64 {
+        self.acked
+    }
+
+    /// Get the number of messages sent but not acknowledged yet.
+    pub fn unacked(&self) -> u64 {
+        self.sent - self.acked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stamp_and_ack() {
+        let mut sequencer = Sequencer::new();
+        let mut first = serde_json::json!({"type": "delta"});
+        let mut second = serde_json::json!({"type": "log_lines"});
+        assert_eq!(sequencer.stamp(&mut first), 1);
+        assert_eq!(sequencer.stamp(&mut second), 2);
+        assert_eq!(second[SEQ_FIELD], 2);
+
+        assert!(sequencer.ack(1));
+        assert!(!sequencer.ack(1));
+        assert!(!sequencer.ack(5));
+        assert_eq!((sequencer.acked(), sequencer.unacked()), (1, 1));
+    }
+}
@@ -1,22 +1,38 @@
 //! Main application server.
 
+use crate::cluster::{self, ClusterConfig, ClusterPeers};
+use crate::compression::{CompressionConfig, CompressionMetrics};
 use crate::config;
+use crate::custom_element::CustomElementRegistry;
+use crate::embed::EmbedConfig;
 use crate::error::Result;
 use crate::executor::AppFn;
+use crate::firewall::{self, FirewallConfig};
+use crate::reload::AppSlot;
 use crate::handler;
-use crate::ws;
+use crate::limits::{self, RequestLimits};
+use crate::listener::{ListenAddr, Listener};
+use crate::media_gc::MediaRetention;
+use crate::plugin::ServerPlugin;
+use crate::refresh::Refresh;
+use crate::ws::{self, SocketContext};
 use axum::{
     extract::DefaultBodyLimit,
-    routing::get,
+    routing::{get, post, put},
     Router,
 };
 use serde::{Deserialize, Serialize};
-use std::net::SocketAddr;
+use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tower_http::cors::CorsLayer;
 use tower_http::trace::TraceLayer;
-use platypus_runtime::SessionStore;
+use platypus_core::clock::{self, Clock};
+use platypus_core::ids::{self, IdGenerator};
+use platypus_runtime::{
+    DataCache, MediaStore, PresenceRegistry, RoomRegistry, SessionStore, ShareStore, SharedBackend, Speaker, SpeechProvider, StateSchema, TempDirs, TopicRegistry,
+    Transcriber, TranscriptionProvider, TransferStore,
+};
 
 /// Server configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,10 +43,51 @@ pub struct ServerConfig {
     pub host: String,
     /// Port to listen on.
     pub port: u16,
+    /// Addresses to listen on instead of `host:port`: TCP addresses, and
+    /// Unix domain sockets as `unix:/path`.
+    #[serde(default)]
+    pub listen: Vec<ListenAddr>,
     /// Maximum body size (bytes).
     pub max_body_size: u64,
-    /// Session timeout (seconds).
+    /// Session timeout (seconds): idle sessions expire after it. Never
+    /// if 0.
     pub session_timeout: u64,
+    /// Warning before an idle session expires (seconds), so the user can
+    /// keep it alive.
+    #[serde(default = "config::default_idle_warning")]
+    pub idle_warning: u64,
+    /// Size limits of uploads and WebSocket messages.
+    #[serde(default)]
+    pub limits: RequestLimits,
+    /// WebSocket message compression.
+    #[serde(default)]
+    pub compression: CompressionConfig,
+    /// Media garbage collection.
+    #[serde(default)]
+    pub media_retention: MediaRetention,
+    /// Bearer token for the admin endpoints; they are disabled without one.
+    #[serde(default)]
+    pub admin_token: Option<String>,
+    /// Dev mode: warn about elements that differ between identical reruns.
+    #[serde(default)]
+    pub check_determinism: bool,
+    /// Fail runs in which an element failed to render, instead of showing
+    /// an inline error in its place, e.g. in CI.
+    #[serde(default)]
+    pub fatal_element_errors: bool,
+    /// Longest a run may take (seconds); runs stop at their first
+    /// checkpoint after it. Unlimited if unset.
+    #[serde(default)]
+    pub run_timeout: Option<u64>,
+    /// Sites allowed to embed the app's fragments.
+    #[serde(default)]
+    pub embed: EmbedConfig,
+    /// This node's identity when several serve the app.
+    #[serde(default)]
+    pub cluster: ClusterConfig,
+    /// Clients and requests the server refuses.
+    #[serde(default)]
+    pub firewall: FirewallConfig,
 }
 
 impl Default for ServerConfig {
@@ -39,9 +96,34 @@ impl Default for ServerConfig {
             app_name: config::DEFAULT_APP_NAME.to_string(),
             host: config::DEFAULT_HOST.to_string(),
             port: config::DEFAULT_PORT,
+            listen: Vec::new(),
             max_body_size: config::DEFAULT_MAX_BODY_SIZE,
             session_timeout: config::DEFAULT_SESSION_TIMEOUT,
+            idle_warning: config::DEFAULT_IDLE_WARNING,
+            limits: RequestLimits::default(),
+            compression: CompressionConfig::default(),
+            media_retention: MediaRetention::default(),
+            admin_token: None,
+            check_determinism: false,
+            fatal_element_errors: false,
+            run_timeout: None,
+            embed: EmbedConfig::default(),
+            cluster: ClusterConfig::default(),
+            firewall: FirewallConfig::default(),
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Get the addresses to listen on: `listen`, or else `host:port`.
+    pub fn listen_addrs(&self) -> Result<Vec<ListenAddr>> {
+        if !self.listen.is_empty() {
+            return Ok(self.listen.clone());
         }
+        let addr = format!("{}:{}", self.host, self.port)
+            .parse()
+            .map_err(|e| crate::error::Error::config(format!("Invalid address: {}", e)))?;
+        Ok(vec![ListenAddr::Tcp(addr)])
     }
 }
 
@@ -51,17 +133,56 @@ pub struct ServerState {
     pub config: ServerConfig,
     /// Session store.
     pub session_store: Arc<SessionStore>,
+    /// Media store for downloads.
+    pub media_store: MediaStore,
+    /// Chunked uploads in progress.
+    pub transfers: TransferStore,
+    /// Shared read-only snapshots.
+    pub shares: ShareStore,
+    /// Backend shared with the other nodes of a cluster.
+    pub shared_backend: Option<Arc<dyn SharedBackend>>,
+    /// Other nodes of the cluster.
+    pub peers: ClusterPeers,
     /// Server start time.
     pub start_time: Instant,
-    /// App function.
-    pub app_fn: Option<AppFn>,
+    /// App the sessions run, swappable while serving.
+    pub app: AppSlot,
 }
 
 /// Main application server.
 pub struct AppServer {
     config: ServerConfig,
     session_store: Arc<SessionStore>,
-    app_fn: Option<AppFn>,
+    media_store: MediaStore,
+    transfers: TransferStore,
+    rooms: RoomRegistry,
+    topics: TopicRegistry,
+    presence: PresenceRegistry,
+    transcriber: Transcriber,
+    speaker: Speaker,
+    temp_dirs: TempDirs,
+    data_cache: DataCache,
+    shares: ShareStore,
+    custom_elements: CustomElementRegistry,
+    plugins: Vec<Arc<dyn ServerPlugin>>,
+    widget_debounce: HashMap<String, Duration>,
+    compression_metrics: CompressionMetrics,
+    refreshes: Vec<Refresh>,
+    shared_backend: Option<Arc<dyn SharedBackend>>,
+    peers: ClusterPeers,
+    #[cfg(feature = "redis")]
+    fan_out: Option<crate::redis::FanOut>,
+    #[cfg(feature = "embeddings")]
+    semantic_index: Option<platypus_runtime::SemanticIndex>,
+    #[cfg(feature = "streaming")]
+    stream_sources: Vec<Arc<dyn crate::streaming::StreamSource>>,
+    #[cfg(feature = "streaming")]
+    stream_options: crate::streaming::StreamOptions,
+    #[cfg(feature = "mqtt")]
+    mqtt_sources: Vec<crate::mqtt::MqttSource>,
+    app: AppSlot,
+    clock: Arc<dyn Clock>,
+    ids: Arc<dyn IdGenerator>,
 }
 
 impl AppServer {
@@ -70,7 +191,36 @@ impl AppServer {
         AppServer {
             config: ServerConfig::default(),
             session_store: Arc::new(SessionStore::new()),
-            app_fn: None,
+            media_store: MediaStore::new(),
+            transfers: TransferStore::new(),
+            rooms: RoomRegistry::new(),
+            topics: TopicRegistry::new(),
+            presence: PresenceRegistry::new(),
+            transcriber: Transcriber::new(),
+            speaker: Speaker::new(),
+            temp_dirs: TempDirs::new(),
+            data_cache: DataCache::new(),
+            shares: ShareStore::new(),
+            custom_elements: CustomElementRegistry::new(),
+            plugins: Vec::new(),
+            widget_debounce: HashMap::new(),
+            compression_metrics: CompressionMetrics::new(),
+            refreshes: Vec::new(),
+            shared_backend: None,
+            peers: ClusterPeers::new(),
+            #[cfg(feature = "redis")]
+            fan_out: None,
+            #[cfg(feature = "embeddings")]
+            semantic_index: None,
+            #[cfg(feature = "streaming")]
+            stream_sources: Vec::new(),
+            #[cfg(feature = "streaming")]
+            stream_options: Default::default(),
+            #[cfg(feature = "mqtt")]
+            mqtt_sources: Vec::new(),
+            app: AppSlot::default(),
+            clock: clock::system_clock(),
+            ids: ids::random_ids(),
         }
     }
 
@@ -79,7 +229,36 @@ impl AppServer {
         AppServer {
             config,
             session_store: Arc::new(SessionStore::new()),
-            app_fn: None,
+            media_store: MediaStore::new(),
+            transfers: TransferStore::new(),
+            rooms: RoomRegistry::new(),
+            topics: TopicRegistry::new(),
+            presence: PresenceRegistry::new(),
+            transcriber: Transcriber::new(),
+            speaker: Speaker::new(),
+            temp_dirs: TempDirs::new(),
+            data_cache: DataCache::new(),
+            shares: ShareStore::new(),
+            custom_elements: CustomElementRegistry::new(),
+            plugins: Vec::new(),
+            widget_debounce: HashMap::new(),
+            compression_metrics: CompressionMetrics::new(),
+            refreshes: Vec::new(),
+            shared_backend: None,
+            peers: ClusterPeers::new(),
+            #[cfg(feature = "redis")]
+            fan_out: None,
+            #[cfg(feature = "embeddings")]
+            semantic_index: None,
+            #[cfg(feature = "streaming")]
+            stream_sources: Vec::new(),
+            #[cfg(feature = "streaming")]
+            stream_options: Default::default(),
+            #[cfg(feature = "mqtt")]
+            mqtt_sources: Vec::new(),
+            app: AppSlot::default(),
+            clock: clock::system_clock(),
+            ids: ids::random_ids(),
         }
     }
 
@@ -88,7 +267,36 @@ impl AppServer {
         AppServer {
             config: ServerConfig::default(),
             session_store: Arc::new(SessionStore::new()),
-            app_fn: Some(app_fn),
+            media_store: MediaStore::new(),
+            transfers: TransferStore::new(),
+            rooms: RoomRegistry::new(),
+            topics: TopicRegistry::new(),
+            presence: PresenceRegistry::new(),
+            transcriber: Transcriber::new(),
+            speaker: Speaker::new(),
+            temp_dirs: TempDirs::new(),
+            data_cache: DataCache::new(),
+            shares: ShareStore::new(),
+            custom_elements: CustomElementRegistry::new(),
+            plugins: Vec::new(),
+            widget_debounce: HashMap::new(),
+            compression_metrics: CompressionMetrics::new(),
+            refreshes: Vec::new(),
+            shared_backend: None,
+            peers: ClusterPeers::new(),
+            #[cfg(feature = "redis")]
+            fan_out: None,
+            #[cfg(feature = "embeddings")]
+            semantic_index: None,
+            #[cfg(feature = "streaming")]
+            stream_sources: Vec::new(),
+            #[cfg(feature = "streaming")]
+            stream_options: Default::default(),
+            #[cfg(feature = "mqtt")]
+            mqtt_sources: Vec::new(),
+            app: AppSlot::new(Some(app_fn)),
+            clock: clock::system_clock(),
+            ids: ids::random_ids(),
         }
     }
 
@@ -97,7 +305,36 @@ impl AppServer {
         AppServer {
             config,
             session_store: Arc::new(SessionStore::new()),
-            app_fn: Some(app_fn),
+            media_store: MediaStore::new(),
+            transfers: TransferStore::new(),
+            rooms: RoomRegistry::new(),
+            topics: TopicRegistry::new(),
+            presence: PresenceRegistry::new(),
+            transcriber: Transcriber::new(),
+            speaker: Speaker::new(),
+            temp_dirs: TempDirs::new(),
+            data_cache: DataCache::new(),
+            shares: ShareStore::new(),
+            custom_elements: CustomElementRegistry::new(),
+            plugins: Vec::new(),
+            widget_debounce: HashMap::new(),
+            compression_metrics: CompressionMetrics::new(),
+            refreshes: Vec::new(),
+            shared_backend: None,
+            peers: ClusterPeers::new(),
+            #[cfg(feature = "redis")]
+            fan_out: None,
+            #[cfg(feature = "embeddings")]
+            semantic_index: None,
+            #[cfg(feature = "streaming")]
+            stream_sources: Vec::new(),
+            #[cfg(feature = "streaming")]
+            stream_options: Default::default(),
+            #[cfg(feature = "mqtt")]
+            mqtt_sources: Vec::new(),
+            app: AppSlot::new(Some(app_fn)),
+            clock: clock::system_clock(),
+            ids: ids::random_ids(),
         }
     }
 
@@ -106,24 +343,265 @@ impl AppServer {
         &self.config
     }
 
+    /// Get the slot holding the app the sessions run.
+    pub fn app_slot(&self) -> &AppSlot {
+        &self.app
+    }
+
+    /// Replace the app while serving: connected sessions rerun with
+    /// `app_fn`, keeping their widget state and WebSockets.
+    pub fn swap_app(&self, app_fn: AppFn) {
+        self.app.swap(app_fn);
+        tracing::info!("Swapped app");
+    }
+
+    /// Register a version of the app the admin endpoint can swap to.
+    pub fn with_app_version(self, version: impl Into<String>, app_fn: AppFn) -> Self {
+        self.app.register(version, app_fn);
+        self
+    }
+
     /// Get the session store.
     pub fn session_store(&self) -> &Arc<SessionStore> {
         &self.session_store
     }
 
+    /// Get the media store.
+    pub fn media_store(&self) -> &MediaStore {
+        &self.media_store
+    }
+
+    /// Get the chunked uploads in progress.
+    pub fn transfers(&self) -> &TransferStore {
+        &self.transfers
+    }
+
+    /// Get the shared state rooms.
+    pub fn rooms(&self) -> &RoomRegistry {
+        &self.rooms
+    }
+
+    /// Get the topics apps subscribe to, e.g. to publish to them.
+    pub fn topics(&self) -> &TopicRegistry {
+        &self.topics
+    }
+
+    /// Get the connected sessions.
+    pub fn presence(&self) -> &PresenceRegistry {
+        &self.presence
+    }
+
+    /// Get the transcription jobs.
+    pub fn transcriber(&self) -> &Transcriber {
+        &self.transcriber
+    }
+
+    /// Transcribe `st.audio_input` recordings with `provider`.
+    pub fn with_transcription_provider(mut self, provider: impl TranscriptionProvider + 'static) -> Self {
+        self.transcriber = self.transcriber.with_provider(provider);
+        self
+    }
+
+    /// Synthesize `st.speak` audio with `provider`.
+    pub fn with_speech_provider(mut self, provider: impl SpeechProvider + 'static) -> Self {
+        self.speaker = self.speaker.with_provider(provider);
+        self
+    }
+
+    /// Get the sessions' scratch directories.
+    pub fn temp_dirs(&self) -> &TempDirs {
+        &self.temp_dirs
+    }
+
+    /// Keep `st.tempdir()` directories in `temp_dirs`, e.g. under another
+    /// root or with another quota.
+    pub fn with_temp_dirs(mut self, temp_dirs: TempDirs) -> Self {
+        self.temp_dirs = temp_dirs;
+        self
+    }
+
+    /// Get the data cache shared by every session, e.g. of `st.sql` results.
+    pub fn data_cache(&self) -> &DataCache {
+        &self.data_cache
+    }
+
+    /// Get the snapshots shared with `st.share_snapshot`, e.g. to revoke them.
+    pub fn shares(&self) -> &ShareStore {
+        &self.shares
+    }
+
+    /// Recompute the cached dataset `key` with `loader` at startup and then
+    /// `every` interval, rerunning the sessions that read it with
+    /// `st.cached_data`.
+    pub fn with_refresh(
+        mut self,
+        key: impl Into<String>,
+        every: Duration,
+        loader: impl Fn() -> std::result::Result<String, String> + Send + Sync + 'static,
+    ) -> Self {
+        self.refreshes.push(Refresh::new(key, every, loader));
+        self
+    }
+
+    /// Get the custom element validation hooks.
+    pub fn custom_elements(&self) -> &CustomElementRegistry {
+        &self.custom_elements
+    }
+
+    /// Register a validation hook for a custom element.
+    pub fn register_custom_element<F>(&self, name: impl Into<String>, validator: F)
+    where
+        F: Fn(&serde_json::Value) -> std::result::Result<(), String> + Send + Sync + 'static,
+    {
+        self.custom_elements.register(name, validator);
+    }
+
+    /// Register a plugin. Hooks run in registration order.
+    pub fn with_plugin(mut self, plugin: impl ServerPlugin) -> Self {
+        self.plugins.push(Arc::new(plugin));
+        self
+    }
+
+    /// Get the registered plugins.
+    pub fn plugins(&self) -> &[Arc<dyn ServerPlugin>] {
+        &self.plugins
+    }
+
+    /// Get the per-session compression stats.
+    pub fn compression_metrics(&self) -> &CompressionMetrics {
+        &self.compression_metrics
+    }
+
+    /// Debounce reruns for a widget: its changes are stored as they arrive,
+    /// but the app reruns only once the widget has been quiet for `delay`.
+    pub fn with_widget_debounce(mut self, key: impl Into<String>, delay: Duration) -> Self {
+        self.widget_debounce.insert(key.into(), delay);
+        self
+    }
+
+    /// Share a semantic index with every session for `st.semantic_search`.
+    #[cfg(feature = "embeddings")]
+    pub fn with_semantic_index(mut self, index: platypus_runtime::SemanticIndex) -> Self {
+        self.semantic_index = Some(index);
+        self
+    }
+
+    /// Version the app's widget and session state with `schema`, so
+    /// sessions saved by older versions of the app are migrated when they
+    /// are imported or taken over.
+    pub fn with_state_schema(mut self, schema: StateSchema) -> Self {
+        self.session_store = Arc::new(self.session_store.as_ref().clone().with_schema(schema));
+        self
+    }
+
+    /// Read the time from `clock` instead of the system, in sessions and
+    /// apps, e.g. to replay a recorded session exactly.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.session_store = Arc::new(self.session_store.as_ref().clone().with_clock(Arc::clone(&clock)));
+        self.clock = clock;
+        self
+    }
+
+    /// Generate session and app IDs with `ids` instead of randomly.
+    pub fn with_ids(mut self, ids: Arc<dyn IdGenerator>) -> Self {
+        self.session_store = Arc::new(self.session_store.as_ref().clone().with_ids(Arc::clone(&ids)));
+        self.ids = ids;
+        self
+    }
+
+    /// Write sessions and cached data through to `backend`, shared with the
+    /// other nodes of a cluster.
+    pub fn with_shared_backend(mut self, backend: Arc<dyn SharedBackend>) -> Self {
+        self.session_store = Arc::new(self.session_store.as_ref().clone().with_shared(Arc::clone(&backend)));
+        self.data_cache = DataCache::new().with_shared(Arc::clone(&backend));
+        self.shared_backend = Some(backend);
+        self
+    }
+
+    /// Join the cluster of nodes sharing the Redis server at `url`: share
+    /// sessions and cached data through it, and fan topic messages out to
+    /// every node through a Redis stream.
+    #[cfg(feature = "redis")]
+    pub fn with_redis(mut self, url: &str) -> Result<Self> {
+        let client = ::redis::Client::open(url)
+            .map_err(|e| crate::error::Error::internal(format!("Invalid Redis URL: {}", e)))?;
+        let fan_out = crate::redis::FanOut::new(client.clone(), self.config.cluster.node_id.clone());
+        self.topics = self.topics.clone().with_relay(fan_out.relay());
+        self.fan_out = Some(fan_out);
+        Ok(self.with_shared_backend(Arc::new(crate::redis::RedisBackend::new(client))))
+    }
+
+    /// Forward the messages of `source` into the rooms of its topics while
+    /// the server runs.
+    #[cfg(feature = "streaming")]
+    pub fn with_stream_source(mut self, source: impl crate::streaming::StreamSource) -> Self {
+        self.stream_sources.push(Arc::new(source));
+        self
+    }
+
+    /// Set how streamed messages are buffered and forwarded.
+    #[cfg(feature = "streaming")]
+    pub fn with_stream_options(mut self, options: crate::streaming::StreamOptions) -> Self {
+        self.stream_options = options;
+        self
+    }
+
+    /// Publish the messages of `source`'s MQTT subscriptions to the
+    /// server's topics while it runs.
+    #[cfg(feature = "mqtt")]
+    pub fn with_mqtt_source(mut self, source: crate::mqtt::MqttSource) -> Self {
+        self.mqtt_sources.push(source);
+        self
+    }
+
     /// Build the router.
     fn build_router(&self) -> Router {
+        let limits = self.config.limits;
+        let max_body_size = usize::try_from(self.config.max_body_size).unwrap_or(usize::MAX);
         let state = Arc::new(ServerState {
             config: self.config.clone(),
             session_store: Arc::clone(&self.session_store),
+            media_store: self.media_store.clone(),
+            transfers: self.transfers.clone(),
+            shares: self.shares.clone(),
+            shared_backend: self.shared_backend.clone(),
+            peers: self.peers.clone(),
             start_time: Instant::now(),
-            app_fn: self.app_fn,
+            app: self.app.clone(),
         });
 
-        let session_store = Arc::clone(&self.session_store);
-        let app_fn = self.app_fn;
+        let context = SocketContext {
+            session_store: Arc::clone(&self.session_store),
+            media_store: self.media_store.clone(),
+            transfers: self.transfers.clone(),
+            rooms: self.rooms.clone(),
+            topics: self.topics.clone(),
+            presence: self.presence.clone(),
+            transcriber: self.transcriber.clone(),
+            speaker: self.speaker.clone(),
+            temp_dirs: self.temp_dirs.clone(),
+            data_cache: self.data_cache.clone(),
+            shares: self.shares.clone(),
+            custom_elements: self.custom_elements.clone(),
+            plugins: self.plugins.clone().into(),
+            widget_debounce: Arc::new(self.widget_debounce.clone()),
+            compression: self.config.compression,
+            check_determinism: self.config.check_determinism,
+            fatal_element_errors: self.config.fatal_element_errors,
+            embed: self.config.embed.clone(),
+            limits: self.config.limits,
+            session_timeout: Duration::from_secs(self.config.session_timeout),
+            idle_warning: Duration::from_secs(self.config.idle_warning),
+            run_timeout: self.config.run_timeout.map(Duration::from_secs),
+            compression_metrics: self.compression_metrics.clone(),
+            #[cfg(feature = "embeddings")]
+            semantic_index: self.semantic_index.clone(),
+            app: self.app.clone(),
+            clock: Arc::clone(&self.clock),
+            ids: Arc::clone(&self.ids),
+        };
 
-        Router::new()
+        let router = Router::new()
             // Health check
             .route(config::HEALTH_CHECK_PATH, get(handler::health))
             // App info
@@ -132,38 +610,116 @@ impl AppServer {
             .route("/favicon.ico", get(handler::favicon))
             // Main app page
             .route(config::INDEX_PATH, get(handler::index))
+            // Downloads and exports
+            .route(config::MEDIA_PATH, get(handler::media))
+            // Resumable chunked uploads
+            .route(
+                config::UPLOAD_PATH,
+                put(handler::upload_chunk)
+                    .layer(DefaultBodyLimit::max(limits.upload_chunk))
+                    .layer(axum::middleware::from_fn_with_state(limits.upload_chunk, limits::reject_oversized))
+                    .get(handler::upload_status),
+            )
+            // Session snapshots for support and migration
+            .route(config::ADMIN_SNAPSHOT_PATH, get(handler::export_session))
+            .route(config::ADMIN_IMPORT_PATH, post(handler::import_session))
+            // Blue-green app swaps
+            .route(config::ADMIN_APP_SWAP_PATH, post(handler::swap_app))
+            // Static renders for reports and visual regression tests
+            .route(config::RENDER_PATH, get(handler::render_session))
+            // Read-only shared snapshots
+            .route(config::SHARE_PATH, get(handler::shared_snapshot))
+            // Fragments framed by other websites
+            .route(config::EMBED_PATH, get(handler::embed))
+            // Node and peer status for cluster mode
+            .route(config::CLUSTER_STATUS_PATH, get(handler::cluster_status))
             // WebSocket endpoint
             .route(
                 config::WEBSOCKET_PATH,
-                get(move |ws| ws::ws_handler(ws, Arc::clone(&session_store), app_fn)),
+                get(move |ws, query| ws::ws_handler(ws, query, context.clone())),
             )
-            .layer(DefaultBodyLimit::max(config::max_body_size_usize()))
+            // Pin browsers to this node for sticky load balancing
+            .layer(axum::middleware::from_fn_with_state(Arc::clone(&state), cluster::sticky_sessions))
+            .with_state(state);
+
+        self.plugins
+            .iter()
+            .filter_map(|plugin| plugin.extra_routes())
+            .fold(router, Router::merge)
+            .layer(DefaultBodyLimit::max(max_body_size))
+            .layer(axum::middleware::from_fn_with_state(max_body_size, limits::reject_oversized))
+            // Refused requests reach no route or plugin
+            .layer(axum::middleware::from_fn_with_state(
+                Arc::new(self.config.firewall.clone()),
+                firewall::filter_requests,
+            ))
             .layer(CorsLayer::permissive())
             .layer(TraceLayer::new_for_http())
-            .with_state(state)
     }
 
     /// Start the server.
     pub async fn run(&self) -> Result<()> {
-        let addr: SocketAddr = format!("{}:{}", self.config.host, self.config.port)
-            .parse()
-            .map_err(|e| crate::error::Error::internal(format!("Invalid address: {}", e)))?;
+        self.config.validate()?;
+        for warning in self.config.warn_on_insecure() {
+            tracing::warn!("{}", warning);
+        }
+        let mut listeners = Vec::new();
+        for addr in self.config.listen_addrs()? {
+            let listener = Listener::bind(&addr)
+                .await
+                .map_err(|e| crate::error::Error::internal(format!("Failed to bind {}: {}", addr, e)))?;
+            tracing::info!("Listening on {}", addr);
+            listeners.push(listener);
+        }
 
-        let router = self.build_router();
+        for plugin in &self.plugins {
+            plugin.on_startup(&self.config)?;
+            tracing::info!("Started plugin {}", plugin.name());
+        }
 
-        tracing::info!(
-            "Starting platypus server on http://{}:{}",
-            self.config.host,
-            self.config.port
-        );
+        let router = self.build_router();
+        let collector = self.config.media_retention.spawn_collector(self.media_store.clone());
+        let refreshes: Vec<_> = self
+            .refreshes
+            .iter()
+            .map(|refresh| refresh.spawn(self.data_cache.clone(), self.topics.clone()))
+            .collect();
+        #[cfg(feature = "streaming")]
+        let streams: Vec<_> = self
+            .stream_sources
+            .iter()
+            .map(|source| crate::streaming::spawn_stream(Arc::clone(source), self.rooms.clone(), self.stream_options))
+            .collect();
+        #[cfg(feature = "mqtt")]
+        let subscriptions: Vec<_> = self.mqtt_sources.iter().map(|source| source.spawn(self.topics.clone())).collect();
+        #[cfg(feature = "redis")]
+        let fan_out = self.fan_out.as_ref().map(|fan_out| {
+            fan_out.spawn(self.topics.clone(), self.peers.clone(), Arc::clone(&self.session_store))
+        });
 
-        let listener = tokio::net::TcpListener::bind(&addr)
-            .await
-            .map_err(|e| crate::error::Error::internal(format!("Failed to bind: {}", e)))?;
+        tracing::info!("Starting platypus server");
 
-        axum::serve(listener, router)
+        // Every listener serves until one fails
+        let served = futures::future::try_join_all(listeners.into_iter().map(|listener| listener.serve(router.clone())))
             .await
-            .map_err(|e| crate::error::Error::internal(format!("Server error: {}", e)))?;
+            .map_err(|e| crate::error::Error::internal(format!("Server error: {}", e)));
+        collector.abort();
+        for refresh in refreshes {
+            refresh.abort();
+        }
+        #[cfg(feature = "streaming")]
+        for stream in streams {
+            stream.abort();
+        }
+        #[cfg(feature = "mqtt")]
+        for subscription in subscriptions {
+            subscription.abort();
+        }
+        #[cfg(feature = "redis")]
+        for task in fan_out.into_iter().flatten() {
+            task.abort();
+        }
+        served?;
 
         Ok(())
     }
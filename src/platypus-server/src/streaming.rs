@@ -0,0 +1,437 @@
+//! Streaming sources feeding live dashboards, enabled by the `streaming`
+//! feature; the `kafka` and `nats` features add consumers for those
+//! brokers.
+//!
+//! The server consumes a [`StreamSource`] and forwards its messages into
+//! the room of each topic, [`room_id`], whose `messages` key holds the
+//! newest messages. Sessions that opened the room rerun on every forwarded
+//! batch, like on any other room write:
+//!
+//! ```no_run
+//! # #[cfg(feature = "kafka")]
+//! # fn server() -> platypus_server::Result<platypus_server::AppServer> {
+//! use platypus_server::streaming::KafkaSource;
+//! use platypus_server::AppServer;
+//!
+//! let source = KafkaSource::new("localhost:9092", "dashboards", &["orders"])?;
+//! Ok(AppServer::new().with_stream_source(source))
+//! # }
+//! ```
+//!
+//! An app then shows the topic with
+//! `st.room(&streaming::room_id("orders")).get("messages")`.
+//!
+//! Messages are read ahead into a bounded buffer; once it is full the
+//! source is not polled until the forwarder catches up, so a slow server
+//! holds messages in the broker rather than in memory. Offsets are
+//! committed after each forwarded batch.
+//!
+//! A failed read is retried after [`STREAM_RETRY_DELAY`], doubling up to
+//! [`MAX_STREAM_RETRY_DELAY`] while reads keep failing. Reading stops when
+//! the source ends, on an error the source reports as terminal, or when
+//! the server stops forwarding.
+
+use crate::error::{Error, Result};
+use futures::future::BoxFuture;
+use platypus_runtime::RoomRegistry;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Prefix of the rooms topics are forwarded into.
+pub const STREAM_ROOM_PREFIX: &str = "stream:";
+
+/// Default messages read ahead of the forwarder.
+pub const DEFAULT_STREAM_BUFFER: usize = 1024;
+
+/// Default most messages forwarded in one room write.
+pub const DEFAULT_STREAM_BATCH: usize = 256;
+
+/// Default messages kept per topic room.
+pub const DEFAULT_STREAM_HISTORY: usize = 100;
+
+/// Wait before retrying the first failed read.
+pub const STREAM_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Longest wait before retrying a failed read.
+pub const MAX_STREAM_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Get the ID of the room `topic` is forwarded into.
+pub fn room_id(topic: &str) -> String {
+    format!("{}{}", STREAM_ROOM_PREFIX, topic)
+}
+
+/// A message consumed from a source.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StreamMessage {
+    pub topic: String,
+    pub partition: i32,
+    pub offset: i64,
+    pub key: Option<String>,
+    /// The payload parsed as JSON, or else as text.
+    pub payload: Value,
+}
+
+impl StreamMessage {
+    /// Parse a raw payload as JSON, falling back to text.
+    pub fn payload_json(payload: &[u8]) -> Value {
+        serde_json::from_slice(payload).unwrap_or_else(|_| String::from_utf8_lossy(payload).into_owned().into())
+    }
+}
+
+/// Next offsets to consume, by topic and partition.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Offsets(BTreeMap<(String, i32), i64>);
+
+impl Offsets {
+    /// Get the next offset of a partition.
+    pub fn get(&self, topic: &str, partition: i32) -> Option<i64> {
+        self.0.get(&(topic.to_string(), partition)).copied()
+    }
+
+    /// Iterate over topics, partitions and their next offsets.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, i32, i64)> {
+        self.0.iter().map(|((topic, partition), offset)| (topic.as_str(), *partition, *offset))
+    }
+
+    fn advance(&mut self, message: &StreamMessage) {
+        let next = self.0.entry((message.topic.clone(), message.partition)).or_insert(0);
+        *next = (*next).max(message.offset + 1);
+    }
+}
+
+/// A broker consumer the server forwards messages from.
+pub trait StreamSource: Send + Sync + 'static {
+    /// Source name, for logs.
+    fn name(&self) -> &str;
+
+    /// Wait for the next message, or `None` once the source has ended.
+    fn next(&self) -> BoxFuture<'_, Result<Option<StreamMessage>>>;
+
+    /// Record that every message before `offsets` was forwarded.
+    fn commit(&self, offsets: &Offsets) -> Result<()>;
+
+    /// Whether `error`, returned by [`StreamSource::next`], means no later
+    /// read can succeed. Other errors are retried; by default all are.
+    fn is_terminal(&self, _error: &Error) -> bool {
+        false
+    }
+}
+
+/// How messages are buffered and forwarded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamOptions {
+    /// Messages read ahead of the forwarder.
+    pub buffer: usize,
+    /// Most messages forwarded in one room write.
+    pub max_batch: usize,
+    /// Messages kept per topic room.
+    pub history: usize,
+}
+
+impl Default for StreamOptions {
+    fn default() -> Self {
+        StreamOptions {
+            buffer: DEFAULT_STREAM_BUFFER,
+            max_batch: DEFAULT_STREAM_BATCH,
+            history: DEFAULT_STREAM_HISTORY,
+        }
+    }
+}
+
+/// Consume `source` and forward its messages into `rooms` until it ends.
+pub fn spawn_stream(source: Arc<dyn StreamSource>, rooms: RoomRegistry, options: StreamOptions) -> JoinHandle<()> {
+    let (sender, mut receiver) = mpsc::channel(options.buffer.max(1));
+    let reader = Arc::clone(&source);
+    let reading = tokio::spawn(async move {
+        let mut retry_delay = STREAM_RETRY_DELAY;
+        loop {
+            match reader.next().await {
+                // Waits while the buffer is full
+                Ok(Some(message)) => {
+                    retry_delay = STREAM_RETRY_DELAY;
+                    if sender.send(message).await.is_err() {
+                        break;
+                    }
+                }
+                Ok(None) => break,
+                Err(e) if reader.is_terminal(&e) => {
+                    tracing::error!("Stream source {} failed: {}", reader.name(), e);
+                    break;
+                }
+                Err(e) => {
+                    tracing::warn!("Stream source {} failed, retrying in {:?}: {}", reader.name(), retry_delay, e);
+                    tokio::select! {
+                        _ = tokio::time::sleep(retry_delay) => {}
+                        _ = sender.closed() => break,
+                    }
+                    retry_delay = (retry_delay * 2).min(MAX_STREAM_RETRY_DELAY);
+                }
+            }
+        }
+    });
+
+    tokio::spawn(async move {
+        let mut offsets = Offsets::default();
+        let mut batch = Vec::new();
+        while receiver.recv_many(&mut batch, options.max_batch.max(1)).await > 0 {
+            forward(&rooms, &batch, options.history);
+            for message in batch.drain(..) {
+                offsets.advance(&message);
+            }
+            if let Err(e) = source.commit(&offsets) {
+                tracing::warn!("Stream source {} failed to commit: {}", source.name(), e);
+            }
+        }
+        reading.abort();
+    })
+}
+
+/// Append a batch to its topics' rooms, keeping the newest `history`.
+fn forward(rooms: &RoomRegistry, batch: &[StreamMessage], history: usize) {
+    let mut topics: BTreeMap<&str, Vec<Value>> = BTreeMap::new();
+    for message in batch {
+        if let Ok(value) = serde_json::to_value(message) {
+            topics.entry(&message.topic).or_default().push(value);
+        }
+    }
+    for (topic, messages) in topics {
+        rooms.open(&room_id(topic), None).append("messages", messages, history);
+    }
+}
+
+/// Kafka consumer of a group, committing offsets as they are forwarded.
+#[cfg(feature = "kafka")]
+pub struct KafkaSource {
+    name: String,
+    consumer: rdkafka::consumer::StreamConsumer,
+}
+
+#[cfg(feature = "kafka")]
+impl KafkaSource {
+    /// Join consumer group `group_id` on `brokers`, reading `topics` from
+    /// the group's committed offsets, or else from the newest message.
+    pub fn new(brokers: &str, group_id: &str, topics: &[&str]) -> Result<Self> {
+        use rdkafka::consumer::Consumer;
+
+        let consumer: rdkafka::consumer::StreamConsumer = rdkafka::ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .set("group.id", group_id)
+            .set("enable.auto.commit", "false")
+            .set("auto.offset.reset", "latest")
+            .create()
+            .map_err(anyhow::Error::from)?;
+        consumer.subscribe(topics).map_err(anyhow::Error::from)?;
+        Ok(KafkaSource {
+            name: format!("kafka:{}", topics.join(",")),
+            consumer,
+        })
+    }
+}
+
+#[cfg(feature = "kafka")]
+impl StreamSource for KafkaSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn next(&self) -> BoxFuture<'_, Result<Option<StreamMessage>>> {
+        use rdkafka::Message;
+
+        Box::pin(async move {
+            let message = self.consumer.recv().await.map_err(anyhow::Error::from)?;
+            Ok(Some(StreamMessage {
+                topic: message.topic().to_string(),
+                partition: message.partition(),
+                offset: message.offset(),
+                key: message.key().map(|key| String::from_utf8_lossy(key).into_owned()),
+                payload: StreamMessage::payload_json(message.payload().unwrap_or_default()),
+            }))
+        })
+    }
+
+    fn commit(&self, offsets: &Offsets) -> Result<()> {
+        use rdkafka::consumer::{CommitMode, Consumer};
+        use rdkafka::{Offset, TopicPartitionList};
+
+        let mut list = TopicPartitionList::new();
+        for (topic, partition, offset) in offsets.iter() {
+            list.add_partition_offset(topic, partition, Offset::Offset(offset))
+                .map_err(anyhow::Error::from)?;
+        }
+        self.consumer.commit(&list, CommitMode::Async).map_err(anyhow::Error::from)?;
+        Ok(())
+    }
+
+    fn is_terminal(&self, error: &Error) -> bool {
+        use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+
+        // Fatal errors leave the consumer unusable; others are transient
+        let Error::Other(error) = error else {
+            return false;
+        };
+        error.downcast_ref::<KafkaError>().and_then(KafkaError::rdkafka_error_code) == Some(RDKafkaErrorCode::Fatal)
+    }
+}
+
+/// NATS subscription. Core NATS keeps no offsets, so messages are numbered
+/// from 0 as received and messages published while the server is down
+/// are missed.
+#[cfg(feature = "nats")]
+pub struct NatsSource {
+    name: String,
+    subscriber: tokio::sync::Mutex<async_nats::Subscriber>,
+    received: std::sync::atomic::AtomicI64,
+}
+
+#[cfg(feature = "nats")]
+impl NatsSource {
+    /// Subscribe to `subject` on the server at `url`; wildcards such as
+    /// `metrics.>` forward each subject into its own room.
+    pub async fn connect(url: &str, subject: &str) -> Result<Self> {
+        let client = async_nats::connect(url).await.map_err(anyhow::Error::from)?;
+        let subscriber = client.subscribe(subject.to_string()).await.map_err(anyhow::Error::from)?;
+        Ok(NatsSource {
+            name: format!("nats:{}", subject),
+            subscriber: tokio::sync::Mutex::new(subscriber),
+            received: std::sync::atomic::AtomicI64::new(0),
+        })
+    }
+}
+
+#[cfg(feature = "nats")]
+impl StreamSource for NatsSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn next(&self) -> BoxFuture<'_, Result<Option<StreamMessage>>> {
+        use futures::StreamExt;
+        use std::sync::atomic::Ordering;
+
+        Box::pin(async move {
+            let Some(message) = self.subscriber.lock().await.next().await else {
+                return Ok(None);
+            };
+            Ok(Some(StreamMessage {
+                topic: message.subject.to_string(),
+                partition: 0,
+                offset: self.received.fetch_add(1, Ordering::Relaxed),
+                key: None,
+                payload: StreamMessage::payload_json(&message.payload),
+            }))
+        })
+    }
+
+    fn commit(&self, _offsets: &Offsets) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+
+    struct Replay {
+        messages: Mutex<VecDeque<StreamMessage>>,
+        committed: Mutex<Offsets>,
+    }
+
+    impl StreamSource for Replay {
+        fn name(&self) -> &str {
+            "replay"
+        }
+
+        fn next(&self) -> BoxFuture<'_, Result<Option<StreamMessage>>> {
+            Box::pin(async move { Ok(self.messages.lock().unwrap().pop_front()) })
+        }
+
+        fn commit(&self, offsets: &Offsets) -> Result<()> {
+            *self.committed.lock().unwrap() = offsets.clone();
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_forwards_into_rooms_and_commits() {
+        let messages = (0..5)
+            .map(|offset| StreamMessage {
+                topic: if offset % 2 == 0 { "orders" } else { "alerts" }.to_string(),
+                partition: 0,
+                offset,
+                key: None,
+                payload: StreamMessage::payload_json(format!("{{\"n\": {}}}", offset).as_bytes()),
+            })
+            .collect();
+        let source = Arc::new(Replay {
+            messages: Mutex::new(messages),
+            committed: Mutex::new(Offsets::default()),
+        });
+        let rooms = RoomRegistry::new();
+        let options = StreamOptions { buffer: 1, max_batch: 2, history: 2 };
+        spawn_stream(source.clone(), rooms.clone(), options).await.unwrap();
+
+        let orders: Vec<Value> = rooms.open(&room_id("orders"), None).get_as("messages").unwrap();
+        // The oldest order was dropped from the history
+        let offsets: Vec<&Value> = orders.iter().map(|message| &message["offset"]).collect();
+        assert_eq!(offsets, [2, 4]);
+        assert_eq!(orders[1]["payload"]["n"], 4);
+        let committed = source.committed.lock().unwrap();
+        assert_eq!(committed.get("orders", 0), Some(5));
+        assert_eq!(committed.get("alerts", 0), Some(4));
+    }
+
+    /// Fails every other read, and ends with a terminal error.
+    struct Flaky {
+        reads: Mutex<i64>,
+    }
+
+    impl StreamSource for Flaky {
+        fn name(&self) -> &str {
+            "flaky"
+        }
+
+        fn next(&self) -> BoxFuture<'_, Result<Option<StreamMessage>>> {
+            Box::pin(async move {
+                let mut reads = self.reads.lock().unwrap();
+                *reads += 1;
+                match *reads {
+                    7.. => Err(Error::internal("gone")),
+                    reads if reads % 2 == 1 => Err(Error::internal("timed out")),
+                    reads => Ok(Some(StreamMessage {
+                        topic: "orders".to_string(),
+                        partition: 0,
+                        offset: reads,
+                        key: None,
+                        payload: Value::Null,
+                    })),
+                }
+            })
+        }
+
+        fn commit(&self, _offsets: &Offsets) -> Result<()> {
+            Ok(())
+        }
+
+        fn is_terminal(&self, error: &Error) -> bool {
+            error.to_string().contains("gone")
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retries_failed_reads_until_a_terminal_error() {
+        let rooms = RoomRegistry::new();
+        let source = Arc::new(Flaky { reads: Mutex::new(0) });
+        spawn_stream(source, rooms.clone(), StreamOptions::default()).await.unwrap();
+
+        let orders: Vec<Value> = rooms.open(&room_id("orders"), None).get_as("messages").unwrap();
+        let offsets: Vec<&Value> = orders.iter().map(|message| &message["offset"]).collect();
+        assert_eq!(offsets, [2, 4, 6]);
+    }
+}
@@ -1,104 +1,343 @@
 //! WebSocket handler for real-time communication.
 
 use axum::extract::ws::{WebSocket, WebSocketUpgrade, Message};
+use axum::extract::Query;
+use futures::stream::{SplitSink, SplitStream};
 use futures::{SinkExt, StreamExt};
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
-use platypus_runtime::SessionStore;
+use std::time::Duration;
+use platypus_core::clock::Clock;
+use platypus_core::ids::IdGenerator;
+use platypus_core::session::SessionId;
+use platypus_core::state::Delta;
+use platypus_runtime::{
+    ColorScheme, DataCache, Event, MediaStore, PresenceChange, PresenceRegistry, RoomChange, RoomRegistry, SessionStore, ShareStore, Speaker, TempDirs, ThemeMode,
+    TopicRegistry, Transcriber, TransferDirection, TransferStore,
+};
+use tokio::sync::broadcast::{self, error::{RecvError, TryRecvError}};
+use crate::compression::{CompressionConfig, CompressionMetrics};
+use crate::custom_element::CustomElementRegistry;
+use crate::log_layer;
+use crate::plugin::{self, BackMessage, PluginFlow, Plugins, SessionEvent};
+use crate::embed::EmbedConfig;
+use crate::idle::{self, IdleNotice, IdleTimer};
+use crate::limits::RequestLimits;
+use crate::reconcile::Reconciler;
+use crate::sequence::Sequencer;
 use crate::message;
-use crate::executor::{ScriptExecutor, AppFn};
+use crate::executor::{ScriptExecutor, WidgetDebounce};
+use crate::reload::AppSlot;
+
+/// Server state shared by every WebSocket connection.
+#[derive(Clone)]
+pub struct SocketContext {
+    pub session_store: Arc<SessionStore>,
+    pub media_store: MediaStore,
+    pub transfers: TransferStore,
+    pub rooms: RoomRegistry,
+    pub topics: TopicRegistry,
+    pub presence: PresenceRegistry,
+    pub transcriber: Transcriber,
+    pub speaker: Speaker,
+    pub temp_dirs: TempDirs,
+    pub data_cache: DataCache,
+    pub shares: ShareStore,
+    pub custom_elements: CustomElementRegistry,
+    pub plugins: Plugins,
+    pub widget_debounce: WidgetDebounce,
+    pub compression: CompressionConfig,
+    pub check_determinism: bool,
+    pub fatal_element_errors: bool,
+    pub embed: EmbedConfig,
+    pub limits: RequestLimits,
+    pub session_timeout: Duration,
+    pub idle_warning: Duration,
+    pub run_timeout: Option<Duration>,
+    pub compression_metrics: CompressionMetrics,
+    #[cfg(feature = "embeddings")]
+    pub semantic_index: Option<platypus_runtime::SemanticIndex>,
+    pub app: AppSlot,
+    pub clock: Arc<dyn Clock>,
+    pub ids: Arc<dyn IdGenerator>,
+}
+
+/// Query parameters of the WebSocket endpoint.
+#[derive(Debug, Default, Deserialize)]
+pub struct SocketParams {
+    /// Compression the client can decode, e.g. `deflate-raw`.
+    pub compression: Option<String>,
+    /// ID of an imported session to resume.
+    pub session: Option<String>,
+    /// Resume token of the session to resume.
+    pub token: Option<String>,
+    /// Fragment to send instead of the whole page, for embedding pages.
+    pub embed: Option<String>,
+}
 
 /// Handle WebSocket upgrade.
 pub async fn ws_handler(
     ws: WebSocketUpgrade,
-    session_store: Arc<SessionStore>,
-    app_fn: Option<AppFn>,
+    Query(params): Query<SocketParams>,
+    context: SocketContext,
 ) -> impl axum::response::IntoResponse {
-    ws.on_upgrade(move |socket| handle_socket(socket, session_store, app_fn))
+    let compress = context.compression.negotiate(params.compression.as_deref());
+    let resume = params
+        .session
+        .and_then(|id| uuid::Uuid::parse_str(&id).ok())
+        .map(|id| (SessionId::from_uuid(id), params.token.unwrap_or_default()));
+    // Embedding pages are only served when embedding is enabled
+    let fragment = params.embed.filter(|_| context.embed.enabled());
+    let ws = ws
+        .max_message_size(context.limits.websocket_message)
+        .max_frame_size(context.limits.websocket_message);
+    ws.on_upgrade(move |socket| handle_socket(socket, context, compress, resume, fragment))
 }
 
 /// Handle WebSocket connection.
-async fn handle_socket(socket: WebSocket, session_store: Arc<SessionStore>, app_fn: Option<AppFn>) {
-    let (mut sender, mut receiver) = socket.split();
+async fn handle_socket(
+    socket: WebSocket,
+    context: SocketContext,
+    compress: bool,
+    resume: Option<(SessionId, String)>,
+    fragment: Option<String>,
+) {
+    let SocketContext {
+        session_store,
+        media_store,
+        transfers,
+        rooms,
+        topics,
+        presence,
+        transcriber,
+        speaker,
+        temp_dirs,
+        data_cache,
+        shares,
+        custom_elements,
+        plugins,
+        widget_debounce,
+        compression,
+        check_determinism,
+        fatal_element_errors,
+        embed,
+        limits,
+        session_timeout,
+        idle_warning,
+        run_timeout,
+        compression_metrics,
+        #[cfg(feature = "embeddings")]
+        semantic_index,
+        app,
+        clock,
+        ids,
+    } = context;
+    let (sender, receiver) = socket.split();
 
-    // Create a new session
-    let session_id = session_store.create_session("app".to_string());
+    // Resume an imported session, or create a new one. Both may go to the
+    // shared store, whose calls block
+    let (resumed, session_id) = match tokio::task::spawn_blocking({
+        let session_store = Arc::clone(&session_store);
+        move || {
+            let resumed = resume
+                .filter(|(session_id, token)| session_store.claim(*session_id, token))
+                .map(|(session_id, _)| session_id);
+            (resumed, resumed.unwrap_or_else(|| session_store.create_session("app".to_string())))
+        }
+    })
+    .await
+    {
+        Ok(started) => started,
+        Err(e) => {
+            tracing::error!("Failed to start a session: {}", e);
+            return;
+        }
+    };
     
-    tracing::info!("WebSocket connection established: {}", session_id);
+    tracing::info!("WebSocket connection established: {} (compression: {})", session_id, compress);
+    for plugin in plugins.iter() {
+        plugin.on_session(session_id, SessionEvent::Started);
+    }
+
+    let mut out = Outbox {
+        sender,
+        plugins: Arc::clone(&plugins),
+        session_id,
+        compression: compress.then_some(compression),
+        metrics: compression_metrics.clone(),
+        sequencer: Sequencer::new(),
+        reconciler: Reconciler::new(),
+    };
 
     // Create executor for script execution
-    let executor = if let Some(app_fn) = app_fn {
-        ScriptExecutor::with_app(session_store.clone(), app_fn)
-    } else {
-        ScriptExecutor::new(session_store.clone())
+    let executor = ScriptExecutor::new(session_store.clone())
+    .with_app_slot(app.clone())
+    .with_media_store(media_store.clone())
+    .with_custom_elements(custom_elements)
+    .with_debounce(widget_debounce)
+    .with_determinism_check(check_determinism)
+    .with_fatal_element_errors(fatal_element_errors)
+    .with_rooms(rooms.clone())
+    .with_topics(topics.clone())
+    .with_presence(presence.clone())
+    .with_transcriber(transcriber.clone())
+    .with_speaker(speaker)
+    .with_temp_dirs(temp_dirs.clone())
+    .with_data_cache(data_cache)
+    .with_shares(shares)
+    // Only embedded apps have a host page to exchange messages with
+    .with_host_origins(if fragment.is_some() { embed.host_origins } else { Vec::new() })
+    .with_fragment(fragment)
+    .with_run_timeout(run_timeout)
+    .with_clock(clock)
+    .with_ids(ids);
+    #[cfg(feature = "embeddings")]
+    let executor = match semantic_index {
+        Some(index) => executor.with_semantic_index(index),
+        None => executor,
+    };
+    // Runs go to a blocking thread, so the socket is read while they run
+    let executor = Arc::new(executor);
+    if let Some(session) = resumed.and_then(|session_id| session_store.get_session(session_id).ok()) {
+        tracing::info!("Resumed imported session {}", session_id);
+        executor.restore_session(session);
+    }
+
+    // Last theme sent to the client, so SetTheme is only sent on changes
+    let mut last_theme = None;
+    // Last log line streamed per log viewer key
+    let mut sent_logs = HashMap::new();
+    // Highest client seq received; every run reflects all received changes
+    let mut client_seq = 0;
+    // Uploads the client started, with the bytes last reported to the app
+    let mut uploads = HashMap::new();
+    let mut transfer_changes = transfers.subscribe();
+    let mut room_changes = rooms.subscribe();
+    let mut topic_changes = topics.subscribe();
+    let mut presence_changes = presence.subscribe();
+    let mut transcription_changes = transcriber.subscribe();
+    let mut app_swaps = app.subscribe();
+    let mut idle_timer = IdleTimer::new(session_timeout, idle_warning);
+
+    let mut inbox = Inbox {
+        receiver,
+        backlog: VecDeque::new(),
+        closed: false,
     };
 
     // Execute initial script and send deltas
-    match executor.execute_script(session_id) {
-        Ok(deltas) => {
-            let json_msg = message::deltas_to_json(deltas);
-            if let Ok(json_str) = serde_json::to_string(&json_msg) {
-                let _ = sender.send(Message::Text(json_str)).await;
+    let rerun = move |executor: &ScriptExecutor| Some(executor.execute_script(session_id));
+    run_script(&mut out, &mut inbox, &executor, rerun, client_seq, &mut last_theme).await;
+
+    // Handle incoming messages, streaming log lines as they are appended,
+    // reporting upload progress, rerunning when another session changes a
+    // joined room or its presence, a subscribed topic gets messages, a
+    // transcription advances or the app is swapped, running reruns
+    // deferred by debounced widgets once they are due, and warning about
+    // then expiring an idle session. Messages that arrived during a run are
+    // handled first.
+    loop {
+        let msg = if let Some(msg) = inbox.backlog.pop_front() {
+            msg
+        } else if inbox.closed {
+            break;
+        } else {
+            tokio::select! {
+                msg = inbox.receiver.next() => match msg {
+                    Some(msg) => msg,
+                    None => break,
+                },
+                _ = executor.logs().changed() => {
+                    send_log_lines(&mut out, &executor, &mut sent_logs).await;
+                    continue;
+                }
+                Ok(()) = transfer_changes.changed() => {
+                    send_upload_progress(&mut out, &mut inbox, &executor, &transfers, &mut uploads, client_seq, &mut last_theme).await;
+                    continue;
+                }
+                change = room_changes.recv() => {
+                    if rooms_changed(change, &mut room_changes, &executor, session_id) {
+                        run_script(&mut out, &mut inbox, &executor, rerun, client_seq, &mut last_theme).await;
+                    }
+                    continue;
+                }
+                change = topic_changes.recv() => {
+                    if topics_changed(change, &mut topic_changes, &executor) {
+                        run_script(&mut out, &mut inbox, &executor, rerun, client_seq, &mut last_theme).await;
+                    }
+                    continue;
+                }
+                change = presence_changes.recv() => {
+                    if presence_changed(change, &mut presence_changes, &executor, session_id) {
+                        run_script(&mut out, &mut inbox, &executor, rerun, client_seq, &mut last_theme).await;
+                    }
+                    continue;
+                }
+                change = transcription_changes.recv() => {
+                    if transcription_changed(change, &mut transcription_changes, session_id) {
+                        run_script(&mut out, &mut inbox, &executor, rerun, client_seq, &mut last_theme).await;
+                    }
+                    continue;
+                }
+                Ok(()) = app_swaps.changed() => {
+                    tracing::info!("Rerunning session {} with the swapped app", session_id);
+                    run_script(&mut out, &mut inbox, &executor, rerun, client_seq, &mut last_theme).await;
+                    continue;
+                }
+                _ = idle::idle_due(idle_timer.deadline()) => {
+                    match idle_timer.poll(std::time::Instant::now()) {
+                        Some(IdleNotice::Expiring(expires_in)) => {
+                            out.send_json(message::session_expiring_to_json(expires_in)).await;
+                        }
+                        Some(IdleNotice::Expired) => {
+                            tracing::info!("Session {} expired after {:?} idle", session_id, session_timeout);
+                            out.send_json(message::session_expired_to_json()).await;
+                            break;
+                        }
+                        None => {}
+                    }
+                    continue;
+                }
+                _ = rerun_due(executor.rerun_deadline()) => {
+                    run_script(&mut out, &mut inbox, &executor, move |executor| executor.run_pending(session_id), client_seq, &mut last_theme).await;
+                    continue;
+                }
             }
-        }
-        Err(e) => {
-            tracing::error!("Initial script execution error: {}", e);
-        }
-    }
+        };
 
-    // Handle incoming messages
-    while let Some(msg) = receiver.next().await {
         match msg {
             Ok(Message::Binary(data)) => {
                 tracing::debug!("Received binary message: {} bytes", data.len());
-                
+                if let Err(error) = limits.check_back_msg(data.len()) {
+                    tracing::warn!("Rejected message from {}: {}", session_id, error);
+                    out.send_json(message::error_to_json(&error)).await;
+                    continue;
+                }
+
                 // Try to deserialize as BackMsg
                 match message::deserialize_back_msg(&data) {
                     Ok(back_msg) => {
                         tracing::debug!("Parsed BackMsg from session: {}", back_msg.session_id);
-                        // Handle the message based on its type
-                        if let Some(msg_type) = back_msg.r#type {
-                            match msg_type {
-                                platypus_proto::back_msg::Type::WidgetStateChange(widget_change) => {
-                                    let widget_key = &widget_change.widget_key;
-                                    tracing::debug!("Widget state change: {}", widget_key);
-                                    
-                                    // Handle widget change and rerun script
-                                    match executor.handle_widget_change(
-                                        session_id,
-                                        widget_key,
-                                        &widget_change.value,
-                                    ) {
-                                        Ok(deltas) => {
-                                            let json_msg = message::deltas_to_json(deltas);
-                                            if let Ok(json_str) = serde_json::to_string(&json_msg) {
-                                                let _ = sender.send(Message::Text(json_str)).await;
-                                            }
-                                        }
-                                        Err(e) => {
-                                            tracing::error!("Script execution error: {}", e);
-                                        }
-                                    }
-                                }
-                                platypus_proto::back_msg::Type::RerunScript(_) => {
-                                    tracing::debug!("Script rerun requested");
-                                    
-                                    // Rerun script
-                                    match executor.execute_script(session_id) {
-                                        Ok(deltas) => {
-                                            let json_msg = message::deltas_to_json(deltas);
-                                            if let Ok(json_str) = serde_json::to_string(&json_msg) {
-                                                let _ = sender.send(Message::Text(json_str)).await;
-                                            }
-                                        }
-                                        Err(e) => {
-                                            tracing::error!("Script execution error: {}", e);
-                                        }
-                                    }
-                                }
-                                platypus_proto::back_msg::Type::UserInteraction(interaction) => {
-                                    tracing::debug!("User interaction: {}", interaction.interaction_type);
-                                }
+                        if plugin::backmsg_flow(&plugins, session_id, BackMessage::Proto(&back_msg)) == PluginFlow::Stop {
+                            continue;
+                        }
+                        client_seq = client_seq.max(back_msg.seq);
+                        out.sequencer.ack(back_msg.ack);
+                        if message::back_msg_is_resync(&back_msg) {
+                            send_snapshot(&mut out, &executor, client_seq, &mut sent_logs, &mut last_theme).await;
+                            continue;
+                        }
+                        if message::back_msg_is_keep_alive(&back_msg) {
+                            idle_timer.touch();
+                            continue;
+                        }
+                        if let Some(event) = message::event_from_back_msg(back_msg) {
+                            idle_timer.touch();
+                            if watch_upload(&mut uploads, &event) {
+                                transfer_changes.mark_changed();
                             }
+                            handle_event(&mut out, &mut inbox, &executor, event, client_seq, &mut last_theme).await;
                         }
                     }
                     Err(e) => {
@@ -107,69 +346,437 @@ async fn handle_socket(socket: WebSocket, session_store: Arc<SessionStore>, app_
                 }
             }
             Ok(Message::Text(text)) => {
+                if let Err(error) = limits.check_back_msg(text.len()) {
+                    tracing::warn!("Rejected message from {}: {}", session_id, error);
+                    out.send_json(message::error_to_json(&error)).await;
+                    continue;
+                }
                 tracing::debug!("Received text message: {}", text);
-                
+
                 // Parse JSON message from frontend
-                if let Ok(msg) = serde_json::from_str::<serde_json::Value>(&text) {
-                    if let Some("widget_change") = msg.get("type").and_then(|v| v.as_str()) {
-                        if let (Some(key), Some(value)) = (
-                            msg.get("key").and_then(|v| v.as_str()),
-                            msg.get("value")
-                        ) {
-                            tracing::debug!("Widget change: {} = {}", key, value);
-                            
-                            // Convert value to string for storage
-                            let value_str = match value {
-                                serde_json::Value::String(s) => s.clone(),
-                                serde_json::Value::Number(n) => n.to_string(),
-                                serde_json::Value::Bool(b) => b.to_string(),
-                                _ => value.to_string(),
-                            };
-                            
-                            // Handle widget change and rerun script
-                            match executor.handle_widget_change(session_id, key, &value_str) {
-                                Ok(deltas) => {
-                                    let json_msg = message::deltas_to_json(deltas);
-                                    if let Ok(json_str) = serde_json::to_string(&json_msg) {
-                                        let _ = sender.send(Message::Text(json_str)).await;
-                                    }
-                                }
-                                Err(e) => {
-                                    tracing::error!("Script execution error: {}", e);
-                                }
-                            }
-                        }
-                    } else if let Some("button_click") = msg.get("type").and_then(|v| v.as_str()) {
-                        if let Some(key) = msg.get("key").and_then(|v| v.as_str()) {
-                            tracing::debug!("Button click: {}", key);
-                            
-                            // Rerun script on button click
-                            match executor.execute_script(session_id) {
-                                Ok(deltas) => {
-                                    let json_msg = message::deltas_to_json(deltas);
-                                    if let Ok(json_str) = serde_json::to_string(&json_msg) {
-                                        let _ = sender.send(Message::Text(json_str)).await;
-                                    }
-                                }
-                                Err(e) => {
-                                    tracing::error!("Script execution error: {}", e);
-                                }
-                            }
+                if let Some(msg) = message::parse_json_msg(&text) {
+                    if plugin::backmsg_flow(&plugins, session_id, BackMessage::Json(&msg)) == PluginFlow::Stop {
+                        continue;
+                    }
+                    client_seq = client_seq.max(message::json_seq(&msg));
+                    if let Some(ack) = message::json_ack(&msg) {
+                        out.sequencer.ack(ack);
+                    }
+                    if message::json_is_resync(&msg) {
+                        send_snapshot(&mut out, &executor, client_seq, &mut sent_logs, &mut last_theme).await;
+                        continue;
+                    }
+                    if message::json_is_keep_alive(&msg) {
+                        idle_timer.touch();
+                        continue;
+                    }
+                    if let Some(event) = message::event_from_json(&msg) {
+                        idle_timer.touch();
+                        if watch_upload(&mut uploads, &event) {
+                            transfer_changes.mark_changed();
                         }
+                        handle_event(&mut out, &mut inbox, &executor, event, client_seq, &mut last_theme).await;
                     }
                 }
             }
             Ok(Message::Close(_)) => {
                 tracing::info!("WebSocket closed: {}", session_id);
-                let _ = session_store.remove_session(session_id);
                 break;
             }
             Err(e) => {
+                // Tell the client why, e.g. a message over the transport
+                // limit, before the connection closes
                 tracing::error!("WebSocket error: {}", e);
-                let _ = session_store.remove_session(session_id);
+                let error = platypus_core::Error::protocol(e.to_string());
+                out.send_json(message::error_to_json(&error)).await;
                 break;
             }
             _ => {}
         }
     }
+
+    // Nobody resumes an unfinished upload of a closed session
+    for id in uploads.keys() {
+        transfers.remove(id);
+    }
+    // Its files are collected once no other session references them
+    media_store.release(session_id);
+    presence.leave(session_id);
+    transcriber.forget(session_id);
+    temp_dirs.remove(session_id);
+    let _ = tokio::task::spawn_blocking(move || session_store.remove_session(session_id)).await;
+    log_layer::unregister_session(session_id);
+    if let Some(stats) = compression_metrics.get(session_id) {
+        tracing::debug!(
+            "Session {} sent {} of {} bytes ({} compressed messages)",
+            session_id,
+            stats.wire_bytes,
+            stats.raw_bytes,
+            stats.compressed_messages
+        );
+    }
+    compression_metrics.remove(session_id);
+    for plugin in plugins.iter() {
+        plugin.on_session(session_id, SessionEvent::Ended);
+    }
+}
+
+/// Sends messages to one client.
+struct Outbox {
+    sender: SplitSink<WebSocket, Message>,
+    plugins: Plugins,
+    session_id: SessionId,
+    /// Set when the client opted in to compression.
+    compression: Option<CompressionConfig>,
+    metrics: CompressionMetrics,
+    sequencer: Sequencer,
+    reconciler: Reconciler,
+}
+
+impl Outbox {
+    /// Send a JSON message after running plugin `on_forwardmsg` hooks,
+    /// numbering it and compressing it if it is large enough.
+    async fn send_json(&mut self, mut msg: serde_json::Value) {
+        for plugin in self.plugins.iter() {
+            plugin.on_forwardmsg(self.session_id, &mut msg);
+        }
+        self.sequencer.stamp(&mut msg);
+        let Ok(json_str) = serde_json::to_string(&msg) else {
+            return;
+        };
+
+        let raw = json_str.len();
+        let compressed = self
+            .compression
+            .and_then(|compression| compression.compress(json_str.as_bytes()));
+        let (message, wire, is_compressed) = match compressed {
+            Some(bytes) if bytes.len() < raw => {
+                let wire = bytes.len();
+                (Message::Binary(bytes), wire, true)
+            }
+            _ => (Message::Text(json_str), raw, false),
+        };
+        self.metrics.record(self.session_id, raw, wire, is_compressed);
+        let _ = self.sender.send(message).await;
+    }
+}
+
+/// Apply a client event and send the resulting run.
+async fn handle_event(
+    out: &mut Outbox,
+    inbox: &mut Inbox,
+    executor: &Arc<ScriptExecutor>,
+    event: Event,
+    client_seq: u64,
+    last_theme: &mut Option<(ThemeMode, ColorScheme)>,
+) {
+    tracing::debug!("Event: {}", event.name());
+    let session_id = out.session_id;
+    // Debounced events run nothing; the rerun is sent when it is due
+    let run = move |executor: &ScriptExecutor| executor.handle_event(session_id, event).transpose();
+    run_script(out, inbox, executor, run, client_seq, last_theme).await;
+}
+
+/// Receives messages from one client.
+struct Inbox {
+    receiver: SplitStream<WebSocket>,
+    /// Messages that arrived during a run, handled once it is sent
+    backlog: VecDeque<Result<Message, axum::Error>>,
+    /// Set when the client disconnected during a run
+    closed: bool,
+}
+
+/// Run the script on a blocking thread and send its result, sending the
+/// pages it flushes at checkpoints meanwhile. `run` returns `None` when
+/// there is nothing to send.
+///
+/// Client messages are read during the run and kept for the main loop. One
+/// carrying an event cancels the run, whose result is dropped: the event's
+/// own run supersedes it.
+async fn run_script(
+    out: &mut Outbox,
+    inbox: &mut Inbox,
+    executor: &Arc<ScriptExecutor>,
+    run: impl FnOnce(&ScriptExecutor) -> Option<Result<Vec<Delta>, String>> + Send + 'static,
+    client_seq: u64,
+    last_theme: &mut Option<(ThemeMode, ColorScheme)>,
+) {
+    let mut partial_pages = executor.partial_pages();
+    partial_pages.mark_unchanged();
+    let mut task = tokio::task::spawn_blocking({
+        let executor = Arc::clone(executor);
+        move || run(&executor)
+    });
+    let mut superseded = false;
+    let result = loop {
+        tokio::select! {
+            biased;
+            result = &mut task => break result,
+            Ok(()) = partial_pages.changed(), if !superseded => {
+                let deltas = partial_pages.borrow_and_update().clone();
+                let deltas = out.reconciler.reconcile(deltas);
+                out.send_json(message::reconciled_deltas_to_json(deltas, client_seq)).await;
+            }
+            msg = inbox.receiver.next(), if !inbox.closed => match msg {
+                Some(msg) => {
+                    if !superseded && msg.as_ref().is_ok_and(supersedes_run) {
+                        executor.cancel_run();
+                        superseded = true;
+                    }
+                    inbox.backlog.push_back(msg);
+                }
+                None => inbox.closed = true,
+            },
+        }
+    };
+    if superseded {
+        return;
+    }
+    match result {
+        Ok(Some(result)) => send_run_result(out, executor, result, client_seq, last_theme).await,
+        Ok(None) => {}
+        Err(e) => tracing::error!("Script execution failed: {}", e),
+    }
+}
+
+/// Check if a client message carries an event, whose run supersedes the
+/// run in progress.
+fn supersedes_run(msg: &Message) -> bool {
+    match msg {
+        Message::Binary(data) => message::deserialize_back_msg(data)
+            .ok()
+            .and_then(message::event_from_back_msg)
+            .is_some(),
+        Message::Text(text) => serde_json::from_str(text)
+            .ok()
+            .and_then(|msg| message::event_from_json(&msg))
+            .is_some(),
+        _ => false,
+    }
+}
+
+/// Start reporting the progress of an upload the client announced.
+///
+/// Returns true for a new upload, whose first chunks may have arrived
+/// before the announcement.
+fn watch_upload(uploads: &mut HashMap<String, u64>, event: &Event) -> bool {
+    match event {
+        Event::TransferProgress { progress } if progress.direction == TransferDirection::Upload => {
+            uploads.insert(progress.id.clone(), progress.loaded).is_none()
+        }
+        _ => false,
+    }
+}
+
+/// Report chunks received for the client's uploads, to the client and as
+/// transfer events to the app. Completed uploads are forgotten.
+async fn send_upload_progress(
+    out: &mut Outbox,
+    inbox: &mut Inbox,
+    executor: &Arc<ScriptExecutor>,
+    transfers: &TransferStore,
+    uploads: &mut HashMap<String, u64>,
+    client_seq: u64,
+    last_theme: &mut Option<(ThemeMode, ColorScheme)>,
+) {
+    let changed: Vec<_> = uploads
+        .iter()
+        .filter_map(|(id, reported)| transfers.progress(id).filter(|progress| progress.loaded != *reported))
+        .collect();
+
+    for progress in changed {
+        out.send_json(message::transfer_progress_to_json(&progress)).await;
+        if progress.is_complete() {
+            uploads.remove(&progress.id);
+            transfers.remove(&progress.id);
+        } else {
+            uploads.insert(progress.id.clone(), progress.loaded);
+        }
+        handle_event(out, inbox, executor, Event::TransferProgress { progress }, client_seq, last_theme).await;
+    }
+}
+
+/// Check if room changes concern the session: another session or replica
+/// changed a room its last run joined. Changes already queued are taken
+/// too, so a burst of writes causes one rerun.
+fn rooms_changed(
+    first: Result<RoomChange, RecvError>,
+    changes: &mut broadcast::Receiver<RoomChange>,
+    executor: &ScriptExecutor,
+    session_id: SessionId,
+) -> bool {
+    let mut relevant = false;
+    let mut next = Some(first);
+    while let Some(change) = next {
+        relevant |= match change {
+            Ok(change) => change.origin != Some(session_id) && executor.in_room(&change.room),
+            // Missed changes might concern the session
+            Err(RecvError::Lagged(_)) => true,
+            Err(RecvError::Closed) => false,
+        };
+        next = match changes.try_recv() {
+            Ok(change) => Some(Ok(change)),
+            Err(TryRecvError::Lagged(missed)) => Some(Err(RecvError::Lagged(missed))),
+            Err(_) => None,
+        };
+    }
+    relevant
+}
+
+/// Check if topics the session's last run subscribed to got messages.
+/// Changes already queued are taken too, so a burst of messages causes one
+/// rerun.
+fn topics_changed(
+    first: Result<String, RecvError>,
+    changes: &mut broadcast::Receiver<String>,
+    executor: &ScriptExecutor,
+) -> bool {
+    let mut relevant = false;
+    let mut next = Some(first);
+    while let Some(change) = next {
+        relevant |= match change {
+            Ok(topic) => executor.subscribes_to(&topic),
+            Err(RecvError::Lagged(_)) => true,
+            Err(RecvError::Closed) => false,
+        };
+        next = match changes.try_recv() {
+            Ok(topic) => Some(Ok(topic)),
+            Err(TryRecvError::Lagged(missed)) => Some(Err(RecvError::Lagged(missed))),
+            Err(_) => None,
+        };
+    }
+    relevant
+}
+
+/// Check if presence changes concern the session: another session joined,
+/// left or moved while the session's last run read presence. Changes
+/// already queued are taken too.
+fn presence_changed(
+    first: Result<PresenceChange, RecvError>,
+    changes: &mut broadcast::Receiver<PresenceChange>,
+    executor: &ScriptExecutor,
+    session_id: SessionId,
+) -> bool {
+    let mut relevant = false;
+    let mut next = Some(first);
+    while let Some(change) = next {
+        relevant |= match change {
+            Ok(change) => change.session_id != session_id,
+            Err(RecvError::Lagged(_)) => true,
+            Err(RecvError::Closed) => false,
+        };
+        next = match changes.try_recv() {
+            Ok(change) => Some(Ok(change)),
+            Err(TryRecvError::Lagged(missed)) => Some(Err(RecvError::Lagged(missed))),
+            Err(_) => None,
+        };
+    }
+    relevant && executor.reads_presence()
+}
+
+/// Check if transcription jobs the session started advanced or finished.
+/// Changes already queued are taken too.
+fn transcription_changed(
+    first: Result<SessionId, RecvError>,
+    changes: &mut broadcast::Receiver<SessionId>,
+    session_id: SessionId,
+) -> bool {
+    let mut relevant = false;
+    let mut next = Some(first);
+    while let Some(change) = next {
+        relevant |= match change {
+            Ok(owner) => owner == session_id,
+            Err(RecvError::Lagged(_)) => true,
+            Err(RecvError::Closed) => false,
+        };
+        next = match changes.try_recv() {
+            Ok(owner) => Some(Ok(owner)),
+            Err(TryRecvError::Lagged(missed)) => Some(Err(RecvError::Lagged(missed))),
+            Err(_) => None,
+        };
+    }
+    relevant
+}
+
+/// Wait until a deferred rerun is due, or forever if none is pending.
+async fn rerun_due(deadline: Option<std::time::Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Send a run's deltas, followed by its queued actions and any theme change.
+///
+/// The deltas carry `client_seq` so the client can drop optimistic state
+/// the run has caught up with.
+async fn send_run_result(
+    out: &mut Outbox,
+    executor: &ScriptExecutor,
+    result: Result<Vec<Delta>, String>,
+    client_seq: u64,
+    last_theme: &mut Option<(ThemeMode, ColorScheme)>,
+) {
+    match result {
+        Ok(deltas) => {
+            let deltas = out.reconciler.reconcile(deltas);
+            out.send_json(message::reconciled_deltas_to_json(deltas, client_seq)).await;
+            for action in executor.take_actions() {
+                out.send_json(message::action_to_json(&action)).await;
+            }
+        }
+        Err(e) => {
+            tracing::error!("Script execution error: {}", e);
+            let error = platypus_core::Error::ScriptExecutionError(e);
+            out.send_json(message::error_to_json(&error)).await;
+        }
+    }
+    send_theme_if_changed(out, executor, last_theme).await;
+}
+
+/// Resend the session's page, log lines and theme after the client found a
+/// gap in the messages it received.
+async fn send_snapshot(
+    out: &mut Outbox,
+    executor: &ScriptExecutor,
+    client_seq: u64,
+    sent_logs: &mut HashMap<String, u64>,
+    last_theme: &mut Option<(ThemeMode, ColorScheme)>,
+) {
+    tracing::debug!(
+        "Resyncing session {} after message {} of {}",
+        out.session_id,
+        out.sequencer.acked(),
+        out.sequencer.sent()
+    );
+    let snapshot = executor.snapshot();
+    out.reconciler.reset(&snapshot);
+    out.send_json(message::snapshot_to_json(snapshot, client_seq)).await;
+    sent_logs.clear();
+    send_log_lines(out, executor, sent_logs).await;
+    *last_theme = None;
+    send_theme_if_changed(out, executor, last_theme).await;
+}
+
+/// Send log lines appended since they were last streamed.
+async fn send_log_lines(out: &mut Outbox, executor: &ScriptExecutor, sent_logs: &mut HashMap<String, u64>) {
+    for (key, lines) in executor.logs().take_unsent(sent_logs) {
+        let items: Vec<_> = lines.iter().map(|line| line.to_item()).collect();
+        out.send_json(message::log_lines_to_json(&key, &items)).await;
+    }
+}
+
+/// Send a SetTheme message if the session's resolved theme changed.
+async fn send_theme_if_changed(
+    out: &mut Outbox,
+    executor: &ScriptExecutor,
+    last_theme: &mut Option<(ThemeMode, ColorScheme)>,
+) {
+    let theme = executor.theme(out.session_id);
+    if *last_theme == Some(theme) {
+        return;
+    }
+    *last_theme = Some(theme);
+
+    out.send_json(message::set_theme_to_json(theme.0, theme.1)).await;
 }
@@ -0,0 +1,88 @@
+//! Property tests of element conversion, delta order and reconciliation,
+//! over random elements from `platypus_core::arbitrary`.
+
+use platypus_core::arbitrary::{element_type, page};
+use platypus_core::element::ElementId;
+use platypus_core::state::Delta;
+use platypus_proto::Element;
+use platypus_server::determinism;
+use platypus_server::message;
+use platypus_server::reconcile::Reconciler;
+use proptest::prelude::*;
+use prost::Message;
+use std::collections::HashSet;
+
+fn key_and_type(delta: &Delta) -> Option<(String, String)> {
+    let Delta::AddElement { element, .. } = delta else {
+        return None;
+    };
+    let json = message::element_to_json(element);
+    Some((json["key"].as_str()?.to_string(), json["type"].as_str()?.to_string()))
+}
+
+proptest! {
+    #[test]
+    fn proto_round_trip(namespace in any::<u32>(), id in any::<u64>(), element in element_type()) {
+        let id = ElementId::namespaced(namespace, id);
+        let proto = message::element_type_to_proto(id, &element);
+        prop_assert_eq!(proto.id.parse::<ElementId>(), Ok(id));
+        let decoded = Element::decode(proto.encode_to_vec().as_slice()).unwrap();
+        prop_assert_eq!(decoded, proto);
+    }
+
+    #[test]
+    fn json_round_trip(element in element_type()) {
+        let json = message::element_to_json(&element);
+        let parsed = message::element_from_json(&json);
+        prop_assert!(parsed.is_some(), "{} did not parse", json);
+        prop_assert_eq!(message::element_to_json(&parsed.unwrap()), json);
+    }
+
+    #[test]
+    fn deltas_in_tree_order(deltas in page(0..40)) {
+        // Every element follows its parent, after the parent's earlier
+        // children and their descendants
+        let mut open: Vec<ElementId> = Vec::new();
+        for delta in &deltas {
+            let Delta::AddElement { id, parent_id, .. } = delta else {
+                panic!("unexpected delta: {:?}", delta);
+            };
+            while open.last() != parent_id.as_ref() {
+                prop_assert!(open.pop().is_some(), "{:?} precedes its parent", id);
+            }
+            open.push(*id);
+        }
+
+        let json = message::deltas_to_json(deltas.clone());
+        prop_assert_eq!(json["elements"].as_array().map(Vec::len), Some(deltas.len()));
+        prop_assert!(determinism::compare_runs(&deltas, &deltas.clone()).is_empty());
+    }
+
+    #[test]
+    fn unchanged_keyed_elements_move(deltas in page(0..40)) {
+        let mut reconciler = Reconciler::new();
+        let first = reconciler.reconcile(deltas.clone());
+        prop_assert!(first.iter().all(|delta| matches!(delta, Delta::AddElement { .. })), "moved without a previous run");
+
+        // Rerun unchanged: the first element of each key and type moves
+        let mut seen = HashSet::new();
+        let second = reconciler.reconcile(deltas.clone());
+        prop_assert_eq!(second.len(), deltas.len());
+        for (sent, delta) in second.iter().zip(&deltas) {
+            let Delta::AddElement { id, parent_id, .. } = delta else {
+                panic!("unexpected delta: {:?}", delta);
+            };
+            match key_and_type(delta).filter(|identity| seen.insert(identity.clone())) {
+                Some(_) => prop_assert!(
+                    matches!(sent, Delta::MoveElement { id: moved, previous_id, parent_id: parent }
+                        if moved == id && previous_id == id && parent == parent_id),
+                    "{:?} was not moved", delta
+                ),
+                None => prop_assert!(
+                    matches!(sent, Delta::AddElement { id: added, .. } if added == id),
+                    "{:?} was not added", delta
+                ),
+            }
+        }
+    }
+}